@@ -25,6 +25,8 @@ impl crate::options::AllowedOptions for Accumulator {
 
     const NO_EQ: bool = false;
 
+    const EQ: bool = false;
+
     const SINGLETON: bool = false;
 
     const JAR: bool = true;
@@ -35,9 +37,42 @@ impl crate::options::AllowedOptions for Accumulator {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = true;
+
+    const SORT_BY_KEY: bool = true;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
 }
 
 fn accumulator_contents(
@@ -142,6 +177,17 @@ fn ingredients_for_impl(
 
 fn accumulator_impl(args: &Args, struct_ty: &syn::Type, data_ty: &syn::Type) -> syn::ItemImpl {
     let jar_ty = args.jar_ty();
+    let dedup = args.dedup.as_ref().map(|_| {
+        quote! {
+            salsa::accumulator::dedup(values);
+        }
+    });
+    // Sorting after removing duplicates means fewer values to sort.
+    let sort = args.sort_by_key.as_ref().map(|sort_by_key| {
+        quote! {
+            values.sort_by(|a, b| Ord::cmp(&#sort_by_key(a), &#sort_by_key(b)));
+        }
+    });
     parse_quote! {
         impl salsa::accumulator::Accumulator for #struct_ty {
             type Data = #data_ty;
@@ -157,6 +203,11 @@ fn accumulator_impl(args: &Args, struct_ty: &syn::Type, data_ty: &syn::Type) ->
                 let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#struct_ty>>::ingredient(jar);
                 ingredients
             }
+
+            fn finish_accumulated(values: &mut Vec<Self::Data>) {
+                #dedup
+                #sort
+            }
         }
     }
 }
@@ -7,10 +7,20 @@ pub(crate) struct Configuration {
     pub(crate) backdate_fn: syn::ImplItemMethod,
     pub(crate) execute_fn: syn::ImplItemMethod,
     pub(crate) recover_fn: syn::ImplItemMethod,
+    pub(crate) cycle_initial_fn: syn::ImplItemMethod,
+    pub(crate) cycle_iterate_fn: syn::ImplItemMethod,
+    pub(crate) cycle_max_iterations_const: Option<syn::ImplItemConst>,
+    pub(crate) heap_size_fn: Option<syn::ImplItemMethod>,
+    pub(crate) share_value_fns: Vec<syn::ImplItemMethod>,
+    pub(crate) cache_items: Vec<syn::ImplItem>,
+    pub(crate) update_items: Vec<syn::ImplItem>,
+    pub(crate) memo_map_shards_const: Option<syn::ImplItemConst>,
+    pub(crate) query_fingerprint_const: syn::ImplItemConst,
+    pub(crate) phase_const: Option<syn::ImplItemConst>,
 }
 
 impl Configuration {
-    pub(crate) fn to_impl(&self, self_ty: &syn::Type) -> syn::ItemImpl {
+    pub(crate) fn to_impl(&self, self_ty: &syn::Type, generics: &syn::Generics) -> syn::ItemImpl {
         let Configuration {
             jar_ty,
             salsa_struct_ty,
@@ -20,17 +30,38 @@ impl Configuration {
             backdate_fn,
             execute_fn,
             recover_fn,
+            cycle_initial_fn,
+            cycle_iterate_fn,
+            cycle_max_iterations_const,
+            heap_size_fn,
+            share_value_fns,
+            cache_items,
+            update_items,
+            memo_map_shards_const,
+            query_fingerprint_const,
+            phase_const,
         } = self;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
         parse_quote! {
-            impl salsa::function::Configuration for #self_ty {
+            impl #impl_generics salsa::function::Configuration for #self_ty #where_clause {
                 type Jar = #jar_ty;
                 type SalsaStruct = #salsa_struct_ty;
                 type Key = #key_ty;
                 type Value = #value_ty;
                 const CYCLE_STRATEGY: salsa::cycle::CycleRecoveryStrategy = #cycle_strategy;
+                #cycle_max_iterations_const
+                #memo_map_shards_const
+                #query_fingerprint_const
+                #phase_const
                 #backdate_fn
                 #execute_fn
                 #recover_fn
+                #cycle_initial_fn
+                #cycle_iterate_fn
+                #heap_size_fn
+                #(#share_value_fns)*
+                #(#cache_items)*
+                #(#update_items)*
             }
         }
     }
@@ -39,6 +70,7 @@ impl Configuration {
 pub(crate) enum CycleRecoveryStrategy {
     Panic,
     Fallback,
+    FixedPoint,
 }
 
 impl quote::ToTokens for CycleRecoveryStrategy {
@@ -50,6 +82,9 @@ impl quote::ToTokens for CycleRecoveryStrategy {
             CycleRecoveryStrategy::Fallback => {
                 tokens.extend(quote! {salsa::cycle::CycleRecoveryStrategy::Fallback})
             }
+            CycleRecoveryStrategy::FixedPoint => {
+                tokens.extend(quote! {salsa::cycle::CycleRecoveryStrategy::FixedPoint})
+            }
         }
     }
 }
@@ -86,6 +121,40 @@ pub(crate) fn panic_cycle_recovery_fn() -> syn::ImplItemMethod {
     }
 }
 
+/// Returns appropriate definitions for `cycle_initial` and `cycle_iterate` for cases
+/// where the cycle recovery is not to iterate to a fixed point.
+pub(crate) fn panic_cycle_iterate_fns() -> (syn::ImplItemMethod, syn::ImplItemMethod) {
+    let cycle_initial_fn = parse_quote! {
+        fn cycle_initial(_db: &salsa::function::DynDb<Self>, _key: Self::Key) -> Self::Value {
+            panic!()
+        }
+    };
+    let cycle_iterate_fn = parse_quote! {
+        fn cycle_iterate(
+            _db: &salsa::function::DynDb<Self>,
+            _value: &Self::Value,
+            _count: u32,
+            _key: Self::Key,
+        ) -> salsa::CycleRecoveryAction<Self::Value> {
+            panic!()
+        }
+    };
+    (cycle_initial_fn, cycle_iterate_fn)
+}
+
+/// Returns the definition of `QUERY_FINGERPRINT` for the FNV-1a hash of `text`,
+/// which describes the query (e.g., its name and signature).
+pub(crate) fn query_fingerprint_const(text: &str) -> syn::ImplItemConst {
+    let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+    for &byte in text.as_bytes() {
+        hash = (hash ^ u128::from(byte)).wrapping_mul(0x0000000001000000000000000000013b);
+    }
+    let hash = proc_macro2::Literal::u128_suffixed(hash);
+    parse_quote! {
+        const QUERY_FINGERPRINT: salsa::cache::Fingerprint = salsa::cache::Fingerprint(#hash);
+    }
+}
+
 pub(crate) fn value_ty(sig: &syn::Signature) -> syn::Type {
     match &sig.output {
         syn::ReturnType::Default => parse_quote!(()),
@@ -29,7 +29,7 @@ pub(crate) fn db(
 
     let as_salsa_database_impl = as_salsa_database_impl(&input);
     let has_jars_impl = has_jars_impl(&args, &input, &storage);
-    let has_jars_dyn_impl = has_jars_dyn_impl(&input, &storage);
+    let has_jars_dyn_impl = has_jars_dyn_impl(&args, &input, &storage);
     let per_jar_impls = per_jar_impls(&args, &input, &storage);
 
     quote! {
@@ -84,11 +84,12 @@ fn as_salsa_database_impl(input: &syn::ItemStruct) -> syn::ItemImpl {
 
 fn has_jars_impl(args: &Args, input: &syn::ItemStruct, storage: &syn::Ident) -> syn::ItemImpl {
     let jar_paths: Vec<&syn::Path> = args.jar_paths.iter().collect();
+    let dynamic_index = Literal::usize_unsuffixed(jar_paths.len());
     let db = &input.ident;
     parse_quote! {
         // ANCHOR: HasJars
         impl salsa::storage::HasJars for #db {
-            type Jars = (#(#jar_paths,)*);
+            type Jars = (#(#jar_paths,)* salsa::jar::DynamicJars,);
             // ANCHOR_END: HasJars
 
             fn jars(&self) -> (&Self::Jars, &salsa::Runtime) {
@@ -96,24 +97,70 @@ fn has_jars_impl(args: &Args, input: &syn::ItemStruct, storage: &syn::Ident) ->
             }
 
             fn jars_mut(&mut self) -> (&mut Self::Jars, &mut salsa::Runtime) {
-                self.#storage.jars_mut()
+                self.#storage.start_write();
+                let __revision = self.#storage.take_started_revision();
+                salsa::storage::report_started_revision(self, __revision);
+                self.#storage.started_jars_mut()
             }
 
             // ANCHOR: create_jars
             fn create_jars(routes: &mut salsa::routes::Routes<Self>) -> Self::Jars {
                 (
                     #(
-                        <#jar_paths as salsa::jar::Jar>::create_jar(routes),
+                        {
+                            routes.begin_jar(std::any::type_name::<#jar_paths>());
+                            <#jar_paths as salsa::jar::Jar>::create_jar(routes)
+                        },
                     )*
+                    salsa::jar::DynamicJars::default(),
                 )
             }
             // ANCHOR_END: create_jars
+
+            fn dynamic_jars(jars: &Self::Jars) -> &salsa::jar::DynamicJars {
+                &jars.#dynamic_index
+            }
+
+            fn dynamic_jars_mut(jars: &mut Self::Jars) -> &mut salsa::jar::DynamicJars {
+                &mut jars.#dynamic_index
+            }
         }
     }
 }
 
-fn has_jars_dyn_impl(input: &syn::ItemStruct, storage: &syn::Ident) -> syn::ItemImpl {
+fn has_jars_dyn_impl(args: &Args, input: &syn::ItemStruct, storage: &syn::Ident) -> syn::ItemImpl {
+    let jar_paths: Vec<&syn::Path> = args.jar_paths.iter().collect();
     let db = &input.ident;
+    // Only with the `persist` feature, like the methods of `HasJarsDyn` they implement.
+    let persist_methods = cfg!(feature = "persist").then(|| {
+        quote! {
+            fn persist(&self) -> Result<salsa::persist::PersistedDatabase, salsa::persist::PersistError> {
+                self.#storage.persist()
+            }
+
+            fn restore(&mut self, persisted: salsa::persist::PersistedDatabase) -> Result<(), salsa::persist::PersistError> {
+                let __result = self.#storage.restore(persisted);
+                let __revision = self.#storage.take_started_revision();
+                salsa::storage::report_started_revision(self, __revision);
+                __result
+            }
+
+            fn start_recording_of_jars(&self) {
+                self.#storage.start_recording()
+            }
+
+            fn take_recording_of_jars(&self) -> salsa::record::Recording {
+                self.#storage.take_recording()
+            }
+
+            fn replay_of_jars(&mut self, revision: &salsa::record::RecordedRevision) -> Result<(), salsa::persist::PersistError> {
+                let __result = self.#storage.replay(revision);
+                let __revision = self.#storage.take_started_revision();
+                salsa::storage::report_started_revision(self, __revision);
+                __result
+            }
+        }
+    });
     parse_quote! {
         impl salsa::storage::HasJarsDyn for #db {
             fn runtime(&self) -> &salsa::Runtime {
@@ -121,7 +168,7 @@ fn has_jars_dyn_impl(input: &syn::ItemStruct, storage: &syn::Ident) -> syn::Item
             }
 
             fn runtime_mut(&mut self) ->&mut salsa::Runtime {
-                self.#storage.runtime_mut()
+                salsa::storage::HasJars::jars_mut(self).1
             }
 
             fn maybe_changed_after(
@@ -149,6 +196,14 @@ fn has_jars_dyn_impl(input: &syn::ItemStruct, storage: &syn::Ident) -> syn::Item
                 ingredient.origin(index.key_index())
             }
 
+            fn value_revisions(
+                &self,
+                index: salsa::DatabaseKeyIndex,
+            ) -> Option<salsa::ingredient::ValueRevisions> {
+                let ingredient = self.#storage.ingredient(index.ingredient_index());
+                ingredient.value_revisions(index.key_index())
+            }
+
             fn mark_validated_output(&self, executor: salsa::DatabaseKeyIndex, output: salsa::key::DependencyIndex) {
                 let ingredient = self.#storage.ingredient(output.ingredient_index());
                 ingredient.mark_validated_output(self, executor, output.key_index());
@@ -168,6 +223,97 @@ fn has_jars_dyn_impl(input: &syn::ItemStruct, storage: &syn::Ident) -> syn::Item
                 let ingredient = self.#storage.ingredient(index.ingredient_index());
                 ingredient.fmt_index(index.key_index(), fmt)
             }
+
+            fn verification_forks(&self, inputs: usize) -> Vec<Box<dyn salsa::Database + Send>> {
+                self.#storage.verification_forks(self, inputs)
+            }
+
+            fn run_verification(&self, job: salsa::storage::VerificationJob) {
+                self.#storage.run_verification(job)
+            }
+
+            fn ingredient_debug_name(&self, ingredient_index: salsa::IngredientIndex) -> &'static str {
+                self.#storage.ingredient(ingredient_index).debug_name()
+            }
+
+            fn set_shadow_verification_of_jars(&self, enabled: bool) {
+                self.#storage.set_shadow_verification(enabled)
+            }
+
+            #persist_methods
+
+            fn sweep_jars(&mut self) -> usize {
+                self.#storage.sweep()
+            }
+
+            fn compact_jars(&mut self) {
+                self.#storage.compact()
+            }
+
+            fn synthetic_write_for_jars(&mut self, index: salsa::key::DependencyIndex) {
+                let __result = self.#storage.synthetic_write_for(index);
+                let __revision = self.#storage.take_started_revision();
+                salsa::storage::report_started_revision(self, __revision);
+                __result
+            }
+
+            fn evict_values_of_jars(&mut self, index: salsa::key::DependencyIndex) -> usize {
+                self.#storage.evict_values(index)
+            }
+
+            fn usage_of_jars(&self) -> salsa::usage::StorageUsage {
+                self.#storage.usage()
+            }
+
+            fn keys_changed_since_of_jars(&self, revision: salsa::Revision) -> Vec<salsa::DatabaseKeyIndex> {
+                self.#storage.keys_changed_since(revision)
+            }
+
+            fn keys_of_jars(&self) -> Vec<salsa::DatabaseKeyIndex> {
+                self.#storage.keys()
+            }
+
+            fn set_memory_budget_of_jars(&mut self, budget: Option<usize>) -> usize {
+                self.#storage.set_memory_budget(budget)
+            }
+
+            fn begin_transaction(&mut self) {
+                self.#storage.begin_transaction()
+            }
+
+            fn end_transaction(&mut self) {
+                self.#storage.end_transaction()
+            }
+
+            fn begin_speculation(&mut self) {
+                self.#storage.begin_speculation()
+            }
+
+            fn end_speculation(&mut self) {
+                let __result = self.#storage.end_speculation();
+                let __revision = self.#storage.take_started_revision();
+                salsa::storage::report_started_revision(self, __revision);
+                __result
+            }
+
+            fn jar_db(&self, jar: std::any::TypeId) -> Option<Box<dyn std::any::Any>> {
+                #(
+                    if jar == std::any::TypeId::of::<#jar_paths>() {
+                        let jar_db = <Self as salsa::storage::DbWithJar<#jar_paths>>::as_jar_db(self);
+                        return Some(salsa::attach::JarDb::<#jar_paths>::boxed(jar_db));
+                    }
+                )*
+                None
+            }
+
+            fn view_of_jars(&self, view: std::any::TypeId) -> Option<Box<dyn std::any::Any>> {
+                #(
+                    if let Some(view) = salsa::view::view_of_jar::<Self, #jar_paths>(self, view) {
+                        return Some(view);
+                    }
+                )*
+                None
+            }
         }
     }
 }
@@ -199,7 +345,7 @@ fn per_jar_impls(args: &Args, input: &syn::ItemStruct, storage: &syn::Ident) ->
                         }
 
                         fn jar_mut(&mut self) -> (&mut #jar_path, &mut salsa::Runtime) {
-                            let (__jars, __runtime) = self.#storage.jars_mut();
+                            let (__jars, __runtime) = salsa::storage::HasJars::jars_mut(self);
                             (&mut __jars.#jar_index, __runtime)
                         }
                     }
@@ -0,0 +1,222 @@
+use proc_macro2::TokenStream;
+use syn::spanned::Spanned;
+
+// #[derive(DebugWithDb)]
+// #[debug_with_db(jar = Jar0)]
+// enum Expr { .. }
+
+pub(crate) fn debug_with_db(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    debug_with_db_impl(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+type Args = crate::options::Options<DebugWithDb>;
+
+struct DebugWithDb;
+
+impl crate::options::AllowedOptions for DebugWithDb {
+    const RETURN_REF: bool = false;
+
+    const SPECIFY: bool = false;
+
+    const NO_EQ: bool = false;
+
+    const EQ: bool = false;
+
+    const SINGLETON: bool = false;
+
+    const JAR: bool = true;
+
+    const DATA: bool = false;
+
+    const DB: bool = false;
+
+    const RECOVERY_FN: bool = false;
+
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
+    const LRU: bool = false;
+
+    const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
+}
+
+fn debug_with_db_impl(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let mut attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("debug_with_db"));
+    let args: Args = match attrs.next() {
+        Some(attr) => attr.parse_args()?,
+        None => Args::default(),
+    };
+    if let Some(attr) = attrs.next() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "`debug_with_db` attribute provided twice",
+        ));
+    }
+
+    debug_with_db_for_jar(input, &args.jar_ty())
+}
+
+/// Generates the `DebugWithDb` impl of `input` for the database of the jar `jar_ty`.
+/// Also used by tracked enums.
+pub(crate) fn debug_with_db_for_jar(
+    input: &syn::DeriveInput,
+    jar_ty: &syn::Type,
+) -> syn::Result<TokenStream> {
+    let db_type: syn::Type = parse_quote! {
+        <#jar_ty as salsa::jar::Jar<'_>>::DynDb
+    };
+
+    let body = match &input.data {
+        syn::Data::Struct(data) => {
+            let name = input.ident.to_string();
+            let (pattern, debug) = fields_debug(&name, &data.fields, &db_type);
+            quote! {
+                let Self #pattern = self;
+                #debug
+            }
+        }
+        syn::Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                let name = variant_ident.to_string();
+                let (pattern, debug) = fields_debug(&name, &variant.fields, &db_type);
+                quote! {
+                    Self::#variant_ident #pattern => { #debug }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`DebugWithDb` cannot be derived for unions",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // `use ::salsa::debug::helper::Fallback` is needed for the fallback to `Debug` impl
+    Ok(quote! {
+        impl #impl_generics ::salsa::DebugWithDb<#db_type> for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>, _db: &#db_type, _include_all_fields: bool) -> ::std::fmt::Result {
+                #[allow(unused_imports)]
+                use ::salsa::debug::helper::Fallback;
+                #body
+            }
+        }
+    })
+}
+
+/// Returns the pattern binding `fields` (to `__field_foo` for a field `foo`, and to
+/// `__field0`, `__field1`, ... for tuple fields) and the code formatting them as a struct or variant named `name`.
+/// Fields whose types implement `DebugWithDb` are formatted with it, the others
+/// with `Debug`.
+fn fields_debug(
+    name: &str,
+    fields: &syn::Fields,
+    db_type: &syn::Type,
+) -> (TokenStream, TokenStream) {
+    let field_debug = |binding: &syn::Ident, field: &syn::Field| {
+        let ty = &field.ty;
+        quote_spanned! { field.span() =>
+            &::salsa::debug::helper::SalsaDebug::<#ty, #db_type>::salsa_debug(
+                #binding,
+                _db,
+                _include_all_fields
+            )
+        }
+    };
+
+    match fields {
+        syn::Fields::Named(named) => {
+            let names: Vec<_> = named
+                .named
+                .iter()
+                .map(|field| field.ident.clone().unwrap())
+                .collect();
+            let bindings: Vec<_> = names
+                .iter()
+                .map(|name| syn::Ident::new(&format!("__field_{name}"), name.span()))
+                .collect();
+            let entries =
+                named
+                    .named
+                    .iter()
+                    .zip(&names)
+                    .zip(&bindings)
+                    .map(|((field, name), binding)| {
+                        let field_name = name.to_string();
+                        let debug = field_debug(binding, field);
+                        quote! {
+                            .field(#field_name, #debug)
+                        }
+                    });
+            (
+                quote! { { #(#names: #bindings),* } },
+                quote! { f.debug_struct(#name) #(#entries)* .finish() },
+            )
+        }
+        syn::Fields::Unnamed(unnamed) => {
+            let bindings: Vec<_> = (0..unnamed.unnamed.len())
+                .map(|i| syn::Ident::new(&format!("__field{i}"), proc_macro2::Span::call_site()))
+                .collect();
+            let entries = unnamed
+                .unnamed
+                .iter()
+                .zip(&bindings)
+                .map(|(field, binding)| {
+                    let debug = field_debug(binding, field);
+                    quote! {
+                        .field(#debug)
+                    }
+                });
+            (
+                quote! { ( #(#bindings),* ) },
+                quote! { f.debug_tuple(#name) #(#entries)* .finish() },
+            )
+        }
+        syn::Fields::Unit => (quote! {}, quote! { f.write_str(#name) }),
+    }
+}
@@ -34,6 +34,9 @@ impl crate::options::AllowedOptions for InputStruct {
     const SPECIFY: bool = false;
 
     const NO_EQ: bool = false;
+
+    const EQ: bool = false;
+
     const SINGLETON: bool = true;
 
     const JAR: bool = true;
@@ -44,30 +47,111 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = true;
+
+    const PERSIST: bool = true;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = true;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
 }
 
 impl InputStruct {
     fn generate_input(&self) -> syn::Result<TokenStream> {
+        self.validate_input()?;
+
         let id_struct = self.id_struct();
         let inherent_impl = self.input_inherent_impl();
+        let builder = self.input_builder();
         let ingredients_for_impl = self.input_ingredients();
         let as_id_impl = self.as_id_impl();
         let salsa_struct_in_db_impl = self.salsa_struct_in_db_impl();
         let as_debug_with_db_impl = self.as_debug_with_db_impl();
+        let as_debug_impl = self.as_debug_impl();
+        let serde_impls = self.input_serde_impls();
 
         Ok(quote! {
             #id_struct
             #inherent_impl
+            #builder
             #ingredients_for_impl
             #as_id_impl
             #as_debug_with_db_impl
+            #as_debug_impl
             #salsa_struct_in_db_impl
+            #serde_impls
         })
     }
 
+    /// Deserializing an input creates a new one, with all fields at low durability.
+    /// Lazy fields are loaded again rather than serialized.
+    fn input_serde_impls(&self) -> Option<TokenStream> {
+        let ident = self.id_ident();
+        let constructor_name = self.constructor_name();
+        let fields: Vec<_> = self.all_fields().filter(|f| f.lazy_fn.is_none()).collect();
+        let required_field_names: Vec<_> = fields
+            .iter()
+            .filter(|f| !f.has_default_attr)
+            .map(|f| f.name())
+            .collect();
+        let default_field_names: Vec<_> = fields
+            .iter()
+            .filter(|f| f.has_default_attr)
+            .map(|f| f.name())
+            .collect();
+        self.serde_impls(
+            &fields,
+            quote! {
+                #ident::builder(#(__fields.#required_field_names),*)
+                    #(.#default_field_names(__fields.#default_field_names))*
+                    .#constructor_name(__db)
+            },
+        )
+    }
+
+    fn validate_input(&self) -> syn::Result<()> {
+        self.disallow_generics("inputs")?;
+        for ef in self.all_fields() {
+            if ef.lazy_fn.is_some() && ef.has_default_attr {
+                return Err(syn::Error::new(
+                    ef.name().span(),
+                    "`#[default]` cannot be used with `#[lazy]` fields",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Generate an inherent impl with methods on the entity type.
     fn input_inherent_impl(&self) -> syn::ItemImpl {
         let ident = self.id_ident();
@@ -76,19 +160,31 @@ impl InputStruct {
         let input_index = self.input_index();
 
         let field_indices = self.all_field_indices();
-        let field_names = self.all_field_names();
         let field_vises = self.all_field_vises();
         let field_tys: Vec<_> = self.all_field_tys();
+        let returned_tys: Vec<_> = self.all_fields().map(SalsaField::returned_ty).collect();
         let field_clones: Vec<_> = self.all_fields().map(SalsaField::is_clone_field).collect();
         let get_field_names: Vec<_> = self.all_get_field_names();
-        let field_getters: Vec<syn::ImplItemMethod> = field_indices.iter().zip(&get_field_names).zip(&field_vises).zip(&field_tys).zip(&field_clones).map(|((((field_index, get_field_name), field_vis), field_ty), is_clone_field)|
+        let field_fetches: Vec<TokenStream> = field_indices
+            .iter()
+            .zip(self.all_fields())
+            .map(|(field_index, field)| match &field.lazy_fn {
+                Some(lazy_fn) => field.returned_ref(quote! {
+                    __ingredients.#field_index.fetch_or_load(__runtime, self, || #lazy_fn(__db, self))
+                }),
+                None => field.returned_ref(quote! {
+                    __ingredients.#field_index.fetch(__runtime, self)
+                }),
+            })
+            .collect();
+        let field_getters: Vec<syn::ImplItemMethod> = field_fetches.iter().zip(&get_field_names).zip(&field_vises).zip(&returned_tys).zip(&field_clones).map(|((((field_fetch, get_field_name), field_vis), field_ty), is_clone_field)|
             if !*is_clone_field {
                 parse_quote! {
                     #field_vis fn #get_field_name<'db>(self, __db: &'db #db_dyn_ty) -> &'db #field_ty
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
                         let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                        __ingredients.#field_index.fetch(__runtime, self)
+                        #field_fetch
                     }
                 }
             } else {
@@ -97,7 +193,7 @@ impl InputStruct {
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
                         let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                        __ingredients.#field_index.fetch(__runtime, self).clone()
+                        #field_fetch.clone()
                     }
                 }
             }
@@ -123,38 +219,80 @@ impl InputStruct {
         })
         .collect();
 
+        // Setters back to the default value of `#[default]` fields.
+        let reset_setters: Vec<syn::ImplItemMethod> = self
+            .all_fields()
+            .zip(&set_field_names)
+            .filter(|(field, _)| field.has_default_attr)
+            .filter_map(|(field, &set_field_name)| {
+                let set_field_name = set_field_name?;
+                let reset_field_name = syn::Ident::new(
+                    &format!("{}_to_default", set_field_name),
+                    set_field_name.span(),
+                );
+                let field_vis = field.vis();
+                let field_ty = field.ty();
+                Some(parse_quote! {
+                    /// Sets this field back to its default value, returning the old one.
+                    #field_vis fn #reset_field_name(self, __db: &mut #db_dyn_ty) -> #field_ty
+                    {
+                        self.#set_field_name(__db).to(Default::default())
+                    }
+                })
+            })
+            .collect();
+
+        // Lazy fields are loaded when first read, and `#[default]` fields start out
+        // with their default value, so neither is given to the constructor.
+        let required_fields: Vec<_> = self
+            .all_fields()
+            .filter(|field| field.lazy_fn.is_none() && !field.has_default_attr)
+            .collect();
+        let required_field_names: Vec<_> = required_fields.iter().map(|f| f.name()).collect();
+        let required_field_tys: Vec<_> = required_fields.iter().map(|f| f.ty()).collect();
+        let default_field_names: Vec<_> = self
+            .all_fields()
+            .filter(|field| field.has_default_attr)
+            .map(|field| field.name())
+            .collect();
+
+        let eager_field_count = self
+            .all_fields()
+            .filter(|field| field.lazy_fn.is_none())
+            .count();
+
         let constructor_name = self.constructor_name();
         let singleton = self.0.is_isingleton();
-
-        let constructor: syn::ImplItemMethod = if singleton {
-            parse_quote! {
-                /// Creates a new singleton input
-                ///
-                /// # Panics
-                ///
-                /// If called when an instance already exists
-                pub fn #constructor_name(__db: &#db_dyn_ty, #(#field_names: #field_tys,)*) -> Self
-                {
-                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                    let __id = __ingredients.#input_index.new_singleton_input(__runtime);
-                    #(
-                        __ingredients.#field_indices.store_new(__runtime, __id, #field_names, salsa::Durability::LOW);
-                    )*
-                    __id
-                }
+        let singleton_panic_doc: Vec<_> = singleton
+            .then_some([
+                "",
+                "# Panics",
+                "",
+                "If called when an instance already exists",
+            ])
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let builder_ident = self.builder_ident();
+        let constructor: syn::ImplItemMethod = parse_quote! {
+            /// Creates a new input with all fields at low durability;
+            /// use `builder` to choose other durabilities.
+            #(#[doc = #singleton_panic_doc])*
+            pub fn #constructor_name(__db: &#db_dyn_ty, #(#required_field_names: #required_field_tys,)*) -> Self
+            {
+                Self::builder(#(#required_field_names,)*).#constructor_name(__db)
             }
-        } else {
-            parse_quote! {
-                pub fn #constructor_name(__db: &#db_dyn_ty, #(#field_names: #field_tys,)*) -> Self
-                {
-                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                    let __id = __ingredients.#input_index.new_input(__runtime);
-                    #(
-                        __ingredients.#field_indices.store_new(__runtime, __id, #field_names, salsa::Durability::LOW);
-                    )*
-                    __id
+        };
+        let builder: syn::ImplItemMethod = parse_quote! {
+            /// Starts building a new input with the given field values;
+            /// the `#[default]` fields can be set on the builder.
+            pub fn builder(#(#required_field_names: #required_field_tys,)*) -> #builder_ident
+            {
+                #builder_ident {
+                    #(#required_field_names,)*
+                    #(#default_field_names: Default::default(),)*
+                    __durabilities: [salsa::Durability::LOW; #eager_field_count],
                 }
             }
         };
@@ -182,6 +320,8 @@ impl InputStruct {
                 impl #ident {
                     #constructor
 
+                    #builder
+
                     #get
 
                     #try_get
@@ -189,16 +329,45 @@ impl InputStruct {
                     #(#field_getters)*
 
                     #(#field_setters)*
+
+                    #(#reset_setters)*
                 }
             }
         } else {
+            let delete: syn::ImplItemMethod = parse_quote! {
+                /// Deletes this input in a new revision. Memoized values of tracked
+                /// functions keyed by this input are discarded, and any other query
+                /// that read one of its fields will re-execute.
+                ///
+                /// # Panics
+                ///
+                /// Reading a field of this input after it is deleted panics.
+                pub fn delete(self, __db: &mut #db_dyn_ty) {
+                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar_mut(__db);
+                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient_mut(__jar);
+                    #(
+                        __ingredients.#field_indices.delete_mut(__runtime, self);
+                    )*
+                    let __db = &*__db;
+                    let (__jar, _) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
+                    __ingredients.#input_index.delete_input(__db, self);
+                }
+            };
+
             parse_quote! {
                 impl #ident {
                     #constructor
 
+                    #builder
+
+                    #delete
+
                     #(#field_getters)*
 
                     #(#field_setters)*
+
+                    #(#reset_setters)*
                 }
             }
         }
@@ -206,6 +375,96 @@ impl InputStruct {
         // }
     }
 
+    /// The name of the builder struct, e.g. `FooBuilder` for `Foo`.
+    fn builder_ident(&self) -> syn::Ident {
+        let ident = self.id_ident();
+        syn::Ident::new(&format!("{}Builder", ident), ident.span())
+    }
+
+    /// Generate the builder returned by `builder`, which holds the initial field
+    /// values and lets the user pick the durability of each before creating the input.
+    fn input_builder(&self) -> TokenStream {
+        let ident = self.id_ident();
+        let vis = self.visibility();
+        let jar_ty = self.jar_ty();
+        let db_dyn_ty = self.db_dyn_ty();
+        let input_index = self.input_index();
+        let builder_ident = self.builder_ident();
+        let constructor_name = self.constructor_name();
+
+        let eager_fields: Vec<_> = self
+            .all_field_indices()
+            .into_iter()
+            .zip(self.all_fields())
+            .filter(|(_, field)| field.lazy_fn.is_none())
+            .collect();
+        let field_indices: Vec<_> = eager_fields.iter().map(|(index, _)| index).collect();
+        let field_names: Vec<_> = eager_fields.iter().map(|(_, f)| f.name()).collect();
+        let field_tys: Vec<_> = eager_fields.iter().map(|(_, f)| f.ty()).collect();
+        let field_count = eager_fields.len();
+        let durability_indices: Vec<_> = (0..field_count).map(Literal::usize_unsuffixed).collect();
+        let durability_setters: Vec<_> = field_names
+            .iter()
+            .map(|name| syn::Ident::new(&format!("{}_durability", name), name.span()))
+            .collect();
+        let default_fields: Vec<_> = eager_fields
+            .iter()
+            .filter(|(_, f)| f.has_default_attr)
+            .map(|(_, f)| f)
+            .collect();
+        let default_field_names: Vec<_> = default_fields.iter().map(|f| f.name()).collect();
+        let default_field_tys: Vec<_> = default_fields.iter().map(|f| f.ty()).collect();
+
+        let new_input = if self.0.is_isingleton() {
+            quote! { new_singleton_input }
+        } else {
+            quote! { new_input }
+        };
+
+        quote! {
+            #vis struct #builder_ident {
+                #(#field_names: #field_tys,)*
+                __durabilities: [salsa::Durability; #field_count],
+            }
+
+            impl #builder_ident {
+                /// Sets the durability of all fields.
+                pub fn durability(mut self, durability: salsa::Durability) -> Self {
+                    self.__durabilities = [durability; #field_count];
+                    self
+                }
+
+                #(
+                    /// Sets the initial value of this field, instead of its default.
+                    pub fn #default_field_names(mut self, value: #default_field_tys) -> Self {
+                        self.#default_field_names = value;
+                        self
+                    }
+                )*
+
+                #(
+                    /// Sets the durability of this field.
+                    pub fn #durability_setters(mut self, durability: salsa::Durability) -> Self {
+                        self.__durabilities[#durability_indices] = durability;
+                        self
+                    }
+                )*
+
+                /// Creates the input.
+                #[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
+                pub fn #constructor_name(self, __db: &#db_dyn_ty) -> #ident {
+                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
+                    let __id = __ingredients.#input_index.#new_input(__runtime);
+                    #(
+                        __ingredients.#field_indices.store_new(__runtime, __id, self.#field_names, self.__durabilities[#durability_indices]);
+                    )*
+                    __id
+                }
+            }
+        }
+    }
+
     /// Generate the `IngredientsFor` impl for this entity.
     ///
     /// The entity's ingredients include both the main entity ingredient along with a
@@ -219,6 +478,7 @@ impl InputStruct {
         let input_index: Literal = self.input_index();
         let debug_name_struct = literal(self.id_ident());
         let debug_name_fields: Vec<_> = self.all_field_names().into_iter().map(literal).collect();
+        let push_method = self.routes_push_method();
 
         parse_quote! {
             impl salsa::storage::IngredientsFor for #ident {
@@ -239,7 +499,7 @@ impl InputStruct {
                     (
                         #(
                             {
-                                let index = routes.push(
+                                let index = routes.#push_method(
                                     |jars| {
                                         let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                                         let ingredients = <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar);
@@ -252,10 +512,14 @@ impl InputStruct {
                                     },
                                 );
                                 salsa::input_field::InputFieldIngredient::new(index, #debug_name_fields)
+                                    .with_clone_fn({
+                                        use salsa::input_field::{CloneFn as _, NoCloneFn as _};
+                                        (&salsa::input_field::CloneProbe::<#field_ty>(std::marker::PhantomData)).clone_fn()
+                                    })
                             },
                         )*
                         {
-                            let index = routes.push(
+                            let index = routes.#push_method(
                                 |jars| {
                                     let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                                     let ingredients = <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar);
@@ -306,14 +570,16 @@ impl InputStruct {
     fn salsa_struct_in_db_impl(&self) -> syn::ItemImpl {
         let ident = self.id_ident();
         let jar_ty = self.jar_ty();
+        let input_index = self.input_index();
         parse_quote! {
             impl<DB> salsa::salsa_struct::SalsaStructInDb<DB> for #ident
             where
                 DB: ?Sized + salsa::DbWithJar<#jar_ty>,
             {
-                fn register_dependent_fn(_db: &DB, _index: salsa::routes::IngredientIndex) {
-                    // Do nothing here, at least for now.
-                    // If/when we add ability to delete inputs, this would become relevant.
+                fn register_dependent_fn(db: &DB, index: salsa::routes::IngredientIndex) {
+                    let (jar, _) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
+                    let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#ident>>::ingredient(jar);
+                    ingredients.#input_index.register_dependent_fn(index)
                 }
             }
         }
@@ -0,0 +1,206 @@
+use syn::ItemStruct;
+
+// #[salsa::input_map(jar = Jar0)]
+// struct InputMap(KeyType, ValueType);
+
+pub(crate) fn input_map(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(args as Args);
+    let struct_impl = syn::parse_macro_input!(input as ItemStruct);
+    input_map_contents(&args, &struct_impl)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+type Args = crate::options::Options<InputMap>;
+
+struct InputMap;
+
+impl crate::options::AllowedOptions for InputMap {
+    const RETURN_REF: bool = false;
+
+    const SPECIFY: bool = false;
+
+    const NO_EQ: bool = false;
+
+    const EQ: bool = false;
+
+    const SINGLETON: bool = false;
+
+    const JAR: bool = true;
+
+    const DATA: bool = false;
+
+    const DB: bool = false;
+
+    const RECOVERY_FN: bool = false;
+
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
+    const LRU: bool = false;
+
+    const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
+}
+
+fn input_map_contents(
+    args: &Args,
+    struct_item: &syn::ItemStruct,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // We expect two anonymous fields, the key and the value.
+    let (key_ty, value_ty) = key_value_tys(struct_item)?;
+    let struct_name = &struct_item.ident;
+    let struct_ty = &parse_quote! {#struct_name};
+
+    let inherent_impl = inherent_impl(args, struct_ty, key_ty, value_ty);
+    let ingredients_for_impl = ingredients_for_impl(args, struct_name, key_ty, value_ty);
+    let struct_item_out = struct_item_out(struct_item, key_ty, value_ty);
+
+    Ok(quote! {
+        #inherent_impl
+        #ingredients_for_impl
+        #struct_item_out
+    })
+}
+
+fn key_value_tys(struct_item: &syn::ItemStruct) -> syn::Result<(&syn::Type, &syn::Type)> {
+    match &struct_item.fields {
+        syn::Fields::Unnamed(fields) if fields.unnamed.len() == 2 => {
+            Ok((&fields.unnamed[0].ty, &fields.unnamed[1].ty))
+        }
+        _ => Err(syn::Error::new(
+            struct_item.ident.span(),
+            "input map structs should have two anonymous fields, the key and the value",
+        )),
+    }
+}
+
+fn struct_item_out(
+    struct_item: &syn::ItemStruct,
+    key_ty: &syn::Type,
+    value_ty: &syn::Type,
+) -> syn::ItemStruct {
+    let mut struct_item_out = struct_item.clone();
+    struct_item_out.fields = syn::Fields::Unnamed(parse_quote! {
+            (std::marker::PhantomData<(#key_ty, #value_ty)>)
+    });
+    struct_item_out
+}
+
+fn inherent_impl(
+    args: &Args,
+    struct_ty: &syn::Type,
+    key_ty: &syn::Type,
+    value_ty: &syn::Type,
+) -> syn::ItemImpl {
+    let jar_ty = args.jar_ty();
+    parse_quote! {
+        impl #struct_ty {
+            /// The value of `key`, if any. Only changes to this entry
+            /// invalidate the queries reading it.
+            pub fn get<'db, DB: ?Sized>(db: &'db DB, key: &#key_ty) -> Option<&'db #value_ty>
+            where
+                DB: salsa::storage::HasJar<#jar_ty>,
+            {
+                let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
+                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #struct_ty >>::ingredient(jar);
+                ingredients.get(runtime, key)
+            }
+
+            /// The keys with a value, in the order they were first used.
+            /// Inserting or removing a key invalidates the queries reading
+            /// them, but changing the value of a key does not.
+            pub fn keys<DB: ?Sized>(db: &DB) -> Vec<#key_ty>
+            where
+                DB: salsa::storage::HasJar<#jar_ty>,
+            {
+                let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
+                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #struct_ty >>::ingredient(jar);
+                ingredients.keys(runtime)
+            }
+
+            pub fn insert<DB: ?Sized>(db: &mut DB, key: #key_ty, value: #value_ty) -> Option<#value_ty>
+            where
+                DB: salsa::storage::HasJar<#jar_ty>,
+            {
+                let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar_mut(db);
+                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #struct_ty >>::ingredient_mut(jar);
+                ingredients.insert_mut(runtime, key, value)
+            }
+
+            pub fn remove<DB: ?Sized>(db: &mut DB, key: &#key_ty) -> Option<#value_ty>
+            where
+                DB: salsa::storage::HasJar<#jar_ty>,
+            {
+                let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar_mut(db);
+                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #struct_ty >>::ingredient_mut(jar);
+                ingredients.remove_mut(runtime, key)
+            }
+        }
+    }
+}
+
+fn ingredients_for_impl(
+    args: &Args,
+    struct_name: &syn::Ident,
+    key_ty: &syn::Type,
+    value_ty: &syn::Type,
+) -> syn::ItemImpl {
+    let jar_ty = args.jar_ty();
+    let debug_name = crate::literal(struct_name);
+    parse_quote! {
+        impl salsa::storage::IngredientsFor for #struct_name {
+            type Ingredients = salsa::input_map::InputMapIngredient<#key_ty, #value_ty>;
+            type Jar = #jar_ty;
+
+            fn create_ingredients<DB>(routes: &mut salsa::routes::Routes<DB>) -> Self::Ingredients
+            where
+                DB: salsa::DbWithJar<Self::Jar> + salsa::storage::JarFromJars<Self::Jar>,
+            {
+                    let index = routes.push(
+                        |jars| {
+                            let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
+                            <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar)
+                        },
+                        |jars| {
+                            let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars_mut(jars);
+                            <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient_mut(jar)
+                        },
+                    );
+                    salsa::input_map::InputMapIngredient::new(index, #debug_name)
+            }
+        }
+    }
+}
@@ -38,6 +38,8 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const NO_EQ: bool = false;
 
+    const EQ: bool = false;
+
     const SINGLETON: bool = false;
 
     const JAR: bool = true;
@@ -48,9 +50,42 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = true;
+
+    const PERSIST: bool = true;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = true;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = true;
+    const INTERN_ARGS: bool = false;
 }
 
 impl InternedStruct {
@@ -63,6 +98,8 @@ impl InternedStruct {
         let named_fields_impl = self.inherent_impl_for_named_fields();
         let salsa_struct_in_db_impl = self.salsa_struct_in_db_impl();
         let as_debug_with_db_impl = self.as_debug_with_db_impl();
+        let as_debug_impl = self.as_debug_impl();
+        let serde_impls = self.interned_serde_impls();
 
         Ok(quote! {
             #id_struct
@@ -72,11 +109,27 @@ impl InternedStruct {
             #named_fields_impl
             #salsa_struct_in_db_impl
             #as_debug_with_db_impl
+            #as_debug_impl
+            #serde_impls
         })
     }
 
+    /// Deserializing an interned struct interns its fields again.
+    fn interned_serde_impls(&self) -> Option<TokenStream> {
+        let id_ty = self.id_ty();
+        let constructor_name = self.constructor_name();
+        let fields: Vec<_> = self.all_fields().collect();
+        let field_names: Vec<_> = fields.iter().map(|f| f.name()).collect();
+        self.serde_impls(
+            &fields,
+            quote! { <#id_ty>::#constructor_name(__db, #(__fields.#field_names),*) },
+        )
+    }
+
     fn validate_interned(&self) -> syn::Result<()> {
         self.disallow_id_fields("interned")?;
+        self.disallow_lazy_fields("interned")?;
+        self.disallow_default_fields("interned")?;
         Ok(())
     }
 
@@ -84,7 +137,9 @@ impl InternedStruct {
     /// as well as a `new` method.
     fn inherent_impl_for_named_fields(&self) -> syn::ItemImpl {
         let vis = self.visibility();
-        let id_ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
         let db_dyn_ty = self.db_dyn_ty();
         let jar_ty = self.jar_ty();
 
@@ -99,16 +154,18 @@ impl InternedStruct {
                     parse_quote! {
                         #field_vis fn #field_get_name(self, db: &#db_dyn_ty) -> #field_ty {
                             let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
-                            let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ident >>::ingredient(jar);
+                            let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(jar);
                             std::clone::Clone::clone(&ingredients.data(runtime, self).#field_name)
                         }
                     }
                 } else {
+                    let field_ty = field.returned_ty();
+                    let field_ref = field.returned_ref(quote! { &ingredients.data(runtime, self).#field_name });
                     parse_quote! {
                         #field_vis fn #field_get_name<'db>(self, db: &'db #db_dyn_ty) -> &'db #field_ty {
                             let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
-                            let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ident >>::ingredient(jar);
-                            &ingredients.data(runtime, self).#field_name
+                            let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(jar);
+                            #field_ref
                         }
                     }
                 }
@@ -118,25 +175,95 @@ impl InternedStruct {
         let field_names = self.all_field_names();
         let field_tys = self.all_field_tys();
         let data_ident = self.data_ident();
+        let phantom_field = self
+            .is_generic()
+            .then(|| quote!(__phantom: std::marker::PhantomData,));
         let constructor_name = self.constructor_name();
-        let new_method: syn::ImplItemMethod = parse_quote! {
-            #vis fn #constructor_name(
+        let intern_body = quote! {
+            let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
+            let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(jar);
+            ingredients.intern(runtime, #data_ident {
+                #(#field_names,)*
+                #phantom_field
+            })
+        };
+        let (new_method, try_new_method): (syn::ImplItemMethod, Option<syn::ImplItemMethod>) =
+            match self.validate() {
+                None => (
+                    parse_quote! {
+                        #vis fn #constructor_name(
+                            db: &#db_dyn_ty,
+                            #(#field_names: #field_tys,)*
+                        ) -> Self {
+                            #intern_body
+                        }
+                    },
+                    None,
+                ),
+                Some((validate_fn, error_ty)) => {
+                    let try_constructor_name = syn::Ident::new(
+                        &format!("try_{}", constructor_name),
+                        constructor_name.span(),
+                    );
+                    let id_name = crate::literal(self.id_ident());
+                    (
+                        parse_quote! {
+                            /// Panics if the fields are rejected by `#[salsa::interned(validate(..))]`,
+                            /// see the fallible constructor.
+                            #vis fn #constructor_name(
+                                db: &#db_dyn_ty,
+                                #(#field_names: #field_tys,)*
+                            ) -> Self {
+                                match Self::#try_constructor_name(db, #(#field_names,)*) {
+                                    Ok(value) => value,
+                                    Err(error) => panic!(
+                                        "`{}` rejected the fields of a `{}`: {:?}",
+                                        stringify!(#validate_fn),
+                                        #id_name,
+                                        error,
+                                    ),
+                                }
+                            }
+                        },
+                        Some(parse_quote! {
+                            /// Interns a struct with these fields, once the validation function
+                            /// accepted them: rejected fields are never interned.
+                            #vis fn #try_constructor_name(
+                                db: &#db_dyn_ty,
+                                #(#field_names: #field_tys,)*
+                            ) -> Result<Self, #error_ty> {
+                                #validate_fn(#(&#field_names,)*)?;
+                                Ok({ #intern_body })
+                            }
+                        }),
+                    )
+                }
+            };
+
+        let get_method: syn::ImplItemMethod = parse_quote! {
+            /// Returns the existing struct with these fields, if any, without interning them.
+            #vis fn get(
                 db: &#db_dyn_ty,
                 #(#field_names: #field_tys,)*
-            ) -> Self {
+            ) -> Option<Self> {
                 let (jar, runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
-                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ident >>::ingredient(jar);
-                ingredients.intern(runtime, #data_ident {
+                let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(jar);
+                ingredients.lookup(runtime, &#data_ident {
                     #(#field_names,)*
+                    #phantom_field
                 })
             }
         };
 
         parse_quote! {
-            impl #id_ident {
+            impl #impl_generics #id_ty #where_clause {
                 #(#field_getters)*
 
                 #new_method
+
+                #try_new_method
+
+                #get_method
             }
         }
     }
@@ -146,13 +273,17 @@ impl InternedStruct {
     /// For a memoized type, the only ingredient is an `InternedIngredient`.
     fn ingredients_for_impl(&self) -> syn::ItemImpl {
         let id_ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
         let debug_name = crate::literal(id_ident);
         let jar_ty = self.jar_ty();
         let data_ident = self.data_ident();
+        let push_method = self.routes_push_method();
         parse_quote! {
-            impl salsa::storage::IngredientsFor for #id_ident {
+            impl #impl_generics salsa::storage::IngredientsFor for #id_ty #where_clause {
                 type Jar = #jar_ty;
-                type Ingredients = salsa::interned::InternedIngredient<#id_ident, #data_ident>;
+                type Ingredients = salsa::interned::InternedIngredient<#id_ty, #data_ident #ty_generics>;
 
                 fn create_ingredients<DB>(
                     routes: &mut salsa::routes::Routes<DB>,
@@ -160,7 +291,7 @@ impl InternedStruct {
                 where
                     DB: salsa::storage::JarFromJars<Self::Jar>,
                 {
-                    let index = routes.push(
+                    let index = routes.#push_method(
                         |jars| {
                             let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                             <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar)
@@ -178,13 +309,12 @@ impl InternedStruct {
 
     /// Implementation of `SalsaStructInDb`.
     fn salsa_struct_in_db_impl(&self) -> syn::ItemImpl {
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
         let jar_ty = self.jar_ty();
+        let generics = self.db_impl_generics(quote!(?Sized + salsa::DbWithJar<#jar_ty>));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
         parse_quote! {
-            impl<DB> salsa::salsa_struct::SalsaStructInDb<DB> for #ident
-            where
-                DB: ?Sized + salsa::DbWithJar<#jar_ty>,
-            {
+            impl #impl_generics salsa::salsa_struct::SalsaStructInDb<DB> for #id_ty #where_clause {
                 fn register_dependent_fn(_db: &DB, _index: salsa::routes::IngredientIndex) {
                     // Do nothing here, at least for now.
                     // If/when we add ability to delete inputs, this would become relevant.
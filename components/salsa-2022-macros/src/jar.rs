@@ -1,7 +1,7 @@
 use proc_macro2::Literal;
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{Field, FieldsUnnamed, Ident, ItemStruct, Path, Token};
+use syn::{Field, FieldsUnnamed, Ident, Item, ItemMod, ItemStruct, Path, Token};
 
 use crate::options::Options;
 
@@ -9,6 +9,17 @@ use crate::options::Options;
 //
 // #[salsa::jar(db = Jar0Db)]
 // pub struct Jar0(Entity0, Ty0, EntityComponent0, my_func);
+//
+// or, listing the salsa items of a module in a generated `Jar`:
+//
+// #[salsa::jar(db = Jar0Db)]
+// mod jar0 {
+//     #[salsa::input]
+//     struct Entity0 { ... }
+//
+//     #[salsa::tracked]
+//     fn my_func(db: &dyn Jar0Db, entity: Entity0) { ... }
+// }
 
 pub(crate) fn jar(
     args: proc_macro::TokenStream,
@@ -19,8 +30,21 @@ pub(crate) fn jar(
         Some(v) => v,
         None => panic!("no `db` specified"),
     };
-    let input = syn::parse_macro_input!(input as ItemStruct);
-    jar_struct_and_friends(&db_path, &input).into()
+    match syn::parse_macro_input!(input as Item) {
+        Item::Struct(input) => {
+            jar_struct_and_friends(&db_path, options.dynamic.as_ref(), &input).into()
+        }
+        Item::Mod(input) => match jar_mod(&db_path, options.dynamic.as_ref(), input) {
+            Ok(s) => s.into(),
+            Err(err) => err.into_compile_error().into(),
+        },
+        item => syn::Error::new(
+            item.span(),
+            "jar can only be applied to structs and inline modules",
+        )
+        .into_compile_error()
+        .into(),
+    }
 }
 
 type Args = Options<Jar>;
@@ -34,6 +58,8 @@ impl crate::options::AllowedOptions for Jar {
 
     const NO_EQ: bool = false;
 
+    const EQ: bool = false;
+
     const SINGLETON: bool = false;
 
     const JAR: bool = false;
@@ -44,13 +70,188 @@ impl crate::options::AllowedOptions for Jar {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = true;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
+}
+
+/// Generates a `Jar` struct at the start of the module `input`, listing the
+/// ingredients declared by the salsa items of the module in order, and adds
+/// `jar = Jar` to the options of these items. Items that already name a jar
+/// other than `Jar` are left out, as are the items of nested modules.
+/// (Attributes on modules are only given the contents of inline modules.)
+fn jar_mod(
+    jar_trait: &Path,
+    dynamic: Option<&syn::Type>,
+    mut input: ItemMod,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let items = match &mut input.content {
+        Some((_, items)) => items,
+        None => {
+            return Err(syn::Error::new(
+                input.span(),
+                "jar can only be applied to inline modules (`mod name { ... }`)",
+            ))
+        }
+    };
+
+    let mut ingredients: Vec<Ident> = vec![];
+    for item in items.iter_mut() {
+        match item {
+            Item::Struct(item) => {
+                let kinds = ["input", "tracked", "interned", "accumulator", "input_map"];
+                if let Some(attr) = salsa_attr(&mut item.attrs, &kinds) {
+                    if in_this_jar(attr)? {
+                        ingredients.push(item.ident.clone());
+                    }
+                }
+            }
+            Item::Enum(item) => {
+                // Tracked enums refer to the jar but have no ingredients.
+                if let Some(attr) = salsa_attr(&mut item.attrs, &["tracked"]) {
+                    in_this_jar(attr)?;
+                }
+            }
+            Item::Fn(item) => {
+                if let Some(attr) = salsa_attr(&mut item.attrs, &["tracked"]) {
+                    if in_this_jar(attr)? {
+                        ingredients.push(item.sig.ident.clone());
+                    }
+                }
+            }
+            Item::Impl(item) => {
+                let attr = match salsa_attr(&mut item.attrs, &["tracked"]) {
+                    Some(attr) => attr,
+                    None => continue,
+                };
+                let self_type = match &*item.self_ty {
+                    // Let `#[salsa::tracked]` report other self types.
+                    syn::Type::Path(self_type) => self_type,
+                    _ => continue,
+                };
+                if !in_this_jar(attr)? {
+                    continue;
+                }
+                let name_prefix = crate::tracked_fn::tracked_method_name_prefix(self_type, item);
+                for impl_item in &item.items {
+                    if let syn::ImplItem::Method(method) = impl_item {
+                        if method
+                            .attrs
+                            .iter()
+                            .any(|attr| crate::is_salsa_attr(attr, "tracked"))
+                        {
+                            let name = format!("{}_{}", name_prefix, method.sig.ident);
+                            ingredients.push(Ident::new(&name, method.sig.ident.span()));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The jar names the database trait, and is named by the items of the
+    // module and by the database struct, so it is as visible as the trait if
+    // that is declared in the module, and otherwise as the module (or crate).
+    let jar_trait_ident = &jar_trait.segments.last().unwrap().ident;
+    let jar_vis = items
+        .iter()
+        .find_map(|item| match item {
+            Item::Trait(item) if item.ident == *jar_trait_ident => Some(item.vis.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| match &input.vis {
+            vis @ syn::Visibility::Public(_) => vis.clone(),
+            _ => parse_quote!(pub(crate)),
+        });
+    let jar_struct: ItemStruct = parse_quote! {
+        #jar_vis struct Jar(#(#ingredients),*);
+    };
+    let jar_tokens = jar_struct_and_friends(jar_trait, dynamic, &jar_struct);
+    items.insert(0, Item::Verbatim(jar_tokens));
+    Ok(quote!(#input))
+}
+
+/// The first attribute of `attrs` that is `#[salsa::<kind>]` for one of `kinds`.
+fn salsa_attr<'a>(
+    attrs: &'a mut [syn::Attribute],
+    kinds: &[&str],
+) -> Option<&'a mut syn::Attribute> {
+    attrs
+        .iter_mut()
+        .find(|attr| kinds.iter().any(|kind| crate::is_salsa_attr(attr, kind)))
+}
+
+/// Whether the salsa item with the attribute `attr` is in the `Jar` of its module,
+/// adding `jar = Jar` to its options unless some jar is given already.
+fn in_this_jar(attr: &mut syn::Attribute) -> syn::Result<bool> {
+    let args = if attr.tokens.is_empty() {
+        proc_macro2::TokenStream::new()
+    } else {
+        attr.parse_args()?
+    };
+
+    // Options are `name` or `name = value`, separated by commas.
+    let mut tokens = args.clone().into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let is_jar = matches!(&token, proc_macro2::TokenTree::Ident(ident) if ident == "jar");
+        let value: Vec<_> = std::iter::from_fn(|| {
+            tokens.next_if(
+                |token| !matches!(token, proc_macro2::TokenTree::Punct(p) if p.as_char() == ','),
+            )
+        })
+        .collect();
+        tokens.next();
+        if is_jar {
+            let value: proc_macro2::TokenStream = value.into_iter().skip(1).collect();
+            return Ok(value.to_string() == "Jar");
+        }
+    }
+
+    attr.tokens = if args.is_empty() {
+        quote!((jar = Jar))
+    } else {
+        quote!((jar = Jar, #args))
+    };
+    Ok(true)
 }
 
 pub(crate) fn jar_struct_and_friends(
     jar_trait: &Path,
+    dynamic: Option<&syn::Type>,
     input: &ItemStruct,
 ) -> proc_macro2::TokenStream {
     let output_struct = jar_struct(input);
@@ -67,12 +268,54 @@ pub(crate) fn jar_struct_and_friends(
 
     let jar_impl = jar_impl(jar_struct, jar_trait, input);
 
+    let dynamic_jar_impls = dynamic.map(|db| dynamic_jar_impls(jar_struct, db));
+
     quote! {
         #output_struct
 
         #(#has_ingredients_for_impls)*
 
         #jar_impl
+
+        #dynamic_jar_impls
+    }
+}
+
+/// For a jar with the `dynamic = <db>` option: the impls that `#[salsa::db]`
+/// generates for the jars it lists, which find the jar among the jars added
+/// to the storage of `db` with `Storage::add_jar`.
+fn dynamic_jar_impls(jar_struct: &Ident, db: &syn::Type) -> proc_macro2::TokenStream {
+    quote! {
+        impl salsa::storage::JarFromJars<#jar_struct> for #db {
+            fn jar_from_jars<'db>(jars: &Self::Jars) -> &#jar_struct {
+                <Self as salsa::storage::HasJars>::dynamic_jars(jars).jar::<#jar_struct>()
+            }
+
+            fn jar_from_jars_mut<'db>(jars: &mut Self::Jars) -> &mut #jar_struct {
+                <Self as salsa::storage::HasJars>::dynamic_jars_mut(jars).jar_mut::<#jar_struct>()
+            }
+        }
+
+        impl salsa::storage::HasJar<#jar_struct> for #db {
+            fn jar(&self) -> (&#jar_struct, &salsa::Runtime) {
+                let (__jars, __runtime) = <Self as salsa::storage::HasJars>::jars(self);
+                (<Self as salsa::storage::JarFromJars<#jar_struct>>::jar_from_jars(__jars), __runtime)
+            }
+
+            fn jar_mut(&mut self) -> (&mut #jar_struct, &mut salsa::Runtime) {
+                let (__jars, __runtime) = <Self as salsa::storage::HasJars>::jars_mut(self);
+                (<Self as salsa::storage::JarFromJars<#jar_struct>>::jar_from_jars_mut(__jars), __runtime)
+            }
+        }
+
+        impl salsa::storage::DbWithJar<#jar_struct> for #db {
+            fn as_jar_db<'db>(&'db self) -> &'db <#jar_struct as salsa::jar::Jar<'db>>::DynDb
+            where
+                'db: 'db,
+            {
+                self as &'db <#jar_struct as salsa::jar::Jar<'db>>::DynDb
+            }
+        }
     }
 }
 
@@ -30,15 +30,28 @@ pub(crate) fn literal(ident: &proc_macro2::Ident) -> proc_macro2::Literal {
     proc_macro2::Literal::string(&ident.to_string())
 }
 
+/// Whether `attr` is `#[salsa::<name>]`, with or without arguments.
+pub(crate) fn is_salsa_attr(attr: &syn::Attribute, name: &str) -> bool {
+    let path = &attr.path.segments;
+    path.len() == 2
+        && path[0].arguments == syn::PathArguments::None
+        && path[0].ident == "salsa"
+        && path[1].arguments == syn::PathArguments::None
+        && path[1].ident == name
+}
+
 mod accumulator;
 mod configuration;
 mod db;
+mod debug_with_db;
 mod input;
+mod input_map;
 mod interned;
 mod jar;
 mod options;
 mod salsa_struct;
 mod tracked;
+mod tracked_enum;
 mod tracked_fn;
 mod tracked_struct;
 
@@ -57,6 +70,11 @@ pub fn db(args: TokenStream, input: TokenStream) -> TokenStream {
     db::db(args, input)
 }
 
+#[proc_macro_attribute]
+pub fn input_map(args: TokenStream, input: TokenStream) -> TokenStream {
+    input_map::input_map(args, input)
+}
+
 #[proc_macro_attribute]
 pub fn interned(args: TokenStream, input: TokenStream) -> TokenStream {
     interned::interned(args, input)
@@ -71,3 +89,8 @@ pub fn input(args: TokenStream, input: TokenStream) -> TokenStream {
 pub fn tracked(args: TokenStream, input: TokenStream) -> TokenStream {
     tracked::tracked(args, input)
 }
+
+#[proc_macro_derive(DebugWithDb, attributes(debug_with_db))]
+pub fn debug_with_db(input: TokenStream) -> TokenStream {
+    debug_with_db::debug_with_db(input)
+}
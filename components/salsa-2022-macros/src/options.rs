@@ -19,6 +19,12 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `no_eq` identifier.
     pub no_eq: Option<syn::Ident>,
 
+    /// The `eq = <path>` option is used to indicate the function used to compare
+    /// old and new values when deciding whether to backdate.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub eq: Option<syn::Path>,
+
     /// The `singleton` option is used on input with only one field
     /// It allows the creation of convenient methods
     pub singleton: Option<syn::Ident>,
@@ -44,6 +50,24 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<path>`.
     pub recovery_fn: Option<syn::Path>,
 
+    /// The `cycle_fn = <path>` option is used to indicate the function invoked
+    /// after each iteration of a query that iterates to a fixed point.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub cycle_fn: Option<syn::Path>,
+
+    /// The `cycle_initial = <path>` option is used to indicate the function that
+    /// computes the initial provisional value of a query that iterates to a fixed point.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub cycle_initial: Option<syn::Path>,
+
+    /// The `cycle_max_iterations = <u32>` option is used to set how many times a
+    /// query that iterates to a fixed point is executed before salsa panics.
+    ///
+    /// If this is `Some`, the value is the `<u32>`.
+    pub cycle_max_iterations: Option<u32>,
+
     /// The `data = <ident>` option is used to define the name of the data type for an interned
     /// struct.
     ///
@@ -61,6 +85,146 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<ident>`.
     pub constructor_name: Option<syn::Ident>,
 
+    /// The `persist` option is used to signal that the ingredients of an item
+    /// should be saved when the database is persisted.
+    ///
+    /// If this is `Some`, the value is the `persist` identifier.
+    pub persist: Option<syn::Ident>,
+
+    /// The `dedup` option is used to signal that an accumulator should
+    /// drop repeated values when they are collected.
+    ///
+    /// If this is `Some`, the value is the `dedup` identifier.
+    pub dedup: Option<syn::Ident>,
+
+    /// The `sort_by_key = <path>` option is used to indicate the function giving
+    /// the key by which the values of an accumulator are sorted when collected.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub sort_by_key: Option<syn::Path>,
+
+    /// The `volatile` option is used to signal that a tracked function
+    /// must be re-executed in every revision.
+    ///
+    /// If this is `Some`, the value is the `volatile` identifier.
+    pub volatile: Option<syn::Ident>,
+
+    /// The `heap_size = <path>` option is used to indicate the function that
+    /// computes the number of bytes a value of a tracked function owns on the heap.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub heap_size: Option<syn::Path>,
+
+    /// The `ttl = <expr>` option is used to indicate how long, as a
+    /// `std::time::Duration`, the value of a tracked function stays fresh.
+    ///
+    /// If this is `Some`, the value is the `<expr>`.
+    pub ttl: Option<syn::Expr>,
+
+    /// The `shards = <usize>` option is used to set the number of shards
+    /// of the map that stores the memoized values of a tracked function.
+    ///
+    /// If this is `Some`, the value is the `<usize>`.
+    pub shards: Option<usize>,
+
+    /// The `history = <usize>` option is used to keep the values replaced
+    /// by the last changes of the memoized values of a tracked function.
+    ///
+    /// If this is `Some`, the value is the `<usize>`.
+    pub history: Option<usize>,
+
+    /// The `content_ids` option is used to signal that the ids of a tracked
+    /// struct are derived from the hash of its id fields.
+    ///
+    /// If this is `Some`, the value is the `content_ids` identifier.
+    pub content_ids: Option<syn::Ident>,
+
+    /// The `dynamic = <type>` option is used to indicate the database to which
+    /// a jar is added at runtime rather than listed in `#[salsa::db]`.
+    ///
+    /// If this is `Some`, the value is the `<type>`.
+    pub dynamic: Option<syn::Type>,
+
+    /// The `share_values` option is used to store the equal values of a
+    /// tracked function once for all keys.
+    ///
+    /// If this is `Some`, the value is the `share_values` identifier.
+    pub share_values: Option<syn::Ident>,
+
+    /// The `retry_errors` option is used to signal that the `Err` results of a
+    /// tracked function must be re-executed in the next revision.
+    ///
+    /// If this is `Some`, the value is the `retry_errors` identifier.
+    pub retry_errors: Option<syn::Ident>,
+
+    /// The `cache = <path>` option is used to indicate the function that
+    /// fingerprints what a value of a tracked function depends on, to share it
+    /// with other databases through a cache backend.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub cache: Option<syn::Path>,
+
+    /// The `update` option is used to signal that the new value of a tracked
+    /// function is written into its old value, reusing what the old value owns
+    /// (see `salsa::update::Update`).
+    ///
+    /// If this is `Some`, the value is the `update` identifier.
+    pub update: Option<syn::Ident>,
+
+    /// The `boxed` option is used to store the values of a tracked function
+    /// boxed, so that a memo whose value was evicted (or is kept by the history)
+    /// only takes the space of a pointer for it.
+    ///
+    /// If this is `Some`, the value is the `boxed` identifier.
+    pub boxed: Option<syn::Ident>,
+
+    /// The `diff` option is used to keep the previous values of a tracked
+    /// function, whose result implements `salsa::diff::Diffable`, to tell what
+    /// changed since a revision.
+    ///
+    /// If this is `Some`, the value is the `diff` identifier.
+    pub diff: Option<syn::Ident>,
+
+    /// The `project(field: Type, ...)` option is used to generate a projection
+    /// query for each listed field of the struct returned by a tracked function,
+    /// which changes only when that field does.
+    ///
+    /// If this is `Some`, the value is the list of fields and their types.
+    pub project: Option<Vec<(syn::Ident, syn::Type)>>,
+
+    /// The `version = <lit>` option is used to change the fingerprint of a
+    /// tracked function when its implementation changes, so that the values
+    /// cached or persisted by the previous version are not reused.
+    ///
+    /// If this is `Some`, the value is the `<lit>`.
+    pub version: Option<syn::Lit>,
+
+    /// The `phase = "<name>"` option is used to count the work of a tracked
+    /// function towards the named phase, in the query statistics.
+    ///
+    /// If this is `Some`, the value is the `"<name>"`.
+    pub phase: Option<syn::LitStr>,
+
+    /// The `validate(<path> -> <ty>)` option is used to check the fields of an
+    /// interned struct before they are interned, with the function at `<path>`,
+    /// which fails with an error of type `<ty>`.
+    ///
+    /// If this is `Some`, the value is the `(<path>, <ty>)`.
+    pub validate: Option<(syn::Path, syn::Type)>,
+
+    /// The `serialize` option is used to generate `Serialize` and `Deserialize`
+    /// impls for a salsa struct, which go through the attached database.
+    ///
+    /// If this is `Some`, the value is the `serialize` identifier.
+    pub serialize: Option<syn::Ident>,
+
+    /// The `intern_args` option is used to intern the argument of a tracked
+    /// function taking a single one into its key, like the arguments of functions
+    /// taking several, rather than requiring it to be a salsa struct.
+    ///
+    /// If this is `Some`, the value is the `intern_args` identifier.
+    pub intern_args: Option<syn::Ident>,
+
     /// Remember the `A` parameter, which plays no role after parsing.
     phantom: PhantomData<A>,
 }
@@ -71,30 +235,95 @@ impl<A: AllowedOptions> Default for Options<A> {
             return_ref: Default::default(),
             specify: Default::default(),
             no_eq: Default::default(),
+            eq: Default::default(),
             jar_ty: Default::default(),
             db_path: Default::default(),
             recovery_fn: Default::default(),
+            cycle_fn: Default::default(),
+            cycle_initial: Default::default(),
+            cycle_max_iterations: Default::default(),
             data: Default::default(),
             constructor_name: Default::default(),
             phantom: Default::default(),
             lru: Default::default(),
             singleton: Default::default(),
+            persist: Default::default(),
+            dedup: Default::default(),
+            sort_by_key: Default::default(),
+            volatile: Default::default(),
+            heap_size: Default::default(),
+            ttl: Default::default(),
+            shards: Default::default(),
+            history: Default::default(),
+            content_ids: Default::default(),
+            dynamic: Default::default(),
+            share_values: Default::default(),
+            retry_errors: Default::default(),
+            cache: Default::default(),
+            update: Default::default(),
+            serialize: Default::default(),
+            boxed: Default::default(),
+            diff: Default::default(),
+            project: Default::default(),
+            version: Default::default(),
+            phase: Default::default(),
+            validate: Default::default(),
+            intern_args: Default::default(),
         }
     }
 }
 
+/// The options generating serde code need the `persist` feature of salsa, which
+/// enables that of this crate.
+fn require_persist_feature(ident: &syn::Ident) -> syn::Result<()> {
+    if cfg!(feature = "persist") {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            ident.span(),
+            format!("the `{ident}` option requires the `persist` feature of salsa"),
+        ))
+    }
+}
+
 /// These flags determine which options are allowed in a given context
 pub(crate) trait AllowedOptions {
     const RETURN_REF: bool;
     const SPECIFY: bool;
     const NO_EQ: bool;
+    const EQ: bool;
     const SINGLETON: bool;
     const JAR: bool;
     const DATA: bool;
     const DB: bool;
     const RECOVERY_FN: bool;
+    const CYCLE_FN: bool;
+    const CYCLE_INITIAL: bool;
+    const CYCLE_MAX_ITERATIONS: bool;
     const LRU: bool;
     const CONSTRUCTOR_NAME: bool;
+    const PERSIST: bool;
+    const DEDUP: bool;
+    const SORT_BY_KEY: bool;
+    const VOLATILE: bool;
+    const HEAP_SIZE: bool;
+    const TTL: bool;
+    const SHARDS: bool;
+    const HISTORY: bool;
+    const CONTENT_IDS: bool;
+    const DYNAMIC: bool;
+    const SHARE_VALUES: bool;
+    const RETRY_ERRORS: bool;
+    const CACHE: bool;
+    const UPDATE: bool;
+    const SERIALIZE: bool;
+    const BOXED: bool;
+    const DIFF: bool;
+    const PROJECT: bool;
+    const VERSION: bool;
+    const PHASE: bool;
+    const VALIDATE: bool;
+    const INTERN_ARGS: bool;
 }
 
 type Equals = syn::Token![=];
@@ -114,6 +343,16 @@ impl<A: AllowedOptions> Options<A> {
     pub(crate) fn should_backdate(&self) -> bool {
         self.no_eq.is_none()
     }
+
+    /// The `Routes` method used to register the ingredients of this item:
+    /// `push_persistent` if the `persist` option was given, `push` otherwise.
+    pub(crate) fn routes_push_method(&self) -> syn::Ident {
+        if self.persist.is_some() {
+            syn::Ident::new("push_persistent", proc_macro2::Span::call_site())
+        } else {
+            syn::Ident::new("push", proc_macro2::Span::call_site())
+        }
+    }
 }
 
 impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
@@ -124,7 +363,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
             let ident: syn::Ident = syn::Ident::parse_any(input)?;
             if ident == "return_ref" {
                 if A::RETURN_REF {
-                    if let Some(old) = std::mem::replace(&mut options.return_ref, Some(ident)) {
+                    if let Some(old) = options.return_ref.replace(ident) {
                         return Err(syn::Error::new(
                             old.span(),
                             "option `return_ref` provided twice",
@@ -138,7 +377,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 }
             } else if ident == "no_eq" {
                 if A::NO_EQ {
-                    if let Some(old) = std::mem::replace(&mut options.no_eq, Some(ident)) {
+                    if let Some(old) = options.no_eq.replace(ident) {
                         return Err(syn::Error::new(old.span(), "option `no_eq` provided twice"));
                     }
                 } else {
@@ -147,9 +386,22 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`no_eq` option not allowed here",
                     ));
                 }
+            } else if ident == "eq" {
+                if A::EQ {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.eq.replace(path) {
+                        return Err(syn::Error::new(old.span(), "option `eq` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`eq` option not allowed here",
+                    ));
+                }
             } else if ident == "singleton" {
                 if A::SINGLETON {
-                    if let Some(old) = std::mem::replace(&mut options.singleton, Some(ident)) {
+                    if let Some(old) = options.singleton.replace(ident) {
                         return Err(syn::Error::new(
                             old.span(),
                             "option `singleton` provided twice",
@@ -163,7 +415,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 }
             } else if ident == "specify" {
                 if A::SPECIFY {
-                    if let Some(old) = std::mem::replace(&mut options.specify, Some(ident)) {
+                    if let Some(old) = options.specify.replace(ident) {
                         return Err(syn::Error::new(
                             old.span(),
                             "option `specify` provided twice",
@@ -179,7 +431,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 if A::JAR {
                     let _eq = Equals::parse(input)?;
                     let ty = syn::Type::parse(input)?;
-                    if let Some(old) = std::mem::replace(&mut options.jar_ty, Some(ty)) {
+                    if let Some(old) = options.jar_ty.replace(ty) {
                         return Err(syn::Error::new(old.span(), "option `jar` provided twice"));
                     }
                 } else {
@@ -192,7 +444,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 if A::DB {
                     let _eq = Equals::parse(input)?;
                     let path = syn::Path::parse(input)?;
-                    if let Some(old) = std::mem::replace(&mut options.db_path, Some(path)) {
+                    if let Some(old) = options.db_path.replace(path) {
                         return Err(syn::Error::new(old.span(), "option `db` provided twice"));
                     }
                 } else {
@@ -205,7 +457,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 if A::RECOVERY_FN {
                     let _eq = Equals::parse(input)?;
                     let path = syn::Path::parse(input)?;
-                    if let Some(old) = std::mem::replace(&mut options.recovery_fn, Some(path)) {
+                    if let Some(old) = options.recovery_fn.replace(path) {
                         return Err(syn::Error::new(
                             old.span(),
                             "option `recovery_fn` provided twice",
@@ -217,11 +469,66 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`recovery_fn` option not allowed here",
                     ));
                 }
+            } else if ident == "cycle_fn" {
+                if A::CYCLE_FN {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.cycle_fn.replace(path) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `cycle_fn` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cycle_fn` option not allowed here",
+                    ));
+                }
+            } else if ident == "cycle_initial" {
+                if A::CYCLE_INITIAL {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.cycle_initial.replace(path) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `cycle_initial` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cycle_initial` option not allowed here",
+                    ));
+                }
+            } else if ident == "cycle_max_iterations" {
+                if A::CYCLE_MAX_ITERATIONS {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::LitInt::parse(input)?;
+                    let value = lit.base10_parse::<u32>()?;
+                    if value == 0 {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "`cycle_max_iterations` must be at least 1",
+                        ));
+                    }
+                    if options.cycle_max_iterations.replace(value).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `cycle_max_iterations` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cycle_max_iterations` option not allowed here",
+                    ));
+                }
             } else if ident == "data" {
                 if A::DATA {
                     let _eq = Equals::parse(input)?;
                     let ident = syn::Ident::parse(input)?;
-                    if let Some(old) = std::mem::replace(&mut options.data, Some(ident)) {
+                    if let Some(old) = options.data.replace(ident) {
                         return Err(syn::Error::new(old.span(), "option `data` provided twice"));
                     }
                 } else {
@@ -235,7 +542,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                     let _eq = Equals::parse(input)?;
                     let lit = syn::LitInt::parse(input)?;
                     let value = lit.base10_parse::<usize>()?;
-                    if let Some(old) = std::mem::replace(&mut options.lru, Some(value)) {
+                    if let Some(old) = options.lru.replace(value) {
                         return Err(syn::Error::new(old.span(), "option `lru` provided twice"));
                     }
                 } else {
@@ -248,8 +555,7 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                 if A::CONSTRUCTOR_NAME {
                     let _eq = Equals::parse(input)?;
                     let ident = syn::Ident::parse(input)?;
-                    if let Some(old) = std::mem::replace(&mut options.constructor_name, Some(ident))
-                    {
+                    if let Some(old) = options.constructor_name.replace(ident) {
                         return Err(syn::Error::new(
                             old.span(),
                             "option `constructor` provided twice",
@@ -261,6 +567,340 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`constructor` option not allowed here",
                     ));
                 }
+            } else if ident == "persist" {
+                if A::PERSIST {
+                    require_persist_feature(&ident)?;
+                    if let Some(old) = options.persist.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `persist` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`persist` option not allowed here",
+                    ));
+                }
+            } else if ident == "dedup" {
+                if A::DEDUP {
+                    if let Some(old) = options.dedup.replace(ident) {
+                        return Err(syn::Error::new(old.span(), "option `dedup` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`dedup` option not allowed here",
+                    ));
+                }
+            } else if ident == "sort_by_key" {
+                if A::SORT_BY_KEY {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.sort_by_key.replace(path) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `sort_by_key` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`sort_by_key` option not allowed here",
+                    ));
+                }
+            } else if ident == "volatile" {
+                if A::VOLATILE {
+                    if let Some(old) = options.volatile.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `volatile` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`volatile` option not allowed here",
+                    ));
+                }
+            } else if ident == "heap_size" {
+                if A::HEAP_SIZE {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.heap_size.replace(path) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `heap_size` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`heap_size` option not allowed here",
+                    ));
+                }
+            } else if ident == "ttl" {
+                if A::TTL {
+                    let _eq = Equals::parse(input)?;
+                    let expr = syn::Expr::parse(input)?;
+                    if let Some(old) = options.ttl.replace(expr) {
+                        return Err(syn::Error::new_spanned(old, "option `ttl` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`ttl` option not allowed here",
+                    ));
+                }
+            } else if ident == "shards" {
+                if A::SHARDS {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::LitInt::parse(input)?;
+                    let value = lit.base10_parse::<usize>()?;
+                    if value < 2 || !value.is_power_of_two() {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "`shards` must be a power of two greater than 1",
+                        ));
+                    }
+                    if options.shards.replace(value).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `shards` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`shards` option not allowed here",
+                    ));
+                }
+            } else if ident == "history" {
+                if A::HISTORY {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::LitInt::parse(input)?;
+                    let value = lit.base10_parse::<usize>()?;
+                    if options.history.replace(value).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `history` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`history` option not allowed here",
+                    ));
+                }
+            } else if ident == "content_ids" {
+                if A::CONTENT_IDS {
+                    if let Some(old) = options.content_ids.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `content_ids` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`content_ids` option not allowed here",
+                    ));
+                }
+            } else if ident == "dynamic" {
+                if A::DYNAMIC {
+                    let _eq = Equals::parse(input)?;
+                    let ty = syn::Type::parse(input)?;
+                    if let Some(old) = options.dynamic.replace(ty) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `dynamic` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`dynamic` option not allowed here",
+                    ));
+                }
+            } else if ident == "share_values" {
+                if A::SHARE_VALUES {
+                    if let Some(old) = options.share_values.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `share_values` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`share_values` option not allowed here",
+                    ));
+                }
+            } else if ident == "retry_errors" {
+                if A::RETRY_ERRORS {
+                    if let Some(old) = options.retry_errors.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `retry_errors` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`retry_errors` option not allowed here",
+                    ));
+                }
+            } else if ident == "cache" {
+                if A::CACHE {
+                    require_persist_feature(&ident)?;
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = options.cache.replace(path) {
+                        return Err(syn::Error::new(old.span(), "option `cache` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cache` option not allowed here",
+                    ));
+                }
+            } else if ident == "update" {
+                if A::UPDATE {
+                    if let Some(old) = options.update.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `update` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`update` option not allowed here",
+                    ));
+                }
+            } else if ident == "boxed" {
+                if A::BOXED {
+                    if let Some(old) = options.boxed.replace(ident) {
+                        return Err(syn::Error::new(old.span(), "option `boxed` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`boxed` option not allowed here",
+                    ));
+                }
+            } else if ident == "diff" {
+                if A::DIFF {
+                    if let Some(old) = options.diff.replace(ident) {
+                        return Err(syn::Error::new(old.span(), "option `diff` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`diff` option not allowed here",
+                    ));
+                }
+            } else if ident == "project" {
+                if A::PROJECT {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let fields =
+                        syn::punctuated::Punctuated::<ProjectField, Comma>::parse_terminated(
+                            &content,
+                        )?;
+                    let fields = fields
+                        .into_iter()
+                        .map(|field| (field.ident, field.ty))
+                        .collect();
+                    if options.project.replace(fields).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `project` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`project` option not allowed here",
+                    ));
+                }
+            } else if ident == "version" {
+                if A::VERSION {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::Lit::parse(input)?;
+                    if let Some(old) = options.version.replace(lit) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `version` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`version` option not allowed here",
+                    ));
+                }
+            } else if ident == "phase" {
+                if A::PHASE {
+                    let _eq = Equals::parse(input)?;
+                    let lit: syn::LitStr = input.parse()?;
+                    if let Some(old) = options.phase.replace(lit) {
+                        return Err(syn::Error::new(old.span(), "option `phase` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`phase` option not allowed here",
+                    ));
+                }
+            } else if ident == "validate" {
+                if A::VALIDATE {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let path = syn::Path::parse(&content)?;
+                    let _arrow: syn::Token![->] = content.parse()?;
+                    let ty = syn::Type::parse(&content)?;
+                    if options.validate.replace((path, ty)).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `validate` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`validate` option not allowed here",
+                    ));
+                }
+            } else if ident == "serialize" {
+                if A::SERIALIZE {
+                    require_persist_feature(&ident)?;
+                    if let Some(old) = options.serialize.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `serialize` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`serialize` option not allowed here",
+                    ));
+                }
+            } else if ident == "intern_args" {
+                if A::INTERN_ARGS {
+                    if let Some(old) = options.intern_args.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `intern_args` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`intern_args` option not allowed here",
+                    ));
+                }
             } else {
                 return Err(syn::Error::new(
                     ident.span(),
@@ -278,3 +918,18 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
         Ok(options)
     }
 }
+
+/// A field listed in the `project` option: `field: Type`.
+struct ProjectField {
+    ident: syn::Ident,
+    ty: syn::Type,
+}
+
+impl syn::parse::Parse for ProjectField {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident = syn::Ident::parse(input)?;
+        let _colon: syn::Token![:] = input.parse()?;
+        let ty = syn::Type::parse(input)?;
+        Ok(ProjectField { ident, ty })
+    }
+}
@@ -64,6 +64,12 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         struct_item: syn::ItemStruct,
     ) -> syn::Result<Self> {
         let args: Options<A> = syn::parse(args)?;
+        if let Some(lifetime) = struct_item.generics.lifetimes().next() {
+            return Err(syn::Error::new_spanned(
+                lifetime,
+                "salsa structs cannot have lifetime parameters",
+            ));
+        }
         let fields = Self::extract_options(&struct_item)?;
         Ok(Self {
             kind,
@@ -133,6 +139,64 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         &self.struct_item.ident
     }
 
+    /// The type of the "identity" struct, with the generic parameters the user gave
+    /// (e.g., `Spanned<T>`).
+    pub(crate) fn id_ty(&self) -> syn::Type {
+        let ident = self.id_ident();
+        let (_, ty_generics, _) = self.struct_item.generics.split_for_impl();
+        parse_quote!(#ident #ty_generics)
+    }
+
+    /// True if the user gave this struct generic parameters.
+    pub(crate) fn is_generic(&self) -> bool {
+        !self.struct_item.generics.params.is_empty()
+    }
+
+    /// The generics of the impls for this struct: those the user gave, with `'static`
+    /// on each type parameter, as ingredients are stored in the database. Each
+    /// instantiation of a generic struct has its own ingredients, which must be listed
+    /// in the jar (e.g., `Jar(Spanned<Expr>, Spanned<Stmt>)`), so the impls also
+    /// require the jar to hold those of the instantiation: `Jar: HasIngredientsFor<Spanned<T>>`.
+    pub(crate) fn impl_generics(&self) -> syn::Generics {
+        let mut generics = self.struct_item.generics.clone();
+        if !self.is_generic() {
+            return generics;
+        }
+        for ty_param in generics.type_params_mut() {
+            ty_param.bounds.push(parse_quote!('static));
+        }
+        let jar_ty = self.jar_ty();
+        let id_ty = self.id_ty();
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#jar_ty: salsa::storage::HasIngredientsFor<#id_ty>));
+        generics
+    }
+
+    /// The generics of [`Self::impl_generics`], with a leading `DB` parameter
+    /// bounded by `db_bounds`, for the impls of traits generic over the database.
+    pub(crate) fn db_impl_generics(&self, db_bounds: TokenStream) -> syn::Generics {
+        let mut generics = self.impl_generics();
+        generics.params.insert(0, parse_quote!(DB));
+        generics
+            .make_where_clause()
+            .predicates
+            .insert(0, parse_quote!(DB: #db_bounds));
+        generics
+    }
+
+    /// The type of the marker field of the structs generated for a generic struct,
+    /// which use each of its type parameters: `PhantomData<fn() -> (T, ...)>`.
+    /// `None` if the struct is not generic.
+    pub(crate) fn phantom_ty(&self) -> Option<syn::Type> {
+        if !self.is_generic() {
+            return None;
+        }
+        let ty_params = self.struct_item.generics.type_params().map(|p| &p.ident);
+        Some(parse_quote!(std::marker::PhantomData<fn() -> (#(#ty_params,)*)>))
+    }
+
     /// Type of the jar for this struct
     pub(crate) fn jar_ty(&self) -> syn::Type {
         self.args.jar_ty()
@@ -143,6 +207,21 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         self.args.singleton.is_some()
     }
 
+    /// checks if the "persist" flag was set
+    pub(crate) fn is_persistent(&self) -> bool {
+        self.args.persist.is_some()
+    }
+
+    /// checks if the "content_ids" flag was set
+    pub(crate) fn has_content_ids(&self) -> bool {
+        self.args.content_ids.is_some()
+    }
+
+    /// The `Routes` method used to register this struct's ingredients.
+    pub(crate) fn routes_push_method(&self) -> syn::Ident {
+        self.args.routes_push_method()
+    }
+
     pub(crate) fn db_dyn_ty(&self) -> syn::Type {
         let jar_ty = self.jar_ty();
         parse_quote! {
@@ -162,8 +241,10 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         }
     }
 
-    /// Generate `struct Foo(Id)`
-    pub(crate) fn id_struct(&self) -> syn::ItemStruct {
+    /// Generate `struct Foo(Id)`, or `struct Foo<T>(Id, PhantomData<..>)` for a
+    /// generic struct, whose traits are implemented by hand: derived ones would
+    /// require them of `T`.
+    pub(crate) fn id_struct(&self) -> TokenStream {
         let ident = self.id_ident();
         let visibility = &self.struct_item.vis;
 
@@ -175,10 +256,56 @@ impl<A: AllowedOptions> SalsaStruct<A> {
             .filter(|attr| !attr.path.is_ident("derive"))
             .collect();
 
-        parse_quote! {
+        let phantom_ty = match self.phantom_ty() {
+            Some(phantom_ty) => phantom_ty,
+            None => {
+                return quote! {
+                    #(#attrs)*
+                    #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+                    #visibility struct #ident(salsa::Id);
+                }
+            }
+        };
+
+        let generics = &self.struct_item.generics;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        quote! {
             #(#attrs)*
-            #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
-            #visibility struct #ident(salsa::Id);
+            #visibility struct #ident #generics (salsa::Id, #phantom_ty) #where_clause;
+
+            impl #impl_generics Copy for #ident #ty_generics #where_clause {}
+
+            impl #impl_generics Clone for #ident #ty_generics #where_clause {
+                fn clone(&self) -> Self {
+                    *self
+                }
+            }
+
+            impl #impl_generics PartialEq for #ident #ty_generics #where_clause {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0 == other.0
+                }
+            }
+
+            impl #impl_generics Eq for #ident #ty_generics #where_clause {}
+
+            impl #impl_generics PartialOrd for #ident #ty_generics #where_clause {
+                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                    Some(std::cmp::Ord::cmp(self, other))
+                }
+            }
+
+            impl #impl_generics Ord for #ident #ty_generics #where_clause {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    self.0.cmp(&other.0)
+                }
+            }
+
+            impl #impl_generics std::hash::Hash for #ident #ty_generics #where_clause {
+                fn hash<__H: std::hash::Hasher>(&self, state: &mut __H) {
+                    self.0.hash(state)
+                }
+            }
         }
     }
 
@@ -191,15 +318,28 @@ impl<A: AllowedOptions> SalsaStruct<A> {
     pub(crate) fn data_struct(&self) -> syn::ItemStruct {
         let ident = self.data_ident();
         let visibility = self.visibility();
+        let generics = &self.struct_item.generics;
+        let where_clause = &generics.where_clause;
+        let phantom_field = self
+            .phantom_ty()
+            .map(|phantom_ty| quote!(__phantom: #phantom_ty,));
         let all_field_names = self.all_field_names();
         let all_field_tys = self.all_field_tys();
+        let serde_attrs: Option<TokenStream> = self.is_persistent().then(|| {
+            quote! {
+                #[derive(salsa::serde::Serialize, salsa::serde::Deserialize)]
+                #[serde(crate = "salsa::serde")]
+            }
+        });
         parse_quote! {
             /// Internal struct used for interned item
             #[derive(Eq, PartialEq, Hash, Clone)]
-            #visibility struct #ident {
+            #serde_attrs
+            #visibility struct #ident #generics #where_clause {
                 #(
                     #all_field_names: #all_field_tys,
                 )*
+                #phantom_field
             }
         }
     }
@@ -217,6 +357,11 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         }
     }
 
+    /// The function checking the fields of an interned struct, and the type of its errors.
+    pub(crate) fn validate(&self) -> Option<&(syn::Path, syn::Type)> {
+        self.args.validate.as_ref()
+    }
+
     /// For each of the fields passed as an argument,
     /// generate a struct named `Ident_Field` and an impl
     /// of `salsa::function::Configuration` for that struct.
@@ -225,8 +370,14 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         fields: impl Iterator<Item = &'a SalsaField>,
     ) -> (Vec<syn::ItemStruct>, Vec<syn::ItemImpl>) {
         let ident = &self.id_ident();
+        let id_ty = self.id_ty();
         let jar_ty = self.jar_ty();
         let visibility = self.visibility();
+        let generics = &self.struct_item.generics;
+        let (_, ty_generics, where_clause) = generics.split_for_impl();
+        let phantom_field = self.phantom_ty().map(|phantom_ty| quote!(, #phantom_ty));
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, impl_where_clause) = impl_generics.split_for_impl();
         fields
             .map(|ef| {
                 let value_field_name = ef.name();
@@ -241,7 +392,7 @@ impl<A: AllowedOptions> SalsaStruct<A> {
                 );
                 let item_struct: syn::ItemStruct = parse_quote! {
                     #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
-                    #visibility struct #config_name(std::convert::Infallible);
+                    #visibility struct #config_name #generics (std::convert::Infallible #phantom_field) #where_clause;
                 };
 
                 let execute_string = Literal::string(&format!("`execute` method for field `{}::{}` invoked",
@@ -254,24 +405,36 @@ impl<A: AllowedOptions> SalsaStruct<A> {
                     ef.name(),
                 ));
 
+                // A lazy field is computed by its function the first time it is read;
+                // other fields are always specified when the struct is created.
+                let execute_body = match &ef.lazy_fn {
+                    Some(lazy_fn) => quote! { #lazy_fn(db, key) },
+                    None => quote! { panic!(#execute_string) },
+                };
+
                 let should_backdate_value_fn = configuration::should_backdate_value_fn(value_field_backdate);
+                let (cycle_initial_fn, cycle_iterate_fn) = configuration::panic_cycle_iterate_fns();
                 let item_impl: syn::ItemImpl = parse_quote! {
-                    impl salsa::function::Configuration for #config_name {
+                    impl #impl_generics salsa::function::Configuration for #config_name #ty_generics #impl_where_clause {
                         type Jar = #jar_ty;
-                        type SalsaStruct = #ident;
-                        type Key = #ident;
+                        type SalsaStruct = #id_ty;
+                        type Key = #id_ty;
                         type Value = #value_field_ty;
                         const CYCLE_STRATEGY: salsa::cycle::CycleRecoveryStrategy = salsa::cycle::CycleRecoveryStrategy::Panic;
 
                         #should_backdate_value_fn
 
                         fn execute(db: &salsa::function::DynDb<Self>, key: Self::Key) -> Self::Value {
-                            panic!(#execute_string)
+                            #execute_body
                         }
 
                         fn recover_from_cycle(db: &salsa::function::DynDb<Self>, cycle: &salsa::Cycle, key: Self::Key) -> Self::Value {
                             panic!(#recover_from_cycle_string)
                         }
+
+                        #cycle_initial_fn
+
+                        #cycle_iterate_fn
                     }
                 };
 
@@ -282,15 +445,21 @@ impl<A: AllowedOptions> SalsaStruct<A> {
 
     /// Generate `impl salsa::AsId for Foo`
     pub(crate) fn as_id_impl(&self) -> syn::ItemImpl {
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        // `AsId` requires `Debug`, which requires the ingredients.
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+        let phantom_field = self
+            .is_generic()
+            .then(|| quote!(, std::marker::PhantomData));
         parse_quote! {
-            impl salsa::AsId for #ident {
+            impl #impl_generics salsa::AsId for #id_ty #where_clause {
                 fn as_id(self) -> salsa::Id {
                     self.0
                 }
 
                 fn from_id(id: salsa::Id) -> Self {
-                    #ident(id)
+                    Self(id #phantom_field)
                 }
             }
 
@@ -300,6 +469,9 @@ impl<A: AllowedOptions> SalsaStruct<A> {
     /// Generate `impl salsa::DebugWithDb for Foo`
     pub(crate) fn as_debug_with_db_impl(&self) -> syn::ItemImpl {
         let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
 
         let db_type = self.db_dyn_ty();
         let ident_string = ident.to_string();
@@ -307,11 +479,18 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         // `::salsa::debug::helper::SalsaDebug` will use `DebugWithDb` or fallbak to `Debug`
         let fields = self
             .all_fields()
-            .into_iter()
             .map(|field| -> TokenStream {
                 let field_name_string = field.name().to_string();
                 let field_getter = field.get_name();
-                let field_ty = field.ty();
+                // The getter of a `#[return_ref(deref)]` field returns a reference to
+                // the target, which may be unsized: debug the reference instead.
+                let field_ty = if field.has_deref_attr {
+                    let returned_ty = field.returned_ty();
+                    quote! { &#returned_ty }
+                } else {
+                    let field_ty = field.ty();
+                    quote! { #field_ty }
+                };
 
                 let field_debug = quote_spanned! { field.field.span() =>
                     debug_struct = debug_struct.field(
@@ -341,7 +520,7 @@ impl<A: AllowedOptions> SalsaStruct<A> {
 
         // `use ::salsa::debug::helper::Fallback` is needed for the fallback to `Debug` impl
         parse_quote_spanned! {ident.span()=>
-            impl ::salsa::DebugWithDb<#db_type> for #ident {
+            impl #impl_generics ::salsa::DebugWithDb<#db_type> for #id_ty #where_clause {
                 fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>, _db: &#db_type, _include_all_fields: bool) -> ::std::fmt::Result {
                     #[allow(unused_imports)]
                     use ::salsa::debug::helper::Fallback;
@@ -354,6 +533,142 @@ impl<A: AllowedOptions> SalsaStruct<A> {
         }
     }
 
+    /// Generate `impl Debug for Foo`, which prints all fields when a database
+    /// is attached (see `salsa::attach`) and only the id otherwise.
+    pub(crate) fn as_debug_impl(&self) -> syn::ItemImpl {
+        let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+        let ident_string = ident.to_string();
+        let jar_ty = self.jar_ty();
+        parse_quote_spanned! {ident.span()=>
+            impl #impl_generics ::std::fmt::Debug for #id_ty #where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    let attached = ::salsa::attach::with_attached_jar_db::<#jar_ty, _>(|db| {
+                        ::salsa::DebugWithDb::fmt(self, f, db, true)
+                    });
+                    match attached {
+                        Some(result) => result,
+                        None => f.debug_tuple(#ident_string).field(&self.0).finish(),
+                    }
+                }
+            }
+        }
+    }
+
+    /// With the `serialize` option, generates the `Serialize` and `Deserialize` impls
+    /// of the id struct, which go through the database attached with `salsa::attach`:
+    /// the values of `fields` (read with their getters) are serialized, and
+    /// `construct`, an expression of `__db` and of the deserialized `__fields`,
+    /// creates (or, for an interned struct, finds) the struct they are those of.
+    pub(crate) fn serde_impls(
+        &self,
+        fields: &[&SalsaField],
+        construct: TokenStream,
+    ) -> Option<TokenStream> {
+        self.args.serialize.as_ref()?;
+
+        let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let ident_string = Literal::string(&ident.to_string());
+        let jar_ty = self.jar_ty();
+        let generics = &self.struct_item.generics;
+        let (_, ty_generics, where_clause) = generics.split_for_impl();
+        let field_names: Vec<_> = fields.iter().map(|f| f.name()).collect();
+        let field_name_strings: Vec<_> = field_names
+            .iter()
+            .map(|name| Literal::string(&name.to_string()))
+            .collect();
+        let field_get_names: Vec<_> = fields.iter().map(|f| f.get_name()).collect();
+        let field_tys: Vec<_> = fields.iter().map(|f| f.ty()).collect();
+        let field_count = fields.len();
+        let no_db_message = Literal::string(&format!(
+            "no database is attached to (de)serialize `{ident}`, see `salsa::attach`"
+        ));
+
+        // For a generic struct, the fields must be (de)serializable, and `__Fields`
+        // uses each type parameter even if the fields being serialized do not.
+        let mut serialize_generics = self.impl_generics();
+        let mut deserialize_generics = self.impl_generics();
+        deserialize_generics.params.insert(0, parse_quote!('de));
+        let phantom_field = self.phantom_ty().map(|phantom_ty| {
+            serialize_generics.make_where_clause().predicates.extend(
+                field_tys.iter().map(|ty| -> syn::WherePredicate {
+                    parse_quote!(#ty: salsa::serde::Serialize)
+                }),
+            );
+            deserialize_generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(__Fields #ty_generics: salsa::serde::Deserialize<'de>));
+            quote! {
+                #[serde(skip)]
+                __phantom: #phantom_ty,
+            }
+        });
+        let (serialize_impl_generics, _, serialize_where_clause) =
+            serialize_generics.split_for_impl();
+        let (deserialize_impl_generics, _, deserialize_where_clause) =
+            deserialize_generics.split_for_impl();
+
+        Some(quote! {
+            const _: () = {
+                use salsa::serde::{de::Error as _, ser::Error as _, ser::SerializeStruct as _};
+
+                impl #serialize_impl_generics salsa::serde::Serialize for #id_ty #serialize_where_clause {
+                    fn serialize<__S>(&self, __serializer: __S) -> Result<__S::Ok, __S::Error>
+                    where
+                        __S: salsa::serde::Serializer,
+                    {
+                        salsa::attach::with_attached_jar_db::<#jar_ty, _>(|__db| {
+                            let mut __state = __serializer.serialize_struct(#ident_string, #field_count)?;
+                            #(
+                                __state.serialize_field(#field_name_strings, &self.#field_get_names(__db))?;
+                            )*
+                            __state.end()
+                        })
+                        .unwrap_or_else(|| Err(__S::Error::custom(#no_db_message)))
+                    }
+                }
+
+                #[derive(salsa::serde::Deserialize)]
+                #[serde(crate = "salsa::serde", rename = #ident_string)]
+                struct __Fields #generics #where_clause {
+                    #(#field_names: #field_tys,)*
+                    #phantom_field
+                }
+
+                impl #deserialize_impl_generics salsa::serde::Deserialize<'de> for #id_ty #deserialize_where_clause {
+                    fn deserialize<__D>(__deserializer: __D) -> Result<Self, __D::Error>
+                    where
+                        __D: salsa::serde::Deserializer<'de>,
+                    {
+                        let __fields = <__Fields #ty_generics as salsa::serde::Deserialize>::deserialize(__deserializer)?;
+                        salsa::attach::with_attached_jar_db::<#jar_ty, _>(|__db| #construct)
+                            .ok_or_else(|| __D::Error::custom(#no_db_message))
+                    }
+                }
+            };
+        })
+    }
+
+    /// Disallow generic parameters on this struct.
+    ///
+    /// # Parameters
+    ///
+    /// * `kind`, the kind of struct in the error message (e.g., `inputs`)
+    pub(crate) fn disallow_generics(&self, kind: &str) -> syn::Result<()> {
+        if self.is_generic() {
+            return Err(syn::Error::new_spanned(
+                &self.struct_item.generics,
+                format!("{kind} cannot have generic parameters"),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Disallow `#[id]` attributes on the fields of this struct.
     ///
     /// If an `#[id]` field is found, return an error.
@@ -373,12 +688,61 @@ impl<A: AllowedOptions> SalsaStruct<A> {
 
         Ok(())
     }
+
+    /// Disallow `#[lazy]` attributes on the fields of this struct;
+    /// only the fields of inputs and tracked structs can be computed on demand.
+    ///
+    /// If a `#[lazy]` field is found, return an error.
+    ///
+    /// # Parameters
+    ///
+    /// * `kind`, the attribute name (e.g., `tracked` or `interned`)
+    pub(crate) fn disallow_lazy_fields(&self, kind: &str) -> syn::Result<()> {
+        for ef in self.all_fields() {
+            if ef.lazy_fn.is_some() {
+                return Err(syn::Error::new(
+                    ef.name().span(),
+                    format!("`#[lazy]` cannot be used with `#[salsa::{kind}]`"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disallow `#[default]` attributes on the fields of this struct;
+    /// only the fields of inputs can be left out of the constructor.
+    ///
+    /// If a `#[default]` field is found, return an error.
+    ///
+    /// # Parameters
+    ///
+    /// * `kind`, the attribute name (e.g., `tracked` or `interned`)
+    pub(crate) fn disallow_default_fields(&self, kind: &str) -> syn::Result<()> {
+        for ef in self.all_fields() {
+            if ef.has_default_attr {
+                return Err(syn::Error::new(
+                    ef.name().span(),
+                    format!("`#[default]` cannot be used with `#[salsa::{kind}]`"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[allow(clippy::type_complexity)]
 pub(crate) const FIELD_OPTION_ATTRIBUTES: &[(&str, fn(&syn::Attribute, &mut SalsaField))] = &[
     ("id", |_, ef| ef.has_id_attr = true),
-    ("return_ref", |_, ef| ef.has_ref_attr = true),
+    ("return_ref", |attr, ef| {
+        ef.has_ref_attr = true;
+        if !attr.tokens.is_empty() {
+            let arg: syn::Ident = attr.parse_args().unwrap();
+            assert!(arg == "deref", "expected `#[return_ref(deref)]`");
+            ef.has_deref_attr = true;
+        }
+    }),
     ("no_eq", |_, ef| ef.has_no_eq_attr = true),
     ("get", |attr, ef| {
         ef.get_name = attr.parse_args().unwrap();
@@ -386,6 +750,10 @@ pub(crate) const FIELD_OPTION_ATTRIBUTES: &[(&str, fn(&syn::Attribute, &mut Sals
     ("set", |attr, ef| {
         ef.set_name = attr.parse_args().unwrap();
     }),
+    ("lazy", |attr, ef| {
+        ef.lazy_fn = Some(attr.parse_args().unwrap());
+    }),
+    ("default", |_, ef| ef.has_default_attr = true),
 ];
 
 pub(crate) struct SalsaField {
@@ -393,9 +761,22 @@ pub(crate) struct SalsaField {
 
     pub(crate) has_id_attr: bool,
     pub(crate) has_ref_attr: bool,
+
+    /// Set by `#[return_ref(deref)]`: the getter returns a reference to the target
+    /// of the field value (e.g., a `&str` for a `String`), rather than to the value.
+    pub(crate) has_deref_attr: bool,
     pub(crate) has_no_eq_attr: bool,
     get_name: syn::Ident,
     set_name: syn::Ident,
+
+    /// The function given by `#[lazy(<path>)]`, which loads the value of this
+    /// input field, or computes the value of this tracked struct field, the
+    /// first time it is read.
+    pub(crate) lazy_fn: Option<syn::Path>,
+
+    /// Set by `#[default]` on an input field, which the constructor then takes
+    /// from `Default::default()` rather than as an argument.
+    pub(crate) has_default_attr: bool,
 }
 
 impl SalsaField {
@@ -405,7 +786,7 @@ impl SalsaField {
         if BANNED_FIELD_NAMES.iter().any(|n| *n == field_name_str) {
             return Err(syn::Error::new(
                 field_name.span(),
-                &format!(
+                format!(
                     "the field name `{}` is disallowed in salsa structs",
                     field_name_str
                 ),
@@ -418,9 +799,12 @@ impl SalsaField {
             field: field.clone(),
             has_id_attr: false,
             has_ref_attr: false,
+            has_deref_attr: false,
             has_no_eq_attr: false,
             get_name,
             set_name,
+            lazy_fn: None,
+            has_default_attr: false,
         };
 
         // Scan the attributes and look for the salsa attributes:
@@ -465,6 +849,27 @@ impl SalsaField {
         !self.has_ref_attr
     }
 
+    /// The type returned by this field's get method, or a reference to which
+    /// is returned for `#[return_ref]` fields.
+    pub(crate) fn returned_ty(&self) -> syn::Type {
+        let ty = self.ty();
+        if self.has_deref_attr {
+            parse_quote!(<#ty as ::std::ops::Deref>::Target)
+        } else {
+            ty.clone()
+        }
+    }
+
+    /// Turns `reference`, to the value of this `#[return_ref]` field, into the
+    /// reference returned by its get method.
+    pub(crate) fn returned_ref(&self, reference: TokenStream) -> TokenStream {
+        if self.has_deref_attr {
+            quote!(::std::ops::Deref::deref(#reference))
+        } else {
+            reference
+        }
+    }
+
     /// Do you potentially backdate the value of this field? (True if it is not a no-eq field)
     pub(crate) fn is_backdate_field(&self) -> bool {
         !self.has_no_eq_attr
@@ -7,11 +7,12 @@ pub(crate) fn tracked(
     let item = syn::parse_macro_input!(input as Item);
     let res = match item {
         syn::Item::Struct(item) => crate::tracked_struct::tracked(args, item),
+        syn::Item::Enum(item) => crate::tracked_enum::tracked(args, item),
         syn::Item::Fn(item) => crate::tracked_fn::tracked_fn(args, item),
         syn::Item::Impl(item) => crate::tracked_fn::tracked_impl(args, item),
         _ => Err(syn::Error::new(
             item.span(),
-            "tracked can only be applied to structs, functions, and impls",
+            "tracked can only be applied to structs, enums, functions, and impls",
         )),
     };
     match res {
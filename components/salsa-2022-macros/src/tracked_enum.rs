@@ -0,0 +1,229 @@
+use proc_macro2::{Literal, TokenStream};
+use syn::spanned::Spanned;
+
+// #[salsa::tracked(jar = Jar0)]
+// enum Item {
+//     Function(Function),
+//     Const(Const),
+// }
+
+pub(crate) fn tracked(
+    args: proc_macro::TokenStream,
+    item_enum: syn::ItemEnum,
+) -> syn::Result<TokenStream> {
+    let args: Args = syn::parse(args)?;
+    TrackedEnum { args, item_enum }.generate()
+}
+
+type Args = crate::options::Options<TrackedEnum>;
+
+struct TrackedEnum {
+    args: Args,
+    item_enum: syn::ItemEnum,
+}
+
+impl crate::options::AllowedOptions for TrackedEnum {
+    const RETURN_REF: bool = false;
+
+    const SPECIFY: bool = false;
+
+    const NO_EQ: bool = false;
+
+    const EQ: bool = false;
+
+    const SINGLETON: bool = false;
+
+    const JAR: bool = true;
+
+    const DATA: bool = false;
+
+    const DB: bool = false;
+
+    const RECOVERY_FN: bool = false;
+
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
+    const LRU: bool = false;
+
+    const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
+}
+
+impl TrackedEnum {
+    fn generate(&self) -> syn::Result<TokenStream> {
+        let variant_tys = self.variant_tys()?;
+        if !self.item_enum.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                self.item_enum.generics.span(),
+                "tracked enums cannot have generic parameters",
+            ));
+        }
+
+        let enum_item = self.enum_item();
+        let as_id_impl = self.as_id_impl(&variant_tys);
+        let salsa_struct_in_db_impl = self.salsa_struct_in_db_impl();
+        let variant_from_impls = self.variant_from_impls(&variant_tys);
+        let debug_with_db_impl = crate::debug_with_db::debug_with_db_for_jar(
+            &parse_quote! { #enum_item },
+            &self.args.jar_ty(),
+        )?;
+
+        Ok(quote! {
+            #enum_item
+            #as_id_impl
+            #salsa_struct_in_db_impl
+            #(#variant_from_impls)*
+            #debug_with_db_impl
+        })
+    }
+
+    /// Returns the type of the salsa struct in each variant, checking that
+    /// every variant has exactly one (unnamed) field.
+    fn variant_tys(&self) -> syn::Result<Vec<&syn::Type>> {
+        if self.item_enum.variants.is_empty() {
+            return Err(syn::Error::new(
+                self.item_enum.ident.span(),
+                "tracked enums must have at least one variant",
+            ));
+        }
+        self.item_enum
+            .variants
+            .iter()
+            .map(|variant| match &variant.fields {
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    Ok(&fields.unnamed[0].ty)
+                }
+                _ => Err(syn::Error::new(
+                    variant.span(),
+                    "the variants of tracked enums must have a single unnamed field, a salsa struct",
+                )),
+            })
+            .collect()
+    }
+
+    /// The enum as written by the user, with our own derives instead of theirs.
+    fn enum_item(&self) -> syn::ItemEnum {
+        let mut enum_item = self.item_enum.clone();
+        let attrs: Vec<_> = std::mem::take(&mut enum_item.attrs)
+            .into_iter()
+            .filter(|attr| !attr.path.is_ident("derive"))
+            .collect();
+        parse_quote! {
+            #(#attrs)*
+            #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
+            #enum_item
+        }
+    }
+
+    /// Generate `impl salsa::AsId for Foo`. The id of the salsa struct in the
+    /// variant is shifted left to make room for the index of the variant in
+    /// the low bits.
+    fn as_id_impl(&self, variant_tys: &[&syn::Type]) -> syn::ItemImpl {
+        let ident = &self.item_enum.ident;
+        let variant_idents: Vec<_> = self.item_enum.variants.iter().map(|v| &v.ident).collect();
+        let tags: Vec<_> = (0..variant_tys.len() as u32)
+            .map(Literal::u32_unsuffixed)
+            .collect();
+        let tag_bits =
+            Literal::u32_unsuffixed(u32::BITS - (variant_tys.len() as u32 - 1).leading_zeros());
+        let error = format!("id of the salsa struct too large to be stored in `{ident}`");
+        parse_quote! {
+            impl salsa::AsId for #ident {
+                fn as_id(self) -> salsa::Id {
+                    const TAG_BITS: u32 = #tag_bits;
+                    let (tag, id): (u32, salsa::Id) = match self {
+                        #(Self::#variant_idents(v) => (#tags, salsa::AsId::as_id(v)),)*
+                    };
+                    let id = id.as_u32();
+                    assert!(id < salsa::Id::MAX_U32 >> TAG_BITS, #error);
+                    salsa::Id::from_u32(id << TAG_BITS | tag)
+                }
+
+                fn from_id(id: salsa::Id) -> Self {
+                    const TAG_BITS: u32 = #tag_bits;
+                    let id = id.as_u32();
+                    let inner = salsa::Id::from_u32(id >> TAG_BITS);
+                    match id & ((1 << TAG_BITS) - 1) {
+                        #(#tags => Self::#variant_idents(salsa::AsId::from_id(inner)),)*
+                        tag => panic!("invalid variant index {tag}"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Implementation of `SalsaStructInDb`, so that tracked enums can be the
+    /// argument of tracked functions.
+    ///
+    /// Their ids differ from the ids of the salsa structs in their variants, so
+    /// they do not register as dependents of those: when such a salsa struct is
+    /// deleted, the memos keyed by it are left in place.
+    fn salsa_struct_in_db_impl(&self) -> syn::ItemImpl {
+        let ident = &self.item_enum.ident;
+        parse_quote! {
+            impl<DB> salsa::salsa_struct::SalsaStructInDb<DB> for #ident
+            where
+                DB: ?Sized + salsa::Database,
+            {
+                fn register_dependent_fn(_db: &DB, _index: salsa::routes::IngredientIndex) {}
+            }
+        }
+    }
+
+    /// Generate `impl From<Bar> for Foo` for each variant `Bar(Bar)`, unless
+    /// several variants hold the same type.
+    fn variant_from_impls(&self, variant_tys: &[&syn::Type]) -> Vec<syn::ItemImpl> {
+        let ident = &self.item_enum.ident;
+        let ty_strings: Vec<_> = variant_tys
+            .iter()
+            .map(|ty| quote!(#ty).to_string())
+            .collect();
+        self.item_enum
+            .variants
+            .iter()
+            .zip(variant_tys)
+            .zip(&ty_strings)
+            .filter(|(_, ty_string)| ty_strings.iter().filter(|s| s == ty_string).count() == 1)
+            .map(|((variant, ty), _)| {
+                let variant_ident = &variant.ident;
+                parse_quote! {
+                    impl From<#ty> for #ident {
+                        fn from(value: #ty) -> Self {
+                            Self::#variant_ident(value)
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
@@ -25,11 +25,22 @@ pub(crate) fn tracked_fn(
         ));
     }
 
+    if let Some(i) = &args.intern_args {
+        if item_fn.sig.inputs.len() != 2 {
+            return Err(syn::Error::new(
+                i.span(),
+                "`intern_args` only applies to tracked functions taking a single argument",
+            ));
+        }
+    }
+
     if let Some(s) = &args.specify {
-        if function_type(&item_fn) == FunctionType::RequiresInterning {
+        if item_fn.sig.inputs.len() == 2
+            && function_type(&args, &item_fn) == FunctionType::RequiresInterning
+        {
             return Err(syn::Error::new(
                 s.span(),
-                "tracked function takes too many arguments to have its value set with `specify`",
+                "tracked function must take a salsa struct to have its value set with `specify`",
             ));
         }
 
@@ -39,8 +50,15 @@ pub(crate) fn tracked_fn(
                 "`specify` and `lru` cannot be used together",
             ));
         }
+        if args.ttl.is_some() {
+            return Err(syn::Error::new(
+                s.span(),
+                "`specify` and `ttl` cannot be used together",
+            ));
+        }
     }
 
+    add_jar_bound(&args, &mut item_fn.sig);
     let (config_ty, fn_struct) = fn_struct(&args, &item_fn)?;
     *item_fn.block = getter_fn(&args, &mut item_fn.sig, item_fn.block.span(), &config_ty)?;
 
@@ -65,6 +83,8 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const NO_EQ: bool = true;
 
+    const EQ: bool = true;
+
     const SINGLETON: bool = false;
 
     const JAR: bool = true;
@@ -75,13 +95,49 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const RECOVERY_FN: bool = true;
 
+    const CYCLE_FN: bool = true;
+
+    const CYCLE_INITIAL: bool = true;
+
+    const CYCLE_MAX_ITERATIONS: bool = true;
+
     const LRU: bool = true;
 
     const CONSTRUCTOR_NAME: bool = false;
+
+    const PERSIST: bool = true;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = true;
+
+    const HEAP_SIZE: bool = true;
+    const TTL: bool = true;
+    const SHARDS: bool = true;
+    const HISTORY: bool = true;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = true;
+    const RETRY_ERRORS: bool = true;
+    const CACHE: bool = true;
+    const UPDATE: bool = true;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = true;
+    const DIFF: bool = true;
+    const PROJECT: bool = true;
+    const VERSION: bool = true;
+    const PHASE: bool = true;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = true;
 }
 
 type ImplArgs = Options<TrackedImpl>;
 
+// The `fold` below deliberately keeps going after an error so that all
+// errors get combined, which `try_fold` would not do.
+#[allow(clippy::manual_try_fold)]
 pub(crate) fn tracked_impl(
     args: proc_macro::TokenStream,
     mut item_impl: syn::ItemImpl,
@@ -96,15 +152,7 @@ pub(crate) fn tracked_impl(
             ))
         }
     };
-    let self_type_name = &self_type.path.segments.last().unwrap().ident;
-    let name_prefix = match &item_impl.trait_ {
-        Some((_, trait_name, _)) => format!(
-            "{}_{}",
-            self_type_name,
-            trait_name.segments.last().unwrap().ident
-        ),
-        None => format!("{}", self_type_name),
-    };
+    let name_prefix = tracked_method_name_prefix(self_type, &item_impl);
     let extra_impls = item_impl
         .items
         .iter_mut()
@@ -113,14 +161,10 @@ pub(crate) fn tracked_impl(
                 syn::ImplItem::Method(item_method) => item_method,
                 _ => return None,
             };
-            let salsa_tracked_attr = item_method.attrs.iter().position(|attr| {
-                let path = &attr.path.segments;
-                path.len() == 2
-                    && path[0].arguments == syn::PathArguments::None
-                    && path[0].ident == "salsa"
-                    && path[1].arguments == syn::PathArguments::None
-                    && path[1].ident == "tracked"
-            })?;
+            let salsa_tracked_attr = item_method
+                .attrs
+                .iter()
+                .position(|attr| crate::is_salsa_attr(attr, "tracked"))?;
             let salsa_tracked_attr = item_method.attrs.remove(salsa_tracked_attr);
             let inner_args = if !salsa_tracked_attr.tokens.is_empty() {
                 salsa_tracked_attr.parse_args()
@@ -158,6 +202,23 @@ pub(crate) fn tracked_impl(
     })
 }
 
+/// The tracked methods of an impl of `self_type` are named (and listed in the jar)
+/// as `SelfType_method`, or `SelfType_Trait_method` in a trait impl.
+pub(crate) fn tracked_method_name_prefix(
+    self_type: &syn::TypePath,
+    item_impl: &syn::ItemImpl,
+) -> String {
+    let self_type_name = &self_type.path.segments.last().unwrap().ident;
+    match &item_impl.trait_ {
+        Some((_, trait_name, _)) => format!(
+            "{}_{}",
+            self_type_name,
+            trait_name.segments.last().unwrap().ident
+        ),
+        None => format!("{}", self_type_name),
+    }
+}
+
 struct TrackedImpl;
 
 impl crate::options::AllowedOptions for TrackedImpl {
@@ -167,6 +228,8 @@ impl crate::options::AllowedOptions for TrackedImpl {
 
     const NO_EQ: bool = false;
 
+    const EQ: bool = false;
+
     const JAR: bool = true;
 
     const DATA: bool = false;
@@ -175,10 +238,42 @@ impl crate::options::AllowedOptions for TrackedImpl {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = false;
 
+    const PERSIST: bool = false;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = false;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = false;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
+
     const SINGLETON: bool = false;
 }
 
@@ -232,6 +327,8 @@ fn tracked_method(
     inputs.extend(original_inputs);
     item_fn.sig.inputs = inputs;
 
+    add_jar_bound(&args, &mut item_fn.sig);
+    add_jar_bound_for(&args, &item_fn.sig, &mut item_method.sig);
     let (config_ty, fn_struct) = crate::tracked_fn::fn_struct(&args, &item_fn)?;
 
     item_method.block = getter_fn(
@@ -265,6 +362,36 @@ fn rename_self_in_block(mut block: syn::Block) -> syn::Result<syn::Block> {
                 *i = syn::Ident::new("__salsa_self", i.span());
             }
         }
+
+        // The tokens of macro invocations (e.g., `format!("{}", self.name(db))`)
+        // are not visited as idents, so rename within them by hand.
+        fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+            syn::visit_mut::visit_macro_mut(self, mac);
+            mac.tokens = self.rename_tokens(std::mem::take(&mut mac.tokens));
+        }
+    }
+
+    impl RenameIdent {
+        fn rename_tokens(&mut self, tokens: TokenStream) -> TokenStream {
+            tokens
+                .into_iter()
+                .map(|token| match token {
+                    proc_macro2::TokenTree::Ident(mut i) => {
+                        syn::visit_mut::VisitMut::visit_ident_mut(self, &mut i);
+                        proc_macro2::TokenTree::Ident(i)
+                    }
+                    proc_macro2::TokenTree::Group(group) => {
+                        let mut renamed = proc_macro2::Group::new(
+                            group.delimiter(),
+                            self.rename_tokens(group.stream()),
+                        );
+                        renamed.set_span(group.span());
+                        proc_macro2::TokenTree::Group(renamed)
+                    }
+                    token => token,
+                })
+                .collect()
+        }
     }
 
     let mut rename = RenameIdent(Ok(()));
@@ -272,17 +399,129 @@ fn rename_self_in_block(mut block: syn::Block) -> syn::Result<syn::Block> {
     rename.0.map(move |()| block)
 }
 
+/// `cycle_fn` and `cycle_initial` must be given together, cannot be combined
+/// with `recovery_fn`, and are needed by `cycle_max_iterations`.
+fn check_cycle_options(args: &FnArgs) -> syn::Result<()> {
+    match (&args.cycle_fn, &args.cycle_initial) {
+        (Some(cycle_fn), None) => Err(syn::Error::new(
+            cycle_fn.span(),
+            "`cycle_fn` requires `cycle_initial` to also be given",
+        )),
+        (None, Some(cycle_initial)) => Err(syn::Error::new(
+            cycle_initial.span(),
+            "`cycle_initial` requires `cycle_fn` to also be given",
+        )),
+        (Some(cycle_fn), Some(_)) if args.recovery_fn.is_some() => Err(syn::Error::new(
+            cycle_fn.span(),
+            "`recovery_fn` and `cycle_fn` cannot be used together",
+        )),
+        (None, None) if args.cycle_max_iterations.is_some() => Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`cycle_max_iterations` requires `cycle_fn` and `cycle_initial` to also be given",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `eq` and `no_eq` are contradictory.
+fn check_boxed_options(args: &FnArgs) -> syn::Result<()> {
+    match (&args.boxed, &args.share_values) {
+        (Some(boxed), Some(_)) => Err(syn::Error::new(
+            boxed.span(),
+            "`boxed` and `share_values` cannot be used together",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The fields of `project` each get an associated function of the configuration
+/// struct, named after them, so they cannot take the names of the others.
+fn check_project_options(args: &FnArgs, item_fn: &syn::ItemFn) -> syn::Result<()> {
+    let fields = match &args.project {
+        Some(fields) => fields,
+        None => return Ok(()),
+    };
+    if !struct_generics(item_fn).params.is_empty() {
+        return Err(syn::Error::new(
+            item_fn.sig.generics.span(),
+            "`project` cannot be used with generic tracked functions",
+        ));
+    }
+    const RESERVED: &[&str] = &[
+        "accumulated",
+        "accumulated_with_provenance",
+        "database_key",
+        "entries",
+        "get",
+        "memo_info",
+        "on_change",
+        "pin",
+        "purge",
+        "purge_key",
+        "set",
+        "set_lru_capacity",
+        "specify",
+        "unpin",
+        "value_as_of",
+        "with_delta",
+    ];
+    for (field, _) in fields {
+        if RESERVED.iter().any(|name| field == name) {
+            return Err(syn::Error::new(
+                field.span(),
+                format!(
+                    "`{field}` cannot be projected, as it names an associated function of `{}`",
+                    item_fn.sig.ident
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_eq_options(args: &FnArgs) -> syn::Result<()> {
+    match (&args.eq, &args.no_eq) {
+        (Some(eq), Some(_)) => Err(syn::Error::new(
+            eq.span(),
+            "`eq` and `no_eq` cannot be used together",
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Create the struct representing the function and all of its impls.
 ///
 /// This returns the name of the constructed type and the code defining everything.
 fn fn_struct(args: &FnArgs, item_fn: &syn::ItemFn) -> syn::Result<(syn::Type, TokenStream)> {
-    let struct_item = configuration_struct(item_fn);
+    check_cycle_options(args)?;
+    check_eq_options(args)?;
+    check_boxed_options(args)?;
+    check_project_options(args, item_fn)?;
+
+    // The memoized value has to be available as soon as the function returns,
+    // and the active query stack cannot be suspended across an `.await`.
+    if let Some(asyncness) = &item_fn.sig.asyncness {
+        return Err(syn::Error::new(
+            asyncness.span(),
+            "tracked functions cannot be `async`",
+        ));
+    }
+
+    let generics = struct_generics(item_fn);
+    let struct_item = configuration_struct(args, item_fn, &generics);
     let configuration = fn_configuration(args, item_fn);
     let struct_item_ident = &struct_item.ident;
-    let config_ty: syn::Type = parse_quote!(#struct_item_ident);
-    let configuration_impl = configuration.to_impl(&config_ty);
-    let ingredients_for_impl = ingredients_for_impl(args, item_fn, &config_ty);
-    let item_impl = setter_impl(args, item_fn, &config_ty)?;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let config_ty: syn::Type = parse_quote!(#struct_item_ident #ty_generics);
+    let configuration_impl = configuration.to_impl(&config_ty, &generics);
+    let ingredients_for_impl = ingredients_for_impl(args, item_fn, &config_ty, &generics);
+    let projections = projection_configurations(args, item_fn, &config_ty);
+    let item_impl = setter_impl(
+        args,
+        &without_struct_generics(item_fn),
+        &config_ty,
+        &generics,
+    )?;
 
     Ok((
         config_ty,
@@ -291,10 +530,102 @@ fn fn_struct(args: &FnArgs, item_fn: &syn::ItemFn) -> syn::Result<(syn::Type, To
             #configuration_impl
             #ingredients_for_impl
             #item_impl
+            #(#projections)*
         },
     ))
 }
 
+/// Returns the generics of the configuration struct of this tracked function: its
+/// type and const parameters, with its where clause (see [`add_jar_bound`]). Each instantiation of a generic
+/// tracked function has its own ingredients, so it must be listed in the jar on its
+/// own (e.g., `Jar(lower<Ast>, lower<Mir>)`).
+fn struct_generics(item_fn: &syn::ItemFn) -> syn::Generics {
+    let mut generics = item_fn.sig.generics.clone();
+    generics.params = std::mem::take(&mut generics.params)
+        .into_iter()
+        .filter(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+        .collect();
+    generics
+}
+
+/// Requires, for a generic tracked function, that its jar holds the ingredients
+/// of the instantiation being invoked: `Jar: HasIngredientsFor<lower<T>>`. Since
+/// generic code calling the function must prove this as well, the bound is added
+/// to the signature of the function itself, along with `T: 'static` for its type
+/// parameters, as ingredients are stored in the database.
+fn add_jar_bound(args: &FnArgs, sig: &mut syn::Signature) {
+    let fn_sig = sig.clone();
+    add_jar_bound_for(args, &fn_sig, sig);
+}
+
+/// Adds the bound of [`add_jar_bound`] for the tracked function `fn_sig` to `sig`.
+fn add_jar_bound_for(args: &FnArgs, fn_sig: &syn::Signature, sig: &mut syn::Signature) {
+    let generic_args: Vec<_> = fn_sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(param) => Some(&param.ident),
+            syn::GenericParam::Const(param) => Some(&param.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    if generic_args.is_empty() {
+        return;
+    }
+    let jar_ty = args.jar_ty();
+    let fn_name = &fn_sig.ident;
+    // Put `'static` next to the user's bounds on each type parameter, which may be
+    // in the where clause.
+    let mut where_clause = sig.generics.where_clause.take();
+    for ty_param in sig.generics.type_params_mut() {
+        let ident = &ty_param.ident;
+        let where_predicate = where_clause
+            .iter_mut()
+            .flat_map(|where_clause| &mut where_clause.predicates)
+            .find_map(|predicate| match predicate {
+                syn::WherePredicate::Type(predicate) if bounds_ty_param(predicate, ident) => {
+                    Some(predicate)
+                }
+                _ => None,
+            });
+        match where_predicate {
+            Some(predicate) => predicate.bounds.push(parse_quote!('static)),
+            None => ty_param.bounds.push(parse_quote!('static)),
+        }
+    }
+    sig.generics.where_clause = where_clause;
+    sig.generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! {
+            #jar_ty: salsa::storage::HasIngredientsFor<#fn_name<#(#generic_args),*>>
+        });
+}
+
+/// Whether `predicate` is of the form `T: ...` for the type parameter `ident`.
+fn bounds_ty_param(predicate: &syn::PredicateType, ident: &syn::Ident) -> bool {
+    predicate.lifetimes.is_none()
+        && matches!(
+            &predicate.bounded_ty,
+            syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident(ident)
+        )
+}
+
+/// Returns a copy of `item_fn` keeping only its lifetime parameters, as the others
+/// are parameters of the configuration struct. Used for the associated functions
+/// generated on that struct.
+fn without_struct_generics(item_fn: &syn::ItemFn) -> syn::ItemFn {
+    let mut item_fn = item_fn.clone();
+    let params = std::mem::take(&mut item_fn.sig.generics.params);
+    item_fn.sig.generics.params = params
+        .into_iter()
+        .filter(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+        .collect();
+    item_fn.sig.generics.where_clause = None;
+    item_fn
+}
+
 /// Returns the key type for this tracked function.
 /// This is a tuple of all the argument types (apart from the database).
 fn key_tuple_ty(item_fn: &syn::ItemFn) -> syn::Type {
@@ -308,12 +639,17 @@ fn key_tuple_ty(item_fn: &syn::ItemFn) -> syn::Type {
     )
 }
 
-fn configuration_struct(item_fn: &syn::ItemFn) -> syn::ItemStruct {
+fn configuration_struct(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    generics: &syn::Generics,
+) -> syn::ItemStruct {
     let fn_name = item_fn.sig.ident.clone();
     let visibility = &item_fn.vis;
+    let where_clause = &generics.where_clause;
 
-    let salsa_struct_ty = salsa_struct_ty(item_fn);
-    let intern_map: syn::Type = match function_type(item_fn) {
+    let salsa_struct_ty = salsa_struct_ty(args, item_fn);
+    let intern_map: syn::Type = match function_type(args, item_fn) {
         FunctionType::Constant => {
             parse_quote! { salsa::interned::IdentityInterner<()> }
         }
@@ -326,15 +662,108 @@ fn configuration_struct(item_fn: &syn::ItemFn) -> syn::ItemStruct {
         }
     };
 
+    let phantom = phantom_field_ty(generics).map(|ty| quote! { phantom: #ty, });
+    let projections = projections(args, item_fn).map(|(_, field, projection_ty)| {
+        quote! { #field: salsa::function::FunctionIngredient<#projection_ty>, }
+    });
+
     parse_quote! {
         #[allow(non_camel_case_types)]
-        #visibility struct #fn_name {
+        #visibility struct #fn_name #generics #where_clause {
             intern_map: #intern_map,
             function: salsa::function::FunctionIngredient<Self>,
+            #(#projections)*
+            #phantom
         }
     }
 }
 
+/// Returns, for each field listed in the `project` option, its name, the name
+/// of the field holding its ingredient in the configuration struct, and the
+/// configuration struct of its projection query (e.g., `__signature_of_return_type`).
+fn projections<'a>(
+    args: &'a FnArgs,
+    item_fn: &syn::ItemFn,
+) -> impl Iterator<Item = (&'a syn::Ident, syn::Ident, syn::Ident)> {
+    let fn_name = item_fn.sig.ident.clone();
+    args.project.iter().flatten().map(move |(field, _)| {
+        (
+            field,
+            syn::Ident::new(&format!("project_{field}"), field.span()),
+            syn::Ident::new(&format!("__{fn_name}_{field}"), field.span()),
+        )
+    })
+}
+
+/// Creates the configuration struct of each projection query of the `project`
+/// option, with its `Configuration` impl. A projection query has the key of the
+/// tracked function, and its value is a clone of one field of the value of the
+/// function: since it backdates that clone, its dependents are only invalidated
+/// when this field changes, whichever other field of the value does.
+fn projection_configurations(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> Vec<TokenStream> {
+    let jar_ty = args.jar_ty();
+    let visibility = &item_fn.vis;
+    let (cycle_initial_fn, cycle_iterate_fn) = configuration::panic_cycle_iterate_fns();
+    projections(args, item_fn)
+        .zip(args.project.iter().flatten())
+        .map(|((field, _, projection_ty), (_, field_ty))| {
+            let configuration = Configuration {
+                jar_ty: jar_ty.clone(),
+                salsa_struct_ty: salsa_struct_ty(args, item_fn),
+                key_ty: key_ty(args, item_fn),
+                value_ty: field_ty.clone(),
+                cycle_strategy: CycleRecoveryStrategy::Panic,
+                backdate_fn: configuration::should_backdate_value_fn(true),
+                execute_fn: parse_quote! {
+                    fn execute(__db: &salsa::function::DynDb<Self>, __id: Self::Key) -> Self::Value {
+                        let (__jar, _) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                        let __ingredients =
+                            <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+                        Clone::clone(&__ingredients.function.fetch(__db, __id).#field)
+                    }
+                },
+                recover_fn: configuration::panic_cycle_recovery_fn(),
+                cycle_initial_fn: cycle_initial_fn.clone(),
+                cycle_iterate_fn: cycle_iterate_fn.clone(),
+                cycle_max_iterations_const: None,
+                heap_size_fn: None,
+                share_value_fns: vec![],
+                cache_items: vec![],
+                update_items: vec![],
+                memo_map_shards_const: None,
+                phase_const: None,
+                query_fingerprint_const: configuration::query_fingerprint_const(&format!(
+                    "{}::{field}: {}",
+                    query_fingerprint_text(args, item_fn),
+                    quote!(#field_ty),
+                )),
+            };
+            let projection_ty: syn::Type = parse_quote!(#projection_ty);
+            let configuration_impl = configuration.to_impl(&projection_ty, &syn::Generics::default());
+            quote! {
+                #[allow(non_camel_case_types)]
+                #visibility struct #projection_ty;
+
+                #configuration_impl
+            }
+        })
+        .collect()
+}
+
+/// The type of the field marking the type parameters of a generic tracked
+/// function as used by its configuration struct, if it has any.
+fn phantom_field_ty(generics: &syn::Generics) -> Option<syn::Type> {
+    let type_params: Vec<_> = generics.type_params().map(|param| &param.ident).collect();
+    if type_params.is_empty() {
+        return None;
+    }
+    Some(parse_quote! { std::marker::PhantomData<fn() -> (#(#type_params,)*)> })
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 enum FunctionType {
     Constant,
@@ -342,21 +771,35 @@ enum FunctionType {
     RequiresInterning,
 }
 
-fn function_type(item_fn: &syn::ItemFn) -> FunctionType {
+fn function_type(args: &FnArgs, item_fn: &syn::ItemFn) -> FunctionType {
     match item_fn.sig.inputs.len() {
         0 => unreachable!(
             "functions have been checked to have at least a database argument by this point"
         ),
         1 => FunctionType::Constant,
+        2 if args.intern_args.is_some() => FunctionType::RequiresInterning,
         2 => FunctionType::SalsaStruct,
         _ => FunctionType::RequiresInterning,
     }
 }
 
+/// The expression for the key of the function, given the names of its arguments,
+/// that is passed to the `intern_map`.
+fn key_expr(args: &FnArgs, item_fn: &syn::ItemFn, arg_names: &[syn::Ident]) -> TokenStream {
+    match function_type(args, item_fn) {
+        FunctionType::Constant | FunctionType::SalsaStruct => quote! { (#(#arg_names),*) },
+        FunctionType::RequiresInterning => quote! { (#(#arg_names,)*) },
+    }
+}
+
 /// Every tracked fn takes a salsa struct as its second argument.
 /// This fn returns the type of that second argument.
-fn salsa_struct_ty(item_fn: &syn::ItemFn) -> syn::Type {
-    if item_fn.sig.inputs.len() == 1 {
+///
+/// Functions with more arguments are keyed by the interned tuple of them, not by
+/// the id of their first argument, so deleting an instance of that salsa struct
+/// must not delete the memo with the same id; they use `Singleton` instead.
+fn salsa_struct_ty(args: &FnArgs, item_fn: &syn::ItemFn) -> syn::Type {
+    if function_type(args, item_fn) != FunctionType::SalsaStruct {
         return parse_quote! { salsa::salsa_struct::Singleton };
     }
     match &item_fn.sig.inputs[1] {
@@ -365,31 +808,43 @@ fn salsa_struct_ty(item_fn: &syn::ItemFn) -> syn::Type {
     }
 }
 
-fn fn_configuration(args: &FnArgs, item_fn: &syn::ItemFn) -> Configuration {
-    let jar_ty = args.jar_ty();
-    let salsa_struct_ty = salsa_struct_ty(item_fn);
-    let key_ty = match function_type(item_fn) {
+/// The key of the ingredient of this tracked function: the interned id of its
+/// arguments, unless it takes a salsa struct or nothing besides the database.
+fn key_ty(args: &FnArgs, item_fn: &syn::ItemFn) -> syn::Type {
+    match function_type(args, item_fn) {
         FunctionType::Constant => parse_quote!(()),
-        FunctionType::SalsaStruct => salsa_struct_ty.clone(),
+        FunctionType::SalsaStruct => salsa_struct_ty(args, item_fn),
         FunctionType::RequiresInterning => parse_quote!(salsa::id::Id),
-    };
-    let value_ty = configuration::value_ty(&item_fn.sig);
+    }
+}
 
-    let fn_ty = item_fn.sig.ident.clone();
+fn fn_configuration(args: &FnArgs, item_fn: &syn::ItemFn) -> Configuration {
+    let jar_ty = args.jar_ty();
+    let salsa_struct_ty = salsa_struct_ty(args, item_fn);
+    let key_ty = key_ty(args, item_fn);
+    let fn_value_ty = configuration::value_ty(&item_fn.sig);
+    let value_ty = match &args.boxed {
+        Some(_) => parse_quote!(Box<#fn_value_ty>),
+        None => fn_value_ty.clone(),
+    };
 
     let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
-    let (cycle_strategy, recover_fn) = if let Some(recovery_fn) = &args.recovery_fn {
+    let (mut cycle_strategy, recover_fn) = if let Some(recovery_fn) = &args.recovery_fn {
         // Create the `recover_from_cycle` function, which (a) maps from the interned id to the actual
         // keys and then (b) invokes the recover function itself.
         let cycle_strategy = CycleRecoveryStrategy::Fallback;
+        let recover = box_value(
+            args,
+            quote! { #recovery_fn(__db, __cycle, #(__key.#indices),*) },
+        );
 
         let cycle_fullback = parse_quote! {
             fn recover_from_cycle(__db: &salsa::function::DynDb<Self>, __cycle: &salsa::Cycle, __id: Self::Key) -> Self::Value {
                 let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
                 let __ingredients =
-                    <_ as salsa::storage::HasIngredientsFor<#fn_ty>>::ingredient(__jar);
+                    <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(__jar);
                 let __key = __ingredients.intern_map.data(__runtime, __id).clone();
-                #recovery_fn(__db, __cycle, #(__key.#indices),*)
+                #recover
             }
         };
         (cycle_strategy, cycle_fullback)
@@ -400,7 +855,170 @@ fn fn_configuration(args: &FnArgs, item_fn: &syn::ItemFn) -> Configuration {
         (cycle_strategy, cycle_panic)
     };
 
-    let backdate_fn = configuration::should_backdate_value_fn(args.should_backdate());
+    let (cycle_initial_fn, cycle_iterate_fn) = match (&args.cycle_initial, &args.cycle_fn) {
+        (Some(cycle_initial), Some(cycle_fn)) => {
+            // Both functions, like `recover_from_cycle`, map from the interned id
+            // to the actual keys before invoking the user's function.
+            cycle_strategy = CycleRecoveryStrategy::FixedPoint;
+            let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
+            let initial = box_value(args, quote! { #cycle_initial(__db, #(__key.#indices),*) });
+            let cycle_initial_fn = parse_quote! {
+                fn cycle_initial(__db: &salsa::function::DynDb<Self>, __id: Self::Key) -> Self::Value {
+                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                    let __ingredients =
+                        <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(__jar);
+                    let __key = __ingredients.intern_map.data(__runtime, __id).clone();
+                    #initial
+                }
+            };
+            let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
+            let call_cycle_fn = quote! { #cycle_fn(__db, __value, __count, #(__key.#indices),*) };
+            let call_cycle_fn = match &args.boxed {
+                Some(_) => quote! {
+                    match #call_cycle_fn {
+                        salsa::CycleRecoveryAction::Iterate => salsa::CycleRecoveryAction::Iterate,
+                        salsa::CycleRecoveryAction::Fallback(__value) => {
+                            salsa::CycleRecoveryAction::Fallback(Box::new(__value))
+                        }
+                    }
+                },
+                None => call_cycle_fn,
+            };
+            let cycle_iterate_fn = parse_quote! {
+                fn cycle_iterate(
+                    __db: &salsa::function::DynDb<Self>,
+                    __value: &Self::Value,
+                    __count: u32,
+                    __id: Self::Key,
+                ) -> salsa::CycleRecoveryAction<Self::Value> {
+                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                    let __ingredients =
+                        <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(__jar);
+                    let __key = __ingredients.intern_map.data(__runtime, __id).clone();
+                    #call_cycle_fn
+                }
+            };
+            (cycle_initial_fn, cycle_iterate_fn)
+        }
+        _ => configuration::panic_cycle_iterate_fns(),
+    };
+
+    let backdate_fn = match &args.eq {
+        Some(eq) => parse_quote! {
+            fn should_backdate_value(v1: &Self::Value, v2: &Self::Value) -> bool {
+                #eq(v1, v2)
+            }
+        },
+        None => configuration::should_backdate_value_fn(args.should_backdate()),
+    };
+
+    // A boxed value is itself on the heap.
+    let boxed_size = args
+        .boxed
+        .as_ref()
+        .map(|_| quote! { std::mem::size_of::<#fn_value_ty>() + });
+    let heap_size_fn = match (&args.heap_size, &boxed_size) {
+        (Some(heap_size), _) => Some(parse_quote! {
+            fn heap_size(value: &Self::Value) -> usize {
+                #boxed_size #heap_size(value)
+            }
+        }),
+        (None, Some(_)) => Some(parse_quote! {
+            fn heap_size(_value: &Self::Value) -> usize {
+                #boxed_size 0
+            }
+        }),
+        (None, None) => None,
+    };
+
+    // Shared values are `Arc`s, which `SharedValues` only knows how to share.
+    let share_value_fns = match &args.share_values {
+        Some(_) => vec![
+            parse_quote! {
+                fn share_value(
+                    __shared: &salsa::function::SharedValues<Self::Value>,
+                    __value: Self::Value,
+                ) -> Self::Value {
+                    __shared.share(__value)
+                }
+            },
+            parse_quote! {
+                fn prune_shared_values(__shared: &mut salsa::function::SharedValues<Self::Value>) {
+                    __shared.prune()
+                }
+            },
+        ],
+        None => vec![],
+    };
+
+    // The fingerprint function takes the same arguments as the function itself; the
+    // values are encoded with serde, which they must implement.
+    let cache_items = match &args.cache {
+        Some(cache) => {
+            let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
+            vec![
+                parse_quote! {
+                    const CACHE: bool = true;
+                },
+                parse_quote! {
+                    fn cache_fingerprint(
+                        __db: &salsa::function::DynDb<Self>,
+                        __id: Self::Key,
+                    ) -> salsa::cache::Fingerprint {
+                        let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+                        let __ingredients =
+                            <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(__jar);
+                        let __key = __ingredients.intern_map.data(__runtime, __id).clone();
+                        #cache(__db, #(__key.#indices),*)
+                    }
+                },
+                parse_quote! {
+                    fn encode_value(__value: &Self::Value) -> Option<Vec<u8>> {
+                        salsa::cache::encode(__value)
+                    }
+                },
+                parse_quote! {
+                    fn decode_value(__bytes: &[u8]) -> Option<Self::Value> {
+                        salsa::cache::decode(__bytes)
+                    }
+                },
+            ]
+        }
+        None => vec![],
+    };
+
+    // The values are updated with `salsa::update::Update`, which they must implement.
+    let update_items = match &args.update {
+        Some(_) => vec![
+            parse_quote! {
+                const UPDATE: bool = true;
+            },
+            parse_quote! {
+                fn update_value(__old_value: &mut Self::Value, __new_value: Self::Value) {
+                    salsa::update::Update::update(__old_value, __new_value)
+                }
+            },
+        ],
+        None => vec![],
+    };
+
+    let memo_map_shards_const = args.shards.map(|shards| {
+        parse_quote! {
+            const MEMO_MAP_SHARDS: usize = #shards;
+        }
+    });
+
+    let cycle_max_iterations_const = args.cycle_max_iterations.map(|max_iterations| {
+        parse_quote! {
+            const CYCLE_MAX_ITERATIONS: u32 = #max_iterations;
+        }
+    });
+
+    let phase_const = args.phase.as_ref().map(|phase| {
+        parse_quote! {
+            const PHASE: Option<&'static str> = Some(#phase);
+        }
+    });
 
     // The type of the configuration struct; this has the same name as the fn itself.
 
@@ -411,18 +1029,56 @@ fn fn_configuration(args: &FnArgs, item_fn: &syn::ItemFn) -> Configuration {
     let mut inner_fn = item_fn.clone();
     inner_fn.sig.ident = inner_fn_name.clone();
 
+    // The type and const parameters of a generic function may not be inferable from
+    // its arguments, so pass them explicitly.
+    let generic_args: Vec<_> = struct_generics(item_fn)
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Type(param) => param.ident.clone(),
+            syn::GenericParam::Const(param) => param.ident.clone(),
+            syn::GenericParam::Lifetime(_) => unreachable!(),
+        })
+        .collect();
+    let inner_fn_path = if generic_args.is_empty() {
+        quote! { #inner_fn_name }
+    } else {
+        quote! { #inner_fn_name::<#(#generic_args),*> }
+    };
+
     // Create the `execute` function, which (a) maps from the interned id to the actual
     // keys and then (b) invokes the function itself (which we embed within).
+    // A volatile function reports an untracked read, so that its memo is only valid
+    // in the revision in which it is computed.
+    let report_volatile = args.volatile.as_ref().map(|_| {
+        quote! {
+            __runtime.report_untracked_read();
+        }
+    });
+    // Likewise, with `retry_errors`, for the `Err` results of a function returning a `Result`.
     let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
+    let call_inner_fn = quote! { #inner_fn_path(__db, #(__key.#indices),*) };
+    let boxed_value = box_value(args, quote! { __value });
+    let call_inner_fn = match &args.retry_errors {
+        Some(_) => quote! {
+            let __value = #call_inner_fn;
+            if ::std::result::Result::is_err(&__value) {
+                __runtime.report_untracked_read();
+            }
+            #boxed_value
+        },
+        None => box_value(args, call_inner_fn),
+    };
     let execute_fn = parse_quote! {
         fn execute(__db: &salsa::function::DynDb<Self>, __id: Self::Key) -> Self::Value {
             #inner_fn
 
             let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
+            #report_volatile
             let __ingredients =
-                <_ as salsa::storage::HasIngredientsFor<#fn_ty>>::ingredient(__jar);
+                <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(__jar);
             let __key = __ingredients.intern_map.data(__runtime, __id).clone();
-            #inner_fn_name(__db, #(__key.#indices),*)
+            #call_inner_fn
         }
     };
 
@@ -435,6 +1091,35 @@ fn fn_configuration(args: &FnArgs, item_fn: &syn::ItemFn) -> Configuration {
         backdate_fn,
         execute_fn,
         recover_fn,
+        cycle_initial_fn,
+        cycle_iterate_fn,
+        cycle_max_iterations_const,
+        heap_size_fn,
+        share_value_fns,
+        cache_items,
+        update_items,
+        memo_map_shards_const,
+        query_fingerprint_const: configuration::query_fingerprint_const(&query_fingerprint_text(
+            args, item_fn,
+        )),
+        phase_const,
+    }
+}
+
+/// What the fingerprint of a tracked function is computed from: its name, its
+/// signature and its `version`, if given. See `Configuration::QUERY_FINGERPRINT`.
+fn query_fingerprint_text(args: &FnArgs, item_fn: &syn::ItemFn) -> String {
+    let sig = &item_fn.sig;
+    let version = args.version.as_ref().map(|version| quote!(#version));
+    format!("{}\0{}\0{}", sig.ident, quote!(#sig), quote!(#version))
+}
+
+/// With the `boxed` option, boxes `value`, a value of the tracked function,
+/// as it is memoized; otherwise, returns it as is.
+fn box_value(args: &FnArgs, value: TokenStream) -> TokenStream {
+    match &args.boxed {
+        Some(_) => quote! { Box::new(#value) },
+        None => value,
     }
 }
 
@@ -442,11 +1127,13 @@ fn ingredients_for_impl(
     args: &FnArgs,
     item_fn: &syn::ItemFn,
     config_ty: &syn::Type,
+    generics: &syn::Generics,
 ) -> syn::ItemImpl {
     let jar_ty = args.jar_ty();
     let debug_name = crate::literal(&item_fn.sig.ident);
+    let push_method = args.routes_push_method();
 
-    let intern_map: syn::Expr = match function_type(item_fn) {
+    let intern_map: syn::Expr = match function_type(args, item_fn) {
         FunctionType::Constant | FunctionType::SalsaStruct => {
             parse_quote! {
                 salsa::interned::IdentityInterner::new()
@@ -455,7 +1142,7 @@ fn ingredients_for_impl(
         FunctionType::RequiresInterning => {
             parse_quote! {
                 {
-                    let index = routes.push(
+                    let index = routes.#push_method(
                         |jars| {
                             let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                             let ingredients =
@@ -478,11 +1165,50 @@ fn ingredients_for_impl(
     // set 0 as default to disable LRU
     let lru = args.lru.unwrap_or(0);
 
+    // the ttl is only set when given, so that memos don't record when they were computed
+    let set_ttl = args
+        .ttl
+        .as_ref()
+        .map(|ttl| quote! { ingredient.set_ttl(Some(#ttl)); });
+
+    // The `diff` option needs the value each memo replaces.
+    let history_depth = match (args.history, &args.diff) {
+        (Some(depth), Some(_)) => Some(depth.max(1)),
+        (None, Some(_)) => Some(1),
+        (depth, None) => depth,
+    };
+    let set_history = history_depth.map(|depth| quote! { ingredient.set_history_depth(#depth); });
+
     // get the name of the function as a string literal
     let debug_name = crate::literal(&item_fn.sig.ident);
 
+    let projections = projections(args, item_fn).map(|(field, ingredient_field, _)| {
+        let debug_name = Literal::string(&format!("{}::{field}", item_fn.sig.ident));
+        quote! {
+            #ingredient_field: {
+                let index = routes.#push_method(
+                    |jars| {
+                        let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
+                        let ingredients =
+                            <_ as salsa::storage::HasIngredientsFor<Self::Ingredients>>::ingredient(jar);
+                        &ingredients.#ingredient_field
+                    },
+                    |jars| {
+                        let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars_mut(jars);
+                        let ingredients =
+                            <_ as salsa::storage::HasIngredientsFor<Self::Ingredients>>::ingredient_mut(jar);
+                        &mut ingredients.#ingredient_field
+                    });
+                salsa::function::FunctionIngredient::new(index, #debug_name)
+            },
+        }
+    });
+
+    let phantom = phantom_field_ty(generics).map(|_| quote! { phantom: std::marker::PhantomData, });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
     parse_quote! {
-        impl salsa::storage::IngredientsFor for #config_ty {
+        impl #impl_generics salsa::storage::IngredientsFor for #config_ty #where_clause {
             type Ingredients = Self;
             type Jar = #jar_ty;
 
@@ -494,7 +1220,7 @@ fn ingredients_for_impl(
                     intern_map: #intern_map,
 
                     function: {
-                        let index = routes.push(
+                        let index = routes.#push_method(
                             |jars| {
                                 let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                                 let ingredients =
@@ -509,8 +1235,14 @@ fn ingredients_for_impl(
                             });
                         let ingredient = salsa::function::FunctionIngredient::new(index, #debug_name);
                         ingredient.set_capacity(#lru);
+                        #set_ttl
+                        #set_history
                         ingredient
-                    }
+                    },
+
+                    #(#projections)*
+
+                    #phantom
                 }
             }
         }
@@ -521,15 +1253,32 @@ fn setter_impl(
     args: &FnArgs,
     item_fn: &syn::ItemFn,
     config_ty: &syn::Type,
+    generics: &syn::Generics,
 ) -> syn::Result<syn::ItemImpl> {
     let ref_getter_fn = ref_getter_fn(args, item_fn, config_ty)?;
-    let accumulated_fn = accumulated_fn(args, item_fn, config_ty)?;
+    let accumulated_with_provenance_fn = accumulated_fn(args, item_fn, config_ty, true)?;
+    let accumulated_fn = accumulated_fn(args, item_fn, config_ty, false)?;
+    let database_key_fn = database_key_fn(args, item_fn, config_ty)?;
+    let memo_info_fn = memo_info_fn(args, item_fn, config_ty)?;
+    let on_change_fn = on_change_fn(args, item_fn, config_ty)?;
     let setter_fn = setter_fn(args, item_fn, config_ty)?;
     let specify_fn = specify_fn(args, item_fn, config_ty)?.map(|f| quote! { #f });
     let set_lru_fn = set_lru_capacity_fn(args, config_ty)?.map(|f| quote! { #f });
+    let value_as_of_fn = value_as_of_fn(args, item_fn, config_ty)?
+        .map(|f| quote! { #[allow(dead_code, clippy::needless_lifetimes)] #f });
+    let with_delta_fn = with_delta_fn(args, item_fn, config_ty)?
+        .map(|f| quote! { #[allow(dead_code, clippy::needless_lifetimes)] #f });
+    let entries_fn = entries_fn(args, item_fn, config_ty)?;
+    let purge_key_fn = purge_fn(args, item_fn, config_ty, true)?;
+    let purge_fn = purge_fn(args, item_fn, config_ty, false)?;
+    let unpin_fn = pin_fn(args, item_fn, config_ty, false)?;
+    let pin_fn = pin_fn(args, item_fn, config_ty, true)?;
+    let project_fns = project_fns(args, item_fn, config_ty)?;
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
 
     let setter_impl: syn::ItemImpl = parse_quote! {
-        impl #config_ty {
+        impl #impl_generics #config_ty #where_clause {
             #[allow(dead_code, clippy::needless_lifetimes)]
             #ref_getter_fn
 
@@ -539,8 +1288,44 @@ fn setter_impl(
             #[allow(dead_code, clippy::needless_lifetimes)]
             #accumulated_fn
 
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #accumulated_with_provenance_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #database_key_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #memo_info_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #on_change_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes, clippy::type_complexity)]
+            #entries_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #purge_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #purge_key_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #pin_fn
+
+            #[allow(dead_code, clippy::needless_lifetimes)]
+            #unpin_fn
+
             #set_lru_fn
 
+            #value_as_of_fn
+
+            #with_delta_fn
+
+            #(
+                #[allow(dead_code, clippy::needless_lifetimes)]
+                #project_fns
+            )*
+
             #specify_fn
         }
     };
@@ -582,13 +1367,13 @@ fn getter_fn(
         make_fn_return_ref(fn_sig)?;
         parse_quote_spanned! {
             block_span => {
-                #config_ty::get(#(#arg_idents,)*)
+                <#config_ty>::get(#(#arg_idents,)*)
             }
         }
     } else {
         parse_quote_spanned! {
             block_span => {
-                Clone::clone(#config_ty::get(#(#arg_idents,)*))
+                Clone::clone(<#config_ty>::get(#(#arg_idents,)*))
             }
         }
     })
@@ -609,11 +1394,12 @@ fn ref_getter_fn(
     make_fn_return_ref(&mut ref_getter_fn.sig)?;
 
     let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
     ref_getter_fn.block = parse_quote! {
         {
             let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
             let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
-            let __key = __ingredients.intern_map.intern(__runtime, (#(#arg_names),*));
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
             __ingredients.function.fetch(#db_var, __key)
         }
     };
@@ -632,6 +1418,7 @@ fn setter_fn(
     // but it takes a value arg and has no return type.
     let jar_ty = args.jar_ty();
     let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
     let mut setter_sig = item_fn.sig.clone();
     let value_ty = configuration::value_ty(&item_fn.sig);
     setter_sig.ident = syn::Ident::new("set", item_fn.sig.ident.span());
@@ -648,6 +1435,7 @@ fn setter_fn(
     let value_arg = syn::Ident::new("__value", item_fn.sig.output.span());
     setter_sig.inputs.push(parse_quote!(#value_arg: #value_ty));
     setter_sig.output = ReturnType::Default;
+    let value = box_value(args, quote! { #value_arg });
     Ok(syn::ImplItemMethod {
         attrs: vec![],
         vis: item_fn.vis.clone(),
@@ -657,8 +1445,8 @@ fn setter_fn(
             {
                 let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar_mut(#db_var);
                 let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient_mut(__jar);
-                let __key = __ingredients.intern_map.intern(__runtime, (#(#arg_names),*));
-                __ingredients.function.store(__runtime, __key, #value_arg, salsa::Durability::LOW)
+                let __key = __ingredients.intern_map.intern(__runtime, #key);
+                __ingredients.function.store(__runtime, __key, #value, salsa::Durability::LOW)
             }
         },
     })
@@ -697,6 +1485,330 @@ fn set_lru_capacity_fn(
     Ok(Some(lru_fn))
 }
 
+/// Create an `entries` associated function that returns every memoized value of
+/// the tracked function (see `FunctionIngredient::entries`), keyed by its argument,
+/// or the tuple of its arguments if it takes more than one.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked]
+/// fn my_tracked_fn(db: &dyn crate::Db, file: File) -> Value { }
+///
+/// for (file, value, changed_at, verified_at) in my_tracked_fn::entries(db) { }
+/// ```
+fn entries_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+    let mut entries_fn = item_fn.clone();
+    entries_fn.sig.ident = syn::Ident::new("entries", item_fn.sig.ident.span());
+    entries_fn.sig.inputs = entries_fn.sig.inputs.into_iter().take(1).collect();
+    let (db_lifetime, _) = db_lifetime_and_ty(&mut entries_fn.sig)?;
+    let key_ty = match &item_fn.sig.inputs.iter().skip(1).collect::<Vec<_>>()[..] {
+        [syn::FnArg::Typed(pat_ty)] => (*pat_ty.ty).clone(),
+        _ => key_tuple_ty(item_fn),
+    };
+    let value_ty = configuration::value_ty(&item_fn.sig);
+    entries_fn.sig.output = parse_quote! {
+        -> Vec<(#key_ty, &#db_lifetime #value_ty, salsa::Revision, salsa::Revision)>
+    };
+
+    let (db_var, _) = fn_args(item_fn)?;
+    let indices = (0..item_fn.sig.inputs.len() - 1).map(Literal::usize_unsuffixed);
+    let value = match &args.boxed {
+        Some(_) => quote! { &**__value },
+        None => quote! { __value },
+    };
+    entries_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            __ingredients
+                .function
+                .entries()
+                .into_iter()
+                .map(|(__id, __value, __changed_at, __verified_at)| {
+                    #[allow(unused_variables)]
+                    let __key = __ingredients.intern_map.data(__runtime, __id).clone();
+                    ((#(__key.#indices),*), #value, __changed_at, __verified_at)
+                })
+                .collect()
+        }
+    };
+
+    Ok(entries_fn)
+}
+
+/// Generates the `purge` function, which removes every memo of the tracked
+/// function (see `FunctionIngredient::purge`) and starts a new revision, so
+/// the values are recomputed the next time they are fetched. If `with_key` is
+/// true, generates `purge_key` instead, which takes the arguments of the
+/// tracked function and only removes their memo.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked]
+/// fn my_tracked_fn(db: &dyn crate::Db, file: File) -> Value { }
+///
+/// my_tracked_fn::purge(db);
+/// my_tracked_fn::purge_key(db, file);
+/// ```
+fn purge_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+    with_key: bool,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let mut purge_fn = item_fn.clone();
+    let (name, key) = if with_key {
+        let key = key_expr(args, item_fn, &arg_names);
+        (
+            "purge_key",
+            quote! { Some(__ingredients.intern_map.intern(__runtime, #key)) },
+        )
+    } else {
+        purge_fn.sig.inputs = purge_fn.sig.inputs.into_iter().take(1).collect();
+        ("purge", quote! { None })
+    };
+    purge_fn.sig.ident = syn::Ident::new(name, item_fn.sig.ident.span());
+    match &mut purge_fn.sig.inputs[0] {
+        // change from `&dyn ...` to `&mut dyn...`
+        syn::FnArg::Receiver(_) => unreachable!(), // early fns should have detected
+        syn::FnArg::Typed(pat_ty) => match &mut *pat_ty.ty {
+            syn::Type::Reference(ty) => {
+                ty.mutability = Some(Token![mut](ty.and_token.span()));
+            }
+            _ => unreachable!(), // early fns should have detected
+        },
+    }
+    purge_fn.sig.output = ReturnType::Default;
+    purge_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar_mut(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient_mut(__jar);
+            let __key = #key;
+            if let Some(__durability) = __ingredients.function.purge(__key) {
+                __runtime.report_tracked_write(__durability);
+            }
+        }
+    };
+
+    Ok(purge_fn)
+}
+
+/// Generates the `pin` function, which exempts the memo for the arguments of the
+/// tracked function from eviction (see `FunctionIngredient::pin`), or, if `pin`
+/// is false, the `unpin` function, which makes it evictable again.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked]
+/// fn my_tracked_fn(db: &dyn crate::Db, file: File) -> Value { }
+///
+/// my_tracked_fn::pin(db, file);
+/// my_tracked_fn::unpin(db, file);
+/// ```
+fn pin_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+    pin: bool,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    let name = if pin { "pin" } else { "unpin" };
+    let method = syn::Ident::new(name, proc_macro2::Span::call_site());
+    let mut pin_fn = item_fn.clone();
+    pin_fn.sig.ident = syn::Ident::new(name, item_fn.sig.ident.span());
+    pin_fn.sig.output = ReturnType::Default;
+    pin_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.#method(__key)
+        }
+    };
+
+    Ok(pin_fn)
+}
+
+/// Create a `value_as_of` associated function that returns the value the tracked
+/// function had in a given revision, if it is still known (see
+/// `FunctionIngredient::value_as_of`). Like the function itself, it returns a
+/// reference if `return_ref` is given.
+/// Note that this function is only generated if the tracked function has the history option set.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked(history=1)]
+/// fn my_tracked_fn(db: &dyn crate::Db, ...) { }
+///
+/// my_tracked_fn::value_as_of(db, ..., revision)
+/// ```
+fn value_as_of_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<Option<syn::ItemFn>> {
+    if args.history.is_none() {
+        return Ok(None);
+    }
+
+    let jar_ty = args.jar_ty();
+    let mut value_as_of_fn = item_fn.clone();
+    value_as_of_fn.sig.ident = syn::Ident::new("value_as_of", item_fn.sig.ident.span());
+    let value_ty = configuration::value_ty(&item_fn.sig);
+    let (output, mut convert) = if args.return_ref.is_some() {
+        let (db_lifetime, _) = db_lifetime_and_ty(&mut value_as_of_fn.sig)?;
+        (quote! { Option<&#db_lifetime #value_ty> }, quote! {})
+    } else {
+        (quote! { Option<#value_ty> }, quote! { .cloned() })
+    };
+    if args.boxed.is_some() {
+        convert = quote! { .map(|__value| &**__value) #convert };
+    }
+    value_as_of_fn.sig.output = parse_quote! { -> #output };
+    value_as_of_fn
+        .sig
+        .inputs
+        .push(parse_quote! { __revision: salsa::Revision });
+
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    value_as_of_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.value_as_of(__key, __revision) #convert
+        }
+    };
+
+    Ok(Some(value_as_of_fn))
+}
+
+/// Create a `with_delta` associated function that returns the value of the tracked
+/// function, like the function itself, along with what changed since a given
+/// revision (see `salsa::diff::Diffable`), if the value it had then is still known.
+/// Note that this function is only generated if the tracked function has the diff option set.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked(diff)]
+/// fn my_tracked_fn(db: &dyn crate::Db, ...) -> Vec<Diagnostic> { }
+///
+/// let (value, delta) = my_tracked_fn::with_delta(db, ..., last_revision);
+/// ```
+fn with_delta_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<Option<syn::ItemFn>> {
+    if args.diff.is_none() {
+        return Ok(None);
+    }
+
+    let jar_ty = args.jar_ty();
+    let mut with_delta_fn = item_fn.clone();
+    with_delta_fn.sig.ident = syn::Ident::new("with_delta", item_fn.sig.ident.span());
+    let value_ty = configuration::value_ty(&item_fn.sig);
+    let delta_ty = quote! { Option<<#value_ty as salsa::diff::Diffable>::Delta> };
+    let (output, value) = if args.return_ref.is_some() {
+        let (db_lifetime, _) = db_lifetime_and_ty(&mut with_delta_fn.sig)?;
+        (
+            quote! { (&#db_lifetime #value_ty, #delta_ty) },
+            quote! { __value },
+        )
+    } else {
+        (
+            quote! { (#value_ty, #delta_ty) },
+            quote! { Clone::clone(__value) },
+        )
+    };
+    with_delta_fn.sig.output = parse_quote! { -> #output };
+    with_delta_fn
+        .sig
+        .inputs
+        .push(parse_quote! { __since: salsa::Revision });
+
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    // Fetching the value first moves the memo it replaces, if any, to the history.
+    with_delta_fn.block = parse_quote! {
+        {
+            let __value: &#value_ty = Self::get(#db_var, #(#arg_names,)*);
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            let __delta = __ingredients.function.value_as_of(__key, __since).map(|__old| {
+                let __old: &#value_ty = __old;
+                salsa::diff::Diffable::diff(__old, __value)
+            });
+            (#value, __delta)
+        }
+    };
+
+    Ok(Some(with_delta_fn))
+}
+
+/// Create an associated function for each field listed in the `project` option,
+/// named after it, which returns the field of the value of the tracked function
+/// through its projection query: unlike the function itself, its callers do not
+/// depend on the other fields.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked(project(return_type: Type))]
+/// fn signature_of(db: &dyn crate::Db, function: Function) -> Signature { }
+///
+/// let return_type = signature_of::return_type(db, function);
+/// ```
+fn project_fns(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<Vec<syn::ItemFn>> {
+    let jar_ty = args.jar_ty();
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    projections(args, item_fn)
+        .zip(args.project.iter().flatten())
+        .map(|((field, ingredient_field, _), (_, field_ty))| {
+            let mut project_fn = item_fn.clone();
+            project_fn.sig.ident = field.clone();
+            project_fn.sig.output = parse_quote! { -> #field_ty };
+            let fetch = quote! { __ingredients.#ingredient_field.fetch(#db_var, __key) };
+            let value = if args.return_ref.is_some() {
+                make_fn_return_ref(&mut project_fn.sig)?;
+                fetch
+            } else {
+                quote! { Clone::clone(#fetch) }
+            };
+            project_fn.block = parse_quote! {
+                {
+                    let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+                    let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+                    let __key = __ingredients.intern_map.intern(__runtime, #key);
+                    #value
+                }
+            };
+            Ok(project_fn)
+        })
+        .collect()
+}
+
 fn specify_fn(
     args: &FnArgs,
     item_fn: &syn::ItemFn,
@@ -710,12 +1822,29 @@ fn specify_fn(
     // but it takes a value arg and has no return type.
     let jar_ty = args.jar_ty();
     let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
     let mut setter_sig = item_fn.sig.clone();
     let value_ty = configuration::value_ty(&item_fn.sig);
     setter_sig.ident = syn::Ident::new("specify", item_fn.sig.ident.span());
     let value_arg = syn::Ident::new("__value", item_fn.sig.output.span());
     setter_sig.inputs.push(parse_quote!(#value_arg: #value_ty));
     setter_sig.output = ReturnType::Default;
+    let value = box_value(args, quote! { #value_arg });
+    let specify = match function_type(args, item_fn) {
+        FunctionType::Constant | FunctionType::SalsaStruct => quote! {
+            __ingredients.function.specify_and_record(#db_var, #key, #value)
+        },
+        // The key is interned, so the tracked struct whose creator can specify
+        // the value is the first argument.
+        FunctionType::RequiresInterning => {
+            let owner = &arg_names[0];
+            quote! {
+                let __owner = salsa::tracked_struct::TrackedStructInDb::database_key_index(#owner, #db_var);
+                let __key = __ingredients.intern_map.intern(__runtime, #key);
+                __ingredients.function.specify_owned_and_record(#db_var, __owner, __key, #value)
+            }
+        }
+    };
     Ok(Some(syn::ImplItemMethod {
         attrs: vec![],
         vis: item_fn.vis.clone(),
@@ -726,7 +1855,7 @@ fn specify_fn(
 
                 let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
                 let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
-                __ingredients.function.specify_and_record(#db_var, #(#arg_names,)* #value_arg)
+                #specify
             }
         },
     }))
@@ -734,7 +1863,7 @@ fn specify_fn(
 /// Given a function def tagged with `#[return_ref]`, modifies `fn_sig` so that
 /// it returns an `&Value` instead of `Value`. May introduce a name for the
 /// database lifetime if required.
-fn make_fn_return_ref(mut fn_sig: &mut syn::Signature) -> syn::Result<()> {
+fn make_fn_return_ref(fn_sig: &mut syn::Signature) -> syn::Result<()> {
     // An input should be a `&dyn Db`.
     // We need to ensure it has a named lifetime parameter.
     let (db_lifetime, _) = db_lifetime_and_ty(fn_sig)?;
@@ -806,21 +1935,35 @@ fn db_lifetime_and_ty(func: &mut syn::Signature) -> syn::Result<(syn::Lifetime,
 
 /// Generates the `accumulated` function, which invokes `accumulated`
 /// on the function ingredient to extract the values pushed (transitively)
-/// into an accumulator.
+/// into an accumulator. If `with_provenance` is true, generates
+/// `accumulated_with_provenance` instead, which pairs each value with
+/// the `DatabaseKeyIndex` of the query that pushed it.
 fn accumulated_fn(
     args: &FnArgs,
     item_fn: &syn::ItemFn,
     config_ty: &syn::Type,
+    with_provenance: bool,
 ) -> syn::Result<syn::ItemFn> {
     let jar_ty = args.jar_ty();
+    let method = if with_provenance {
+        syn::Ident::new("accumulated_with_provenance", item_fn.sig.ident.span())
+    } else {
+        syn::Ident::new("accumulated", item_fn.sig.ident.span())
+    };
 
     let mut accumulated_fn = item_fn.clone();
-    accumulated_fn.sig.ident = syn::Ident::new("accumulated", item_fn.sig.ident.span());
+    accumulated_fn.sig.ident = method.clone();
     accumulated_fn.sig.generics.params.push(parse_quote! {
         __A: salsa::accumulator::Accumulator
     });
-    accumulated_fn.sig.output = parse_quote! {
-        -> Vec<<__A as salsa::accumulator::Accumulator>::Data>
+    accumulated_fn.sig.output = if with_provenance {
+        parse_quote! {
+            -> Vec<(salsa::DatabaseKeyIndex, <__A as salsa::accumulator::Accumulator>::Data)>
+        }
+    } else {
+        parse_quote! {
+            -> Vec<<__A as salsa::accumulator::Accumulator>::Data>
+        }
     };
 
     let (db_lifetime, _) = db_lifetime_and_ty(&mut accumulated_fn.sig)?;
@@ -833,18 +1976,136 @@ fn accumulated_fn(
     }
 
     let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
     accumulated_fn.block = parse_quote! {
         {
             let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
             let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
-            let __key = __ingredients.intern_map.intern(__runtime, (#(#arg_names),*));
-            __ingredients.function.accumulated::<__A>(#db_var, __key)
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.#method::<__A>(#db_var, __key)
         }
     };
 
     Ok(accumulated_fn)
 }
 
+/// Generates the `database_key` function, which returns the `DatabaseKeyIndex`
+/// identifying the memoized value for some inputs (e.g., to pass to
+/// `salsa::debug::dump_dependency_graph`).
+fn database_key_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+
+    let mut database_key_fn = item_fn.clone();
+    database_key_fn.sig.ident = syn::Ident::new("database_key", item_fn.sig.ident.span());
+    database_key_fn.sig.output = parse_quote! {
+        -> salsa::DatabaseKeyIndex
+    };
+
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    database_key_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.database_key_index(__key)
+        }
+    };
+
+    Ok(database_key_fn)
+}
+
+/// Create a `memo_info` associated function that returns the durability and the
+/// revisions of the memoized value for the given arguments, if there is one
+/// (see `FunctionIngredient::memo_info`).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked]
+/// fn my_tracked_fn(db: &dyn crate::Db, file: File) -> Value { }
+///
+/// let info = my_tracked_fn::memo_info(db, file).unwrap();
+/// assert_eq!(info.durability, salsa::Durability::HIGH);
+/// ```
+fn memo_info_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+
+    let mut memo_info_fn = item_fn.clone();
+    memo_info_fn.sig.ident = syn::Ident::new("memo_info", item_fn.sig.ident.span());
+    memo_info_fn.sig.output = parse_quote! {
+        -> Option<salsa::function::MemoInfo>
+    };
+
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    memo_info_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.memo_info(__key)
+        }
+    };
+
+    Ok(memo_info_fn)
+}
+
+/// Create an `on_change` associated function that registers a callback invoked
+/// with the old and the new value each time the value for the given arguments
+/// changes (see `FunctionIngredient::on_change`).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[salsa::tracked]
+/// fn my_tracked_fn(db: &dyn crate::Db, file: File) -> Value { }
+///
+/// my_tracked_fn::on_change(db, file, |old, new| println!("{old:?} became {new:?}"));
+/// ```
+fn on_change_fn(
+    args: &FnArgs,
+    item_fn: &syn::ItemFn,
+    config_ty: &syn::Type,
+) -> syn::Result<syn::ItemFn> {
+    let jar_ty = args.jar_ty();
+    let value_ty = configuration::value_ty(&item_fn.sig);
+
+    let mut on_change_fn = item_fn.clone();
+    on_change_fn.sig.ident = syn::Ident::new("on_change", item_fn.sig.ident.span());
+    on_change_fn.sig.output = syn::ReturnType::Default;
+    on_change_fn.sig.inputs.push(parse_quote! {
+        __callback: impl Fn(&#value_ty, &#value_ty) + Send + Sync + 'static
+    });
+
+    let (db_var, arg_names) = fn_args(item_fn)?;
+    let key = key_expr(args, item_fn, &arg_names);
+    let callback = match &args.boxed {
+        Some(_) => quote! {
+            move |__old: &Box<#value_ty>, __new: &Box<#value_ty>| __callback(__old, __new)
+        },
+        None => quote! { __callback },
+    };
+    on_change_fn.block = parse_quote! {
+        {
+            let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(#db_var);
+            let __ingredients = <_ as salsa::storage::HasIngredientsFor<#config_ty>>::ingredient(__jar);
+            let __key = __ingredients.intern_map.intern(__runtime, #key);
+            __ingredients.function.on_change(__key, #callback)
+        }
+    };
+
+    Ok(on_change_fn)
+}
+
 /// Examines the function arguments and returns a tuple of:
 ///
 /// * the name of the database argument
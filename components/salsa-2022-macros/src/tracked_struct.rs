@@ -32,6 +32,8 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const NO_EQ: bool = false;
 
+    const EQ: bool = false;
+
     const SINGLETON: bool = false;
 
     const JAR: bool = true;
@@ -42,9 +44,42 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_FN: bool = false;
+
+    const CYCLE_INITIAL: bool = false;
+
+    const CYCLE_MAX_ITERATIONS: bool = false;
+
     const LRU: bool = false;
 
     const CONSTRUCTOR_NAME: bool = true;
+
+    const PERSIST: bool = true;
+
+    const DEDUP: bool = false;
+
+    const SORT_BY_KEY: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const HEAP_SIZE: bool = false;
+    const TTL: bool = false;
+    const SHARDS: bool = false;
+    const HISTORY: bool = false;
+    const CONTENT_IDS: bool = true;
+    const DYNAMIC: bool = false;
+    const SHARE_VALUES: bool = false;
+    const RETRY_ERRORS: bool = false;
+    const CACHE: bool = false;
+    const UPDATE: bool = false;
+    const SERIALIZE: bool = true;
+    const BOXED: bool = false;
+    const DIFF: bool = false;
+    const PROJECT: bool = false;
+    const VERSION: bool = false;
+    const PHASE: bool = false;
+    const VALIDATE: bool = false;
+    const INTERN_ARGS: bool = false;
 }
 
 impl TrackedStruct {
@@ -61,6 +96,8 @@ impl TrackedStruct {
         let tracked_struct_in_db_impl = self.tracked_struct_in_db_impl();
         let as_id_impl = self.as_id_impl();
         let as_debug_with_db_impl = self.as_debug_with_db_impl();
+        let as_debug_impl = self.as_debug_impl();
+        let serde_impls = self.tracked_serde_impls();
         Ok(quote! {
             #(#config_structs)*
             #id_struct
@@ -70,17 +107,44 @@ impl TrackedStruct {
             #tracked_struct_in_db_impl
             #as_id_impl
             #as_debug_with_db_impl
+            #as_debug_impl
+            #serde_impls
             #(#config_impls)*
         })
     }
 
+    /// Deserializing a tracked struct creates it, so it must be done by a tracked
+    /// function. Lazy fields are computed again rather than serialized.
+    fn tracked_serde_impls(&self) -> Option<TokenStream> {
+        let id_ty = self.id_ty();
+        let constructor_name = self.constructor_name();
+        let fields: Vec<_> = self.all_fields().filter(|f| f.lazy_fn.is_none()).collect();
+        let field_names: Vec<_> = fields.iter().map(|f| f.name()).collect();
+        self.serde_impls(
+            &fields,
+            quote! { <#id_ty>::#constructor_name(__db, #(__fields.#field_names),*) },
+        )
+    }
+
     fn validate_tracked(&self) -> syn::Result<()> {
+        self.disallow_default_fields("tracked")?;
+        for ef in self.id_fields() {
+            if ef.lazy_fn.is_some() {
+                return Err(syn::Error::new(
+                    ef.name().span(),
+                    "`#[lazy]` cannot be used with `#[id]` fields",
+                ));
+            }
+        }
+
         Ok(())
     }
 
     /// Generate an inherent impl with methods on the tracked type.
     fn tracked_inherent_impl(&self) -> syn::ItemImpl {
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
         let jar_ty = self.jar_ty();
         let db_dyn_ty = self.db_dyn_ty();
         let struct_index = self.tracked_struct_index();
@@ -88,17 +152,26 @@ impl TrackedStruct {
         let id_field_indices: Vec<_> = self.id_field_indices();
         let id_field_names: Vec<_> = self.id_fields().map(SalsaField::name).collect();
         let id_field_get_names: Vec<_> = self.id_fields().map(SalsaField::get_name).collect();
-        let id_field_tys: Vec<_> = self.id_fields().map(SalsaField::ty).collect();
+        let id_field_tys: Vec<_> = self.id_fields().map(SalsaField::returned_ty).collect();
+        let id_field_refs: Vec<_> = id_field_indices
+            .iter()
+            .zip(self.id_fields())
+            .map(|(field_index, field)| {
+                field.returned_ref(quote! {
+                    &__ingredients.#struct_index.tracked_struct_data(__runtime, self).#field_index
+                })
+            })
+            .collect();
         let id_field_vises: Vec<_> = self.id_fields().map(SalsaField::vis).collect();
         let id_field_clones: Vec<_> = self.id_fields().map(SalsaField::is_clone_field).collect();
-        let id_field_getters: Vec<syn::ImplItemMethod> = id_field_indices.iter().zip(&id_field_get_names).zip(&id_field_tys).zip(&id_field_vises).zip(&id_field_clones).map(|((((field_index, field_get_name), field_ty), field_vis), is_clone_field)|
+        let id_field_getters: Vec<syn::ImplItemMethod> = id_field_indices.iter().zip(&id_field_get_names).zip(&id_field_tys).zip(&id_field_vises).zip(&id_field_clones).zip(&id_field_refs).map(|(((((field_index, field_get_name), field_ty), field_vis), is_clone_field), field_ref)|
             if !*is_clone_field {
                 parse_quote! {
                     #field_vis fn #field_get_name<'db>(self, __db: &'db #db_dyn_ty) -> &'db #field_ty
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                        &__ingredients.#struct_index.tracked_struct_data(__runtime, self).#field_index
+                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(__jar);
+                        #field_ref
                     }
                 }
             } else {
@@ -106,7 +179,7 @@ impl TrackedStruct {
                     #field_vis fn #field_get_name<'db>(self, __db: &'db #db_dyn_ty) -> #field_ty
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
+                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(__jar);
                         __ingredients.#struct_index.tracked_struct_data(__runtime, self).#field_index.clone()
                     }
                 }
@@ -115,22 +188,28 @@ impl TrackedStruct {
         .collect();
 
         let value_field_indices = self.value_field_indices();
-        let value_field_names: Vec<_> = self.value_fields().map(SalsaField::name).collect();
         let value_field_vises: Vec<_> = self.value_fields().map(SalsaField::vis).collect();
-        let value_field_tys: Vec<_> = self.value_fields().map(SalsaField::ty).collect();
+        let value_field_tys: Vec<_> = self.value_fields().map(SalsaField::returned_ty).collect();
+        let value_field_refs: Vec<_> = value_field_indices
+            .iter()
+            .zip(self.value_fields())
+            .map(|(field_index, field)| {
+                field.returned_ref(quote! { __ingredients.#field_index.fetch(__db, self) })
+            })
+            .collect();
         let value_field_get_names: Vec<_> = self.value_fields().map(SalsaField::get_name).collect();
         let value_field_clones: Vec<_> = self
             .value_fields()
             .map(SalsaField::is_clone_field)
             .collect();
-        let value_field_getters: Vec<syn::ImplItemMethod> = value_field_indices.iter().zip(&value_field_get_names).zip(&value_field_tys).zip(&value_field_vises).zip(&value_field_clones).map(|((((field_index, field_get_name), field_ty), field_vis), is_clone_field)|
+        let value_field_getters: Vec<syn::ImplItemMethod> = value_field_indices.iter().zip(&value_field_get_names).zip(&value_field_tys).zip(&value_field_vises).zip(&value_field_clones).zip(&value_field_refs).map(|(((((field_index, field_get_name), field_ty), field_vis), is_clone_field), field_ref)|
             if !*is_clone_field {
                 parse_quote! {
                     #field_vis fn #field_get_name<'db>(self, __db: &'db #db_dyn_ty) -> &'db #field_ty
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
-                        __ingredients.#field_index.fetch(__db, self)
+                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(__jar);
+                        #field_ref
                     }
                 }
             } else {
@@ -138,7 +217,7 @@ impl TrackedStruct {
                     #field_vis fn #field_get_name<'db>(self, __db: &'db #db_dyn_ty) -> #field_ty
                     {
                         let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
+                        let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(__jar);
                         __ingredients.#field_index.fetch(__db, self).clone()
                     }
                 }
@@ -146,19 +225,36 @@ impl TrackedStruct {
         )
         .collect();
 
-        let all_field_names = self.all_field_names();
-        let all_field_tys = self.all_field_tys();
+        // Lazy fields are computed when first read, so they are not given to the constructor.
+        let constructor_fields: Vec<_> = self
+            .all_fields()
+            .filter(|ef| ef.lazy_fn.is_none())
+            .collect();
+        let constructor_field_names: Vec<_> =
+            constructor_fields.iter().map(|ef| ef.name()).collect();
+        let constructor_field_tys: Vec<_> = constructor_fields.iter().map(|ef| ef.ty()).collect();
+        let specified_field_indices: Vec<_> = self
+            .value_fields()
+            .zip(&value_field_indices)
+            .filter(|(ef, _)| ef.lazy_fn.is_none())
+            .map(|(_, index)| index)
+            .collect();
+        let specified_field_names: Vec<_> = self
+            .value_fields()
+            .filter(|ef| ef.lazy_fn.is_none())
+            .map(SalsaField::name)
+            .collect();
         let constructor_name = self.constructor_name();
 
         parse_quote! {
-            impl #ident {
-                pub fn #constructor_name(__db: &#db_dyn_ty, #(#all_field_names: #all_field_tys,)*) -> Self
+            impl #impl_generics #id_ty #where_clause {
+                pub fn #constructor_name(__db: &#db_dyn_ty, #(#constructor_field_names: #constructor_field_tys,)*) -> Self
                 {
                     let (__jar, __runtime) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(__db);
-                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #ident >>::ingredient(__jar);
+                    let __ingredients = <#jar_ty as salsa::storage::HasIngredientsFor< #id_ty >>::ingredient(__jar);
                     let __id = __ingredients.#struct_index.new_struct(__runtime, (#(#id_field_names,)*));
                     #(
-                        __ingredients.#value_field_indices.specify_and_record(__db, __id, #value_field_names);
+                        __ingredients.#specified_field_indices.specify_and_record(__db, __id, #specified_field_names);
                     )*
                     __id
                 }
@@ -176,7 +272,9 @@ impl TrackedStruct {
     /// function ingredient for each of the value fields.
     fn tracked_struct_ingredients(&self, config_structs: &[syn::ItemStruct]) -> syn::ItemImpl {
         use crate::literal;
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
+        let impl_generics = self.impl_generics();
+        let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
         let jar_ty = self.jar_ty();
         let id_field_tys: Vec<&syn::Type> = self.id_fields().map(SalsaField::ty).collect();
         let value_field_indices: Vec<Literal> = self.value_field_indices();
@@ -184,15 +282,21 @@ impl TrackedStruct {
         let config_struct_names = config_structs.iter().map(|s| &s.ident);
         let debug_name_struct = literal(self.id_ident());
         let debug_name_fields: Vec<_> = self.all_field_names().into_iter().map(literal).collect();
+        let push_method = self.routes_push_method();
+        let with_content_ids = if self.has_content_ids() {
+            quote! { .with_content_ids() }
+        } else {
+            quote! {}
+        };
 
         parse_quote! {
-            impl salsa::storage::IngredientsFor for #ident {
+            impl #impl_generics salsa::storage::IngredientsFor for #id_ty #where_clause {
                 type Jar = #jar_ty;
                 type Ingredients = (
                     #(
-                        salsa::function::FunctionIngredient<#config_struct_names>,
+                        salsa::function::FunctionIngredient<#config_struct_names #ty_generics>,
                     )*
-                    salsa::tracked_struct::TrackedStructIngredient<#ident, (#(#id_field_tys,)*)>,
+                    salsa::tracked_struct::TrackedStructIngredient<#id_ty, (#(#id_field_tys,)*)>,
                 );
 
                 fn create_ingredients<DB>(
@@ -204,7 +308,7 @@ impl TrackedStruct {
                     (
                         #(
                             {
-                                let index = routes.push(
+                                let index = routes.#push_method(
                                     |jars| {
                                         let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                                         let ingredients = <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar);
@@ -220,7 +324,7 @@ impl TrackedStruct {
                             },
                         )*
                         {
-                            let index = routes.push(
+                            let index = routes.#push_method(
                                 |jars| {
                                     let jar = <DB as salsa::storage::JarFromJars<Self::Jar>>::jar_from_jars(jars);
                                     let ingredients = <_ as salsa::storage::HasIngredientsFor<Self>>::ingredient(jar);
@@ -232,7 +336,7 @@ impl TrackedStruct {
                                     &mut ingredients.#tracked_struct_index
                                 },
                             );
-                            salsa::tracked_struct::TrackedStructIngredient::new(index, #debug_name_struct)
+                            salsa::tracked_struct::TrackedStructIngredient::new(index, #debug_name_struct)#with_content_ids
                         },
                     )
                 }
@@ -242,17 +346,16 @@ impl TrackedStruct {
 
     /// Implementation of `SalsaStructInDb`.
     fn salsa_struct_in_db_impl(&self) -> syn::ItemImpl {
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
         let jar_ty = self.jar_ty();
+        let generics = self.db_impl_generics(quote!(?Sized + salsa::DbWithJar<#jar_ty>));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
         let tracked_struct_index: Literal = self.tracked_struct_index();
         parse_quote! {
-            impl<DB> salsa::salsa_struct::SalsaStructInDb<DB> for #ident
-            where
-                DB: ?Sized + salsa::DbWithJar<#jar_ty>,
-            {
+            impl #impl_generics salsa::salsa_struct::SalsaStructInDb<DB> for #id_ty #where_clause {
                 fn register_dependent_fn(db: &DB, index: salsa::routes::IngredientIndex) {
                     let (jar, _) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
-                    let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#ident>>::ingredient(jar);
+                    let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#id_ty>>::ingredient(jar);
                     ingredients.#tracked_struct_index.register_dependent_fn(index)
                 }
             }
@@ -261,17 +364,16 @@ impl TrackedStruct {
 
     /// Implementation of `TrackedStructInDb`.
     fn tracked_struct_in_db_impl(&self) -> syn::ItemImpl {
-        let ident = self.id_ident();
+        let id_ty = self.id_ty();
         let jar_ty = self.jar_ty();
+        let generics = self.db_impl_generics(quote!(?Sized + salsa::DbWithJar<#jar_ty>));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
         let tracked_struct_index = self.tracked_struct_index();
         parse_quote! {
-            impl<DB> salsa::tracked_struct::TrackedStructInDb<DB> for #ident
-            where
-                DB: ?Sized + salsa::DbWithJar<#jar_ty>,
-            {
+            impl #impl_generics salsa::tracked_struct::TrackedStructInDb<DB> for #id_ty #where_clause {
                 fn database_key_index(self, db: &DB) -> salsa::DatabaseKeyIndex {
                     let (jar, _) = <_ as salsa::storage::HasJar<#jar_ty>>::jar(db);
-                    let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#ident>>::ingredient(jar);
+                    let ingredients = <#jar_ty as salsa::storage::HasIngredientsFor<#id_ty>>::ingredient(jar);
                     ingredients.#tracked_struct_index.database_key_index(self)
                 }
             }
@@ -1,15 +1,16 @@
 //! Basic test of accumulator functionality.
 
-use std::fmt;
+use std::{fmt, hash::Hash};
 
 use crate::{
     cycle::CycleRecoveryStrategy,
-    hash::FxDashMap,
-    ingredient::{fmt_index, Ingredient, IngredientRequiresReset},
+    hash::{FxDashMap, FxHashSet},
+    ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions},
     key::DependencyIndex,
     runtime::local_state::QueryOrigin,
     storage::HasJar,
-    DatabaseKeyIndex, Event, EventKind, IngredientIndex, Revision, Runtime,
+    usage::{EvictionCandidate, IngredientUsage},
+    DatabaseKeyIndex, Durability, Event, EventKind, IngredientIndex, Revision, Runtime,
 };
 
 pub trait Accumulator {
@@ -19,7 +20,21 @@ pub trait Accumulator {
     fn accumulator_ingredient<Db>(db: &Db) -> &AccumulatorIngredient<Self::Data>
     where
         Db: ?Sized + HasJar<Self::Jar>;
+
+    /// Called on the values collected by `accumulated`, in the order they
+    /// were pushed, before they are returned. The `dedup` and `sort_by_key`
+    /// options of `#[salsa::accumulator]` generate this.
+    fn finish_accumulated(values: &mut Vec<Self::Data>) {
+        let _ = values;
+    }
+}
+
+/// Removes the values that are equal to an earlier one, keeping the rest in order.
+pub fn dedup<Data: Clone + Eq + Hash>(values: &mut Vec<Data>) {
+    let mut seen = FxHashSet::default();
+    values.retain(|value| seen.insert(value.clone()));
 }
+
 pub struct AccumulatorIngredient<Data: Clone> {
     index: IngredientIndex,
     map: FxDashMap<DatabaseKeyIndex, AccumulatedValues<Data>>,
@@ -73,11 +88,12 @@ impl<Data: Clone> AccumulatorIngredient<Data> {
         accumulated_values.values.push(value);
     }
 
+    /// Passes the values pushed by `query` in the current revision, if any, to `output`.
     pub(crate) fn produced_by(
         &self,
         runtime: &Runtime,
         query: DatabaseKeyIndex,
-        output: &mut Vec<Data>,
+        output: impl FnOnce(&[Data]),
     ) {
         let current_revision = runtime.current_revision();
         if let Some(v) = self.map.get(&query) {
@@ -92,7 +108,7 @@ impl<Data: Clone> AccumulatorIngredient<Data> {
             } = v.value();
 
             if *produced_at == current_revision {
-                output.extend(values.iter().cloned());
+                output(values);
             }
         }
     }
@@ -115,6 +131,10 @@ where
         None
     }
 
+    fn value_revisions(&self, _key_index: crate::Id) -> Option<ValueRevisions> {
+        None
+    }
+
     fn mark_validated_output(
         &self,
         db: &DB,
@@ -155,9 +175,67 @@ where
         panic!("unexpected call: accumulator is not registered as a dependent fn");
     }
 
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys_changed_since(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys(&self) -> Vec<crate::Id> {
+        // The values are those of the queries that accumulated them.
+        vec![]
+    }
+
+    fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        // Values accumulated by a query are only needed as long as its memo is.
+        let len = self.map.len();
+        self.map.retain(|query, _| is_live(*query));
+        len - self.map.len()
+    }
+
+    fn synthetic_write(
+        &mut self,
+        _key_index: Option<crate::Id>,
+        _revision: Revision,
+    ) -> Option<Durability> {
+        // Accumulated values change when the query that pushed them is re-executed.
+        None
+    }
+
+    fn evict_values(&mut self, _key_index: Option<crate::Id>) -> usize {
+        // Accumulated values cannot be recomputed without their query.
+        0
+    }
+
+    fn compact(&mut self) {
+        self.map.shrink_to_fit();
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        self.map
+            .iter()
+            .map(|entry| IngredientUsage {
+                entries: entry.values.len(),
+                bytes: std::mem::size_of::<(DatabaseKeyIndex, AccumulatedValues<Data>)>()
+                    + entry.values.capacity() * std::mem::size_of::<Data>(),
+                heap_bytes: 0,
+            })
+            .fold(IngredientUsage::default(), |sum, usage| sum + usage)
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
 }
 
 impl<Data> IngredientRequiresReset for AccumulatorIngredient<Data>
@@ -0,0 +1,89 @@
+//! Attaching a database to the current thread, so that the `Debug` impls
+//! of salsa structs can print their fields, and so that those given the
+//! `serialize` option can be serialized and deserialized.
+
+use std::{
+    any::{Any, TypeId},
+    cell::Cell,
+    ptr::NonNull,
+};
+
+use crate::{jar::Jar, Database};
+
+thread_local! {
+    static ATTACHED: Cell<Option<NonNull<dyn Database>>> = const { Cell::new(None) };
+}
+
+/// Runs `op` with `db` attached to the current thread. Within `op`, the ordinary
+/// `Debug` impls of salsa structs from the jars of `db` print all their fields,
+/// like [`crate::DebugWithDb::debug_all`], which makes `dbg!` and panic messages
+/// useful. Outside of `attach`, they only print the id.
+///
+/// The salsa structs with the `serialize` option serialize their fields, read from
+/// `db`, and are deserialized into `db`: interned structs are interned again,
+/// inputs are created, and tracked structs are created by the active query.
+/// Outside of `attach`, (de)serializing them fails.
+///
+/// Like `debug_all`, this reads every field of the structs that are printed, so
+/// printing them within a tracked function can add dependencies to it.
+///
+/// Calls to `attach` can be nested; the innermost database is the one used.
+pub fn attach<Db, R>(db: &Db, op: impl FnOnce() -> R) -> R
+where
+    Db: ?Sized + Database,
+{
+    let db: NonNull<dyn Database + '_> = NonNull::from(db.as_salsa_database());
+    // SAFETY: The pointer is only dereferenced while `op` runs (see
+    // `with_attached_jar_db`), during which `db` is borrowed, and the
+    // guard detaches it once `op` returns or unwinds.
+    let db: NonNull<dyn Database> = unsafe { std::mem::transmute(db) };
+    let _guard = AttachGuard {
+        previous: ATTACHED.with(|attached| attached.replace(Some(db))),
+    };
+    op()
+}
+
+struct AttachGuard {
+    previous: Option<NonNull<dyn Database>>,
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        ATTACHED.with(|attached| attached.set(self.previous));
+    }
+}
+
+/// Invokes `op` with the `DynDb` of the jar `J` of the attached database, if there
+/// is one and it has that jar. Used by the generated `Debug` impls of salsa structs.
+#[doc(hidden)]
+pub fn with_attached_jar_db<J, R>(op: impl FnOnce(&<J as Jar<'static>>::DynDb) -> R) -> Option<R>
+where
+    J: for<'db> Jar<'db> + 'static,
+{
+    let db = ATTACHED.with(|attached| attached.get())?;
+    // SAFETY: See `attach`, which keeps the database alive while it is attached.
+    let db = unsafe { db.as_ref() };
+    let jar_db = db.jar_db(TypeId::of::<J>())?;
+    let jar_db = jar_db.downcast_ref::<JarDb<J>>()?;
+    // SAFETY: The jar's `DynDb` borrows from the attached database.
+    Some(op(unsafe { &*jar_db.0 }))
+}
+
+/// The `DynDb` of the jar `J` of some database, with its lifetime erased.
+/// Returned (as `dyn Any`) by [`crate::storage::HasJarsDyn::jar_db`].
+#[doc(hidden)]
+pub struct JarDb<J>(*const <J as Jar<'static>>::DynDb)
+where
+    J: Jar<'static>;
+
+impl<J> JarDb<J>
+where
+    J: for<'db> Jar<'db> + 'static,
+{
+    pub fn boxed<'db>(db: &'db <J as Jar<'db>>::DynDb) -> Box<dyn Any> {
+        let db: *const <J as Jar<'db>>::DynDb = db;
+        // SAFETY: The two pointer types only differ in lifetimes.
+        let db: *const <J as Jar<'static>>::DynDb = unsafe { std::mem::transmute_copy(&db) };
+        Box::new(JarDb::<J>(db))
+    }
+}
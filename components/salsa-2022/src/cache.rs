@@ -0,0 +1,119 @@
+//! Sharing the values of tracked functions between databases, e.g., between the
+//! machines of a CI fleet, through a [`CacheBackend`].
+//!
+//! A tracked function with the `cache = <path>` option names a function that takes
+//! the same arguments and returns the [`Fingerprint`] of what the value depends on
+//! (e.g., of the text of the files it reads). When a backend is set with
+//! [`crate::Storage::set_cache_backend`], executing the function first looks for
+//! a value stored at that fingerprint, and stores the value it computes otherwise.
+//!
+//! A value found in the cache depends on what the fingerprint function read, and
+//! on nothing else: that function must read everything the value depends on. The
+//! value must not contain ids (of inputs, interned or tracked structs), which are
+//! not the same in another database, and the function must not have outputs.
+//!
+//! The values are stored at the fingerprint combined with that of the function
+//! itself (see [`crate::function::Configuration::QUERY_FINGERPRINT`]), so that
+//! a machine running another version of the function does not find them.
+
+use std::{fmt, io, io::Write as _, sync::Arc};
+
+use parking_lot::RwLock;
+#[cfg(feature = "persist")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// A fingerprint of what the value of a tracked function depends on, the same on
+/// every machine running the same code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
+pub struct Fingerprint(pub u128);
+
+impl Fingerprint {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    /// Fingerprints the serialization of `value` (with FNV-1a), which, unlike its
+    /// `Hash` impl, does not depend on the platform or on the version of Rust.
+    #[cfg(feature = "persist")]
+    pub fn of<T: ?Sized + Serialize>(value: &T) -> Fingerprint {
+        let mut hasher = Fnv(Self::OFFSET_BASIS);
+        serde_json::to_writer(&mut hasher, value).expect("value cannot be serialized");
+        Fingerprint(hasher.0)
+    }
+
+    /// Combines this fingerprint with `other`, e.g., of another input the value depends on.
+    pub fn combine(self, other: Fingerprint) -> Fingerprint {
+        // The serialization of the pair, so that this is `Fingerprint::of(&(self, other))`.
+        let mut hasher = Fnv(Self::OFFSET_BASIS);
+        write!(hasher, "[{},{}]", self.0, other.0).expect("writing to a hasher cannot fail");
+        Fingerprint(hasher.0)
+    }
+}
+
+/// Computes the FNV-1a hash of what is written to it.
+struct Fnv(u128);
+
+impl io::Write for Fnv {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u128::from(byte)).wrapping_mul(Fingerprint::PRIME);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Stores the encoded values of tracked functions, by function and fingerprint,
+/// e.g., in a directory shared by several machines or on a server.
+///
+/// Failures (e.g., of the network) are best reported as misses: the value is
+/// then computed.
+pub trait CacheBackend: Send + Sync {
+    /// Returns the value stored for the function named `query` at `fingerprint`, if any.
+    fn get(&self, query: &str, fingerprint: Fingerprint) -> Option<Vec<u8>>;
+
+    /// Stores `value` for the function named `query` at `fingerprint`.
+    fn put(&self, query: &str, fingerprint: Fingerprint, value: Vec<u8>);
+}
+
+/// The backend set by [`crate::Storage::set_cache_backend`], shared by a runtime
+/// and all of its snapshots.
+#[derive(Default)]
+pub(crate) struct CacheBackendSlot(RwLock<Option<Arc<dyn CacheBackend>>>);
+
+impl CacheBackendSlot {
+    pub(crate) fn get(&self) -> Option<Arc<dyn CacheBackend>> {
+        self.0.read().clone()
+    }
+
+    pub(crate) fn set(&self, backend: Option<Arc<dyn CacheBackend>>) {
+        *self.0.write() = backend;
+    }
+}
+
+impl fmt::Debug for CacheBackendSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CacheBackendSlot")
+            .field(&self.0.read().is_some())
+            .finish()
+    }
+}
+
+/// Encodes the value of a tracked function with the `cache` option. Invoked by
+/// the generated code so as to give a better error message.
+#[doc(hidden)]
+#[cfg(feature = "persist")]
+pub fn encode<V: Serialize>(value: &V) -> Option<Vec<u8>> {
+    serde_json::to_vec(value).ok()
+}
+
+/// Decodes a value encoded by [`encode`], or returns `None` if it cannot be
+/// (e.g., because it was stored by another version of the program).
+#[doc(hidden)]
+#[cfg(feature = "persist")]
+pub fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Option<V> {
+    serde_json::from_slice(bytes).ok()
+}
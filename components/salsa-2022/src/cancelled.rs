@@ -1,14 +1,27 @@
 use std::{
     fmt,
     panic::{self, UnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
+use parking_lot::Mutex;
+
 /// A panic payload indicating that execution of a salsa query was cancelled.
 ///
 /// This can occur for a few reasons:
-/// *
-/// *
-/// *
+/// * another handle is about to write to the database ([`Cancelled::PendingWrite`]);
+/// * the work of this handle was cancelled through its [`CancellationHandle`]
+///   ([`Cancelled::Requested`]);
+/// * the handle ran past the deadline set with [`CancellationHandle::set_deadline`]
+///   ([`Cancelled::Timeout`]);
+/// * the query waited on a thread that panicked ([`Cancelled::PropagatedPanic`]).
+///
+/// Use [`Cancelled::catch`] to turn the unwind back into a value, e.g., to answer
+/// a request with "cancelled" rather than crashing the thread serving it.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Cancelled {
@@ -16,7 +29,18 @@ pub enum Cancelled {
     #[non_exhaustive]
     PendingWrite,
 
-    /// The query was blocked on another thread, and that thread panicked.
+    /// [`CancellationHandle::cancel`] was called for the handle running the query.
+    #[non_exhaustive]
+    Requested,
+
+    /// The deadline of the handle running the query passed; see
+    /// [`CancellationHandle::set_deadline`].
+    #[non_exhaustive]
+    Timeout,
+
+    /// The query was blocked on another thread, and that thread panicked
+    /// other than in the query itself (for which [`crate::QueryPanicked`] is raised),
+    /// e.g., because of a cycle.
     #[non_exhaustive]
     PropagatedPanic,
 }
@@ -41,17 +65,131 @@ impl Cancelled {
             },
         }
     }
+
+    /// A short description of why execution was cancelled, e.g., "pending write".
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Cancelled::PendingWrite => "pending write",
+            Cancelled::Requested => "cancellation request",
+            Cancelled::Timeout => "timeout",
+            Cancelled::PropagatedPanic => "propagated panic",
+        }
+    }
 }
 
 impl std::fmt::Display for Cancelled {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let why = match self {
-            Cancelled::PendingWrite => "pending write",
-            Cancelled::PropagatedPanic => "propagated panic",
-        };
         f.write_str("cancelled because of ")?;
-        f.write_str(why)
+        f.write_str(self.reason())
     }
 }
 
 impl std::error::Error for Cancelled {}
+
+/// Cancels the queries running on one database handle, and on the forks made
+/// for them by [`crate::par_map`], from any thread. Obtained with
+/// [`crate::Database::cancellation_handle`].
+///
+/// Unlike a write, this leaves the other handles alone: a server can give each
+/// request its own snapshot and cancel just the requests the client gave up on.
+/// The queries unwind with [`Cancelled::Requested`] the next time they check for
+/// cancellation, and so does any query started on the handle afterwards, until
+/// the next revision begins. The handle can also bound the time the work may
+/// take, see [`CancellationHandle::set_deadline`].
+#[derive(Clone, Debug, Default)]
+pub struct CancellationHandle {
+    state: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    requested: AtomicBool,
+
+    /// Whether `deadline` is set, so that checking for cancellation need not
+    /// take the lock (or read the clock) otherwise.
+    has_deadline: AtomicBool,
+    deadline: Mutex<Option<Instant>>,
+
+    /// Whether `anchored_until` is set, like `has_deadline`.
+    has_anchor: AtomicBool,
+    anchored_until: Mutex<Option<Instant>>,
+}
+
+impl CancellationHandle {
+    /// Requests cancellation of the queries running on the handle.
+    pub fn cancel(&self) {
+        self.state.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`CancellationHandle::cancel`] was called since the current revision began.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.requested.load(Ordering::SeqCst)
+    }
+
+    /// Makes the queries running on the handle unwind with [`Cancelled::Timeout`]
+    /// once `deadline` has passed, the next time they check for cancellation.
+    /// Unlike [`CancellationHandle::cancel`], the deadline stays in effect across
+    /// revisions, until it is replaced or cleared with `None`.
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        let mut guard = self.state.deadline.lock();
+        *guard = deadline;
+        self.state
+            .has_deadline
+            .store(deadline.is_some(), Ordering::SeqCst);
+    }
+
+    /// Sets the deadline to `timeout` from now; see [`CancellationHandle::set_deadline`].
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.set_deadline(Some(Instant::now() + timeout));
+    }
+
+    /// The deadline set with [`CancellationHandle::set_deadline`], if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        *self.state.deadline.lock()
+    }
+
+    /// Exempts the queries running on the handle from the cancellation caused by
+    /// writes until `until` (or lifts the exemption with `None`): a write then waits
+    /// for them to complete instead, whatever the [`crate::WritePolicy`], so that
+    /// work that is useless unless finished (e.g., formatting a file on save) is
+    /// not thrown away. Once `until` has passed, the queries unwind with
+    /// [`Cancelled::PendingWrite`] as usual, the next time they check for
+    /// cancellation. See [`crate::Snapshot::anchored`].
+    pub fn set_anchor(&self, until: Option<Instant>) {
+        let mut guard = self.state.anchored_until.lock();
+        *guard = until;
+        self.state
+            .has_anchor
+            .store(until.is_some(), Ordering::SeqCst);
+    }
+
+    /// Whether the queries running on the handle are exempt from the cancellation
+    /// caused by writes right now; see [`CancellationHandle::set_anchor`].
+    pub fn is_anchored(&self) -> bool {
+        self.state.has_anchor.load(Ordering::Relaxed)
+            && self
+                .state
+                .anchored_until
+                .lock()
+                .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Why the queries of the handle must unwind, if they must.
+    pub(crate) fn check(&self) -> Option<Cancelled> {
+        if self.is_cancelled() {
+            return Some(Cancelled::Requested);
+        }
+        if self.state.has_deadline.load(Ordering::Relaxed) {
+            if let Some(deadline) = self.deadline() {
+                if Instant::now() >= deadline {
+                    return Some(Cancelled::Timeout);
+                }
+            }
+        }
+        None
+    }
+
+    pub(crate) fn reset(&self) {
+        self.state.requested.store(false, Ordering::SeqCst);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::debug::DebugWithDb;
-use crate::{key::DatabaseKeyIndex, Database};
+use crate::{key::DatabaseKeyIndex, runtime::RuntimeId, Database};
 use std::{panic::AssertUnwindSafe, sync::Arc};
 
 /// Captures the participants of a cycle that occurred when executing a query.
@@ -20,13 +20,30 @@ use std::{panic::AssertUnwindSafe, sync::Arc};
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Cycle {
     participants: CycleParticipants,
+
+    /// The runtime executing each participant: several of them if the
+    /// cycle spans multiple threads.
+    runtime_ids: Arc<Vec<RuntimeId>>,
+
+    /// The index in `participants` of the query whose re-entry closed the cycle.
+    entered_first: usize,
 }
 
 pub(crate) type CycleParticipants = Arc<Vec<DatabaseKeyIndex>>;
 
 impl Cycle {
-    pub(crate) fn new(participants: CycleParticipants) -> Self {
-        Self { participants }
+    pub(crate) fn new(
+        participants: CycleParticipants,
+        runtime_ids: Arc<Vec<RuntimeId>>,
+        entered_first: usize,
+    ) -> Self {
+        debug_assert_eq!(participants.len(), runtime_ids.len());
+        debug_assert!(entered_first < participants.len());
+        Self {
+            participants,
+            runtime_ids,
+            entered_first,
+        }
     }
 
     /// True if two `Cycle` values represent the same cycle.
@@ -39,7 +56,9 @@ impl Cycle {
         std::panic::resume_unwind(Box::new(self))
     }
 
-    pub(crate) fn catch<T>(execute: impl FnOnce() -> T) -> Result<T, Cycle> {
+    /// Runs `execute`, and catches the `Cycle` raised by a cycle that was not
+    /// recovered from, e.g., to report it as a "recursive definition" error.
+    pub fn catch<T>(execute: impl FnOnce() -> T) -> Result<T, Cycle> {
         match std::panic::catch_unwind(AssertUnwindSafe(execute)) {
             Ok(v) => Ok(v),
             Err(err) => match err.downcast::<Cycle>() {
@@ -57,6 +76,36 @@ impl Cycle {
         self.participants.iter().copied()
     }
 
+    /// Like [`Cycle::participant_keys`], but in the order the queries were entered:
+    /// starting with the query that was re-entered, each participant called the next
+    /// one, and the last called the first.
+    pub fn participant_keys_in_entry_order(&self) -> impl Iterator<Item = DatabaseKeyIndex> + '_ {
+        let (before, after) = self.participants.split_at(self.entered_first);
+        after.iter().chain(before).copied()
+    }
+
+    /// Like [`Cycle::participant_keys`], but along with the id of the runtime
+    /// (i.e., of the database handle, and hence thread) executing each query.
+    /// A cycle spanning several threads would otherwise be a deadlock.
+    pub fn participant_keys_and_runtimes(
+        &self,
+    ) -> impl Iterator<Item = (DatabaseKeyIndex, RuntimeId)> + '_ {
+        self.participant_keys()
+            .zip(self.runtime_ids.iter().copied())
+    }
+
+    /// The distinct runtimes executing the participants in the cycle,
+    /// in the order they first appear.
+    pub fn runtimes(&self) -> Vec<RuntimeId> {
+        let mut runtimes: Vec<RuntimeId> = vec![];
+        for &runtime_id in self.runtime_ids.iter() {
+            if !runtimes.contains(&runtime_id) {
+                runtimes.push(runtime_id);
+            }
+        }
+        runtimes
+    }
+
     /// Returns a vector with the debug information for
     /// all the participants in the cycle.
     pub fn all_participants<DB: ?Sized + Database>(&self, db: &DB) -> Vec<String> {
@@ -71,7 +120,7 @@ impl Cycle {
     pub fn unexpected_participants<DB: ?Sized + Database>(&self, db: &DB) -> Vec<String> {
         self.participant_keys()
             .filter(|&d| {
-                db.cycle_recovery_strategy(d.ingredient_index) == CycleRecoveryStrategy::Panic
+                db.cycle_recovery_strategy(d.ingredient_index) != CycleRecoveryStrategy::Fallback
             })
             .map(|d| format!("{:?}", d.debug(db)))
             .collect()
@@ -92,6 +141,7 @@ impl Cycle {
                         "unexpected_participants",
                         &self.c.unexpected_participants(self.db),
                     )
+                    .field("runtimes", &self.c.runtimes())
                     .finish()
             }
         }
@@ -114,7 +164,7 @@ pub enum CycleRecoveryStrategy {
     /// strategies.
     ///
     /// In the case of a failure due to a cycle, the panic
-    /// value will be XXX (FIXME).
+    /// value is the [`Cycle`], listing its participants.
     Panic,
 
     /// Recovers from cycles by storing a sentinel value.
@@ -122,4 +172,44 @@ pub enum CycleRecoveryStrategy {
     /// This value is computed by the `QueryFunction::cycle_fallback`
     /// function.
     Fallback,
+
+    /// Recovers from cycles by iterating to a fixed point.
+    ///
+    /// When the query is re-entered during its own execution, the
+    /// re-entrant call returns a provisional value (initially computed by
+    /// the `cycle_initial` function, afterwards the result of the previous
+    /// iteration). The query is then re-executed until its result no longer
+    /// changes, or until the `cycle_fn` function decides to stop.
+    ///
+    /// This strategy is needed by the query that gets re-entered, i.e., by each
+    /// query in the cycle that may be called first; the other queries in the
+    /// cycle are simply re-executed on each iteration.
+    /// Cycles that span multiple threads are treated as `Panic`.
+    FixedPoint,
+}
+
+/// The default maximum number of times a query using [`CycleRecoveryStrategy::FixedPoint`]
+/// is executed before salsa gives up and panics; the `cycle_max_iterations` option
+/// of tracked functions sets another one.
+pub const DEFAULT_MAX_ITERATIONS: u32 = 200;
+
+/// Returned by the `cycle_fn` of a query using [`CycleRecoveryStrategy::FixedPoint`]
+/// to decide what happens after an iteration that did not reach a fixed point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CycleRecoveryAction<T> {
+    /// Execute the query again, using the value just computed as the
+    /// provisional value.
+    Iterate,
+
+    /// Stop iterating and use the given value as the result of the query.
+    Fallback(T),
+}
+
+/// Identifies the iteration of a fixed-point query whose provisional
+/// value was (directly or indirectly) read when computing a memo.
+/// Such a memo can only be reused during that same iteration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CycleHead {
+    pub(crate) database_key_index: DatabaseKeyIndex,
+    pub(crate) iteration: u32,
 }
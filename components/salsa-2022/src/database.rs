@@ -1,4 +1,24 @@
-use crate::{storage::HasJarsDyn, DebugWithDb, Durability, Event};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "persist")]
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    cancelled::CancellationHandle,
+    frozen::Frozen,
+    key::{DatabaseKeyIndex, DependencyIndex, KeyDescription},
+    statistics::{self, EdgeCost, QueryStatistics},
+    storage::HasJarsDyn,
+    subscription::Subscription,
+    usage::StorageUsage,
+    DebugWithDb, Durability, Event, Revision,
+};
+#[cfg(feature = "persist")]
+use crate::{
+    persist::{PersistError, PersistedDatabase},
+    record::{RecordedRevision, Recording},
+};
 
 pub trait Database: HasJarsDyn + AsSalsaDatabase {
     /// This function is invoked at key points in the salsa
@@ -23,6 +43,70 @@ pub trait Database: HasJarsDyn + AsSalsaDatabase {
         self.runtime_mut().report_tracked_write(durability);
     }
 
+    /// A synthetic write of a single value, `index`, which is a
+    /// [`crate::DatabaseKeyIndex`] (e.g., from the `database_key` function of
+    /// a tracked function), or an [`crate::IngredientIndex`] for all the values of
+    /// an ingredient. Only the memoized values affected, and those depending on
+    /// them, are re-executed. See [`crate::Storage::synthetic_write_for`].
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this triggers cancellation.
+    fn synthetic_write_for(&mut self, index: impl Into<DependencyIndex>)
+    where
+        Self: Sized,
+    {
+        self.synthetic_write_for_jars(index.into());
+    }
+
+    /// Starts unwinding the stack, with a [`crate::Cancelled`] payload, if the
+    /// current revision is cancelled or cancellation of this handle was requested.
+    ///
+    /// This method can be called by query implementations that perform
+    /// potentially expensive computations, in order to speed up propagation of
+    /// cancellation. It is safe to call from inside tracked functions (e.g., once
+    /// per item of a long loop): unless it unwinds, it adds no dependency to the
+    /// calling query. A query that unwinds memoizes nothing.
+    ///
+    /// Cancellation will automatically be triggered by salsa on any query
+    /// invocation.
+    ///
+    /// This method should not be overridden by `Database` implementors. A
+    /// `salsa_event` is emitted when this method is called, so that should be
+    /// used instead.
+    fn unwind_if_cancelled(&self) {
+        self.runtime().unwind_if_revision_cancelled(self);
+    }
+
+    /// Whether another handle is waiting to write to the database, and so for
+    /// the snapshots to be dropped. Long-running work can poll this to stop early
+    /// (e.g., between the items it processes) rather than at its next query.
+    ///
+    /// A tracked function that returns early this way should call
+    /// [`Database::report_untracked_read`] first, or its partial result may be
+    /// reused in the next revision; [`Database::unwind_if_cancelled`] does so for you.
+    fn is_write_pending(&self) -> bool {
+        self.runtime().is_write_pending()
+    }
+
+    /// Returns a handle that cancels the queries running on this database handle
+    /// (and on its forks). Take it from a snapshot before sending the snapshot to
+    /// the thread serving a request, so that the request can be cancelled from
+    /// elsewhere; its queries then unwind with [`crate::Cancelled::Requested`]. The
+    /// handle can also give the work a time budget, after which they unwind with
+    /// [`crate::Cancelled::Timeout`].
+    fn cancellation_handle(&self) -> CancellationHandle {
+        self.runtime().cancellation_handle()
+    }
+
+    /// Returns the queries executing on this database handle, from the outermost
+    /// to the innermost one, e.g., to report what was being computed when an
+    /// error occurred. Each key can be shown with its debug name, such as
+    /// `area_circle(0)`, with [`crate::DebugWithDb::debug`].
+    ///
+    /// The queries that forked this handle (see [`crate::par_map`]) are not included.
+    fn active_query_stack(&self) -> Vec<DatabaseKeyIndex> {
+        self.runtime().active_query_stack()
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -30,6 +114,272 @@ pub trait Database: HasJarsDyn + AsSalsaDatabase {
     fn report_untracked_read(&self) {
         self.runtime().report_untracked_read();
     }
+
+    /// Reports that the query depends on some state unknown to salsa, which
+    /// changes only as often as inputs of durability `durability` (for example,
+    /// files of the standard library, which are not expected to change).
+    ///
+    /// The query is re-executed only after a new revision in which an input
+    /// of that durability, or a lower one, has changed.
+    fn report_synthetic_read(&self, durability: Durability) {
+        self.runtime().report_synthetic_read(durability);
+    }
+
+    /// Reports that the value the query is computing is the same as its previous
+    /// one, even if it is not equal to it (e.g., it only differs in spans that moved).
+    /// The value is then backdated as if it were equal, and the queries that read
+    /// the previous value are not re-executed for it.
+    ///
+    /// Has no effect outside of a query, or when the query had no previous value, and
+    /// the value still changes if it became less durable (see `constant_to_non_constant`).
+    fn report_unchanged(&self) {
+        self.runtime().report_unchanged();
+    }
+
+    /// Runs `edit`, which typically sets several inputs, as a single change: the
+    /// writes share one revision (and so cause only one round of cancellation
+    /// and revalidation) rather than each starting a revision of its own.
+    ///
+    /// If `edit` reads a tracked function (fetches it, reads what it accumulated,
+    /// or checks it with `has_changed_since`), what it saw must not be
+    /// invalidated by a later write in the same revision, so the next write
+    /// starts a new revision after all.
+    fn transaction<R>(&mut self, edit: impl FnOnce(&mut Self) -> R) -> R
+    where
+        Self: Sized,
+    {
+        self.begin_transaction();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| edit(self)));
+        self.end_transaction();
+        match result {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    /// Runs `what_if`, which typically sets inputs and fetches tracked functions, and
+    /// then discards its writes, giving the inputs their previous values back (e.g., to
+    /// preview the effect of a quick-fix without cloning the database). Memoized values
+    /// that do not depend on the discarded writes stay valid; the others are
+    /// recomputed when next fetched, and are then usually backdated.
+    ///
+    /// The input fields set in `what_if` must have types that implement `Clone`, and
+    /// input maps cannot be written. Inputs created in `what_if` are deleted after it
+    /// (so reading them panics), and its writes are left out of recordings (see
+    /// [`crate::record`]).
+    fn speculate<R>(&mut self, what_if: impl FnOnce(&mut Self) -> R) -> R
+    where
+        Self: Sized,
+    {
+        self.begin_speculation();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| what_if(self)));
+        self.end_speculation();
+        match result {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    /// Returns how often each memoized function has been executed, validated,
+    /// and backdated since this database was created (or since the last call to
+    /// [`Database::reset_query_statistics`]), and how long threads blocked on each
+    /// value another thread was computing. Work done on snapshots is included.
+    fn query_statistics(&self) -> QueryStatistics {
+        self.runtime().statistics().snapshot()
+    }
+
+    /// Resets all counts reported by [`Database::query_statistics`] to zero,
+    /// e.g., to measure the work caused by a single edit.
+    fn reset_query_statistics(&self) {
+        self.runtime().statistics().reset();
+    }
+
+    /// Returns the (at most) `n` most expensive dependency edges under `root`, the
+    /// edges from `root` to its inputs and, transitively, from those to theirs, as
+    /// recorded by the last revalidation of each query while edge profiling was
+    /// enabled (see [`crate::Storage::set_edge_profiling`]). The edges are ranked
+    /// by the time spent on the input itself, rather than on the inputs of the
+    /// input, so that the first one names the dependency that made verifying
+    /// `root` slow; see [`EdgeCost`].
+    fn most_expensive_edges(&self, root: DatabaseKeyIndex, n: usize) -> Vec<EdgeCost> {
+        statistics::most_expensive_edges(self, root, n)
+    }
+
+    /// Drops the memoized value at `index`, a [`crate::DatabaseKeyIndex`], or every
+    /// value of an ingredient, given its [`crate::IngredientIndex`], while keeping
+    /// their dependencies. Returns how many values were dropped.
+    /// See [`crate::Storage::evict_values`].
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this blocks until
+    /// all snapshots are dropped.
+    fn evict_values(&mut self, index: impl Into<DependencyIndex>) -> usize
+    where
+        Self: Sized,
+    {
+        self.evict_values_of_jars(index.into())
+    }
+
+    /// Returns how many values each ingredient of this database stores, and about
+    /// how much memory they take. See [`crate::usage`].
+    fn storage_usage(&self) -> StorageUsage {
+        self.usage_of_jars()
+    }
+
+    /// The current revision of the database. It lets [`Database::keys_changed_since`]
+    /// tell what changed after this point, e.g., because of a batch of edits.
+    fn current_revision(&self) -> Revision {
+        self.runtime().current_revision()
+    }
+
+    /// The last revision in which an input of durability `durability` (or one
+    /// above it) changed. A value computed from inputs of at least that durability
+    /// is still up to date as long as this does not move, which lets caches
+    /// outside of salsa skip re-querying it; see [`crate::EventKind::DidStartRevision`].
+    fn last_changed_revision(&self, durability: Durability) -> Revision {
+        self.runtime().last_changed_revision(durability)
+    }
+
+    /// Resolves `key` into its name, durability and last change, e.g., to report
+    /// the keys of [`Event`]s or [`crate::statistics::QueryStatistics`] elsewhere.
+    fn describe_key(&self, key: DatabaseKeyIndex) -> KeyDescription {
+        let revisions = self.value_revisions(key);
+        KeyDescription {
+            ingredient_name: self.ingredient_debug_name(key.ingredient_index()),
+            key_debug: format!("{:?}", key.debug(self)),
+            durability: revisions.map(|r| r.durability),
+            changed_at: revisions.map(|r| r.changed_at),
+        }
+    }
+
+    /// Returns whether the value at `key` may have changed after `revision`, without
+    /// returning it, e.g., to tell whether an output built from it is still fresh.
+    /// Like fetching it, this may re-execute the queries it depends on (and `key`
+    /// itself) to find out; a value never memoized is reported as changed.
+    fn has_changed_since(&self, key: DatabaseKeyIndex, revision: Revision) -> bool {
+        self.runtime().report_revision_observed();
+        self.maybe_changed_after(key.into(), revision)
+    }
+
+    /// Subscribes to the value at `key`, typically one a client displays (e.g., the
+    /// diagnostics of an open file): the subscription receives a notification
+    /// each time [`Database::notify_subscribers`] finds out that the value may
+    /// have changed since the client last knew it, so that the client need not
+    /// fetch it again after each revision. Subscribe once the value was fetched,
+    /// as a value never memoized is reported as changed.
+    fn subscribe(&self, key: DatabaseKeyIndex) -> Subscription {
+        let runtime = self.runtime();
+        runtime
+            .subscribers()
+            .subscribe(key, runtime.current_revision())
+    }
+
+    /// Notifies the subscriptions (see [`Database::subscribe`]) whose value may
+    /// have changed since the last revision in which they were notified, or in
+    /// which they were taken, and returns how many were notified. Call it after
+    /// setting inputs, e.g., from a snapshot on another thread: like
+    /// [`Database::has_changed_since`], this verifies each value, which may
+    /// execute the queries it depends on again, so that a value whose inputs
+    /// changed but which turns out the same is not notified.
+    ///
+    /// If the verification is cancelled, the subscriptions not verified yet are
+    /// notified by the next call.
+    fn notify_subscribers(&self) -> usize {
+        self.runtime().subscribers().notify(self)
+    }
+
+    /// Returns the input fields and memoized values that changed after `revision`.
+    /// Memoized values are only included if they were fetched since they changed.
+    /// See [`crate::Storage::keys_changed_since`].
+    fn keys_changed_since(&self, revision: Revision) -> Vec<DatabaseKeyIndex> {
+        self.keys_changed_since_of_jars(revision)
+    }
+
+    /// Limits the heap memory owned by memoized values to about `budget` bytes,
+    /// evicting the values used least recently when it is exceeded at the start
+    /// of a revision. Returns how many values were evicted right away.
+    /// See [`crate::Storage::set_memory_budget`].
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this blocks until
+    /// all snapshots are dropped.
+    fn set_memory_budget(&mut self, budget: Option<usize>) -> usize {
+        self.set_memory_budget_of_jars(budget)
+    }
+
+    /// Discards memoized values, tracked structs, and interned values that were
+    /// not used in the current revision, returning how many were discarded.
+    /// See [`crate::Storage::sweep`] for what counts as "used".
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this blocks until
+    /// all snapshots are dropped.
+    fn sweep(&mut self) -> usize {
+        self.sweep_jars()
+    }
+
+    /// Shrinks the tables of the database to the values it stores now, e.g., after
+    /// a [`Database::sweep`]. See [`crate::Storage::compact`].
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this blocks until
+    /// all snapshots are dropped.
+    fn compact(&mut self) {
+        self.compact_jars()
+    }
+
+    /// Checks every value validated without executing its function against a
+    /// new execution, panicking if they differ; see
+    /// [`crate::Storage::set_shadow_verification`]. Meant for tests, as it
+    /// gives up most of the work saved by incremental reuse.
+    fn set_shadow_verification(&self, enabled: bool) {
+        self.set_shadow_verification_of_jars(enabled)
+    }
+
+    /// Starts recording the creation of persistent inputs and the writes to their
+    /// fields, to replay them in another database. See [`crate::record`].
+    #[cfg(feature = "persist")]
+    fn start_recording(&self) {
+        self.start_recording_of_jars()
+    }
+
+    /// Stops recording, and returns the mutations recorded since
+    /// [`Database::start_recording`].
+    #[cfg(feature = "persist")]
+    fn take_recording(&self) -> Recording {
+        self.take_recording_of_jars()
+    }
+
+    /// Applies the mutations recorded in one revision of a [`Recording`] (see
+    /// [`Recording::revisions`]), starting a new revision. The database should be
+    /// fresh, apart from the earlier revisions of the recording being replayed.
+    ///
+    /// **WARNING:** Like [`Database::synthetic_write`], this blocks until
+    /// all snapshots are dropped.
+    #[cfg(feature = "persist")]
+    fn replay(&mut self, revision: &RecordedRevision) -> Result<(), PersistError> {
+        self.replay_of_jars(revision)
+    }
+
+    /// Writes the contents of the persistent ingredients of this database
+    /// to `serializer`. See [`crate::persist`] for what gets saved.
+    #[cfg(feature = "persist")]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        Self: Sized,
+        S: Serializer,
+    {
+        let persisted = self.persist().map_err(ser::Error::custom)?;
+        persisted.serialize(serializer)
+    }
+
+    /// Loads data written by [`Database::serialize`] into this database,
+    /// which should be freshly created (see [`crate::Storage::restore`]).
+    #[cfg(feature = "persist")]
+    fn deserialize<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        Self: Sized,
+        D: Deserializer<'de>,
+    {
+        let persisted = PersistedDatabase::deserialize(deserializer)?;
+        self.restore(persisted).map_err(de::Error::custom)
+    }
 }
 
 /// Indicates a database that also supports parallel query
@@ -51,7 +401,7 @@ pub trait ParallelDatabase: Database + Send {
     /// series of queries in parallel and arranging the results. Using
     /// this method for that purpose ensures that those queries will
     /// see a consistent view of the database (it is also advisable
-    /// for those queries to use the [`Runtime::unwind_if_cancelled`]
+    /// for those queries to use the [`Database::unwind_if_cancelled`]
     /// method to check for cancellation).
     ///
     /// # Panics
@@ -96,6 +446,16 @@ pub trait ParallelDatabase: Database + Send {
     /// }
     /// ```
     fn snapshot(&self) -> Snapshot<Self>;
+
+    /// Consumes the database, so that its inputs can no longer be set, and shares it
+    /// between threads without them having to take snapshots; see [`Frozen`].
+    /// This suits batch tools, which set all of their inputs up front.
+    fn freeze(self) -> Arc<Frozen<Self>>
+    where
+        Self: Sized,
+    {
+        Arc::new(Frozen::new(self))
+    }
 }
 pub trait AsSalsaDatabase {
     fn as_salsa_database(&self) -> &dyn Database;
@@ -124,6 +484,27 @@ where
     pub fn new(db: DB) -> Self {
         Snapshot { db }
     }
+
+    /// Shares this snapshot between threads: unlike `Snapshot`, which can be moved to
+    /// another thread but not used by several at once, the result can be handed to
+    /// every task of a thread pool, each thread that runs one creating its own
+    /// handle on first use (see [`Frozen`]).
+    pub fn share(self) -> Arc<Frozen<DB>> {
+        Arc::new(Frozen::new(self.db))
+    }
+
+    /// Anchors this snapshot for up to `timeout`: until then, its queries are not
+    /// cancelled by writes, which wait for the snapshot to be dropped instead
+    /// (see [`crate::CancellationHandle::set_anchor`]). This suits the work that
+    /// must run to completion, such as formatting a file on save or generating
+    /// code for a build, for which a result made stale by an edit is still better
+    /// than none; the timeout bounds how long the edit may wait.
+    pub fn anchored(self, timeout: Duration) -> Self {
+        self.db
+            .cancellation_handle()
+            .set_anchor(Some(Instant::now() + timeout));
+        self
+    }
 }
 
 impl<DB> std::ops::Deref for Snapshot<DB>
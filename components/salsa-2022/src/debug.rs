@@ -1,10 +1,20 @@
 use std::{
     collections::{HashMap, HashSet},
-    fmt,
+    fmt::{self, Write},
     rc::Rc,
     sync::Arc,
 };
 
+#[cfg(feature = "persist")]
+use serde::Serialize;
+
+#[cfg(feature = "persist")]
+use crate::Revision;
+use crate::{
+    hash::FxIndexSet, key::DependencyIndex, runtime::local_state::QueryOrigin, Database,
+    DatabaseKeyIndex, Durability,
+};
+
 pub trait DebugWithDb<Db: ?Sized> {
     fn debug<'me, 'db>(&'me self, db: &'me Db) -> DebugWith<'me, Db>
     where
@@ -148,6 +158,16 @@ where
     }
 }
 
+impl<Db: ?Sized, T> DebugWithDb<Db> for [T]
+where
+    T: DebugWithDb<Db>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, db: &Db, include_all_fields: bool) -> fmt::Result {
+        let elements = self.iter().map(|e| e.debug_with(db, include_all_fields));
+        f.debug_list().entries(elements).finish()
+    }
+}
+
 impl<Db: ?Sized, T> DebugWithDb<Db> for Option<T>
 where
     T: DebugWithDb<Db>,
@@ -212,6 +232,168 @@ where
     }
 }
 
+/// Renders the dependency graph below `root` in the [DOT] language of Graphviz.
+///
+/// Starting from `root`, this follows the edges recorded by the last execution
+/// of each memoized function: solid edges point to the values a query read and
+/// dashed edges point to the values it created or assigned. Each node is labeled
+/// with the value it stands for, followed by the revision in which the value last
+/// changed, the revision in which it was last verified (for memoized functions)
+/// and its durability.
+///
+/// [DOT]: https://graphviz.org/doc/info/lang.html
+pub fn dump_dependency_graph<Db: ?Sized + Database>(db: &Db, root: DatabaseKeyIndex) -> String {
+    let mut nodes: FxIndexSet<DependencyIndex> = FxIndexSet::default();
+    let mut edges: FxIndexSet<(DependencyIndex, DependencyIndex, bool)> = FxIndexSet::default();
+    nodes.insert(root.into());
+
+    // `nodes` doubles as the work list: everything after `next` is yet to be visited.
+    let mut next = 0;
+    while let Some(&node) = nodes.get_index(next) {
+        next += 1;
+        let key = match DatabaseKeyIndex::try_from(node) {
+            Ok(key) => key,
+            Err(()) => continue,
+        };
+        match db.origin(key) {
+            Some(QueryOrigin::Derived(query_edges))
+            | Some(QueryOrigin::DerivedUntracked(query_edges)) => {
+                for input in query_edges.inputs() {
+                    nodes.insert(input);
+                    edges.insert((node, input, false));
+                }
+                for output in query_edges.outputs() {
+                    nodes.insert(output);
+                    edges.insert((node, output, true));
+                }
+            }
+            Some(QueryOrigin::Assigned(by)) => {
+                nodes.insert(by.into());
+                edges.insert((by.into(), node, true));
+            }
+            Some(QueryOrigin::BaseInput) | None => {}
+        }
+    }
+
+    let mut dot = String::from("digraph {\n");
+    for (index, &node) in nodes.iter().enumerate() {
+        let mut label = format!("{:?}", node.debug(db));
+        let revisions = DatabaseKeyIndex::try_from(node)
+            .ok()
+            .and_then(|key| db.value_revisions(key));
+        if let Some(revisions) = revisions {
+            write!(label, "\nchanged at {:?}", revisions.changed_at).unwrap();
+            if let Some(verified_at) = revisions.verified_at {
+                write!(label, ", verified at {verified_at:?}").unwrap();
+            }
+            write!(
+                label,
+                "\ndurability {}",
+                durability_name(revisions.durability)
+            )
+            .unwrap();
+        }
+        // The `Debug` string escapes quotes and newlines the same way DOT does.
+        writeln!(dot, "    n{index} [label={label:?}];").unwrap();
+    }
+    for (from, to, dashed) in edges {
+        let from = nodes.get_index_of(&from).unwrap();
+        let to = nodes.get_index_of(&to).unwrap();
+        let style = if dashed { " [style=dashed]" } else { "" };
+        writeln!(dot, "    n{from} -> n{to}{style};").unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes everything the database stores, as JSON, to `writer`.
+///
+/// The dump lists the current revision, then every input field, interned value,
+/// tracked struct and memoized value, in the order of their ingredients and ids.
+/// Each value comes with the revisions in which it last changed and was last
+/// verified, its durability and, for memoized values, the inputs and outputs
+/// recorded by their last execution. Values and edges are labeled with their
+/// `Debug` strings, so two dumps of the same program can be diffed, e.g., to find
+/// out why a query ran again.
+#[cfg(feature = "persist")]
+pub fn dump<Db: ?Sized + Database>(db: &Db, writer: impl std::io::Write) -> serde_json::Result<()> {
+    let label = |index: DependencyIndex| format!("{:?}", index.debug(db));
+    let values = db
+        .keys_of_jars()
+        .into_iter()
+        .map(|key| {
+            let revisions = db.value_revisions(key);
+            let origin = db.origin(key).map(|origin| match &origin {
+                QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) => {
+                    DumpedOrigin::Derived {
+                        untracked: matches!(origin, QueryOrigin::DerivedUntracked(_)),
+                        inputs: edges.inputs().map(label).collect(),
+                        outputs: edges.outputs().map(label).collect(),
+                    }
+                }
+                QueryOrigin::Assigned(by) => DumpedOrigin::Assigned {
+                    by: label((*by).into()),
+                },
+                QueryOrigin::BaseInput => DumpedOrigin::Input,
+            });
+            DumpedValue {
+                key: label(key.into()),
+                changed_at: revisions.as_ref().map(|r| r.changed_at),
+                verified_at: revisions.as_ref().and_then(|r| r.verified_at),
+                durability: revisions.as_ref().map(|r| durability_name(r.durability)),
+                origin,
+            }
+        })
+        .collect();
+    let dump = Dump {
+        revision: db.runtime().current_revision(),
+        values,
+    };
+    serde_json::to_writer_pretty(writer, &dump)
+}
+
+#[cfg(feature = "persist")]
+#[derive(Serialize)]
+struct Dump {
+    revision: Revision,
+    values: Vec<DumpedValue>,
+}
+
+#[cfg(feature = "persist")]
+#[derive(Serialize)]
+struct DumpedValue {
+    key: String,
+    changed_at: Option<Revision>,
+    verified_at: Option<Revision>,
+    durability: Option<&'static str>,
+    origin: Option<DumpedOrigin>,
+}
+
+#[cfg(feature = "persist")]
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum DumpedOrigin {
+    Input,
+    Assigned {
+        by: String,
+    },
+    Derived {
+        untracked: bool,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+    },
+}
+
+fn durability_name(durability: Durability) -> &'static str {
+    if durability == Durability::LOW {
+        "LOW"
+    } else if durability == Durability::MEDIUM {
+        "MEDIUM"
+    } else {
+        "HIGH"
+    }
+}
+
 /// This is used by the macro generated code.
 /// If the field type implements `DebugWithDb`, uses that, otherwise, uses `Debug`.
 /// That's the "has impl" trick (https://github.com/nvzqz/impls#how-it-works)
@@ -221,11 +403,7 @@ pub mod helper {
     use std::{fmt, marker::PhantomData};
 
     pub trait Fallback<T: fmt::Debug, Db: ?Sized> {
-        fn salsa_debug<'a, 'b>(
-            a: &'a T,
-            _db: &'b Db,
-            _include_all_fields: bool,
-        ) -> &'a dyn fmt::Debug {
+        fn salsa_debug<'a>(a: &'a T, _db: &Db, _include_all_fields: bool) -> &'a dyn fmt::Debug {
             a
         }
     }
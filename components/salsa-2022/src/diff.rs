@@ -0,0 +1,76 @@
+//! The values of tracked functions with the `diff` option, which hand their
+//! consumers what changed since they last looked, e.g., so that a UI rendering a
+//! long list of diagnostics does not compare the whole list again each revision.
+//!
+//! ```ignore
+//! #[salsa::tracked(return_ref, diff)]
+//! fn diagnostics(db: &dyn Db, file: File) -> Vec<Diagnostic> { ... }
+//!
+//! let (diagnostics, delta) = diagnostics::with_delta(db, file, last_rendered);
+//! ```
+//!
+//! The function keeps the value each memo replaces, as with `history = 1`, so the
+//! delta is known as long as the value changed at most once since the revision.
+
+use std::hash::Hash;
+
+use crate::hash::FxHashMap;
+
+/// A value that can tell what changed from another value of its type.
+pub trait Diffable {
+    /// What changed between two values.
+    type Delta;
+
+    /// Returns the changes that turn `old` into `new`.
+    fn diff(old: &Self, new: &Self) -> Self::Delta;
+}
+
+/// The elements added to and removed from a list, in the order of the lists;
+/// moving an element within the list is not a change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListDelta<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+}
+
+impl<T> ListDelta<T> {
+    /// True if no element was added or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<T: Clone + Eq + Hash> Diffable for Vec<T> {
+    type Delta = ListDelta<T>;
+
+    fn diff(old: &Self, new: &Self) -> ListDelta<T> {
+        let mut old_counts: FxHashMap<&T, usize> = FxHashMap::default();
+        for element in old {
+            *old_counts.entry(element).or_default() += 1;
+        }
+
+        let mut added = vec![];
+        for element in new {
+            match old_counts.get_mut(element) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => added.push(element.clone()),
+            }
+        }
+
+        // What is left of the counts are the removed elements, the last ones of
+        // each value being those that are kept.
+        let removed = old
+            .iter()
+            .filter(|element| match old_counts.get_mut(element) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect();
+
+        ListDelta { added, removed }
+    }
+}
@@ -0,0 +1,213 @@
+//! Serving requests, e.g., those of the client of a language server: reads run
+//! on snapshots, on worker threads, and writes cancel them.
+//!
+//! ```ignore
+//! let mut dispatcher = salsa::dispatch::Dispatcher::new(db, 4);
+//! for message in connection {
+//!     match message {
+//!         // A newer request for the hover of a file supersedes the older ones.
+//!         Message::Hover(file, offset) => {
+//!             let key = Some(("hover", file));
+//!             respond_later(dispatcher.read(key, move |db| hover(db, file, offset)));
+//!         }
+//!         Message::Edit(file, text) => dispatcher.write(|db| file.set_text(db).to(text)),
+//!     }
+//! }
+//! ```
+
+use std::{
+    hash::Hash,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crossbeam::channel::{self, Receiver, Sender};
+use parking_lot::Mutex;
+
+use crate::{hash::FxHashMap, CancellationHandle, Cancelled, ParallelDatabase};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Owns a database, runs the reads given to [`Dispatcher::read`] on snapshots of
+/// it, on worker threads, and applies the writes given to [`Dispatcher::write`].
+///
+/// A write cancels the reads in flight, which salsa does anyway before it sets
+/// an input, and those waiting for a worker do not start. A read can be given a
+/// key, such as the kind of request and the document it is about: it then
+/// cancels the earlier reads with the same key, whose results are stale.
+pub struct Dispatcher<DB, K>
+where
+    DB: ParallelDatabase,
+{
+    db: DB,
+
+    /// Taken when dropped, to stop the workers.
+    jobs: Option<Sender<Job>>,
+    workers: Vec<thread::JoinHandle<()>>,
+
+    /// The latest read for each key, with its cancellation handle.
+    latest: Arc<Mutex<FxHashMap<K, (u64, CancellationHandle)>>>,
+    next_read: AtomicU64,
+}
+
+impl<DB, K> Dispatcher<DB, K>
+where
+    DB: ParallelDatabase + Send + 'static,
+    K: Hash + Eq + Clone + Send + 'static,
+{
+    /// Creates a dispatcher for `db`, which runs reads on `threads` workers (at least
+    /// one). With the `single-threaded` feature, there are none: each read runs in
+    /// [`Dispatcher::read`], which returns once it is done.
+    pub fn new(db: DB, threads: usize) -> Self {
+        let (jobs, receiver) = channel::unbounded::<Job>();
+        let threads = if cfg!(feature = "single-threaded") {
+            0
+        } else {
+            threads.max(1)
+        };
+        let workers = (0..threads)
+            .map(|index| {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("salsa-dispatch-{index}"))
+                    .spawn(move || {
+                        for job in receiver {
+                            job();
+                        }
+                    })
+                    .expect("cannot spawn a worker thread")
+            })
+            .collect();
+        Self {
+            db,
+            jobs: Some(jobs),
+            workers,
+            latest: Default::default(),
+            next_read: AtomicU64::new(0),
+        }
+    }
+
+    /// The database, e.g., to run a query on the current thread.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Runs `f` on a snapshot of the database, on a worker thread, and returns the
+    /// request whose result it will be. With a `key`, the earlier reads with the
+    /// same key are cancelled.
+    ///
+    /// The snapshot is taken now, so the read sees the inputs as they are now.
+    pub fn read<T>(&self, key: Option<K>, f: impl FnOnce(&DB) -> T + Send + 'static) -> Request<T>
+    where
+        T: Send + 'static,
+    {
+        let snapshot = self.db.snapshot();
+        let cancellation = snapshot.cancellation_handle();
+        let id = self.next_read.fetch_add(1, Ordering::Relaxed);
+        if let Some(key) = &key {
+            let superseded = self
+                .latest
+                .lock()
+                .insert(key.clone(), (id, cancellation.clone()));
+            if let Some((_, superseded)) = superseded {
+                superseded.cancel();
+            }
+        }
+
+        let (sender, receiver) = channel::bounded(1);
+        let latest = self.latest.clone();
+        let job = {
+            let cancellation = cancellation.clone();
+            move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    // A read that was cancelled before it started does not start.
+                    if snapshot.is_write_pending() {
+                        return Err(Cancelled::PendingWrite);
+                    }
+                    if let Some(cancelled) = cancellation.check() {
+                        return Err(cancelled);
+                    }
+                    Cancelled::catch(AssertUnwindSafe(|| f(&snapshot)))
+                }));
+                // Drop the snapshot first, as a write may be waiting for it.
+                drop(snapshot);
+                if let Some(key) = key {
+                    let mut latest = latest.lock();
+                    if latest.get(&key).is_some_and(|&(latest, _)| latest == id) {
+                        latest.remove(&key);
+                    }
+                }
+                let _ = sender.send(result);
+            }
+        };
+        if self.workers.is_empty() {
+            job();
+        } else {
+            self.jobs
+                .as_ref()
+                .unwrap()
+                .send(Box::new(job))
+                .expect("the workers are gone");
+        }
+
+        Request {
+            result: receiver,
+            cancellation,
+        }
+    }
+
+    /// Runs `f` on the database, once the reads in flight have been cancelled, if
+    /// it sets inputs.
+    pub fn write<T>(&mut self, f: impl FnOnce(&mut DB) -> T) -> T {
+        f(&mut self.db)
+    }
+}
+
+impl<DB, K> Drop for Dispatcher<DB, K>
+where
+    DB: ParallelDatabase,
+{
+    fn drop(&mut self) {
+        // The workers finish the reads given to them, then stop.
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A read given to [`Dispatcher::read`], whose result is either the value it
+/// returned or why it was cancelled.
+pub struct Request<T> {
+    result: Receiver<thread::Result<Result<T, Cancelled>>>,
+    cancellation: CancellationHandle,
+}
+
+impl<T> Request<T> {
+    /// Waits for the result of the read.
+    ///
+    /// # Panics
+    ///
+    /// If the read panicked (other than because it was cancelled), the panic is
+    /// resumed on the calling thread.
+    pub fn wait(self) -> Result<T, Cancelled> {
+        match self.result.recv().expect("the worker is gone") {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Whether the read finished, so that [`Request::wait`] does not block.
+    pub fn is_done(&self) -> bool {
+        !self.result.is_empty()
+    }
+
+    /// Cancels the read, e.g., because the client no longer wants its result.
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
 /// Describes how likely a value is to change -- how "durable" it is.
 /// By default, inputs have `Durability::LOW` and interned values have
 /// `Durability::HIGH`. But inputs can be explicitly set with other
@@ -15,7 +18,13 @@
 /// frequently editing. Medium or high durabilities are used for
 /// configuration, the source from library crates, or other things
 /// that are unlikely to be edited.
+///
+/// Applications that need a finer division, say one level per layer of a
+/// workspace, can make their own durabilities with [`Durability::new`] and
+/// give the storage enough levels to tell them apart, see
+/// [`crate::Storage::with_durability_levels`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub struct Durability(u8);
 
 impl Durability {
@@ -35,14 +44,21 @@ impl Durability {
     /// Example: the standard library or something from crates.io
     pub const HIGH: Durability = Durability(2);
 
-    /// The maximum possible durability; equivalent to HIGH but
-    /// "conceptually" distinct (i.e., if we add more durability
-    /// levels, this could change).
-    pub(crate) const MAX: Durability = Self::HIGH;
+    /// The maximum possible durability, at least as high as any other;
+    /// a runtime treats it like its highest level.
+    pub(crate) const MAX: Durability = Durability(u8::MAX);
 
-    /// Number of durability levels.
+    /// Number of durability levels, unless configured otherwise.
     pub(crate) const LEN: usize = 3;
 
+    /// The durability at `level`, where `Durability::LOW` is level 0 and
+    /// higher levels are less likely to change. The storage distinguishes
+    /// only as many levels as it was created with; any level beyond those
+    /// is treated like the highest one.
+    pub const fn new(level: u8) -> Durability {
+        Durability(level)
+    }
+
     pub(crate) fn index(self) -> usize {
         self.0 as usize
     }
@@ -1,5 +1,6 @@
 use crate::{
     debug::DebugWithDb, key::DatabaseKeyIndex, key::DependencyIndex, runtime::RuntimeId, Database,
+    Revision,
 };
 use std::fmt;
 
@@ -78,6 +79,16 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that a query that iterates to a fixed point did not converge
+    /// and will be executed again.
+    WillIterateCycle {
+        /// The database-key for the cycle head. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// The number of iterations performed so far.
+        iteration_count: u32,
+    },
+
     /// Indicates that `unwind_if_cancelled` was called and salsa will check if
     /// the current revision has been cancelled.
     WillCheckCancellation,
@@ -105,6 +116,15 @@ pub enum EventKind {
         /// Accumulator that was accumulated into
         accumulator: DependencyIndex,
     },
+
+    /// Indicates that a write started a new revision, e.g., so that caches
+    /// outside of salsa can compare it with [`Database::last_changed_revision`].
+    ///
+    /// Executes before the write itself, with the revision already current.
+    DidStartRevision {
+        /// The new current revision.
+        revision: Revision,
+    },
 }
 
 impl fmt::Debug for EventKind {
@@ -126,6 +146,14 @@ impl fmt::Debug for EventKind {
                 .debug_struct("WillExecute")
                 .field("database_key", database_key)
                 .finish(),
+            EventKind::WillIterateCycle {
+                database_key,
+                iteration_count,
+            } => fmt
+                .debug_struct("WillIterateCycle")
+                .field("database_key", database_key)
+                .field("iteration_count", iteration_count)
+                .finish(),
             EventKind::WillCheckCancellation => fmt.debug_struct("WillCheckCancellation").finish(),
             EventKind::WillDiscardStaleOutput {
                 execute_key,
@@ -146,6 +174,10 @@ impl fmt::Debug for EventKind {
                 .field("executor_key", executor_key)
                 .field("accumulator", accumulator)
                 .finish(),
+            EventKind::DidStartRevision { revision } => fmt
+                .debug_struct("DidStartRevision")
+                .field("revision", revision)
+                .finish(),
         }
     }
 }
@@ -186,6 +218,17 @@ where
                     &database_key.debug_with(db, include_all_fields),
                 )
                 .finish(),
+            EventKind::WillIterateCycle {
+                database_key,
+                iteration_count,
+            } => fmt
+                .debug_struct("WillIterateCycle")
+                .field(
+                    "database_key",
+                    &database_key.debug_with(db, include_all_fields),
+                )
+                .field("iteration_count", iteration_count)
+                .finish(),
             EventKind::WillCheckCancellation => fmt.debug_struct("WillCheckCancellation").finish(),
             EventKind::WillDiscardStaleOutput {
                 execute_key,
@@ -216,6 +259,10 @@ where
                     &accumulator.debug_with(db, include_all_fields),
                 )
                 .finish(),
+            EventKind::DidStartRevision { revision } => fmt
+                .debug_struct("DidStartRevision")
+                .field("revision", revision)
+                .finish(),
         }
     }
 }
@@ -0,0 +1,70 @@
+//! Databases that cannot change while they are shared by any number of threads
+//! (see [`ParallelDatabase::freeze`] and [`Snapshot::share`]).
+
+use std::ops::Deref;
+use std::thread::{self, ThreadId};
+
+use parking_lot::Mutex;
+
+use crate::hash::FxDashMap;
+use crate::{ParallelDatabase, Snapshot};
+
+/// A database whose inputs cannot be set while it exists, produced by
+/// [`ParallelDatabase::freeze`] or [`Snapshot::share`]. Unlike the database itself,
+/// it is `Sync`: it can be shared by any number of threads (typically in an `Arc`,
+/// e.g., by the tasks of a thread pool), on each of which it dereferences to a
+/// handle of the database for that thread.
+///
+/// A thread's handle is a snapshot, created the first time the thread uses the
+/// frozen database and kept until the frozen database is dropped; afterwards, using
+/// it only takes a lookup in a concurrent map. If the database itself was frozen,
+/// nothing can write to it anymore, so its queries are never cancelled; a shared
+/// snapshot is cancelled like any other when another handle is about to write
+/// (which waits for it to be dropped).
+pub struct Frozen<DB: ParallelDatabase> {
+    /// The database that was frozen, which the handles are snapshots of.
+    db: Mutex<DB>,
+
+    /// The handle of each thread that used the frozen database.
+    handles: FxDashMap<ThreadId, ThreadHandle<DB>>,
+}
+
+/// Boxed, so that references to the handle remain valid as the map grows.
+struct ThreadHandle<DB: ParallelDatabase>(Box<Snapshot<DB>>);
+
+// SAFETY: the handle is only used by the thread whose id is its key in
+// `Frozen::handles`; other threads can only move or drop it, which `DB: Send` allows.
+unsafe impl<DB: ParallelDatabase> Sync for ThreadHandle<DB> {}
+
+impl<DB: ParallelDatabase> Frozen<DB> {
+    pub(crate) fn new(db: DB) -> Self {
+        Self {
+            db: Mutex::new(db),
+            handles: Default::default(),
+        }
+    }
+
+    /// The handle of the database for the current thread.
+    pub fn db(&self) -> &DB {
+        let thread = thread::current().id();
+        let db: *const DB = match self.handles.get(&thread) {
+            Some(handle) => &**handle.0,
+            None => {
+                let handle = ThreadHandle(Box::new(self.db.lock().snapshot()));
+                &**self.handles.entry(thread).or_insert(handle).0
+            }
+        };
+        // SAFETY: the box is not dropped before `self`, since handles are never
+        // removed, and the returned reference cannot leave this thread (a database
+        // is never `Sync`), so this is the only thread using the handle.
+        unsafe { &*db }
+    }
+}
+
+impl<DB: ParallelDatabase> Deref for Frozen<DB> {
+    type Target = DB;
+
+    fn deref(&self) -> &DB {
+        self.db()
+    }
+}
@@ -0,0 +1,183 @@
+//! Files as inputs (enabled by the `fs` feature), kept up to date with the
+//! events of a [`notify`] watcher.
+//!
+//! ```ignore
+//! #[salsa::db(salsa::fs::Jar, crate::Jar)]
+//! struct Database {
+//!     storage: salsa::Storage<Self>,
+//!     files: salsa::fs::Files,
+//! }
+//!
+//! impl salsa::fs::Db for Database {
+//!     fn files(&self) -> &salsa::fs::Files {
+//!         &self.files
+//!     }
+//! }
+//!
+//! let (tx, rx) = std::sync::mpsc::channel();
+//! db.files.set_watcher(notify::recommended_watcher(tx)?);
+//! let file = db.files.file(&db, "src/main.rs");
+//! loop {
+//!     let text = salsa::fs::read_to_string(&db, file);
+//!     // ... run queries ...
+//!     salsa::fs::apply_event(&mut db, &rx.recv()??);
+//! }
+//! ```
+//!
+//! A [`File`] does not store the contents of the file: [`read_to_string`] reads
+//! them from the disk the first time they are needed, and again once an event
+//! for the path has been applied. A file that does not exist, or was deleted,
+//! reads as an error, until it is created again: the watcher watches the
+//! directory of each file rather than the file itself, so that it sees the file
+//! being created. The directory must exist when the file is created.
+
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use parking_lot::Mutex;
+
+pub use notify;
+
+use crate::Durability;
+
+#[salsa::jar(db = Db)]
+pub struct Jar(File, read_to_string);
+
+/// Implemented by databases that include [`Jar`], to find the files created so far.
+pub trait Db: salsa::DbWithJar<Jar> {
+    fn files(&self) -> &Files;
+}
+
+/// The file at a path, as given to [`Files::file`].
+#[salsa::input(jar = Jar)]
+pub struct File {
+    #[return_ref]
+    pub path: PathBuf,
+
+    /// Incremented by [`apply_event`] each time the file changes, so that
+    /// [`read_to_string`] reads it again.
+    version: u64,
+}
+
+/// The contents of `file`, or the kind of error that reading it failed with.
+#[salsa::tracked(jar = Jar, return_ref)]
+pub fn read_to_string(db: &dyn Db, file: File) -> Result<String, io::ErrorKind> {
+    // Depend on the version, so that the file is read again once it changes.
+    file.version(db);
+    std::fs::read_to_string(file.path(db)).map_err(|error| error.kind())
+}
+
+/// The files of a database by path, each created the first time it is asked for,
+/// and the watcher told to watch their directories.
+#[derive(Default)]
+pub struct Files {
+    files: DashMap<PathBuf, (File, Durability)>,
+    watcher: Mutex<Watcher>,
+}
+
+#[derive(Default)]
+struct Watcher {
+    watcher: Option<Box<dyn notify::Watcher + Send>>,
+    /// The directories watched so far.
+    directories: HashSet<PathBuf>,
+}
+
+impl Watcher {
+    /// Watches the directory of `path`, unless it is watched already.
+    fn watch_directory_of(&mut self, path: &Path) {
+        let watcher = match &mut self.watcher {
+            Some(watcher) => watcher,
+            None => return,
+        };
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        if self.directories.contains(directory) {
+            return;
+        }
+        match watcher.watch(directory, notify::RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                self.directories.insert(directory.to_path_buf());
+            }
+            Err(error) => log::debug!("cannot watch {}: {}", directory.display(), error),
+        }
+    }
+}
+
+impl Files {
+    /// Sets the watcher told to watch the files created from now on, e.g., the
+    /// one returned by `notify::recommended_watcher`, whose events are to be
+    /// given to [`apply_event`].
+    pub fn set_watcher(&self, watcher: impl notify::Watcher + Send + 'static) {
+        *self.watcher.lock() = Watcher {
+            watcher: Some(Box::new(watcher)),
+            directories: HashSet::new(),
+        };
+    }
+
+    /// The file at `path`, with low durability if it is created. Files are keyed by
+    /// their path as given, which should be canonical, as the paths of events are.
+    pub fn file(&self, db: &dyn Db, path: impl AsRef<Path>) -> File {
+        self.file_with_durability(db, path, Durability::LOW)
+    }
+
+    /// The file at `path`, with `durability` if it is created: e.g., high for the
+    /// files of the libraries a project depends on, which seldom change. The
+    /// changes to the file keep that durability.
+    pub fn file_with_durability(
+        &self,
+        db: &dyn Db,
+        path: impl AsRef<Path>,
+        durability: Durability,
+    ) -> File {
+        let path = path.as_ref();
+        if let Some(entry) = self.files.get(path) {
+            return entry.0;
+        }
+        match self.files.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.get().0,
+            Entry::Vacant(entry) => {
+                // Watch the file before it is read, so that no change goes unnoticed.
+                self.watcher.lock().watch_directory_of(path);
+                let file = File::builder(path.to_path_buf(), 0)
+                    .durability(durability)
+                    .new(db);
+                entry.insert((file, durability));
+                file
+            }
+        }
+    }
+
+    /// The file at `path`, if it was created.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<File> {
+        self.files.get(path.as_ref()).map(|entry| entry.0)
+    }
+}
+
+/// Applies the event of a watcher to the files it concerns, which are read again,
+/// in a new revision, the next time they are needed. Paths that no file was
+/// created for are ignored, and so are accesses.
+///
+/// Returns the number of files changed.
+pub fn apply_event(db: &mut dyn Db, event: &notify::Event) -> usize {
+    if let notify::EventKind::Access(_) = event.kind {
+        return 0;
+    }
+    let changed: Vec<(File, Durability)> = event
+        .paths
+        .iter()
+        .filter_map(|path| db.files().files.get(path).map(|entry| *entry))
+        .collect();
+    for &(file, durability) in &changed {
+        let version = file.version(db);
+        file.set_version(db)
+            .with_durability(durability)
+            .to(version + 1);
+    }
+    changed.len()
+}
@@ -4,30 +4,54 @@ use arc_swap::ArcSwap;
 use crossbeam::{atomic::AtomicCell, queue::SegQueue};
 
 use crate::{
-    cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, IngredientRequiresReset},
+    cache::Fingerprint,
+    cycle::{CycleRecoveryAction, CycleRecoveryStrategy},
+    ingredient::{fmt_index, IngredientRequiresReset, ValueRevisions},
     jar::Jar,
     key::{DatabaseKeyIndex, DependencyIndex},
     runtime::local_state::QueryOrigin,
     salsa_struct::SalsaStructInDb,
-    Cycle, DbWithJar, Event, EventKind, Id, Revision,
+    usage::{EvictionCandidate, IngredientUsage},
+    Cycle, DbWithJar, Durability, Event, EventKind, Id, Revision,
 };
 
 use super::{ingredient::Ingredient, routes::IngredientIndex, AsId};
 
 mod accumulated;
 mod backdate;
+mod cache;
+mod changed_since;
 mod delete;
 mod diff_outputs;
+mod evict;
 mod execute;
 mod fetch;
+mod fixpoint;
+mod history;
 mod inputs;
 mod lru;
 mod maybe_changed_after;
 mod memo;
+mod memo_info;
+mod observe;
+mod parallel_verify;
+#[cfg(feature = "persist")]
+mod persist;
+mod pin;
+mod purge;
+mod shadow;
+mod share;
 mod specify;
 mod store;
+mod sweep;
 mod sync;
+mod synthetic_write;
+mod ttl;
+mod update;
+mod usage;
+
+pub use memo_info::{MemoInfo, OriginKind};
+pub use share::SharedValues;
 
 /// Function ingredients are the "workhorse" of salsa.
 /// They are used for tracked functions, for the "value" fields of tracked structs, and for the fields of input structs.
@@ -36,8 +60,8 @@ mod sync;
 ///
 /// * the `fetch` method, which is invoked when the function is called by the user's code;
 ///   it will return a memoized value if one exists, or execute the function otherwise.
-/// * the `specify` method, which can only be used when the key is an entity created by the active query.
-///   It sets the value of the function imperatively, so that when later fetches occur, they'll return this value.
+/// * the `specify` method, which can only be used when the key is an entity created by the active query
+///   (or, for functions taking several arguments, when the first one is). It sets the value of the function imperatively, so that when later fetches occur, they'll return this value.
 /// * the `store` method, which can only be invoked with an `&mut` reference, and is to set input fields.
 pub struct FunctionIngredient<C: Configuration> {
     /// The ingredient index we were assigned in the database.
@@ -54,6 +78,21 @@ pub struct FunctionIngredient<C: Configuration> {
     /// Used to find memos to throw out when we have too many memoized values.
     lru: lru::Lru,
 
+    /// Remembers when each value was computed, to expire values older than their ttl.
+    ttl: ttl::Ttl,
+
+    /// The keys whose memos are never evicted, see [`FunctionIngredient::pin`].
+    pins: pin::Pins,
+
+    /// The previous values of the memos, for [`FunctionIngredient::value_as_of`].
+    history: history::History<C::Value>,
+
+    /// The callbacks invoked when the value for a key changes.
+    observers: observe::Observers<C::Value>,
+
+    /// The values stored once for all keys, with the `share_values` option.
+    shared_values: SharedValues<C::Value>,
+
     /// When `fetch` and friends executes, they return a reference to the
     /// value stored in the memo that is extended to live as long as the `&self`
     /// reference we start with. This means that whenever we remove something
@@ -95,6 +134,36 @@ pub trait Configuration {
     /// (and, if so, how).
     const CYCLE_STRATEGY: CycleRecoveryStrategy;
 
+    /// With [`CycleRecoveryStrategy::FixedPoint`], the number of iterations after which
+    /// salsa panics, set with the `cycle_max_iterations` option.
+    const CYCLE_MAX_ITERATIONS: u32 = crate::cycle::DEFAULT_MAX_ITERATIONS;
+
+    /// The number of shards of the map storing the memos, set with the `shards`
+    /// option; zero picks the default of `dashmap`, which is based on the number of CPUs.
+    /// Each shard is locked separately, so more shards mean less contention
+    /// between threads fetching different keys.
+    const MEMO_MAP_SHARDS: usize = 0;
+
+    /// Whether the function has the `cache` option, so that its values can be
+    /// shared with other databases through a [`crate::cache::CacheBackend`].
+    const CACHE: bool = false;
+
+    /// Whether the function has the `update` option, so that a new value is written
+    /// into the old one with [`Configuration::update_value`] when it can be.
+    const UPDATE: bool = false;
+
+    /// A fingerprint of the implementation of the function, computed by the macro
+    /// from its name, its signature and its `version` option. The values stored in
+    /// a [`crate::cache::CacheBackend`] or persisted (see [`crate::persist`]) by
+    /// an implementation with another fingerprint are not reused, since they may
+    /// not be what this one computes: bump the `version` when changing the body
+    /// of a function whose values are cached or persisted.
+    const QUERY_FINGERPRINT: Fingerprint = Fingerprint(0);
+
+    /// The phase the work of the function counts towards in the query statistics,
+    /// given by its `phase` option; see [`crate::statistics::QueryStatistics::phase`].
+    const PHASE: Option<&'static str> = None;
+
     /// Invokes after a new result `new_value`` has been computed for which an older memoized
     /// value existed `old_value`. Returns true if the new value is equal to the older one
     /// and hence should be "backdated" (i.e., marked as having last changed in an older revision,
@@ -115,6 +184,65 @@ pub trait Configuration {
     /// This invokes the recovery function given by the user.
     fn recover_from_cycle(db: &DynDb<Self>, cycle: &Cycle, key: Self::Key) -> Self::Value;
 
+    /// If the cycle strategy is `FixedPoint`, invoked when `key` is first re-entered
+    /// during its own execution, to get the provisional value for the first iteration.
+    ///
+    /// This invokes the `cycle_initial` function given by the user.
+    fn cycle_initial(db: &DynDb<Self>, key: Self::Key) -> Self::Value;
+
+    /// If the cycle strategy is `FixedPoint`, invoked when an iteration computed a
+    /// `value` that differs from the provisional value it started from.
+    /// `count` is the number of iterations executed so far.
+    ///
+    /// This invokes the `cycle_fn` function given by the user.
+    fn cycle_iterate(
+        db: &DynDb<Self>,
+        value: &Self::Value,
+        count: u32,
+        key: Self::Key,
+    ) -> CycleRecoveryAction<Self::Value>;
+
+    /// The number of bytes that `value` owns on the heap, as computed by the
+    /// `heap_size` function given by the user; zero if there is none.
+    /// These are reported by [`crate::Database::storage_usage`].
+    fn heap_size(_value: &Self::Value) -> usize {
+        0
+    }
+
+    /// With the `cache` option, the fingerprint of what the value for `key` depends on,
+    /// as computed by the function given by the user.
+    fn cache_fingerprint(_db: &DynDb<Self>, _key: Self::Key) -> Fingerprint {
+        panic!("the function has no `cache` option")
+    }
+
+    /// With the `cache` option, encodes `value` to be stored by the cache backend.
+    fn encode_value(_value: &Self::Value) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// With the `cache` option, decodes a value encoded by [`Configuration::encode_value`].
+    fn decode_value(_bytes: &[u8]) -> Option<Self::Value> {
+        None
+    }
+
+    /// With the `update` option, writes `new_value` into `old_value`, the value of
+    /// the memo it replaces, which no one can read anymore.
+    ///
+    /// This invokes the user's impl of [`crate::update::Update`].
+    fn update_value(old_value: &mut Self::Value, new_value: Self::Value) {
+        *old_value = new_value;
+    }
+
+    /// With the `share_values` option, returns the value equal to `value` from
+    /// `shared`, so that the memos of all keys store it once; otherwise, `value`.
+    fn share_value(_shared: &SharedValues<Self::Value>, value: Self::Value) -> Self::Value {
+        value
+    }
+
+    /// With the `share_values` option, forgets the values of `shared` no memo stores
+    /// anymore. Invoked at the start of each revision.
+    fn prune_shared_values(_shared: &mut SharedValues<Self::Value>) {}
+
     /// Given a salsa Id, returns the key. Convenience function to avoid
     /// having to type `<C::Key as AsId>::from_id`.
     fn key_from_id(id: Id) -> Self::Key {
@@ -142,8 +270,13 @@ where
     pub fn new(index: IngredientIndex, debug_name: &'static str) -> Self {
         Self {
             index,
-            memo_map: memo::MemoMap::default(),
+            memo_map: memo::MemoMap::with_shards(C::MEMO_MAP_SHARDS),
             lru: Default::default(),
+            ttl: Default::default(),
+            pins: Default::default(),
+            history: Default::default(),
+            observers: Default::default(),
+            shared_values: Default::default(),
             sync_map: Default::default(),
             deleted_entries: Default::default(),
             registered: Default::default(),
@@ -151,17 +284,79 @@ where
         }
     }
 
-    fn database_key_index(&self, k: C::Key) -> DatabaseKeyIndex {
+    pub fn database_key_index(&self, k: C::Key) -> DatabaseKeyIndex {
         DatabaseKeyIndex {
             ingredient_index: self.index,
             key_index: k.as_id(),
         }
     }
 
+    /// See [`Configuration::QUERY_FINGERPRINT`].
+    pub fn query_fingerprint(&self) -> Fingerprint {
+        C::QUERY_FINGERPRINT
+    }
+
     pub fn set_capacity(&self, capacity: usize) {
         self.lru.set_capacity(capacity);
     }
 
+    /// Values older than `ttl` are treated as changed when they are next
+    /// validated in a new revision (see the `ttl` option of tracked functions).
+    pub fn set_ttl(&self, ttl: Option<std::time::Duration>) {
+        self.ttl.set_duration(ttl);
+    }
+
+    /// Keeps the values replaced by the last `depth` changes of each memo, so that
+    /// [`FunctionIngredient::value_as_of`] can find them (see the `history` option of
+    /// tracked functions). Zero, the default, keeps none.
+    pub fn set_history_depth(&self, depth: usize) {
+        for memo in self.history.set_depth(depth) {
+            self.deleted_entries.push(ArcSwap::from(memo));
+        }
+    }
+
+    /// Returns the value memoized for `key` that was up to date in `revision`, if it
+    /// is still known: either the current value, if it was computed or verified since
+    /// then, or one kept by the history. This does not execute or verify anything.
+    pub fn value_as_of(&self, key: C::Key, revision: Revision) -> Option<&C::Value> {
+        if let Some(memo) = self.memo_map.get(key) {
+            if memo.was_valid_in(revision) {
+                memo.mark_lent();
+                // Unsafety invariant: memo is present in memo_map.
+                return unsafe { self.extend_memo_lifetime(&memo) };
+            }
+        }
+        let memo = self.history.find(key.as_id(), revision)?;
+        // Unsafety invariant: memo is present in the history, which pushes the memos
+        // it drops to `deleted_entries`.
+        unsafe { self.extend_memo_lifetime(&memo) }
+    }
+
+    /// Returns every memoized value, with its key, the revision in which it last
+    /// changed and the revision in which it was last verified, ordered by key.
+    /// Values that were evicted are skipped. This does not execute or verify anything,
+    /// so a value verified before the current revision may be stale.
+    pub fn entries(&self) -> Vec<(C::Key, &C::Value, Revision, Revision)> {
+        let mut entries: Vec<_> = self
+            .memo_map
+            .memos()
+            .into_iter()
+            .filter_map(|(key, memo)| {
+                memo.mark_lent();
+                // Unsafety invariant: memo is present in memo_map.
+                let value = unsafe { self.extend_memo_lifetime(&memo) }?;
+                Some((
+                    key,
+                    value,
+                    memo.revisions.changed_at,
+                    memo.verified_at.load(),
+                ))
+            })
+            .collect();
+        entries.sort_by_key(|&(key, ..)| key.as_id());
+        entries
+    }
+
     /// Returns a reference to the memo value that lives as long as self.
     /// This is UNSAFE: the caller is responsible for ensuring that the
     /// memo will not be released so long as the `&self` is valid.
@@ -190,7 +385,13 @@ where
             // value is returned) and anything removed from map is added to deleted entries (ensured elsewhere).
             self.extend_memo_lifetime(&memo)
         };
-        if let Some(old_value) = self.memo_map.insert(key, memo) {
+        if let Some(old_value) = self.memo_map.insert(key, memo.clone()) {
+            if let Some(dropped) = self
+                .history
+                .record(key.as_id(), old_value.load_full(), &memo)
+            {
+                self.deleted_entries.push(ArcSwap::from(dropped));
+            }
             // In case there is a reference to the old memo out there, we have to store it
             // in the deleted entries. This will get cleared when a new revision starts.
             self.deleted_entries.push(old_value);
@@ -228,6 +429,15 @@ where
         self.origin(key)
     }
 
+    fn value_revisions(&self, key_index: Id) -> Option<ValueRevisions> {
+        let key = C::key_from_id(key_index);
+        self.memo_map.get(key).map(|memo| ValueRevisions {
+            changed_at: memo.revisions.changed_at,
+            verified_at: Some(memo.verified_at.load()),
+            durability: memo.revisions.durability,
+        })
+    }
+
     fn mark_validated_output(
         &self,
         db: &DB,
@@ -251,6 +461,8 @@ where
 
     fn reset_for_new_revision(&mut self) {
         std::mem::take(&mut self.deleted_entries);
+        self.sync_map.reset_for_new_revision();
+        C::prune_shared_values(&mut self.shared_values);
     }
 
     fn salsa_struct_deleted(&self, db: &DB, id: crate::Id) {
@@ -273,9 +485,57 @@ where
         }
     }
 
+    fn keys_verified_in(&self, revision: Revision) -> Vec<Id> {
+        self.keys_verified_in(revision)
+    }
+
+    fn keys_changed_since(&self, revision: Revision) -> Vec<Id> {
+        self.keys_changed_since(revision)
+    }
+
+    fn keys(&self) -> Vec<Id> {
+        self.memo_map
+            .memos()
+            .into_iter()
+            .map(|(key, _)| key.as_id())
+            .collect()
+    }
+
+    fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        self.sweep(is_live)
+    }
+
+    fn synthetic_write(
+        &mut self,
+        key_index: Option<Id>,
+        _revision: Revision,
+    ) -> Option<Durability> {
+        self.synthetic_write(key_index)
+    }
+
+    fn evict_values(&mut self, key_index: Option<Id>) -> usize {
+        self.evict_values(key_index)
+    }
+
+    fn compact(&mut self) {
+        self.compact()
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        self.usage()
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        self.eviction_candidates()
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
 }
 
 impl<C> IngredientRequiresReset for FunctionIngredient<C>
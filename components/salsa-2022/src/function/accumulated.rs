@@ -19,21 +19,68 @@ where
         DynDb<'db, C>: HasJar<A::Jar>,
         A: Accumulator,
     {
-        // To start, ensure that the value is up to date:
+        let mut result = vec![];
+        self.for_each_accumulated::<A>(db, key, |_, values| {
+            result.extend(values.iter().cloned());
+        });
+        A::finish_accumulated(&mut result);
+        result
+    }
+
+    /// Like [`Self::accumulated`], but pairs each value with the query that pushed it.
+    /// Values come in the order they were pushed by each query; the `dedup` and
+    /// `sort_by_key` options of the accumulator are not applied, as values that are
+    /// equal but pushed by different queries are told apart here.
+    pub fn accumulated_with_provenance<'db, A>(
+        &self,
+        db: &DynDb<'db, C>,
+        key: C::Key,
+    ) -> Vec<(DatabaseKeyIndex, A::Data)>
+    where
+        DynDb<'db, C>: HasJar<A::Jar>,
+        A: Accumulator,
+    {
+        let mut result = vec![];
+        self.for_each_accumulated::<A>(db, key, |query, values| {
+            result.extend(values.iter().map(|value| (query, value.clone())));
+        });
+        result
+    }
+
+    /// Calls `op` with each query this query transitively depends on (including
+    /// itself) and the values it accumulated into `A`.
+    fn for_each_accumulated<'db, A>(
+        &self,
+        db: &DynDb<'db, C>,
+        key: C::Key,
+        mut op: impl FnMut(DatabaseKeyIndex, &[A::Data]),
+    ) where
+        DynDb<'db, C>: HasJar<A::Jar>,
+        A: Accumulator,
+    {
+        // To start, ensure that the value is up to date (which also records
+        // that the current revision was observed):
         self.fetch(db, key);
 
         // Now walk over all the things that the value depended on
         // and find the values they accumulated into the given
-        // accumulator:
+        // accumulator. Only values pushed or validated in the current
+        // revision are returned, so each dependency is validated first:
+        // the value may have been validated by its durability alone,
+        // without visiting its dependencies. As the value is valid, so are
+        // they, and validating them is cheap.
         let runtime = db.runtime();
-        let mut result = vec![];
+        let current_revision = runtime.current_revision();
         let accumulator_ingredient = A::accumulator_ingredient(db);
-        let mut stack = Stack::new(self.database_key_index(key));
+        let root = self.database_key_index(key);
+        let mut stack = Stack::new(root);
         while let Some(input) = stack.pop() {
-            accumulator_ingredient.produced_by(runtime, input, &mut result);
+            if input != root {
+                db.maybe_changed_after(input.into(), current_revision);
+            }
+            accumulator_ingredient.produced_by(runtime, input, |values| op(input, values));
             stack.extend(db.origin(input));
         }
-        result
     }
 }
 
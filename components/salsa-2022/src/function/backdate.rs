@@ -1,4 +1,4 @@
-use crate::runtime::local_state::QueryRevisions;
+use crate::{runtime::local_state::QueryRevisions, Revision};
 
 use super::{memo::Memo, Configuration, FunctionIngredient};
 
@@ -9,28 +9,61 @@ where
     /// If the value/durability of this memo is equal to what is found in `revisions`/`value`,
     /// then updates `revisions.changed_at` to match `self.revisions.changed_at`. This is invoked
     /// on an old memo when a new memo has been produced to check whether there have been changed.
+    ///
+    /// Otherwise, makes sure `revisions.changed_at` is after the revision in which the old memo
+    /// was verified.
+    ///
+    /// If the value of the old memo was evicted, it can only be backdated if the old memo
+    /// was verified in the current revision: then its inputs did not change.
+    ///
+    /// If `reported_unchanged` (see [`crate::Runtime::report_unchanged`]), the values are
+    /// taken to be equal without comparing them, or even if the old one was evicted.
     pub(super) fn backdate_if_appropriate(
         &self,
         old_memo: &Memo<C::Value>,
         revisions: &mut QueryRevisions,
         value: &C::Value,
+        revision_now: Revision,
+        reported_unchanged: bool,
     ) {
+        // A provisional value (one computed from the provisional result of a
+        // fixed-point iteration in progress) is never backdated: its readers would
+        // otherwise consider themselves up-to-date with a value that may still change.
+        if !revisions.cycle_heads.is_empty() {
+            return;
+        }
+
         if let Some(old_value) = &old_memo.value {
             // Careful: if the value became less durable than it
             // used to be, that is a "breaking change" that our
             // consumers must be aware of. Becoming *more* durable
             // is not. See the test `constant_to_non_constant`.
             if revisions.durability >= old_memo.revisions.durability
-                && C::should_backdate_value(old_value, value)
+                && (reported_unchanged || C::should_backdate_value(old_value, value))
             {
                 log::debug!(
                     "value is equal, back-dating to {:?}",
                     old_memo.revisions.changed_at,
                 );
 
-                assert!(old_memo.revisions.changed_at <= revisions.changed_at);
+                // Usually, `old_memo.revisions.changed_at <= revisions.changed_at`, but not
+                // if the old value changed without any of its inputs changing (e.g., because
+                // it expired). Either way, the value is the same since `changed_at`.
                 revisions.changed_at = old_memo.revisions.changed_at;
+            } else if revisions.changed_at <= old_memo.verified_at.load() {
+                // The inputs tracked by salsa have not changed, so the value must have
+                // been invalidated by `Storage::synthetic_write_for`, or have expired.
+                // The queries that read the old value must still see that it changed.
+                revisions.changed_at = revision_now;
             }
+        } else if (reported_unchanged || old_memo.verified_at.load() == revision_now)
+            && revisions.durability >= old_memo.revisions.durability
+        {
+            log::debug!(
+                "old memo is still valid, back-dating to {:?}",
+                old_memo.revisions.changed_at,
+            );
+            revisions.changed_at = old_memo.revisions.changed_at;
         }
     }
 }
@@ -0,0 +1,43 @@
+use crate::storage::HasJarsDyn;
+
+use super::{Configuration, DynDb, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Executes the function for `key`, unless the backend set with
+    /// [`crate::Storage::set_cache_backend`] has a value stored at its fingerprint.
+    /// See [`crate::cache`].
+    pub(super) fn execute_cached(&self, db: &DynDb<C>, key: C::Key) -> C::Value {
+        if !C::CACHE {
+            return C::execute(db, key);
+        }
+        let backend = match db.runtime().cache_backend() {
+            Some(backend) => backend,
+            None => return C::execute(db, key),
+        };
+
+        // Reading what the fingerprint is computed from is what the value depends
+        // on, if it comes from the cache. The values stored by other versions of
+        // the function are at other fingerprints.
+        let fingerprint = C::cache_fingerprint(db, key).combine(C::QUERY_FINGERPRINT);
+        let cached = backend
+            .get(self.debug_name, fingerprint)
+            .and_then(|bytes| C::decode_value(&bytes));
+        if let Some(value) = cached {
+            log::debug!(
+                "{:?}: found in the cache at {:?}",
+                self.database_key_index(key),
+                fingerprint
+            );
+            return value;
+        }
+
+        let value = C::execute(db, key);
+        if let Some(bytes) = C::encode_value(&value) {
+            backend.put(self.debug_name, fingerprint, bytes);
+        }
+        value
+    }
+}
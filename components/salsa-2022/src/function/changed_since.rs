@@ -0,0 +1,21 @@
+use crate::{AsId, Id, Revision};
+
+use super::{Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Keys whose memo changed after `revision`, as of when it was last verified.
+    /// Provisional memos from a fixed-point iteration may still change, so they don't count.
+    pub(super) fn keys_changed_since(&self, revision: Revision) -> Vec<Id> {
+        self.memo_map
+            .memos()
+            .into_iter()
+            .filter(|(_, memo)| {
+                memo.revisions.changed_at > revision && memo.revisions.cycle_heads.is_empty()
+            })
+            .map(|(key, _)| key.as_id())
+            .collect()
+    }
+}
@@ -1,4 +1,6 @@
-use crate::runtime::local_state::QueryOrigin;
+use arc_swap::ArcSwap;
+
+use crate::{runtime::local_state::QueryOrigin, AsId};
 
 use super::{Configuration, FunctionIngredient};
 
@@ -9,6 +11,12 @@ where
     /// Removes the memoized value for `key` from the memo-map.
     /// Pushes the memo onto `deleted_entries` to ensure that any references into that memo which were handed out remain valid.
     pub(super) fn delete_memo(&self, key: C::Key) -> Option<QueryOrigin> {
+        self.ttl.forget(key.as_id());
+        self.pins.forget(key.as_id());
+        self.observers.forget(key.as_id());
+        for memo in self.history.forget(key.as_id()) {
+            self.deleted_entries.push(ArcSwap::from(memo));
+        }
         if let Some(memo) = self.memo_map.remove(key) {
             let origin = memo.load().revisions.origin.clone();
             self.deleted_entries.push(memo);
@@ -0,0 +1,27 @@
+use crate::{AsId, Id};
+
+use super::{Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Evicts the value of the memo for `key` (or, if `None`, of every memo): like
+    /// the LRU does, but with `&mut self`, and hence freeing the values right away.
+    /// Pinned memos are skipped.
+    pub(super) fn evict_values(&mut self, key: Option<Id>) -> usize {
+        std::mem::take(&mut self.deleted_entries);
+        let keys = match key {
+            Some(key) => vec![C::key_from_id(key)],
+            None => self
+                .memo_map
+                .memos()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect(),
+        };
+        keys.into_iter()
+            .filter(|&key| !self.pins.contains(key.as_id()) && self.memo_map.evict(key))
+            .count()
+    }
+}
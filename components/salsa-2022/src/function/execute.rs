@@ -1,10 +1,15 @@
-use std::sync::Arc;
+use std::{panic::AssertUnwindSafe, sync::Arc};
 
 use crate::{
+    cycle::CycleRecoveryStrategy,
     debug::DebugWithDb,
-    runtime::{local_state::ActiveQueryGuard, StampedValue},
+    runtime::{
+        local_state::{ActiveQueryGuard, QueryOrigin},
+        StampedValue,
+    },
     storage::HasJarsDyn,
-    Cycle, Database, Event, EventKind,
+    Cancelled, Cycle, Database, DatabaseKeyIndex, Durability, Event, EventKind, LimitExceeded,
+    QueryPanicked,
 };
 
 use super::{memo::Memo, Configuration, DynDb, FunctionIngredient};
@@ -33,6 +38,13 @@ where
         let database_key_index = active_query.database_key_index;
 
         log::info!("{:?}: executing query", database_key_index);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "execute",
+            query = ?database_key_index.debug(db),
+            revision = ?revision_now,
+        )
+        .entered();
 
         db.salsa_event(Event {
             runtime_id: runtime.id(),
@@ -40,39 +52,64 @@ where
                 database_key: database_key_index,
             },
         });
+        runtime
+            .statistics()
+            .record(database_key_index.ingredient_index(), C::PHASE, |counts| {
+                counts.executions += 1
+            });
+
+        // Times the execution, unless an execution of the same phase encloses it.
+        let _phase = C::PHASE.map(|phase| runtime.enter_phase(phase));
 
         // Query was not previously executed, or value is potentially
         // stale, or value is absent. Let's execute!
         let database_key_index = active_query.database_key_index;
         let key = C::key_from_id(database_key_index.key_index);
-        let value = match Cycle::catch(|| C::execute(db, key)) {
-            Ok(v) => v,
-            Err(cycle) => {
-                log::debug!(
-                    "{:?}: caught cycle {:?}, have strategy {:?}",
-                    database_key_index.debug(db),
-                    cycle,
-                    C::CYCLE_STRATEGY
-                );
-                match C::CYCLE_STRATEGY {
-                    crate::cycle::CycleRecoveryStrategy::Panic => cycle.throw(),
-                    crate::cycle::CycleRecoveryStrategy::Fallback => {
-                        if let Some(c) = active_query.take_cycle() {
-                            assert!(c.is(&cycle));
-                            C::recover_from_cycle(db, &cycle, key)
-                        } else {
-                            // we are not a participant in this cycle
-                            debug_assert!(!cycle
-                                .participant_keys()
-                                .any(|k| k == database_key_index));
-                            cycle.throw()
+        let value = self.poison_on_panic(database_key_index, || {
+            if C::CYCLE_STRATEGY == CycleRecoveryStrategy::FixedPoint {
+                self.execute_to_fixpoint(db, &active_query, key)
+            } else {
+                match Cycle::catch(|| self.execute_cached(db, key)) {
+                    Ok(v) => v,
+                    Err(cycle) => {
+                        log::debug!(
+                            "{:?}: caught cycle {:?}, have strategy {:?}",
+                            database_key_index.debug(db),
+                            cycle,
+                            C::CYCLE_STRATEGY
+                        );
+                        match C::CYCLE_STRATEGY {
+                            CycleRecoveryStrategy::Panic | CycleRecoveryStrategy::FixedPoint => {
+                                cycle.throw()
+                            }
+                            CycleRecoveryStrategy::Fallback => {
+                                if let Some(c) = active_query.take_cycle() {
+                                    assert!(c.is(&cycle));
+                                    C::recover_from_cycle(db, &cycle, key)
+                                } else {
+                                    // we are not a participant in this cycle
+                                    debug_assert!(!cycle
+                                        .participant_keys()
+                                        .any(|k| k == database_key_index));
+                                    cycle.throw()
+                                }
+                            }
                         }
                     }
                 }
             }
-        };
+        });
+        let value = C::share_value(&self.shared_values, value);
+        let reported_unchanged = active_query.reported_unchanged();
         let mut revisions = active_query.pop(runtime);
 
+        if self.ttl.is_enabled() {
+            // The value can expire in any revision, so whoever reads it
+            // must not skip validating it based on durability.
+            revisions.durability = Durability::LOW;
+            self.ttl.record_execution(database_key_index.key_index);
+        }
+
         // We assume that query is side-effect free -- that is, does
         // not mutate the "inputs" to the query system. Sanity check
         // that assumption here, at least to the best of our ability.
@@ -87,10 +124,39 @@ where
         // "backdate" its `changed_at` revision to be the same as the
         // old value.
         if let Some(old_memo) = &opt_old_memo {
-            self.backdate_if_appropriate(old_memo, &mut revisions, &value);
+            let changed_at = revisions.changed_at;
+            self.backdate_if_appropriate(
+                old_memo,
+                &mut revisions,
+                &value,
+                revision_now,
+                reported_unchanged,
+            );
+            if revisions.changed_at < changed_at {
+                runtime.statistics().record(
+                    database_key_index.ingredient_index(),
+                    C::PHASE,
+                    |counts| counts.backdates += 1,
+                );
+            }
+            if runtime.edge_profiling() {
+                if let (QueryOrigin::Derived(edges), QueryOrigin::Derived(old_edges)) =
+                    (&revisions.origin, &old_memo.revisions.origin)
+                {
+                    edges.inherit_costs(old_edges);
+                }
+            }
             self.diff_outputs(db, database_key_index, old_memo, &revisions);
+            self.notify_change(key, old_memo, &revisions, &value);
         }
 
+        let value = match opt_old_memo {
+            Some(old_memo) if C::UPDATE => {
+                self.update_old_value(key, old_memo, value, revision_now)
+            }
+            _ => value,
+        };
+
         let value = self
             .insert_memo(
                 db,
@@ -101,6 +167,9 @@ where
 
         let stamped_value = revisions.stamped_value(value);
 
+        // If our value is provisional, so is the value of whoever reads it.
+        runtime.report_cycle_heads(&revisions.cycle_heads);
+
         log::debug!(
             "{:?}: read_upgrade: result.revisions = {:#?}",
             database_key_index.debug(db),
@@ -109,4 +178,30 @@ where
 
         stamped_value
     }
+
+    /// Runs `compute`, which executes the query `database_key_index`. If it panics
+    /// (other than to unwind a cycle or because it was cancelled), records the panic
+    /// so that those waiting for the query, and any later fetch in this revision,
+    /// get a [`QueryPanicked`] rather than executing it again.
+    fn poison_on_panic<T>(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+        compute: impl FnOnce() -> T,
+    ) -> T {
+        match std::panic::catch_unwind(AssertUnwindSafe(compute)) {
+            Ok(value) => value,
+            Err(payload) => {
+                // Like cancellation, exceeding a limit says nothing of the value
+                // the query would have if executed from another, shallower query.
+                if !payload.is::<Cycle>()
+                    && !payload.is::<Cancelled>()
+                    && !payload.is::<LimitExceeded>()
+                {
+                    let panicked = QueryPanicked::new(database_key_index, &*payload);
+                    self.sync_map.poison(database_key_index.key_index, panicked);
+                }
+                std::panic::resume_unwind(payload)
+            }
+        }
+    }
 }
@@ -1,6 +1,9 @@
 use arc_swap::Guard;
 
-use crate::{database::AsSalsaDatabase, runtime::StampedValue, storage::HasJarsDyn, AsId};
+use crate::{
+    cycle::CycleRecoveryStrategy, database::AsSalsaDatabase, runtime::StampedValue,
+    storage::HasJarsDyn, AsId,
+};
 
 use super::{Configuration, DynDb, FunctionIngredient};
 
@@ -12,6 +15,7 @@ where
         let runtime = db.runtime();
 
         runtime.unwind_if_revision_cancelled(db);
+        runtime.report_revision_observed();
 
         let StampedValue {
             value,
@@ -19,8 +23,14 @@ where
             changed_at,
         } = self.compute_value(db, key);
 
-        if let Some(evicted) = self.lru.record_use(key.as_id()) {
-            self.evict(AsId::from_id(evicted));
+        // Pinned memos don't take up the capacity of the LRU; one that was pinned
+        // after its key was last used is kept when the LRU pops it.
+        if self.lru.is_enabled() && !self.pins.contains(key.as_id()) {
+            if let Some(evicted) = self.lru.record_use(key.as_id()) {
+                if !self.pins.contains(evicted) {
+                    self.evict(AsId::from_id(evicted));
+                }
+            }
         }
 
         db.runtime().report_tracked_read(
@@ -52,6 +62,7 @@ where
                         // Unsafety invariant: memo is present in memo_map
                         self.extend_memo_lifetime(memo).unwrap()
                     };
+                    runtime.report_cycle_heads(&memo.revisions.cycle_heads);
                     return Some(memo.revisions.stamped_value(value));
                 }
             }
@@ -63,23 +74,36 @@ where
         let runtime = db.runtime();
         let database_key_index = self.database_key_index(key);
 
+        // If we are re-entering a query that is iterating to a fixed point on this thread,
+        // we would have found its provisional value on the hot path if it had one.
+        if C::CYCLE_STRATEGY == CycleRecoveryStrategy::FixedPoint {
+            if let Some(iteration) = runtime.iteration_in_progress(database_key_index) {
+                return Some(self.initial_provisional_value(db, key, iteration));
+            }
+        }
+
         // Try to claim this query: if someone else has claimed it already, go back and start again.
         let _claim_guard = self
             .sync_map
             .claim(db.as_salsa_database(), database_key_index)?;
+        self.sync_map
+            .throw_if_poisoned(database_key_index.key_index);
 
         // Push the query on the stack.
         let active_query = runtime.push_query(database_key_index);
 
         // Now that we've claimed the item, check again to see if there's a "hot" value.
         // This time we can do a *deep* verify. Because this can recurse, don't hold the arcswap guard.
+        // If the value was evicted but the memo is still valid, the value is recomputed
+        // and then backdated, since it is the same as before.
         let opt_old_memo = self.memo_map.get(key).map(Guard::into_inner);
         if let Some(old_memo) = &opt_old_memo {
-            if old_memo.value.is_some() && self.deep_verify_memo(db, old_memo, &active_query) {
+            if self.deep_verify_memo(db, old_memo, &active_query) && old_memo.value.is_some() {
                 let value = unsafe {
                     // Unsafety invariant: memo is present in memo_map.
                     self.extend_memo_lifetime(old_memo).unwrap()
                 };
+                runtime.report_cycle_heads(&old_memo.revisions.cycle_heads);
                 return Some(old_memo.revisions.stamped_value(value));
             }
         }
@@ -88,6 +112,6 @@ where
     }
 
     fn evict(&self, key: C::Key) {
-        self.memo_map.evict(key);
+        let _ = self.memo_map.evict(key);
     }
 }
@@ -0,0 +1,137 @@
+use crate::{
+    cycle::{CycleHead, CycleRecoveryAction},
+    debug::DebugWithDb,
+    runtime::{
+        local_state::{ActiveQueryGuard, QueryEdges, QueryOrigin, QueryRevisions},
+        StampedValue,
+    },
+    storage::HasJarsDyn,
+    Database, Durability, Event, EventKind,
+};
+
+use super::{memo::Memo, Configuration, DynDb, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Executes the query function for a query with the `FixedPoint` cycle strategy.
+    ///
+    /// If the query reads its own (provisional) value while executing, the result
+    /// is compared to that provisional value; if they differ, the result becomes the
+    /// new provisional value and the query executes again, until the two agree or
+    /// `C::cycle_iterate` asks us to stop.
+    ///
+    /// Every iteration starts with a fresh `active_query` frame, so the edges that
+    /// remain once this returns are those of the final iteration.
+    pub(super) fn execute_to_fixpoint(
+        &self,
+        db: &DynDb<C>,
+        active_query: &ActiveQueryGuard<'_>,
+        key: C::Key,
+    ) -> C::Value {
+        let runtime = db.runtime();
+        let database_key_index = active_query.database_key_index;
+        let mut iteration = 0;
+
+        loop {
+            active_query.start_iteration(iteration);
+            let value = C::execute(db, key);
+
+            if !active_query.take_cycle_head() {
+                // Our provisional value was not read: there is no cycle (or no longer one).
+                return value;
+            }
+
+            let converged = match self.memo_map.get(key) {
+                Some(provisional_memo) => match &provisional_memo.value {
+                    Some(provisional_value) => C::should_backdate_value(provisional_value, &value),
+                    None => false,
+                },
+                None => false,
+            };
+            if converged {
+                log::debug!(
+                    "{:?}: converged after {} iteration(s)",
+                    database_key_index.debug(db),
+                    iteration + 1,
+                );
+                return value;
+            }
+
+            iteration += 1;
+            if iteration >= C::CYCLE_MAX_ITERATIONS {
+                panic!(
+                    "{:?}: execution did not converge after {} iterations",
+                    database_key_index.debug(db),
+                    C::CYCLE_MAX_ITERATIONS,
+                );
+            }
+
+            match C::cycle_iterate(db, &value, iteration, key) {
+                CycleRecoveryAction::Iterate => {}
+                CycleRecoveryAction::Fallback(value) => return value,
+            }
+
+            db.salsa_event(Event {
+                runtime_id: runtime.id(),
+                kind: EventKind::WillIterateCycle {
+                    database_key: database_key_index,
+                    iteration_count: iteration,
+                },
+            });
+
+            // The value we just computed is the provisional value for the next iteration.
+            let mut revisions = active_query.revisions(runtime);
+            revisions.cycle_heads.push(CycleHead {
+                database_key_index,
+                iteration,
+            });
+            self.insert_memo(
+                db,
+                key,
+                Memo::new(Some(value), runtime.current_revision(), revisions),
+            );
+        }
+    }
+
+    /// Invoked when `key` is re-entered while it is iterating to a fixed point
+    /// on this thread and there is no provisional value for `iteration` yet.
+    /// Stores and returns the initial provisional value from `C::cycle_initial`.
+    pub(super) fn initial_provisional_value(
+        &self,
+        db: &DynDb<C>,
+        key: C::Key,
+        iteration: u32,
+    ) -> StampedValue<&C::Value> {
+        let runtime = db.runtime();
+        let database_key_index = self.database_key_index(key);
+
+        log::debug!(
+            "{:?}: re-entered during iteration {}, using initial value",
+            database_key_index.debug(db),
+            iteration,
+        );
+
+        let value = C::cycle_initial(db, key);
+        let revisions = QueryRevisions {
+            changed_at: runtime.current_revision(),
+            durability: Durability::MAX,
+            origin: QueryOrigin::Derived(QueryEdges::new(runtime.empty_dependencies())),
+            cycle_heads: vec![CycleHead {
+                database_key_index,
+                iteration,
+            }],
+        };
+        runtime.report_cycle_heads(&revisions.cycle_heads);
+
+        let value = self
+            .insert_memo(
+                db,
+                key,
+                Memo::new(Some(value), runtime.current_revision(), revisions.clone()),
+            )
+            .unwrap();
+        revisions.stamped_value(value)
+    }
+}
@@ -0,0 +1,97 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use crossbeam_utils::atomic::AtomicCell;
+
+use crate::{hash::FxDashMap, Id, Revision};
+
+use super::memo::Memo;
+
+/// The memos replaced by newer ones, newest first, kept for up to `depth` changes
+/// of each value (see the `history` option of tracked functions).
+///
+/// References to the values of these memos are handed out with `&self`, so the
+/// memos removed from here are returned to the caller, which must keep them alive
+/// until a new revision starts (see `FunctionIngredient::deleted_entries`).
+pub(super) struct History<V> {
+    depth: AtomicCell<usize>,
+    memos: FxDashMap<Id, VecDeque<Arc<Memo<V>>>>,
+}
+
+impl<V> Default for History<V> {
+    fn default() -> Self {
+        Self {
+            depth: Default::default(),
+            memos: Default::default(),
+        }
+    }
+}
+
+impl<V> History<V> {
+    pub(super) fn is_enabled(&self) -> bool {
+        self.depth.load() > 0
+    }
+
+    /// Records that `old_memo`, the memo for `index`, was replaced by `new_memo`.
+    /// Only values that actually changed are worth keeping: a backdated memo
+    /// already covers the revisions of the memo it replaces.
+    pub(super) fn record(
+        &self,
+        index: Id,
+        old_memo: Arc<Memo<V>>,
+        new_memo: &Memo<V>,
+    ) -> Option<Arc<Memo<V>>> {
+        let depth = self.depth.load();
+        if depth == 0
+            || old_memo.value.is_none()
+            || !old_memo.revisions.cycle_heads.is_empty()
+            || new_memo.revisions.changed_at <= old_memo.revisions.changed_at
+        {
+            return None;
+        }
+
+        let mut memos = self.memos.entry(index).or_default();
+        memos.push_front(old_memo);
+        if memos.len() > depth {
+            memos.pop_back()
+        } else {
+            None
+        }
+    }
+
+    /// The newest memo for `index` that was valid in `revision`.
+    pub(super) fn find(&self, index: Id, revision: Revision) -> Option<Arc<Memo<V>>> {
+        let memos = self.memos.get(&index)?;
+        memos
+            .iter()
+            .find(|memo| memo.was_valid_in(revision))
+            .cloned()
+    }
+
+    pub(super) fn forget(&self, index: Id) -> Vec<Arc<Memo<V>>> {
+        match self.memos.remove(&index) {
+            Some((_, memos)) => memos.into(),
+            None => vec![],
+        }
+    }
+
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(Id) -> bool) {
+        self.memos.retain(|&index, _| keep(index));
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.memos.shrink_to_fit();
+    }
+
+    pub(super) fn set_depth(&self, depth: usize) -> Vec<Arc<Memo<V>>> {
+        self.depth.store(depth);
+
+        let mut removed = vec![];
+        for mut memos in self.memos.iter_mut() {
+            while memos.len() > depth {
+                removed.extend(memos.pop_back());
+            }
+        }
+        self.memos.retain(|_, memos| !memos.is_empty());
+        removed
+    }
+}
@@ -10,6 +10,10 @@ pub(super) struct Lru {
 }
 
 impl Lru {
+    pub(super) fn is_enabled(&self) -> bool {
+        self.capacity.load() != 0
+    }
+
     pub(super) fn record_use(&self, index: Id) -> Option<Id> {
         let capacity = self.capacity.load();
 
@@ -27,6 +31,10 @@ impl Lru {
         None
     }
 
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.set.get_mut().shrink_to_fit();
+    }
+
     pub(super) fn set_capacity(&self, capacity: usize) {
         self.capacity.store(capacity);
 
@@ -1,19 +1,27 @@
+use std::time::Instant;
+
 use arc_swap::Guard;
 
 use crate::{
+    cycle::CycleRecoveryStrategy,
     database::AsSalsaDatabase,
     debug::DebugWithDb,
-    key::DatabaseKeyIndex,
+    hash::FxHashSet,
+    key::{DatabaseKeyIndex, DependencyIndex},
     runtime::{
-        local_state::{ActiveQueryGuard, EdgeKind, QueryOrigin},
+        local_state::{ActiveQueryGuard, EdgeKind, QueryEdges, QueryOrigin},
         StampedValue,
     },
     storage::HasJarsDyn,
-    Revision, Runtime,
+    Database, Revision, Runtime,
 };
 
 use super::{memo::Memo, Configuration, DynDb, FunctionIngredient};
 
+/// How many queries may be verified, each verifying an input of the previous one,
+/// before [`verify_without_recursion`] takes over.
+const MAX_RECURSIVE_VERIFICATION_DEPTH: usize = 64;
+
 impl<C> FunctionIngredient<C>
 where
     C: Configuration,
@@ -26,6 +34,7 @@ where
     ) -> bool {
         let runtime = db.runtime();
         runtime.unwind_if_revision_cancelled(db);
+        runtime.report_revision_observed();
 
         loop {
             let database_key_index = self.database_key_index(key);
@@ -46,7 +55,9 @@ where
                 if let Some(mcs) = self.maybe_changed_after_cold(db, key, revision) {
                     return mcs;
                 } else {
-                    // We failed to claim, have to retry.
+                    // Another thread had claimed the query, and we were blocked until
+                    // it completed (see `SyncMap::claim`), so this is no busy loop.
+                    // Retry: we typically find the memo it verified on the hot path.
                 }
             } else {
                 // No memo? Assume has changed.
@@ -64,9 +75,19 @@ where
         let runtime = db.runtime();
         let database_key_index = self.database_key_index(key_index);
 
+        // A query that is iterating to a fixed point on this thread has no final value yet;
+        // conservatively report it as changed rather than treating this as a cycle.
+        if C::CYCLE_STRATEGY == CycleRecoveryStrategy::FixedPoint
+            && runtime.iteration_in_progress(database_key_index).is_some()
+        {
+            return Some(true);
+        }
+
         let _claim_guard = self
             .sync_map
             .claim(db.as_salsa_database(), database_key_index)?;
+        self.sync_map
+            .throw_if_poisoned(database_key_index.key_index);
         let active_query = runtime.push_query(database_key_index);
 
         // Load the current memo, if any. Use a real arc, not an arc-swap guard,
@@ -119,6 +140,20 @@ where
             database_key_index.debug(db),
             memo,
         );
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "shallow_verify_memo",
+            query = ?database_key_index.debug(db),
+            revision = ?revision_now,
+            verified_at = ?verified_at,
+        )
+        .entered();
+
+        if !memo.revisions.cycle_heads.is_empty() {
+            // A provisional memo is only valid for the fixed-point iterations it was computed in.
+            return verified_at == revision_now
+                && runtime.cycle_heads_in_progress(&memo.revisions.cycle_heads);
+        }
 
         if verified_at == revision_now {
             // Already verified.
@@ -127,7 +162,12 @@ where
 
         if memo.check_durability(runtime) {
             // No input of the suitable durability has changed since last verified.
-            memo.mark_as_verified(db.as_salsa_database(), runtime, database_key_index);
+            memo.mark_as_verified(
+                db.as_salsa_database(),
+                runtime,
+                database_key_index,
+                C::PHASE,
+            );
             return true;
         }
 
@@ -156,11 +196,24 @@ where
             database_key_index.debug(db),
             old_memo
         );
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "deep_verify_memo",
+            query = ?database_key_index.debug(db),
+            revision = ?runtime.current_revision(),
+            verified_at = ?old_memo.verified_at.load(),
+        )
+        .entered();
 
         if self.shallow_verify_memo(db, runtime, database_key_index, old_memo) {
             return true;
         }
 
+        if !old_memo.revisions.cycle_heads.is_empty() {
+            // Provisional memos are never valid once their iteration is over.
+            return false;
+        }
+
         match &old_memo.revisions.origin {
             QueryOrigin::Assigned(_) => {
                 // If the value was assigneed by another query,
@@ -178,6 +231,10 @@ where
                 // Untracked inputs? Have to assume that it changed.
                 return false;
             }
+            QueryOrigin::Derived(_) if self.ttl.is_expired(database_key_index.key_index) => {
+                // The value is too old to trust, even if its inputs haven't changed.
+                return false;
+            }
             QueryOrigin::Derived(edges) => {
                 // Fully tracked inputs? Iterate over the inputs and check them, one by one.
                 //
@@ -186,10 +243,32 @@ where
                 // valid, then some later input I1 might never have executed at all, so verifying
                 // it is still up to date is meaningless.
                 let last_verified_at = old_memo.verified_at.load();
-                for &(edge_kind, dependency_index) in edges.input_outputs.iter() {
+                let profiling = runtime.edge_profiling();
+                if profiling {
+                    edges.start_recording_costs();
+                }
+                for (position, &(edge_kind, dependency_index)) in
+                    edges.input_outputs.iter().enumerate()
+                {
                     match edge_kind {
                         EdgeKind::Input => {
-                            if db.maybe_changed_after(dependency_index, last_verified_at) {
+                            if position == 0 || edges.input_outputs[position - 1].0 != edge_kind {
+                                self.verify_inputs_in_parallel(
+                                    db,
+                                    &edges.input_outputs[position..],
+                                    last_verified_at,
+                                );
+                            }
+                            let start = profiling.then(Instant::now);
+                            if runtime.query_depth() >= MAX_RECURSIVE_VERIFICATION_DEPTH {
+                                verify_without_recursion(db.as_salsa_database(), dependency_index);
+                            }
+                            let changed =
+                                db.maybe_changed_after(dependency_index, last_verified_at);
+                            if let Some(start) = start {
+                                edges.record_cost(position, start.elapsed());
+                            }
+                            if changed {
                                 return false;
                             }
                         }
@@ -217,7 +296,82 @@ where
             }
         }
 
-        old_memo.mark_as_verified(db.as_salsa_database(), runtime, database_key_index);
+        old_memo.mark_as_verified(
+            db.as_salsa_database(),
+            runtime,
+            database_key_index,
+            C::PHASE,
+        );
+        self.shadow_verify_memo(db, old_memo, active_query);
         true
     }
 }
+
+/// Verifies the memo at `root`, if it is one not yet verified in this revision, and
+/// those it depends on, without the recursion of `deep_verify_memo`, which could
+/// overflow the stack for long chains of dependencies.
+///
+/// The dependencies are walked with an explicit stack and verified from the bottom
+/// up, so that each `maybe_changed_after` finds the inputs of its memo verified and
+/// returns without recursing. The edges of each memo are visited in order, as
+/// `deep_verify_memo` does (stopping at the first input that changed, and marking
+/// the outputs until then as validated), so the same queries are verified, or
+/// re-executed, as with the recursion.
+fn verify_without_recursion(db: &dyn Database, root: DependencyIndex) {
+    /// A memo whose edges are visited up to `position`.
+    struct Frame {
+        key: DatabaseKeyIndex,
+        edges: QueryEdges,
+        verified_at: Revision,
+        position: usize,
+    }
+
+    let current_revision = db.runtime().current_revision();
+    // The frame of the memo at `index`, if it has yet to be verified.
+    let frame = |index: DependencyIndex| -> Option<Frame> {
+        let key = DatabaseKeyIndex::try_from(index).ok()?;
+        let verified_at = db.value_revisions(key)?.verified_at?;
+        match db.origin(key)? {
+            QueryOrigin::Derived(edges) if verified_at != current_revision => Some(Frame {
+                key,
+                edges,
+                verified_at,
+                position: 0,
+            }),
+            _ => None,
+        }
+    };
+
+    let mut stack: Vec<Frame> = frame(root).into_iter().collect();
+    let mut on_stack: FxHashSet<DatabaseKeyIndex> = stack.iter().map(|f| f.key).collect();
+    while let Some(top) = stack.last_mut() {
+        let done = match top.edges.input_outputs.get(top.position).copied() {
+            None => true,
+            Some((EdgeKind::Output, output)) => {
+                db.mark_validated_output(top.key, output);
+                top.position += 1;
+                false
+            }
+            Some((EdgeKind::Input, input)) => match frame(input) {
+                // Verify the input first, then come back to this edge.
+                Some(child) if on_stack.insert(child.key) => {
+                    stack.push(child);
+                    false
+                }
+                // The input is verified (or part of a cycle, which is left to
+                // `maybe_changed_after` to report), so this does not recurse far.
+                _ => {
+                    top.position += 1;
+                    db.maybe_changed_after(input, top.verified_at)
+                }
+            },
+        };
+        if done {
+            // All inputs are verified, or one of them changed: either way,
+            // verifying the memo itself is now shallow.
+            let top = stack.pop().unwrap();
+            on_stack.remove(&top.key);
+            db.maybe_changed_after(top.key.into(), top.verified_at);
+        }
+    }
+}
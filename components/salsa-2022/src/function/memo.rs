@@ -1,18 +1,21 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use arc_swap::{ArcSwap, Guard};
 use crossbeam_utils::atomic::AtomicCell;
 
 use crate::{
-    hash::FxDashMap, key::DatabaseKeyIndex, runtime::local_state::QueryRevisions, AsId, Event,
-    EventKind, Revision, Runtime,
+    hash::TableDashMap, key::DatabaseKeyIndex, runtime::local_state::QueryRevisions, AsId,
+    Durability, Event, EventKind, Revision, Runtime,
 };
 
 /// The memo map maps from a key of type `K` to the memoized value for that `K`.
 /// The memoized value is a `Memo<V>` which contains, in addition to the value `V`,
 /// dependency information.
 pub(super) struct MemoMap<K: AsId, V> {
-    map: FxDashMap<K, ArcSwap<Memo<V>>>,
+    map: TableDashMap<K, ArcSwap<Memo<V>>>,
 }
 
 impl<K: AsId, V> Default for MemoMap<K, V> {
@@ -24,6 +27,17 @@ impl<K: AsId, V> Default for MemoMap<K, V> {
 }
 
 impl<K: AsId, V> MemoMap<K, V> {
+    /// Creates an empty map with `shards` shards (a power of two), or the default
+    /// number if `shards` is zero.
+    pub(super) fn with_shards(shards: usize) -> Self {
+        if shards == 0 {
+            return Self::default();
+        }
+        Self {
+            map: TableDashMap::with_hasher_and_shard_amount(Default::default(), shards),
+        }
+    }
+
     /// Inserts the memo for the given key; (atomically) overwrites any previously existing memo.-
     #[must_use]
     pub(super) fn insert(&self, key: K, memo: Arc<Memo<V>>) -> Option<ArcSwap<Memo<V>>> {
@@ -43,10 +57,32 @@ impl<K: AsId, V> MemoMap<K, V> {
         self.map.get(&key).map(|v| v.load())
     }
 
+    /// Returns the current memo for each key, in no particular order.
+    pub(super) fn memos(&self) -> Vec<(K, Arc<Memo<V>>)> {
+        self.map
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().load_full()))
+            .collect()
+    }
+
+    /// Removes every memo whose key is rejected by `keep`, returning how many were removed.
+    /// Requires `&mut self`, so no references into the removed memos can remain.
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(K, &Memo<V>) -> bool) -> usize {
+        let len = self.map.len();
+        self.map.retain(|key, memo| keep(*key, &memo.load()));
+        len - self.map.len()
+    }
+
+    /// Shrinks the map to the memos it stores now.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.map.shrink_to_fit();
+    }
+
     /// Evicts the existing memo for the given key, replacing it
     /// with an equivalent memo that has no value. If the memo is untracked, BaseInput,
     /// or has values assigned as output of another query, this has no effect.
-    pub(super) fn evict(&self, key: K) {
+    /// Returns true if a value was evicted.
+    pub(super) fn evict(&self, key: K) -> bool {
         use crate::runtime::local_state::QueryOrigin;
         use dashmap::mapref::entry::Entry::*;
 
@@ -60,9 +96,13 @@ impl<K: AsId, V> MemoMap<K, V> {
                     // assigned as output of another query
                     // or those with untracked inputs
                     // as their values cannot be reconstructed.
+                    false
                 }
 
                 QueryOrigin::Derived(_) => {
+                    if memo.value.is_none() {
+                        return false;
+                    }
                     let memo_evicted = Arc::new(Memo::new(
                         None::<V>,
                         memo.verified_at.load(),
@@ -70,10 +110,79 @@ impl<K: AsId, V> MemoMap<K, V> {
                     ));
 
                     entry.get().store(memo_evicted);
+                    true
+                }
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Takes the value of the memo for `key`, replacing it with an equivalent memo
+    /// that has no value, provided that no one else holds the memo, that it was last
+    /// verified before `revision_now` and that its value was never lent out (see
+    /// [`Memo::mark_lent`]). The references to the value handed out when it
+    /// was verified, in earlier revisions, are gone, so no one can read it anymore.
+    pub(super) fn take_value(&self, key: K, revision_now: Revision) -> Option<V> {
+        use dashmap::mapref::entry::Entry::*;
+
+        if let Occupied(mut entry) = self.map.entry(key) {
+            let memo = entry.get().load();
+            if memo.value.is_none()
+                || memo.verified_at.load() >= revision_now
+                || memo.lent.load(Ordering::Acquire)
+            {
+                return None;
+            }
+            let memo_taken = Arc::new(Memo::new(
+                None::<V>,
+                memo.verified_at.load(),
+                memo.revisions.clone(),
+            ));
+            drop(memo);
+            let memo = std::mem::replace(entry.get_mut(), ArcSwap::from(memo_taken)).into_inner();
+            match Arc::try_unwrap(memo) {
+                Ok(memo) => memo.value,
+                Err(memo) => {
+                    // This is the same memo, which no one could see missing,
+                    // as the entry is locked.
+                    *entry.get_mut() = ArcSwap::from(memo);
+                    None
                 }
             }
+        } else {
+            None
         }
     }
+
+    /// Replaces the memo for `key`, if it was derived, with one whose origin is
+    /// untracked but which still has the old value, so that the value can be
+    /// backdated when it is re-executed. Returns the durability of the memo.
+    /// Requires `&mut self`, so that no one else should hold the old memo.
+    pub(super) fn mark_untracked(&mut self, key: K) -> Option<Durability> {
+        use crate::runtime::local_state::QueryOrigin;
+
+        let memo = self.remove(key)?.into_inner();
+        let durability = memo.revisions.durability;
+        let (memo, durability) = match &memo.revisions.origin {
+            QueryOrigin::Derived(edges) => {
+                let origin = QueryOrigin::DerivedUntracked(edges.clone());
+                let mut memo = match Arc::try_unwrap(memo) {
+                    Ok(memo) => memo,
+                    // Someone still holds the memo, so its value cannot be taken;
+                    // without one, the memo is as good as deleted.
+                    Err(_) => return Some(durability),
+                };
+                memo.revisions.origin = origin;
+                (Arc::new(memo), Some(durability))
+            }
+            QueryOrigin::DerivedUntracked(_) => (memo, Some(durability)),
+            // Values that were assigned or stored are never re-executed.
+            QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => (memo, None),
+        };
+        let _ = self.insert(key, memo);
+        durability
+    }
 }
 
 #[derive(Debug)]
@@ -87,6 +196,10 @@ pub(super) struct Memo<V> {
 
     /// Revision information
     pub(super) revisions: QueryRevisions,
+
+    /// True if a reference to the value may have been handed out without the
+    /// memo being verified first, so that it may still be alive in any revision.
+    lent: AtomicBool,
 }
 
 impl<V> Memo<V> {
@@ -95,8 +208,25 @@ impl<V> Memo<V> {
             value,
             verified_at: AtomicCell::new(revision_now),
             revisions,
+            lent: AtomicBool::new(false),
         }
     }
+
+    /// Records that a reference to the value is handed out without verifying the
+    /// memo first (as [`FunctionIngredient::entries`](super::FunctionIngredient::entries)
+    /// does), so that the value must not be taken from the memo anymore.
+    pub(super) fn mark_lent(&self) {
+        self.lent.store(true, Ordering::Release);
+    }
+
+    /// True if this memo is known to have been up to date in `revision`, i.e., its
+    /// value was the value of the query then. Provisional values never were.
+    pub(super) fn was_valid_in(&self, revision: Revision) -> bool {
+        self.revisions.changed_at <= revision
+            && revision <= self.verified_at.load()
+            && self.revisions.cycle_heads.is_empty()
+    }
+
     /// True if this memo is known not to have changed based on its durability.
     pub(super) fn check_durability(&self, runtime: &Runtime) -> bool {
         let last_changed = runtime.last_changed_revision(self.revisions.durability);
@@ -111,12 +241,14 @@ impl<V> Memo<V> {
     }
 
     /// Mark memo as having been verified in the `revision_now`, which should
-    /// be the current revision.
+    /// be the current revision. The validation counts towards `phase`, the
+    /// phase of the function, if it has one.
     pub(super) fn mark_as_verified(
         &self,
         db: &dyn crate::Database,
         runtime: &crate::Runtime,
         database_key_index: DatabaseKeyIndex,
+        phase: Option<&'static str>,
     ) {
         db.salsa_event(Event {
             runtime_id: runtime.id(),
@@ -126,6 +258,11 @@ impl<V> Memo<V> {
         });
 
         self.verified_at.store(runtime.current_revision());
+        runtime
+            .statistics()
+            .record(database_key_index.ingredient_index(), phase, |counts| {
+                counts.validations += 1
+            });
 
         // Also mark the outputs as verified
         for output in self.revisions.origin.outputs() {
@@ -0,0 +1,60 @@
+use crate::{runtime::local_state::QueryOrigin, Durability, Revision};
+
+use super::{Configuration, FunctionIngredient};
+
+/// What is known of the memoized value of a tracked function for some key,
+/// returned by the `memo_info` function generated for it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoInfo {
+    /// The lowest durability of the inputs the value was computed from: a change
+    /// to an input of that durability (or a lower one) makes salsa verify it again.
+    pub durability: Durability,
+
+    /// The revision in which the value last changed.
+    pub changed_at: Revision,
+
+    /// The revision in which the value was last verified (or computed).
+    pub verified_at: Revision,
+
+    /// How the value came to be.
+    pub origin_kind: OriginKind,
+}
+
+/// How a memoized value came to be; see [`MemoInfo`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OriginKind {
+    /// Executing the function, which read tracked inputs only.
+    Derived,
+
+    /// Executing the function, which also read untracked inputs (e.g., it is
+    /// `volatile`): the value is computed again in each revision it is used in.
+    DerivedUntracked,
+
+    /// Assigned by another query, with `specify`.
+    Assigned,
+
+    /// Set with the `set` function of the tracked function.
+    BaseInput,
+}
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// See [`MemoInfo`].
+    pub fn memo_info(&self, key: C::Key) -> Option<MemoInfo> {
+        let memo = self.memo_map.get(key)?;
+        let origin_kind = match &memo.revisions.origin {
+            QueryOrigin::Derived(_) => OriginKind::Derived,
+            QueryOrigin::DerivedUntracked(_) => OriginKind::DerivedUntracked,
+            QueryOrigin::Assigned(_) => OriginKind::Assigned,
+            QueryOrigin::BaseInput => OriginKind::BaseInput,
+        };
+        Some(MemoInfo {
+            durability: memo.revisions.durability,
+            changed_at: memo.revisions.changed_at,
+            verified_at: memo.verified_at.load(),
+            origin_kind,
+        })
+    }
+}
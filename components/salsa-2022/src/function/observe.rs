@@ -0,0 +1,80 @@
+use crate::{hash::FxDashMap, runtime::local_state::QueryRevisions, AsId, Id};
+
+use super::{memo::Memo, Configuration, FunctionIngredient};
+
+/// A callback given to [`FunctionIngredient::on_change`].
+type OnChange<V> = Box<dyn Fn(&V, &V) + Send + Sync>;
+
+/// The callbacks registered for each key with [`FunctionIngredient::on_change`].
+pub(super) struct Observers<V> {
+    callbacks: FxDashMap<Id, Vec<OnChange<V>>>,
+}
+
+impl<V> Default for Observers<V> {
+    fn default() -> Self {
+        Self {
+            callbacks: Default::default(),
+        }
+    }
+}
+
+impl<V> Observers<V> {
+    pub(super) fn forget(&self, index: Id) {
+        self.callbacks.remove(&index);
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.callbacks.shrink_to_fit();
+    }
+}
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Registers `callback`, which is invoked with the old and the new value each
+    /// time the value for `key` is replaced by one that is not backdated, i.e.,
+    /// that actually changed. Values that are not memoized yet (or were evicted)
+    /// have no old value, so they do not invoke it.
+    ///
+    /// The callback is invoked while the query is executing, so it must not use the
+    /// database. It is kept until the salsa struct of `key` is deleted.
+    pub fn on_change(
+        &self,
+        key: C::Key,
+        callback: impl Fn(&C::Value, &C::Value) + Send + Sync + 'static,
+    ) {
+        self.observers
+            .callbacks
+            .entry(key.as_id())
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    /// Invokes the callbacks registered for `key` if `new_value`, whose revisions
+    /// (once backdated) are `revisions`, changed from the value of `old_memo`.
+    /// Provisional values are part of a computation that is not done yet.
+    pub(super) fn notify_change(
+        &self,
+        key: C::Key,
+        old_memo: &Memo<C::Value>,
+        revisions: &QueryRevisions,
+        new_value: &C::Value,
+    ) {
+        let old_value = match &old_memo.value {
+            Some(old_value) => old_value,
+            None => return,
+        };
+        if revisions.changed_at <= old_memo.revisions.changed_at
+            || !revisions.cycle_heads.is_empty()
+            || !old_memo.revisions.cycle_heads.is_empty()
+        {
+            return;
+        }
+        if let Some(callbacks) = self.observers.callbacks.get(&key.as_id()) {
+            for callback in callbacks.iter() {
+                callback(old_value, new_value);
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crossbeam::channel;
+
+use crate::{key::DependencyIndex, runtime::local_state::EdgeKind, storage::HasJarsDyn, Revision};
+
+use super::{Configuration, DynDb, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Verifies the inputs at the start of `edges`, up to the first output, on the
+    /// workers of the database, if it verifies that many inputs in parallel (see
+    /// [`crate::Storage::set_parallel_verification`]). This only warms up their memos:
+    /// `deep_verify_memo` still decides, visiting the edges in order, whether anything
+    /// changed. The inputs after an output are left for later, as they may read it
+    /// before `deep_verify_memo` marks it as validated.
+    pub(super) fn verify_inputs_in_parallel(
+        &self,
+        db: &DynDb<C>,
+        edges: &[(EdgeKind, DependencyIndex)],
+        last_verified_at: Revision,
+    ) {
+        let len = edges
+            .iter()
+            .take_while(|(edge_kind, _)| *edge_kind == EdgeKind::Input)
+            .count();
+        let forks = db.verification_forks(len);
+        if forks.is_empty() {
+            return;
+        }
+        let inputs = &edges[..len];
+
+        // The position of the first input found to have changed: the inputs after it
+        // are not worth verifying, since the query may not read them anymore.
+        let first_changed = Arc::new(AtomicUsize::new(usize::MAX));
+        // Each job holds a sender, so `done` disconnects once all of them are done.
+        let (done_sender, done) = channel::bounded::<()>(0);
+        let chunk_len = inputs.len().div_ceil(forks.len());
+        for (chunk_index, (fork, chunk)) in
+            forks.into_iter().zip(inputs.chunks(chunk_len)).enumerate()
+        {
+            let chunk: Vec<DependencyIndex> = chunk.iter().map(|&(_, input)| input).collect();
+            let first_changed = first_changed.clone();
+            let done_sender = done_sender.clone();
+            db.run_verification(Box::new(move || {
+                let start = chunk_index * chunk_len;
+                for (offset, &input) in chunk.iter().enumerate() {
+                    let position = start + offset;
+                    if first_changed.load(Ordering::Relaxed) < position {
+                        break;
+                    }
+                    // If verifying this input panics, so will `deep_verify_memo`
+                    // unless an earlier input changed.
+                    let changed = panic::catch_unwind(AssertUnwindSafe(|| {
+                        fork.maybe_changed_after(input, last_verified_at)
+                    }))
+                    .unwrap_or(true);
+                    if changed {
+                        first_changed.fetch_min(position, Ordering::Relaxed);
+                        break;
+                    }
+                }
+                // The fork must be dropped before the memo is, so before we are done.
+                drop(fork);
+                drop(done_sender);
+            }));
+        }
+        drop(done_sender);
+        let _ = done.recv();
+    }
+}
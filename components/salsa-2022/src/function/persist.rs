@@ -0,0 +1,84 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    cache::Fingerprint,
+    persist::PersistentIngredient,
+    runtime::local_state::{QueryOrigin, QueryRevisions},
+    AsId, Id, IngredientIndex, Revision,
+};
+
+use super::{memo::Memo, memo::MemoMap, Configuration, FunctionIngredient};
+
+/// Serialized form of a [`Memo`].
+#[derive(Serialize, Deserialize)]
+struct PersistedMemo<V> {
+    value: Option<V>,
+    verified_at: Revision,
+    revisions: QueryRevisions,
+}
+
+impl<C> PersistentIngredient for FunctionIngredient<C>
+where
+    C: Configuration,
+    C::Value: Serialize + DeserializeOwned,
+{
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn query_fingerprint(&self) -> Option<Fingerprint> {
+        Some(C::QUERY_FINGERPRINT)
+    }
+
+    fn serialize(
+        &self,
+        is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value> {
+        let mut memos = self
+            .memo_map
+            .memos()
+            .into_iter()
+            // Provisional memos (see `CycleRecoveryStrategy::FixedPoint`) can never be reused later.
+            .filter(|(_, memo)| memo.revisions.cycle_heads.is_empty())
+            .filter(|(_, memo)| can_validate_after_restore(&memo.revisions.origin, is_persistent))
+            .map(|(key, memo)| {
+                let persisted_memo = PersistedMemo {
+                    value: memo.value.as_ref(),
+                    verified_at: memo.verified_at.load(),
+                    revisions: memo.revisions.clone(),
+                };
+                Ok((key.as_id(), serde_json::to_value(persisted_memo)?))
+            })
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        memos.sort_by_key(|(id, _)| *id);
+        serde_json::to_value(memos)
+    }
+
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()> {
+        let memos: Vec<(Id, PersistedMemo<C::Value>)> = serde_json::from_value(data)?;
+        self.memo_map = MemoMap::with_shards(C::MEMO_MAP_SHARDS);
+        for (id, memo) in memos {
+            let memo = Memo::new(memo.value, memo.verified_at, memo.revisions);
+            let old_memo = self.memo_map.insert(C::key_from_id(id), memo.into());
+            assert!(old_memo.is_none(), "persisted memos have distinct keys");
+        }
+        Ok(())
+    }
+}
+
+/// A memo can only be used after a restore if everything needed to validate
+/// it is restored too.
+fn can_validate_after_restore(
+    origin: &QueryOrigin,
+    is_persistent: &dyn Fn(IngredientIndex) -> bool,
+) -> bool {
+    match origin {
+        QueryOrigin::BaseInput => true,
+        QueryOrigin::Assigned(by_query) => is_persistent(by_query.ingredient_index),
+        QueryOrigin::Derived(edges) => edges
+            .input_outputs
+            .iter()
+            .all(|(_, dependency_index)| is_persistent(dependency_index.ingredient_index)),
+        QueryOrigin::DerivedUntracked(_) => false,
+    }
+}
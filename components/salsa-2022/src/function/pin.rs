@@ -0,0 +1,53 @@
+use crate::{hash::FxHashSet, AsId, Id};
+
+use parking_lot::Mutex;
+
+use super::{Configuration, FunctionIngredient};
+
+/// The keys whose memos are pinned, see [`FunctionIngredient::pin`].
+#[derive(Default)]
+pub(super) struct Pins {
+    keys: Mutex<FxHashSet<Id>>,
+}
+
+impl Pins {
+    pub(super) fn contains(&self, index: Id) -> bool {
+        self.keys.lock().contains(&index)
+    }
+
+    pub(super) fn keys(&self) -> Vec<Id> {
+        self.keys.lock().iter().copied().collect()
+    }
+
+    pub(super) fn forget(&self, index: Id) {
+        self.keys.lock().remove(&index);
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.keys.get_mut().shrink_to_fit();
+    }
+}
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Exempts the memo for `key` from eviction until it is unpinned: the LRU,
+    /// [`crate::Storage::evict_values`], the memory budget, and [`crate::Storage::sweep`]
+    /// (which keeps what the memo depends on, too) all keep it. The memo is still
+    /// verified and re-executed as usual. Pinning a key that has no memo yet
+    /// applies to the memo once it is computed; deleting the tracked struct that
+    /// is the key drops the pin with the memo.
+    pub fn pin(&self, key: C::Key) {
+        self.pins.keys.lock().insert(key.as_id());
+    }
+
+    /// Makes the memo for `key` evictable again, see [`FunctionIngredient::pin`].
+    pub fn unpin(&self, key: C::Key) {
+        self.pins.forget(key.as_id());
+    }
+
+    pub fn is_pinned(&self, key: C::Key) -> bool {
+        self.pins.contains(key.as_id())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{runtime::local_state::QueryOrigin, Durability};
+
+use super::{Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Removes the memo for `key` (or, if `None`, every memo), dependencies and all,
+    /// so that its value is recomputed the next time it is fetched; the caller must
+    /// then report a write of the returned durability, the highest of the memos
+    /// removed, so that the queries that read them are re-validated. Values that
+    /// were set or specified cannot be recomputed, so they are kept.
+    pub fn purge(&mut self, key: Option<C::Key>) -> Option<Durability> {
+        std::mem::take(&mut self.deleted_entries);
+        let mut durability = None;
+        self.memo_map.retain(|memo_key, memo| {
+            let purged = key.is_none_or(|key| key == memo_key)
+                && matches!(
+                    memo.revisions.origin,
+                    QueryOrigin::Derived(_) | QueryOrigin::DerivedUntracked(_)
+                );
+            if purged {
+                durability = durability.max(Some(memo.revisions.durability));
+            }
+            !purged
+        });
+        let memo_map = &self.memo_map;
+        self.ttl
+            .retain(|key_index| memo_map.get(C::key_from_id(key_index)).is_some());
+        self.history
+            .retain(|key_index| memo_map.get(C::key_from_id(key_index)).is_some());
+        durability
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    cycle::CycleRecoveryStrategy,
+    debug::DebugWithDb,
+    runtime::local_state::{ActiveQueryGuard, EdgeKind, QueryOrigin},
+    storage::HasJarsDyn,
+};
+
+use super::{memo::Memo, Configuration, DynDb, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// With shadow verification enabled (see [`crate::Storage::set_shadow_verification`]),
+    /// executes the function again for a memo that `deep_verify_memo` just found to be
+    /// up to date, and panics if the result differs from the memoized value.
+    ///
+    /// Must be called after the memo is marked as verified, so that the execution
+    /// does not see the query as still being verified, and with `active_query`
+    /// still on the stack: it collects the dependencies of the execution, which
+    /// are dropped with it.
+    pub(super) fn shadow_verify_memo(
+        &self,
+        db: &DynDb<C>,
+        memo: &Memo<C::Value>,
+        active_query: &ActiveQueryGuard<'_>,
+    ) {
+        let runtime = db.runtime();
+        if !runtime.shadow_verification() {
+            return;
+        }
+
+        // Executing the function again would repeat its outputs (tracked structs,
+        // specified values, accumulated values), and the value of a cycle participant
+        // depends on where the cycle was entered, so these are not checked.
+        if C::CYCLE_STRATEGY != CycleRecoveryStrategy::Panic {
+            return;
+        }
+        match &memo.revisions.origin {
+            QueryOrigin::Derived(edges)
+                if edges
+                    .input_outputs
+                    .iter()
+                    .all(|&(edge_kind, _)| edge_kind == EdgeKind::Input) => {}
+            _ => return,
+        }
+        let old_value = match &memo.value {
+            Some(value) => value,
+            None => return,
+        };
+
+        // Values not equal to themselves (e.g., of `no_eq` functions) can't be compared.
+        if !C::should_backdate_value(old_value, old_value) {
+            return;
+        }
+
+        let database_key_index = active_query.database_key_index;
+        log::debug!(
+            "{:?}: shadow verification of {:?}",
+            database_key_index.debug(db),
+            memo
+        );
+        let key = C::key_from_id(database_key_index.key_index);
+        let new_value = C::execute(db, key);
+        if !C::should_backdate_value(old_value, &new_value) {
+            panic!(
+                "shadow verification failed for {:?}: the memoized value {:?} was validated, \
+                 but executing the function again returns {:?}",
+                database_key_index.debug(db),
+                old_value,
+                new_value,
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::{hash::Hash, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::hash::FxHashSet;
+
+/// The values of a tracked function with the `share_values` option, each stored
+/// once: when a value computed for some key is equal to one of them, the memo
+/// stores a clone of that one instead, sharing its allocation.
+pub struct SharedValues<V> {
+    values: Mutex<FxHashSet<V>>,
+}
+
+impl<V> Default for SharedValues<V> {
+    fn default() -> Self {
+        Self {
+            values: Default::default(),
+        }
+    }
+}
+
+impl<T: ?Sized + Eq + Hash> SharedValues<Arc<T>> {
+    /// Returns the shared value equal to `value`, sharing `value` if there is none.
+    pub fn share(&self, value: Arc<T>) -> Arc<T> {
+        let mut values = self.values.lock();
+        match values.get(&value) {
+            Some(shared) => shared.clone(),
+            None => {
+                values.insert(value.clone());
+                value
+            }
+        }
+    }
+
+    /// Forgets the values that are no longer stored anywhere else (e.g., in a memo).
+    pub fn prune(&mut self) {
+        self.values
+            .get_mut()
+            .retain(|value| Arc::strong_count(value) > 1);
+    }
+}
@@ -1,5 +1,3 @@
-use crossbeam::atomic::AtomicCell;
-
 use crate::{
     database::AsSalsaDatabase,
     runtime::local_state::{QueryOrigin, QueryRevisions},
@@ -16,16 +14,16 @@ where
 {
     /// Specifies the value of the function for the given key.
     /// This is a way to imperatively set the value of a function.
-    /// It only works if the key is a tracked struct created in the current query.
+    /// It only works if `owner`, the tracked struct that is the key (or the first
+    /// argument of the key), was created in the current query.
     pub(crate) fn specify<'db>(
         &self,
         db: &'db DynDb<'db, C>,
+        owner: DatabaseKeyIndex,
         key: C::Key,
         value: C::Value,
         origin: impl Fn(DatabaseKeyIndex) -> QueryOrigin,
-    ) where
-        C::Key: TrackedStructInDb<DynDb<'db, C>>,
-    {
+    ) {
         let runtime = db.runtime();
 
         let (active_query_key, current_deps) = match runtime.active_query() {
@@ -45,9 +43,10 @@ where
         // * Q4 invokes Q2 and then Q1
         //
         // Now, if We invoke Q3 first, We get one result for Q2, but if We invoke Q4 first, We get a different value. That's no good.
-        let database_key_index = key.database_key_index(db);
-        let dependency_index = database_key_index.into();
-        if !runtime.is_output_of_active_query(dependency_index) {
+        //
+        // For a function taking several arguments, the first one is a tracked struct, and
+        // the same holds: no other query can reach the key before it is created.
+        if !runtime.is_output_of_active_query(owner.into()) {
             panic!("can only use `specfiy` on entities created during current query");
         }
 
@@ -71,22 +70,21 @@ where
         // - a result that is NOT verified and has untracked inputs, which will re-execute (and likely panic)
 
         let revision = runtime.current_revision();
+        let database_key_index = self.database_key_index(key);
         let mut revisions = QueryRevisions {
             changed_at: current_deps.changed_at,
             durability: current_deps.durability,
             origin: origin(active_query_key),
+            cycle_heads: vec![],
         };
 
         if let Some(old_memo) = self.memo_map.get(key) {
-            self.backdate_if_appropriate(&old_memo, &mut revisions, &value);
+            self.backdate_if_appropriate(&old_memo, &mut revisions, &value, revision, false);
             self.diff_outputs(db, database_key_index, &old_memo, &revisions);
+            self.notify_change(key, &old_memo, &revisions, &value);
         }
 
-        let memo = Memo {
-            value: Some(value),
-            verified_at: AtomicCell::new(revision),
-            revisions,
-        };
+        let memo = Memo::new(Some(value), revision, revisions);
 
         log::debug!("specify: about to add memo {:#?} for key {:?}", memo, key);
         self.insert_memo(db, key, memo);
@@ -98,7 +96,20 @@ where
     where
         C::Key: TrackedStructInDb<DynDb<'db, C>>,
     {
-        self.specify(db, key, value, |database_key_index| {
+        let owner = key.database_key_index(db);
+        self.specify_owned_and_record(db, owner, key, value);
+    }
+
+    /// Like `specify_and_record`, for functions taking several arguments,
+    /// where `owner` is the tracked struct passed as the first one.
+    pub fn specify_owned_and_record<'db>(
+        &self,
+        db: &'db DynDb<'db, C>,
+        owner: DatabaseKeyIndex,
+        key: C::Key,
+        value: C::Value,
+    ) {
+        self.specify(db, owner, key, value, |database_key_index| {
             QueryOrigin::Assigned(database_key_index)
         });
 
@@ -136,6 +147,11 @@ where
         }
 
         let database_key_index = self.database_key_index(key);
-        memo.mark_as_verified(db.as_salsa_database(), runtime, database_key_index);
+        memo.mark_as_verified(
+            db.as_salsa_database(),
+            runtime,
+            database_key_index,
+            C::PHASE,
+        );
     }
 }
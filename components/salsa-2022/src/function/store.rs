@@ -1,7 +1,5 @@
 use std::sync::Arc;
 
-use crossbeam::atomic::AtomicCell;
-
 use crate::{
     durability::Durability,
     runtime::local_state::{QueryOrigin, QueryRevisions},
@@ -22,15 +20,16 @@ where
         durability: Durability,
     ) {
         let revision = runtime.current_revision();
-        let memo = Memo {
-            value: Some(value),
-            verified_at: AtomicCell::new(revision),
-            revisions: QueryRevisions {
+        let memo = Memo::new(
+            Some(value),
+            revision,
+            QueryRevisions {
                 changed_at: revision,
                 durability,
                 origin: QueryOrigin::BaseInput,
+                cycle_heads: vec![],
             },
-        };
+        );
 
         if let Some(old_value) = self.memo_map.insert(key, Arc::new(memo)) {
             // NB: we don't have to store `old_value` into `deleted_entries` because we have `&mut self`.
@@ -0,0 +1,63 @@
+use crate::{runtime::local_state::QueryOrigin, AsId, DatabaseKeyIndex, Id, Revision};
+
+use super::{Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Keys whose memo was executed or verified in `revision`, and those of pinned memos,
+    /// which `sweep` must keep as well.
+    /// Provisional memos from a fixed-point iteration are never reused, so they don't count.
+    pub(super) fn keys_verified_in(&self, revision: Revision) -> Vec<Id> {
+        let mut keys: Vec<Id> = self
+            .memo_map
+            .memos()
+            .into_iter()
+            .filter(|(_, memo)| {
+                memo.verified_at.load() == revision && memo.revisions.cycle_heads.is_empty()
+            })
+            .map(|(key, _)| key.as_id())
+            .collect();
+        keys.extend(
+            self.pins
+                .keys()
+                .into_iter()
+                .filter(|&index| self.memo_map.get(C::key_from_id(index)).is_some()),
+        );
+        keys
+    }
+
+    /// Discards the memos whose database key is not live.
+    /// Input values set with `store` cannot be recomputed, so they are always kept.
+    pub(super) fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        std::mem::take(&mut self.deleted_entries);
+        let index = self.index;
+        let discarded = self.memo_map.retain(|key, memo| {
+            matches!(memo.revisions.origin, QueryOrigin::BaseInput)
+                || is_live(DatabaseKeyIndex {
+                    ingredient_index: index,
+                    key_index: key.as_id(),
+                })
+        });
+        let memo_map = &self.memo_map;
+        self.ttl
+            .retain(|key_index| memo_map.get(C::key_from_id(key_index)).is_some());
+        self.history
+            .retain(|key_index| memo_map.get(C::key_from_id(key_index)).is_some());
+        discarded
+    }
+
+    /// Shrinks the memo map and the tables kept next to it to the memos stored now.
+    /// Like `sweep`, this frees the deleted entries, as no references into them remain.
+    pub(super) fn compact(&mut self) {
+        std::mem::take(&mut self.deleted_entries);
+        self.memo_map.shrink_to_fit();
+        self.sync_map.shrink_to_fit();
+        self.lru.shrink_to_fit();
+        self.ttl.shrink_to_fit();
+        self.pins.shrink_to_fit();
+        self.history.shrink_to_fit();
+        self.observers.shrink_to_fit();
+    }
+}
@@ -4,12 +4,15 @@ use crate::{
     hash::FxDashMap,
     key::DatabaseKeyIndex,
     runtime::{RuntimeId, WaitResult},
-    Database, Id, Runtime,
+    Database, Id, QueryPanicked, Runtime,
 };
 
 #[derive(Default)]
 pub(super) struct SyncMap {
     sync_map: FxDashMap<Id, SyncState>,
+
+    /// Queries that panicked in the current revision, see [`QueryPanicked`].
+    poisoned: FxDashMap<Id, QueryPanicked>,
 }
 
 struct SyncState {
@@ -21,6 +24,13 @@ struct SyncState {
 }
 
 impl SyncMap {
+    /// Claims the query `database_key_index` for the current thread, so that no
+    /// other thread executes or validates it at the same time.
+    ///
+    /// If another thread holds the claim, returns `None` once its claim is released:
+    /// until then, the current thread is blocked, as recorded in the dependency graph
+    /// of the runtime so that cycles between threads are detected (see
+    /// [`Runtime::block_on_or_unwind`]). The caller should then retry.
     pub(super) fn claim<'me>(
         &'me self,
         db: &'me dyn Database,
@@ -36,7 +46,7 @@ impl SyncMap {
                 Some(ClaimGuard {
                     database_key: database_key_index,
                     runtime,
-                    sync_map: &self.sync_map,
+                    sync_map: self,
                 })
             }
             dashmap::mapref::entry::Entry::Occupied(entry) => {
@@ -53,6 +63,35 @@ impl SyncMap {
             }
         }
     }
+
+    /// Records that executing the query `key_index` panicked, so that the queries
+    /// waiting for it, and later fetches in this revision, panic too.
+    pub(super) fn poison(&self, key_index: Id, panicked: QueryPanicked) {
+        self.poisoned.insert(key_index, panicked);
+    }
+
+    /// Panics with a [`QueryPanicked`] if the query `key_index` panicked in this revision.
+    pub(super) fn throw_if_poisoned(&self, key_index: Id) {
+        if let Some(panicked) = self.poisoned(key_index) {
+            panicked.throw()
+        }
+    }
+
+    fn poisoned(&self, key_index: Id) -> Option<QueryPanicked> {
+        self.poisoned
+            .get(&key_index)
+            .map(|panicked| panicked.clone())
+    }
+
+    /// Queries that panicked are executed again in a new revision.
+    pub(super) fn reset_for_new_revision(&mut self) {
+        self.poisoned.clear();
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.sync_map.shrink_to_fit();
+        self.poisoned.shrink_to_fit();
+    }
 }
 
 /// Marks an active 'claim' in the synchronization map. The claim is
@@ -61,13 +100,16 @@ impl SyncMap {
 pub(super) struct ClaimGuard<'me> {
     database_key: DatabaseKeyIndex,
     runtime: &'me Runtime,
-    sync_map: &'me FxDashMap<Id, SyncState>,
+    sync_map: &'me SyncMap,
 }
 
 impl<'me> ClaimGuard<'me> {
     fn remove_from_map_and_unblock_queries(&self, wait_result: WaitResult) {
-        let (_, SyncState { anyone_waiting, .. }) =
-            self.sync_map.remove(&self.database_key.key_index).unwrap();
+        let (_, SyncState { anyone_waiting, .. }) = self
+            .sync_map
+            .sync_map
+            .remove(&self.database_key.key_index)
+            .unwrap();
 
         // NB: `Ordering::Relaxed` is sufficient here,
         // see `store` above for explanation.
@@ -81,7 +123,7 @@ impl<'me> ClaimGuard<'me> {
 impl<'me> Drop for ClaimGuard<'me> {
     fn drop(&mut self) {
         let wait_result = if std::thread::panicking() {
-            WaitResult::Panicked
+            WaitResult::Panicked(self.sync_map.poisoned(self.database_key.key_index))
         } else {
             WaitResult::Completed
         };
@@ -0,0 +1,27 @@
+use crate::{Durability, Id};
+
+use super::{Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Marks the memo for `key` (or, if `None`, every memo) as having read
+    /// untracked state, so that it is re-executed the next time it is verified,
+    /// after a write of its durability. Returns the highest durability of
+    /// the memos marked.
+    pub(super) fn synthetic_write(&mut self, key: Option<Id>) -> Option<Durability> {
+        let keys = match key {
+            Some(key) => vec![C::key_from_id(key)],
+            None => self
+                .memo_map
+                .memos()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect(),
+        };
+        keys.into_iter()
+            .filter_map(|key| self.memo_map.mark_untracked(key))
+            .max()
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+use crate::{hash::FxDashMap, Id};
+
+use crossbeam_utils::atomic::AtomicCell;
+
+#[derive(Default)]
+pub(super) struct Ttl {
+    duration: AtomicCell<Option<Duration>>,
+    computed_at: FxDashMap<Id, Instant>,
+}
+
+impl Ttl {
+    pub(super) fn is_enabled(&self) -> bool {
+        self.duration.load().is_some()
+    }
+
+    pub(super) fn record_execution(&self, index: Id) {
+        if self.is_enabled() {
+            self.computed_at.insert(index, Instant::now());
+        }
+    }
+
+    /// True if the value for `index` was computed more than the ttl ago.
+    /// Values whose age is unknown (e.g., restored from disk) count as expired.
+    pub(super) fn is_expired(&self, index: Id) -> bool {
+        match self.duration.load() {
+            None => false,
+            Some(duration) => match self.computed_at.get(&index) {
+                Some(computed_at) => computed_at.elapsed() > duration,
+                None => true,
+            },
+        }
+    }
+
+    pub(super) fn forget(&self, index: Id) {
+        self.computed_at.remove(&index);
+    }
+
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(Id) -> bool) {
+        self.computed_at.retain(|&index, _| keep(index));
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.computed_at.shrink_to_fit();
+    }
+
+    pub(super) fn set_duration(&self, duration: Option<Duration>) {
+        self.duration.store(duration);
+
+        if duration.is_none() {
+            self.computed_at.clear();
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use crate::Revision;
+
+use super::{memo::Memo, Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// With the `update` option, writes `new_value` into the value of `old_memo`,
+    /// the memo for `key` it is about to replace, and returns that value: what the
+    /// old value owns is then reused, rather than kept until the next revision with
+    /// the old memo and freed. Returns `new_value` if the old value cannot be taken.
+    pub(super) fn update_old_value(
+        &self,
+        key: C::Key,
+        old_memo: Arc<Memo<C::Value>>,
+        new_value: C::Value,
+        revision_now: Revision,
+    ) -> C::Value {
+        // The history hands out references to the old values it keeps.
+        if self.history.is_enabled() {
+            return new_value;
+        }
+
+        // The memo map must be the only one holding the old memo.
+        drop(old_memo);
+        match self.memo_map.take_value(key, revision_now) {
+            Some(mut old_value) => {
+                C::update_value(&mut old_value, new_value);
+                old_value
+            }
+            None => new_value,
+        }
+    }
+}
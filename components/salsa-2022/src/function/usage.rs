@@ -0,0 +1,69 @@
+use std::mem::size_of;
+
+use arc_swap::ArcSwap;
+
+use crate::{
+    key::DependencyIndex,
+    runtime::local_state::{EdgeKind, QueryOrigin},
+    usage::{EvictionCandidate, IngredientUsage},
+    AsId,
+};
+
+use super::{memo::Memo, Configuration, FunctionIngredient};
+
+impl<C> FunctionIngredient<C>
+where
+    C: Configuration,
+{
+    /// Counts the memos along with their dependencies. Memos that were deleted,
+    /// but which cannot be freed before the next revision, add to the bytes too.
+    /// The heap size is only known for the values that are still memoized.
+    pub(super) fn usage(&self) -> IngredientUsage {
+        let memo_bytes = |memo: &Memo<C::Value>| {
+            let edges = match &memo.revisions.origin {
+                QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) => {
+                    edges.input_outputs.len()
+                }
+                QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => 0,
+            };
+            size_of::<Memo<C::Value>>() + edges * size_of::<(EdgeKind, DependencyIndex)>()
+        };
+        let entries = self.memo_map.memos();
+        let bytes = entries
+            .iter()
+            .map(|(_, memo)| size_of::<(C::Key, ArcSwap<Memo<C::Value>>)>() + memo_bytes(memo))
+            .sum::<usize>()
+            + self.deleted_entries.len() * size_of::<Memo<C::Value>>();
+        let heap_bytes = entries
+            .iter()
+            .filter_map(|(_, memo)| memo.value.as_ref())
+            .map(C::heap_size)
+            .sum();
+        IngredientUsage {
+            entries: entries.len(),
+            bytes,
+            heap_bytes,
+        }
+    }
+
+    /// The memos whose values [`FunctionIngredient::evict_values`] can drop,
+    /// skipping those that own no heap memory: evicting them would not help.
+    pub(super) fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        self.memo_map
+            .memos()
+            .into_iter()
+            .filter(|(key, memo)| {
+                matches!(memo.revisions.origin, QueryOrigin::Derived(_))
+                    && !self.pins.contains(key.as_id())
+            })
+            .filter_map(|(key, memo)| {
+                let heap_bytes = C::heap_size(memo.value.as_ref()?);
+                (heap_bytes > 0).then(|| EvictionCandidate {
+                    key_index: key.as_id(),
+                    verified_at: memo.verified_at.load(),
+                    heap_bytes,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,4 +1,4 @@
-use std::hash::{BuildHasher, Hash, Hasher};
+use std::hash::{BuildHasher, Hash};
 
 pub(crate) type FxHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
 pub(crate) type FxIndexSet<K> = indexmap::IndexSet<K, FxHasher>;
@@ -6,9 +6,17 @@ pub(crate) type FxIndexMap<K, V> = indexmap::IndexMap<K, V, FxHasher>;
 pub(crate) type FxDashMap<K, V> = dashmap::DashMap<K, V, FxHasher>;
 pub(crate) type FxLinkedHashSet<K> = hashlink::LinkedHashSet<K, FxHasher>;
 pub(crate) type FxHashSet<K> = std::collections::HashSet<K, FxHasher>;
+pub(crate) type FxHashMap<K, V> = std::collections::HashMap<K, V, FxHasher>;
+
+/// Hasher for the tables of values that are looked up on every query: interned
+/// values, memos, and the fields of inputs. This is FxHash, unless the `ahash`
+/// feature is enabled, which is faster for long keys (e.g., interned strings).
+#[cfg(not(feature = "ahash"))]
+pub(crate) type TableHasher = FxHasher;
+#[cfg(feature = "ahash")]
+pub(crate) type TableHasher = std::hash::BuildHasherDefault<ahash::AHasher>;
+pub(crate) type TableDashMap<K, V> = dashmap::DashMap<K, V, TableHasher>;
 
 pub(crate) fn hash<T: Hash>(t: &T) -> u64 {
-    let mut hasher = FxHasher::default().build_hasher();
-    t.hash(&mut hasher);
-    hasher.finish()
+    FxHasher::default().hash_one(t)
 }
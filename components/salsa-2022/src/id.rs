@@ -2,6 +2,9 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::num::NonZeroU32;
 
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 /// An Id is a newtype'd u32 ranging from `0..Id::MAX_U32`.
 /// The maximum range is smaller than a standard u32 to leave
 /// room for niches; currently there is only one niche, so that
@@ -16,7 +19,7 @@ pub struct Id {
 }
 
 impl Id {
-    pub const MAX_U32: u32 = std::u32::MAX - 0xFF;
+    pub const MAX_U32: u32 = u32::MAX - 0xFF;
     pub const MAX_USIZE: usize = Self::MAX_U32 as usize;
 
     /// Create a `salsa::Id` from a u32 value. This value should
@@ -26,10 +29,11 @@ impl Id {
     /// but it can be useful if you are using the type as a general
     /// purpose "identifier" internally.
     #[track_caller]
-    pub fn from_u32(x: u32) -> Self {
+    pub const fn from_u32(x: u32) -> Self {
         assert!(x < Self::MAX_U32);
-        Id {
-            value: NonZeroU32::new(x + 1).unwrap(),
+        match NonZeroU32::new(x + 1) {
+            Some(value) => Id { value },
+            None => unreachable!(),
         }
     }
 
@@ -38,6 +42,24 @@ impl Id {
     }
 }
 
+#[cfg(feature = "persist")]
+impl Serialize for Id {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_u32().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let x = u32::deserialize(deserializer)?;
+        if x >= Self::MAX_U32 {
+            return Err(serde::de::Error::custom(format!("id `{}` out of range", x)));
+        }
+        Ok(Id::from_u32(x))
+    }
+}
+
 impl From<u32> for Id {
     fn from(n: u32) -> Self {
         Id::from_u32(n)
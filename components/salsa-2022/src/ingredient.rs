@@ -1,8 +1,11 @@
 use std::fmt;
 
 use crate::{
-    cycle::CycleRecoveryStrategy, key::DependencyIndex, runtime::local_state::QueryOrigin,
-    DatabaseKeyIndex, Id,
+    cycle::CycleRecoveryStrategy,
+    key::DependencyIndex,
+    runtime::local_state::QueryOrigin,
+    usage::{EvictionCandidate, IngredientUsage},
+    DatabaseKeyIndex, Durability, Id, Runtime,
 };
 
 use super::Revision;
@@ -28,6 +31,10 @@ pub trait Ingredient<DB: ?Sized> {
     /// What were the inputs (if any) that were used to create the value at `key_index`.
     fn origin(&self, key_index: Id) -> Option<QueryOrigin>;
 
+    /// When did the value at `key_index` last change, and how durable is it?
+    /// Returns `None` if this ingredient doesn't store a value for `key_index`.
+    fn value_revisions(&self, key_index: Id) -> Option<ValueRevisions>;
+
     /// Invoked when the value `output_key` should be marked as valid in the current revision.
     /// This occurs because the value for `executor`, which generated it, was marked as valid
     /// in the current revision.
@@ -62,15 +69,82 @@ pub trait Ingredient<DB: ?Sized> {
     /// [`IngredientRequiresReset::RESET_ON_NEW_REVISION`] to true.
     fn reset_for_new_revision(&mut self);
 
+    /// Returns the keys of the values in this ingredient that were computed or verified
+    /// in `revision`, or that are pinned. These are the roots from which [`Storage::sweep`](`crate::Storage::sweep`)
+    /// finds the values that are still in use.
+    fn keys_verified_in(&self, revision: Revision) -> Vec<Id>;
+
+    /// Returns the keys of the values in this ingredient that changed after `revision`;
+    /// see [`Storage::keys_changed_since`](`crate::Storage::keys_changed_since`).
+    fn keys_changed_since(&self, revision: Revision) -> Vec<Id>;
+
+    /// Returns the keys of all the values in this ingredient, in no particular order;
+    /// see [`crate::debug::dump`].
+    fn keys(&self) -> Vec<Id>;
+
+    /// Invoked by [`Storage::sweep`](`crate::Storage::sweep`) to discard every value whose
+    /// database key is rejected by `is_live`. Returns the number of values discarded.
+    ///
+    /// Like [`Ingredient::reset_for_new_revision`], this is called with an `&mut`-reference,
+    /// so the backing memory can be freed right away.
+    fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize;
+
+    /// Invoked by [`Storage::synthetic_write_for`](`crate::Storage::synthetic_write_for`)
+    /// to act as though the value at `key_index` (or, if `None`, every value in this
+    /// ingredient) changed in `revision`. Returns the highest durability of the values
+    /// affected, which is the durability of the write to report, or `None` if there
+    /// were no such values.
+    fn synthetic_write(&mut self, key_index: Option<Id>, revision: Revision) -> Option<Durability>;
+
+    /// Invoked by [`Storage::evict_values`](`crate::Storage::evict_values`) to drop the
+    /// value at `key_index` (or, if `None`, every value in this ingredient) while keeping
+    /// what is needed to tell whether it changed. Returns the number of values dropped.
+    fn evict_values(&mut self, key_index: Option<Id>) -> usize;
+
+    /// Invoked by [`Storage::compact`](`crate::Storage::compact`) to shrink the tables of
+    /// this ingredient, which keep the capacity of the most values they ever stored,
+    /// to the values it stores now.
+    fn compact(&mut self) {}
+
+    /// How many values this ingredient stores, and about how much memory they take;
+    /// see [`Storage::usage`](`crate::Storage::usage`).
+    fn usage(&self) -> IngredientUsage;
+
+    /// The values that [`Storage::set_memory_budget`](`crate::Storage::set_memory_budget`)
+    /// may evict: those that [`Ingredient::evict_values`] would drop, and that own memory
+    /// on the heap.
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate>;
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// The name of this ingredient, as [`Ingredient::fmt_index`] shows it
+    /// (e.g., the name of the tracked function).
+    fn debug_name(&self) -> &'static str;
+
+    /// Invoked by [`Storage::end_speculation`](`crate::Storage::end_speculation`) on the
+    /// ingredients that reported writes with [`Runtime::report_speculative_write`] during
+    /// the speculation at `depth`, to put back the values they replaced, as though they
+    /// were set in the current revision. See [`crate::Database::speculate`].
+    fn undo_speculation(&mut self, _runtime: &mut Runtime, _depth: usize) {}
+}
+
+/// Revision information about a stored value; see [`Ingredient::value_revisions`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ValueRevisions {
+    /// The revision in which the value last changed.
+    pub changed_at: Revision,
+
+    /// The revision in which a memoized value was last verified;
+    /// `None` for input fields, which are always up to date.
+    pub verified_at: Option<Revision>,
+
+    /// The durability of the value.
+    pub durability: Durability,
 }
 
-/// A helper function to show human readable fmt.
-pub(crate) fn fmt_index(
-    debug_name: &str,
-    id: Option<Id>,
-    fmt: &mut fmt::Formatter<'_>,
-) -> fmt::Result {
+/// Formats the value `id` of the ingredient `debug_name` as `debug_name(id)`,
+/// or the ingredient as a whole as `debug_name()`, for [`Ingredient::fmt_index`].
+pub fn fmt_index(debug_name: &str, id: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
     if let Some(i) = id {
         write!(fmt, "{}({})", debug_name, u32::from(i))
     } else {
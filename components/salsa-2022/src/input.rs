@@ -5,10 +5,17 @@ use std::{
 
 use crate::{
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, IngredientRequiresReset},
+    ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions},
+    ingredient_list::IngredientList,
     key::{DatabaseKeyIndex, DependencyIndex},
     runtime::{local_state::QueryOrigin, Runtime},
-    AsId, IngredientIndex, Revision,
+    usage::{EvictionCandidate, IngredientUsage},
+    AsId, Database, Durability, Event, EventKind, IngredientIndex, Revision,
+};
+#[cfg(feature = "persist")]
+use crate::{
+    persist::PersistentIngredient,
+    record::{Mutation, PendingKind},
 };
 
 pub trait InputId: AsId {}
@@ -21,6 +28,12 @@ where
     ingredient_index: IngredientIndex,
     counter: AtomicU32,
     debug_name: &'static str,
+
+    /// A list of each tracked function `f` whose key is an input of this type.
+    /// Whenever an input `i` is deleted, each function is notified
+    /// so that it can remove its memo for `i`.
+    dependent_fns: IngredientList,
+
     _phantom: std::marker::PhantomData<Id>,
 }
 
@@ -33,6 +46,7 @@ where
             ingredient_index: index,
             counter: Default::default(),
             debug_name,
+            dependent_fns: IngredientList::new(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -44,24 +58,60 @@ where
         }
     }
 
-    pub fn new_input(&self, _runtime: &Runtime) -> Id {
+    pub fn new_input(&self, runtime: &Runtime) -> Id {
         let next_id = self.counter.fetch_add(1, Ordering::Relaxed);
+        self.record_new(runtime, crate::Id::from_u32(next_id));
         Id::from_id(crate::Id::from_u32(next_id))
     }
 
-    pub fn new_singleton_input(&self, _runtime: &Runtime) -> Id {
+    pub fn new_singleton_input(&self, runtime: &Runtime) -> Id {
         // when one exists already, panic
         if self.counter.load(Ordering::Relaxed) >= 1 {
             panic!("singleton struct may not be duplicated");
         }
         // fresh new ingredient
         self.counter.store(1, Ordering::Relaxed);
+        self.record_new(runtime, crate::Id::from_u32(0));
         Id::from_id(crate::Id::from_u32(0))
     }
 
+    #[cfg_attr(not(feature = "persist"), allow(unused_variables))]
+    fn record_new(&self, runtime: &Runtime, id: crate::Id) {
+        #[cfg(feature = "persist")]
+        runtime.recorder().report(
+            runtime.current_revision(),
+            self.ingredient_index,
+            id,
+            PendingKind::NewInput,
+        );
+    }
+
     pub fn get_singleton_input(&self, _runtime: &Runtime) -> Option<Id> {
         (self.counter.load(Ordering::Relaxed) > 0).then(|| Id::from_id(crate::Id::from_u32(0)))
     }
+
+    /// Notifies the dependent functions that `id` was deleted, so that they discard
+    /// their memos for it. The generated `delete` method invokes this after removing
+    /// the fields of `id` in a new revision; the id itself is never reused.
+    pub fn delete_input<DB: ?Sized + Database>(&self, db: &DB, id: Id) {
+        db.salsa_event(Event {
+            runtime_id: db.runtime().id(),
+            kind: EventKind::DidDiscard {
+                key: self.database_key_index(id),
+            },
+        });
+
+        for dependent_fn in self.dependent_fns.iter() {
+            db.salsa_struct_deleted(dependent_fn, id.as_id());
+        }
+    }
+
+    /// Adds a dependent function (one keyed by this input) to our list.
+    /// When instances of this input are deleted, these dependent functions
+    /// will be notified.
+    pub fn register_dependent_fn(&self, index: IngredientIndex) {
+        self.dependent_fns.push(index);
+    }
 }
 
 impl<DB: ?Sized, Id> Ingredient<DB> for InputIngredient<Id>
@@ -82,6 +132,10 @@ where
         None
     }
 
+    fn value_revisions(&self, _key_index: crate::Id) -> Option<ValueRevisions> {
+        None
+    }
+
     fn mark_validated_output(
         &self,
         _db: &DB,
@@ -116,9 +170,91 @@ where
         );
     }
 
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys_changed_since(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys(&self) -> Vec<crate::Id> {
+        // The fields of an input are ingredients of their own.
+        vec![]
+    }
+
+    fn sweep(&mut self, _is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        // Inputs are owned by the user and only go away when they say so.
+        0
+    }
+
+    fn synthetic_write(
+        &mut self,
+        _key_index: Option<crate::Id>,
+        _revision: Revision,
+    ) -> Option<Durability> {
+        // The fields of an input are ingredients of their own.
+        None
+    }
+
+    fn evict_values(&mut self, _key_index: Option<crate::Id>) -> usize {
+        // Input values cannot be recomputed.
+        0
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        // The values are stored by the ingredients of the fields.
+        IngredientUsage {
+            entries: self.counter.load(Ordering::Relaxed) as usize,
+            bytes: 0,
+            heap_bytes: 0,
+        }
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<Id> PersistentIngredient for InputIngredient<Id>
+where
+    Id: InputId,
+{
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn serialize(
+        &self,
+        _is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self.counter.load(Ordering::Relaxed))
+    }
+
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()> {
+        *self.counter.get_mut() = serde_json::from_value(data)?;
+        Ok(())
+    }
+
+    fn replay(
+        &mut self,
+        runtime: &mut Runtime,
+        id: crate::Id,
+        mutation: &Mutation,
+    ) -> serde_json::Result<bool> {
+        // Ids are handed out in order, so creating the inputs in the order
+        // they were recorded gives them the same ids.
+        Ok(matches!(mutation, Mutation::NewInput) && self.new_input(runtime).as_id() == id)
+    }
 }
 
 impl<Id> IngredientRequiresReset for InputIngredient<Id>
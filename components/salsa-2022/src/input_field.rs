@@ -1,13 +1,25 @@
 use crate::cycle::CycleRecoveryStrategy;
-use crate::ingredient::{fmt_index, Ingredient, IngredientRequiresReset};
+use crate::hash::TableDashMap;
+use crate::ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions};
 use crate::key::DependencyIndex;
+#[cfg(feature = "persist")]
+use crate::persist::PersistentIngredient;
+#[cfg(feature = "persist")]
+use crate::record::{Mutation, PendingKind};
 use crate::runtime::local_state::QueryOrigin;
 use crate::runtime::StampedValue;
+use crate::usage::{EvictionCandidate, IngredientUsage};
 use crate::{AsId, DatabaseKeyIndex, Durability, Id, IngredientIndex, Revision, Runtime};
 use dashmap::mapref::entry::Entry;
-use dashmap::DashMap;
+use dashmap::DashSet;
+use parking_lot::Mutex;
+#[cfg(feature = "persist")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "persist")]
+use serde::Serialize;
 use std::fmt;
 use std::hash::Hash;
+use std::marker::PhantomData;
 
 /// Ingredient used to represent the fields of a `#[salsa::input]`.
 ///
@@ -20,8 +32,20 @@ use std::hash::Hash;
 /// structs.
 pub struct InputFieldIngredient<K, F> {
     index: IngredientIndex,
-    map: DashMap<K, Box<StampedValue<F>>>,
+    map: TableDashMap<K, Box<StampedValue<F>>>,
+    /// Keys whose field was removed by [`Self::delete_mut`], so that lazy fields
+    /// are not loaded again for them.
+    deleted: DashSet<K>,
     debug_name: &'static str,
+
+    /// Clones a value of the field, if its type implements `Clone`; this is what
+    /// lets it be set during a speculation (see [`crate::Database::speculate`]).
+    clone_value: Option<fn(&F) -> F>,
+
+    /// For each write during a speculation, the speculation depth and the key,
+    /// with the value it replaced (`None` for a new input).
+    #[allow(clippy::type_complexity)]
+    speculative_writes: Mutex<Vec<(usize, K, Option<Box<StampedValue<F>>>)>>,
 }
 
 impl<K, F> InputFieldIngredient<K, F>
@@ -32,17 +56,53 @@ where
         Self {
             index,
             map: Default::default(),
+            deleted: Default::default(),
             debug_name,
+            clone_value: None,
+            speculative_writes: Default::default(),
+        }
+    }
+
+    /// Gives the function cloning values of the field, found with a [`CloneProbe`].
+    pub fn with_clone_fn(self, clone_value: Option<fn(&F) -> F>) -> Self {
+        Self {
+            clone_value,
+            ..self
         }
     }
 
+    /// If a speculation is in progress, saves a copy of the current value of `key`
+    /// (if any), to be put back when the speculation ends.
+    fn save_for_speculation(&mut self, runtime: &Runtime, key: K) {
+        let depth = runtime.speculation_depth();
+        if depth == 0 {
+            return;
+        }
+        let saved = self.map.get(&key).map(|value| {
+            let clone_value = self.clone_value.unwrap_or_else(|| {
+                panic!(
+                    "cannot set `{}` during a speculation, since its type does not implement `Clone`",
+                    self.debug_name
+                )
+            });
+            Box::new(StampedValue {
+                value: clone_value(&value.value),
+                durability: value.durability,
+                changed_at: value.changed_at,
+            })
+        });
+        self.speculative_writes.get_mut().push((depth, key, saved));
+        runtime.report_speculative_write(self.index);
+    }
+
     pub fn store_mut(
         &mut self,
-        runtime: &Runtime,
+        runtime: &mut Runtime,
         key: K,
         value: F,
         durability: Durability,
     ) -> Option<F> {
+        self.save_for_speculation(runtime, key);
         let revision = runtime.current_revision();
         let stamped_value = Box::new(StampedValue {
             value,
@@ -50,9 +110,70 @@ where
             changed_at: revision,
         });
 
-        self.map
-            .insert(key, stamped_value)
-            .map(|old_value| old_value.value)
+        #[cfg(feature = "persist")]
+        if runtime.speculation_depth() == 0 {
+            runtime.recorder().report(
+                revision,
+                self.index,
+                key.as_id(),
+                PendingKind::SetField(durability),
+            );
+        }
+        let old_value = self.map.insert(key, stamped_value)?;
+
+        // Readers of the old value may have recorded its durability, so that is
+        // the durability whose last change has to be bumped, whatever the new one is.
+        runtime.report_tracked_write(old_value.durability);
+        Some(old_value.value)
+    }
+
+    /// Modifies the field of `key` in place, as though it had been set to the
+    /// result. Returns false (without calling `update`) if the field has no value.
+    pub fn update_mut(
+        &mut self,
+        runtime: &mut Runtime,
+        key: K,
+        update: impl FnOnce(&mut F),
+        durability: Durability,
+    ) -> bool {
+        self.save_for_speculation(runtime, key);
+        match self.map.get_mut(&key) {
+            Some(mut stamped_value) => {
+                runtime.report_tracked_write(stamped_value.durability);
+                update(&mut stamped_value.value);
+                stamped_value.durability = durability;
+                stamped_value.changed_at = runtime.current_revision();
+                #[cfg(feature = "persist")]
+                if runtime.speculation_depth() == 0 {
+                    runtime.recorder().report(
+                        stamped_value.changed_at,
+                        self.index,
+                        key.as_id(),
+                        PendingKind::SetField(durability),
+                    );
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the field of a deleted input, returning its old value. During a
+    /// speculation, the old value is kept to be put back, and `None` is returned.
+    pub fn delete_mut(&mut self, runtime: &mut Runtime, key: K) -> Option<F> {
+        self.deleted.insert(key);
+        let (_, old_value) = self.map.remove(&key)?;
+        // As when the field is set, readers may have recorded its durability.
+        runtime.report_tracked_write(old_value.durability);
+        let depth = runtime.speculation_depth();
+        if depth == 0 {
+            return Some(old_value.value);
+        }
+        self.speculative_writes
+            .get_mut()
+            .push((depth, key, Some(old_value)));
+        runtime.report_speculative_write(self.index);
+        None
     }
 
     /// Set the field of a new input.
@@ -74,14 +195,33 @@ where
                 entry.insert(stamped_value);
             }
         }
+        let depth = runtime.speculation_depth();
+        if depth > 0 {
+            self.speculative_writes.lock().push((depth, key, None));
+            runtime.report_speculative_write(self.index);
+        } else {
+            #[cfg(feature = "persist")]
+            runtime.recorder().report(
+                revision,
+                self.index,
+                key.as_id(),
+                PendingKind::NewField(durability),
+            );
+        }
     }
 
-    pub fn fetch<'db>(&'db self, runtime: &'db Runtime, key: K) -> &F {
+    pub fn fetch<'db>(&'db self, runtime: &'db Runtime, key: K) -> &'db F {
         let StampedValue {
             value,
             durability,
             changed_at,
-        } = &**self.map.get(&key).unwrap();
+        } = &**self.map.get(&key).unwrap_or_else(|| {
+            panic!(
+                "`{}` read from input {:?} after it was deleted",
+                self.debug_name,
+                key.as_id()
+            )
+        });
 
         runtime.report_tracked_read(
             self.database_key_index(key).into(),
@@ -96,6 +236,30 @@ where
         unsafe { transmute_lifetime(self, value) }
     }
 
+    /// Like [`Self::fetch`], but if the field of `key` has not been set yet (because
+    /// it is a `#[lazy]` field), first calls `load` to compute its value and stores it
+    /// as though it had been set in the current revision.
+    pub fn fetch_or_load<'db>(
+        &'db self,
+        runtime: &'db Runtime,
+        key: K,
+        load: impl FnOnce() -> F,
+    ) -> &'db F {
+        if !self.map.contains_key(&key) && !self.deleted.contains(&key) {
+            // Call `load` without holding a lock on the map,
+            // since it is free to read other fields.
+            let value = load();
+            if let Entry::Vacant(entry) = self.map.entry(key) {
+                entry.insert(Box::new(StampedValue {
+                    value,
+                    durability: Durability::LOW,
+                    changed_at: runtime.current_revision(),
+                }));
+            }
+        }
+        self.fetch(runtime, key)
+    }
+
     fn database_key_index(&self, key: K) -> DatabaseKeyIndex {
         DatabaseKeyIndex {
             ingredient_index: self.index,
@@ -104,11 +268,37 @@ where
     }
 }
 
+/// Lets the generated code find out whether a field type implements `Clone`, without
+/// requiring it: `(&CloneProbe::<F>(PhantomData)).clone_fn()` resolves to the
+/// [`CloneFn`] impl when `F: Clone`, and to the [`NoCloneFn`] one (a level of
+/// autoref further) otherwise.
+pub struct CloneProbe<F>(pub PhantomData<F>);
+
+pub trait CloneFn<F> {
+    fn clone_fn(&self) -> Option<fn(&F) -> F>;
+}
+
+impl<F: Clone> CloneFn<F> for CloneProbe<F> {
+    fn clone_fn(&self) -> Option<fn(&F) -> F> {
+        Some(F::clone)
+    }
+}
+
+pub trait NoCloneFn<F> {
+    fn clone_fn(&self) -> Option<fn(&F) -> F>;
+}
+
+impl<F> NoCloneFn<F> for &CloneProbe<F> {
+    fn clone_fn(&self) -> Option<fn(&F) -> F> {
+        None
+    }
+}
+
 // Returns `u` but with the lifetime of `t`.
 //
 // Safe if you know that data at `u` will remain shared
 // until the reference `t` expires.
-unsafe fn transmute_lifetime<'t, 'u, T, U>(_t: &'t T, u: &'u U) -> &'t U {
+unsafe fn transmute_lifetime<'t, T, U>(_t: &'t T, u: &U) -> &'t U {
     std::mem::transmute(u)
 }
 
@@ -122,13 +312,26 @@ where
 
     fn maybe_changed_after(&self, _db: &DB, input: DependencyIndex, revision: Revision) -> bool {
         let key = K::from_id(input.key_index.unwrap());
-        self.map.get(&key).unwrap().changed_at > revision
+        match self.map.get(&key) {
+            Some(value) => value.changed_at > revision,
+            // The input was deleted.
+            None => true,
+        }
     }
 
     fn origin(&self, _key_index: Id) -> Option<QueryOrigin> {
         None
     }
 
+    fn value_revisions(&self, key_index: Id) -> Option<ValueRevisions> {
+        let key = K::from_id(key_index);
+        self.map.get(&key).map(|value| ValueRevisions {
+            changed_at: value.changed_at,
+            verified_at: None,
+            durability: value.durability,
+        })
+    }
+
     fn mark_validated_output(
         &self,
         _db: &DB,
@@ -153,9 +356,176 @@ where
         panic!("unexpected call: input fields don't register for resets");
     }
 
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<Id> {
+        vec![]
+    }
+
+    fn keys_changed_since(&self, revision: Revision) -> Vec<Id> {
+        self.map
+            .iter()
+            .filter(|entry| entry.value().changed_at > revision)
+            .map(|entry| entry.key().as_id())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<Id> {
+        self.map.iter().map(|entry| entry.key().as_id()).collect()
+    }
+
+    fn sweep(&mut self, _is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        0
+    }
+
+    fn synthetic_write(&mut self, key_index: Option<Id>, revision: Revision) -> Option<Durability> {
+        let mark_changed = |value: &mut StampedValue<F>| {
+            value.changed_at = revision;
+            value.durability
+        };
+        match key_index {
+            Some(key_index) => self
+                .map
+                .get_mut(&K::from_id(key_index))
+                .map(|mut value| mark_changed(&mut value)),
+            None => self
+                .map
+                .iter_mut()
+                .map(|mut value| mark_changed(&mut value))
+                .max(),
+        }
+    }
+
+    fn evict_values(&mut self, _key_index: Option<crate::Id>) -> usize {
+        // Input values cannot be recomputed.
+        0
+    }
+
+    fn compact(&mut self) {
+        self.map.shrink_to_fit();
+        self.deleted.shrink_to_fit();
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        let entries = self.map.len();
+        IngredientUsage {
+            entries,
+            bytes: entries
+                * (std::mem::size_of::<(K, Box<StampedValue<F>>)>()
+                    + std::mem::size_of::<StampedValue<F>>()),
+            heap_bytes: 0,
+        }
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn undo_speculation(&mut self, runtime: &mut Runtime, depth: usize) {
+        let writes = self.speculative_writes.get_mut();
+        let first = writes.partition_point(|&(d, _, _)| d < depth);
+        let undone: Vec<_> = writes.drain(first..).collect();
+        let revision = runtime.current_revision();
+        for (_, key, saved) in undone.into_iter().rev() {
+            let durability = match saved {
+                Some(mut saved) => {
+                    saved.changed_at = revision;
+                    let durability = saved.durability;
+                    self.deleted.remove(&key);
+                    match self.map.insert(key, saved) {
+                        Some(replaced) => replaced.durability.max(durability),
+                        None => durability,
+                    }
+                }
+                None => match self.map.remove(&key) {
+                    Some((_, replaced)) => replaced.durability,
+                    None => continue,
+                },
+            };
+            runtime.report_tracked_write(durability);
+        }
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<K, F> PersistentIngredient for InputFieldIngredient<K, F>
+where
+    K: AsId,
+    F: Serialize + DeserializeOwned,
+{
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn serialize(
+        &self,
+        _is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value> {
+        let mut fields = self
+            .map
+            .iter()
+            .map(|entry| {
+                let StampedValue {
+                    value,
+                    durability,
+                    changed_at,
+                } = &**entry.value();
+                let stamped_value = StampedValue {
+                    value: serde_json::to_value(value)?,
+                    durability: *durability,
+                    changed_at: *changed_at,
+                };
+                Ok((entry.key().as_id(), stamped_value))
+            })
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        fields.sort_by_key(|(id, _)| *id);
+        serde_json::to_value(fields)
+    }
+
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()> {
+        let fields: Vec<(Id, StampedValue<F>)> = serde_json::from_value(data)?;
+        self.map = fields
+            .into_iter()
+            .map(|(id, stamped_value)| (K::from_id(id), Box::new(stamped_value)))
+            .collect();
+        Ok(())
+    }
+
+    fn recorded_value(&self, id: Id) -> serde_json::Result<Option<serde_json::Value>> {
+        match self.map.get(&K::from_id(id)) {
+            Some(stamped_value) => serde_json::to_value(&stamped_value.value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn replay(
+        &mut self,
+        runtime: &mut Runtime,
+        id: Id,
+        mutation: &Mutation,
+    ) -> serde_json::Result<bool> {
+        match mutation {
+            Mutation::NewField { value, durability } => {
+                if self.map.contains_key(&K::from_id(id)) {
+                    return Ok(false);
+                }
+                let value = F::deserialize(value)?;
+                self.store_new(runtime, K::from_id(id), value, *durability);
+            }
+            Mutation::SetField { value, durability } => {
+                let value = F::deserialize(value)?;
+                self.store_mut(runtime, K::from_id(id), value, *durability);
+            }
+            Mutation::NewInput => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
 impl<K, F> IngredientRequiresReset for InputFieldIngredient<K, F>
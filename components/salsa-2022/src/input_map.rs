@@ -0,0 +1,284 @@
+use crossbeam::atomic::AtomicCell;
+use dashmap::mapref::entry::Entry;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::cycle::CycleRecoveryStrategy;
+use crate::hash::{FxDashMap, TableDashMap};
+use crate::ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions};
+use crate::key::DependencyIndex;
+use crate::runtime::local_state::QueryOrigin;
+use crate::usage::{EvictionCandidate, IngredientUsage};
+use crate::{DatabaseKeyIndex, Durability, Id, IngredientIndex, Revision, Runtime};
+
+/// Ingredient used to represent a `#[salsa::input_map]`: a map whose entries are
+/// inputs of their own. A query reading one entry depends on that entry only,
+/// so inserting or removing another key does not invalidate it. The set of
+/// keys, as returned by [`InputMapIngredient::keys`], is tracked separately.
+///
+/// Each key that was ever inserted or looked up is given an id, which it keeps
+/// after it is removed, so that a query which found a key missing learns when
+/// the key is inserted.
+pub struct InputMapIngredient<K, V> {
+    index: IngredientIndex,
+    ids: TableDashMap<K, Id>,
+    entries: FxDashMap<Id, Box<MapEntry<K, V>>>,
+    counter: AtomicCell<u32>,
+
+    /// The last revision in which a key was inserted or removed.
+    keys_changed_at: AtomicCell<Revision>,
+    debug_name: &'static str,
+}
+
+struct MapEntry<K, V> {
+    key: K,
+    value: Option<V>,
+    changed_at: Revision,
+}
+
+impl<K, V> InputMapIngredient<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    pub fn new(index: IngredientIndex, debug_name: &'static str) -> Self {
+        Self {
+            index,
+            ids: Default::default(),
+            entries: Default::default(),
+            counter: Default::default(),
+            keys_changed_at: AtomicCell::new(Revision::start()),
+            debug_name,
+        }
+    }
+
+    /// The value of `key`, if any, recording a dependency on that entry.
+    pub fn get<'db>(&'db self, runtime: &'db Runtime, key: &K) -> Option<&'db V> {
+        let id = self.id(key);
+        let entry = self.entries.get(&id).unwrap();
+        runtime.report_tracked_read(
+            self.dependency_index(Some(id)),
+            Durability::LOW,
+            entry.changed_at,
+        );
+
+        // SAFETY:
+        // The entry is stored in a box so internal moves in the dashmap don't
+        // invalidate the reference to the value inside the box.
+        // Values are only removed or altered when we have `&mut self`.
+        unsafe { transmute_lifetime(self, entry.value.as_ref()) }
+    }
+
+    /// The keys that have a value, in the order they were first used,
+    /// recording a dependency on the set of keys (but not on their values).
+    pub fn keys(&self, runtime: &Runtime) -> Vec<K> {
+        runtime.report_tracked_read(
+            self.dependency_index(None),
+            Durability::LOW,
+            self.keys_changed_at.load(),
+        );
+        let mut keys: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.value().value.is_some())
+            .map(|entry| (*entry.key(), entry.value().key.clone()))
+            .collect();
+        keys.sort_by_key(|(id, _)| *id);
+        keys.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Sets the value of `key`, returning the previous value.
+    pub fn insert_mut(&mut self, runtime: &mut Runtime, key: K, value: V) -> Option<V> {
+        self.store_mut(runtime, &key, Some(value))
+    }
+
+    /// Removes the value of `key`, returning it.
+    pub fn remove_mut(&mut self, runtime: &mut Runtime, key: &K) -> Option<V> {
+        self.store_mut(runtime, key, None)
+    }
+
+    fn store_mut(&mut self, runtime: &mut Runtime, key: &K, value: Option<V>) -> Option<V> {
+        assert!(
+            runtime.speculation_depth() == 0,
+            "cannot write to `{}` during a speculation",
+            self.debug_name
+        );
+        let id = self.id(key);
+        let revision = runtime.current_revision();
+        let mut entry = self.entries.get_mut(&id).unwrap();
+        if entry.value.is_none() && value.is_none() {
+            // Nothing to remove.
+            return None;
+        }
+        if entry.value.is_some() != value.is_some() {
+            self.keys_changed_at.store(revision);
+        }
+        entry.changed_at = revision;
+        runtime.report_tracked_write(Durability::LOW);
+        std::mem::replace(&mut entry.value, value)
+    }
+
+    /// The id of `key`, given to it the first time it is used.
+    fn id(&self, key: &K) -> Id {
+        if let Some(id) = self.ids.get(key) {
+            return *id;
+        }
+        match self.ids.entry(key.clone()) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let id = Id::from_u32(self.counter.fetch_add(1));
+                self.entries.insert(
+                    id,
+                    Box::new(MapEntry {
+                        key: key.clone(),
+                        value: None,
+                        changed_at: Revision::start(),
+                    }),
+                );
+                entry.insert(id);
+                id
+            }
+        }
+    }
+
+    fn dependency_index(&self, id: Option<Id>) -> DependencyIndex {
+        DependencyIndex {
+            ingredient_index: self.index,
+            key_index: id,
+        }
+    }
+}
+
+// Returns `u` but with the lifetime of `t`.
+//
+// Safe if you know that data at `u` will remain shared
+// until the reference `t` expires.
+unsafe fn transmute_lifetime<'t, T, U>(_t: &'t T, u: Option<&U>) -> Option<&'t U> {
+    std::mem::transmute(u)
+}
+
+impl<DB: ?Sized, K, V> Ingredient<DB> for InputMapIngredient<K, V>
+where
+    K: Eq + Hash,
+{
+    fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+        CycleRecoveryStrategy::Panic
+    }
+
+    fn maybe_changed_after(&self, _db: &DB, input: DependencyIndex, revision: Revision) -> bool {
+        match input.key_index {
+            Some(id) => self.entries.get(&id).unwrap().changed_at > revision,
+            None => self.keys_changed_at.load() > revision,
+        }
+    }
+
+    fn origin(&self, _key_index: Id) -> Option<QueryOrigin> {
+        None
+    }
+
+    fn value_revisions(&self, key_index: Id) -> Option<ValueRevisions> {
+        self.entries.get(&key_index).map(|entry| ValueRevisions {
+            changed_at: entry.changed_at,
+            verified_at: None,
+            durability: Durability::LOW,
+        })
+    }
+
+    fn mark_validated_output(
+        &self,
+        _db: &DB,
+        _executor: DatabaseKeyIndex,
+        _output_key: Option<Id>,
+    ) {
+    }
+
+    fn remove_stale_output(
+        &self,
+        _db: &DB,
+        _executor: DatabaseKeyIndex,
+        _stale_output_key: Option<Id>,
+    ) {
+    }
+
+    fn salsa_struct_deleted(&self, _db: &DB, _id: Id) {
+        panic!("unexpected call: input maps are not keyed by salsa structs");
+    }
+
+    fn reset_for_new_revision(&mut self) {
+        panic!("unexpected call: input maps don't register for resets");
+    }
+
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<Id> {
+        vec![]
+    }
+
+    fn keys_changed_since(&self, revision: Revision) -> Vec<Id> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.value().changed_at > revision)
+            .map(|entry| *entry.key())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<Id> {
+        self.entries.iter().map(|entry| *entry.key()).collect()
+    }
+
+    fn sweep(&mut self, _is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        // The id of a removed key may still be read from.
+        0
+    }
+
+    fn synthetic_write(&mut self, key_index: Option<Id>, revision: Revision) -> Option<Durability> {
+        match key_index {
+            Some(id) => self.entries.get_mut(&id).map(|mut entry| {
+                entry.changed_at = revision;
+                Durability::LOW
+            }),
+            None => {
+                self.keys_changed_at.store(revision);
+                for mut entry in self.entries.iter_mut() {
+                    entry.changed_at = revision;
+                }
+                Some(Durability::LOW)
+            }
+        }
+    }
+
+    fn evict_values(&mut self, _key_index: Option<Id>) -> usize {
+        // Input values cannot be recomputed.
+        0
+    }
+
+    fn compact(&mut self) {
+        self.ids.shrink_to_fit();
+        self.entries.shrink_to_fit();
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        let entries = self.entries.len();
+        IngredientUsage {
+            entries,
+            bytes: entries
+                * (std::mem::size_of::<(K, Id)>()
+                    + std::mem::size_of::<(Id, Box<MapEntry<K, V>>)>()
+                    + std::mem::size_of::<MapEntry<K, V>>()),
+            heap_bytes: 0,
+        }
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
+    fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_index(self.debug_name, index, fmt)
+    }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+}
+
+impl<K, V> IngredientRequiresReset for InputMapIngredient<K, V> {
+    const RESET_ON_NEW_REVISION: bool = false;
+}
@@ -0,0 +1,183 @@
+//! A page, served over HTTP, to browse what a database stores while a
+//! program runs (enabled by the `inspect` feature).
+//!
+//! ```ignore
+//! let inspector = salsa::inspect::Inspector::serve("127.0.0.1:7878")?;
+//! loop {
+//!     // ... read a line, set inputs, run queries ...
+//!     inspector.update(&db);
+//! }
+//! ```
+//!
+//! The page lists the values of the last [`Inspector::update`] (the same ones as
+//! [`crate::debug::dump`]), and shows, for the one clicked, its revisions, its
+//! durability, the values it read and created, and the values that read it. It
+//! polls the inspector, so the revision ticks as the program updates it.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::Database;
+
+const PAGE: &str = include_str!("inspect.html");
+
+/// How long a client has to send its request, and then to take each part of
+/// the response: the server responds to one client at a time, so one that
+/// stalls holds up the others until then.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The most bytes the request line and the headers of a request can take.
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+/// Serves the page of the inspector from a thread of its own, until dropped.
+///
+/// The database is not shared with that thread: [`Inspector::update`] takes a
+/// dump of it, which the page shows until the next update.
+pub struct Inspector {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+
+    /// Disconnected when the server thread exits.
+    exited: mpsc::Receiver<()>,
+}
+
+struct Shared {
+    /// The JSON of the last update.
+    dump: Mutex<Arc<String>>,
+
+    /// Set when the inspector is dropped, to stop the server.
+    stopped: AtomicBool,
+}
+
+impl Inspector {
+    /// Starts serving the page at `addr`; use port 0 to let the system pick one,
+    /// then [`Inspector::local_addr`] to find it.
+    pub fn serve(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            dump: Mutex::new(Arc::new(String::from(r#"{"revision":null,"values":[]}"#))),
+            stopped: AtomicBool::new(false),
+        });
+        let (exited_tx, exited) = mpsc::channel();
+        thread::Builder::new()
+            .name("salsa-inspect".to_string())
+            .spawn({
+                let shared = shared.clone();
+                move || {
+                    for stream in listener.incoming() {
+                        if shared.stopped.load(Ordering::Acquire) {
+                            break;
+                        }
+                        // A client that goes away midway is none of our business.
+                        if let Ok(stream) = stream {
+                            let _ = respond(&shared, stream);
+                        }
+                    }
+                    // Stop listening before letting the drop know the server has stopped.
+                    drop(listener);
+                    drop(exited_tx);
+                }
+            })?;
+        Ok(Self {
+            addr,
+            shared,
+            exited,
+        })
+    }
+
+    /// The address the page is served at.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Replaces what the page shows with the current contents of `db`.
+    pub fn update<Db: ?Sized + Database>(&self, db: &Db) {
+        let mut json = Vec::new();
+        crate::debug::dump(db, &mut json).unwrap();
+        *self.shared.dump.lock() = Arc::new(String::from_utf8(json).unwrap());
+    }
+}
+
+impl Drop for Inspector {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        // Wake up the server, which is waiting for a connection, or responding
+        // to one, which takes a few timeouts at most. Don't wait for longer than
+        // that: a client reading the response slowly can hold the server up for
+        // longer, and the server then stops on its own once it is done.
+        if TcpStream::connect(self.addr).is_ok() {
+            let _ = self.exited.recv_timeout(3 * REQUEST_TIMEOUT);
+        }
+    }
+}
+
+fn respond(shared: &Shared, stream: TcpStream) -> io::Result<()> {
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let head = Deadline {
+        stream: &stream,
+        deadline: Instant::now() + REQUEST_TIMEOUT,
+    };
+    let mut reader = BufReader::new(head.take(MAX_REQUEST_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers; no request has a body.
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    if reader.get_ref().limit() == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the request head is too large",
+        ));
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/")) => ("200 OK", "text/html", Arc::new(PAGE.to_string())),
+        (Some("GET"), Some("/dump.json")) => {
+            ("200 OK", "application/json", shared.dump.lock().clone())
+        }
+        _ => (
+            "404 Not Found",
+            "text/plain",
+            Arc::new("not found".to_string()),
+        ),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}; charset=utf-8\r\n\
+         Content-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+/// Reads from a stream, failing once the deadline has passed.
+struct Deadline<'s> {
+    stream: &'s TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
@@ -4,15 +4,24 @@ use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
+#[cfg(feature = "persist")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
 use crate::durability::Durability;
 use crate::id::AsId;
-use crate::ingredient::{fmt_index, IngredientRequiresReset};
+use crate::ingredient::{fmt_index, IngredientRequiresReset, ValueRevisions};
 use crate::key::DependencyIndex;
+use crate::limits::{Limit, LimitExceeded};
+#[cfg(feature = "persist")]
+use crate::persist::PersistentIngredient;
 use crate::runtime::local_state::QueryOrigin;
 use crate::runtime::Runtime;
+use crate::usage::{EvictionCandidate, IngredientUsage};
 use crate::DatabaseKeyIndex;
 
-use super::hash::FxDashMap;
+use super::hash::{FxDashMap, TableDashMap};
 use super::ingredient::Ingredient;
 use super::routes::IngredientIndex;
 use super::Revision;
@@ -33,7 +42,7 @@ pub struct InternedIngredient<Id: InternedId, Data: InternedData> {
     /// Maps from data to the existing interned id for that data.
     ///
     /// Deadlock requirement: We access `value_map` while holding lock on `key_map`, but not vice versa.
-    key_map: FxDashMap<Data, Id>,
+    key_map: TableDashMap<Data, Id>,
 
     /// Maps from an interned id to its data.
     ///
@@ -43,6 +52,10 @@ pub struct InternedIngredient<Id: InternedId, Data: InternedData> {
     /// counter for the next id.
     counter: AtomicCell<u32>,
 
+    /// If true, ids are derived from the hash of the data rather than taken from
+    /// `counter`; see [`InternedIngredient::set_content_ids`].
+    content_ids: bool,
+
     /// Stores the revision when this interned ingredient was last cleared.
     /// You can clear an interned table at any point, deleting all its entries,
     /// but that will make anything dependent on those entries dirty and in need
@@ -56,6 +69,10 @@ pub struct InternedIngredient<Id: InternedId, Data: InternedData> {
     /// guaranteeing that there are no more references to it.
     deleted_entries: SegQueue<Box<Data>>,
 
+    /// Ids that were interned outside of any query. Since no query records a
+    /// dependency on them, they are never reclaimed by `sweep`.
+    pinned: FxDashMap<Id, ()>,
+
     debug_name: &'static str,
 }
 
@@ -70,19 +87,72 @@ where
             key_map: Default::default(),
             value_map: Default::default(),
             counter: AtomicCell::default(),
+            content_ids: false,
             reset_at: Revision::start(),
             deleted_entries: Default::default(),
+            pinned: Default::default(),
             debug_name,
         }
     }
 
+    /// Interns `data`, unwinding with [`LimitExceeded`] if it is new while the
+    /// ingredient holds [`crate::Limits::max_interned_keys`] values already.
+    ///
+    /// The limit is approximate: the number of values is read when a new one is
+    /// about to be stored, so threads interning new values at the same time may
+    /// each add one beyond it.
     pub fn intern(&self, runtime: &Runtime, data: Data) -> Id {
-        runtime.report_tracked_read(
-            DependencyIndex::for_table(self.ingredient_index),
-            Durability::MAX,
-            self.reset_at,
-        );
+        let max = runtime.limits().max_interned_keys();
+        self.intern_unlimited(runtime, data, || {
+            if let Some(max) = max {
+                if self.value_map.len() >= max {
+                    let ingredient = self.ingredient_index;
+                    let query = runtime.active_query().map(|(key, _)| key);
+                    LimitExceeded::new(Limit::InternedKeys { ingredient, max }, query).throw();
+                }
+            }
+        })
+    }
 
+    /// Like [`InternedIngredient::intern`], but not bounded by the number of values:
+    /// the tracked structs bound the number created in each revision instead.
+    /// `on_new` is invoked before a fresh id is allocated for `data`, and may unwind
+    /// to refuse it.
+    pub(crate) fn intern_unlimited(
+        &self,
+        runtime: &Runtime,
+        data: Data,
+        on_new: impl FnOnce(),
+    ) -> Id {
+        let id = self.intern_id(data, on_new);
+        if runtime.active_query().is_some() {
+            self.report_read(runtime, id);
+        } else {
+            // Nothing records that the caller holds on to `id`,
+            // so it must never be reclaimed by `sweep`.
+            self.pinned.insert(id, ());
+        }
+        id
+    }
+
+    /// Returns the id of `data` if it was interned already, without interning it.
+    ///
+    /// No value records that `data` is missing, so a query that does not find it
+    /// is treated as having performed an untracked read, and executed again in
+    /// each new revision (in case `data` was interned since).
+    pub fn lookup(&self, runtime: &Runtime, data: &Data) -> Option<Id> {
+        let id = self.key_map.get(data).map(|id| *id);
+        match id {
+            Some(id) if runtime.active_query().is_some() => self.report_read(runtime, id),
+            Some(id) => {
+                self.pinned.insert(id, ());
+            }
+            None => runtime.report_untracked_read(),
+        }
+        id
+    }
+
+    fn intern_id(&self, data: Data, on_new: impl FnOnce()) -> Id {
         // Optimisation to only get read lock on the map if the data has already
         // been interned.
         if let Some(id) = self.key_map.get(&data) {
@@ -94,6 +164,12 @@ where
             dashmap::mapref::entry::Entry::Occupied(entry) => *entry.get(),
             // We won any races so should intern the data
             dashmap::mapref::entry::Entry::Vacant(entry) => {
+                on_new();
+                if self.content_ids {
+                    let id = self.insert_at_content_id(data);
+                    entry.insert(id);
+                    return id;
+                }
                 let next_id = self.counter.fetch_add(1);
                 let next_id = Id::from_id(crate::id::Id::from_u32(next_id));
                 let old_value = self.value_map.insert(next_id, Box::new(data));
@@ -107,6 +183,46 @@ where
         }
     }
 
+    /// Stores `data` under the id given by its hash or, if another value
+    /// already has that id, under the first free id after it.
+    fn insert_at_content_id(&self, data: Data) -> Id {
+        let mut candidate = (crate::hash::hash(&data) % u64::from(crate::Id::MAX_U32)) as u32;
+        loop {
+            let id = Id::from_id(crate::id::Id::from_u32(candidate));
+            match self.value_map.entry(id) {
+                dashmap::mapref::entry::Entry::Occupied(_) => {
+                    candidate = (candidate + 1) % crate::Id::MAX_U32;
+                }
+                dashmap::mapref::entry::Entry::Vacant(entry) => {
+                    entry.insert(Box::new(data));
+                    return id;
+                }
+            }
+        }
+    }
+
+    /// Derives the id of each value from the hash of the value, so that the same
+    /// values get the same ids in every process, whatever the order in which they
+    /// are interned. Two values with the same hash (modulo the range of ids) get
+    /// the ids following it in the order they are interned, so only their ids depend
+    /// on that order.
+    pub(crate) fn set_content_ids(&mut self) {
+        self.content_ids = true;
+    }
+
+    /// Records that the active query used the value for `id`.
+    /// Reads are recorded per id so that `sweep` can tell which ids are still in use.
+    fn report_read(&self, runtime: &Runtime, id: Id) {
+        runtime.report_tracked_read(
+            DependencyIndex {
+                ingredient_index: self.ingredient_index,
+                key_index: Some(id.as_id()),
+            },
+            Durability::MAX,
+            self.reset_at,
+        );
+    }
+
     pub(crate) fn reset_at(&self) -> Revision {
         self.reset_at
     }
@@ -116,15 +232,12 @@ where
         self.reset_at = revision;
         self.key_map.clear();
         self.value_map.clear();
+        self.pinned.clear();
     }
 
     #[track_caller]
     pub fn data<'db>(&'db self, runtime: &'db Runtime, id: Id) -> &'db Data {
-        runtime.report_tracked_read(
-            DependencyIndex::for_table(self.ingredient_index),
-            Durability::MAX,
-            self.reset_at,
-        );
+        self.report_read(runtime, id);
 
         let data = match self.value_map.get(&id) {
             Some(d) => d,
@@ -176,16 +289,57 @@ where
         self.deleted_entries.push(key);
     }
 
+    /// Removes every entry whose id is rejected by `keep`, returning how many were removed.
+    /// Since this requires `&mut self`, the data is freed immediately.
+    /// The ids of the values currently interned.
+    pub(crate) fn ids(&self) -> Vec<crate::Id> {
+        self.value_map
+            .iter()
+            .map(|entry| entry.key().as_id())
+            .collect()
+    }
+
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(Id) -> bool) -> usize {
+        self.clear_deleted_indices();
+        let len = self.value_map.len();
+        self.value_map.retain(|id, _| keep(*id));
+        let value_map = &self.value_map;
+        self.key_map.retain(|_, id| value_map.contains_key(id));
+        len - self.value_map.len()
+    }
+
     pub(crate) fn clear_deleted_indices(&mut self) {
         std::mem::take(&mut self.deleted_entries);
     }
+
+    /// Shrinks the tables to the values interned now; see [`Ingredient::compact`].
+    pub(crate) fn compact(&mut self) {
+        self.clear_deleted_indices();
+        self.key_map.shrink_to_fit();
+        self.value_map.shrink_to_fit();
+        self.pinned.shrink_to_fit();
+    }
+
+    /// Each interned value is stored twice: as a key of `key_map`, and boxed in `value_map`.
+    pub(crate) fn usage(&self) -> IngredientUsage {
+        use std::mem::size_of;
+
+        let entries = self.value_map.len();
+        let entry_bytes =
+            size_of::<(Data, Id)>() + size_of::<(Id, Box<Data>)>() + size_of::<Data>();
+        IngredientUsage {
+            entries,
+            bytes: entries * entry_bytes + self.deleted_entries.len() * size_of::<Data>(),
+            heap_bytes: 0,
+        }
+    }
 }
 
 // Returns `u` but with the lifetime of `t`.
 //
 // Safe if you know that data at `u` will remain shared
 // until the reference `t` expires.
-unsafe fn transmute_lifetime<'t, 'u, T, U>(_t: &'t T, u: &'u U) -> &'t U {
+unsafe fn transmute_lifetime<'t, T, U>(_t: &'t T, u: &U) -> &'t U {
     std::mem::transmute(u)
 }
 
@@ -194,8 +348,12 @@ where
     Id: InternedId,
     Data: InternedData,
 {
-    fn maybe_changed_after(&self, _db: &DB, _input: DependencyIndex, revision: Revision) -> bool {
-        revision < self.reset_at
+    fn maybe_changed_after(&self, _db: &DB, input: DependencyIndex, revision: Revision) -> bool {
+        // Ids are never reused, so an id changes only by being removed.
+        match input.key_index {
+            Some(id) => revision < self.reset_at || !self.value_map.contains_key(&Id::from_id(id)),
+            None => revision < self.reset_at,
+        }
     }
 
     fn cycle_recovery_strategy(&self) -> crate::cycle::CycleRecoveryStrategy {
@@ -206,6 +364,10 @@ where
         None
     }
 
+    fn value_revisions(&self, _key_index: crate::Id) -> Option<ValueRevisions> {
+        None
+    }
+
     fn mark_validated_output(
         &self,
         _db: &DB,
@@ -241,9 +403,125 @@ where
         panic!("unexpected call: interned ingredients do not register for salsa struct deletion events");
     }
 
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys_changed_since(&self, _revision: Revision) -> Vec<crate::Id> {
+        vec![]
+    }
+
+    fn keys(&self) -> Vec<crate::Id> {
+        self.ids()
+    }
+
+    fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        // Every query that interned or read an id depends on it,
+        // so anything unreachable from the live memos is no longer in use.
+        let ingredient_index = self.ingredient_index;
+        let pinned = std::mem::take(&mut self.pinned);
+        let removed = self.retain(|id| {
+            pinned.contains_key(&id)
+                || is_live(DatabaseKeyIndex {
+                    ingredient_index,
+                    key_index: id.as_id(),
+                })
+        });
+        self.pinned = pinned;
+        removed
+    }
+
+    fn synthetic_write(
+        &mut self,
+        _key_index: Option<crate::Id>,
+        _revision: Revision,
+    ) -> Option<Durability> {
+        // Interned values never change.
+        None
+    }
+
+    fn evict_values(&mut self, _key_index: Option<crate::Id>) -> usize {
+        // Interned values are needed to tell whether they changed.
+        0
+    }
+
+    fn compact(&mut self) {
+        self.compact()
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        self.usage()
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+}
+
+/// Serialized form of an [`InternedIngredient`].
+#[cfg(feature = "persist")]
+#[derive(Serialize, Deserialize)]
+struct PersistedInterned<Data> {
+    counter: u32,
+    reset_at: Revision,
+    entries: Vec<(crate::Id, Data)>,
+}
+
+#[cfg(feature = "persist")]
+impl<Id, Data> PersistentIngredient for InternedIngredient<Id, Data>
+where
+    Id: InternedId,
+    Data: InternedData + Serialize + DeserializeOwned,
+{
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn serialize(
+        &self,
+        _is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value> {
+        let mut entries = self
+            .value_map
+            .iter()
+            .map(|entry| Ok((entry.key().as_id(), serde_json::to_value(&**entry.value())?)))
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        entries.sort_by_key(|(id, _)| *id);
+        serde_json::to_value(PersistedInterned {
+            counter: self.counter.load(),
+            reset_at: self.reset_at,
+            entries,
+        })
+    }
+
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()> {
+        let PersistedInterned {
+            counter,
+            reset_at,
+            entries,
+        } = serde_json::from_value::<PersistedInterned<Data>>(data)?;
+        self.key_map.clear();
+        self.value_map.clear();
+        self.pinned.clear();
+        for (id, data) in entries {
+            let id = Id::from_id(id);
+            self.key_map.insert(data.clone(), id);
+            self.value_map.insert(id, Box::new(data));
+            // We don't know who holds on to restored ids (e.g., restored inputs).
+            self.pinned.insert(id, ());
+        }
+        self.counter.store(counter);
+        self.reset_at = reset_at;
+        Ok(())
+    }
 }
 
 impl<Id, Data> IngredientRequiresReset for InternedIngredient<Id, Data>
@@ -1,4 +1,7 @@
+use std::any::{Any, TypeId};
+
 use crate::{
+    hash::FxHashMap,
     storage::{HasJar, JarFromJars},
     Database, DbWithJar,
 };
@@ -12,3 +15,43 @@ pub trait Jar<'db>: Sized {
     where
         DB: JarFromJars<Self> + DbWithJar<Self>;
 }
+
+/// The jars added to a database at runtime with [`crate::Storage::add_jar`],
+/// stored after the jars listed in `#[salsa::db]`.
+#[derive(Default)]
+pub struct DynamicJars {
+    jars: FxHashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl DynamicJars {
+    /// The jar `J`. Panics if it was not added to the database.
+    pub fn jar<J: Any>(&self) -> &J {
+        match self.jars.get(&TypeId::of::<J>()) {
+            Some(jar) => jar.downcast_ref().unwrap(),
+            None => panic!("{}", Self::missing::<J>()),
+        }
+    }
+
+    /// Like [`DynamicJars::jar`], but for `&mut` references.
+    pub fn jar_mut<J: Any>(&mut self) -> &mut J {
+        match self.jars.get_mut(&TypeId::of::<J>()) {
+            Some(jar) => jar.downcast_mut().unwrap(),
+            None => panic!("{}", Self::missing::<J>()),
+        }
+    }
+
+    pub(crate) fn contains<J: Any>(&self) -> bool {
+        self.jars.contains_key(&TypeId::of::<J>())
+    }
+
+    pub(crate) fn insert<J: Any + Send + Sync>(&mut self, jar: J) {
+        self.jars.insert(TypeId::of::<J>(), Box::new(jar));
+    }
+
+    fn missing<J>() -> String {
+        format!(
+            "the jar `{}` was not added to the database (see `Storage::add_jar`)",
+            std::any::type_name::<J>()
+        )
+    }
+}
@@ -1,12 +1,16 @@
 use std::fmt::Debug;
 
-use crate::{Database, DebugWithDb, Id, IngredientIndex};
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Database, DebugWithDb, Durability, Id, IngredientIndex, Revision};
 
 /// An integer that uniquely identifies a particular query instance within the
 /// database. Used to track dependencies between queries. Fully ordered and
 /// equatable but those orderings are arbitrary, and meant to be used only for
 /// inserting into maps and the like.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub struct DependencyIndex {
     pub(crate) ingredient_index: IngredientIndex,
     pub(crate) key_index: Option<Id>,
@@ -25,6 +29,15 @@ impl DependencyIndex {
         }
     }
 
+    /// The value `key_index` of the ingredient at `ingredient_index`, or, if `None`,
+    /// the ingredient as a whole.
+    pub fn new(ingredient_index: IngredientIndex, key_index: Option<Id>) -> Self {
+        Self {
+            ingredient_index,
+            key_index,
+        }
+    }
+
     pub fn ingredient_index(self) -> IngredientIndex {
         self.ingredient_index
     }
@@ -53,6 +66,7 @@ where
 /// that is actively executing. In that case, the `key_index` cannot be
 /// None.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub struct DatabaseKeyIndex {
     pub(crate) ingredient_index: IngredientIndex,
     pub(crate) key_index: Id,
@@ -60,6 +74,14 @@ pub struct DatabaseKeyIndex {
 // ANCHOR_END: DatabaseKeyIndex
 
 impl DatabaseKeyIndex {
+    /// The value `key_index` of the ingredient at `ingredient_index`.
+    pub fn new(ingredient_index: IngredientIndex, key_index: Id) -> Self {
+        Self {
+            ingredient_index,
+            key_index,
+        }
+    }
+
     pub fn ingredient_index(self) -> IngredientIndex {
         self.ingredient_index
     }
@@ -69,6 +91,24 @@ impl DatabaseKeyIndex {
     }
 }
 
+/// A [`DatabaseKeyIndex`] resolved into plain data, which can be kept or sent
+/// elsewhere without the database; see [`Database::describe_key`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyDescription {
+    /// The name of the ingredient, e.g., of the tracked function.
+    pub ingredient_name: &'static str,
+
+    /// The key as `Debug` shows it with the database, e.g., `my_function(3)`.
+    pub key_debug: String,
+
+    /// The durability of the value, if the ingredient tracks it
+    /// (input fields and memoized values do, interned values do not).
+    pub durability: Option<Durability>,
+
+    /// The revision in which the value last changed, if the ingredient tracks it.
+    pub changed_at: Option<Revision>,
+}
+
 impl<Db> crate::debug::DebugWithDb<Db> for DatabaseKeyIndex
 where
     Db: ?Sized + Database,
@@ -84,6 +124,15 @@ where
     }
 }
 
+impl From<IngredientIndex> for DependencyIndex {
+    fn from(ingredient_index: IngredientIndex) -> Self {
+        Self {
+            ingredient_index,
+            key_index: None,
+        }
+    }
+}
+
 impl From<DatabaseKeyIndex> for DependencyIndex {
     fn from(value: DatabaseKeyIndex) -> Self {
         Self {
@@ -1,10 +1,21 @@
+// The items of the `fs` module are declared with the macros, which refer to `salsa`.
+#[cfg(feature = "fs")]
+extern crate self as salsa;
+
 pub mod accumulator;
+pub mod attach;
+pub mod cache;
 pub mod cancelled;
 pub mod cycle;
 pub mod database;
 pub mod debug;
+pub mod diff;
+pub mod dispatch;
 pub mod durability;
 pub mod event;
+pub mod frozen;
+#[cfg(feature = "fs")]
+pub mod fs;
 pub mod function;
 pub mod hash;
 pub mod id;
@@ -12,21 +23,43 @@ pub mod ingredient;
 pub mod ingredient_list;
 pub mod input;
 pub mod input_field;
+pub mod input_map;
+#[cfg(feature = "inspect")]
+pub mod inspect;
 pub mod interned;
 pub mod jar;
 pub mod key;
+pub mod limits;
+#[cfg(all(feature = "mmap", unix))]
+pub mod mmap;
+pub mod panicked;
+pub mod par;
+#[cfg(feature = "persist")]
+pub mod persist;
 pub mod plumbing;
+#[cfg(feature = "persist")]
+pub mod record;
 pub mod revision;
 pub mod routes;
 pub mod runtime;
 pub mod salsa_struct;
 pub mod setter;
+pub mod statistics;
 pub mod storage;
+pub mod subscription;
+pub mod symbol;
+pub mod testing;
 #[doc(hidden)]
 pub mod tracked_struct;
+pub mod update;
+pub mod usage;
+pub mod validate;
+pub mod view;
 
-pub use self::cancelled::Cancelled;
+pub use self::attach::attach;
+pub use self::cancelled::{CancellationHandle, Cancelled};
 pub use self::cycle::Cycle;
+pub use self::cycle::CycleRecoveryAction;
 pub use self::database::Database;
 pub use self::database::ParallelDatabase;
 pub use self::database::Snapshot;
@@ -35,19 +68,34 @@ pub use self::debug::DebugWithDb;
 pub use self::durability::Durability;
 pub use self::event::Event;
 pub use self::event::EventKind;
+pub use self::frozen::Frozen;
 pub use self::id::AsId;
 pub use self::id::Id;
 pub use self::key::DatabaseKeyIndex;
+pub use self::key::KeyDescription;
+pub use self::limits::{LimitExceeded, Limits};
+pub use self::panicked::QueryPanicked;
+pub use self::par::par_map;
 pub use self::revision::Revision;
 pub use self::routes::IngredientIndex;
 pub use self::runtime::Runtime;
 pub use self::storage::DbWithJar;
 pub use self::storage::Storage;
+pub use self::storage::WritePolicy;
+pub use self::subscription::Subscription;
+pub use self::symbol::Symbol;
 pub use self::tracked_struct::TrackedStructData;
 pub use self::tracked_struct::TrackedStructId;
+pub use self::update::Update;
+pub use self::view::AsView;
 pub use salsa_2022_macros::accumulator;
 pub use salsa_2022_macros::db;
 pub use salsa_2022_macros::input;
+pub use salsa_2022_macros::input_map;
 pub use salsa_2022_macros::interned;
 pub use salsa_2022_macros::jar;
 pub use salsa_2022_macros::tracked;
+pub use salsa_2022_macros::DebugWithDb;
+#[cfg(feature = "persist")]
+#[doc(hidden)]
+pub use serde;
@@ -0,0 +1,193 @@
+//! Bounds on the work a database does for its inputs, so that an input crafted
+//! (or generated) to recurse or to create values without end fails the query
+//! reading it, rather than overflowing the stack or exhausting the memory of the
+//! process serving every other request too. See [`crate::Storage::set_limits`].
+
+use std::{
+    fmt,
+    panic::{self, UnwindSafe},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{key::DatabaseKeyIndex, IngredientIndex};
+
+/// The limits set with [`crate::Storage::set_limits`]; `None` means no limit,
+/// as by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// How many queries may be executing (or being verified) at once on a thread,
+    /// each called by the previous one.
+    pub max_query_depth: Option<usize>,
+
+    /// How many values an ingredient of an interned struct may hold. Threads
+    /// interning new values at the same time may each add one beyond it.
+    pub max_interned_keys: Option<usize>,
+
+    /// How many tracked structs may be created in a revision, over all queries.
+    /// A query executing again and creating a struct it created in an earlier
+    /// revision, with the same fields, reuses it, which does not count.
+    pub max_tracked_structs: Option<usize>,
+}
+
+/// The limit that was exceeded, see [`LimitExceeded`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// [`Limits::max_query_depth`].
+    #[non_exhaustive]
+    QueryDepth { max: usize },
+
+    /// [`Limits::max_interned_keys`], for the interned struct with ingredient `ingredient`.
+    #[non_exhaustive]
+    InternedKeys {
+        ingredient: IngredientIndex,
+        max: usize,
+    },
+
+    /// [`Limits::max_tracked_structs`].
+    #[non_exhaustive]
+    TrackedStructs { max: usize },
+}
+
+/// A panic payload indicating that a query exceeded one of the [`Limits`] of the
+/// database. The queries on the stack of the thread unwind; other threads blocked
+/// on them see [`crate::Cancelled::PropagatedPanic`]. As for cancellation, nothing
+/// is memoized for the queries that unwound, so they are executed again when read
+/// next. Use [`LimitExceeded::catch`] to turn the unwind into an error, e.g., to
+/// report it in answer to the request whose input exceeded the limit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LimitExceeded {
+    limit: Limit,
+    query: Option<DatabaseKeyIndex>,
+}
+
+impl LimitExceeded {
+    pub(crate) fn new(limit: Limit, query: Option<DatabaseKeyIndex>) -> Self {
+        Self { limit, query }
+    }
+
+    pub(crate) fn throw(self) -> ! {
+        // As for `Cancelled`, resume rather than panic, to skip the panic hook.
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// The offending query: the one that would have been executed past the
+    /// maximum depth, or the one creating the value past the maximum number.
+    /// `None` if the value was created outside of any query.
+    pub fn query(&self) -> Option<DatabaseKeyIndex> {
+        self.query
+    }
+
+    /// Runs `f`, and catches any query exceeding the limits of the database.
+    pub fn catch<F, T>(f: F) -> Result<T, LimitExceeded>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(exceeded) => Err(*exceeded),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::QueryDepth { max } => write!(f, "more than {max} nested queries")?,
+            Limit::InternedKeys { ingredient, max } => {
+                write!(f, "more than {max} values interned in {ingredient:?}")?
+            }
+            Limit::TrackedStructs { max } => {
+                write!(f, "more than {max} tracked structs created in a revision")?
+            }
+        }
+        if let Some(query) = self.query {
+            write!(f, " (in query {query:?})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// The [`Limits`] of a database, shared by all of its handles, and the count of
+/// the tracked structs created in the current revision.
+#[derive(Debug)]
+pub(crate) struct LimitsSlot {
+    /// `usize::MAX` stands for no limit, so as to check the limits without a lock.
+    max_query_depth: AtomicUsize,
+    max_interned_keys: AtomicUsize,
+    max_tracked_structs: AtomicUsize,
+
+    tracked_structs: AtomicUsize,
+}
+
+impl Default for LimitsSlot {
+    fn default() -> Self {
+        Self {
+            max_query_depth: AtomicUsize::new(usize::MAX),
+            max_interned_keys: AtomicUsize::new(usize::MAX),
+            max_tracked_structs: AtomicUsize::new(usize::MAX),
+            tracked_structs: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl LimitsSlot {
+    pub(crate) fn set(&self, limits: Limits) {
+        let store = |slot: &AtomicUsize, max: Option<usize>| {
+            slot.store(max.unwrap_or(usize::MAX), Ordering::Relaxed)
+        };
+        store(&self.max_query_depth, limits.max_query_depth);
+        store(&self.max_interned_keys, limits.max_interned_keys);
+        store(&self.max_tracked_structs, limits.max_tracked_structs);
+    }
+
+    pub(crate) fn get(&self) -> Limits {
+        Limits {
+            max_query_depth: load(&self.max_query_depth),
+            max_interned_keys: load(&self.max_interned_keys),
+            max_tracked_structs: load(&self.max_tracked_structs),
+        }
+    }
+
+    /// Unwinds if a query pushed above `depth` others would be too deep.
+    pub(crate) fn check_query_depth(&self, depth: usize, query: DatabaseKeyIndex) {
+        let max = self.max_query_depth.load(Ordering::Relaxed);
+        if depth >= max {
+            LimitExceeded::new(Limit::QueryDepth { max }, Some(query)).throw();
+        }
+    }
+
+    pub(crate) fn max_interned_keys(&self) -> Option<usize> {
+        load(&self.max_interned_keys)
+    }
+
+    /// Counts a tracked struct newly created in the current revision, unwinding
+    /// if it is one too many.
+    pub(crate) fn count_tracked_struct(&self, query: DatabaseKeyIndex) {
+        let max = self.max_tracked_structs.load(Ordering::Relaxed);
+        if max != usize::MAX && self.tracked_structs.fetch_add(1, Ordering::Relaxed) >= max {
+            LimitExceeded::new(Limit::TrackedStructs { max }, Some(query)).throw();
+        }
+    }
+
+    /// Starts counting the tracked structs of a new revision.
+    pub(crate) fn new_revision(&self) {
+        self.tracked_structs.store(0, Ordering::Relaxed);
+    }
+}
+
+fn load(slot: &AtomicUsize) -> Option<usize> {
+    match slot.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
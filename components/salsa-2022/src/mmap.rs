@@ -0,0 +1,287 @@
+//! A [`CacheBackend`] in a memory-mapped file (enabled by the experimental `mmap`
+//! feature, on Unix), shared by the processes of the same build: one process (e.g.,
+//! a daemon) owns the writes, and the others (e.g., command-line tools) read the
+//! values it computed straight from the mapping, without asking it for them.
+//!
+//! ```ignore
+//! // In the daemon:
+//! let cache = salsa::mmap::MappedCache::create("target/salsa.cache")?;
+//! db.storage.set_cache_backend(Some(Arc::new(cache)));
+//!
+//! // In a tool:
+//! let cache = salsa::mmap::MappedCache::open("target/salsa.cache")?;
+//! db.storage.set_cache_backend(Some(Arc::new(cache)));
+//! ```
+//!
+//! Only the values of the tracked functions with the `cache` option are stored,
+//! under the conditions of the [`crate::cache`] module: as they must not contain
+//! ids, the interned tables of the processes are not shared.
+//!
+//! The file starts with a header holding the length of its committed part, after
+//! which the writer appends each value as a record. A record is visible to the
+//! readers once the length covers it, and the file is only ever appended to, so
+//! the readers never see a record being written: they remap the file when it has
+//! grown past their mapping. Creating the file again while other processes have
+//! it open is not supported.
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::unix::{fs::FileExt, io::AsRawFd},
+    path::Path,
+    ptr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use parking_lot::Mutex;
+
+use crate::cache::{CacheBackend, Fingerprint};
+
+/// Identifies the files written by [`MappedCache::create`], in this format.
+const MAGIC: &[u8; 8] = b"salsamm1";
+
+/// The length of the header: the magic number, then the committed length.
+const HEADER_LEN: usize = 16;
+
+/// The values of tracked functions, in a memory-mapped file written by one
+/// process and read by any number of others.
+pub struct MappedCache {
+    file: File,
+
+    /// True if this process writes the file, with [`MappedCache::create`]; the
+    /// values put in a cache opened for reading are dropped.
+    writer: bool,
+
+    state: Mutex<State>,
+}
+
+struct State {
+    mapping: Mapping,
+
+    /// The length of the committed part of the file, up to which the records
+    /// have been indexed.
+    indexed: usize,
+
+    /// The offset and length of the value of each record, by query and fingerprint.
+    index: HashMap<(Box<str>, Fingerprint), (usize, usize)>,
+}
+
+impl MappedCache {
+    /// Creates the file at `path`, replacing any existing one, and opens it for writing.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<MappedCache> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&(HEADER_LEN as u64).to_le_bytes())?;
+        Self::with_file(file, true)
+    }
+
+    /// Opens the file at `path`, written by [`MappedCache::create`] in another
+    /// process, for reading.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<MappedCache> {
+        let file = File::open(path)?;
+        Self::with_file(file, false)
+    }
+
+    fn with_file(file: File, writer: bool) -> io::Result<MappedCache> {
+        let mapping = Mapping::new(&file)?;
+        if mapping.len < HEADER_LEN || &mapping.bytes()[..MAGIC.len()] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a file written by `MappedCache::create`",
+            ));
+        }
+        Ok(MappedCache {
+            file,
+            writer,
+            state: Mutex::new(State {
+                mapping,
+                indexed: HEADER_LEN,
+                index: HashMap::new(),
+            }),
+        })
+    }
+
+    /// The number of values in the file, as of its last commit.
+    pub fn len(&self) -> usize {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        state.index.len()
+    }
+
+    /// True if there are no values in the file.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indexes the records committed since the last call, remapping the file if
+    /// they are past the mapping. Failures leave the index as it was: the values
+    /// not indexed are then computed.
+    fn refresh(&self, state: &mut State) {
+        let committed = state.mapping.committed_len();
+        if committed <= state.indexed {
+            return;
+        }
+        if committed > state.mapping.len {
+            match Mapping::new(&self.file) {
+                Ok(mapping) if mapping.len >= committed => state.mapping = mapping,
+                _ => return,
+            }
+        }
+
+        let State {
+            mapping,
+            indexed,
+            index,
+        } = state;
+        let bytes = &mapping.bytes()[..committed];
+        while *indexed < committed {
+            match Record::parse(bytes, *indexed) {
+                Some(record) => {
+                    index.insert(
+                        (record.query.into(), record.fingerprint),
+                        (record.value_offset, record.value_len),
+                    );
+                    *indexed = record.value_offset + record.value_len;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Appends a record to the file, then commits it.
+    fn append(&self, query: &str, fingerprint: Fingerprint, value: &[u8]) -> io::Result<()> {
+        let mut record = Vec::with_capacity(8 + query.len() + 16 + value.len());
+        record.extend_from_slice(&(query.len() as u32).to_le_bytes());
+        record.extend_from_slice(query.as_bytes());
+        record.extend_from_slice(&fingerprint.0.to_le_bytes());
+        record.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        record.extend_from_slice(value);
+
+        let state = self.state.lock();
+        let end = state.mapping.committed_len().max(state.indexed);
+        self.file.write_all_at(&record, end as u64)?;
+        let committed = (end + record.len()) as u64;
+        self.file
+            .write_all_at(&committed.to_le_bytes(), MAGIC.len() as u64)
+    }
+}
+
+impl CacheBackend for MappedCache {
+    fn get(&self, query: &str, fingerprint: Fingerprint) -> Option<Vec<u8>> {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        let &(offset, len) = state.index.get(&(Box::from(query), fingerprint))?;
+        Some(state.mapping.bytes()[offset..offset + len].to_vec())
+    }
+
+    fn put(&self, query: &str, fingerprint: Fingerprint, value: Vec<u8>) {
+        if !self.writer || value.len() > u32::MAX as usize {
+            return;
+        }
+        // A value that could not be written is computed again by the readers.
+        let _ = self.append(query, fingerprint, &value);
+    }
+}
+
+/// A record of the file: the length and bytes of the query name, the fingerprint,
+/// then the length and bytes of the value, all little-endian.
+struct Record<'b> {
+    query: &'b str,
+    fingerprint: Fingerprint,
+    value_offset: usize,
+    value_len: usize,
+}
+
+impl<'b> Record<'b> {
+    /// Parses the record at `offset` of `bytes`, if it is entirely within them.
+    fn parse(bytes: &'b [u8], offset: usize) -> Option<Self> {
+        let take = |offset: usize, len: usize| bytes.get(offset..offset.checked_add(len)?);
+        let query_len = u32::from_le_bytes(take(offset, 4)?.try_into().ok()?) as usize;
+        let query = std::str::from_utf8(take(offset + 4, query_len)?).ok()?;
+        let offset = offset + 4 + query_len;
+        let fingerprint = u128::from_le_bytes(take(offset, 16)?.try_into().ok()?);
+        let value_len = u32::from_le_bytes(take(offset + 16, 4)?.try_into().ok()?) as usize;
+        let value_offset = offset + 20;
+        take(value_offset, value_len)?;
+        Some(Record {
+            query,
+            fingerprint: Fingerprint(fingerprint),
+            value_offset,
+            value_len,
+        })
+    }
+}
+
+/// A read-only mapping of a whole file, as long as it was when mapped.
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only, and only unmapped when dropped.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn new(file: &File) -> io::Result<Mapping> {
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Mapping {
+                ptr: ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: the file is open, and the mapping is only read in `bytes`.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping { ptr, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` maps `len` bytes, which the file is only appended to past
+        // the committed length, and never truncated while mapped.
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+
+    /// The committed length in the header, as last written by the writer.
+    fn committed_len(&self) -> usize {
+        if self.len < HEADER_LEN {
+            return 0;
+        }
+        // SAFETY: the header is within the mapping, and the page is aligned, so
+        // the length at offset 8 is too.
+        let committed = unsafe { &*(self.ptr.cast::<u8>().add(MAGIC.len()) as *const AtomicU64) };
+        committed.load(Ordering::Acquire) as usize
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            // SAFETY: `ptr` and `len` are those of a mapping no one borrows anymore.
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
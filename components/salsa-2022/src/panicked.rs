@@ -0,0 +1,80 @@
+use std::{
+    any::Any,
+    fmt,
+    panic::{self, UnwindSafe},
+};
+
+use crate::key::DatabaseKeyIndex;
+
+/// A panic payload indicating that a salsa query panicked when executing.
+///
+/// The thread executing the query sees the original panic. Other threads that
+/// were blocked waiting for its result, and any later attempt to fetch it in
+/// the same revision, see this payload instead. The query is executed again,
+/// if needed, in the next revision.
+#[derive(Clone, Debug)]
+pub struct QueryPanicked {
+    database_key: DatabaseKeyIndex,
+    message: Option<String>,
+}
+
+impl QueryPanicked {
+    /// Describes the panic with payload `payload` that occurred when executing `database_key`.
+    /// If the panic was itself caused by another query panicking, that query is reported.
+    pub(crate) fn new(database_key: DatabaseKeyIndex, payload: &(dyn Any + Send)) -> Self {
+        if let Some(panicked) = payload.downcast_ref::<QueryPanicked>() {
+            return panicked.clone();
+        }
+        let message = if let Some(s) = payload.downcast_ref::<&'static str>() {
+            Some(s.to_string())
+        } else {
+            payload.downcast_ref::<String>().cloned()
+        };
+        Self {
+            database_key,
+            message,
+        }
+    }
+
+    pub(crate) fn throw(self) -> ! {
+        // As for `Cancelled`, resume rather than panic, to skip the panic hook.
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// The query that panicked.
+    pub fn database_key(&self) -> DatabaseKeyIndex {
+        self.database_key
+    }
+
+    /// The message of the panic, if it had one.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Runs `f`, and catches any panic of a query that panicked in another thread,
+    /// or earlier in the current revision.
+    pub fn catch<F, T>(f: F) -> Result<T, QueryPanicked>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(panicked) => Err(*panicked),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl fmt::Display for QueryPanicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "query {:?} panicked", self.database_key)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for QueryPanicked {}
@@ -0,0 +1,91 @@
+//! Running a tracked function on many keys in parallel.
+
+use std::ops::Deref;
+
+use crate::Database;
+
+/// Calls `f(db, key)` for each of `keys` and returns the results in order,
+/// doing the actual work on worker threads (as many as the available parallelism).
+///
+/// This is meant to be called from within a tracked function, with `f` invoking
+/// other tracked functions. Those are first executed on worker threads, each using
+/// a handle created by `fork` (which typically wraps [`Storage::fork`](`crate::Storage::fork`)).
+/// Then `f` is called once more for each key on `db` itself: these calls find the
+/// memoized values, and record the dependencies of the calling query just as if
+/// `f` had been called sequentially.
+///
+/// Since dyn database types cannot be cloned, `fork` has to be provided by the
+/// caller, e.g. through a method on the jar's database trait. (Spelling out the
+/// lifetimes in a helper function is what lets the worker handles be used as
+/// the same `dyn Db` type as `db`.)
+///
+/// ```rust,ignore
+/// trait Db: salsa::DbWithJar<Jar> + Send {
+///     fn fork_db(&self) -> Box<dyn Db>;
+/// }
+///
+/// fn fork<'db>(db: &(dyn Db + 'db)) -> Box<dyn Db + 'db> {
+///     db.fork_db()
+/// }
+///
+/// #[salsa::tracked]
+/// fn check_all(db: &dyn Db, krate: Crate) -> Vec<Diagnostics> {
+///     salsa::par_map(db, krate.files(db), fork, |db, file| check_file(db, file))
+/// }
+/// ```
+///
+/// With the `single-threaded` feature, `f` is only called on `db`, sequentially.
+///
+/// # Panics
+///
+/// If a call panics on a worker thread (including on cancellation), the panic
+/// is resumed on the calling thread.
+///
+/// # Deadlock warning
+///
+/// `f` must not (transitively) depend on the query that calls `par_map`.
+/// Such a cycle would leave a worker blocked on the calling thread,
+/// which is itself waiting for the workers to finish.
+pub fn par_map<Db, H, K, V>(
+    db: &Db,
+    keys: Vec<K>,
+    fork: impl Fn(&Db) -> H,
+    f: impl Fn(&Db, K) -> V + Sync,
+) -> Vec<V>
+where
+    Db: ?Sized + Database,
+    H: Deref<Target = Db> + Send,
+    K: Clone + Send + Sync,
+{
+    let threads = if cfg!(feature = "single-threaded") {
+        0
+    } else {
+        std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(keys.len())
+    };
+    if threads > 0 {
+        let f = &f;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = keys
+                .chunks(keys.len().div_ceil(threads))
+                .map(|chunk| {
+                    // `db` is not `Sync`, so the forks are created on this thread.
+                    let forked_db = fork(db);
+                    scope.spawn(move || {
+                        for key in chunk {
+                            f(&forked_db, key.clone());
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                if let Err(payload) = handle.join() {
+                    std::panic::resume_unwind(payload);
+                }
+            }
+        });
+    }
+
+    keys.into_iter().map(|key| f(db, key)).collect()
+}
@@ -0,0 +1,322 @@
+//! Support for writing the contents of a database to disk (or anywhere else
+//! serde can write) and loading them back in a later process. Requires the
+//! `persist` feature.
+//!
+//! Only ingredients that opt in are saved: the `persist` option can be given to
+//! `#[salsa::input]`, `#[salsa::interned]`, `#[salsa::tracked]` structs and
+//! `#[salsa::tracked]` functions. The ingredients of those items are registered
+//! with [`Routes::push_persistent`](`crate::routes::Routes::push_persistent`) and
+//! must implement [`PersistentIngredient`].
+//!
+//! Persisted data refers to ingredients by their [`IngredientIndex`] and to
+//! values by their [`Id`](`crate::Id`), so it can only be loaded into a database
+//! with the same jars, declared in the same order, as the one that wrote it.
+//! The exception is mounting it as the base of another database with
+//! [`Storage::mount_base`](`crate::Storage::mount_base`), which matches the
+//! ingredients by name and translates the indices within the data.
+//! Ids are stable because the id counters of each ingredient are saved too, as
+//! are the strings interned as [`Symbol`](`crate::Symbol`)s.
+//!
+//! A memoized value is saved only if everything recorded in its dependency
+//! edges is persistent as well and it did not perform untracked reads;
+//! otherwise there would be no way to validate it after loading. The memoized
+//! values of a tracked function are saved with its fingerprint, and not loaded
+//! by a version of the function with another one (see
+//! [`Configuration::QUERY_FINGERPRINT`](`crate::function::Configuration::QUERY_FINGERPRINT`)),
+//! so that they are computed again.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Fingerprint;
+use crate::hash::FxHashMap;
+use crate::{record::Mutation, Id, IngredientIndex, Revision, Runtime};
+
+/// The persisted contents of a database, produced by
+/// [`Storage::persist`](`crate::Storage::persist`) and consumed by
+/// [`Storage::restore`](`crate::Storage::restore`).
+///
+/// This type implements `Serialize` and `Deserialize`; the values stored within
+/// are kept in a self-describing form, so it must be written with a
+/// self-describing format (e.g., JSON).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistedDatabase {
+    /// The "last changed" revision for each durability, as stored in the runtime.
+    revisions: Vec<Revision>,
+
+    /// The data for each persistent ingredient, in order of ingredient index.
+    ingredients: Vec<PersistedIngredient>,
+
+    /// The strings interned as [`crate::Symbol`]s, in order, which persisted values
+    /// may refer to.
+    #[serde(default)]
+    symbols: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedIngredient {
+    index: IngredientIndex,
+
+    /// The type name of the jar of the ingredient, used to match it when mounting.
+    #[serde(default)]
+    jar: String,
+
+    /// Used to detect that the ingredient at `index` is not the one that wrote `data`.
+    debug_name: String,
+
+    /// See [`PersistentIngredient::query_fingerprint`].
+    #[serde(default)]
+    fingerprint: Option<Fingerprint>,
+
+    data: serde_json::Value,
+}
+
+impl PersistedDatabase {
+    pub(crate) fn new(revisions: Vec<Revision>, symbols: Vec<String>) -> Self {
+        Self {
+            revisions,
+            ingredients: vec![],
+            symbols,
+        }
+    }
+
+    pub(crate) fn push_ingredient(
+        &mut self,
+        index: IngredientIndex,
+        jar: &str,
+        debug_name: &str,
+        fingerprint: Option<Fingerprint>,
+        data: serde_json::Value,
+    ) {
+        self.ingredients.push(PersistedIngredient {
+            index,
+            jar: jar.to_string(),
+            debug_name: debug_name.to_string(),
+            fingerprint,
+            data,
+        });
+    }
+
+    pub(crate) fn revisions(&self) -> &[Revision] {
+        &self.revisions
+    }
+
+    pub(crate) fn take_symbols(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.symbols)
+    }
+
+    pub(crate) fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    pub(crate) fn ingredients(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            IngredientIndex,
+            &str,
+            &str,
+            Option<Fingerprint>,
+            &serde_json::Value,
+        ),
+    > {
+        self.ingredients.iter().map(|i| {
+            (
+                i.index,
+                &i.jar[..],
+                &i.debug_name[..],
+                i.fingerprint,
+                &i.data,
+            )
+        })
+    }
+
+    pub(crate) fn into_ingredients(
+        self,
+    ) -> impl Iterator<
+        Item = (
+            IngredientIndex,
+            String,
+            Option<Fingerprint>,
+            serde_json::Value,
+        ),
+    > {
+        self.ingredients
+            .into_iter()
+            .map(|i| (i.index, i.debug_name, i.fingerprint, i.data))
+    }
+}
+
+thread_local! {
+    /// While the ingredients of a base database are deserialized by
+    /// [`crate::Storage::mount_base`], maps the indices of the base's ingredients
+    /// to those of the database it is mounted in.
+    static INDEX_TRANSLATION: RefCell<Option<FxHashMap<IngredientIndex, IngredientIndex>>> =
+        const { RefCell::new(None) };
+}
+
+/// Runs `op` with every [`IngredientIndex`] deserialized on this thread translated
+/// through `translation`.
+pub(crate) fn with_index_translation<R>(
+    translation: FxHashMap<IngredientIndex, IngredientIndex>,
+    op: impl FnOnce() -> R,
+) -> R {
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            INDEX_TRANSLATION.with(|t| *t.borrow_mut() = None);
+        }
+    }
+
+    INDEX_TRANSLATION.with(|t| *t.borrow_mut() = Some(translation));
+    let _reset = Reset;
+    op()
+}
+
+/// The index that a deserialized `index` stands for; `None` if it names an
+/// ingredient of the base database being mounted that has no counterpart.
+pub(crate) fn translate_index(index: IngredientIndex) -> Option<IngredientIndex> {
+    INDEX_TRANSLATION.with(|t| match &*t.borrow() {
+        Some(translation) => translation.get(&index).copied(),
+        None => Some(index),
+    })
+}
+
+/// Implemented by ingredients that can be saved into a [`PersistedDatabase`].
+pub trait PersistentIngredient {
+    /// Name used to check, on restore, that the data belongs to this ingredient.
+    fn debug_name(&self) -> &'static str;
+
+    /// The fingerprint of the code computing the data of this ingredient, if it
+    /// computes any: data saved with another fingerprint is not loaded, since
+    /// the code may compute something else now.
+    fn query_fingerprint(&self) -> Option<Fingerprint> {
+        None
+    }
+
+    /// Serializes the contents of this ingredient.
+    ///
+    /// `is_persistent` reports whether the ingredient with a given index will
+    /// also be saved. Memoized values whose dependencies are not all persistent
+    /// must be skipped, since they could not be validated after a restore.
+    fn serialize(
+        &self,
+        is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value>;
+
+    /// Replaces the contents of this ingredient with `data`, previously
+    /// produced by [`PersistentIngredient::serialize`].
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()>;
+
+    /// The serialized value this ingredient stores for `id`, for a
+    /// [`crate::record::Recording`]; `None` if it stores none.
+    fn recorded_value(&self, _id: Id) -> serde_json::Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+
+    /// Applies `mutation` of `id`, recorded from an ingredient like this one (see
+    /// [`crate::record`]). Returns false if this ingredient cannot apply it.
+    fn replay(
+        &mut self,
+        _runtime: &mut Runtime,
+        _id: Id,
+        _mutation: &Mutation,
+    ) -> serde_json::Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Error that occurs while persisting or restoring a database.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PersistError {
+    /// Some ingredient data could not be converted to or from its serialized form.
+    Serde(serde_json::Error),
+
+    /// The persisted data does not match the ingredients of this database; this
+    /// generally means the jars have changed since the data was written.
+    IngredientMismatch {
+        index: IngredientIndex,
+        expected: String,
+        found: Option<&'static str>,
+    },
+
+    /// The persisted data was written with a different number of durability levels.
+    DurabilityMismatch { expected: usize, found: usize },
+
+    /// A recorded mutation of `id` could not be replayed by the ingredient at `index`
+    /// (e.g., the recording created the input with another id); this generally means
+    /// that the database it is replayed in was not fresh.
+    ReplayMismatch { index: IngredientIndex, id: Id },
+
+    /// The persisted symbol `index` is already the symbol of another string in this
+    /// database (which should be freshly created, apart from predefined symbols).
+    SymbolMismatch { index: u32, symbol: String },
+
+    /// The base database being mounted has data for an ingredient of `jar` that
+    /// this database does not have (or does not persist).
+    MissingIngredient { jar: String, debug_name: String },
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(err: serde_json::Error) -> Self {
+        PersistError::Serde(err)
+    }
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Serde(err) => write!(f, "failed to (de)serialize ingredient: {}", err),
+            PersistError::IngredientMismatch {
+                index,
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "persisted data for `{}` found at {:?}, which holds `{}`",
+                expected, index, found
+            ),
+            PersistError::IngredientMismatch {
+                index,
+                expected,
+                found: None,
+            } => write!(
+                f,
+                "persisted data for `{}` found at {:?}, which is not persistent",
+                expected, index
+            ),
+            PersistError::DurabilityMismatch { expected, found } => write!(
+                f,
+                "persisted data has {} durability levels, expected {}",
+                found, expected
+            ),
+            PersistError::ReplayMismatch { index, id } => write!(
+                f,
+                "recorded mutation of {:?} cannot be replayed at {:?}",
+                id, index
+            ),
+            PersistError::SymbolMismatch { index, symbol } => write!(
+                f,
+                "persisted symbol {} cannot be restored, `{}` was interned first",
+                index, symbol
+            ),
+            PersistError::MissingIngredient { jar, debug_name } => write!(
+                f,
+                "the base database has data for `{}` of `{}`, which this database does not persist",
+                debug_name, jar
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Serde(err) => Some(err),
+            _ => None,
+        }
+    }
+}
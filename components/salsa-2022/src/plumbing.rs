@@ -1 +1,36 @@
+//! The API for implementing ingredients outside of salsa.
+//!
+//! Everything re-exported here is what the salsa macros build on, and is kept
+//! stable across releases in the same way as the rest of the public API, so that
+//! other crates can define custom ingredients (e.g., a side table of values set
+//! by queries) without depending on salsa's internals.
+//!
+//! An ingredient is a type implementing [`Ingredient`] and
+//! [`IngredientRequiresReset`]. To put it in a jar, declare a type to list in the
+//! `#[salsa::jar]` struct and implement [`IngredientsFor`] for it: its
+//! `create_ingredients` method registers the ingredient with [`Routes::push`]
+//! (or [`Routes::push_persistent`]), which returns the [`IngredientIndex`] the
+//! ingredient should store. The jar then implements [`HasIngredientsFor`], which
+//! gives the ingredient to the methods of the type through [`HasJar::jar`] and
+//! [`HasJar::jar_mut`].
+//!
+//! Each value of an ingredient is identified by a [`DependencyIndex`] (or a
+//! [`DatabaseKeyIndex`] when it has a key), made of the ingredient's index and
+//! an [`Id`] of its choice. The ingredient reports reads of its values with
+//! [`Runtime::report_tracked_read`], and writes with
+//! [`Runtime::report_tracked_write`] after getting `&mut` access to the jar
+//! (which starts a new revision). Salsa then asks the ingredient whether a value
+//! changed with [`Ingredient::maybe_changed_after`] when validating the memos
+//! that read it.
 
+pub use crate::cycle::CycleRecoveryStrategy;
+pub use crate::ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions};
+pub use crate::key::{DatabaseKeyIndex, DependencyIndex};
+pub use crate::routes::{IngredientIndex, Routes};
+pub use crate::runtime::local_state::{EdgeKind, QueryEdges, QueryOrigin};
+pub use crate::salsa_struct::SalsaStructInDb;
+pub use crate::storage::{
+    DbWithJar, HasIngredientsFor, HasJar, HasJars, IngredientsFor, JarFromJars,
+};
+pub use crate::usage::{EvictionCandidate, IngredientUsage};
+pub use crate::{Durability, Id, Revision, Runtime};
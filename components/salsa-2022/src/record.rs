@@ -0,0 +1,226 @@
+//! Support for recording the mutations of inputs, to replay them later against
+//! a fresh database (e.g., to reproduce a bug that only appears after a
+//! particular sequence of edits).
+//!
+//! Once [`crate::Database::start_recording`] is called, the creation of each
+//! input and each write to one of its fields is recorded, along with the new
+//! value. [`crate::Database::take_recording`] returns the mutations, grouped by
+//! the revision in which they happened, as a [`Recording`] that can be
+//! serialized. [`crate::Database::replay`] applies the mutations of one revision,
+//! so that the queries can be run again between revisions.
+//!
+//! Like [`crate::persist`], values are serialized with their ingredients, so only
+//! inputs declared with the `persist` option can be recorded: the mutations of
+//! other inputs are left out of the recording (with a warning in the log).
+//! Deleting inputs is not recorded either. A recording refers to ingredients by
+//! their index, so it can only be replayed in a database with the same jars.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Durability, Id, IngredientIndex, Revision};
+
+/// The input mutations recorded since [`crate::Database::start_recording`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Recording {
+    revisions: Vec<RecordedRevision>,
+}
+
+impl Recording {
+    /// The recorded mutations, grouped by revision, oldest first.
+    pub fn revisions(&self) -> &[RecordedRevision] {
+        &self.revisions
+    }
+}
+
+/// The input mutations recorded in one revision; see [`crate::Database::replay`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedRevision {
+    mutations: Vec<RecordedMutation>,
+}
+
+impl RecordedRevision {
+    pub fn mutations(&self) -> &[RecordedMutation] {
+        &self.mutations
+    }
+}
+
+/// One recorded mutation of the ingredient at [`RecordedMutation::ingredient_index`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedMutation {
+    index: IngredientIndex,
+
+    /// Used to detect that the ingredient at `index` is not the one that recorded this.
+    debug_name: String,
+
+    id: Id,
+
+    mutation: Mutation,
+}
+
+impl RecordedMutation {
+    pub fn ingredient_index(&self) -> IngredientIndex {
+        self.index
+    }
+
+    pub fn debug_name(&self) -> &str {
+        &self.debug_name
+    }
+
+    /// The id of the input that was created or written.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn mutation(&self) -> &Mutation {
+        &self.mutation
+    }
+}
+
+/// What happened to an input in a [`RecordedMutation`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Mutation {
+    /// A new input was created.
+    NewInput,
+
+    /// A field of a new input was given its first value.
+    NewField {
+        value: serde_json::Value,
+        durability: Durability,
+    },
+
+    /// A field of an existing input was set (or updated in place) to `value`.
+    SetField {
+        value: serde_json::Value,
+        durability: Durability,
+    },
+}
+
+/// Collects mutations while recording. Input ingredients report their mutations
+/// without serializing the values (they cannot, not knowing whether the values are
+/// serializable); [`crate::Storage`] later reads the values back from the
+/// persistent ingredients. That reads the value that was written, because each
+/// write happens after a call to [`crate::Storage::jars_mut`], which flushes the
+/// mutations reported before it.
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    enabled: AtomicBool,
+    log: Mutex<RecorderLog>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderLog {
+    pending: Vec<PendingMutation>,
+    recorded: Vec<(Revision, RecordedMutation)>,
+}
+
+#[derive(Debug)]
+struct PendingMutation {
+    revision: Revision,
+    index: IngredientIndex,
+    id: Id,
+    kind: PendingKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum PendingKind {
+    NewInput,
+    NewField(Durability),
+    SetField(Durability),
+}
+
+/// Reads back the value behind a pending mutation: the debug name of the ingredient
+/// at a given index, and the serialized value it stores for an id, if it can tell.
+pub(crate) type ReadValue<'a> =
+    dyn Fn(IngredientIndex, Id) -> Option<(&'static str, Option<serde_json::Value>)> + 'a;
+
+impl Recorder {
+    pub(crate) fn start(&self) {
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn report(
+        &self,
+        revision: Revision,
+        index: IngredientIndex,
+        id: Id,
+        kind: PendingKind,
+    ) {
+        if self.enabled.load(Ordering::Relaxed) {
+            self.log.lock().pending.push(PendingMutation {
+                revision,
+                index,
+                id,
+                kind,
+            });
+        }
+    }
+
+    /// Serializes the values of the pending mutations with `read_value`.
+    pub(crate) fn flush(&self, read_value: &ReadValue<'_>) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut log = self.log.lock();
+        let log = &mut *log;
+        for pending in log.pending.drain(..) {
+            let (debug_name, value) = match read_value(pending.index, pending.id) {
+                Some(found) => found,
+                None => {
+                    log::warn!(
+                        "not recording a mutation of {:?}, which is not persistent",
+                        pending.index
+                    );
+                    continue;
+                }
+            };
+            let mutation = match (pending.kind, value) {
+                (PendingKind::NewInput, _) => Mutation::NewInput,
+                (PendingKind::NewField(durability), Some(value)) => {
+                    Mutation::NewField { value, durability }
+                }
+                (PendingKind::SetField(durability), Some(value)) => {
+                    Mutation::SetField { value, durability }
+                }
+                // The input was deleted since.
+                (_, None) => continue,
+            };
+            log.recorded.push((
+                pending.revision,
+                RecordedMutation {
+                    index: pending.index,
+                    debug_name: debug_name.to_string(),
+                    id: pending.id,
+                    mutation,
+                },
+            ));
+        }
+    }
+
+    /// Stops recording, and returns what was recorded (after a [`Recorder::flush`]).
+    pub(crate) fn take(&self) -> Recording {
+        self.enabled.store(false, Ordering::SeqCst);
+        let mut log = self.log.lock();
+        log.pending.clear();
+        let mut recording = Recording::default();
+        let mut last_revision = None;
+        for (revision, mutation) in log.recorded.drain(..) {
+            if last_revision != Some(revision) {
+                last_revision = Some(revision);
+                recording
+                    .revisions
+                    .push(RecordedRevision { mutations: vec![] });
+            }
+            recording
+                .revisions
+                .last_mut()
+                .unwrap()
+                .mutations
+                .push(mutation);
+        }
+        recording
+    }
+}
@@ -1,6 +1,9 @@
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
 /// Value of the initial revision, as a u64. We don't use 0
 /// because we want to use a `NonZeroUsize`.
 const START: usize = 1;
@@ -8,15 +11,19 @@ const START: usize = 1;
 /// A unique identifier for the current version of the database; each
 /// time an input is changed, the revision number is incremented.
 /// `Revision` is used internally to track which values may need to be
-/// recomputed, but is not something you should have to interact with
-/// directly as a user of salsa.
+/// recomputed; users mostly need it to ask what changed since then
+/// (see [`crate::Database::keys_changed_since`]).
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "persist", serde(transparent))]
 pub struct Revision {
     generation: NonZeroUsize,
 }
 
 impl Revision {
-    pub(crate) fn start() -> Self {
+    /// The first revision of a database, in which the values that were never
+    /// written are considered to have last changed.
+    pub fn start() -> Self {
         Self::from(START)
     }
 
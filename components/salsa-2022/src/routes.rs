@@ -1,4 +1,12 @@
+#[cfg(feature = "persist")]
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+#[cfg(feature = "persist")]
+use std::sync::Arc;
+
 use crate::ingredient::IngredientRequiresReset;
+#[cfg(feature = "persist")]
+use crate::persist::PersistentIngredient;
 
 use super::{ingredient::Ingredient, storage::HasJars};
 
@@ -6,12 +14,26 @@ use super::{ingredient::Ingredient, storage::HasJars};
 /// The database contains a number of jars, and each jar contains a number of ingredients.
 /// Each ingredient is given a unique index as the database is being created.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "persist", derive(Serialize))]
+#[cfg_attr(feature = "persist", serde(transparent))]
 pub struct IngredientIndex(u32);
 
+/// Translated when the data of a base database is mounted (see
+/// [`crate::Storage::mount_base`]), since its indices are those of the base.
+#[cfg(feature = "persist")]
+impl<'de> Deserialize<'de> for IngredientIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let index = IngredientIndex(u32::deserialize(deserializer)?);
+        crate::persist::translate_index(index).ok_or_else(|| {
+            de::Error::custom(format!("{:?} of the base database is not mounted", index))
+        })
+    }
+}
+
 impl IngredientIndex {
     /// Create an ingredient index from a usize.
     fn from(v: usize) -> Self {
-        assert!(v < (std::u32::MAX as usize));
+        assert!(v < (u32::MAX as usize));
         Self(v as u32)
     }
 
@@ -40,6 +62,20 @@ pub type DynRoute<DB: HasJars> = dyn Fn(&DB::Jars) -> (&dyn Ingredient<DB>) + Se
 pub type DynMutRoute<DB: HasJars> =
     dyn Fn(&mut DB::Jars) -> (&mut dyn Ingredient<DB>) + Send + Sync;
 
+/// Like a `DynRoute`, but yields the ingredient as a [`PersistentIngredient`].
+#[cfg(feature = "persist")]
+#[allow(type_alias_bounds)]
+#[allow(unused_parens)]
+pub type DynPersistRoute<DB: HasJars> =
+    dyn Fn(&DB::Jars) -> (&dyn PersistentIngredient) + Send + Sync;
+
+/// Like a `DynPersistRoute`, but for `&mut` references.
+#[cfg(feature = "persist")]
+#[allow(type_alias_bounds)]
+#[allow(unused_parens)]
+pub type DynPersistMutRoute<DB: HasJars> =
+    dyn Fn(&mut DB::Jars) -> (&mut dyn PersistentIngredient) + Send + Sync;
+
 /// The "routes" structure is used to navigate the database.
 /// The database contains a number of jars, and each jar contains a number of ingredients.
 /// When the database is created, it creates each jar in turn.
@@ -56,6 +92,18 @@ pub struct Routes<DB: HasJars> {
 
     /// Indices of routes which need a 'reset' call.
     needs_reset: Vec<IngredientIndex>,
+
+    /// Ingredients registered with [`Routes::push_persistent`], in order of index.
+    #[cfg(feature = "persist")]
+    #[allow(clippy::type_complexity)]
+    persistent: Vec<(
+        IngredientIndex,
+        Box<DynPersistRoute<DB>>,
+        Box<DynPersistMutRoute<DB>>,
+    )>,
+
+    /// The type name of each jar, with the index of its first ingredient.
+    jars: Vec<(IngredientIndex, &'static str)>,
 }
 
 impl<DB: HasJars> Routes<DB> {
@@ -64,6 +112,25 @@ impl<DB: HasJars> Routes<DB> {
         Routes {
             routes: vec![],
             needs_reset: vec![],
+            #[cfg(feature = "persist")]
+            persistent: vec![],
+            jars: vec![],
+        }
+    }
+
+    /// Records that the ingredients pushed from now on belong to the jar `jar`,
+    /// the type name of the jar struct.
+    pub fn begin_jar(&mut self, jar: &'static str) {
+        self.jars
+            .push((IngredientIndex::from(self.routes.len()), jar));
+    }
+
+    /// The type name of the jar of the ingredient `index` (see [`Routes::begin_jar`]).
+    pub fn jar_name(&self, index: IngredientIndex) -> &'static str {
+        let position = self.jars.partition_point(|&(first, _)| first <= index);
+        match position.checked_sub(1) {
+            Some(position) => self.jars[position].1,
+            None => "",
         }
     }
 
@@ -102,6 +169,37 @@ impl<DB: HasJars> Routes<DB> {
         index
     }
 
+    /// Like [`Routes::push`], but also records that the ingredient should be
+    /// saved when the database is persisted (see [`crate::persist`]).
+    #[cfg(feature = "persist")]
+    pub fn push_persistent<I>(
+        &mut self,
+        route: impl (Fn(&DB::Jars) -> &I) + Send + Sync + 'static,
+        mut_route: impl (Fn(&mut DB::Jars) -> &mut I) + Send + Sync + 'static,
+    ) -> IngredientIndex
+    where
+        I: Ingredient<DB> + IngredientRequiresReset + PersistentIngredient + 'static,
+    {
+        let route = Arc::new(route);
+        let mut_route = Arc::new(mut_route);
+        let index = self.push(
+            {
+                let route = route.clone();
+                move |jars| route(jars)
+            },
+            {
+                let mut_route = mut_route.clone();
+                move |jars| mut_route(jars)
+            },
+        );
+        self.persistent.push((
+            index,
+            Box::new(move |jars| route(jars)),
+            Box::new(move |jars| mut_route(jars)),
+        ));
+        index
+    }
+
     /// Given an ingredient index, return the "route"
     /// (a function that, given a `&Jars`, returns the ingredient).
     pub fn route(&self, index: IngredientIndex) -> &dyn Fn(&DB::Jars) -> &dyn Ingredient<DB> {
@@ -117,10 +215,54 @@ impl<DB: HasJars> Routes<DB> {
         &self.routes[index.as_usize()].1
     }
 
+    /// Returns the index of every ingredient, in order.
+    pub fn indices(&self) -> impl Iterator<Item = IngredientIndex> + '_ {
+        (0..self.routes.len()).map(IngredientIndex::from)
+    }
+
     /// Returns the mut routes for ingredients that need to be reset at the start of each revision.
     pub fn reset_routes(
         &self,
     ) -> impl Iterator<Item = &dyn Fn(&mut DB::Jars) -> &mut dyn Ingredient<DB>> + '_ {
         self.needs_reset.iter().map(|&index| self.route_mut(index))
     }
+
+    /// True if the ingredient at `index` was registered with [`Routes::push_persistent`].
+    #[cfg(feature = "persist")]
+    pub fn is_persistent(&self, index: IngredientIndex) -> bool {
+        self.persistent_position(index).is_some()
+    }
+
+    /// Returns the routes for each persistent ingredient, along with its index.
+    #[cfg(feature = "persist")]
+    pub fn persistent_routes(
+        &self,
+    ) -> impl Iterator<Item = (IngredientIndex, &DynPersistRoute<DB>)> + '_ {
+        self.persistent
+            .iter()
+            .map(|(index, route, _)| (*index, &**route))
+    }
+
+    /// Given an ingredient index, return the "route" to it as a persistent ingredient,
+    /// or `None` if it was not registered with [`Routes::push_persistent`].
+    #[cfg(feature = "persist")]
+    pub fn persistent_route(&self, index: IngredientIndex) -> Option<&DynPersistRoute<DB>> {
+        let position = self.persistent_position(index)?;
+        Some(&*self.persistent[position].1)
+    }
+
+    /// Given an ingredient index, return the "mut route" to it as a persistent ingredient,
+    /// or `None` if it was not registered with [`Routes::push_persistent`].
+    #[cfg(feature = "persist")]
+    pub fn persistent_route_mut(&self, index: IngredientIndex) -> Option<&DynPersistMutRoute<DB>> {
+        let position = self.persistent_position(index)?;
+        Some(&*self.persistent[position].2)
+    }
+
+    #[cfg(feature = "persist")]
+    fn persistent_position(&self, index: IngredientIndex) -> Option<usize> {
+        self.persistent
+            .binary_search_by_key(&index, |(i, _, _)| *i)
+            .ok()
+    }
 }
@@ -1,15 +1,27 @@
 use std::{
+    cell::RefCell,
     panic::panic_any,
     sync::{atomic::Ordering, Arc},
+    time::Instant,
 };
 
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "persist")]
+use crate::record::Recorder;
 use crate::{
-    cycle::CycleRecoveryStrategy,
+    cache::CacheBackend,
+    cycle::{CycleHead, CycleRecoveryStrategy},
     debug::DebugWithDb,
     durability::Durability,
     key::{DatabaseKeyIndex, DependencyIndex},
+    limits::LimitsSlot,
     runtime::active_query::ActiveQuery,
-    Cancelled, Cycle, Database, Event, EventKind, Revision,
+    statistics::StatisticsCounters,
+    subscription::Subscribers,
+    symbol::SymbolTable,
+    CancellationHandle, Cancelled, Cycle, Database, Event, EventKind, QueryPanicked, Revision,
 };
 
 use self::{
@@ -31,14 +43,26 @@ pub struct Runtime {
     /// Local state that is specific to this runtime (thread).
     local_state: local_state::LocalState,
 
+    /// Set to cancel the queries of this runtime, and of its forks.
+    cancellation: CancellationHandle,
+
     /// Shared state that is accessible via all runtimes.
     shared_state: Arc<shared_state::SharedState>,
+
+    /// For each speculation in progress on this runtime (see
+    /// [`crate::Database::speculate`]), innermost last, the ingredients written in it.
+    speculations: RefCell<Vec<Vec<IngredientIndex>>>,
+
+    /// The phases of the functions executing on this runtime, innermost last;
+    /// see [`Runtime::enter_phase`].
+    active_phases: RefCell<Vec<&'static str>>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum WaitResult {
     Completed,
-    Panicked,
+    /// The query panicked; if the panic poisoned it, here is the payload for the waiters.
+    Panicked(Option<QueryPanicked>),
     Cycle(Cycle),
 }
 
@@ -51,6 +75,7 @@ pub struct RuntimeId {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub(crate) struct StampedValue<V> {
     pub(crate) value: V,
     pub(crate) durability: Durability,
@@ -72,6 +97,47 @@ impl Default for Runtime {
             id: RuntimeId { counter: 0 },
             shared_state: Default::default(),
             local_state: Default::default(),
+            cancellation: Default::default(),
+            speculations: Default::default(),
+            active_phases: Default::default(),
+        }
+    }
+}
+
+impl Runtime {
+    /// Creates a runtime that distinguishes `levels` durabilities, from
+    /// `Durability::new(0)` (i.e., `Durability::LOW`) to `Durability::new(levels - 1)`.
+    /// The default is three levels, up to `Durability::HIGH`.
+    ///
+    /// # Panics
+    ///
+    /// If `levels` is zero or more than 256.
+    pub fn with_durability_levels(levels: usize) -> Self {
+        assert!(
+            (1..=usize::from(u8::MAX) + 1).contains(&levels),
+            "a runtime needs between 1 and 256 durability levels, not {levels}"
+        );
+        Runtime {
+            shared_state: Arc::new(shared_state::SharedState::with_durabilities(levels)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Returned by [`Runtime::enter_phase`].
+pub(crate) struct PhaseGuard<'me> {
+    runtime: &'me Runtime,
+    phase: &'static str,
+    started_at: Option<Instant>,
+}
+
+impl Drop for PhaseGuard<'_> {
+    fn drop(&mut self) {
+        self.runtime.active_phases.borrow_mut().pop();
+        if let Some(started_at) = self.started_at {
+            self.runtime
+                .statistics()
+                .record_phase_time(self.phase, started_at.elapsed());
         }
     }
 }
@@ -86,14 +152,27 @@ impl std::fmt::Debug for Runtime {
 }
 
 impl Runtime {
-    pub(crate) fn id(&self) -> RuntimeId {
+    /// The id of this runtime, as found in [`Event`]s and [`Cycle`]s.
+    pub fn id(&self) -> RuntimeId {
         self.id
     }
 
-    pub(crate) fn current_revision(&self) -> Revision {
+    /// The current revision of the database; see [`crate::Database::current_revision`].
+    pub fn current_revision(&self) -> Revision {
         self.shared_state.revisions[0].load()
     }
 
+    /// The queries executing on this runtime, from the outermost to the innermost one
+    /// (which called this method). See [`crate::Database::active_query_stack`].
+    pub fn active_query_stack(&self) -> Vec<DatabaseKeyIndex> {
+        self.local_state.active_query_stack()
+    }
+
+    /// The number of queries executing (or being verified) on this thread.
+    pub(crate) fn query_depth(&self) -> usize {
+        self.local_state.query_depth()
+    }
+
     /// Returns the index of the active query along with its *current* durability/changed-at
     /// information. As the query continues to execute, naturally, that information may change.
     pub(crate) fn active_query(&self) -> Option<(DatabaseKeyIndex, StampedValue<()>)> {
@@ -109,6 +188,16 @@ impl Runtime {
             panic!("it is not legal to `snapshot` during a query (see salsa-rs/salsa#80)");
         }
 
+        Runtime {
+            cancellation: Default::default(),
+            ..self.fork()
+        }
+    }
+
+    /// Like [`Runtime::snapshot`], but may be used while a query is in progress.
+    /// The fork must be dropped before that query completes; see [`crate::par_map`].
+    /// Cancelling the queries of this runtime also cancels those of the fork.
+    pub fn fork(&self) -> Self {
         let id = RuntimeId {
             counter: self.shared_state.next_id.fetch_add(1, Ordering::SeqCst),
         };
@@ -117,10 +206,20 @@ impl Runtime {
             id,
             shared_state: self.shared_state.clone(),
             local_state: Default::default(),
+            cancellation: self.cancellation.clone(),
+            speculations: Default::default(),
+            active_phases: Default::default(),
         }
     }
 
-    pub(crate) fn report_tracked_read(
+    /// A handle to cancel the queries running on this runtime from another thread.
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.cancellation.clone()
+    }
+
+    /// Reports that the active query, if any, read the value `key_index`, which
+    /// has durability `durability` and last changed in `changed_at`.
+    pub fn report_tracked_read(
         &self,
         key_index: DependencyIndex,
         durability: Durability,
@@ -130,6 +229,26 @@ impl Runtime {
             .report_tracked_read(key_index, durability, changed_at)
     }
 
+    /// Reports that the active query read a value that is provisional on
+    /// the given fixed-point iterations (see [`CycleRecoveryStrategy::FixedPoint`]).
+    pub(crate) fn report_cycle_heads(&self, cycle_heads: &[CycleHead]) {
+        self.local_state.report_cycle_heads(cycle_heads)
+    }
+
+    /// If `database_key_index` is currently executing on this thread,
+    /// returns the fixed-point iteration it is in.
+    pub(crate) fn iteration_in_progress(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+    ) -> Option<u32> {
+        self.local_state.iteration_in_progress(database_key_index)
+    }
+
+    /// True if a memo that is provisional on `cycle_heads` can still be used.
+    pub(crate) fn cycle_heads_in_progress(&self, cycle_heads: &[CycleHead]) -> bool {
+        self.local_state.cycle_heads_in_progress(cycle_heads)
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -139,19 +258,71 @@ impl Runtime {
             .report_untracked_read(self.current_revision());
     }
 
+    /// Reports that the query depends on some state unknown to salsa, which
+    /// changes no more often than inputs of durability `durability`.
+    ///
+    /// Queries which report synthetic reads will be re-executed in the next
+    /// revision where an input of durability `durability` or lower has changed.
+    pub fn report_synthetic_read(&self, durability: Durability) {
+        let changed_at = self.last_changed_revision(durability);
+        self.local_state
+            .report_synthetic_read(durability, changed_at);
+    }
+
+    /// Reports that the value the query is computing is the same as its previous
+    /// one, even if it is not equal to it (e.g., because only the spans in it moved):
+    /// it is then backdated, so the queries that read it are not re-executed.
+    pub fn report_unchanged(&self) {
+        self.local_state.report_unchanged();
+    }
+
     /// Reports that an input with durability `durability` changed.
     /// This will update the 'last changed at' values for every durability
     /// less than or equal to `durability` to the current revision.
-    pub(crate) fn report_tracked_write(&mut self, durability: Durability) {
+    pub fn report_tracked_write(&mut self, durability: Durability) {
         let new_revision = self.current_revision();
-        for rev in &self.shared_state.revisions[1..=durability.index()] {
+        let index = self.durability_index(durability);
+        for rev in &self.shared_state.revisions[1..=index] {
             rev.store(new_revision);
         }
     }
 
+    /// How many speculations are in progress on this runtime (see
+    /// [`crate::Database::speculate`]). While it is not zero, ingredients that accept
+    /// writes must be able to undo them, and report them with
+    /// [`Runtime::report_speculative_write`].
+    pub fn speculation_depth(&self) -> usize {
+        self.speculations.borrow().len()
+    }
+
+    /// Reports that the ingredient `index` was written during the innermost
+    /// speculation, so that its [`crate::ingredient::Ingredient::undo_speculation`]
+    /// is invoked when that speculation ends.
+    pub fn report_speculative_write(&self, index: IngredientIndex) {
+        if let Some(written) = self.speculations.borrow_mut().last_mut() {
+            if !written.contains(&index) {
+                written.push(index);
+            }
+        }
+    }
+
+    pub(crate) fn begin_speculation(&mut self) {
+        self.speculations.get_mut().push(vec![]);
+    }
+
+    /// Ends the innermost speculation, returning the ingredients written in it.
+    pub(crate) fn end_speculation(&mut self) -> Vec<IngredientIndex> {
+        self.speculations
+            .get_mut()
+            .pop()
+            .expect("no speculation in progress")
+    }
+
     /// Adds `key` to the list of output created by the current query
-    /// (if not already present).
-    pub(crate) fn add_output(&self, key: DependencyIndex) {
+    /// (if not already present). If the query is validated in a later revision,
+    /// the ingredient of `key` is told with [`crate::ingredient::Ingredient::mark_validated_output`];
+    /// if it no longer creates `key`, with [`crate::ingredient::Ingredient::remove_stale_output`].
+    pub fn add_output(&self, key: DependencyIndex) {
         self.local_state.add_output(key);
     }
 
@@ -189,8 +360,37 @@ impl Runtime {
     /// value may have changed, which allows us to skip walking its
     /// dependencies.
     #[inline]
-    pub(crate) fn last_changed_revision(&self, d: Durability) -> Revision {
-        self.shared_state.revisions[d.index()].load()
+    pub fn last_changed_revision(&self, d: Durability) -> Revision {
+        self.shared_state.revisions[self.durability_index(d)].load()
+    }
+
+    /// The index of `d` in the "last changed" revisions; durabilities above
+    /// the levels this runtime distinguishes share the highest one.
+    fn durability_index(&self, d: Durability) -> usize {
+        d.index().min(self.shared_state.revisions.len() - 1)
+    }
+
+    /// The revision in which values of each durability last changed,
+    /// indexed by durability (so the first element is the current revision).
+    #[cfg(feature = "persist")]
+    pub(crate) fn last_changed_revisions(&self) -> Vec<Revision> {
+        self.shared_state
+            .revisions
+            .iter()
+            .map(|r| r.load())
+            .collect()
+    }
+
+    /// Overwrites the "last changed" revisions with ones previously obtained from
+    /// [`Runtime::last_changed_revisions`]; used when restoring a persisted database.
+    ///
+    /// This should only be done by the storage when the state is "quiescent".
+    #[cfg(feature = "persist")]
+    pub(crate) fn restore_last_changed_revisions(&mut self, revisions: &[Revision]) {
+        assert_eq!(revisions.len(), self.shared_state.revisions.len());
+        for (r, &revision) in self.shared_state.revisions.iter().zip(revisions) {
+            r.store(revision);
+        }
     }
 
     /// Starts unwinding the stack if the current revision is cancelled.
@@ -210,13 +410,17 @@ impl Runtime {
             runtime_id: self.id(),
             kind: EventKind::WillCheckCancellation,
         });
-        if self.shared_state.revision_canceled.load() {
+        if self.is_write_pending() {
             db.salsa_event(Event {
                 runtime_id: self.id(),
                 kind: EventKind::WillCheckCancellation,
             });
             self.unwind_cancelled();
         }
+        if let Some(cancelled) = self.cancellation.check() {
+            self.report_untracked_read();
+            cancelled.throw();
+        }
     }
 
     #[cold]
@@ -225,10 +429,83 @@ impl Runtime {
         Cancelled::PendingWrite.throw();
     }
 
+    /// Whether another handle is waiting to write to the database, and so for
+    /// this one to be dropped, unless it is anchored (see
+    /// [`CancellationHandle::set_anchor`]). See [`crate::Database::is_write_pending`].
+    pub fn is_write_pending(&self) -> bool {
+        self.shared_state.revision_canceled.load() && !self.cancellation.is_anchored()
+    }
+
     pub(crate) fn set_cancellation_flag(&self) {
         self.shared_state.revision_canceled.store(true);
     }
 
+    /// Clears the cancellation flag without starting a new revision.
+    /// Used when the storage needed `&mut` access but did not change any inputs.
+    pub(crate) fn clear_cancellation_flag(&mut self) {
+        self.shared_state.revision_canceled.store(false);
+    }
+
+    /// Shrinks the buffers shared between the runtimes, see [`crate::Storage::compact`].
+    pub(crate) fn compact(&mut self) {
+        self.shared_state.dependency_graph.lock().shrink_to_fit();
+    }
+
+    /// The recorder for [`crate::Database::start_recording`].
+    #[cfg(feature = "persist")]
+    pub(crate) fn recorder(&self) -> &Recorder {
+        &self.shared_state.recorder
+    }
+
+    /// The table of [`crate::Symbol`]s.
+    pub(crate) fn symbols(&self) -> &SymbolTable {
+        &self.shared_state.symbols
+    }
+
+    pub(crate) fn subscribers(&self) -> &Subscribers {
+        &self.shared_state.subscribers
+    }
+
+    /// Whether memos validated without executing their function are checked
+    /// against a new execution, see [`crate::Storage::set_shadow_verification`].
+    pub(crate) fn shadow_verification(&self) -> bool {
+        self.shared_state.shadow_verification.load()
+    }
+
+    pub(crate) fn set_shadow_verification(&self, enabled: bool) {
+        self.shared_state.shadow_verification.store(enabled);
+    }
+
+    /// Whether the time spent verifying each input of a memo is recorded,
+    /// see [`crate::Storage::set_edge_profiling`].
+    pub(crate) fn edge_profiling(&self) -> bool {
+        self.shared_state.edge_profiling.load()
+    }
+
+    pub(crate) fn set_edge_profiling(&self, enabled: bool) {
+        self.shared_state.edge_profiling.store(enabled);
+    }
+
+    /// The backend that values of tracked functions with the `cache` option are
+    /// shared through, see [`crate::Storage::set_cache_backend`].
+    pub(crate) fn cache_backend(&self) -> Option<Arc<dyn CacheBackend>> {
+        self.shared_state.cache_backend.get()
+    }
+
+    pub(crate) fn set_cache_backend(&self, backend: Option<Arc<dyn CacheBackend>>) {
+        self.shared_state.cache_backend.set(backend);
+    }
+
+    /// The limits on the work done for the inputs, see [`crate::Storage::set_limits`].
+    pub(crate) fn limits(&self) -> &LimitsSlot {
+        &self.shared_state.limits
+    }
+
+    /// The counters for [`crate::Database::query_statistics`].
+    pub(crate) fn statistics(&self) -> &StatisticsCounters {
+        &self.shared_state.statistics
+    }
+
     /// Increments the "current revision" counter and clears
     /// the cancellation flag.
     ///
@@ -238,14 +515,49 @@ impl Runtime {
         let r_new = r_old.next();
         self.shared_state.revisions[0].store(r_new);
         self.shared_state.revision_canceled.store(false);
+        self.shared_state.revision_observed.store(false);
+        self.shared_state.limits.new_revision();
+        self.cancellation.reset();
         r_new
     }
 
+    /// Records that a value was read in the current revision: fetched, read for
+    /// what it accumulated, or checked for changes (see [`crate::Database::has_changed_since`]).
+    /// Later writes must then start a new revision.
+    #[inline]
+    pub(crate) fn report_revision_observed(&self) {
+        if !self.shared_state.revision_observed.load() {
+            self.shared_state.revision_observed.store(true);
+        }
+    }
+
+    /// True if any value was read in the current revision, see [`Runtime::report_revision_observed`].
+    pub(crate) fn revision_observed(&self) -> bool {
+        self.shared_state.revision_observed.load()
+    }
+
     #[inline]
     pub(crate) fn push_query(&self, database_key_index: DatabaseKeyIndex) -> ActiveQueryGuard<'_> {
+        self.shared_state
+            .limits
+            .check_query_depth(self.local_state.query_depth(), database_key_index);
         self.local_state.push_query(database_key_index)
     }
 
+    /// Starts executing a function of `phase`, until the guard is dropped. The time
+    /// spent is recorded in the statistics of the phase, unless the execution of
+    /// another function of the phase is in progress already, and so accounts for it.
+    pub(crate) fn enter_phase(&self, phase: &'static str) -> PhaseGuard<'_> {
+        let mut active_phases = self.active_phases.borrow_mut();
+        let started_at = (!active_phases.contains(&phase)).then(Instant::now);
+        active_phases.push(phase);
+        PhaseGuard {
+            runtime: self,
+            phase,
+            started_at,
+        }
+    }
+
     /// Block until `other_id` completes executing `database_key`;
     /// panic or unwind in the case of a cycle.
     ///
@@ -293,8 +605,18 @@ impl Runtime {
             },
         });
 
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "block_on",
+            query = ?database_key.debug(db),
+            revision = ?self.current_revision(),
+            other_runtime = ?other_id,
+        )
+        .entered();
+
         let stack = self.local_state.take_query_stack();
 
+        let blocked_at = Instant::now();
         let (stack, result) = DependencyGraph::block_on(
             dg,
             self.id(),
@@ -303,16 +625,24 @@ impl Runtime {
             stack,
             query_mutex_guard,
         );
+        self.statistics()
+            .record_blocking(database_key, blocked_at.elapsed());
 
         self.local_state.restore_query_stack(stack);
 
         match result {
             WaitResult::Completed => (),
 
-            // If the other thread panicked, then we consider this thread
-            // cancelled. The assumption is that the panic will be detected
-            // by the other thread and responded to appropriately.
-            WaitResult::Panicked => Cancelled::PropagatedPanic.throw(),
+            // If the query panicked, report that to this thread too.
+            WaitResult::Panicked(Some(panicked)) => panicked.throw(),
+
+            // If the other thread panicked otherwise (e.g., it was cancelled), then we
+            // consider this thread cancelled. The assumption is that the panic will be
+            // detected by the other thread and responded to appropriately. An anchored
+            // runtime must not be cancelled by the write that cancelled the other
+            // thread, though, so it claims the query again, and executes it itself.
+            WaitResult::Panicked(None) if self.cancellation.is_anchored() => (),
+            WaitResult::Panicked(None) => Cancelled::PropagatedPanic.throw(),
 
             WaitResult::Cycle(c) => c.throw(),
         }
@@ -349,15 +679,17 @@ impl Runtime {
         // Identify the cycle participants:
         let cycle = {
             let mut v = vec![];
+            let mut runtime_ids = vec![];
             dg.for_each_cycle_participant(
                 from_id,
                 &mut from_stack,
                 database_key_index,
                 to_id,
-                |aqs| {
+                |runtime_id, aqs| {
                     aqs.iter_mut().for_each(|aq| {
                         cycle_query.add_from(aq);
                         v.push(aq.database_key_index);
+                        runtime_ids.push(runtime_id);
                     });
                 },
             );
@@ -365,15 +697,19 @@ impl Runtime {
             // We want to give the participants in a deterministic order
             // (at least for this execution, not necessarily across executions),
             // no matter where it started on the stack. Find the minimum
-            // key and rotate it to the front.
+            // key and rotate it to the front. The re-entered query, which came
+            // first, moves from index 0 to `entered_first`.
             let min = v.iter().min().unwrap();
             let index = v.iter().position(|p| p == min).unwrap();
             v.rotate_left(index);
+            runtime_ids.rotate_left(index);
+            let entered_first = (v.len() - index) % v.len();
 
             // No need to store extra memory.
             v.shrink_to_fit();
+            runtime_ids.shrink_to_fit();
 
-            Cycle::new(Arc::new(v))
+            Cycle::new(Arc::new(v), Arc::new(runtime_ids), entered_first)
         };
         log::debug!(
             "cycle {:?}, cycle_query {:#?}",
@@ -390,21 +726,29 @@ impl Runtime {
         // Mark each cycle participant that has recovery set, along with
         // any frames that come after them on the same thread. Those frames
         // are going to be unwound so that fallback can occur.
-        dg.for_each_cycle_participant(from_id, &mut from_stack, database_key_index, to_id, |aqs| {
-            aqs.iter_mut()
-                .skip_while(|aq| {
-                    match db.cycle_recovery_strategy(aq.database_key_index.ingredient_index) {
-                        CycleRecoveryStrategy::Panic => true,
-                        CycleRecoveryStrategy::Fallback => false,
-                    }
-                })
-                .for_each(|aq| {
-                    log::debug!("marking {:?} for fallback", aq.database_key_index.debug(db));
-                    aq.take_inputs_from(&cycle_query);
-                    assert!(aq.cycle.is_none());
-                    aq.cycle = Some(cycle.clone());
-                });
-        });
+        dg.for_each_cycle_participant(
+            from_id,
+            &mut from_stack,
+            database_key_index,
+            to_id,
+            |_, aqs| {
+                aqs.iter_mut()
+                    .skip_while(|aq| {
+                        match db.cycle_recovery_strategy(aq.database_key_index.ingredient_index) {
+                            CycleRecoveryStrategy::Panic | CycleRecoveryStrategy::FixedPoint => {
+                                true
+                            }
+                            CycleRecoveryStrategy::Fallback => false,
+                        }
+                    })
+                    .for_each(|aq| {
+                        log::debug!("marking {:?} for fallback", aq.database_key_index.debug(db));
+                        aq.take_inputs_from(&cycle_query);
+                        assert!(aq.cycle.is_none());
+                        aq.cycle = Some(cycle.clone());
+                    });
+            },
+        );
 
         // Unblock every thread that has cycle recovery with a `WaitResult::Cycle`.
         // They will throw the cycle, which will be caught by the frame that has
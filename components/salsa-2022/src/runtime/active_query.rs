@@ -1,4 +1,5 @@
 use crate::{
+    cycle::CycleHead,
     durability::Durability,
     hash::{FxIndexMap, FxIndexSet},
     key::{DatabaseKeyIndex, DependencyIndex},
@@ -31,9 +32,20 @@ pub(super) struct ActiveQuery {
     /// True if there was an untracked read.
     pub(super) untracked_read: bool,
 
+    /// True if the query reported its value as unchanged, see
+    /// [`Runtime::report_unchanged`].
+    pub(super) reported_unchanged: bool,
+
     /// Stores the entire cycle, if one is found and this query is part of it.
     pub(super) cycle: Option<Cycle>,
 
+    /// For queries that iterate to a fixed point, the number of the iteration
+    /// being executed; 0 for all other queries.
+    pub(super) iteration: u32,
+
+    /// Fixed-point iterations whose provisional values were read thus far.
+    pub(super) cycle_heads: FxIndexSet<CycleHead>,
+
     /// When new entities are created, their data is hashed, and the resulting
     /// hash is added to this map. If it is not present, then the disambiguator is 0.
     /// Otherwise it is 1 more than the current value (which is incremented).
@@ -48,7 +60,10 @@ impl ActiveQuery {
             changed_at: Revision::start(),
             input_outputs: FxIndexSet::default(),
             untracked_read: false,
+            reported_unchanged: false,
             cycle: None,
+            iteration: 0,
+            cycle_heads: Default::default(),
             disambiguator_map: Default::default(),
         }
     }
@@ -105,6 +120,7 @@ impl ActiveQuery {
             changed_at: self.changed_at,
             origin,
             durability: self.durability,
+            cycle_heads: self.cycle_heads.iter().copied().collect(),
         }
     }
 
@@ -116,6 +132,7 @@ impl ActiveQuery {
         self.untracked_read |= other.untracked_read;
         self.input_outputs
             .extend(other.input_outputs.iter().copied());
+        self.cycle_heads.extend(other.cycle_heads.iter().copied());
     }
 
     /// Removes the participants in `cycle` from my dependencies.
@@ -133,6 +150,7 @@ impl ActiveQuery {
         self.changed_at = cycle_query.changed_at;
         self.durability = cycle_query.durability;
         self.input_outputs = cycle_query.input_outputs.clone();
+        self.cycle_heads = cycle_query.cycle_heads.clone();
     }
 
     pub(super) fn disambiguate(&mut self, hash: u64) -> Disambiguator {
@@ -39,6 +39,13 @@ struct Edge {
 }
 
 impl DependencyGraph {
+    /// Shrinks the maps to the runtimes blocked now.
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.edges.shrink_to_fit();
+        self.query_dependents.shrink_to_fit();
+        self.wait_results.shrink_to_fit();
+    }
+
     /// True if `from_id` depends on `to_id`.
     ///
     /// (i.e., there is a path from `from_id` to `to_id` in the graph.)
@@ -66,7 +73,7 @@ impl DependencyGraph {
         from_stack: &mut QueryStack,
         database_key: DatabaseKeyIndex,
         to_id: RuntimeId,
-        mut closure: impl FnMut(&mut [ActiveQuery]),
+        mut closure: impl FnMut(RuntimeId, &mut [ActiveQuery]),
     ) {
         debug_assert!(self.depends_on(to_id, from_id));
 
@@ -108,7 +115,7 @@ impl DependencyGraph {
                 .iter_mut()
                 .take_while(|p| p.database_key_index != key)
                 .count();
-            closure(&mut edge.stack[prefix..]);
+            closure(id, &mut edge.stack[prefix..]);
             id = edge.blocked_on_id;
             key = edge.blocked_on_key;
         }
@@ -118,7 +125,7 @@ impl DependencyGraph {
             .iter_mut()
             .take_while(|p| p.database_key_index != key)
             .count();
-        closure(&mut from_stack[prefix..]);
+        closure(from_id, &mut from_stack[prefix..]);
     }
 
     /// Unblock each blocked runtime (excluding the current one) if some
@@ -1,5 +1,8 @@
 use log::debug;
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
 
+use crate::cycle::CycleHead;
 use crate::durability::Durability;
 use crate::key::DatabaseKeyIndex;
 use crate::key::DependencyIndex;
@@ -7,8 +10,11 @@ use crate::runtime::Revision;
 use crate::tracked_struct::Disambiguator;
 use crate::Cycle;
 use crate::Runtime;
+use parking_lot::Mutex;
 use std::cell::RefCell;
+use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::active_query::ActiveQuery;
 use super::StampedValue;
@@ -32,6 +38,7 @@ pub(super) struct LocalState {
 
 /// Summarizes "all the inputs that a query used"
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub(crate) struct QueryRevisions {
     /// The most revision in which some input changed.
     pub(crate) changed_at: Revision,
@@ -41,6 +48,12 @@ pub(crate) struct QueryRevisions {
 
     /// How was this query computed?
     pub(crate) origin: QueryOrigin,
+
+    /// The fixed-point iterations whose provisional values this query read.
+    /// If this is non-empty, the memo is provisional: it may only be reused
+    /// while those iterations are in progress.
+    #[cfg_attr(feature = "persist", serde(skip))]
+    pub(crate) cycle_heads: Vec<CycleHead>,
 }
 
 impl QueryRevisions {
@@ -55,6 +68,7 @@ impl QueryRevisions {
 
 /// Tracks the way that a memoized value for a query was created.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub enum QueryOrigin {
     /// The value was assigned as the output of another query (e.g., using `specify`).
     /// The `DatabaseKeyIndex` is the identity of the assigning query.
@@ -87,6 +101,7 @@ impl QueryOrigin {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub enum EdgeKind {
     Input,
     Output,
@@ -98,6 +113,7 @@ pub enum EdgeKind {
 /// and output edges
 /// (e.g., when Q0 specified the value for another query Q2).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub struct QueryEdges {
     /// The list of outgoing edges from this node.
     /// This list combines *both* inputs and outputs.
@@ -106,19 +122,25 @@ pub struct QueryEdges {
     /// Untracked reads mean that we can't verify values, so we don't use the list of inputs for that,
     /// but we still use it for finding the transitive inputs to an accumulator.
     ///
-    /// You can access the input/output list via the methods [`inputs`] and [`outputs`] respectively.
+    /// You can access the input/output list via the methods [`QueryEdges::inputs`] and
+    /// [`QueryEdges::outputs`] respectively.
     ///
     /// Important:
     ///
     /// * The inputs must be in **execution order** for the red-green algorithm to work.
     pub input_outputs: Arc<[(EdgeKind, DependencyIndex)]>,
+
+    /// How long verifying the inputs took, see [`QueryEdges::input_costs`].
+    /// Shared by the clones of these edges, and not persisted.
+    #[cfg_attr(feature = "persist", serde(skip))]
+    costs: Arc<EdgeCosts>,
 }
 
 impl QueryEdges {
     /// Returns the (tracked) inputs that were executed in computing this memoized value.
     ///
     /// These will always be in execution order.
-    pub(crate) fn inputs(&self) -> impl Iterator<Item = DependencyIndex> + '_ {
+    pub fn inputs(&self) -> impl Iterator<Item = DependencyIndex> + '_ {
         self.input_outputs
             .iter()
             .filter(|(edge_kind, _)| *edge_kind == EdgeKind::Input)
@@ -128,7 +150,7 @@ impl QueryEdges {
     /// Returns the (tracked) outputs that were executed in computing this memoized value.
     ///
     /// These will always be in execution order.
-    pub(crate) fn outputs(&self) -> impl Iterator<Item = DependencyIndex> + '_ {
+    pub fn outputs(&self) -> impl Iterator<Item = DependencyIndex> + '_ {
         self.input_outputs
             .iter()
             .filter(|(edge_kind, _)| *edge_kind == EdgeKind::Output)
@@ -137,7 +159,75 @@ impl QueryEdges {
 
     /// Creates a new `QueryEdges`; the values given for each field must meet struct invariants.
     pub(crate) fn new(input_outputs: Arc<[(EdgeKind, DependencyIndex)]>) -> Self {
-        Self { input_outputs }
+        Self {
+            input_outputs,
+            costs: Default::default(),
+        }
+    }
+
+    /// Returns how long verifying each input took (including executing it
+    /// again, if it had changed) during the last revalidation of the memoized
+    /// value, for the inputs verified then. Only recorded while edge profiling
+    /// is enabled, see [`crate::Storage::set_edge_profiling`].
+    pub fn input_costs(&self) -> Vec<(DependencyIndex, Duration)> {
+        match &*self.costs.durations.lock() {
+            Some(durations) => self
+                .input_outputs
+                .iter()
+                .zip(durations.iter())
+                .filter_map(|(&(_, input), &duration)| Some((input, duration?)))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Forgets the costs of the last revalidation, as a new one starts.
+    pub(crate) fn start_recording_costs(&self) {
+        *self.costs.durations.lock() = Some(vec![None; self.input_outputs.len()].into());
+    }
+
+    /// Records how long verifying the input at `position` took.
+    pub(crate) fn record_cost(&self, position: usize, duration: Duration) {
+        if let Some(durations) = &mut *self.costs.durations.lock() {
+            durations[position] = Some(duration);
+        }
+    }
+
+    /// Takes over the costs recorded for the edges of `old` that these edges
+    /// start with: a query executed again because an input changed reads the
+    /// inputs verified before that in the same order, so verifying them cost
+    /// what was recorded for the old memo.
+    pub(crate) fn inherit_costs(&self, old: &QueryEdges) {
+        let old_durations = match &*old.costs.durations.lock() {
+            Some(durations) => durations.clone(),
+            None => return,
+        };
+        let mut durations = vec![None; self.input_outputs.len()];
+        for (position, (duration, (new_edge, old_edge))) in durations
+            .iter_mut()
+            .zip(self.input_outputs.iter().zip(old.input_outputs.iter()))
+            .enumerate()
+        {
+            if new_edge != old_edge {
+                break;
+            }
+            *duration = old_durations[position];
+        }
+        *self.costs.durations.lock() = Some(durations.into());
+    }
+}
+
+/// The time spent verifying the inputs of a memoized value.
+#[derive(Default)]
+struct EdgeCosts {
+    /// Indexed like `input_outputs`, with `None` for the outputs and for the inputs
+    /// not verified; `None` as a whole if no revalidation was recorded.
+    durations: Mutex<Option<Box<[Option<Duration>]>>>,
+}
+
+impl fmt::Debug for EdgeCosts {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.durations.lock().fmt(fmt)
     }
 }
 
@@ -174,6 +264,21 @@ impl LocalState {
         self.with_query_stack(|stack| !stack.is_empty())
     }
 
+    /// The number of queries executing on this thread.
+    pub(super) fn query_depth(&self) -> usize {
+        self.with_query_stack(|stack| stack.len())
+    }
+
+    /// The queries executing on this thread, from the outermost to the innermost.
+    pub(super) fn active_query_stack(&self) -> Vec<DatabaseKeyIndex> {
+        self.with_query_stack(|stack| {
+            stack
+                .iter()
+                .map(|active_query| active_query.database_key_index)
+                .collect()
+        })
+    }
+
     /// Returns the index of the active query along with its *current* durability/changed-at
     /// information. As the query continues to execute, naturally, that information may change.
     pub(super) fn active_query(&self) -> Option<(DatabaseKeyIndex, StampedValue<()>)> {
@@ -251,6 +356,46 @@ impl LocalState {
         })
     }
 
+    /// Records that the top query read a value that is provisional on the
+    /// given fixed-point iterations.
+    pub(super) fn report_cycle_heads(&self, cycle_heads: &[CycleHead]) {
+        if cycle_heads.is_empty() {
+            return;
+        }
+        self.with_query_stack(|stack| {
+            if let Some(top_query) = stack.last_mut() {
+                top_query.cycle_heads.extend(cycle_heads.iter().copied());
+            }
+        })
+    }
+
+    /// If `database_key_index` is on the query stack, returns the
+    /// fixed-point iteration it is executing.
+    pub(super) fn iteration_in_progress(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+    ) -> Option<u32> {
+        self.with_query_stack(|stack| {
+            stack
+                .iter()
+                .find(|active_query| active_query.database_key_index == database_key_index)
+                .map(|active_query| active_query.iteration)
+        })
+    }
+
+    /// True if every one of the given fixed-point iterations is still
+    /// in progress on this thread.
+    pub(super) fn cycle_heads_in_progress(&self, cycle_heads: &[CycleHead]) -> bool {
+        self.with_query_stack(|stack| {
+            cycle_heads.iter().all(|head| {
+                stack.iter().any(|active_query| {
+                    active_query.database_key_index == head.database_key_index
+                        && active_query.iteration == head.iteration
+                })
+            })
+        })
+    }
+
     pub(super) fn report_untracked_read(&self, current_revision: Revision) {
         self.with_query_stack(|stack| {
             if let Some(top_query) = stack.last_mut() {
@@ -259,10 +404,16 @@ impl LocalState {
         })
     }
 
+    pub(super) fn report_unchanged(&self) {
+        self.with_query_stack(|stack| {
+            if let Some(top_query) = stack.last_mut() {
+                top_query.reported_unchanged = true;
+            }
+        })
+    }
+
     /// Update the top query on the stack to act as though it read a value
     /// of durability `durability` which changed in `revision`.
-    // FIXME: Use or remove this.
-    #[allow(dead_code)]
     pub(super) fn report_synthetic_read(&self, durability: Durability, revision: Revision) {
         self.with_query_stack(|stack| {
             if let Some(top_query) = stack.last_mut() {
@@ -346,6 +497,43 @@ impl ActiveQueryGuard<'_> {
         popped_query.revisions(runtime)
     }
 
+    /// Begins iteration `iteration` of a query that iterates to a fixed point,
+    /// discarding everything recorded by the previous iteration.
+    pub(crate) fn start_iteration(&self, iteration: u32) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            let mut active_query = ActiveQuery::new(self.database_key_index);
+            active_query.iteration = iteration;
+            *stack.last_mut().unwrap() = active_query;
+        })
+    }
+
+    /// Whether the active query reported its value as unchanged (see
+    /// [`Runtime::report_unchanged`]).
+    pub(crate) fn reported_unchanged(&self) -> bool {
+        self.local_state
+            .with_query_stack(|stack| stack.last().unwrap().reported_unchanged)
+    }
+
+    /// Returns the revisions recorded by the active query so far, without popping it.
+    pub(crate) fn revisions(&self, runtime: &Runtime) -> QueryRevisions {
+        self.local_state
+            .with_query_stack(|stack| stack.last().unwrap().revisions(runtime))
+    }
+
+    /// Removes the active query itself from the set of cycle heads it read,
+    /// returning true if it was present (i.e., the query read its own
+    /// provisional value during this iteration).
+    pub(crate) fn take_cycle_head(&self) -> bool {
+        let database_key_index = self.database_key_index;
+        self.local_state.with_query_stack(|stack| {
+            let cycle_heads = &mut stack.last_mut().unwrap().cycle_heads;
+            let len = cycle_heads.len();
+            cycle_heads.retain(|head| head.database_key_index != database_key_index);
+            cycle_heads.len() != len
+        })
+    }
+
     /// If the active query is registered as a cycle participant, remove and
     /// return that cycle.
     pub(crate) fn take_cycle(&self) -> Option<Cycle> {
@@ -3,7 +3,13 @@ use std::sync::{atomic::AtomicUsize, Arc};
 use crossbeam::atomic::AtomicCell;
 use parking_lot::Mutex;
 
-use crate::{durability::Durability, key::DependencyIndex, revision::AtomicRevision};
+#[cfg(feature = "persist")]
+use crate::record::Recorder;
+use crate::{
+    cache::CacheBackendSlot, durability::Durability, key::DependencyIndex, limits::LimitsSlot,
+    revision::AtomicRevision, statistics::StatisticsCounters, subscription::Subscribers,
+    symbol::SymbolTable,
+};
 
 use super::{dependency_graph::DependencyGraph, local_state::EdgeKind};
 
@@ -21,6 +27,11 @@ pub(super) struct SharedState {
     /// is set back to false once the input has been changed.
     pub(super) revision_canceled: AtomicCell<bool>,
 
+    /// Set to true when a tracked function is first fetched in the current revision.
+    /// Until then, a write in a [`crate::Database::transaction`] can reuse the
+    /// revision of the transaction's previous write.
+    pub(super) revision_observed: AtomicCell<bool>,
+
     /// Stores the "last change" revision for values of each duration.
     /// This vector is always of length at least 1 (for Durability 0)
     /// but its total length depends on the number of durations. The
@@ -35,6 +46,31 @@ pub(super) struct SharedState {
     /// The dependency graph tracks which runtimes are blocked on one
     /// another, waiting for queries to terminate.
     pub(super) dependency_graph: Mutex<DependencyGraph>,
+
+    /// Counts the work done by each function, see [`crate::Database::query_statistics`].
+    pub(super) statistics: StatisticsCounters,
+
+    /// Records the mutations of inputs, see [`crate::Database::start_recording`].
+    #[cfg(feature = "persist")]
+    pub(super) recorder: Recorder,
+
+    /// The strings interned as [`crate::Symbol`]s.
+    pub(super) symbols: SymbolTable,
+
+    /// Set by [`crate::Storage::set_shadow_verification`].
+    pub(super) shadow_verification: AtomicCell<bool>,
+
+    /// Set by [`crate::Storage::set_edge_profiling`].
+    pub(super) edge_profiling: AtomicCell<bool>,
+
+    /// Set by [`crate::Storage::set_cache_backend`].
+    pub(super) cache_backend: CacheBackendSlot,
+
+    /// See [`crate::Database::subscribe`].
+    pub(super) subscribers: Subscribers,
+
+    /// Set by [`crate::Storage::set_limits`].
+    pub(super) limits: LimitsSlot,
 }
 
 impl Default for SharedState {
@@ -44,13 +80,23 @@ impl Default for SharedState {
 }
 
 impl SharedState {
-    fn with_durabilities(durabilities: usize) -> Self {
+    pub(super) fn with_durabilities(durabilities: usize) -> Self {
         SharedState {
             next_id: AtomicUsize::new(1),
             empty_dependencies: None.into_iter().collect(),
             revision_canceled: Default::default(),
+            revision_observed: Default::default(),
             revisions: (0..durabilities).map(|_| AtomicRevision::start()).collect(),
             dependency_graph: Default::default(),
+            statistics: Default::default(),
+            #[cfg(feature = "persist")]
+            recorder: Default::default(),
+            symbols: Default::default(),
+            shadow_verification: Default::default(),
+            edge_profiling: Default::default(),
+            cache_backend: Default::default(),
+            subscribers: Default::default(),
+            limits: Default::default(),
         }
     }
 }
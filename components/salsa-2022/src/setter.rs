@@ -27,13 +27,38 @@ where
         }
     }
 
+    /// Gives the field `durability` from now on (the default is `Durability::LOW`),
+    /// independent of the durability of the other fields of the input.
     pub fn with_durability(self, durability: Durability) -> Self {
         Setter { durability, ..self }
     }
 
+    /// Sets the field to `value`, returning the previous value.
+    ///
+    /// # Panics
+    ///
+    /// If the field has no value yet, which can only happen for a `#[lazy]`
+    /// field that has not been read.
     pub fn to(self, value: F) -> F {
         self.ingredient
             .store_mut(self.runtime, self.key, value, self.durability)
-            .unwrap()
+            .expect("setting a lazy input field that has not been loaded")
+    }
+
+    /// Modifies the field in place, e.g., to append to a large value without
+    /// first reading and cloning it. This counts as a change to the field even
+    /// if `update` leaves it unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Like [`Setter::to`], if the field has no value yet.
+    pub fn update(self, update: impl FnOnce(&mut F)) {
+        let updated = self
+            .ingredient
+            .update_mut(self.runtime, self.key, update, self.durability);
+        assert!(
+            updated,
+            "updating a lazy input field that has not been loaded"
+        );
     }
 }
@@ -0,0 +1,275 @@
+//! Counts of the work done by each memoized function, and of the time threads
+//! waited on each other, for profiling and testing.
+//! See [`crate::Database::query_statistics`] and [`crate::Database::most_expensive_edges`].
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use crate::{
+    hash::{FxDashMap, FxHashSet},
+    key::{DatabaseKeyIndex, DependencyIndex},
+    runtime::local_state::QueryOrigin,
+    Database, DebugWithDb, IngredientIndex,
+};
+
+/// How often the memoized values of a single function were computed or reused.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryCounts {
+    /// Number of times the function was executed.
+    pub executions: u64,
+
+    /// Number of times a memoized value from an earlier revision was found
+    /// to still be valid, and hence reused without executing the function.
+    pub validations: u64,
+
+    /// Number of executions whose result was equal to the old value,
+    /// so that the value was backdated and dependent queries could be reused.
+    pub backdates: u64,
+}
+
+/// How often threads blocked because another thread was computing (or verifying)
+/// a value they needed, and for how long.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockingCounts {
+    /// Number of times a thread blocked.
+    pub blocks: u64,
+
+    /// The time spent blocked, summed over all threads.
+    pub wait_time: Duration,
+}
+
+impl BlockingCounts {
+    fn add(self, other: BlockingCounts) -> BlockingCounts {
+        BlockingCounts {
+            blocks: self.blocks + other.blocks,
+            wait_time: self.wait_time + other.wait_time,
+        }
+    }
+}
+
+/// The work done by the functions of a phase (given by the `phase` option of
+/// tracked functions), e.g., all those type-checking a program.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhaseCounts {
+    /// The counts summed over the functions of the phase.
+    pub counts: QueryCounts,
+
+    /// The time spent executing the functions of the phase, summed over all
+    /// threads. An execution called by another of the same phase is not
+    /// counted again, but one called by a function of another phase is
+    /// counted in both phases.
+    pub time: Duration,
+}
+
+/// A snapshot of the [`QueryCounts`] for each function that did any work,
+/// of the [`PhaseCounts`] for each phase, and of the [`BlockingCounts`] for
+/// each value that threads blocked on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryStatistics {
+    counts: BTreeMap<IngredientIndex, QueryCounts>,
+    phases: BTreeMap<&'static str, PhaseCounts>,
+    blocking: BTreeMap<DatabaseKeyIndex, BlockingCounts>,
+}
+
+impl QueryStatistics {
+    /// The counts for the function with the given ingredient index
+    /// (e.g., from `my_function::database_key(db, ...).ingredient_index()`).
+    pub fn get(&self, ingredient_index: IngredientIndex) -> QueryCounts {
+        self.counts
+            .get(&ingredient_index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The counts summed over all functions.
+    pub fn total(&self) -> QueryCounts {
+        self.counts
+            .values()
+            .fold(QueryCounts::default(), |sum, counts| QueryCounts {
+                executions: sum.executions + counts.executions,
+                validations: sum.validations + counts.validations,
+                backdates: sum.backdates + counts.backdates,
+            })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IngredientIndex, QueryCounts)> + '_ {
+        self.counts.iter().map(|(&index, &counts)| (index, counts))
+    }
+
+    /// The work done by the functions of the phase named `phase`.
+    pub fn phase(&self, phase: &str) -> PhaseCounts {
+        self.phases.get(phase).copied().unwrap_or_default()
+    }
+
+    /// The phases whose functions did any work, by name.
+    pub fn phases(&self) -> impl Iterator<Item = (&'static str, PhaseCounts)> + '_ {
+        self.phases.iter().map(|(&phase, &counts)| (phase, counts))
+    }
+
+    /// How often threads blocked on the value at `key`.
+    pub fn blocking(&self, key: DatabaseKeyIndex) -> BlockingCounts {
+        self.blocking.get(&key).copied().unwrap_or_default()
+    }
+
+    /// How often threads blocked on any value.
+    pub fn total_blocking(&self) -> BlockingCounts {
+        self.blocking
+            .values()
+            .fold(BlockingCounts::default(), |sum, &counts| sum.add(counts))
+    }
+
+    /// The (at most) `n` values that threads spent the most time blocked on,
+    /// from the one blocked on the longest: if one query serializes threads that
+    /// could otherwise run in parallel, it comes first.
+    pub fn most_blocked(&self, n: usize) -> Vec<(DatabaseKeyIndex, BlockingCounts)> {
+        let mut blocking: Vec<_> = self
+            .blocking
+            .iter()
+            .map(|(&key, &counts)| (key, counts))
+            .collect();
+        blocking.sort_by_key(|&(key, counts)| (std::cmp::Reverse(counts.wait_time), key));
+        blocking.truncate(n);
+        blocking
+    }
+}
+
+impl<Db> DebugWithDb<Db> for QueryStatistics
+where
+    Db: ?Sized + Database,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, db: &Db, _include_all_fields: bool) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (index, counts) in self.iter() {
+            map.entry(&DependencyIndex::for_table(index).debug(db), &counts);
+        }
+        map.finish()
+    }
+}
+
+/// How long verifying the input of a query took during the last revalidation
+/// of the query, see [`crate::Database::most_expensive_edges`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EdgeCost {
+    /// The query that read the input.
+    pub query: DatabaseKeyIndex,
+
+    pub input: DependencyIndex,
+
+    /// The time spent verifying the input, including verifying (or executing)
+    /// the inputs of the input in turn.
+    pub duration: Duration,
+
+    /// `duration`, less the time recorded for the edges of the input: the time
+    /// spent on the input itself, e.g., executing it again.
+    pub self_duration: Duration,
+}
+
+/// See [`crate::Database::most_expensive_edges`].
+pub(crate) fn most_expensive_edges<Db: ?Sized + Database>(
+    db: &Db,
+    root: DatabaseKeyIndex,
+    n: usize,
+) -> Vec<EdgeCost> {
+    let input_costs = |key: DatabaseKeyIndex| match db.origin(key) {
+        Some(QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges)) => {
+            edges.input_costs()
+        }
+        _ => vec![],
+    };
+
+    let mut costs = vec![];
+    let mut visited = FxHashSet::default();
+    visited.insert(root);
+    let mut stack = vec![(root, input_costs(root))];
+    while let Some((query, inputs)) = stack.pop() {
+        for (input, duration) in inputs {
+            let mut own_duration = Duration::ZERO;
+            if let Some(key_index) = input.key_index {
+                let key = DatabaseKeyIndex::new(input.ingredient_index, key_index);
+                let own_inputs = input_costs(key);
+                own_duration = own_inputs.iter().map(|&(_, duration)| duration).sum();
+                if visited.insert(key) {
+                    stack.push((key, own_inputs));
+                }
+            }
+            costs.push(EdgeCost {
+                query,
+                input,
+                duration,
+                self_duration: duration.saturating_sub(own_duration),
+            });
+        }
+    }
+    costs.sort_by_key(|cost| {
+        (
+            std::cmp::Reverse(cost.self_duration),
+            cost.query,
+            cost.input,
+        )
+    });
+    costs.truncate(n);
+    costs
+}
+
+/// The counters, shared by a runtime and all of its snapshots.
+#[derive(Debug, Default)]
+pub(crate) struct StatisticsCounters {
+    counts: FxDashMap<IngredientIndex, QueryCounts>,
+    phases: FxDashMap<&'static str, PhaseCounts>,
+    blocking: FxDashMap<DatabaseKeyIndex, BlockingCounts>,
+}
+
+impl StatisticsCounters {
+    /// Applies `op` to the counts of the function at `ingredient_index`,
+    /// and to those of its phase, if it has one.
+    pub(crate) fn record(
+        &self,
+        ingredient_index: IngredientIndex,
+        phase: Option<&'static str>,
+        op: impl Fn(&mut QueryCounts),
+    ) {
+        op(&mut self.counts.entry(ingredient_index).or_default());
+        if let Some(phase) = phase {
+            op(&mut self.phases.entry(phase).or_default().counts);
+        }
+    }
+
+    /// Records that executing functions of `phase` took `time`.
+    pub(crate) fn record_phase_time(&self, phase: &'static str, time: Duration) {
+        self.phases.entry(phase).or_default().time += time;
+    }
+
+    /// Records that a thread blocked on `key` for `wait_time`.
+    pub(crate) fn record_blocking(&self, key: DatabaseKeyIndex, wait_time: Duration) {
+        let mut counts = self.blocking.entry(key).or_default();
+        *counts = counts.add(BlockingCounts {
+            blocks: 1,
+            wait_time,
+        });
+    }
+
+    pub(crate) fn snapshot(&self) -> QueryStatistics {
+        QueryStatistics {
+            counts: self
+                .counts
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            phases: self
+                .phases
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            blocking: self
+                .blocking
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.counts.clear();
+        self.phases.clear();
+        self.blocking.clear();
+    }
+}
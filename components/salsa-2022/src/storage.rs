@@ -1,13 +1,36 @@
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    mem::ManuallyDrop,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
-use parking_lot::Condvar;
+#[cfg(feature = "persist")]
+use std::collections::VecDeque;
 
+use crossbeam::channel::{self, Sender};
+use parking_lot::{Condvar, Mutex};
+
+use crate::cache::CacheBackend;
+#[cfg(feature = "persist")]
+use crate::cache::Fingerprint;
 use crate::cycle::CycleRecoveryStrategy;
-use crate::ingredient::Ingredient;
-use crate::jar::Jar;
+#[cfg(feature = "persist")]
+use crate::hash::FxHashMap;
+use crate::hash::FxHashSet;
+use crate::ingredient::{Ingredient, ValueRevisions};
+use crate::jar::{DynamicJars, Jar};
 use crate::key::DependencyIndex;
+use crate::limits::Limits;
+#[cfg(feature = "persist")]
+use crate::persist::{self, PersistError, PersistedDatabase};
+#[cfg(feature = "persist")]
+use crate::record::{RecordedRevision, Recording};
 use crate::runtime::local_state::QueryOrigin;
 use crate::runtime::Runtime;
+use crate::usage::{EvictionCandidate, StorageUsage};
 use crate::{Database, DatabaseKeyIndex, Id, IngredientIndex};
 
 use super::routes::Routes;
@@ -22,7 +45,14 @@ pub struct Storage<DB: HasJars> {
     ///
     /// Even though this struct is stored in an `Arc`, we sometimes get mutable access to it
     /// by using `Arc::get_mut`. This is only possible when all parallel snapshots have been dropped.
-    shared: Arc<Shared<DB>>,
+    ///
+    /// This is only a `ManuallyDrop` so that our `Drop` impl can release it
+    /// before notifying `coordinate`; it is never used after that.
+    shared: ManuallyDrop<Arc<Shared<DB>>>,
+
+    /// Used by storage handles to tell a writer blocked in `cancel_other_workers`
+    /// that they have released `shared`.
+    coordinate: Arc<Coordinate>,
 
     /// The "ingredients" structure stores the information about how to find each ingredient in the database.
     /// It allows us to take the [`IngredientIndex`] assigned to a particular ingredient
@@ -34,6 +64,46 @@ pub struct Storage<DB: HasJars> {
     /// The runtime for this particular salsa database handle.
     /// Each handle gets its own runtime, but the runtimes have shared state between them.
     runtime: Runtime,
+
+    /// Number of [`crate::Database::transaction`] calls in progress on this handle.
+    transaction_depth: usize,
+
+    /// The revision started by a write in the current transaction, if any.
+    /// Further writes in the transaction reuse it as long as no tracked
+    /// function has been fetched in it.
+    transaction_revision: Option<Revision>,
+
+    /// The revision started by the last write, until the database reports it
+    /// with [`EventKind::DidStartRevision`](`crate::EventKind::DidStartRevision`).
+    started_revision: Option<Revision>,
+
+    /// Set by [`Storage::set_memory_budget`].
+    memory_budget: Option<usize>,
+
+    /// Set by [`Storage::set_write_policy`].
+    write_policy: WritePolicy,
+
+    /// Set by [`Storage::set_parallel_verification`].
+    parallel_verification: Option<Arc<ParallelVerification<DB>>>,
+}
+
+/// A job verifying some inputs of a memo on a worker; see [`Storage::run_verification`].
+pub type VerificationJob = Box<dyn FnOnce() + Send>;
+
+/// How memos with many inputs are verified; see [`Storage::set_parallel_verification`].
+struct ParallelVerification<DB> {
+    /// The number of threads verifying the inputs of a memo.
+    threads: usize,
+
+    /// Sends jobs to the `threads` workers, which stop once it is dropped.
+    jobs: Sender<VerificationJob>,
+
+    /// The number of inputs from which a memo is verified in parallel.
+    min_inputs: usize,
+
+    /// Creates a handle for a worker thread.
+    #[allow(clippy::type_complexity)]
+    fork: Box<dyn Fn(&DB) -> Box<dyn Database + Send> + Send + Sync>,
 }
 
 /// Data shared between all threads.
@@ -44,9 +114,39 @@ struct Shared<DB: HasJars> {
     /// Each jar stores its own structs in there that ultimately contain ingredients
     /// (types that implement the [`Ingredient`] trait, like [`crate::function::FunctionIngredient`]).
     jars: DB::Jars,
+}
+
+/// How a write waits for the queries running on other handles of the database,
+/// see [`Storage::set_write_policy`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// The write cancels the queries right away, so it waits only as long as
+    /// they take to notice (see [`crate::Cancelled`]). This favors the latency of
+    /// edits, at the expense of the work the cancelled queries did.
+    #[default]
+    WriterPriority,
 
-    /// Conditional variable that is used to coordinate cancellation.
-    /// When the main thread writes to the database, it blocks until each of the snapshots can be cancelled.
+    /// The write waits for the queries to complete, without cancelling them.
+    /// No work is lost, but an edit may wait as long as the slowest query.
+    Fair,
+
+    /// The write lets the queries complete for up to `max_latency`, then
+    /// cancels the ones still running.
+    ReadBatching {
+        /// How long the write waits before cancelling the queries.
+        max_latency: Duration,
+    },
+}
+
+/// Coordinates cancellation between the handles of a storage.
+/// When the main thread writes to the database, it blocks until each of the snapshots can be cancelled.
+#[derive(Default)]
+struct Coordinate {
+    /// Held while checking whether the snapshots are gone, and while announcing
+    /// that one is, so that the announcement cannot be missed.
+    mutex: Mutex<()>,
+
+    /// Notified each time a storage handle is dropped.
     cvar: Condvar,
 }
 
@@ -56,16 +156,7 @@ where
     DB: HasJars,
 {
     fn default() -> Self {
-        let mut routes = Routes::new();
-        let jars = DB::create_jars(&mut routes);
-        Self {
-            shared: Arc::new(Shared {
-                jars,
-                cvar: Default::default(),
-            }),
-            routes: Arc::new(routes),
-            runtime: Runtime::default(),
-        }
+        Self::with_runtime(Runtime::default())
     }
 }
 // ANCHOR_END: default
@@ -74,14 +165,106 @@ impl<DB> Storage<DB>
 where
     DB: HasJars,
 {
+    /// Creates storage whose runtime distinguishes `levels` durabilities,
+    /// see [`Runtime::with_durability_levels`].
+    pub fn with_durability_levels(levels: usize) -> Self {
+        Self::with_runtime(Runtime::with_durability_levels(levels))
+    }
+
+    fn with_runtime(runtime: Runtime) -> Self {
+        let mut routes = Routes::new();
+        let jars = DB::create_jars(&mut routes);
+        Self {
+            shared: ManuallyDrop::new(Arc::new(Shared { jars })),
+            coordinate: Default::default(),
+            routes: Arc::new(routes),
+            runtime,
+            transaction_depth: 0,
+            transaction_revision: None,
+            started_revision: None,
+            memory_budget: None,
+            write_policy: WritePolicy::default(),
+            parallel_verification: None,
+        }
+    }
+
+    /// Interns `symbols` before any other string, so that the `i`th one is
+    /// [`Symbol::predefined(i)`](`crate::Symbol::predefined`), e.g., for keywords
+    /// to be constants. Panics if another string was interned already.
+    pub fn with_symbols(self, symbols: &[&'static str]) -> Self {
+        self.runtime.symbols().predefine(symbols);
+        self
+    }
+
+    /// Adds the ingredients of the jar `J` to this database, for jars that are
+    /// not known when the database is defined (e.g., those of plugins loaded at
+    /// runtime). `J` must be declared with `#[salsa::jar(db = Db, dynamic = DB)]`,
+    /// where `DB` is this database, rather than listed in `#[salsa::db]`.
+    ///
+    /// Its ingredients get the indices following those of the ingredients already
+    /// in the database, so jars should be added in the same order in each process
+    /// that is to load a persisted database or replay a recording. They should also
+    /// be added before the database is used: the functions of `J` panic until then.
+    ///
+    /// Panics if `J` was added already, or if a snapshot of this database exists.
+    pub fn add_jar<J>(&mut self)
+    where
+        DB: DbWithJar<J> + JarFromJars<J>,
+        J: for<'db> Jar<'db> + Send + Sync + 'static,
+    {
+        let shared = Arc::get_mut(&mut self.shared)
+            .unwrap_or_else(|| panic!("cannot add a jar while a snapshot exists"));
+        let dynamic_jars = DB::dynamic_jars_mut(&mut shared.jars);
+        assert!(
+            !dynamic_jars.contains::<J>(),
+            "the jar `{}` was added twice",
+            std::any::type_name::<J>()
+        );
+        let routes = Arc::get_mut(&mut self.routes)
+            .unwrap_or_else(|| panic!("cannot add a jar while a snapshot exists"));
+        routes.begin_jar(std::any::type_name::<J>());
+        dynamic_jars.insert(J::create_jar(routes));
+    }
+
     pub fn snapshot(&self) -> Storage<DB>
     where
         DB: ParallelDatabase,
     {
         Self {
             shared: self.shared.clone(),
+            coordinate: self.coordinate.clone(),
             routes: self.routes.clone(),
             runtime: self.runtime.snapshot(),
+            transaction_depth: 0,
+            transaction_revision: None,
+            started_revision: None,
+            memory_budget: self.memory_budget,
+            write_policy: self.write_policy,
+            parallel_verification: self.parallel_verification.clone(),
+        }
+    }
+
+    /// Like [`Storage::snapshot`], but may be used while a query is in progress.
+    /// This is used to run queries on other threads on behalf of the active query
+    /// (see [`crate::par_map`]); the fork must be dropped before that query completes.
+    /// A fork verifies every memo on its own thread (see [`Storage::set_parallel_verification`]).
+    pub fn fork(&self) -> Storage<DB>
+    where
+        DB: ParallelDatabase,
+    {
+        Self {
+            shared: self.shared.clone(),
+            coordinate: self.coordinate.clone(),
+            routes: self.routes.clone(),
+            runtime: self.runtime.fork(),
+            transaction_depth: 0,
+            transaction_revision: None,
+            started_revision: None,
+            memory_budget: self.memory_budget,
+            write_policy: self.write_policy,
+            // The workers verifying in parallel run on forks: were they to queue
+            // jobs of their own, they could wait for jobs queued behind them.
+            parallel_verification: None,
         }
     }
 
@@ -99,56 +282,153 @@ where
 
     // ANCHOR: jars_mut
     /// Gets mutable access to the jars. This will trigger a new revision
-    /// and it will also cancel any ongoing work in the current revision.
+    /// (unless it can reuse the revision of an earlier write in the same
+    /// [`Storage::begin_transaction`]) and it will also cancel any ongoing work
+    /// in the current revision.
     /// Any actual writes that occur to data in a jar should use
     /// [`Runtime::report_tracked_write`].
     pub fn jars_mut(&mut self) -> (&mut DB::Jars, &mut Runtime) {
+        self.start_write();
+        self.started_jars_mut()
+    }
+    // ANCHOR_END: jars_mut
+
+    /// The first half of [`Storage::jars_mut`]: cancels any ongoing work and
+    /// triggers a new revision, without borrowing the jars, so that the database
+    /// can report the revision before handing them out.
+    pub fn start_write(&mut self) {
         // Wait for all snapshots to be dropped.
         self.cancel_other_workers();
 
-        // Increment revision counter.
-        self.runtime.new_revision();
+        // The values written by the caller must not overwrite those recorded
+        // before, which have not been read yet.
+        #[cfg(feature = "persist")]
+        self.flush_recording();
 
         // Acquire `&mut` access to `self.shared` -- this is only possible because
         // the snapshots have all been dropped, so we hold the only handle to the `Arc`.
         let shared = Arc::get_mut(&mut self.shared).unwrap();
 
-        // Inform other ingredients that a new revision has begun.
-        // This gives them a chance to free resources that were being held until the next revision.
-        let routes = self.routes.clone();
-        for route in routes.reset_routes() {
-            route(&mut shared.jars).reset_for_new_revision();
+        let current_revision = self.runtime.current_revision();
+        if self.transaction_revision == Some(current_revision) && !self.runtime.revision_observed()
+        {
+            // Nothing has been computed in the revision of the previous write
+            // of this transaction, so this write can be part of it too.
+            self.runtime.clear_cancellation_flag();
+        } else {
+            // Increment revision counter.
+            let new_revision = self.runtime.new_revision();
+            if self.transaction_depth > 0 {
+                self.transaction_revision = Some(new_revision);
+            }
+            self.started_revision = Some(new_revision);
+
+            // Inform other ingredients that a new revision has begun.
+            // This gives them a chance to free resources that were being held until the next revision.
+            let routes = self.routes.clone();
+            for route in routes.reset_routes() {
+                route(&mut shared.jars).reset_for_new_revision();
+            }
+
+            if let Some(budget) = self.memory_budget {
+                Self::evict_to_budget(&routes, &mut shared.jars, budget);
+            }
         }
+    }
 
-        // Return mut ref to jars + runtime.
+    /// The second half of [`Storage::jars_mut`], after [`Storage::start_write`].
+    #[doc(hidden)]
+    pub fn started_jars_mut(&mut self) -> (&mut DB::Jars, &mut Runtime) {
+        // A snapshot may have been taken while the new revision was reported.
+        self.cancel_other_workers();
+        self.runtime.clear_cancellation_flag();
+        let shared = Arc::get_mut(&mut self.shared).unwrap();
         (&mut shared.jars, &mut self.runtime)
     }
-    // ANCHOR_END: jars_mut
+
+    /// Takes the revision started by the last write, if it was not taken yet.
+    #[doc(hidden)]
+    pub fn take_started_revision(&mut self) -> Option<Revision> {
+        self.started_revision.take()
+    }
+
+    /// Starts a transaction: until the matching [`Storage::end_transaction`],
+    /// writes share a single revision as long as no tracked function is fetched
+    /// in between. See [`crate::Database::transaction`].
+    pub fn begin_transaction(&mut self) {
+        self.transaction_depth += 1;
+    }
+
+    /// Ends a transaction started by [`Storage::begin_transaction`].
+    pub fn end_transaction(&mut self) {
+        self.transaction_depth -= 1;
+        if self.transaction_depth == 0 {
+            self.transaction_revision = None;
+        }
+    }
+
+    /// Starts a speculation: the writes until the matching
+    /// [`Storage::end_speculation`] are undone by it. See [`crate::Database::speculate`].
+    pub fn begin_speculation(&mut self) {
+        self.runtime.begin_speculation();
+    }
+
+    /// Ends a speculation started by [`Storage::begin_speculation`], putting back the
+    /// values written in it; this starts a new revision, like [`Storage::jars_mut`].
+    pub fn end_speculation(&mut self) {
+        let depth = self.runtime.speculation_depth();
+        let written = self.runtime.end_speculation();
+        if written.is_empty() {
+            return;
+        }
+        let routes = self.routes.clone();
+        let (jars, runtime) = self.jars_mut();
+        for index in written {
+            routes.route_mut(index)(jars).undo_speculation(runtime, depth);
+        }
+    }
 
     // ANCHOR: cancel_other_workers
     /// Sets cancellation flag and blocks until all other workers with access
-    /// to this storage have completed.
+    /// to this storage have completed. When the flag is set depends on the
+    /// [`WritePolicy`].
     ///
     /// This could deadlock if there is a single worker with two handles to the
     /// same database!
     fn cancel_other_workers(&mut self) {
+        let mut cancel_at = match self.write_policy {
+            WritePolicy::WriterPriority => {
+                self.runtime.set_cancellation_flag();
+                None
+            }
+            WritePolicy::Fair => None,
+            WritePolicy::ReadBatching { max_latency } => Some(Instant::now() + max_latency),
+        };
+        let mut guard = self.coordinate.mutex.lock();
         loop {
-            self.runtime.set_cancellation_flag();
-
             // If we have unique access to the jars, we are done.
             if Arc::get_mut(&mut self.shared).is_some() {
                 return;
             }
 
             // Otherwise, wait until some other storage entities have dropped.
-            // We create a mutex here because the cvar api requires it, but we
-            // don't really need one as the data being protected is actually
-            // the jars above.
-            //
-            // The cvar `self.shared.cvar` is notified by the `Drop` impl.
-            let mutex = parking_lot::Mutex::new(());
-            let mut guard = mutex.lock();
-            self.shared.cvar.wait(&mut guard);
+            // The cvar is notified by the `Drop` impl, which has to acquire the
+            // mutex to do so; so it cannot slip in between our check and
+            // the wait.
+            match cancel_at {
+                Some(deadline) => {
+                    if self
+                        .coordinate
+                        .cvar
+                        .wait_until(&mut guard, deadline)
+                        .timed_out()
+                    {
+                        self.runtime.set_cancellation_flag();
+                        cancel_at = None;
+                    }
+                }
+                None => self.coordinate.cvar.wait(&mut guard),
+            }
         }
     }
     // ANCHOR_END: cancel_other_workers
@@ -157,14 +437,635 @@ where
         let route = self.routes.route(ingredient_index);
         route(&self.shared.jars)
     }
+
+    /// Saves the contents of each persistent ingredient (see [`crate::persist`]).
+    #[cfg(feature = "persist")]
+    pub fn persist(&self) -> Result<PersistedDatabase, PersistError> {
+        let mut persisted = PersistedDatabase::new(
+            self.runtime.last_changed_revisions(),
+            self.runtime.symbols().texts(),
+        );
+        let is_persistent = |index| self.routes.is_persistent(index);
+        for (index, route) in self.routes.persistent_routes() {
+            let ingredient = route(&self.shared.jars);
+            let data = ingredient.serialize(&is_persistent)?;
+            let jar = self.routes.jar_name(index);
+            persisted.push_ingredient(
+                index,
+                jar,
+                ingredient.debug_name(),
+                ingredient.query_fingerprint(),
+                data,
+            );
+        }
+        Ok(persisted)
+    }
+
+    /// Starts recording the creation of inputs and the writes to their fields,
+    /// until [`Storage::take_recording`] (see [`crate::record`]).
+    #[cfg(feature = "persist")]
+    pub fn start_recording(&self) {
+        self.runtime.recorder().start();
+    }
+
+    /// Stops recording, and returns the mutations recorded since
+    /// [`Storage::start_recording`].
+    #[cfg(feature = "persist")]
+    pub fn take_recording(&self) -> Recording {
+        self.flush_recording();
+        self.runtime.recorder().take()
+    }
+
+    /// Reads the values written by the mutations reported to the recorder since
+    /// the last flush, from the persistent ingredients.
+    #[cfg(feature = "persist")]
+    fn flush_recording(&self) {
+        let jars = &self.shared.jars;
+        self.runtime.recorder().flush(&|index, id| {
+            let ingredient = self.routes.persistent_route(index)?(jars);
+            let value = match ingredient.recorded_value(id) {
+                Ok(value) => value,
+                Err(err) => {
+                    log::warn!("not recording a mutation of {:?}: {}", index, err);
+                    None
+                }
+            };
+            Some((ingredient.debug_name(), value))
+        });
+    }
+
+    /// Enables or disables shadow verification, a debugging aid for finding bugs
+    /// that make incremental results wrong. While enabled, whenever the value of a
+    /// tracked function is validated because none of its inputs changed, the function
+    /// is executed again, and salsa panics, naming the query, if the result differs
+    /// from the validated value. Because the functions a query reads from are checked
+    /// when they are validated in turn, the values of all the queries demanded in a
+    /// revision are then what executing them from scratch would give.
+    ///
+    /// This catches untracked reads of state outside the database, and `Eq` impls that
+    /// consider different values equal (the value that was wrongly backdated is not
+    /// itself reported, but the first query that reads it and now returns something
+    /// else is). Functions with outputs (tracked structs, `specify`, accumulators),
+    /// with cycle recovery, or with `no_eq` are not executed again. The setting is
+    /// shared with the snapshots of this database.
+    pub fn set_shadow_verification(&self, enabled: bool) {
+        self.runtime.set_shadow_verification(enabled);
+    }
+
+    /// Enables or disables edge profiling. While enabled, revalidating a memoized
+    /// value records how long verifying each of its inputs took, which may include
+    /// executing the input again (see [`crate::plumbing::QueryEdges::input_costs`]),
+    /// so that [`crate::Database::most_expensive_edges`] can find the dependencies
+    /// that make a query slow after an edit. The setting is shared with the
+    /// snapshots of this database.
+    pub fn set_edge_profiling(&self, enabled: bool) {
+        self.runtime.set_edge_profiling(enabled);
+    }
+
+    /// Sets the limits on the work done for the inputs of the database, beyond
+    /// which the query at fault unwinds with a [`crate::LimitExceeded`] (see
+    /// [`crate::limits`]). The limits are shared with the snapshots of this database.
+    pub fn set_limits(&self, limits: Limits) {
+        self.runtime.limits().set(limits);
+    }
+
+    /// The limits set with [`Storage::set_limits`].
+    pub fn limits(&self) -> Limits {
+        self.runtime.limits().get()
+    }
+
+    /// Sets the backend through which the values of tracked functions with the `cache`
+    /// option are shared with other databases (see [`crate::cache`]), or unsets it.
+    /// The backend is shared with the snapshots of this database.
+    pub fn set_cache_backend(&self, backend: Option<Arc<dyn CacheBackend>>) {
+        self.runtime.set_cache_backend(backend);
+    }
+
+    /// Applies the mutations of one revision of a [`Recording`], in a new revision.
+    /// Replaying each revision of a recording in turn, into a fresh database with
+    /// the same jars as the one recorded, recreates the inputs of that database.
+    #[cfg(feature = "persist")]
+    pub fn replay(&mut self, revision: &RecordedRevision) -> Result<(), PersistError> {
+        let routes = self.routes.clone();
+        let (jars, runtime) = self.jars_mut();
+        for mutation in revision.mutations() {
+            let index = mutation.ingredient_index();
+            let route = match routes.persistent_route_mut(index) {
+                Some(route) => route,
+                None => {
+                    return Err(PersistError::IngredientMismatch {
+                        index,
+                        expected: mutation.debug_name().to_string(),
+                        found: None,
+                    })
+                }
+            };
+            let ingredient = route(jars);
+            if ingredient.debug_name() != mutation.debug_name() {
+                return Err(PersistError::IngredientMismatch {
+                    index,
+                    expected: mutation.debug_name().to_string(),
+                    found: Some(ingredient.debug_name()),
+                });
+            }
+            if !ingredient.replay(runtime, mutation.id(), mutation.mutation())? {
+                return Err(PersistError::ReplayMismatch {
+                    index,
+                    id: mutation.id(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads data saved by [`Storage::persist`] into the persistent ingredients,
+    /// replacing their current contents. Like [`Storage::jars_mut`], this
+    /// cancels other workers and starts a new revision.
+    ///
+    /// This is meant to be called on a freshly created database: memoized values
+    /// of non-persistent ingredients are kept, and they may have been computed
+    /// from inputs that the restore overwrites.
+    #[cfg(feature = "persist")]
+    pub fn restore(&mut self, mut persisted: PersistedDatabase) -> Result<(), PersistError> {
+        let revisions = persisted.revisions().to_vec();
+        let symbols = persisted.take_symbols();
+        self.load(revisions, symbols, persisted.into_ingredients())
+    }
+
+    /// Mounts `base`, the data of a frozen database saved by [`Storage::persist`],
+    /// under this freshly created database: its memoized values are used as if they
+    /// had been computed here, and only what changes afterwards is computed and
+    /// stored by this database. The base's inputs may still be set here; this
+    /// overrides them in this database only, since `base` itself is never modified
+    /// and can be mounted by any number of databases (e.g., the analysis of a
+    /// standard library, shipped with a tool).
+    ///
+    /// Unlike [`Storage::restore`], the base may have been written by a database
+    /// with other jars, or with the same jars in another order: the `k`-th
+    /// persistent ingredient with a given name in a jar of the base is mounted as the
+    /// `k`-th persistent ingredient with that name in the same jar here, and the
+    /// ingredient indices within the memoized dependencies are translated
+    /// accordingly. Each ingredient of the base must have such a counterpart; the
+    /// other ingredients are left empty.
+    #[cfg(feature = "persist")]
+    pub fn mount_base(&mut self, base: &PersistedDatabase) -> Result<(), PersistError> {
+        let mut by_name: FxHashMap<(&str, &str), VecDeque<IngredientIndex>> = FxHashMap::default();
+        for (index, route) in self.routes.persistent_routes() {
+            let key = (
+                self.routes.jar_name(index),
+                route(&self.shared.jars).debug_name(),
+            );
+            by_name.entry(key).or_default().push_back(index);
+        }
+
+        let mut translation = FxHashMap::default();
+        let mut ingredients = vec![];
+        for (base_index, jar, debug_name, fingerprint, data) in base.ingredients() {
+            let key = (jar, debug_name);
+            let index = match by_name.get_mut(&key).and_then(|i| i.pop_front()) {
+                Some(index) => index,
+                None => {
+                    return Err(PersistError::MissingIngredient {
+                        jar: jar.to_string(),
+                        debug_name: debug_name.to_string(),
+                    })
+                }
+            };
+            translation.insert(base_index, index);
+            ingredients.push((index, debug_name.to_string(), fingerprint, data.clone()));
+        }
+
+        let revisions = base.revisions().to_vec();
+        let symbols = base.symbols().to_vec();
+        persist::with_index_translation(translation, || {
+            self.load(revisions, symbols, ingredients.into_iter())
+        })
+    }
+
+    /// Loads persisted `ingredients` into the ingredients at their indices; shared by
+    /// [`Storage::restore`] and [`Storage::mount_base`].
+    #[cfg(feature = "persist")]
+    fn load(
+        &mut self,
+        revisions: Vec<Revision>,
+        symbols: Vec<String>,
+        ingredients: impl Iterator<
+            Item = (
+                IngredientIndex,
+                String,
+                Option<Fingerprint>,
+                serde_json::Value,
+            ),
+        >,
+    ) -> Result<(), PersistError> {
+        let expected = self.runtime.last_changed_revisions().len();
+        if revisions.len() != expected {
+            return Err(PersistError::DurabilityMismatch {
+                expected,
+                found: revisions.len(),
+            });
+        }
+
+        // Check every ingredient before loading any, so that a mismatch leaves
+        // the database as it was rather than half restored.
+        let ingredients: Vec<_> = ingredients.collect();
+        for (index, debug_name, _, _) in &ingredients {
+            let found = self
+                .routes
+                .persistent_route(*index)
+                .map(|route| route(&self.shared.jars).debug_name());
+            if found != Some(debug_name.as_str()) {
+                return Err(PersistError::IngredientMismatch {
+                    index: *index,
+                    expected: debug_name.clone(),
+                    found,
+                });
+            }
+        }
+
+        if let Err((index, symbol)) = self.runtime.symbols().restore(symbols) {
+            return Err(PersistError::SymbolMismatch { index, symbol });
+        }
+
+        let routes = self.routes.clone();
+        let (jars, runtime) = self.jars_mut();
+        for (index, debug_name, fingerprint, data) in ingredients {
+            let ingredient = routes.persistent_route_mut(index).unwrap()(jars);
+            if ingredient.query_fingerprint() != fingerprint {
+                log::debug!("not loading the data of another version of `{debug_name}`");
+                continue;
+            }
+            ingredient.deserialize(data)?;
+        }
+
+        // Never move a revision backwards: anything verified in the current
+        // revision must not appear to be up-to-date with the restored data.
+        let revisions: Vec<_> = runtime
+            .last_changed_revisions()
+            .into_iter()
+            .zip(revisions)
+            .map(|(current, persisted)| current.max(persisted))
+            .collect();
+        runtime.restore_last_changed_revisions(&revisions);
+        Ok(())
+    }
+    /// Discards memoized values, tracked structs, interned values, and accumulated
+    /// values that are no longer in use, returning how many were discarded.
+    ///
+    /// A memoized value is in use if it was computed or verified in the current
+    /// revision or is pinned (see [`crate::function::FunctionIngredient::pin`]), or if
+    /// such a value depends on it (directly or transitively).
+    /// Tracked structs are kept if the query that created them is, and interned
+    /// values if some query that is kept interned or read them. Values interned
+    /// outside of any query are never discarded.
+    /// So the typical pattern is to fetch the queries you care about
+    /// (e.g., the diagnostics for each open file) and then call `sweep`.
+    /// Anything discarded is simply recomputed if it is needed again.
+    ///
+    /// Like [`Storage::jars_mut`], this cancels other workers, but it does not start
+    /// a new revision.
+    pub fn sweep(&mut self) -> usize {
+        self.cancel_other_workers();
+        self.runtime.clear_cancellation_flag();
+
+        let live = self.live_keys();
+        let is_live = |key: DatabaseKeyIndex| live.contains(&key);
+
+        let shared = Arc::get_mut(&mut self.shared).unwrap();
+        let routes = self.routes.clone();
+        routes
+            .indices()
+            .map(|index| routes.route_mut(index)(&mut shared.jars).sweep(&is_live))
+            .sum()
+    }
+
+    /// Shrinks the tables of every ingredient (memo maps, interned values, and so on),
+    /// which keep the capacity of the most values they ever stored, to the values
+    /// stored now; as well as the buffers of the dependency graph between threads.
+    /// This is worth it after [`Storage::sweep`], or after many inputs were deleted.
+    ///
+    /// Like [`Storage::sweep`], this cancels other workers but does not start a
+    /// new revision.
+    pub fn compact(&mut self) {
+        self.cancel_other_workers();
+        self.runtime.clear_cancellation_flag();
+
+        let shared = Arc::get_mut(&mut self.shared).unwrap();
+        let routes = self.routes.clone();
+        for index in routes.indices() {
+            routes.route_mut(index)(&mut shared.jars).compact();
+        }
+        self.runtime.compact();
+    }
+
+    /// Starts a new revision in which the value at `index` acts as though it had
+    /// changed, like [`crate::Database::synthetic_write`] but without making
+    /// salsa re-execute every query of some durability. If `index` has no key,
+    /// every value of its ingredient is affected.
+    ///
+    /// A memoized value is re-executed the next time it is fetched, and it is
+    /// backdated if it turns out to be the same as before. This is for queries
+    /// that read some cache outside of salsa, after an entry of that cache
+    /// is invalidated.
+    pub fn synthetic_write_for(&mut self, index: DependencyIndex) {
+        let routes = self.routes.clone();
+        let (jars, runtime) = self.jars_mut();
+        let ingredient = routes.route_mut(index.ingredient_index)(jars);
+        if let Some(durability) =
+            ingredient.synthetic_write(index.key_index, runtime.current_revision())
+        {
+            runtime.report_tracked_write(durability);
+        }
+    }
+
+    /// Drops the memoized value at `index` (or, if `index` has no key, every memoized
+    /// value of its ingredient) to save memory, returning how many values were dropped.
+    ///
+    /// Unlike [`Storage::sweep`], the dependencies of the memos are kept. So the
+    /// queries that read them can still be validated without recomputing them, and
+    /// a value is only recomputed once it is fetched again. Values of queries that
+    /// read untracked state, or that were specified, cannot be recomputed, so they
+    /// are kept.
+    ///
+    /// Like [`Storage::sweep`], this cancels other workers but does not start a
+    /// new revision.
+    pub fn evict_values(&mut self, index: DependencyIndex) -> usize {
+        self.cancel_other_workers();
+        self.runtime.clear_cancellation_flag();
+
+        let shared = Arc::get_mut(&mut self.shared).unwrap();
+        let route = self.routes.route_mut(index.ingredient_index);
+        route(&mut shared.jars).evict_values(index.key_index)
+    }
+
+    /// Sets how a write waits for the queries running on snapshots of this database
+    /// (see [`WritePolicy`]), trading the latency of edits against the work
+    /// cancelled queries lose. The default is [`WritePolicy::WriterPriority`].
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.write_policy = policy;
+    }
+
+    /// Makes salsa verify the inputs of a memo on a pool of `threads` worker threads
+    /// when it has at least `min_inputs` of them, each worker using a handle created
+    /// by `fork` (which should wrap [`Storage::fork`], so that the workers verify the
+    /// memos they reach on their own thread). This is for queries that read hundreds
+    /// of independent inputs, whose verification after an edit takes long. The pool
+    /// is shared with the snapshots of this database.
+    ///
+    /// The threads verify contiguous ranges of the inputs, each in order, while the
+    /// thread that verifies the memo waits; then it checks them in order as usual,
+    /// finding them verified. So whether the memo is re-executed is decided as though
+    /// they were verified one by one, but the threads may verify (and re-execute)
+    /// inputs that come after one that changed, which the query may not read anymore.
+    /// The panics in doing so are ignored.
+    ///
+    /// # Deadlock warning
+    ///
+    /// As with [`crate::par_map`], no input of a memo verified in parallel may
+    /// (transitively) depend on that memo.
+    ///
+    /// With the `single-threaded` feature, this has no effect.
+    pub fn set_parallel_verification(
+        &mut self,
+        threads: usize,
+        min_inputs: usize,
+        fork: impl Fn(&DB) -> DB + Send + Sync + 'static,
+    ) where
+        DB: ParallelDatabase + 'static,
+    {
+        let (jobs, receiver) = channel::unbounded::<VerificationJob>();
+        let workers = if cfg!(feature = "single-threaded") {
+            0
+        } else {
+            threads
+        };
+        for index in 0..workers {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("salsa-verify-{index}"))
+                .spawn(move || {
+                    for job in receiver {
+                        // A job catches the panics of verifying, but whatever
+                        // happens, the worker stays for the next ones.
+                        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                })
+                .expect("cannot spawn a verification thread");
+        }
+        self.parallel_verification = Some(Arc::new(ParallelVerification {
+            threads,
+            jobs,
+            min_inputs,
+            fork: Box::new(move |db| Box::new(fork(db))),
+        }));
+    }
+
+    /// The handles on which to verify `inputs` inputs of a memo in parallel,
+    /// or none to verify them on the current thread;
+    /// see [`Storage::set_parallel_verification`].
+    pub fn verification_forks(&self, db: &DB, inputs: usize) -> Vec<Box<dyn Database + Send>> {
+        let parallel_verification = match &self.parallel_verification {
+            Some(parallel_verification) => parallel_verification,
+            None => return vec![],
+        };
+        let threads = parallel_verification.threads.min(inputs);
+        if cfg!(feature = "single-threaded")
+            || inputs < parallel_verification.min_inputs
+            || threads < 2
+        {
+            return vec![];
+        }
+        (0..threads)
+            .map(|_| (parallel_verification.fork)(db))
+            .collect()
+    }
+
+    /// Runs `job` on a worker of the pool set up by [`Storage::set_parallel_verification`],
+    /// once one is free. Only called with the handles of [`Storage::verification_forks`],
+    /// which are none without the pool.
+    pub fn run_verification(&self, job: VerificationJob) {
+        let parallel_verification = self
+            .parallel_verification
+            .as_ref()
+            .expect("no parallel verification");
+        // The workers only stop once `jobs` is dropped.
+        parallel_verification.jobs.send(job).unwrap();
+    }
+
+    /// Limits the memory taken by memoized values across the whole database to
+    /// about `budget` bytes, or lifts the limit if `budget` is `None`. Returns how
+    /// many values were evicted to get under the new budget.
+    ///
+    /// At the start of each revision, if the values that can be evicted (see
+    /// [`Storage::evict_values`]) own more than `budget` bytes on the heap, the
+    /// values used least recently are evicted until they don't. Only the tracked
+    /// functions with a `heap_size` function are counted, and only their values
+    /// are evicted.
+    ///
+    /// Like [`Storage::sweep`], this cancels other workers but does not start a
+    /// new revision.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) -> usize {
+        self.cancel_other_workers();
+        self.runtime.clear_cancellation_flag();
+
+        self.memory_budget = budget;
+        match budget {
+            Some(budget) => {
+                let shared = Arc::get_mut(&mut self.shared).unwrap();
+                Self::evict_to_budget(&self.routes, &mut shared.jars, budget)
+            }
+            None => 0,
+        }
+    }
+
+    /// Evicts the values used least recently, across all ingredients, until
+    /// the values that can be evicted own at most `budget` bytes on the heap.
+    fn evict_to_budget(routes: &Routes<DB>, jars: &mut DB::Jars, budget: usize) -> usize {
+        let mut candidates: Vec<(IngredientIndex, EvictionCandidate)> = routes
+            .indices()
+            .flat_map(|index| {
+                routes.route(index)(jars)
+                    .eviction_candidates()
+                    .into_iter()
+                    .map(move |candidate| (index, candidate))
+            })
+            .collect();
+        let mut heap_bytes: usize = candidates.iter().map(|(_, c)| c.heap_bytes).sum();
+        if heap_bytes <= budget {
+            return 0;
+        }
+
+        candidates.sort_by_key(|(_, candidate)| candidate.verified_at);
+        let mut evicted = 0;
+        for (index, candidate) in candidates {
+            if heap_bytes <= budget {
+                break;
+            }
+            evicted += routes.route_mut(index)(jars).evict_values(Some(candidate.key_index));
+            heap_bytes -= candidate.heap_bytes;
+        }
+        evicted
+    }
+
+    /// Returns how many values each ingredient stores, and about how much memory
+    /// they take. This walks every ingredient, so it is meant to be called
+    /// now and then (e.g., to log it), not on every revision.
+    pub fn usage(&self) -> StorageUsage {
+        StorageUsage::new(
+            self.routes
+                .indices()
+                .map(|index| (index, self.ingredient(index).usage())),
+        )
+    }
+
+    /// Returns the input fields and memoized values that changed after `revision`
+    /// (e.g., a revision obtained with [`Runtime::current_revision`] before a batch of
+    /// edits), such as the outputs to emit again.
+    ///
+    /// Memoized values are only considered as of the last revision in which they were
+    /// computed or verified: a value not fetched since the edits is not reported, even
+    /// if fetching it would change it. Like [`Storage::usage`], this walks every ingredient.
+    pub fn keys_changed_since(&self, revision: Revision) -> Vec<DatabaseKeyIndex> {
+        self.routes
+            .indices()
+            .flat_map(|ingredient_index| {
+                self.ingredient(ingredient_index)
+                    .keys_changed_since(revision)
+                    .into_iter()
+                    .map(move |key_index| DatabaseKeyIndex {
+                        ingredient_index,
+                        key_index,
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the keys of all the values stored in the database, ingredient by ingredient.
+    /// Like [`Storage::usage`], this walks every ingredient.
+    pub fn keys(&self) -> Vec<DatabaseKeyIndex> {
+        let mut keys: Vec<DatabaseKeyIndex> = self
+            .routes
+            .indices()
+            .flat_map(|ingredient_index| {
+                self.ingredient(ingredient_index)
+                    .keys()
+                    .into_iter()
+                    .map(move |key_index| DatabaseKeyIndex {
+                        ingredient_index,
+                        key_index,
+                    })
+            })
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Finds the keys that [`Storage::sweep`] must keep: those verified in the
+    /// current revision and everything reachable from them through their
+    /// inputs and outputs.
+    fn live_keys(&self) -> FxHashSet<DatabaseKeyIndex> {
+        let revision = self.runtime.current_revision();
+        let mut stack: Vec<DatabaseKeyIndex> = self
+            .routes
+            .indices()
+            .flat_map(|ingredient_index| {
+                self.ingredient(ingredient_index)
+                    .keys_verified_in(revision)
+                    .into_iter()
+                    .map(move |key_index| DatabaseKeyIndex {
+                        ingredient_index,
+                        key_index,
+                    })
+            })
+            .collect();
+
+        let mut live = FxHashSet::default();
+        while let Some(key) = stack.pop() {
+            if !live.insert(key) {
+                continue;
+            }
+            match self.ingredient(key.ingredient_index).origin(key.key_index) {
+                Some(QueryOrigin::Derived(edges)) | Some(QueryOrigin::DerivedUntracked(edges)) => {
+                    stack.extend(
+                        edges
+                            .inputs()
+                            .chain(edges.outputs())
+                            .filter_map(|dependency| DatabaseKeyIndex::try_from(dependency).ok()),
+                    );
+                }
+                Some(QueryOrigin::Assigned(by)) => stack.push(by),
+                Some(QueryOrigin::BaseInput) | None => {}
+            }
+        }
+        live
+    }
 }
 
-impl<DB> Drop for Shared<DB>
+impl<DB> Drop for Storage<DB>
 where
     DB: HasJars,
 {
     fn drop(&mut self) {
-        self.cvar.notify_all();
+        // Release our handle to the jars before telling a (possibly)
+        // waiting writer to check again.
+        //
+        // SAFETY: `self.shared` is not used after this point.
+        unsafe { ManuallyDrop::drop(&mut self.shared) };
+
+        let _guard = self.coordinate.mutex.lock();
+        self.coordinate.cvar.notify_all();
+    }
+}
+
+/// Reports the revision started by a write of `db`, taken from its storage with
+/// [`Storage::take_started_revision`]. Called by the code of `#[salsa::db]`.
+#[doc(hidden)]
+pub fn report_started_revision<DB: ?Sized + Database>(db: &DB, revision: Option<Revision>) {
+    if let Some(revision) = revision {
+        db.salsa_event(crate::Event {
+            runtime_id: db.runtime().id(),
+            kind: crate::EventKind::DidStartRevision { revision },
+        });
     }
 }
 
@@ -178,10 +1079,15 @@ pub trait HasJars: HasJarsDyn + Sized {
     fn jars_mut(&mut self) -> (&mut Self::Jars, &mut Runtime);
 
     fn create_jars(routes: &mut Routes<Self>) -> Self::Jars;
+
+    /// The jars added with [`Storage::add_jar`], which are kept in `jars`.
+    fn dynamic_jars(jars: &Self::Jars) -> &DynamicJars;
+
+    fn dynamic_jars_mut(jars: &mut Self::Jars) -> &mut DynamicJars;
 }
 
 pub trait DbWithJar<J>: HasJar<J> + Database {
-    fn as_jar_db<'db>(&'db self) -> &<J as Jar<'db>>::DynDb
+    fn as_jar_db<'db>(&'db self) -> &'db <J as Jar<'db>>::DynDb
     where
         J: Jar<'db>;
 }
@@ -211,6 +1117,9 @@ pub trait HasJarsDyn {
 
     fn origin(&self, input: DatabaseKeyIndex) -> Option<QueryOrigin>;
 
+    /// Revision information for the value at `input`; see [`Ingredient::value_revisions`].
+    fn value_revisions(&self, input: DatabaseKeyIndex) -> Option<ValueRevisions>;
+
     fn mark_validated_output(&self, executor: DatabaseKeyIndex, output: DependencyIndex);
 
     /// Invoked when `executor` used to output `stale_output` but no longer does.
@@ -228,6 +1137,82 @@ pub trait HasJarsDyn {
     fn salsa_struct_deleted(&self, ingredient: IngredientIndex, id: Id);
 
     fn fmt_index(&self, index: DependencyIndex, fmt: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// See [`Storage::verification_forks`].
+    fn verification_forks(&self, inputs: usize) -> Vec<Box<dyn Database + Send>>;
+
+    /// See [`Storage::run_verification`].
+    fn run_verification(&self, job: VerificationJob);
+
+    /// The [`Ingredient::debug_name`] of the ingredient at `ingredient_index`.
+    fn ingredient_debug_name(&self, ingredient_index: IngredientIndex) -> &'static str;
+
+    /// Saves the persistent ingredients; see [`Storage::persist`].
+    #[cfg(feature = "persist")]
+    fn persist(&self) -> Result<PersistedDatabase, PersistError>;
+
+    /// Restores the persistent ingredients; see [`Storage::restore`].
+    #[cfg(feature = "persist")]
+    fn restore(&mut self, persisted: PersistedDatabase) -> Result<(), PersistError>;
+
+    /// See [`Storage::set_shadow_verification`].
+    fn set_shadow_verification_of_jars(&self, enabled: bool);
+
+    /// See [`Storage::start_recording`].
+    #[cfg(feature = "persist")]
+    fn start_recording_of_jars(&self);
+
+    /// See [`Storage::take_recording`].
+    #[cfg(feature = "persist")]
+    fn take_recording_of_jars(&self) -> Recording;
+
+    /// See [`Storage::replay`].
+    #[cfg(feature = "persist")]
+    fn replay_of_jars(&mut self, revision: &RecordedRevision) -> Result<(), PersistError>;
+
+    /// Discards data that is no longer in use; see [`Storage::sweep`].
+    fn sweep_jars(&mut self) -> usize;
+
+    /// See [`Storage::compact`].
+    fn compact_jars(&mut self);
+
+    /// See [`Storage::synthetic_write_for`].
+    fn synthetic_write_for_jars(&mut self, index: DependencyIndex);
+
+    /// See [`Storage::evict_values`].
+    fn evict_values_of_jars(&mut self, index: DependencyIndex) -> usize;
+
+    /// See [`Storage::usage`].
+    fn usage_of_jars(&self) -> StorageUsage;
+
+    /// See [`Storage::keys_changed_since`].
+    fn keys_changed_since_of_jars(&self, revision: Revision) -> Vec<DatabaseKeyIndex>;
+
+    /// See [`Storage::keys`].
+    fn keys_of_jars(&self) -> Vec<DatabaseKeyIndex>;
+
+    /// See [`Storage::set_memory_budget`].
+    fn set_memory_budget_of_jars(&mut self, budget: Option<usize>) -> usize;
+
+    /// See [`Storage::begin_transaction`].
+    fn begin_transaction(&mut self);
+
+    /// See [`Storage::end_transaction`].
+    fn end_transaction(&mut self);
+
+    /// See [`Storage::begin_speculation`].
+    fn begin_speculation(&mut self);
+
+    /// See [`Storage::end_speculation`].
+    fn end_speculation(&mut self);
+
+    /// The `DynDb` of the jar whose type id is `jar`, as a [`crate::attach::JarDb`],
+    /// or `None` if this database has no such jar.
+    fn jar_db(&self, jar: std::any::TypeId) -> Option<Box<dyn std::any::Any>>;
+
+    /// The `DynDb` of the jar whose `DynDb` has type id `view`, for
+    /// [`crate::AsView::as_view`], or `None` if this database has no such jar.
+    fn view_of_jars(&self, view: std::any::TypeId) -> Option<Box<dyn std::any::Any>>;
 }
 // ANCHOR_END: HasJarsDyn
 
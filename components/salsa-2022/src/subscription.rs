@@ -0,0 +1,154 @@
+//! Notifying clients when the values they are interested in may have changed,
+//! so that they need not fetch them again after each revision to find out.
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+
+use crate::{Database, DatabaseKeyIndex, Revision};
+
+/// Receives a notification for each revision in which the value at its key may
+/// have changed, once [`Database::notify_subscribers`] found out. Obtained with
+/// [`Database::subscribe`]; dropping it ends the subscription.
+///
+/// ```rust,ignore
+/// let subscription = db.subscribe(diagnostics::database_key(&db, file));
+/// file.set_text(&mut db).to(new_text);
+/// db.notify_subscribers();
+/// if subscription.changed().is_some() {
+///     publish(diagnostics(&db, file));
+/// }
+/// ```
+pub struct Subscription {
+    key: DatabaseKeyIndex,
+    receiver: Receiver<Revision>,
+
+    /// Identifies this subscription in `subscribers`, which it leaves when dropped.
+    id: u64,
+    subscribers: Weak<Mutex<SubscribersState>>,
+}
+
+impl Subscription {
+    /// The key subscribed to.
+    pub fn key(&self) -> DatabaseKeyIndex {
+        self.key
+    }
+
+    /// Returns the latest revision in which the value may have changed, if it was
+    /// notified since the last call, without blocking.
+    pub fn changed(&self) -> Option<Revision> {
+        self.receiver.try_iter().last()
+    }
+
+    /// Blocks until the value may have changed, and returns the revision in which
+    /// it did (or `None` if the database was dropped).
+    pub fn wait(&self) -> Option<Revision> {
+        let revision = self.receiver.recv().ok()?;
+        Some(self.changed().unwrap_or(revision))
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            let mut state = subscribers.lock();
+            if let Some(position) = state.subscribers.iter().position(|s| s.id == self.id) {
+                state.subscribers.swap_remove(position);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Subscription")
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+/// The subscriptions to the values of a database, shared by all of its handles.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    /// Shared with the subscriptions, to leave it when they are dropped.
+    state: Arc<Mutex<SubscribersState>>,
+}
+
+#[derive(Default)]
+struct SubscribersState {
+    /// Identifies the next subscriber.
+    next_id: u64,
+    subscribers: Vec<Subscriber>,
+}
+
+struct Subscriber {
+    id: u64,
+    key: DatabaseKeyIndex,
+
+    /// The revision as of which the subscriber knows the value.
+    known_as_of: Revision,
+
+    sender: Sender<Revision>,
+}
+
+impl Subscribers {
+    pub(crate) fn subscribe(&self, key: DatabaseKeyIndex, current: Revision) -> Subscription {
+        let (sender, receiver) = mpsc::channel();
+        let mut state = self.state.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.push(Subscriber {
+            id,
+            key,
+            known_as_of: current,
+            sender,
+        });
+        Subscription {
+            key,
+            receiver,
+            id,
+            subscribers: Arc::downgrade(&self.state),
+        }
+    }
+
+    /// See [`Database::notify_subscribers`].
+    pub(crate) fn notify<DB: ?Sized + Database>(&self, db: &DB) -> usize {
+        let current = db.current_revision();
+        // The lock is not held while verifying, which may execute queries.
+        let pending: Vec<_> = self
+            .state
+            .lock()
+            .subscribers
+            .iter()
+            .filter(|subscriber| subscriber.known_as_of < current)
+            .map(|subscriber| (subscriber.id, subscriber.key, subscriber.known_as_of))
+            .collect();
+
+        let mut notified = 0;
+        for (id, key, known_as_of) in pending {
+            let changed = db.maybe_changed_after(key.into(), known_as_of);
+            let mut state = self.state.lock();
+            let position = match state.subscribers.iter().position(|s| s.id == id) {
+                Some(position) => position,
+                None => continue,
+            };
+            let subscriber = &mut state.subscribers[position];
+            subscriber.known_as_of = current;
+            // The subscription is still there, so is its receiver.
+            if changed && subscriber.sender.send(current).is_ok() {
+                notified += 1;
+            }
+        }
+        notified
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Subscribers")
+            .field("len", &self.state.lock().subscribers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crossbeam::atomic::AtomicCell;
+use dashmap::mapref::entry::Entry;
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{FxDashMap, TableDashMap};
+use crate::{AsId, Database, DebugWithDb, Id};
+
+/// An interned string, shared by all the jars of a database, so that each
+/// project need not declare its own `#[salsa::interned]` struct for names.
+///
+/// A symbol is a `Copy` id, cheap to compare and hash. [`Symbol::new`] only
+/// allocates the first time a string is interned, and symbols are never freed:
+/// like the values of other interned structs, they cannot change, so reading
+/// them records no dependency.
+///
+/// Symbols are numbered in the order they are first interned. Strings known
+/// in advance (e.g., the keywords of a language) can be interned when the
+/// storage is created with [`crate::Storage::with_symbols`], which makes them
+/// available as constants with [`Symbol::predefined`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
+pub struct Symbol(Id);
+
+impl Symbol {
+    /// Interns `text`.
+    pub fn new<DB: ?Sized + Database>(db: &DB, text: &str) -> Self {
+        db.runtime()
+            .symbols()
+            .intern(Cow::Borrowed(text), |text| Cow::Owned(text.into_owned()))
+    }
+
+    /// The string that was interned.
+    pub fn as_str<DB: ?Sized + Database>(self, db: &DB) -> &str {
+        db.runtime().symbols().text(self)
+    }
+
+    /// The `index`th symbol given to [`crate::Storage::with_symbols`].
+    pub const fn predefined(index: u32) -> Self {
+        Symbol(Id::from_u32(index))
+    }
+}
+
+impl AsId for Symbol {
+    fn as_id(self) -> Id {
+        self.0
+    }
+
+    fn from_id(id: Id) -> Self {
+        Symbol(id)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Symbol").field(&self.0.as_u32()).finish()
+    }
+}
+
+impl<Db: ?Sized + Database> DebugWithDb<Db> for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, db: &Db, _include_all_fields: bool) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(db), f)
+    }
+}
+
+/// The symbols of a database, shared by its snapshots.
+#[derive(Default)]
+pub(crate) struct SymbolTable {
+    symbols: TableDashMap<Cow<'static, str>, Symbol>,
+
+    /// Strings are never removed, and those that are not `'static` are owned
+    /// by a `String`, whose contents don't move when the map does.
+    texts: FxDashMap<Symbol, Cow<'static, str>>,
+    counter: AtomicCell<u32>,
+}
+
+impl SymbolTable {
+    /// Interns `text`, converted with `to_static` if it is new.
+    fn intern<'t>(
+        &self,
+        text: Cow<'t, str>,
+        to_static: impl FnOnce(Cow<'t, str>) -> Cow<'static, str>,
+    ) -> Symbol {
+        if let Some(symbol) = self.symbols.get(&*text) {
+            return *symbol;
+        }
+        match self.symbols.entry(to_static(text)) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let symbol = Symbol(Id::from_u32(self.counter.fetch_add(1)));
+                self.texts.insert(symbol, entry.key().clone());
+                entry.insert(symbol);
+                symbol
+            }
+        }
+    }
+
+    /// Interns each of `texts`, which must be the first strings interned.
+    pub(crate) fn predefine(&self, texts: &[&'static str]) {
+        assert_eq!(
+            self.counter.load(),
+            0,
+            "symbols must be predefined before any other string is interned"
+        );
+        for (index, &text) in texts.iter().enumerate() {
+            let symbol = self.intern(Cow::Borrowed(text), |text| text);
+            assert_eq!(
+                symbol,
+                Symbol::predefined(index as u32),
+                "`{}` is predefined twice",
+                text
+            );
+        }
+    }
+
+    fn text(&self, symbol: Symbol) -> &str {
+        let text = self
+            .texts
+            .get(&symbol)
+            .unwrap_or_else(|| panic!("no string interned for {:?}", symbol));
+
+        // SAFETY: see `texts`.
+        unsafe { transmute_lifetime(self, &**text) }
+    }
+
+    /// The interned strings, in the order of their symbols (up to one being
+    /// interned concurrently, if any).
+    #[cfg(feature = "persist")]
+    pub(crate) fn texts(&self) -> Vec<String> {
+        (0..self.counter.load())
+            .map_while(|index| {
+                let text = self.texts.get(&Symbol(Id::from_u32(index)))?;
+                Some(text.to_string())
+            })
+            .collect()
+    }
+
+    /// Interns `texts` in order, returning the first one whose symbol is not
+    /// its index (because other strings were interned before).
+    #[cfg(feature = "persist")]
+    pub(crate) fn restore(&self, texts: Vec<String>) -> Result<(), (u32, String)> {
+        for (index, text) in (0..).zip(texts) {
+            let symbol = self.intern(Cow::Owned(text), |text| text);
+            if symbol.0.as_u32() != index {
+                return Err((index, self.text(symbol).to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns `u` but with the lifetime of `t`.
+//
+// Safe if you know that data at `u` will remain shared
+// until the reference `t` expires.
+unsafe fn transmute_lifetime<'t, T, U: ?Sized>(_t: &'t T, u: &U) -> &'t U {
+    std::mem::transmute(u)
+}
+
+impl fmt::Debug for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SymbolTable")
+            .field("len", &self.counter.load())
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+//! Asserting which queries salsa executed, e.g., to check that an edit does not
+//! make it re-execute more than it should.
+//!
+//! ```ignore
+//! #[salsa::db(crate::Jar)]
+//! #[derive(Default)]
+//! struct Database {
+//!     storage: salsa::Storage<Self>,
+//!     logger: salsa::testing::EventLogger,
+//! }
+//!
+//! impl salsa::Database for Database {
+//!     fn salsa_event(&self, event: salsa::Event) {
+//!         self.logger.log(&event);
+//!     }
+//! }
+//!
+//! impl salsa::testing::HasLogger for Database {
+//!     fn logger(&self) -> &salsa::testing::EventLogger {
+//!         &self.logger
+//!     }
+//! }
+//!
+//! db.logger.checkpoint();
+//! file.set_text(&mut db).to(text);
+//! check(&db, file);
+//! salsa::assert_executed!(db, [parse]);
+//! salsa::assert_not_executed!(db, [check_library]);
+//! ```
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::{debug::DebugWithDb, Database, DatabaseKeyIndex, Event, EventKind};
+
+/// Records the queries executed on a database and on its snapshots, which share
+/// the logger when they clone it. Databases give it the events they get in
+/// [`Database::salsa_event`].
+#[derive(Clone, Debug, Default)]
+pub struct EventLogger {
+    executed: Arc<Mutex<Vec<DatabaseKeyIndex>>>,
+}
+
+impl EventLogger {
+    /// Records `event`, if it is that of a query about to execute.
+    pub fn log(&self, event: &Event) {
+        if let EventKind::WillExecute { database_key } = event.kind {
+            self.executed.lock().push(database_key);
+        }
+    }
+
+    /// Forgets the queries executed so far, so that the assertions that follow
+    /// concern the queries executed from now on only.
+    pub fn checkpoint(&self) {
+        self.executed.lock().clear();
+    }
+
+    /// The queries executed since the last checkpoint, in order.
+    pub fn executed(&self) -> Vec<DatabaseKeyIndex> {
+        self.executed.lock().clone()
+    }
+}
+
+/// Implemented by databases that have an [`EventLogger`], for
+/// [`crate::assert_executed`] and [`crate::assert_not_executed`].
+pub trait HasLogger: Database {
+    fn logger(&self) -> &EventLogger;
+
+    /// Panics unless each of the functions named `queries` executed (for some key)
+    /// since the last checkpoint, or, if `executed` is false, if any did.
+    #[doc(hidden)]
+    fn check_executed(&self, queries: &[&str], executed: bool) {
+        let keys = self.logger().executed();
+        for query in queries {
+            // The name of a function given by its path.
+            let name = query.rsplit("::").next().unwrap().trim();
+            let found = keys
+                .iter()
+                .any(|&key| self.describe_key(key).ingredient_name == name);
+            if found != executed {
+                let keys: Vec<_> = keys.iter().map(|key| key.debug(self)).collect();
+                panic!(
+                    "expected `{name}` {} since the last checkpoint, but the queries executed were {keys:?}",
+                    if executed { "to have executed" } else { "not to have executed" },
+                );
+            }
+        }
+    }
+}
+
+/// Asserts that each of the given tracked functions executed, for some key, since
+/// the last [`EventLogger::checkpoint`] of the database, which implements
+/// [`HasLogger`]: `salsa::assert_executed!(db, [parse, type_check])`.
+#[macro_export]
+macro_rules! assert_executed {
+    ($db:expr, [$($query:path),* $(,)?]) => {{
+        use $crate::testing::HasLogger as _;
+        $db.check_executed(&[$(stringify!($query)),*], true)
+    }};
+}
+
+/// Asserts that none of the given tracked functions executed since the last
+/// [`EventLogger::checkpoint`] of the database; see [`crate::assert_executed`].
+#[macro_export]
+macro_rules! assert_not_executed {
+    ($db:expr, [$($query:path),* $(,)?]) => {{
+        use $crate::testing::HasLogger as _;
+        $db.check_executed(&[$(stringify!($query)),*], false)
+    }};
+}
@@ -1,14 +1,22 @@
 use std::fmt;
 
+#[cfg(feature = "persist")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "persist")]
+use crate::persist::PersistentIngredient;
 use crate::{
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, IngredientRequiresReset},
+    ingredient::{fmt_index, Ingredient, IngredientRequiresReset, ValueRevisions},
     ingredient_list::IngredientList,
     interned::{InternedData, InternedId, InternedIngredient},
     key::{DatabaseKeyIndex, DependencyIndex},
     runtime::{local_state::QueryOrigin, Runtime},
     salsa_struct::SalsaStructInDb,
-    Database, Event, IngredientIndex, Revision,
+    usage::{EvictionCandidate, IngredientUsage},
+    Database, Durability, Event, IngredientIndex, Revision,
 };
 
 pub trait TrackedStructId: InternedId {}
@@ -49,6 +57,7 @@ where
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 struct TrackedStructKey<Data> {
     query_key: Option<DatabaseKeyIndex>,
     disambiguator: Disambiguator,
@@ -56,6 +65,7 @@ struct TrackedStructKey<Data> {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
 pub struct Disambiguator(pub u32);
 
 impl<Id, Data> TrackedStructIngredient<Id, Data>
@@ -71,6 +81,17 @@ where
         }
     }
 
+    /// Derives the id of each struct from the hash of its id fields, together with
+    /// the query creating it and the disambiguator counting the structs with the
+    /// same id fields created before by that query, rather than from the order in
+    /// which the structs are created (see the `content_ids` option of tracked
+    /// structs). The ids are then the same in every process running the same
+    /// program on the same inputs, provided the keys of the creating queries are.
+    pub fn with_content_ids(mut self) -> Self {
+        self.interned.set_content_ids();
+        self
+    }
+
     pub fn database_key_index(&self, id: Id) -> DatabaseKeyIndex {
         DatabaseKeyIndex {
             ingredient_index: self.interned.ingredient_index(),
@@ -90,7 +111,9 @@ where
             disambiguator,
             data,
         };
-        let result = self.interned.intern(runtime, entity_key);
+        let result = self.interned.intern_unlimited(runtime, entity_key, || {
+            runtime.limits().count_tracked_struct(query_key)
+        });
         runtime.add_output(self.database_key_index(result).into());
         result
     }
@@ -149,6 +172,10 @@ where
         None
     }
 
+    fn value_revisions(&self, _key_index: crate::Id) -> Option<ValueRevisions> {
+        None
+    }
+
     fn mark_validated_output(
         &self,
         _db: &DB,
@@ -180,9 +207,86 @@ where
         panic!("unexpected call: interned ingredients do not register for salsa struct deletion events");
     }
 
+    fn keys_verified_in(&self, _revision: Revision) -> Vec<crate::Id> {
+        // Tracked structs are outputs of the queries that created them,
+        // which are reached from the memos.
+        vec![]
+    }
+
+    fn keys_changed_since(&self, _revision: Revision) -> Vec<crate::Id> {
+        // The changes of tracked structs are those of their fields.
+        vec![]
+    }
+
+    fn keys(&self) -> Vec<crate::Id> {
+        self.interned.ids()
+    }
+
+    fn sweep(&mut self, is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+        let ingredient_index = self.interned.ingredient_index();
+        self.interned.retain(|id| {
+            is_live(DatabaseKeyIndex {
+                ingredient_index,
+                key_index: id.as_id(),
+            })
+        })
+    }
+
+    fn synthetic_write(
+        &mut self,
+        _key_index: Option<crate::Id>,
+        _revision: Revision,
+    ) -> Option<Durability> {
+        // The fields of a tracked struct change when the query creating it is re-executed.
+        None
+    }
+
+    fn evict_values(&mut self, _key_index: Option<crate::Id>) -> usize {
+        // Tracked structs are needed to tell whether they changed.
+        0
+    }
+
+    fn compact(&mut self) {
+        self.interned.compact()
+    }
+
+    fn usage(&self) -> IngredientUsage {
+        self.interned.usage()
+    }
+
+    fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        vec![]
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(self.debug_name, index, fmt)
     }
+
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<Id, Data> PersistentIngredient for TrackedStructIngredient<Id, Data>
+where
+    Id: TrackedStructId,
+    Data: TrackedStructData + Serialize + DeserializeOwned,
+{
+    fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    fn serialize(
+        &self,
+        is_persistent: &dyn Fn(IngredientIndex) -> bool,
+    ) -> serde_json::Result<serde_json::Value> {
+        self.interned.serialize(is_persistent)
+    }
+
+    fn deserialize(&mut self, data: serde_json::Value) -> serde_json::Result<()> {
+        self.interned.deserialize(data)
+    }
 }
 
 impl<Id, Data> IngredientRequiresReset for TrackedStructIngredient<Id, Data>
@@ -0,0 +1,169 @@
+//! Writing the new value of a tracked function into its old value, with the
+//! `update` option, so that what the old value owns on the heap (e.g., the
+//! buffers of the vectors of a big syntax tree) is reused.
+//!
+//! ```ignore
+//! #[derive(Debug, PartialEq, Eq)]
+//! struct Ast {
+//!     items: Vec<Item>,
+//! }
+//!
+//! impl salsa::Update for Ast {
+//!     fn update(&mut self, new_value: Self) {
+//!         self.items.update(new_value.items);
+//!     }
+//! }
+//!
+//! #[salsa::tracked(update)]
+//! fn parse(db: &dyn Db, file: File) -> Ast {
+//!     // ...
+//! }
+//! ```
+//!
+//! The old value is only written into when no one can read it anymore: it is
+//! not when it was read in the current revision (e.g., as the provisional value of
+//! a cycle), nor when the function keeps a `history` of its values.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    hash::{BuildHasher, Hash},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// A value that a new value of its type can be written into, reusing what the
+/// old value owns where it can.
+///
+/// Afterwards, the value must be the same as `new_value` would have been: salsa
+/// compares values and hands them out as if it had stored `new_value`.
+pub trait Update {
+    /// Makes `self` the same as `new_value`.
+    fn update(&mut self, new_value: Self);
+}
+
+macro_rules! update_by_assignment {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Update for $ty {
+                fn update(&mut self, new_value: Self) {
+                    *self = new_value;
+                }
+            }
+        )*
+    };
+}
+
+update_by_assignment! {
+    (), bool, char,
+    u8, u16, u32, u64, u128, usize,
+    i8, i16, i32, i64, i128, isize,
+    f32, f64,
+    &'static str,
+    crate::Id, crate::Symbol,
+}
+
+impl Update for String {
+    fn update(&mut self, new_value: Self) {
+        // Copying the text reuses the buffer, if it is big enough.
+        self.clone_from(&new_value);
+    }
+}
+
+impl<T: Update> Update for Vec<T> {
+    fn update(&mut self, new_value: Self) {
+        self.truncate(new_value.len());
+        let mut new_values = new_value.into_iter();
+        for (old, new) in self.iter_mut().zip(&mut new_values) {
+            old.update(new);
+        }
+        self.extend(new_values);
+    }
+}
+
+impl<T: Update> Update for VecDeque<T> {
+    fn update(&mut self, new_value: Self) {
+        self.truncate(new_value.len());
+        let mut new_values = new_value.into_iter();
+        for (old, new) in self.iter_mut().zip(&mut new_values) {
+            old.update(new);
+        }
+        self.extend(new_values);
+    }
+}
+
+impl<T: Update> Update for Option<T> {
+    fn update(&mut self, new_value: Self) {
+        match (self, new_value) {
+            (Some(old), Some(new)) => old.update(new),
+            (old, new) => *old = new,
+        }
+    }
+}
+
+impl<T: Update, E: Update> Update for Result<T, E> {
+    fn update(&mut self, new_value: Self) {
+        match (self, new_value) {
+            (Ok(old), Ok(new)) => old.update(new),
+            (Err(old), Err(new)) => old.update(new),
+            (old, new) => *old = new,
+        }
+    }
+}
+
+impl<T: Update> Update for Box<T> {
+    fn update(&mut self, new_value: Self) {
+        (**self).update(*new_value);
+    }
+}
+
+/// The target may be shared, so it is not written into.
+impl<T: ?Sized> Update for Arc<T> {
+    fn update(&mut self, new_value: Self) {
+        *self = new_value;
+    }
+}
+
+/// The target may be shared, so it is not written into.
+impl<T: ?Sized> Update for Rc<T> {
+    fn update(&mut self, new_value: Self) {
+        *self = new_value;
+    }
+}
+
+/// The values of the keys in both maps are written into; the others are removed
+/// or inserted.
+impl<K: Hash + Eq, V: Update, S: BuildHasher> Update for HashMap<K, V, S> {
+    fn update(&mut self, mut new_value: Self) {
+        self.retain(|key, _| new_value.contains_key(key));
+        for (key, old) in self.iter_mut() {
+            old.update(new_value.remove(key).unwrap());
+        }
+        self.extend(new_value);
+    }
+}
+
+/// As for `HashMap`.
+impl<K: Ord, V: Update> Update for BTreeMap<K, V> {
+    fn update(&mut self, mut new_value: Self) {
+        self.retain(|key, _| new_value.contains_key(key));
+        for (key, old) in self.iter_mut() {
+            old.update(new_value.remove(key).unwrap());
+        }
+        self.extend(new_value);
+    }
+}
+
+macro_rules! update_tuple {
+    ($($name:ident $index:tt),*) => {
+        impl<$($name: Update),*> Update for ($($name,)*) {
+            fn update(&mut self, new_value: Self) {
+                $(self.$index.update(new_value.$index);)*
+            }
+        }
+    };
+}
+
+update_tuple!(A 0);
+update_tuple!(A 0, B 1);
+update_tuple!(A 0, B 1, C 2);
+update_tuple!(A 0, B 1, C 2, D 3);
@@ -0,0 +1,94 @@
+//! How many values each ingredient stores, and about how much memory they take,
+//! for capacity planning. See [`crate::Database::storage_usage`].
+
+use std::{collections::BTreeMap, fmt, ops::Add};
+
+use crate::{key::DependencyIndex, Database, DebugWithDb, Id, IngredientIndex, Revision};
+
+/// The values stored by a single ingredient.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IngredientUsage {
+    /// Number of values: memos for functions, ids for inputs, interned values and
+    /// tracked structs, field values for the fields of inputs, and accumulated values.
+    pub entries: usize,
+
+    /// Approximate number of bytes taken by those values and their bookkeeping
+    /// (e.g., the dependencies of memos). Memory owned by the values themselves,
+    /// like the contents of a `String`, is not included.
+    pub bytes: usize,
+
+    /// Number of bytes owned by the values on the heap, for the tracked
+    /// functions with a `heap_size` function; zero for anything else.
+    pub heap_bytes: usize,
+}
+
+impl Add for IngredientUsage {
+    type Output = IngredientUsage;
+
+    fn add(self, other: IngredientUsage) -> IngredientUsage {
+        IngredientUsage {
+            entries: self.entries + other.entries,
+            bytes: self.bytes + other.bytes,
+            heap_bytes: self.heap_bytes + other.heap_bytes,
+        }
+    }
+}
+
+/// A memoized value that [`crate::Storage::set_memory_budget`] may evict.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EvictionCandidate {
+    pub key_index: Id,
+
+    /// The last revision in which the value was used: the oldest values are evicted first.
+    pub verified_at: Revision,
+
+    /// Number of bytes owned by the value on the heap, freed by evicting it.
+    pub heap_bytes: usize,
+}
+
+/// The [`IngredientUsage`] of each ingredient in the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    usage: BTreeMap<IngredientIndex, IngredientUsage>,
+}
+
+impl StorageUsage {
+    pub(crate) fn new(usage: impl IntoIterator<Item = (IngredientIndex, IngredientUsage)>) -> Self {
+        Self {
+            usage: usage.into_iter().collect(),
+        }
+    }
+
+    /// The usage of the ingredient with the given index
+    /// (e.g., from `my_function::database_key(db, ...).ingredient_index()`).
+    pub fn get(&self, ingredient_index: IngredientIndex) -> IngredientUsage {
+        self.usage
+            .get(&ingredient_index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The usage summed over all ingredients.
+    pub fn total(&self) -> IngredientUsage {
+        self.usage
+            .values()
+            .fold(IngredientUsage::default(), |sum, &usage| sum + usage)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IngredientIndex, IngredientUsage)> + '_ {
+        self.usage.iter().map(|(&index, &usage)| (index, usage))
+    }
+}
+
+impl<Db> DebugWithDb<Db> for StorageUsage
+where
+    Db: ?Sized + Database,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, db: &Db, _include_all_fields: bool) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (index, usage) in self.iter() {
+            map.entry(&DependencyIndex::for_table(index).debug(db), &usage);
+        }
+        map.finish()
+    }
+}
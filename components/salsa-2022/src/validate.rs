@@ -0,0 +1,81 @@
+//! Verifying the memoized values of a database ahead of the queries reading them.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    runtime::local_state::QueryOrigin, Cancelled, DatabaseKeyIndex, ParallelDatabase, Snapshot,
+};
+
+impl<DB> Snapshot<DB>
+where
+    DB: ParallelDatabase + Send,
+{
+    /// Verifies every memoized value of the database, as reading it from a
+    /// query would, then drops the snapshot. Called on otherwise idle threads
+    /// after an edit, this takes the verification off the path of the next
+    /// request: the memos it finds to be valid are marked as verified in the
+    /// current revision, and those whose inputs changed are executed again, so
+    /// that the request finds them all up to date.
+    ///
+    /// The memos are split between `threads` snapshots of this one (with the
+    /// `single-threaded` feature, or if `threads` is at most 1, this snapshot
+    /// verifies them all by itself). The values computed from untracked reads are
+    /// skipped, as they would be executed again on their next read anyway.
+    ///
+    /// Returns how many memos were verified. This stops, returning the reason,
+    /// as soon as the snapshot is cancelled (see [`crate::Database::cancellation_handle`])
+    /// or another handle is waiting to write: the memos not verified yet are then
+    /// left to the queries reading them.
+    pub fn validate_all(self, threads: usize) -> Result<usize, Cancelled> {
+        let keys: Vec<DatabaseKeyIndex> = self
+            .keys_of_jars()
+            .into_iter()
+            .filter(|&key| matches!(self.origin(key), Some(QueryOrigin::Derived(_))))
+            .collect();
+        let next = AtomicUsize::new(0);
+        let handle = self.cancellation_handle();
+        let validate = |db: &DB| {
+            let mut verified = 0;
+            Cancelled::catch(AssertUnwindSafe(|| loop {
+                // The snapshots of this one have their own cancellation handles.
+                if let Some(cancelled) = handle.check() {
+                    cancelled.throw();
+                }
+                let key = match keys.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    Some(&key) => key,
+                    None => return,
+                };
+                db.maybe_changed_after(key.into(), db.current_revision());
+                verified += 1;
+            }))
+            .map(|()| verified)
+        };
+
+        let threads = if cfg!(feature = "single-threaded") {
+            1
+        } else {
+            threads.min(keys.len())
+        };
+        if threads <= 1 {
+            return validate(&self);
+        }
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    // `self` is not `Sync`, so the snapshots are taken on this thread.
+                    let snapshot = self.snapshot();
+                    let validate = &validate;
+                    scope.spawn(move || validate(&snapshot))
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| match worker.join() {
+                    Ok(result) => result,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                })
+                .try_fold(0, |total, verified| Ok(total + verified?))
+        })
+    }
+}
@@ -0,0 +1,68 @@
+//! Casting a database between the database traits of its jars.
+//!
+//! When a compiler is split across crates, each with its own jar, the code of
+//! one crate only sees the database through its own trait (e.g., `&dyn a::Db`),
+//! even though the concrete database implements the traits of every jar. With
+//! [`AsView::as_view`], that code can get the database as `&dyn b::Db` to call
+//! the queries of the jar of `b`, without an upcasting method in every trait.
+//!
+//! The database traits that a database can be viewed as are the `DynDb` types
+//! of its jars (the `db = ...` given to `#[salsa::jar]`), as registered by
+//! `#[salsa::db]`.
+
+use std::any::{Any, TypeId};
+
+use crate::{jar::Jar, storage::DbWithJar, Database};
+
+/// Views a database as the database trait of another of its jars.
+///
+/// Implemented for all databases, including `dyn` database traits.
+pub trait AsView {
+    /// Returns this database as `V`, which must be the database trait of one
+    /// of its jars, e.g., `db.as_view::<dyn other_crate::Db>()`.
+    ///
+    /// Panics if no jar of the database has `V` for its database trait.
+    fn as_view<V>(&self) -> &V
+    where
+        V: ?Sized + 'static;
+}
+
+impl<DB> AsView for DB
+where
+    DB: ?Sized + Database,
+{
+    fn as_view<V>(&self) -> &V
+    where
+        V: ?Sized + 'static,
+    {
+        let view = self.view_of_jars(TypeId::of::<V>()).unwrap_or_else(|| {
+            panic!(
+                "no jar of the database has `{}` for its database trait",
+                std::any::type_name::<V>()
+            )
+        });
+        let view = view.downcast_ref::<ViewDb<V>>().unwrap();
+        // SAFETY: The view borrows from `self`, see `view_of_jar`.
+        unsafe { &*view.0 }
+    }
+}
+
+/// The `DynDb` of the jar `J` of `db`, as a `dyn Any`, if that is the view of
+/// type id `view`. Used by [`crate::storage::HasJarsDyn::view_of_jars`].
+#[doc(hidden)]
+pub fn view_of_jar<'db, DB, J>(db: &'db DB, view: TypeId) -> Option<Box<dyn Any>>
+where
+    DB: DbWithJar<J>,
+    J: for<'j> Jar<'j> + 'static,
+{
+    if view != TypeId::of::<<J as Jar<'static>>::DynDb>() {
+        return None;
+    }
+    let db: *const <J as Jar<'db>>::DynDb = db.as_jar_db();
+    // SAFETY: The two pointer types only differ in lifetimes. The pointer is
+    // turned back into a reference with the lifetime of `db` by `as_view`.
+    let db: *const <J as Jar<'static>>::DynDb = unsafe { std::mem::transmute_copy(&db) };
+    Some(Box::new(ViewDb(db)))
+}
+
+struct ViewDb<V: ?Sized>(*const V);
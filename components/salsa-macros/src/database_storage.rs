@@ -246,11 +246,3 @@ impl Parse for QueryGroup {
         Ok(QueryGroup { group_path })
     }
 }
-
-struct Nothing;
-
-impl Parse for Nothing {
-    fn parse(_input: ParseStream) -> syn::Result<Self> {
-        Ok(Nothing)
-    }
-}
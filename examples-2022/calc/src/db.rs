@@ -1,6 +1,4 @@
-use std::sync::{Arc, Mutex};
-
-use salsa::DebugWithDb;
+use salsa::testing::{EventLogger, HasLogger};
 
 // ANCHOR: db_struct
 #[derive(Default)]
@@ -8,55 +6,49 @@ use salsa::DebugWithDb;
 pub(crate) struct Database {
     storage: salsa::Storage<Self>,
 
-    // The logs are only used for testing and demonstrating reuse:
-    //
-    logs: Option<Arc<Mutex<Vec<String>>>>,
+    // The logger is only used for testing and demonstrating reuse:
+    // it records the queries that execute.
+    logger: EventLogger,
 }
 // ANCHOR_END: db_struct
 
 impl Database {
-    /// Enable logging of each salsa event.
-    #[cfg(test)]
-    pub fn enable_logging(self) -> Self {
-        assert!(self.logs.is_none());
-        Self {
-            storage: self.storage,
-            logs: Some(Default::default()),
-        }
-    }
-
+    /// Takes the queries executed since the last call, as shown by `Debug`.
     #[cfg(test)]
     pub fn take_logs(&mut self) -> Vec<String> {
-        if let Some(logs) = &self.logs {
-            std::mem::take(&mut *logs.lock().unwrap())
-        } else {
-            panic!("logs not enabled");
-        }
+        use salsa::DebugWithDb;
+
+        let logs = self
+            .logger
+            .executed()
+            .iter()
+            .map(|key| format!("{:?}", key.debug(self)))
+            .collect();
+        self.logger.checkpoint();
+        logs
     }
 }
 
 // ANCHOR: db_impl
 impl salsa::Database for Database {
     fn salsa_event(&self, event: salsa::Event) {
-        // Log interesting events, if logging is enabled
-        if let Some(logs) = &self.logs {
-            // don't log boring events
-            if let salsa::EventKind::WillExecute { .. } = event.kind {
-                logs.lock()
-                    .unwrap()
-                    .push(format!("Event: {:?}", event.debug(self)));
-            }
-        }
+        self.logger.log(&event);
     }
 }
 // ANCHOR_END: db_impl
 
+impl HasLogger for Database {
+    fn logger(&self) -> &EventLogger {
+        &self.logger
+    }
+}
+
 // ANCHOR: par_db_impl
 impl salsa::ParallelDatabase for Database {
     fn snapshot(&self) -> salsa::Snapshot<Self> {
         salsa::Snapshot::new(Database {
             storage: self.storage.snapshot(),
-            logs: self.logs.clone(),
+            logger: self.logger.clone(),
         })
     }
 }
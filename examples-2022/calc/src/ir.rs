@@ -105,4 +105,11 @@ pub struct Diagnostic {
     pub end: usize,
     pub message: String,
 }
+
+impl Diagnostic {
+    /// The byte offsets in the source text that the diagnostic is about.
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
 // ANCHOR_END: diagnostic
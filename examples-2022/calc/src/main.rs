@@ -1,4 +1,4 @@
-use ir::{Diagnostics, SourceProgram};
+use std::io::{BufRead, IsTerminal, Write};
 
 // ANCHOR: jar_struct
 #[salsa::jar(db = Db)]
@@ -30,12 +30,23 @@ mod compile;
 mod db;
 mod ir;
 mod parser;
+mod repl;
+mod reporting;
 mod type_check;
 
+/// Checks the program entered on the standard input, line by line.
 pub fn main() {
-    let db = db::Database::default();
-    let source_program = SourceProgram::new(&db, String::new());
-    compile::compile(&db, source_program);
-    let diagnostics = compile::compile::accumulated::<Diagnostics>(&db, source_program);
-    eprintln!("{diagnostics:?}");
+    let interactive = std::io::stdin().is_terminal();
+    let mut repl = repl::Repl::new(std::io::stdout().is_terminal());
+    let prompt = || {
+        if interactive {
+            print!("> ");
+            std::io::stdout().flush().unwrap();
+        }
+    };
+    prompt();
+    for line in std::io::stdin().lock().lines() {
+        print!("{}", repl.enter(&line.unwrap()));
+        prompt();
+    }
 }
@@ -338,10 +338,8 @@ impl Parser<'_> {
 
             if s.is_empty() {
                 None
-            } else if let Ok(n) = str::parse(&s) {
-                Some(n)
             } else {
-                None
+                str::parse(&s).ok()
             }
         })
     }
@@ -0,0 +1,91 @@
+use salsa::{testing::HasLogger, DebugWithDb};
+
+use crate::{
+    compile::compile,
+    db::Database,
+    ir::{Diagnostics, SourceProgram},
+    reporting,
+};
+
+// ANCHOR: repl
+/// Reads a program line by line, checking it after each one: the text of the
+/// `SourceProgram` is set anew for each line, and only the queries that depend
+/// on what changed execute again.
+pub struct Repl {
+    db: Database,
+    source_program: SourceProgram,
+    color: bool,
+}
+
+impl Repl {
+    pub fn new(color: bool) -> Self {
+        let db = Database::default();
+        let source_program = SourceProgram::new(&db, String::new());
+        Repl {
+            db,
+            source_program,
+            color,
+        }
+    }
+
+    /// Adds `line` to the program, unless the program then has errors, and
+    /// returns what to show: the errors, if any, then the queries that executed.
+    pub fn enter(&mut self, line: &str) -> String {
+        let old_text = self.source_program.text(&self.db).clone();
+        let text = format!("{old_text}{line}\n");
+        self.db.logger().checkpoint();
+        self.source_program.set_text(&mut self.db).to(text);
+
+        compile(&self.db, self.source_program);
+        let diagnostics = compile::accumulated::<Diagnostics>(&self.db, self.source_program);
+        let mut output = reporting::render(
+            &self.db,
+            self.source_program,
+            "input",
+            &diagnostics,
+            self.color,
+        );
+        let executed: Vec<_> = self
+            .db
+            .logger()
+            .executed()
+            .iter()
+            .map(|key| format!("{:?}", key.debug(&self.db)))
+            .collect();
+        output.push_str(&format!("executed: {}\n", executed.join(", ")));
+
+        if !diagnostics.is_empty() {
+            output.push_str("(the line was not added)\n");
+            self.source_program.set_text(&mut self.db).to(old_text);
+        }
+        output
+    }
+}
+// ANCHOR_END: repl
+
+#[test]
+fn enter_lines() {
+    let mut repl = Repl::new(false);
+    expect_test::expect![[r#"
+        executed: compile(0), parse_statements(0), type_check_program(0), type_check_function(0)
+    "#]]
+    .assert_eq(&repl.enter("fn double(x) = x * 2"));
+    expect_test::expect![[r#"
+        executed: parse_statements(0), compile(0), type_check_program(1), type_check_function(1), find_function(0)
+    "#]].assert_eq(&repl.enter("print double(3)"));
+    expect_test::expect![[r#"
+        Error: the function `triple` is not declared
+           ╭─[ input:3:7 ]
+           │
+         3 │ print triple(3)
+           │       ────┬────  
+           │           ╰────── the function `triple` is not declared
+        ───╯
+        executed: parse_statements(0), compile(0), type_check_program(2), type_check_function(2), find_function(1), find_function(2)
+        (the line was not added)
+    "#]].assert_eq(&repl.enter("print triple(3)"));
+    expect_test::expect![[r#"
+        executed: parse_statements(0), type_check_program(2), find_function(1)
+    "#]]
+    .assert_eq(&repl.enter("print double(4)"));
+}
@@ -0,0 +1,63 @@
+use ariadne::{Config, IndexType, Label, Report, ReportKind, Source};
+
+use crate::ir::{Diagnostic, SourceProgram};
+
+// ANCHOR: render
+/// Renders `diagnostics` against the text of `source_program`: each with its
+/// message and the line it is about, with its span underlined. The source is
+/// called `name` in the output.
+pub fn render(
+    db: &dyn crate::Db,
+    source_program: SourceProgram,
+    name: &str,
+    diagnostics: &[Diagnostic],
+    color: bool,
+) -> String {
+    let text = source_program.text(db);
+    let config = Config::default()
+        .with_color(color)
+        .with_index_type(IndexType::Byte);
+    let mut output = Vec::new();
+    for diagnostic in diagnostics {
+        let span = (name, diagnostic.span());
+        Report::build(ReportKind::Error, span.clone())
+            .with_config(config)
+            .with_message(&diagnostic.message)
+            .with_label(Label::new(span).with_message(&diagnostic.message))
+            .finish()
+            .write((name, Source::from(text.as_str())), &mut output)
+            .unwrap();
+    }
+    String::from_utf8(output).unwrap()
+}
+// ANCHOR_END: render
+
+#[test]
+fn render_type_errors() {
+    use crate::{compile::compile, db::Database, ir::Diagnostics};
+
+    let db = Database::default();
+    let source_program = SourceProgram::new(
+        &db,
+        "fn add_one(a) = a + b\nprint add_one(22)\nprint c".to_string(),
+    );
+    compile(&db, source_program);
+    let diagnostics = compile::accumulated::<Diagnostics>(&db, source_program);
+    expect_test::expect![[r#"
+        Error: the variable `c` is not declared
+           ╭─[ input:3:7 ]
+           │
+         3 │ print c
+           │       ┬  
+           │       ╰── the variable `c` is not declared
+        ───╯
+        Error: the variable `b` is not declared
+           ╭─[ input:1:21 ]
+           │
+         1 │ fn add_one(a) = a + b
+           │                     ─┬  
+           │                      ╰── the variable `b` is not declared
+        ───╯
+    "#]]
+    .assert_eq(&render(&db, source_program, "input", &diagnostics, false));
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 /// Sources for the [selection pattern chapter][c] of the salsa book.
 ///
 /// [c]: https://salsa-rs.github.io/salsa/common_patterns/selection.html
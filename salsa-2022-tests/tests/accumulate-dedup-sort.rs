@@ -0,0 +1,177 @@
+//! Test the `dedup` and `sort_by_key` options of accumulators,
+//! which make collected values independent of how often and in
+//! which order queries pushed them.
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Workspace,
+    File,
+    Diagnostics,
+    UniqueDiagnostics,
+    SortedDiagnostics,
+    check_file,
+    check_files,
+);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Workspace {
+    files: Vec<File>,
+}
+
+#[salsa::input]
+struct File {
+    path: String,
+    #[return_ref]
+    lines: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Diagnostic {
+    path: String,
+    line: usize,
+    message: String,
+}
+
+fn diagnostic_key(diagnostic: &Diagnostic) -> (&str, usize) {
+    (&diagnostic.path, diagnostic.line)
+}
+
+#[salsa::accumulator]
+struct Diagnostics(Diagnostic);
+
+#[salsa::accumulator(dedup)]
+struct UniqueDiagnostics(Diagnostic);
+
+#[salsa::accumulator(dedup, sort_by_key = diagnostic_key)]
+struct SortedDiagnostics(Diagnostic);
+
+#[salsa::tracked]
+fn check_file(db: &dyn Db, file: File) {
+    // Lines are checked from last to first, and each check
+    // reports the file's missing header again.
+    for (line, text) in file.lines(db).iter().enumerate().rev() {
+        if text.is_empty() {
+            push(db, file, line, "empty line");
+        }
+        push(db, file, 0, "missing header");
+    }
+}
+
+fn push(db: &dyn Db, file: File, line: usize, message: &str) {
+    let diagnostic = Diagnostic {
+        path: file.path(db),
+        line,
+        message: message.to_string(),
+    };
+    Diagnostics::push(db, diagnostic.clone());
+    UniqueDiagnostics::push(db, diagnostic.clone());
+    SortedDiagnostics::push(db, diagnostic);
+}
+
+#[salsa::tracked]
+fn check_files(db: &dyn Db, workspace: Workspace) {
+    for file in workspace.files(db) {
+        check_file(db, file);
+    }
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn file(db: &Database, path: &str, lines: &[&str]) -> File {
+    File::new(
+        db,
+        path.to_string(),
+        lines.iter().map(|l| l.to_string()).collect(),
+    )
+}
+
+#[test]
+fn dedup() {
+    let db = Database::default();
+    let a = file(&db, "a.rs", &["", "fn main() {}"]);
+
+    assert_eq!(check_file::accumulated::<Diagnostics>(&db, a).len(), 3);
+    expect![[r#"
+        [
+            Diagnostic {
+                path: "a.rs",
+                line: 0,
+                message: "missing header",
+            },
+            Diagnostic {
+                path: "a.rs",
+                line: 0,
+                message: "empty line",
+            },
+        ]
+    "#]]
+    .assert_debug_eq(&check_file::accumulated::<UniqueDiagnostics>(&db, a));
+}
+
+#[test]
+fn dedup_and_sort() {
+    let mut db = Database::default();
+    let a = file(&db, "a.rs", &["", "fn main() {}", ""]);
+    let b = file(&db, "b.rs", &["mod a;"]);
+
+    let expected = expect![[r#"
+        [
+            (
+                "a.rs",
+                0,
+                "missing header",
+            ),
+            (
+                "a.rs",
+                0,
+                "empty line",
+            ),
+            (
+                "a.rs",
+                2,
+                "empty line",
+            ),
+            (
+                "b.rs",
+                0,
+                "missing header",
+            ),
+        ]
+    "#]];
+    let workspace = Workspace::new(&db, vec![b, a]);
+    let sorted = |db: &Database| {
+        check_files(db, workspace);
+        check_files::accumulated::<SortedDiagnostics>(db, workspace)
+            .into_iter()
+            .map(|d| (d.path, d.line, d.message))
+            .collect::<Vec<_>>()
+    };
+    expected.assert_debug_eq(&sorted(&db));
+
+    workspace.set_files(&mut db).to(vec![a, b]);
+    expected.assert_debug_eq(&sorted(&db));
+
+    // After an edit re-executes `check_file(a)`, the order is still the same.
+    a.set_lines(&mut db)
+        .to(vec!["".to_string(), "".to_string(), "".to_string()]);
+    b.set_lines(&mut db).to(vec!["mod a;".to_string()]);
+    a.set_lines(&mut db).to(vec![
+        "".to_string(),
+        "fn main() {}".to_string(),
+        "".to_string(),
+    ]);
+    expected.assert_debug_eq(&sorted(&db));
+}
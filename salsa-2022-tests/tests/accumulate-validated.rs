@@ -0,0 +1,112 @@
+//! Test that `accumulated` returns the values pushed by exactly the queries
+//! that the (current) result depends on, however they were validated.
+
+use salsa::Durability;
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Workspace, File, Diagnostics, check_file, check_files);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Workspace {
+    files: Vec<File>,
+}
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+#[salsa::accumulator]
+struct Diagnostics(String);
+
+#[salsa::tracked]
+fn check_file(db: &dyn Db, file: File) {
+    let contents = file.contents(db);
+    if contents.contains("todo") {
+        Diagnostics::push(db, contents);
+    }
+}
+
+#[salsa::tracked]
+fn check_files(db: &dyn Db, workspace: Workspace) {
+    for file in workspace.files(db) {
+        check_file(db, file);
+    }
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn diagnostics(db: &Database, workspace: Workspace) -> Vec<String> {
+    check_files(db, workspace);
+    let mut diagnostics = check_files::accumulated::<Diagnostics>(db, workspace);
+    diagnostics.sort();
+    diagnostics
+}
+
+#[test]
+fn validated_by_durability() {
+    let mut db = Database::default();
+    let file = File::builder("todo: std".to_string())
+        .durability(Durability::HIGH)
+        .new(&db);
+    let workspace = Workspace::builder(vec![file])
+        .durability(Durability::HIGH)
+        .new(&db);
+    let other = File::new(&db, "".to_string());
+    assert_eq!(diagnostics(&db, workspace), ["todo: std"]);
+
+    // `check_files` is validated without visiting `check_file`,
+    // whose values are still current.
+    other.set_contents(&mut db).to("todo".to_string());
+    assert_eq!(diagnostics(&db, workspace), ["todo: std"]);
+}
+
+#[test]
+fn removed_and_reexecuted_queries() {
+    let mut db = Database::default();
+    let a = File::new(&db, "todo: a".to_string());
+    let b = File::new(&db, "todo: b".to_string());
+    let c = File::new(&db, "todo: c".to_string());
+    let workspace = Workspace::new(&db, vec![a, b, c]);
+    expect![[r#"
+        [
+            "todo: a",
+            "todo: b",
+            "todo: c",
+        ]
+    "#]]
+    .assert_debug_eq(&diagnostics(&db, workspace));
+
+    // `check_file(b)` is no longer called, and `check_file(c)` no longer pushes.
+    workspace.set_files(&mut db).to(vec![a, c]);
+    c.set_contents(&mut db).to("done".to_string());
+    expect![[r#"
+        [
+            "todo: a",
+        ]
+    "#]]
+    .assert_debug_eq(&diagnostics(&db, workspace));
+
+    // Its values come back with `check_file(b)`.
+    workspace.set_files(&mut db).to(vec![a, b, c]);
+    expect![[r#"
+        [
+            "todo: a",
+            "todo: b",
+        ]
+    "#]]
+    .assert_debug_eq(&diagnostics(&db, workspace));
+}
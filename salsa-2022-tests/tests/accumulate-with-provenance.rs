@@ -0,0 +1,114 @@
+//! Test `accumulated_with_provenance`, which tells which query
+//! pushed each accumulated value.
+
+use salsa::DebugWithDb;
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Workspace, File, Diagnostics, check_file, check_files);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Workspace {
+    files: Vec<File>,
+}
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+#[salsa::accumulator]
+struct Diagnostics(String);
+
+#[salsa::tracked]
+fn check_file(db: &dyn Db, file: File) {
+    for word in file.contents(db).split_whitespace() {
+        if word.contains("todo") {
+            Diagnostics::push(db, format!("found `{word}`"));
+        }
+    }
+}
+
+#[salsa::tracked]
+fn check_files(db: &dyn Db, workspace: Workspace) {
+    for file in workspace.files(db) {
+        check_file(db, file);
+    }
+    Diagnostics::push(db, "checked workspace".to_string());
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn provenance() {
+    let mut db = Database::default();
+    let a = File::new(&db, "todo!() todo_later".to_string());
+    let b = File::new(&db, "ok".to_string());
+    let c = File::new(&db, "todo".to_string());
+    let workspace = Workspace::new(&db, vec![a, b, c]);
+
+    check_files(&db, workspace);
+    let accumulated = check_files::accumulated_with_provenance::<Diagnostics>(&db, workspace);
+    expect![[r#"
+        [
+            (
+                "check_files(0)",
+                "checked workspace",
+            ),
+            (
+                "check_file(2)",
+                "found `todo`",
+            ),
+            (
+                "check_file(0)",
+                "found `todo!()`",
+            ),
+            (
+                "check_file(0)",
+                "found `todo_later`",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(
+        &accumulated
+            .iter()
+            .map(|(query, value)| (format!("{:?}", query.debug(&db)), value))
+            .collect::<Vec<_>>(),
+    );
+
+    // The values are the ones `accumulated` returns.
+    let mut values: Vec<_> = accumulated.into_iter().map(|(_, value)| value).collect();
+    let mut expected = check_files::accumulated::<Diagnostics>(&db, workspace);
+    values.sort();
+    expected.sort();
+    assert_eq!(values, expected);
+
+    // Leave out the values pushed for `a`, after it re-executed.
+    a.set_contents(&mut db).to("todo".to_string());
+    let a_key = check_file::database_key(&db, a);
+    let not_from_a: Vec<_> =
+        check_files::accumulated_with_provenance::<Diagnostics>(&db, workspace)
+            .into_iter()
+            .filter(|(query, _)| *query != a_key)
+            .map(|(_, value)| value)
+            .collect();
+    expect![[r#"
+        [
+            "checked workspace",
+            "found `todo`",
+        ]
+    "#]]
+    .assert_debug_eq(&not_from_a);
+}
@@ -0,0 +1,82 @@
+//! Test that `Database::active_query_stack` lists the queries
+//! executing on the current thread, outermost first.
+
+use salsa::{Database as _, DebugWithDb};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Program, Circle, compile, area_circle);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Program {
+    circles: Vec<u32>,
+}
+
+#[salsa::interned]
+struct Circle {
+    radius: u32,
+}
+
+#[salsa::tracked]
+fn compile(db: &dyn Db, program: Program) -> u32 {
+    program
+        .circles(db)
+        .into_iter()
+        .map(|radius| area_circle(db, Circle::new(db, radius)))
+        .sum()
+}
+
+#[salsa::tracked]
+fn area_circle(db: &dyn Db, circle: Circle) -> u32 {
+    let backtrace: Vec<String> = db
+        .active_query_stack()
+        .iter()
+        .rev()
+        .map(|key| format!("while computing {:?}", key.debug(db)))
+        .collect();
+    db.push_log(backtrace.join(", "));
+    3 * circle.radius(db) * circle.radius(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let program = Program::new(&db, vec![1, 2]);
+
+    assert!(db.active_query_stack().is_empty());
+    assert_eq!(compile(&db, program), 15);
+    db.assert_logs(expect![[r#"
+        [
+            "while computing area_circle(0), while computing compile(0)",
+            "while computing area_circle(1), while computing compile(0)",
+        ]"#]]);
+
+    // Called outside of `compile`, only `area_circle` is executing.
+    area_circle(&db, Circle::new(&db, 3));
+    db.assert_logs(expect![[r#"
+        [
+            "while computing area_circle(2)",
+        ]"#]]);
+}
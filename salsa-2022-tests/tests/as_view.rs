@@ -0,0 +1,116 @@
+//! Test `AsView::as_view`, which casts a database between the database
+//! traits of its jars.
+
+use expect_test::expect;
+use salsa::AsView;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+/// A crate that knows nothing of `b`.
+mod a {
+    #[salsa::jar(db = Db)]
+    pub struct Jar(File, text_len);
+
+    pub trait Db: salsa::DbWithJar<Jar> + salsa_2022_tests::HasLogger {}
+
+    #[salsa::input(jar = Jar)]
+    pub struct File {
+        #[return_ref]
+        pub text: String,
+    }
+
+    #[salsa::tracked(jar = Jar)]
+    pub fn text_len(db: &dyn Db, file: File) -> usize {
+        db.push_log(format!("text_len({:?})", file.text(db)));
+        file.text(db).len()
+    }
+}
+
+/// A crate downstream of `a`.
+mod b {
+    use salsa::AsView;
+
+    #[salsa::jar(db = Db)]
+    pub struct Jar(word_count);
+
+    pub trait Db: salsa::DbWithJar<Jar> + crate::a::Db {}
+
+    #[salsa::tracked(jar = Jar)]
+    pub fn word_count(db: &dyn Db, file: crate::a::File) -> usize {
+        db.push_log(format!("word_count({:?})", file.text(db)));
+        file.text(db).split_whitespace().count()
+    }
+
+    /// Called back by code that only has the database of `a`.
+    pub fn describe(db: &dyn crate::a::Db, file: crate::a::File) -> String {
+        let db = db.as_view::<dyn Db>();
+        format!(
+            "{} bytes, {} words",
+            crate::a::text_len(db, file),
+            word_count(db, file)
+        )
+    }
+}
+
+#[salsa::db(a::Jar, b::Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl a::Db for Database {}
+
+impl b::Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn view_downstream_jar() {
+    let mut db = Database::default();
+    let file = a::File::new(&db, "hello world".to_string());
+    let a_db: &dyn a::Db = &db;
+    assert_eq!(b::describe(a_db, file), "11 bytes, 2 words");
+    db.assert_logs(expect![[r#"
+        [
+            "text_len(\"hello world\")",
+            "word_count(\"hello world\")",
+        ]"#]]);
+
+    // Queries called through the view are memoized as usual.
+    assert_eq!(b::describe(&db, file), "11 bytes, 2 words");
+    db.assert_logs(expect!["[]"]);
+
+    file.set_text(&mut db).to("hi".to_string());
+    assert_eq!(b::describe(&db, file), "2 bytes, 1 words");
+    db.assert_logs(expect![[r#"
+        [
+            "text_len(\"hi\")",
+            "word_count(\"hi\")",
+        ]"#]]);
+}
+
+#[test]
+fn view_own_jar() {
+    let db = Database::default();
+    let file = a::File::new(&db, "abc".to_string());
+    let b_db: &dyn b::Db = &db;
+    assert_eq!(a::text_len(b_db.as_view::<dyn a::Db>(), file), 3);
+}
+
+#[test]
+#[should_panic(
+    expected = "no jar of the database has `dyn as_view::Unrelated` for its database trait"
+)]
+fn view_without_jar() {
+    let db = Database::default();
+    db.as_view::<dyn Unrelated>();
+}
+
+trait Unrelated {}
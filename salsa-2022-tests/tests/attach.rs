@@ -0,0 +1,125 @@
+//! Test that the `Debug` impls of salsa structs print their fields
+//! within `salsa::attach`.
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Name, Item, items);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    path: String,
+}
+
+#[salsa::interned]
+struct Name {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Item {
+    #[id]
+    name: Name,
+    size: usize,
+}
+
+/// Not a salsa type, so formatting it goes through `Debug`.
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Located {
+    file: File,
+    line: u32,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Db, file: File) -> Vec<Item> {
+    let item = Item::new(db, Name::new(db, file.path(db)), 12);
+    // Attaching works with the dyn database of a tracked function.
+    let debug = salsa::attach(db, || format!("{item:?}"));
+    assert_eq!(
+        debug,
+        r#"Item { [salsa id]: 0, name: Name { [salsa id]: 0, text: "lib.rs" }, size: 12 }"#
+    );
+    vec![item]
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[salsa::jar(db = OtherDb)]
+struct OtherJar(Unrelated);
+
+trait OtherDb: salsa::DbWithJar<OtherJar> {}
+
+#[salsa::input(jar = OtherJar)]
+struct Unrelated {
+    field: u32,
+}
+
+#[salsa::db(OtherJar)]
+#[derive(Default)]
+struct OtherDatabase {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for OtherDatabase {}
+
+impl OtherDb for OtherDatabase {}
+
+#[test]
+fn attach() {
+    let db = Database::default();
+    let file = File::new(&db, "lib.rs".to_string());
+    let located = Located { file, line: 3 };
+
+    expect![[r#"
+        Located { file: File(Id { value: 1 }), line: 3 }
+    "#]]
+    .assert_eq(&format!("{located:?}\n"));
+
+    let debug = salsa::attach(&db, || format!("{located:?}\n"));
+    expect![[r#"
+        Located { file: File { [salsa id]: 0, path: "lib.rs" }, line: 3 }
+    "#]]
+    .assert_eq(&debug);
+
+    // Detached again.
+    expect![[r#"
+        File(Id { value: 1 })
+    "#]]
+    .assert_eq(&format!("{file:?}\n"));
+
+    items(&db, file);
+}
+
+#[test]
+fn nested_attach() {
+    let db = Database::default();
+    let other_db = OtherDatabase::default();
+    let file = File::new(&db, "lib.rs".to_string());
+    let unrelated = Unrelated::new(&other_db, 22);
+
+    salsa::attach(&db, || {
+        salsa::attach(&other_db, || {
+            // `file` is not from the innermost database.
+            expect![[r#"
+                File(Id { value: 1 }) Unrelated { [salsa id]: 0, field: 22 }
+            "#]]
+            .assert_eq(&format!("{file:?} {unrelated:?}\n"));
+        });
+        expect![[r#"
+            File { [salsa id]: 0, path: "lib.rs" } Unrelated(Id { value: 1 })
+        "#]]
+        .assert_eq(&format!("{file:?} {unrelated:?}\n"));
+    });
+}
@@ -0,0 +1,12 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyTracked);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::tracked(jar = Jar)]
+struct MyTracked {
+    #[default]
+    field: u32,
+}
+
+fn main() {}
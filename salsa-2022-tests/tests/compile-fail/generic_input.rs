@@ -0,0 +1,11 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput<u32>);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput<T> {
+    field: T,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyTracked);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+fn load_field(_db: &dyn Db, _tracked: MyTracked) -> u32 {
+    22
+}
+
+#[salsa::tracked(jar = Jar)]
+struct MyTracked {
+    #[id]
+    #[lazy(load_field)]
+    field: u32,
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+//! Test that `specify` does not work for a function taking several arguments
+//! if the first one is not a tracked struct
+//! compilation fails
+#![allow(warnings)]
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, MyTracked, tracked_fn);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(jar = Jar)]
+struct MyTracked {
+    field: u32,
+}
+
+#[salsa::tracked(jar = Jar, specify)]
+fn tracked_fn(db: &dyn Db, input: MyInput, tracked: MyTracked) -> u32 {
+    input.field(db) + tracked.field(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn main() {}
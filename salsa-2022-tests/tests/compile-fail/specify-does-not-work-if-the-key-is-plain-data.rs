@@ -0,0 +1,11 @@
+#[salsa::jar(db = Db)]
+struct Jar(tracked_fn);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::tracked(jar = Jar, specify, intern_args)]
+fn tracked_fn(db: &dyn Db, input: u32) -> u32 {
+    input * 2
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(jar = Jar)]
+enum WithNamedField {
+    Input { input: MyInput },
+}
+
+#[salsa::tracked(jar = Jar)]
+enum WithoutField {
+    Input(MyInput),
+    Nothing,
+}
+
+#[salsa::tracked(jar = Jar)]
+enum Empty {}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, tracked_fn_async);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(jar = Jar)]
+async fn tracked_fn_async(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, tracked_fn);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+fn same(a: &u32, b: &u32) -> bool {
+    a == b
+}
+
+#[salsa::tracked(jar = Jar, eq = same, no_eq)]
+fn tracked_fn(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+fn main() {}
@@ -34,10 +34,17 @@ fn tracked_fn_with_one_input(db: &dyn Db) -> u32 {
 fn tracked_fn_with_receiver_not_applied_to_impl_block(&self, db: &dyn Db) -> u32 {
 }
 
-#[salsa::tracked(jar = Jar, specify)]
-fn tracked_fn_with_receiver_not_applied_to_impl_block(db: &dyn Db, input: MyInput, input: MyInput) -> u32 {
+#[salsa::tracked(jar = Jar, intern_args)]
+fn tracked_fn_with_intern_args_and_two_arguments(db: &dyn Db, input: MyInput, value: u32) -> u32 {
+    value
 }
 
+#[salsa::tracked(jar = Jar, cycle_max_iterations = 3)]
+fn tracked_fn_with_cycle_max_iterations_without_cycle_fn(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+
 
 
 
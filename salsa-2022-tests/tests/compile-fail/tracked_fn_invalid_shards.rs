@@ -0,0 +1,16 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, tracked_fn_with_three_shards);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(jar = Jar, shards = 3)]
+fn tracked_fn_with_three_shards(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+fn main() {}
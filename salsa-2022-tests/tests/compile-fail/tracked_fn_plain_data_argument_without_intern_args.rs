@@ -0,0 +1,14 @@
+//! Test that the single argument of a tracked function must be a salsa struct,
+//! unless the function has the `intern_args` option.
+
+#[salsa::jar(db = Db)]
+struct Jar(tracked_fn);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::tracked(jar = Jar)]
+fn tracked_fn(db: &dyn Db, input: u32) -> u32 {
+    input * 2
+}
+
+fn main() {}
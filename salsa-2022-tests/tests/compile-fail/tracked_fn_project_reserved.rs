@@ -0,0 +1,25 @@
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, tracked_fn);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(jar = Jar)]
+struct MyInput {
+    field: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Pair {
+    get: u32,
+    other: u32,
+}
+
+#[salsa::tracked(jar = Jar, project(other: u32, get: u32))]
+fn tracked_fn(db: &dyn Db, input: MyInput) -> Pair {
+    Pair {
+        get: input.field(db),
+        other: 0,
+    }
+}
+
+fn main() {}
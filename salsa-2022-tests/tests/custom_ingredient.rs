@@ -0,0 +1,256 @@
+//! Test an ingredient defined outside of salsa with `salsa::plumbing`:
+//! a tally of counters, each of which queries can depend on separately.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(tally::Tally, MyInput, doubled_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+mod tally {
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use parking_lot::Mutex;
+    use salsa::plumbing::*;
+
+    /// Listed in the jar, to which it adds a [`TallyIngredient`].
+    pub(crate) struct Tally;
+
+    impl Tally {
+        pub(crate) fn count(db: &dyn crate::Db, counter: u32) -> u32 {
+            let (jar, runtime) = <_ as HasJar<crate::Jar>>::jar(db);
+            <crate::Jar as HasIngredientsFor<Tally>>::ingredient(jar).count(runtime, counter)
+        }
+
+        pub(crate) fn increment(db: &mut dyn crate::Db, counter: u32) {
+            let (jar, runtime) = <_ as HasJar<crate::Jar>>::jar_mut(db);
+            <crate::Jar as HasIngredientsFor<Tally>>::ingredient_mut(jar)
+                .increment(runtime, counter)
+        }
+    }
+
+    impl IngredientsFor for Tally {
+        type Jar = crate::Jar;
+        type Ingredients = TallyIngredient;
+
+        fn create_ingredients<DB>(routes: &mut Routes<DB>) -> Self::Ingredients
+        where
+            DB: DbWithJar<Self::Jar> + JarFromJars<Self::Jar>,
+        {
+            let index = routes.push(
+                |jars| {
+                    let jar = <DB as JarFromJars<Self::Jar>>::jar_from_jars(jars);
+                    <_ as HasIngredientsFor<Self>>::ingredient(jar)
+                },
+                |jars| {
+                    let jar = <DB as JarFromJars<Self::Jar>>::jar_from_jars_mut(jars);
+                    <_ as HasIngredientsFor<Self>>::ingredient_mut(jar)
+                },
+            );
+            TallyIngredient {
+                index,
+                counters: Default::default(),
+            }
+        }
+    }
+
+    pub(crate) struct TallyIngredient {
+        index: IngredientIndex,
+
+        /// The count of each counter, and when it last changed.
+        counters: Mutex<HashMap<u32, (u32, Revision)>>,
+    }
+
+    impl TallyIngredient {
+        fn count(&self, runtime: &Runtime, counter: u32) -> u32 {
+            let (count, changed_at) = self
+                .counters
+                .lock()
+                .get(&counter)
+                .copied()
+                .unwrap_or((0, Revision::start()));
+            runtime.report_tracked_read(
+                DependencyIndex::new(self.index, Some(Id::from_u32(counter))),
+                Durability::LOW,
+                changed_at,
+            );
+            count
+        }
+
+        fn increment(&mut self, runtime: &mut Runtime, counter: u32) {
+            let revision = runtime.current_revision();
+            let mut counters = self.counters.lock();
+            let entry = counters.entry(counter).or_insert((0, revision));
+            *entry = (entry.0 + 1, revision);
+            runtime.report_tracked_write(Durability::LOW);
+        }
+    }
+
+    impl<DB: ?Sized> Ingredient<DB> for TallyIngredient {
+        fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+            CycleRecoveryStrategy::Panic
+        }
+
+        fn maybe_changed_after(
+            &self,
+            _db: &DB,
+            input: DependencyIndex,
+            revision: Revision,
+        ) -> bool {
+            let counter = input.key_index().unwrap().as_u32();
+            match self.counters.lock().get(&counter) {
+                Some(&(_, changed_at)) => changed_at > revision,
+                None => false,
+            }
+        }
+
+        fn origin(&self, _key_index: Id) -> Option<QueryOrigin> {
+            None
+        }
+
+        fn value_revisions(&self, key_index: Id) -> Option<ValueRevisions> {
+            let counters = self.counters.lock();
+            let &(_, changed_at) = counters.get(&key_index.as_u32())?;
+            Some(ValueRevisions {
+                changed_at,
+                verified_at: None,
+                durability: Durability::LOW,
+            })
+        }
+
+        fn mark_validated_output(
+            &self,
+            _db: &DB,
+            _executor: DatabaseKeyIndex,
+            _output_key: Option<Id>,
+        ) {
+        }
+
+        fn remove_stale_output(
+            &self,
+            _db: &DB,
+            _executor: DatabaseKeyIndex,
+            _stale_output_key: Option<Id>,
+        ) {
+        }
+
+        fn salsa_struct_deleted(&self, _db: &DB, _id: Id) {}
+
+        fn reset_for_new_revision(&mut self) {}
+
+        fn keys_verified_in(&self, _revision: Revision) -> Vec<Id> {
+            vec![]
+        }
+
+        fn keys_changed_since(&self, revision: Revision) -> Vec<Id> {
+            self.counters
+                .lock()
+                .iter()
+                .filter(|(_, &(_, changed_at))| changed_at > revision)
+                .map(|(&counter, _)| Id::from_u32(counter))
+                .collect()
+        }
+
+        fn keys(&self) -> Vec<Id> {
+            self.counters
+                .lock()
+                .keys()
+                .map(|&counter| Id::from_u32(counter))
+                .collect()
+        }
+
+        fn sweep(&mut self, _is_live: &dyn Fn(DatabaseKeyIndex) -> bool) -> usize {
+            0
+        }
+
+        fn synthetic_write(
+            &mut self,
+            _key_index: Option<Id>,
+            _revision: Revision,
+        ) -> Option<Durability> {
+            None
+        }
+
+        fn evict_values(&mut self, _key_index: Option<Id>) -> usize {
+            0
+        }
+
+        fn usage(&self) -> IngredientUsage {
+            IngredientUsage {
+                entries: self.counters.lock().len(),
+                ..Default::default()
+            }
+        }
+
+        fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+            vec![]
+        }
+
+        fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt_index("Tally", index, fmt)
+        }
+
+        fn debug_name(&self) -> &'static str {
+            "Tally"
+        }
+    }
+
+    impl IngredientRequiresReset for TallyIngredient {
+        const RESET_ON_NEW_REVISION: bool = false;
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    counter: u32,
+}
+
+#[salsa::tracked]
+fn doubled_count(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("doubled_count({})", input.counter(db)));
+    tally::Tally::count(db, input.counter(db)) * 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn queries_depend_on_their_counter() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(doubled_count(&db, input), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "doubled_count(1)",
+        ]"#]]);
+
+    tally::Tally::increment(&mut db, 1);
+    assert_eq!(doubled_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "doubled_count(1)",
+        ]"#]]);
+
+    // Another counter changing does not invalidate the query.
+    tally::Tally::increment(&mut db, 2);
+    assert_eq!(doubled_count(&db, input), 2);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,228 @@
+//! Test queries that iterate to a fixed point when they participate in a cycle.
+
+use salsa::DebugWithDb;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    MyInput,
+    count_up,
+    read_count,
+    diverge,
+    read_diverge,
+    capped,
+    read_capped,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    limit: u32,
+    cyclic: bool,
+}
+
+/// Counts up by one on each iteration until reaching `limit`.
+#[salsa::tracked(cycle_fn = count_up_cycle_fn, cycle_initial = count_up_initial)]
+fn count_up(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("count_up({:?})", input));
+    if input.cyclic(db) {
+        (read_count(db, input) + 1).min(input.limit(db))
+    } else {
+        input.limit(db)
+    }
+}
+
+fn count_up_initial(_db: &dyn Db, _input: MyInput) -> u32 {
+    0
+}
+
+fn count_up_cycle_fn(
+    _db: &dyn Db,
+    _value: &u32,
+    _count: u32,
+    _input: MyInput,
+) -> salsa::CycleRecoveryAction<u32> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+#[salsa::tracked]
+fn read_count(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("read_count({:?})", input));
+    count_up(db, input)
+}
+
+/// Never converges; the cycle fn gives up after a few iterations.
+#[salsa::tracked(cycle_fn = diverge_cycle_fn, cycle_initial = diverge_initial)]
+fn diverge(db: &dyn Db, input: MyInput) -> u32 {
+    read_diverge(db, input) + 1
+}
+
+fn diverge_initial(_db: &dyn Db, _input: MyInput) -> u32 {
+    0
+}
+
+fn diverge_cycle_fn(
+    db: &dyn Db,
+    value: &u32,
+    count: u32,
+    input: MyInput,
+) -> salsa::CycleRecoveryAction<u32> {
+    db.push_log(format!(
+        "diverge_cycle_fn(value = {}, count = {})",
+        value, count
+    ));
+    if count >= input.limit(db) {
+        salsa::CycleRecoveryAction::Fallback(u32::MAX)
+    } else {
+        salsa::CycleRecoveryAction::Iterate
+    }
+}
+
+#[salsa::tracked]
+fn read_diverge(db: &dyn Db, input: MyInput) -> u32 {
+    diverge(db, input)
+}
+
+/// Never converges, and iterates at most 3 times.
+#[salsa::tracked(
+    cycle_fn = count_up_cycle_fn,
+    cycle_initial = count_up_initial,
+    cycle_max_iterations = 3
+)]
+fn capped(db: &dyn Db, input: MyInput) -> u32 {
+    read_capped(db, input) + 1
+}
+
+#[salsa::tracked]
+fn read_capped(db: &dyn Db, input: MyInput) -> u32 {
+    capped(db, input)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let salsa::EventKind::WillIterateCycle { .. } = event.kind {
+            self.push_log(format!("salsa_event({:?})", event.kind.debug(self)));
+        }
+    }
+}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn iterates_until_fixed_point() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 2, true);
+
+    assert_eq!(count_up(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 1 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 2 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+        ]"#]]);
+
+    // `read_count` only ever saw provisional values, so it re-executes
+    // and reads the final value.
+    assert_eq!(read_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "read_count(MyInput(Id { value: 1 }))",
+        ]"#]]);
+
+    // Both are memoized from now on.
+    assert_eq!(count_up(&db, input), 2);
+    assert_eq!(read_count(&db, input), 2);
+    db.assert_logs(expect!["[]"]);
+
+    // Verifying `count_up` re-executes `read_count`, which sees the initial value;
+    // the first iteration can then reuse that result.
+    input.set_limit(&mut db).to(3);
+    assert_eq!(count_up(&db, input), 3);
+    assert_eq!(read_count(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "read_count(MyInput(Id { value: 1 }))",
+            "count_up(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 1 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 2 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 3 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+        ]"#]]);
+}
+
+#[test]
+fn cycle_disappears() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, true);
+
+    assert_eq!(count_up(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+            "salsa_event(WillIterateCycle { database_key: count_up(0), iteration_count: 1 })",
+            "count_up(MyInput(Id { value: 1 }))",
+            "read_count(MyInput(Id { value: 1 }))",
+        ]"#]]);
+
+    input.set_cyclic(&mut db).to(false);
+    input.set_limit(&mut db).to(5);
+    assert_eq!(count_up(&db, input), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "count_up(MyInput(Id { value: 1 }))",
+        ]"#]]);
+}
+
+#[test]
+fn cycle_fn_can_stop_iteration() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 3, true);
+
+    assert_eq!(diverge(&db, input), u32::MAX);
+    assert_eq!(read_diverge(&db, input), u32::MAX);
+    db.assert_logs(expect![[r#"
+        [
+            "diverge_cycle_fn(value = 1, count = 1)",
+            "salsa_event(WillIterateCycle { database_key: diverge(0), iteration_count: 1 })",
+            "diverge_cycle_fn(value = 2, count = 2)",
+            "salsa_event(WillIterateCycle { database_key: diverge(0), iteration_count: 2 })",
+            "diverge_cycle_fn(value = 3, count = 3)",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "capped(0): execution did not converge after 3 iterations")]
+fn iterations_are_capped() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 0, true);
+    capped(&db, input);
+}
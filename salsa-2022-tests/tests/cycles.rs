@@ -3,7 +3,7 @@
 use std::panic::{RefUnwindSafe, UnwindSafe};
 
 use expect_test::expect;
-use salsa::Durability;
+use salsa::{storage::HasJarsDyn, DebugWithDb, Durability};
 
 // Axes:
 //
@@ -192,6 +192,32 @@ fn cycle_memoized() {
         ]
     "#]];
     expected.assert_debug_eq(&cycle.all_participants(&db));
+    assert_eq!(cycle.runtimes(), vec![db.runtime().id()]);
+}
+
+#[test]
+fn cycle_entry_order() {
+    let db = Database::default();
+    let input = MyInput::new(&db);
+    let cycle = salsa::Cycle::catch(|| memoized_b(&db, input)).unwrap_err();
+    let entered: Vec<String> = cycle
+        .participant_keys_in_entry_order()
+        .map(|key| format!("{:?}", key.debug(&db)))
+        .collect();
+    let expected = expect![[r#"
+        [
+            "memoized_b(0)",
+            "memoized_a(0)",
+        ]
+    "#]];
+    expected.assert_debug_eq(&entered);
+    let expected = expect![[r#"
+        [
+            "memoized_a(0)",
+            "memoized_b(0)",
+        ]
+    "#]];
+    expected.assert_debug_eq(&cycle.all_participants(&db));
 }
 
 #[test]
@@ -0,0 +1,111 @@
+//! Test `#[derive(salsa::DebugWithDb)]` on structs and enums
+//! that contain salsa structs.
+
+use expect_test::expect;
+use salsa::DebugWithDb;
+
+#[salsa::jar(db = Db)]
+struct Jar(Name, Function);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::interned]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::input]
+struct Function {
+    name: Name,
+    body: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, salsa::DebugWithDb)]
+enum Expr {
+    Number(u32),
+    Variable(Name),
+    Call { callee: Name, args: Vec<Expr> },
+    Unit,
+}
+
+#[derive(salsa::DebugWithDb)]
+struct Program<'a> {
+    functions: Vec<Function>,
+    main: Option<&'a Expr>,
+    // Shadows the formatter used in the generated code.
+    f: bool,
+}
+
+#[derive(salsa::DebugWithDb)]
+struct Pair(Name, String);
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn derive() {
+    let db = Database::default();
+    let print = Name::new(&db, "print".to_string());
+    let x = Name::new(&db, "x".to_string());
+    let call = Expr::Call {
+        callee: print,
+        args: vec![Expr::Variable(x), Expr::Number(1), Expr::Unit],
+    };
+
+    expect![[r#"
+        Call {
+            callee: Name {
+                [salsa id]: 0,
+                text: "print",
+            },
+            args: [
+                Variable(
+                    Name {
+                        [salsa id]: 1,
+                        text: "x",
+                    },
+                ),
+                Number(
+                    1,
+                ),
+                Unit,
+            ],
+        }
+    "#]]
+    .assert_debug_eq(&call.debug(&db));
+
+    expect![[r#"
+        Pair(
+            Name {
+                [salsa id]: 1,
+                text: "x",
+            },
+            "y",
+        )
+    "#]]
+    .assert_debug_eq(&Pair(x, "y".to_string()).debug(&db));
+
+    // Fields of salsa structs are only included with `debug_all`.
+    let main = Function::new(&db, Name::new(&db, "main".to_string()), call.clone());
+    let program = Program {
+        functions: vec![main],
+        main: Some(&call),
+        f: true,
+    };
+    expect![[r#"
+        Program { functions: [Function { [salsa id]: 0 }], main: Some(Call { callee: Name { [salsa id]: 0, text: "print" }, args: [Variable(Name { [salsa id]: 1, text: "x" }), Number(1), Unit] }), f: true }
+    "#]]
+    .assert_eq(&format!("{:?}\n", program.debug(&db)));
+    expect![[r#"
+        Program { functions: [Function { [salsa id]: 0, name: Name { [salsa id]: 2, text: "main" }, body: Call { callee: Name { [salsa id]: 0, text: "print" }, args: [Variable(Name { [salsa id]: 1, text: "x" }), Number(1), Unit] } }], main: Some(Call { callee: Name { [salsa id]: 0, text: "print" }, args: [Variable(Name { [salsa id]: 1, text: "x" }), Number(1), Unit] }), f: true }
+    "#]]
+    .assert_eq(&format!("{:?}\n", program.debug_all(&db)));
+}
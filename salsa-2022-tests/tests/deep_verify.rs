@@ -0,0 +1,54 @@
+//! Test that verifying a long chain of memos, each depending on the next,
+//! does not overflow the stack.
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Node, sum);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Node {
+    value: u64,
+    next: Option<Node>,
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Db, node: Node) -> u64 {
+    node.value(db) + node.next(db).map_or(0, |next| sum(db, next))
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+const LENGTH: u64 = 20_000;
+
+#[test]
+fn verify_long_chain() {
+    let mut db = Database::default();
+    let leaf = Node::new(&db, 1, None);
+    let mut head = leaf;
+    for value in 2..=LENGTH {
+        head = Node::new(&db, value, Some(head));
+        // Computed from the leaf up, so that no execution goes deep.
+        sum(&db, head);
+    }
+    assert_eq!(sum(&db, head), LENGTH * (LENGTH + 1) / 2);
+
+    // Verifying the head now goes through the whole chain.
+    leaf.set_value(&mut db).to(2);
+    assert_eq!(sum(&db, head), LENGTH * (LENGTH + 1) / 2 + 1);
+
+    // So does it when nothing it reads changed.
+    let other = Node::new(&db, 0, None);
+    other.set_value(&mut db).to(1);
+    assert_eq!(sum(&db, head), LENGTH * (LENGTH + 1) / 2 + 1);
+}
@@ -0,0 +1,91 @@
+//! Test that `dump_dependency_graph` renders the inputs and outputs
+//! recorded for a memoized value.
+
+use salsa::debug::dump_dependency_graph;
+use salsa::{Database as _, Durability};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    MyInput,
+    MyTracked,
+    final_result,
+    create_tracked,
+    tracked_value,
+);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn final_result(db: &dyn Db, input: MyInput) -> u32 {
+    tracked_value(db, create_tracked(db, input)) + input.field(db)
+}
+
+#[salsa::tracked]
+fn create_tracked(db: &dyn Db, input: MyInput) -> MyTracked {
+    MyTracked::new(db, input.field(db) * 2)
+}
+
+#[salsa::tracked]
+fn tracked_value(db: &dyn Db, tracked: MyTracked) -> u32 {
+    tracked.field(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn dump_graph() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(final_result(&db, input), 3);
+
+    db.synthetic_write(Durability::LOW);
+    input
+        .set_field(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(1);
+    assert_eq!(final_result(&db, input), 3);
+
+    let dot = dump_dependency_graph(&db, final_result::database_key(&db, input));
+    expect![[r#"
+        digraph {
+            n0 [label="final_result(0)\nchanged at R1, verified at R3\ndurability LOW"];
+            n1 [label="create_tracked(0)\nchanged at R1, verified at R3\ndurability HIGH"];
+            n2 [label="tracked_value(0)\nchanged at R1, verified at R3\ndurability LOW"];
+            n3 [label="field(0)\nchanged at R3\ndurability HIGH"];
+            n4 [label="MyTracked()"];
+            n5 [label="MyTracked(0)"];
+            n6 [label="field(0)\nchanged at R1, verified at R3\ndurability HIGH"];
+            n0 -> n1;
+            n0 -> n2;
+            n0 -> n3;
+            n1 -> n3;
+            n1 -> n4;
+            n1 -> n5;
+            n1 -> n5 [style=dashed];
+            n1 -> n6 [style=dashed];
+            n2 -> n6;
+        }
+    "#]]
+    .assert_eq(&dot);
+}
@@ -0,0 +1,94 @@
+//! Test `Database::describe_key`, which resolves a `DatabaseKeyIndex` into plain data.
+
+use expect_test::expect;
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Word, length);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct Word {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Db, input: MyInput) -> usize {
+    Word::new(db, input.text(db).clone()).text(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn describe_keys() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "hello".to_string());
+    length(&db, input);
+    input.set_text(&mut db).to("bye".to_string());
+    length(&db, input);
+
+    let descriptions: Vec<_> = db
+        .storage
+        .keys()
+        .into_iter()
+        .map(|key| db.describe_key(key))
+        .collect();
+    expect![[r#"
+        [
+            KeyDescription {
+                ingredient_name: "text",
+                key_debug: "text(0)",
+                durability: Some(
+                    Durability(
+                        0,
+                    ),
+                ),
+                changed_at: Some(
+                    R2,
+                ),
+            },
+            KeyDescription {
+                ingredient_name: "Word",
+                key_debug: "Word(0)",
+                durability: None,
+                changed_at: None,
+            },
+            KeyDescription {
+                ingredient_name: "Word",
+                key_debug: "Word(1)",
+                durability: None,
+                changed_at: None,
+            },
+            KeyDescription {
+                ingredient_name: "length",
+                key_debug: "length(0)",
+                durability: Some(
+                    Durability(
+                        0,
+                    ),
+                ),
+                changed_at: Some(
+                    R2,
+                ),
+            },
+        ]
+    "#]]
+    .assert_debug_eq(&descriptions);
+}
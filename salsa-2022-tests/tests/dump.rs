@@ -0,0 +1,144 @@
+//! Test `salsa::debug::dump`, which writes the keys and memo metadata of a
+//! database as JSON.
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Name, Item, items, last_item);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Item {
+    name: Name,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Db, file: File) -> Vec<Item> {
+    file.text(db)
+        .split_whitespace()
+        .map(|word| Item::new(db, Name::new(db, word.to_string())))
+        .collect()
+}
+
+#[salsa::tracked]
+fn last_item(db: &dyn Db, file: File) -> Option<Item> {
+    items(db, file).last().copied()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn dump(db: &Database) -> String {
+    let mut json = Vec::new();
+    salsa::debug::dump(db, &mut json).unwrap();
+    String::from_utf8(json).unwrap()
+}
+
+#[test]
+fn dump_after_edit() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a b".to_string());
+    last_item(&db, file);
+    file.set_text(&mut db).to("a".to_string());
+    last_item(&db, file);
+    expect![[r#"
+        {
+          "revision": 2,
+          "values": [
+            {
+              "key": "text(0)",
+              "changed_at": 2,
+              "verified_at": null,
+              "durability": "LOW",
+              "origin": null
+            },
+            {
+              "key": "Name(0)",
+              "changed_at": null,
+              "verified_at": null,
+              "durability": null,
+              "origin": null
+            },
+            {
+              "key": "Name(1)",
+              "changed_at": null,
+              "verified_at": null,
+              "durability": null,
+              "origin": null
+            },
+            {
+              "key": "name(0)",
+              "changed_at": 1,
+              "verified_at": 2,
+              "durability": "LOW",
+              "origin": {
+                "kind": "assigned",
+                "by": "items(0)"
+              }
+            },
+            {
+              "key": "Item(0)",
+              "changed_at": null,
+              "verified_at": null,
+              "durability": null,
+              "origin": null
+            },
+            {
+              "key": "items(0)",
+              "changed_at": 2,
+              "verified_at": 2,
+              "durability": "LOW",
+              "origin": {
+                "kind": "derived",
+                "untracked": false,
+                "inputs": [
+                  "text(0)",
+                  "Name(0)",
+                  "Item()",
+                  "Item(0)"
+                ],
+                "outputs": [
+                  "Item(0)",
+                  "name(0)"
+                ]
+              }
+            },
+            {
+              "key": "last_item(0)",
+              "changed_at": 2,
+              "verified_at": 2,
+              "durability": "LOW",
+              "origin": {
+                "kind": "derived",
+                "untracked": false,
+                "inputs": [
+                  "items(0)"
+                ],
+                "outputs": []
+              }
+            }
+          ]
+        }"#]]
+    .assert_eq(&dump(&db));
+}
@@ -0,0 +1,98 @@
+//! Test a storage with more durability levels than the default, with which
+//! changes to one layer don't make salsa revalidate queries over higher layers.
+
+use salsa::{Database as _, Durability};
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, contents_len, total_len);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+#[salsa::tracked]
+fn contents_len(db: &dyn Db, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[salsa::tracked]
+fn total_len(db: &dyn Db, file: File) -> usize {
+    contents_len(db, file)
+}
+
+#[salsa::db(Jar)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+const WORKSPACE: Durability = Durability::new(3);
+const STDLIB: Durability = Durability::new(4);
+
+/// Reads `total_len` of a stdlib file after editing a workspace file,
+/// returning how many memos were validated to do so.
+fn validations_after_workspace_edit(mut db: Database) -> u64 {
+    let stdlib = File::builder("fn main() {}".to_string())
+        .durability(STDLIB)
+        .new(&db);
+    let workspace = File::builder("".to_string()).durability(WORKSPACE).new(&db);
+    assert_eq!(total_len(&db, stdlib), 12);
+
+    workspace
+        .set_contents(&mut db)
+        .with_durability(WORKSPACE)
+        .to("mod a;".to_string());
+    db.reset_query_statistics();
+    assert_eq!(total_len(&db, stdlib), 12);
+    db.query_statistics().total().validations
+}
+
+#[test]
+fn levels_are_distinguished() {
+    let db = Database {
+        storage: salsa::Storage::with_durability_levels(5),
+    };
+    // `total_len` is validated by its durability alone.
+    assert_eq!(validations_after_workspace_edit(db), 1);
+}
+
+#[test]
+fn levels_beyond_the_highest_are_merged() {
+    let db = Database {
+        storage: salsa::Storage::default(),
+    };
+    // By default, both layers count as `Durability::HIGH`, so salsa has to
+    // walk the dependencies of `total_len`.
+    assert_eq!(validations_after_workspace_edit(db), 2);
+}
+
+#[test]
+fn edit_highest_level() {
+    let mut db = Database {
+        storage: salsa::Storage::with_durability_levels(5),
+    };
+    let stdlib = File::builder("fn main() {}".to_string())
+        .durability(STDLIB)
+        .new(&db);
+    assert_eq!(total_len(&db, stdlib), 12);
+
+    stdlib
+        .set_contents(&mut db)
+        .with_durability(STDLIB)
+        .to("".to_string());
+    assert_eq!(total_len(&db, stdlib), 0);
+}
+
+#[test]
+#[should_panic(expected = "between 1 and 256 durability levels")]
+fn no_levels() {
+    salsa::Storage::<Database>::with_durability_levels(0);
+}
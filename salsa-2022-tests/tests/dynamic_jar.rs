@@ -0,0 +1,117 @@
+//! Test jars added to a database at runtime with `Storage::add_jar`,
+//! rather than listed in `#[salsa::db]`.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, line_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("line_count({:?})", file.text(db)));
+    file.text(db).lines().count()
+}
+
+/// A plugin, which the database does not list.
+mod plugin {
+    #[salsa::jar(db = Db, dynamic = crate::Database)]
+    pub(crate) struct Jar(Word, words, word_count);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + crate::Db {}
+
+    impl Db for crate::Database {}
+
+    #[salsa::interned(jar = Jar)]
+    pub(crate) struct Word {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    #[salsa::tracked(jar = Jar, return_ref)]
+    pub(crate) fn words(db: &dyn Db, file: crate::File) -> Vec<Word> {
+        db.push_log(format!("words({:?})", file.text(db)));
+        file.text(db)
+            .split_whitespace()
+            .map(|word| Word::new(db, word.to_string()))
+            .collect()
+    }
+
+    #[salsa::tracked(jar = Jar)]
+    pub(crate) fn word_count(db: &dyn Db, file: crate::File) -> usize {
+        db.push_log("word_count".to_string());
+        words(db, file).len()
+    }
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+impl Database {
+    fn with_plugin() -> Self {
+        let mut db = Database::default();
+        db.storage.add_jar::<plugin::Jar>();
+        db
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::with_plugin();
+    let file = File::new(&db, "a b\nc".to_string());
+    assert_eq!(plugin::word_count(&db, file), 3);
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(plugin::words(&db, file)[2].text(&db), "c");
+    db.assert_logs(expect![[r#"
+        [
+            "word_count",
+            "words(\"a b\\nc\")",
+            "line_count(\"a b\\nc\")",
+        ]"#]]);
+
+    file.set_text(&mut db).to("d e".to_string());
+    assert_eq!(plugin::word_count(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "words(\"d e\")",
+            "word_count",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "the jar `dynamic_jar::plugin::Jar` was not added to the database")]
+fn missing_jar() {
+    let db = Database::default();
+    let file = File::new(&db, "a".to_string());
+    plugin::word_count(&db, file);
+}
+
+#[test]
+#[should_panic(expected = "the jar `dynamic_jar::plugin::Jar` was added twice")]
+fn added_twice() {
+    let mut db = Database::with_plugin();
+    db.storage.add_jar::<plugin::Jar>();
+}
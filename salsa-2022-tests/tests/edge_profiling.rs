@@ -0,0 +1,79 @@
+//! Test `Database::most_expensive_edges`, which finds the dependencies that
+//! made revalidating a query slow, as recorded with edge profiling enabled.
+
+use std::time::Duration;
+
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, hover, fast, slow);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn hover(db: &dyn Db, file: File) -> usize {
+    fast(db, file) + slow(db, file)
+}
+
+#[salsa::tracked]
+fn fast(db: &dyn Db, file: File) -> usize {
+    file.text(db).is_empty() as usize
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn Db, file: File) -> usize {
+    std::thread::sleep(Duration::from_millis(20));
+    file.text(db).lines().count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn slowest_dependency_comes_first() {
+    let mut db = Database::default();
+    db.storage.set_edge_profiling(true);
+    let file = File::new(&db, "a".to_string());
+    assert_eq!(hover(&db, file), 1);
+
+    // Both `fast` and `slow` execute again, but `hover` is still valid.
+    file.set_text(&mut db).to("b".to_string());
+    assert_eq!(hover(&db, file), 1);
+    let root = hover::database_key(&db, file);
+    let edges = db.most_expensive_edges(root, 2);
+    assert_eq!(edges.len(), 2);
+    assert_eq!(edges[0].query, root);
+    assert_eq!(edges[0].input, slow::database_key(&db, file).into());
+    assert!(edges[0].self_duration >= Duration::from_millis(20));
+    assert!(edges[0].duration >= edges[0].self_duration);
+    assert!(edges[1].self_duration < edges[0].self_duration);
+
+    // The edges from `fast` and `slow` to the text are included too.
+    assert_eq!(db.most_expensive_edges(root, 10).len(), 4);
+}
+
+#[test]
+fn nothing_is_recorded_unless_enabled() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a".to_string());
+    assert_eq!(hover(&db, file), 1);
+
+    file.set_text(&mut db).to("b".to_string());
+    assert_eq!(hover(&db, file), 1);
+    let root = hover::database_key(&db, file);
+    assert_eq!(db.most_expensive_edges(root, 10), vec![]);
+}
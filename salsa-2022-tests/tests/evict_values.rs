@@ -0,0 +1,117 @@
+//! Test `Database::evict_values`, which drops memoized values
+//! but keeps what is needed to validate the queries reading them.
+
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, parse, item_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(return_ref)]
+fn parse(db: &dyn Db, file: File) -> Vec<String> {
+    db.push_log(format!("parse({})", file.text(db)));
+    file.text(db).split(',').map(str::to_string).collect()
+}
+
+#[salsa::tracked]
+fn item_count(db: &dyn Db, file: File) -> usize {
+    db.push_log("item_count".to_string());
+    parse(db, file).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn validates_without_recomputing() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a,b".to_string());
+    let other = File::new(&db, "c".to_string());
+    assert_eq!(item_count(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count",
+            "parse(a,b)",
+        ]"#]]);
+
+    let parse_index = parse::database_key(&db, file).ingredient_index();
+    assert_eq!(db.evict_values(parse_index), 1);
+    assert_eq!(db.evict_values(parse_index), 0);
+
+    // `item_count` is still valid, without parsing again.
+    other.set_text(&mut db).to("d".to_string());
+    assert_eq!(item_count(&db, file), 2);
+    db.assert_logs(expect!["[]"]);
+
+    // The value is recomputed once it is needed, and it is backdated,
+    // so `item_count` is still valid.
+    assert_eq!(parse(&db, file), &["a", "b"]);
+    other.set_text(&mut db).to("e".to_string());
+    assert_eq!(item_count(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(a,b)",
+        ]"#]]);
+}
+
+#[test]
+fn recomputed_after_a_change() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a,b".to_string());
+    assert_eq!(item_count(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count",
+            "parse(a,b)",
+        ]"#]]);
+
+    assert_eq!(db.evict_values(parse::database_key(&db, file)), 1);
+    file.set_text(&mut db).to("a,b,c".to_string());
+    assert_eq!(item_count(&db, file), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count",
+            "parse(a,b,c)",
+        ]"#]]);
+}
+
+#[test]
+fn recomputed_in_the_same_revision() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a,b".to_string());
+    assert_eq!(item_count(&db, file), 2);
+    assert_eq!(db.evict_values(parse::database_key(&db, file)), 1);
+    assert_eq!(parse(&db, file), &["a", "b"]);
+    assert_eq!(item_count(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count",
+            "parse(a,b)",
+            "parse(a,b)",
+        ]"#]]);
+}
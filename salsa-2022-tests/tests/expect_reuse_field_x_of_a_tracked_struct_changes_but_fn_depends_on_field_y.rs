@@ -43,7 +43,7 @@ struct MyTracked {
 
 #[salsa::tracked(jar = Jar)]
 fn intermediate_result(db: &dyn Db, input: MyInput) -> MyTracked {
-    MyTracked::new(db, (input.field(db) + 1) / 2, input.field(db) / 2)
+    MyTracked::new(db, input.field(db).div_ceil(2), input.field(db) / 2)
 }
 
 #[salsa::db(Jar)]
@@ -0,0 +1,117 @@
+//! Test `ParallelDatabase::freeze` and `Snapshot::share`, which share a
+//! database that cannot change between threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use salsa::ParallelDatabase;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, double);
+
+trait Db: salsa::DbWithJar<Jar> {
+    fn executed(&self);
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    db.executed();
+    input.field(db) * 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    executions: Arc<AtomicUsize>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn executed(&self) {
+        self.executions.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl ParallelDatabase for Database {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(Database {
+            storage: self.storage.snapshot(),
+            executions: self.executions.clone(),
+        })
+    }
+}
+
+#[test]
+fn read_from_many_threads() {
+    let db = Database::default();
+    let executions = db.executions.clone();
+    let inputs: Vec<MyInput> = (0..100).map(|i| MyInput::new(&db, i)).collect();
+
+    let frozen = db.freeze();
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            let frozen = frozen.clone();
+            let inputs = &inputs;
+            scope.spawn(move || {
+                let sum: u32 = inputs.iter().map(|&input| double(frozen.db(), input)).sum();
+                assert_eq!(sum, 9900);
+            });
+        }
+    });
+
+    // The threads shared the memoized values.
+    assert_eq!(executions.load(Ordering::SeqCst), 100);
+    for &input in &inputs {
+        assert_eq!(double(frozen.db(), input), input.field(frozen.db()) * 2);
+    }
+    assert_eq!(executions.load(Ordering::SeqCst), 100);
+}
+
+#[test]
+fn one_handle_per_thread() {
+    let frozen = Database::default().freeze();
+    assert!(std::ptr::eq(frozen.db(), &**frozen));
+
+    let other = std::thread::scope(|scope| {
+        scope
+            .spawn(|| frozen.db() as *const Database as usize)
+            .join()
+            .unwrap()
+    });
+    assert_ne!(other, frozen.db() as *const Database as usize);
+}
+
+#[test]
+fn share_snapshot() {
+    fn assert_send<T: Send + 'static>(_: &T) {}
+
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_send(&db.snapshot());
+
+    let shared = db.snapshot().share();
+    assert_send(&shared);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            std::thread::spawn(move || double(shared.db(), input))
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+
+    // Once the shared snapshot is dropped, the database can be written again.
+    drop(shared);
+    input.set_field(&mut db).to(2);
+    assert_eq!(double(&db, input), 4);
+    assert_eq!(db.executions.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,178 @@
+//! Test `salsa::fs`, whose files are read again once the events of a
+//! watcher for them are applied.
+
+use std::path::{Path, PathBuf};
+
+use expect_test::expect;
+use salsa::fs::notify::{event, Event, EventKind};
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(line_count);
+
+trait Db: salsa::DbWithJar<Jar> + salsa::fs::Db + HasLogger {}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: salsa::fs::File) -> Option<usize> {
+    db.push_log(format!("line_count({})", file_name(file.path(db))));
+    salsa::fs::read_to_string(db, file)
+        .as_ref()
+        .ok()
+        .map(|text| text.lines().count())
+}
+
+#[salsa::db(salsa::fs::Jar, Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    files: salsa::fs::Files,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl salsa::fs::Db for Database {
+    fn files(&self) -> &salsa::fs::Files {
+        &self.files
+    }
+}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+/// A directory of its own for `test`, emptied.
+fn directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("salsa-fs-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn event(kind: EventKind, path: &Path) -> Event {
+    Event::new(kind).add_path(path.to_path_buf())
+}
+
+#[test]
+fn files_are_read_again_after_events() {
+    let directory = directory("events");
+    let path = directory.join("a.txt");
+    std::fs::write(&path, "1\n2\n").unwrap();
+
+    let mut db = Database::default();
+    let file = db.files.file(&db, &path);
+    assert_eq!(db.files.file(&db, &path), file);
+    assert_eq!(line_count(&db, file), Some(2));
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a.txt)",
+        ]"#]]);
+
+    // The file is not read again until the event is applied.
+    std::fs::write(&path, "1\n2\n3\n").unwrap();
+    assert_eq!(line_count(&db, file), Some(2));
+    let modify = EventKind::Modify(event::ModifyKind::Data(event::DataChange::Content));
+    assert_eq!(salsa::fs::apply_event(&mut db, &event(modify, &path)), 1);
+    assert_eq!(line_count(&db, file), Some(3));
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a.txt)",
+        ]"#]]);
+
+    // Accesses and other paths change nothing.
+    let access = EventKind::Access(event::AccessKind::Any);
+    assert_eq!(salsa::fs::apply_event(&mut db, &event(access, &path)), 0);
+    let other = directory.join("b.txt");
+    assert_eq!(salsa::fs::apply_event(&mut db, &event(modify, &other)), 0);
+    assert_eq!(db.files.get(&other), None);
+    assert_eq!(line_count(&db, file), Some(3));
+    db.assert_logs(expect!["[]"]);
+
+    // A deleted file reads as an error.
+    std::fs::remove_file(&path).unwrap();
+    let remove = EventKind::Remove(event::RemoveKind::File);
+    assert_eq!(salsa::fs::apply_event(&mut db, &event(remove, &path)), 1);
+    assert_eq!(line_count(&db, file), None);
+    assert_eq!(
+        salsa::fs::read_to_string(&db, file),
+        &Err(std::io::ErrorKind::NotFound)
+    );
+}
+
+/// The durability of the input field `version` of each file, from the dump.
+fn version_durabilities(db: &Database) -> Vec<String> {
+    let mut json = Vec::new();
+    salsa::debug::dump(db, &mut json).unwrap();
+    let dump: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    dump["values"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|value| value["key"].as_str().unwrap().starts_with("version("))
+        .map(|value| format!("{} {}", value["key"], value["durability"]))
+        .collect()
+}
+
+#[test]
+fn changes_keep_the_durability_of_the_file() {
+    let directory = directory("durability");
+    let library = directory.join("library.txt");
+    let local = directory.join("local.txt");
+    std::fs::write(&library, "1\n").unwrap();
+    std::fs::write(&local, "1\n").unwrap();
+
+    let mut db = Database::default();
+    db.files
+        .file_with_durability(&db, &library, salsa::Durability::HIGH);
+    db.files.file(&db, &local);
+    expect![[r#"
+        [
+            "\"version(0)\" \"HIGH\"",
+            "\"version(1)\" \"LOW\"",
+        ]
+    "#]]
+    .assert_debug_eq(&version_durabilities(&db));
+
+    let modify = EventKind::Modify(event::ModifyKind::Any);
+    let both = Event::new(modify).add_path(library).add_path(local);
+    assert_eq!(salsa::fs::apply_event(&mut db, &both), 2);
+    expect![[r#"
+        [
+            "\"version(0)\" \"HIGH\"",
+            "\"version(1)\" \"LOW\"",
+        ]
+    "#]]
+    .assert_debug_eq(&version_durabilities(&db));
+}
+
+#[test]
+fn the_watcher_sees_files_being_created() {
+    let directory = directory("created");
+    let path = directory.join("new.txt");
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut db = Database::default();
+    db.files
+        .set_watcher(salsa::fs::notify::recommended_watcher(tx).unwrap());
+    let file = db.files.file(&db, &path);
+    assert_eq!(line_count(&db, file), None);
+
+    // The file did not exist when it was asked for, but its directory is watched.
+    std::fs::write(&path, "1\n").unwrap();
+    let timeout = std::time::Duration::from_secs(10);
+    let mut changed = 0;
+    while changed == 0 {
+        let event = rx.recv_timeout(timeout).unwrap().unwrap();
+        changed = salsa::fs::apply_event(&mut db, &event);
+    }
+    assert_eq!(line_count(&db, file), Some(1));
+}
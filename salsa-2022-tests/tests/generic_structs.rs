@@ -0,0 +1,130 @@
+//! Test tracked and interned structs with type parameters, each instantiation
+//! of which has its own ingredients in the jar.
+
+use salsa::DebugWithDb;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    SourceFile,
+    Spanned<Expr>,
+    Spanned<Stmt>,
+    Name<Expr>,
+    Name<Stmt>,
+    parse,
+);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct SourceFile {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Expr(String);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Stmt(Vec<Spanned<Expr>>);
+
+/// A node of the syntax tree, with the range of the text it was parsed from.
+#[salsa::tracked(serialize)]
+struct Spanned<T: Clone + std::fmt::Debug + Eq + Send + Sync> {
+    #[id]
+    start: usize,
+    end: usize,
+    #[return_ref]
+    node: T,
+}
+
+/// A name, in the namespace of the nodes of type `K`.
+#[salsa::interned(serialize)]
+struct Name<K: Clone + Eq + std::hash::Hash + Send + Sync> {
+    #[return_ref]
+    text: String,
+}
+
+/// Parses a statement of each line, and an expression of each word of the line.
+#[salsa::tracked]
+fn parse(db: &dyn Db, file: SourceFile) -> Vec<Spanned<Stmt>> {
+    let mut start = 0;
+    file.text(db)
+        .lines()
+        .map(|line| {
+            let mut word_start = start;
+            let exprs = line
+                .split(' ')
+                .map(|word| {
+                    let expr = Spanned::new(
+                        db,
+                        word_start,
+                        word_start + word.len(),
+                        Expr(word.to_string()),
+                    );
+                    word_start += word.len() + 1;
+                    expr
+                })
+                .collect();
+            let stmt = Spanned::new(db, start, start + line.len(), Stmt(exprs));
+            start += line.len() + 1;
+            stmt
+        })
+        .collect()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn instantiations_of_tracked_struct() {
+    let db = Database::default();
+    let file = SourceFile::new(&db, "a b\ncd".to_string());
+    let stmts = parse(&db, file);
+    assert_eq!(stmts.len(), 2);
+    assert_eq!((stmts[1].start(&db), stmts[1].end(&db)), (4, 6));
+
+    let exprs = &stmts[0].node(&db).0;
+    let spans: Vec<_> = exprs
+        .iter()
+        .map(|expr| (expr.start(&db), expr.end(&db), expr.node(&db).0.as_str()))
+        .collect();
+    assert_eq!(spans, [(0, 1, "a"), (2, 3, "b")]);
+    assert_eq!(
+        format!("{:?}", exprs[1].debug_all(&db)),
+        r#"Spanned { [salsa id]: 1, start: 2, end: 3, node: Expr("b") }"#
+    );
+}
+
+#[test]
+fn instantiations_of_interned_struct() {
+    let db = Database::default();
+    let expr_name = Name::<Expr>::new(&db, "x".to_string());
+    let stmt_name = Name::<Stmt>::new(&db, "x".to_string());
+    assert_eq!(expr_name, Name::new(&db, "x".to_string()));
+    assert_eq!(stmt_name.text(&db), "x");
+
+    // Each instantiation has its own ingredient, so the same text is interned
+    // in both, and only in the one it was interned in.
+    assert_eq!(Name::<Expr>::get(&db, "y".to_string()), None);
+    let stmt_other = Name::<Stmt>::new(&db, "y".to_string());
+    assert_eq!(Name::<Expr>::get(&db, "y".to_string()), None);
+    assert_eq!(Name::<Stmt>::get(&db, "y".to_string()), Some(stmt_other));
+}
+
+#[test]
+fn serialize_instantiations() {
+    let db = Database::default();
+    let name = Name::<Stmt>::new(&db, "x".to_string());
+    let json = salsa::attach(&db, || serde_json::to_string(&name).unwrap());
+    assert_eq!(json, r#"{"text":"x"}"#);
+    let name2: Name<Stmt> = salsa::attach(&db, || serde_json::from_str(&json).unwrap());
+    assert_eq!(name, name2);
+}
@@ -0,0 +1,106 @@
+//! Test `Database::has_changed_since`, which tells whether a value may have
+//! changed after a revision without returning it.
+
+use salsa::{Database as _, DebugWithDb};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, double, parity);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("double({})", input.a(db)));
+    input.a(db) * 2
+}
+
+#[salsa::tracked]
+fn parity(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("parity({})", input.b(db)));
+    input.b(db) % 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn key_named(db: &Database, name: &str) -> salsa::DatabaseKeyIndex {
+    db.storage
+        .keys()
+        .into_iter()
+        .find(|key| format!("{:?}", key.debug(db)) == name)
+        .unwrap()
+}
+
+#[test]
+fn probe_memoized_values() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+    double(&db, input);
+    parity(&db, input);
+    db.assert_logs(expect![[r#"
+        [
+            "double(1)",
+            "parity(2)",
+        ]"#]]);
+    let double_key = key_named(&db, "double(0)");
+    let parity_key = key_named(&db, "parity(0)");
+
+    let before_edits = db.current_revision();
+    assert!(!db.has_changed_since(double_key, before_edits));
+    assert!(!db.has_changed_since(parity_key, before_edits));
+
+    input.set_a(&mut db).to(2);
+    input.set_b(&mut db).to(4);
+
+    // Finding out re-executes the queries whose inputs changed; `parity` was
+    // backdated, as its value did not change.
+    assert!(db.has_changed_since(double_key, before_edits));
+    assert!(!db.has_changed_since(parity_key, before_edits));
+    db.assert_logs(expect![[r#"
+        [
+            "double(2)",
+            "parity(4)",
+        ]"#]]);
+
+    // The values are then memoized.
+    assert_eq!(double(&db, input), 4);
+    assert_eq!(parity(&db, input), 0);
+    db.assert_logs(expect!["[]"]);
+    assert!(!db.has_changed_since(double_key, db.current_revision()));
+}
+
+#[test]
+fn purged_values_have_changed() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+    double(&db, input);
+    let double_key = key_named(&db, "double(0)");
+    let revision = db.current_revision();
+
+    double::purge(&mut db);
+    assert!(db.has_changed_since(double_key, revision));
+}
@@ -0,0 +1,87 @@
+//! Test that inputs created with `builder` get the requested durabilities.
+
+use salsa::{storage::HasJarsDyn, Durability};
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, path_len, contents_len);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    path: String,
+    contents: String,
+}
+
+#[salsa::tracked]
+fn path_len(db: &dyn Db, file: File) -> usize {
+    file.path(db).len()
+}
+
+#[salsa::tracked]
+fn contents_len(db: &dyn Db, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn durability(db: &Database, key: salsa::DatabaseKeyIndex) -> Durability {
+    db.value_revisions(key).unwrap().durability
+}
+
+#[test]
+fn new_uses_low_durability() {
+    let db = Database::default();
+    let file = File::new(&db, "lib.rs".to_string(), "fn main() {}".to_string());
+    assert_eq!(path_len(&db, file), 6);
+    assert_eq!(
+        durability(&db, path_len::database_key(&db, file)),
+        Durability::LOW
+    );
+}
+
+#[test]
+fn builder_durability() {
+    let db = Database::default();
+    let file = File::builder("lib.rs".to_string(), "fn main() {}".to_string())
+        .durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(path_len(&db, file), 6);
+    assert_eq!(contents_len(&db, file), 12);
+    assert_eq!(
+        durability(&db, path_len::database_key(&db, file)),
+        Durability::HIGH
+    );
+    assert_eq!(
+        durability(&db, contents_len::database_key(&db, file)),
+        Durability::HIGH
+    );
+}
+
+#[test]
+fn builder_field_durability() {
+    let db = Database::default();
+    let file = File::builder("lib.rs".to_string(), "fn main() {}".to_string())
+        .path_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(path_len(&db, file), 6);
+    assert_eq!(contents_len(&db, file), 12);
+    assert_eq!(
+        durability(&db, path_len::database_key(&db, file)),
+        Durability::HIGH
+    );
+    assert_eq!(
+        durability(&db, contents_len::database_key(&db, file)),
+        Durability::LOW
+    );
+}
@@ -0,0 +1,104 @@
+//! Test `#[default]` fields of inputs, which the constructor leaves out.
+
+use salsa::{storage::HasJarsDyn, Durability};
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Settings, MySingleton, summary);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Settings {
+    name: String,
+
+    #[default]
+    verbose: bool,
+
+    #[default]
+    #[return_ref]
+    include_paths: Vec<String>,
+
+    #[default]
+    #[set(set_level_to)]
+    level: u32,
+}
+
+#[salsa::input(singleton)]
+struct MySingleton {
+    #[default]
+    count: u32,
+}
+
+#[salsa::tracked]
+fn summary(db: &dyn Db, settings: Settings) -> String {
+    format!(
+        "{} verbose={} paths={:?} level={}",
+        settings.name(db),
+        settings.verbose(db),
+        settings.include_paths(db),
+        settings.level(db),
+    )
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn constructor_omits_default_fields() {
+    let mut db = Database::default();
+    let settings = Settings::new(&db, "release".to_string());
+    assert_eq!(
+        summary(&db, settings),
+        "release verbose=false paths=[] level=0"
+    );
+
+    settings.set_verbose(&mut db).to(true);
+    settings.set_level_to(&mut db).to(3);
+    assert_eq!(
+        summary(&db, settings),
+        "release verbose=true paths=[] level=3"
+    );
+
+    assert!(settings.set_verbose_to_default(&mut db));
+    assert_eq!(settings.set_level_to_to_default(&mut db), 3);
+    assert_eq!(
+        summary(&db, settings),
+        "release verbose=false paths=[] level=0"
+    );
+
+    assert_eq!(MySingleton::new(&db).count(&db), 0);
+}
+
+#[test]
+fn builder_sets_default_fields() {
+    let db = Database::default();
+    let settings = Settings::builder("debug".to_string())
+        .include_paths(vec!["src".to_string()])
+        .level(2)
+        .verbose_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(
+        summary(&db, settings),
+        "debug verbose=false paths=[\"src\"] level=2"
+    );
+
+    let verbose = db
+        .storage
+        .keys()
+        .into_iter()
+        .find(|&key| format!("{:?}", salsa::DebugWithDb::debug(&key, &db)) == "verbose(0)")
+        .unwrap();
+    assert_eq!(
+        db.value_revisions(verbose).unwrap().durability,
+        Durability::HIGH
+    );
+}
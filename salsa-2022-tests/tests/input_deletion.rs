@@ -0,0 +1,126 @@
+//! Test that deleting an input discards the memos keyed on it
+//! and re-executes the queries that read its fields.
+
+use salsa::{DebugWithDb, Durability};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Workspace, file_length, total_length);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    contents: String,
+}
+
+#[salsa::input]
+struct Workspace {
+    #[return_ref]
+    files: Vec<File>,
+}
+
+#[salsa::tracked]
+fn file_length(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("file_length({:?})", file));
+    file.contents(db).len()
+}
+
+#[salsa::tracked]
+fn total_length(db: &dyn Db, workspace: Workspace) -> usize {
+    db.push_log(format!("total_length({:?})", workspace));
+    workspace
+        .files(db)
+        .iter()
+        .map(|&file| file_length(db, file))
+        .sum()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let salsa::EventKind::DidDiscard { .. } = event.kind {
+            self.push_log(format!("salsa_event({:?})", event.kind.debug(self)));
+        }
+    }
+}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn delete_input() {
+    let mut db = Database::default();
+    let a = File::new(&db, "aaa".to_string());
+    let b = File::new(&db, "bb".to_string());
+    let workspace = Workspace::new(&db, vec![a, b]);
+    assert_eq!(total_length(&db, workspace), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "total_length(Workspace(Id { value: 1 }))",
+            "file_length(File(Id { value: 1 }))",
+            "file_length(File(Id { value: 2 }))",
+        ]"#]]);
+
+    workspace.set_files(&mut db).to(vec![b]);
+    a.delete(&mut db);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidDiscard { key: File(0) })",
+            "salsa_event(DidDiscard { key: file_length(0) })",
+        ]"#]]);
+
+    assert_eq!(total_length(&db, workspace), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "total_length(Workspace(Id { value: 1 }))",
+        ]"#]]);
+
+    // Ids of deleted inputs are not reused.
+    let c = File::new(&db, "c".to_string());
+    assert_ne!(c, a);
+}
+
+#[test]
+#[should_panic(expected = "`contents` read from input Id { value: 1 } after it was deleted")]
+fn read_deleted_input() {
+    let mut db = Database::default();
+    let a = File::new(&db, "aaa".to_string());
+    a.delete(&mut db);
+    a.contents(&db);
+}
+
+#[test]
+#[should_panic(expected = "`contents` read from input Id { value: 1 } after it was deleted")]
+fn delete_durable_input() {
+    let mut db = Database::default();
+    let a = File::new(&db, String::new());
+    a.set_contents(&mut db)
+        .with_durability(Durability::HIGH)
+        .to("aaa".to_string());
+    let workspace = Workspace::new(&db, vec![]);
+    workspace
+        .set_files(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(vec![a]);
+    assert_eq!(total_length(&db, workspace), 3);
+
+    // Only durable inputs were read, but `total_length` still re-executes.
+    a.delete(&mut db);
+    total_length(&db, workspace);
+}
@@ -0,0 +1,104 @@
+//! Test that each field of an input has its own durability, which its
+//! setter can change, and that setting a field invalidates its readers
+//! whatever durabilities are involved.
+
+use salsa::{storage::HasJarsDyn, Database as _, Durability};
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, path_len, contents_len);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    path: String,
+    contents: String,
+}
+
+#[salsa::tracked]
+fn path_len(db: &dyn Db, file: File) -> usize {
+    file.path(db).len()
+}
+
+#[salsa::tracked]
+fn contents_len(db: &dyn Db, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn set_high_durability_field() {
+    let mut db = Database::default();
+    let file = File::builder("lib.rs".to_string(), "".to_string())
+        .path_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(path_len(&db, file), 6);
+
+    file.set_path(&mut db)
+        .with_durability(Durability::HIGH)
+        .to("main.rs".to_string());
+    assert_eq!(path_len(&db, file), 7);
+}
+
+#[test]
+fn lower_durability_of_field() {
+    let mut db = Database::default();
+    let file = File::builder("lib.rs".to_string(), "".to_string())
+        .path_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(path_len(&db, file), 6);
+
+    // The old value was read at high durability, so lowering the durability
+    // has to invalidate its readers too.
+    file.set_path(&mut db).to("main.rs".to_string());
+    assert_eq!(path_len(&db, file), 7);
+
+    file.set_path(&mut db).to("mod.rs".to_string());
+    assert_eq!(path_len(&db, file), 6);
+}
+
+#[test]
+fn durability_is_per_field() {
+    let mut db = Database::default();
+    let file = File::builder("lib.rs".to_string(), "fn main() {}".to_string())
+        .path_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(path_len(&db, file), 6);
+    assert_eq!(contents_len(&db, file), 12);
+    assert_eq!(
+        db.value_revisions(path_len::database_key(&db, file))
+            .unwrap()
+            .durability,
+        Durability::HIGH
+    );
+
+    // Changing the volatile contents only re-executes `contents_len`.
+    db.reset_query_statistics();
+    file.set_contents(&mut db).to("".to_string());
+    assert_eq!(path_len(&db, file), 6);
+    assert_eq!(contents_len(&db, file), 0);
+    assert_eq!(db.query_statistics().total().executions, 1);
+
+    // Once lowered, the durability of the path is that of the contents.
+    file.set_path(&mut db)
+        .with_durability(Durability::LOW)
+        .to("main.rs".to_string());
+    assert_eq!(path_len(&db, file), 7);
+    assert_eq!(
+        db.value_revisions(path_len::database_key(&db, file))
+            .unwrap()
+            .durability,
+        Durability::LOW
+    );
+}
@@ -0,0 +1,101 @@
+//! Test that the queries reading an entry of an `#[salsa::input_map]`
+//! are only invalidated by changes to that entry.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Files, line_count, file_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input_map]
+struct Files(String, String);
+
+#[salsa::tracked(intern_args)]
+fn line_count(db: &dyn Db, path: String) -> Option<usize> {
+    db.push_log(format!("line_count({path})"));
+    Files::get(db, &path).map(|contents| contents.lines().count())
+}
+
+#[salsa::tracked]
+fn file_count(db: &dyn Db) -> usize {
+    db.push_log("file_count".to_string());
+    Files::keys(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn entries_are_tracked_separately() {
+    let mut db = Database::default();
+    Files::insert(&mut db, "a".to_string(), "1".to_string());
+    Files::insert(&mut db, "b".to_string(), "1\n2".to_string());
+    assert_eq!(line_count(&db, "a".to_string()), Some(1));
+    assert_eq!(line_count(&db, "b".to_string()), Some(2));
+    assert_eq!(line_count(&db, "c".to_string()), None);
+    assert_eq!(file_count(&db), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "line_count(b)",
+            "line_count(c)",
+            "file_count",
+        ]"#]]);
+
+    // Changing a value only invalidates the readers of that entry.
+    let old = Files::insert(&mut db, "b".to_string(), "1\n2\n3".to_string());
+    assert_eq!(old.as_deref(), Some("1\n2"));
+    assert_eq!(line_count(&db, "a".to_string()), Some(1));
+    assert_eq!(line_count(&db, "b".to_string()), Some(3));
+    assert_eq!(line_count(&db, "c".to_string()), None);
+    assert_eq!(file_count(&db), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(b)",
+        ]"#]]);
+
+    // Inserting a key invalidates those who found it missing, and the readers of the keys.
+    Files::insert(&mut db, "c".to_string(), "".to_string());
+    assert_eq!(line_count(&db, "a".to_string()), Some(1));
+    assert_eq!(line_count(&db, "c".to_string()), Some(0));
+    assert_eq!(file_count(&db), 3);
+    assert_eq!(Files::keys(&db), vec!["a", "b", "c"]);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(c)",
+            "file_count",
+        ]"#]]);
+
+    assert_eq!(
+        Files::remove(&mut db, &"a".to_string()).as_deref(),
+        Some("1")
+    );
+    assert_eq!(Files::remove(&mut db, &"a".to_string()), None);
+    assert_eq!(line_count(&db, "a".to_string()), None);
+    assert_eq!(line_count(&db, "b".to_string()), Some(3));
+    assert_eq!(file_count(&db), 2);
+    assert_eq!(Files::keys(&db), vec!["b", "c"]);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "file_count",
+        ]"#]]);
+}
@@ -0,0 +1,104 @@
+//! Test `salsa::inspect::Inspector`, which serves the contents of a database over HTTP.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use salsa::inspect::Inspector;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, double);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+/// Returns the status line and the body of the response to `GET path`.
+fn get(addr: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+#[test]
+fn serves_the_last_update() {
+    let mut db = Database::default();
+    let inspector = Inspector::serve("127.0.0.1:0").unwrap();
+    let addr = inspector.local_addr();
+
+    let (status, page) = get(addr, "/");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(page.contains("/dump.json"));
+
+    let input = MyInput::new(&db, 1);
+    double(&db, input);
+    inspector.update(&db);
+    let (_, json) = get(addr, "/dump.json");
+    let dump: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(dump["revision"], 1);
+    assert_eq!(dump["values"][1]["key"], "double(0)");
+    assert_eq!(dump["values"][1]["origin"]["inputs"][0], "field(0)");
+
+    // The page shows the database as of the last update.
+    input.set_field(&mut db).to(2);
+    let revision = || {
+        let (_, json) = get(addr, "/dump.json");
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()["revision"].clone()
+    };
+    assert_eq!(revision(), 1);
+    inspector.update(&db);
+    assert_eq!(revision(), 2);
+
+    let (status, _) = get(addr, "/nothing");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+    drop(inspector);
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn stalled_clients_time_out() {
+    let inspector = Inspector::serve("127.0.0.1:0").unwrap();
+    let addr = inspector.local_addr();
+
+    // A client that never sends its request only holds up the others for a while.
+    let _stalled = TcpStream::connect(addr).unwrap();
+    let (status, _) = get(addr, "/");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
+
+#[test]
+fn endless_requests_are_cut_off() {
+    let inspector = Inspector::serve("127.0.0.1:0").unwrap();
+    let addr = inspector.local_addr();
+
+    // The server stops reading a request whose headers never end, and closes it.
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET / HTTP/1.1\r\n").unwrap();
+    let header = format!("X-Padding: {}\r\n", "a".repeat(1000));
+    while stream.write_all(header.as_bytes()).is_ok() {}
+
+    let (status, _) = get(addr, "/");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+}
@@ -0,0 +1,84 @@
+//! Test the generated `get` method of interned structs, which looks up
+//! a struct without interning it.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Name, is_defined);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn is_defined(db: &dyn Db, input: MyInput) -> bool {
+    db.push_log(format!("is_defined({:?})", input.text(db)));
+    Name::get(db, input.text(db).clone()).is_some()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn get_does_not_intern() {
+    let db = Database::default();
+    assert_eq!(Name::get(&db, "a".to_string()), None);
+    assert_eq!(Name::get(&db, "a".to_string()), None);
+
+    let a = Name::new(&db, "a".to_string());
+    assert_eq!(Name::get(&db, "a".to_string()), Some(a));
+    assert_eq!(Name::get(&db, "b".to_string()), None);
+}
+
+#[test]
+fn missing_names_are_looked_up_again() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "a".to_string());
+    let other = MyInput::new(&db, "b".to_string());
+    assert!(!is_defined(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "is_defined(\"a\")",
+        ]"#]]);
+
+    // Interning the name changes nothing else, but the query must see it
+    // in the next revision.
+    Name::new(&db, "a".to_string());
+    other.set_text(&mut db).to("c".to_string());
+    assert!(is_defined(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "is_defined(\"a\")",
+        ]"#]]);
+
+    // Once found, the query depends on the interned name only.
+    other.set_text(&mut db).to("d".to_string());
+    assert!(is_defined(&db, input));
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,108 @@
+//! Test the `validate` option of interned structs, which checks the fields
+//! before interning them, in the generated `try_new` method.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Name, parse_name);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum NameError {
+    Empty,
+    TooLong(usize),
+}
+
+fn check_name(text: &str) -> Result<(), NameError> {
+    match text.len() {
+        0 => Err(NameError::Empty),
+        len if len > 8 => Err(NameError::TooLong(len)),
+        _ => Ok(()),
+    }
+}
+
+#[salsa::interned(validate(check_name -> NameError))]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn parse_name(db: &dyn Db, input: MyInput) -> Result<Name, NameError> {
+    db.push_log(format!("parse_name({:?})", input.text(db)));
+    Name::try_new(db, input.text(db).trim().to_string())
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn valid_fields_are_interned() {
+    let db = Database::default();
+    let a = Name::try_new(&db, "a".to_string()).unwrap();
+    assert_eq!(a.text(&db), "a");
+    assert_eq!(Name::try_new(&db, "a".to_string()), Ok(a));
+    assert_eq!(Name::new(&db, "a".to_string()), a);
+}
+
+#[test]
+fn invalid_fields_are_not_interned() {
+    let db = Database::default();
+    assert_eq!(Name::try_new(&db, String::new()), Err(NameError::Empty));
+    assert_eq!(
+        Name::try_new(&db, "a_long_name".to_string()),
+        Err(NameError::TooLong(11))
+    );
+    assert_eq!(Name::get(&db, String::new()), None);
+    assert_eq!(Name::get(&db, "a_long_name".to_string()), None);
+}
+
+#[test]
+#[should_panic(expected = "`check_name` rejected the fields of a `Name`: TooLong(11)")]
+fn new_panics_on_invalid_fields() {
+    let db = Database::default();
+    Name::new(&db, "a_long_name".to_string());
+}
+
+#[test]
+fn errors_are_memoized() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, " ".to_string());
+    assert_eq!(parse_name(&db, input), Err(NameError::Empty));
+    assert_eq!(parse_name(&db, input), Err(NameError::Empty));
+    db.assert_logs(expect![[r#"
+        [
+            "parse_name(\" \")",
+        ]"#]]);
+
+    input.set_text(&mut db).to(" b ".to_string());
+    let b = parse_name(&db, input).unwrap();
+    assert_eq!(b.text(&db), "b");
+    db.assert_logs(expect![[r#"
+        [
+            "parse_name(\" b \")",
+        ]"#]]);
+}
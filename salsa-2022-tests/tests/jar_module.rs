@@ -0,0 +1,154 @@
+//! Test `#[salsa::jar]` on a module, which generates the jar struct
+//! from the salsa items of the module.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+mod ir {
+    use salsa_2022_tests::HasLogger;
+
+    pub trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+    #[salsa::input]
+    pub struct Source {
+        #[return_ref]
+        pub text: String,
+    }
+
+    #[salsa::interned]
+    pub struct Name {
+        #[return_ref]
+        pub text: String,
+    }
+
+    #[salsa::tracked]
+    pub struct Item {
+        #[id]
+        pub name: Name,
+        pub len: usize,
+    }
+
+    #[salsa::accumulator]
+    pub struct Diagnostic(String);
+
+    #[salsa::tracked(return_ref)]
+    pub fn items(db: &dyn Db, source: Source) -> Vec<Item> {
+        db.push_log(format!("items({:?})", source.text(db)));
+        source
+            .text(db)
+            .split_whitespace()
+            .map(|word| {
+                if word.len() > 3 {
+                    Diagnostic::push(db, format!("`{}` is long", word));
+                }
+                Item::new(db, Name::new(db, word.to_string()), word.len())
+            })
+            .collect()
+    }
+
+    #[salsa::tracked]
+    impl Source {
+        #[salsa::tracked]
+        pub fn total_len(self, db: &dyn Db) -> usize {
+            db.push_log("total_len".to_string());
+            items(db, self).iter().map(|item| item.len(db)).sum()
+        }
+    }
+}
+
+#[salsa::db(ir::Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl ir::Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn items_of_the_module_are_registered() {
+    let mut db = Database::default();
+    let source = ir::Source::new(&db, "a bcde fg".to_string());
+    assert_eq!(source.total_len(&db), 7);
+    let names: Vec<_> = ir::items(&db, source)
+        .iter()
+        .map(|item| item.name(&db).text(&db).clone())
+        .collect();
+    assert_eq!(names, ["a", "bcde", "fg"]);
+    expect![[r#"
+        [
+            "`bcde` is long",
+        ]
+    "#]]
+    .assert_debug_eq(&ir::items::accumulated::<ir::Diagnostic>(&db, source));
+    db.assert_logs(expect![[r#"
+        [
+            "total_len",
+            "items(\"a bcde fg\")",
+        ]"#]]);
+
+    source.set_text(&mut db).to("hij k".to_string());
+    assert_eq!(source.total_len(&db), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "items(\"hij k\")",
+            "total_len",
+        ]"#]]);
+}
+
+/// A module with private items, whose database trait is declared outside.
+#[salsa::jar(db = super::PrivateDb)]
+mod private {
+    #[salsa::input]
+    pub(super) struct Counter {
+        pub(super) count: u32,
+    }
+
+    #[salsa::tracked]
+    pub(super) fn double(db: &dyn super::PrivateDb, counter: Counter) -> u32 {
+        counter.count(db) * 2
+    }
+
+    // Not in this jar.
+    #[salsa::tracked(jar = crate::OtherJar)]
+    pub(super) fn triple(db: &dyn crate::OtherDb, counter: Counter) -> u32 {
+        counter.count(db) * 3
+    }
+}
+
+trait PrivateDb: salsa::DbWithJar<private::Jar> {}
+
+#[salsa::jar(db = OtherDb)]
+struct OtherJar(private::triple);
+
+trait OtherDb: salsa::DbWithJar<OtherJar> + PrivateDb {}
+
+#[salsa::db(private::Jar, OtherJar)]
+#[derive(Default)]
+struct PrivateDatabase {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for PrivateDatabase {}
+
+impl PrivateDb for PrivateDatabase {}
+
+impl OtherDb for PrivateDatabase {}
+
+#[test]
+fn items_naming_other_jars_are_left_out() {
+    let db = PrivateDatabase::default();
+    let counter = private::Counter::new(&db, 7);
+    assert_eq!(private::double(&db, counter), 14);
+    assert_eq!(private::triple(&db, counter), 21);
+}
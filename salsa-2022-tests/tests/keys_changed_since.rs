@@ -0,0 +1,85 @@
+//! Test `Database::keys_changed_since`, listing the input fields and
+//! memoized values that changed after a given revision.
+
+use salsa::{Database as _, DebugWithDb};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, double, parity);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    input.a(db) * 2
+}
+
+#[salsa::tracked]
+fn parity(db: &dyn Db, input: MyInput) -> u32 {
+    input.b(db) % 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn changed_since(db: &Database, revision: salsa::Revision) -> Vec<String> {
+    let mut keys: Vec<String> = db
+        .keys_changed_since(revision)
+        .into_iter()
+        .map(|key| format!("{:?}", key.debug(db)))
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+    assert_eq!(double(&db, input), 2);
+    assert_eq!(parity(&db, input), 0);
+
+    let before_edits = db.current_revision();
+    assert!(changed_since(&db, before_edits).is_empty());
+
+    input.set_a(&mut db).to(2);
+    input.set_b(&mut db).to(4);
+
+    // The memoized values are only reported once they have been fetched.
+    let expected = expect![[r#"
+        [
+            "a(0)",
+            "b(0)",
+        ]
+    "#]];
+    expected.assert_debug_eq(&changed_since(&db, before_edits));
+
+    // `parity` was backdated: its value is unchanged.
+    assert_eq!(double(&db, input), 4);
+    assert_eq!(parity(&db, input), 0);
+    let expected = expect![[r#"
+        [
+            "a(0)",
+            "b(0)",
+            "double(0)",
+        ]
+    "#]];
+    expected.assert_debug_eq(&changed_since(&db, before_edits));
+
+    assert!(changed_since(&db, db.current_revision()).is_empty());
+}
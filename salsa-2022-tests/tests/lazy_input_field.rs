@@ -0,0 +1,85 @@
+//! Test that a `#[lazy]` input field is loaded the first time it is read,
+//! and behaves like any other input field afterwards.
+
+use std::collections::HashMap;
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, line_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    fn read_file(&self, path: &str) -> String;
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    path: String,
+
+    #[return_ref]
+    #[lazy(load_contents)]
+    contents: String,
+}
+
+fn load_contents(db: &dyn Db, file: File) -> String {
+    db.read_file(file.path(db))
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("line_count({})", file.path(db)));
+    file.contents(db).lines().count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    disk: HashMap<String, String>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn read_file(&self, path: &str) -> String {
+        self.push_log(format!("read_file({})", path));
+        self.disk[path].clone()
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn load_on_first_read() {
+    let mut db = Database::default();
+    db.disk.insert("a".to_string(), "1\n2\n".to_string());
+    db.disk.insert("b".to_string(), "1\n".to_string());
+    let a = File::new(&db, "a".to_string());
+    let _b = File::new(&db, "b".to_string());
+
+    // Only `a` is ever read, and only once.
+    assert_eq!(line_count(&db, a), 2);
+    assert_eq!(a.contents(&db), "1\n2\n");
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "read_file(a)",
+        ]"#]]);
+
+    // A lazy field can be set like any other field.
+    a.set_contents(&mut db).to("1\n2\n3\n".to_string());
+    assert_eq!(line_count(&db, a), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+        ]"#]]);
+}
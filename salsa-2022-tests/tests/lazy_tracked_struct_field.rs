@@ -0,0 +1,109 @@
+//! Test that a `#[lazy]` field of a tracked struct is computed the first time
+//! it is read, and recomputed only when what it read changed.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Module, Function, lower);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Module {
+    /// The name and text of each function.
+    #[return_ref]
+    functions: Vec<(String, String)>,
+}
+
+#[salsa::tracked]
+struct Function {
+    #[id]
+    #[return_ref]
+    name: String,
+
+    #[return_ref]
+    text: String,
+
+    #[lazy(compute_body_len)]
+    body_len: usize,
+}
+
+#[salsa::tracked]
+fn lower(db: &dyn Db, module: Module) -> Vec<Function> {
+    module
+        .functions(db)
+        .iter()
+        .map(|(name, text)| Function::new(db, name.clone(), text.clone()))
+        .collect()
+}
+
+/// Stands in for an expensive computation.
+fn compute_body_len(db: &dyn Db, function: Function) -> usize {
+    db.push_log(format!("body_len({})", function.name(db)));
+    function.text(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn functions(texts: &[(&str, &str)]) -> Vec<(String, String)> {
+    texts
+        .iter()
+        .map(|(name, text)| (name.to_string(), text.to_string()))
+        .collect()
+}
+
+#[test]
+fn computed_when_read() {
+    let mut db = Database::default();
+    let module = Module::new(&db, functions(&[("f", "1"), ("g", "22")]));
+    let lowered = lower(&db, module);
+    db.assert_logs(expect!["[]"]);
+
+    assert_eq!(lowered[0].body_len(&db), 1);
+    assert_eq!(lowered[0].body_len(&db), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "body_len(f)",
+        ]"#]]);
+
+    // Only the text of `g` changed.
+    module
+        .set_functions(&mut db)
+        .to(functions(&[("f", "1"), ("g", "333")]));
+    let lowered = lower(&db, module);
+    assert_eq!(lowered[0].body_len(&db), 1);
+    assert_eq!(lowered[1].body_len(&db), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "body_len(g)",
+        ]"#]]);
+
+    module
+        .set_functions(&mut db)
+        .to(functions(&[("f", "4444"), ("g", "333")]));
+    let lowered = lower(&db, module);
+    assert_eq!(lowered[0].body_len(&db), 4);
+    assert_eq!(lowered[1].body_len(&db), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "body_len(f)",
+        ]"#]]);
+}
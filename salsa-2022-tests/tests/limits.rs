@@ -0,0 +1,135 @@
+//! Test `Storage::set_limits`: queries exceeding the limits of the database
+//! unwind with `LimitExceeded`, naming the offending query.
+
+use std::panic::AssertUnwindSafe;
+
+use salsa::limits::Limit;
+use salsa::{LimitExceeded, Limits};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Level, Item, count_down, items);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    n: u32,
+}
+
+#[salsa::interned]
+struct Level {
+    n: u32,
+}
+
+#[salsa::tracked]
+struct Item {
+    n: u32,
+}
+
+#[salsa::tracked]
+fn count_down(db: &dyn Db, level: Level) -> u32 {
+    match level.n(db) {
+        0 => 0,
+        n => count_down(db, Level::new(db, n - 1)) + 1,
+    }
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Db, input: MyInput) -> Vec<Item> {
+    (0..input.n(db)).map(|n| Item::new(db, n)).collect()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn query_depth() {
+    let db = Database::default();
+    db.storage.set_limits(Limits {
+        max_query_depth: Some(10),
+        ..Limits::default()
+    });
+    let deep = Level::new(&db, 20);
+    let exceeded = LimitExceeded::catch(AssertUnwindSafe(|| count_down(&db, deep))).unwrap_err();
+    assert!(matches!(
+        exceeded.limit(),
+        Limit::QueryDepth { max: 10, .. }
+    ));
+    // The eleventh query, counting down from 10.
+    let query = exceeded.query().unwrap();
+    assert_eq!(query, count_down::database_key(&db, Level::new(&db, 10)));
+    assert_eq!(
+        exceeded.to_string(),
+        format!("more than 10 nested queries (in query {query:?})")
+    );
+
+    assert_eq!(count_down(&db, Level::new(&db, 5)), 5);
+
+    // The queries that unwound are not poisoned: they execute again in the same revision.
+    db.storage.set_limits(Limits::default());
+    assert_eq!(count_down(&db, deep), 20);
+}
+
+#[test]
+fn interned_keys() {
+    let db = Database::default();
+    db.storage.set_limits(Limits {
+        max_interned_keys: Some(3),
+        ..Limits::default()
+    });
+    for n in 0..3 {
+        Level::new(&db, n);
+    }
+    // Interning a value again is fine.
+    Level::new(&db, 0);
+    let exceeded = LimitExceeded::catch(AssertUnwindSafe(|| Level::new(&db, 3))).unwrap_err();
+    assert!(matches!(
+        exceeded.limit(),
+        Limit::InternedKeys { max: 3, .. }
+    ));
+    assert_eq!(exceeded.query(), None);
+    assert_eq!(count_down(&db, Level::new(&db, 2)), 2);
+
+    // From a query, the query is reported.
+    db.storage.set_limits(Limits {
+        max_interned_keys: Some(4),
+        ..Limits::default()
+    });
+    let five = Level::new(&db, 5);
+    let exceeded = LimitExceeded::catch(AssertUnwindSafe(|| count_down(&db, five))).unwrap_err();
+    assert_eq!(exceeded.query(), Some(count_down::database_key(&db, five)));
+}
+
+#[test]
+fn tracked_structs_per_revision() {
+    let mut db = Database::default();
+    db.storage.set_limits(Limits {
+        max_tracked_structs: Some(5),
+        ..Limits::default()
+    });
+    let input = MyInput::new(&db, 5);
+    assert_eq!(items(&db, input).len(), 5);
+
+    // The count starts over in each revision, and the structs created
+    // again are reused, so only the new ones count.
+    input.set_n(&mut db).to(8);
+    assert_eq!(items(&db, input).len(), 8);
+    input.set_n(&mut db).to(14);
+    let exceeded = LimitExceeded::catch(AssertUnwindSafe(|| items(&db, input))).unwrap_err();
+    assert!(matches!(
+        exceeded.limit(),
+        Limit::TrackedStructs { max: 5, .. }
+    ));
+    assert_eq!(exceeded.query(), Some(items::database_key(&db, input)));
+
+    input.set_n(&mut db).to(4);
+    assert_eq!(items(&db, input).len(), 4);
+}
@@ -19,7 +19,7 @@ trait Db: salsa::DbWithJar<Jar> + HasLogger {}
 struct HotPotato(u32);
 
 thread_local! {
-    static N_POTATOES: AtomicUsize = AtomicUsize::new(0)
+    static N_POTATOES: AtomicUsize = const { AtomicUsize::new(0) }
 }
 
 impl HotPotato {
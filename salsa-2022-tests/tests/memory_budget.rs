@@ -0,0 +1,118 @@
+//! Test `set_memory_budget`, which evicts the values used least recently
+//! once the values of tracked functions take too much memory.
+
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, contents, header);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    name: u32,
+}
+
+fn bytes_heap_size(bytes: &Vec<u8>) -> usize {
+    bytes.capacity()
+}
+
+#[salsa::tracked(return_ref, heap_size = bytes_heap_size)]
+fn contents(db: &dyn Db, file: File) -> Vec<u8> {
+    db.push_log(format!("contents({})", file.name(db)));
+    vec![file.name(db) as u8; 10]
+}
+
+// Has no `heap_size`, so it is never evicted.
+#[salsa::tracked]
+fn header(db: &dyn Db, file: File) -> u8 {
+    db.push_log(format!("header({})", file.name(db)));
+    contents(db, file)[0]
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn evicts_least_recently_used() {
+    let mut db = Database::default();
+    assert_eq!(db.set_memory_budget(Some(25)), 0);
+    let unrelated = File::new(&db, 0);
+    let files: Vec<File> = (1..=3).map(|name| File::new(&db, name)).collect();
+
+    // Uses the files in turn, each in a revision of its own,
+    // using the first file again along with the last.
+    assert_eq!(header(&db, files[0]), 1);
+    unrelated.set_name(&mut db).to(10);
+    assert_eq!(header(&db, files[1]), 2);
+    unrelated.set_name(&mut db).to(20);
+    assert_eq!(header(&db, files[2]), 3);
+    assert_eq!(contents(&db, files[0])[0], 1);
+    db.assert_logs(expect![[r#"
+        [
+            "header(1)",
+            "contents(1)",
+            "header(2)",
+            "contents(2)",
+            "header(3)",
+            "contents(3)",
+        ]"#]]);
+
+    // The next revision is over budget, so the contents of the second file are evicted.
+    unrelated.set_name(&mut db).to(30);
+    assert_eq!(contents(&db, files[0])[0], 1);
+    assert_eq!(contents(&db, files[2])[0], 3);
+    assert_eq!(header(&db, files[1]), 2);
+    db.assert_logs(expect![[r#"
+        []"#]]);
+    assert_eq!(contents(&db, files[1])[0], 2);
+    db.assert_logs(expect![[r#"
+        [
+            "contents(2)",
+        ]"#]]);
+}
+
+#[test]
+fn evicts_when_budget_is_set() {
+    let mut db = Database::default();
+    let files: Vec<File> = (1..=3).map(|name| File::new(&db, name)).collect();
+    for &file in &files {
+        assert_eq!(contents(&db, file).len(), 10);
+    }
+    db.assert_logs(expect![[r#"
+        [
+            "contents(1)",
+            "contents(2)",
+            "contents(3)",
+        ]"#]]);
+
+    let total_heap_bytes = |db: &Database| db.storage_usage().total().heap_bytes;
+    assert_eq!(total_heap_bytes(&db), 30);
+    assert_eq!(db.set_memory_budget(Some(10)), 2);
+    assert_eq!(total_heap_bytes(&db), 10);
+
+    // Lifting the budget evicts nothing more.
+    assert_eq!(db.set_memory_budget(None), 0);
+    for &file in &files {
+        assert_eq!(contents(&db, file).len(), 10);
+    }
+    assert_eq!(total_heap_bytes(&db), 30);
+}
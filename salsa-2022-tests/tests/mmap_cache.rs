@@ -0,0 +1,133 @@
+//! Test `MappedCache`, a cache backend in a memory-mapped file, written by one
+//! database and read by others (as if in other processes).
+#![cfg(unix)]
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use expect_test::expect;
+use salsa::cache::CacheBackend;
+use salsa::mmap::MappedCache;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, words);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+fn text_fingerprint(db: &dyn Db, file: File) -> salsa::cache::Fingerprint {
+    salsa::cache::Fingerprint::of(file.text(db))
+}
+
+#[salsa::tracked(cache = text_fingerprint)]
+fn words(db: &dyn Db, file: File) -> Vec<String> {
+    db.push_log(format!("words({:?})", file.text(db)));
+    file.text(db)
+        .split_whitespace()
+        .map(|word| word.to_string())
+        .collect()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+/// A path in the temporary directory, removed when dropped.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(name: &str) -> Self {
+        let name = format!("salsa-mmap-{}-{name}", std::process::id());
+        TempPath(std::env::temp_dir().join(name))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn database(cache: MappedCache) -> Database {
+    let db = Database::default();
+    db.storage.set_cache_backend(Some(Arc::new(cache)));
+    db
+}
+
+#[test]
+fn readers_find_the_values_of_the_writer() {
+    let path = TempPath::new("readers");
+    let mut writer = database(MappedCache::create(&path.0).unwrap());
+    let reader_cache = MappedCache::open(&path.0).unwrap();
+    assert!(reader_cache.is_empty());
+    let mut reader = database(reader_cache);
+
+    // The reader opened the file before the writer computed the value, whose
+    // record is past its mapping.
+    let long_text = "word ".repeat(2000);
+    let file = File::new(&writer, long_text.clone());
+    assert_eq!(words(&writer, file).len(), 2000);
+    writer.assert_logs_len(1);
+
+    let file = File::new(&reader, long_text);
+    assert_eq!(words(&reader, file).len(), 2000);
+    reader.assert_logs(expect!["[]"]);
+
+    // The values the reader computes are not written.
+    file.set_text(&mut reader).to("a b".to_string());
+    assert_eq!(words(&reader, file), ["a", "b"]);
+    reader.assert_logs(expect![[r#"
+        [
+            "words(\"a b\")",
+        ]"#]]);
+    let file = File::new(&writer, "a b".to_string());
+    assert_eq!(words(&writer, file), ["a", "b"]);
+    writer.assert_logs(expect![[r#"
+        [
+            "words(\"a b\")",
+        ]"#]]);
+}
+
+#[test]
+fn values_are_kept_in_the_file() {
+    let path = TempPath::new("kept");
+    let writer = MappedCache::create(&path.0).unwrap();
+    let fingerprint = salsa::cache::Fingerprint(1);
+    writer.put("words", fingerprint, b"[\"a\"]".to_vec());
+    writer.put("other", fingerprint, b"[]".to_vec());
+    drop(writer);
+
+    // A reader opened after the writer is gone sees the same values.
+    let reader = MappedCache::open(&path.0).unwrap();
+    assert_eq!(reader.len(), 2);
+    assert_eq!(reader.get("words", fingerprint), Some(b"[\"a\"]".to_vec()));
+    assert_eq!(reader.get("words", salsa::cache::Fingerprint(2)), None);
+}
+
+#[test]
+fn other_files_are_rejected() {
+    let path = TempPath::new("other");
+    std::fs::write(&path.0, "not a cache").unwrap();
+    let error = MappedCache::open(&path.0).err().unwrap();
+    expect!["not a file written by `MappedCache::create`"].assert_eq(&error.to_string());
+}
@@ -0,0 +1,152 @@
+//! Test `Storage::mount_base`, which uses the persisted data of a frozen
+//! database, with other jars, as the starting point of another database.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+/// The library whose analysis is shipped prebuilt.
+mod std_lib {
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(Module, line_count);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + salsa_2022_tests::HasLogger {}
+
+    #[salsa::input(jar = Jar, persist)]
+    pub(crate) struct Module {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    #[salsa::tracked(jar = Jar, persist)]
+    pub(crate) fn line_count(db: &dyn Db, module: Module) -> usize {
+        db.push_log(format!("line_count({:?})", module.text(db)));
+        module.text(db).lines().count()
+    }
+}
+
+/// The tool, whose ingredients come before those of `std_lib`.
+mod app {
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(File, uses);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + crate::std_lib::Db {}
+
+    #[salsa::input(jar = Jar, persist)]
+    pub(crate) struct File {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    #[salsa::tracked(jar = Jar)]
+    pub(crate) fn uses(db: &dyn Db, file: File, module: crate::std_lib::Module) -> usize {
+        db.push_log(format!("uses({:?})", file.text(db)));
+        file.text(db).lines().count() + crate::std_lib::line_count(db, module)
+    }
+}
+
+#[salsa::db(std_lib::Jar)]
+#[derive(Default)]
+struct BaseDatabase {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for BaseDatabase {}
+
+impl std_lib::Db for BaseDatabase {}
+
+impl HasLogger for BaseDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::db(app::Jar, std_lib::Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl std_lib::Db for Database {}
+
+impl app::Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn build_base() -> (salsa::persist::PersistedDatabase, std_lib::Module) {
+    let mut db = BaseDatabase::default();
+    let module = std_lib::Module::new(&db, "a\nb".to_string());
+    assert_eq!(std_lib::line_count(&db, module), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(\"a\\nb\")",
+        ]"#]]);
+    (db.storage.persist().unwrap(), module)
+}
+
+fn mounted(base: &salsa::persist::PersistedDatabase) -> Database {
+    let mut db = Database::default();
+    db.storage.mount_base(base).unwrap();
+    db
+}
+
+#[test]
+fn memos_of_the_base_are_used() {
+    let (base, module) = build_base();
+    let mut db = mounted(&base);
+    assert_eq!(std_lib::line_count(&db, module), 2);
+    db.assert_logs(expect!["[]"]);
+
+    let file = app::File::new(&db, "x".to_string());
+    assert_eq!(app::uses(&db, file, module), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "uses(\"x\")",
+        ]"#]]);
+
+    // Changing an input of the base recomputes what depends on it.
+    module.set_text(&mut db).to("a\nb\nc".to_string());
+    assert_eq!(app::uses(&db, file, module), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(\"a\\nb\\nc\")",
+            "uses(\"x\")",
+        ]"#]]);
+}
+
+#[test]
+fn base_is_not_modified() {
+    let (base, module) = build_base();
+    let mut db = mounted(&base);
+    module.set_text(&mut db).to("a".to_string());
+    assert_eq!(std_lib::line_count(&db, module), 1);
+
+    let mut db = mounted(&base);
+    assert_eq!(module.text(&db), "a\nb");
+    assert_eq!(std_lib::line_count(&db, module), 2);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn missing_ingredient() {
+    let db = Database::default();
+    app::File::new(&db, "x".to_string());
+    let persisted = db.storage.persist().unwrap();
+    let mut base_db = BaseDatabase::default();
+    let err = base_db.storage.mount_base(&persisted).unwrap_err();
+    expect![[r#"
+        MissingIngredient {
+            jar: "mount_base::app::Jar",
+            debug_name: "text",
+        }
+    "#]]
+    .assert_debug_eq(&err);
+}
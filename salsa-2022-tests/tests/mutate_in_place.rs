@@ -1,5 +1,5 @@
 //! Test that a setting a field on a `#[salsa::input]`
-//! overwrites and returns the old value.
+//! overwrites and returns the old value, and that a field can be updated in place.
 
 use salsa_2022_tests::{HasLogger, Logger};
 
@@ -50,3 +50,14 @@ fn execute() {
     // Check if the stored String is the one we expected
     assert_eq!(input.field(&db), "Hello World!");
 }
+
+#[test]
+fn update() {
+    let mut db = Database::default();
+
+    let input = MyInput::new(&db, "Hello".to_string());
+    input
+        .set_field(&mut db)
+        .update(|field| field.push_str(" World!"));
+    assert_eq!(input.field(&db), "Hello World!");
+}
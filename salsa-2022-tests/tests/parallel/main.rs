@@ -1,7 +1,18 @@
 mod setup;
 
+mod parallel_anchored;
+mod parallel_cancellation;
+mod parallel_cancellation_requested;
 mod parallel_cycle_all_recover;
 mod parallel_cycle_mid_recover;
 mod parallel_cycle_none_recover;
 mod parallel_cycle_one_recover;
+mod parallel_dispatch;
+mod parallel_map;
+mod parallel_maybe_changed_after;
+mod parallel_panic;
+mod parallel_validate_all;
+mod parallel_verify;
+mod parallel_write_pending;
+mod parallel_write_policy;
 mod signal;
@@ -0,0 +1,72 @@
+//! Test `Snapshot::anchored`: the queries of an anchored snapshot run to
+//! completion while a write waits for them, until the anchor times out.
+
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Cancelled, Database as _, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a1);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a1(db: &dyn Db, input: MyInput) -> i32 {
+    input.field(db) * 10
+}
+
+#[test]
+fn write_waits_for_anchored_snapshot() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let anchored = db.snapshot().anchored(Duration::from_secs(60));
+        let observer = db.snapshot();
+        move || {
+            // Wait until the write is pending, as the other snapshot sees.
+            while !observer.is_write_pending() {
+                std::thread::yield_now();
+            }
+            drop(observer);
+            assert!(!anchored.is_write_pending());
+            a1(&*anchored, input)
+        }
+    });
+
+    // Setting the field blocks until the anchored snapshot is dropped.
+    input.set_field(&mut db).to(2);
+    assert_eq!(thread_a.join().unwrap(), 10);
+    assert_eq!(a1(&db, input), 20);
+}
+
+#[test]
+fn anchor_times_out() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let anchored = db.snapshot().anchored(Duration::ZERO);
+        move || {
+            while !anchored.is_write_pending() {
+                std::thread::yield_now();
+            }
+            Cancelled::catch(AssertUnwindSafe(|| a1(&*anchored, input)))
+        }
+    });
+
+    input.set_field(&mut db).to(2);
+    let cancelled = thread_a.join().unwrap().unwrap_err();
+    assert_eq!(cancelled.reason(), "pending write");
+    assert_eq!(a1(&db, input), 20);
+}
@@ -0,0 +1,62 @@
+//! Test that a write cancels queries running on a snapshot,
+//! and that they are recomputed in the new revision.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Cancelled, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a1, dummy);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a1(db: &dyn Db, input: MyInput) -> i32 {
+    if input.field(db) == 0 {
+        // Tell the main thread we have started, then spin until it cancels us.
+        db.signal(1);
+        loop {
+            db.unwind_if_cancelled();
+            std::thread::yield_now();
+        }
+    }
+    dummy(db, input)
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn dummy(db: &dyn Db, input: MyInput) -> i32 {
+    input.field(db) * 10
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 0);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || a1(&*db, input)
+    });
+
+    // Setting the field blocks until the snapshot held by thread A is dropped,
+    // which only happens once it observes the cancellation.
+    db.wait_for(1);
+    input.set_field(&mut db).to(1);
+
+    match thread_a.join() {
+        Ok(value) => panic!("expected cancellation, got {:?}", value),
+        Err(payload) => match payload.downcast::<Cancelled>() {
+            Ok(_) => {}
+            Err(payload) => std::panic::resume_unwind(payload),
+        },
+    }
+
+    assert_eq!(a1(&db, input), 10);
+}
@@ -0,0 +1,61 @@
+//! Test that a cancellation handle cancels the queries of its snapshot only,
+//! and that `Cancelled::catch` reports why.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Cancelled, Database as _, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a1);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a1(db: &dyn Db, input: MyInput) -> i32 {
+    if input.field(db) == 0 {
+        // Tell the main thread we have started, then spin until it cancels us.
+        db.signal(1);
+        loop {
+            db.unwind_if_cancelled();
+            std::thread::yield_now();
+        }
+    }
+    input.field(db) * 10
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 0);
+
+    let snapshot = db.snapshot();
+    let handle = snapshot.cancellation_handle();
+    let thread_a = std::thread::spawn(move || {
+        Cancelled::catch(std::panic::AssertUnwindSafe(|| a1(&*snapshot, input)))
+    });
+
+    db.wait_for(1);
+    assert!(!db.cancellation_handle().is_cancelled());
+    handle.cancel();
+
+    match thread_a.join().unwrap() {
+        Err(cancelled @ Cancelled::Requested { .. }) => {
+            assert_eq!(
+                cancelled.to_string(),
+                "cancelled because of cancellation request"
+            );
+        }
+        result => panic!("expected a requested cancellation, got {:?}", result),
+    }
+
+    // The main handle was not cancelled, and can write now that the snapshot is gone.
+    input.set_field(&mut db).to(1);
+    assert_eq!(a1(&db, input), 10);
+}
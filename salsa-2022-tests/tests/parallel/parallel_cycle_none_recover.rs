@@ -69,6 +69,12 @@ fn execute() {
             ]
         "#]];
         expected.assert_debug_eq(&c.all_participants(&db));
+
+        // Each participant is executed by the thread that called it first.
+        let runtimes: Vec<_> = c.participant_keys_and_runtimes().map(|(_, r)| r).collect();
+        assert_eq!(runtimes.len(), 2);
+        assert_ne!(runtimes[0], runtimes[1]);
+        assert_eq!(c.runtimes(), runtimes);
     } else {
         panic!("b failed in an unexpected way: {:?}", err_b);
     }
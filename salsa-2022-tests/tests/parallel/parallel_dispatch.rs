@@ -0,0 +1,118 @@
+//! Test `salsa::dispatch`: reads run on worker threads, a newer read with the
+//! same key cancels the older one, and writes cancel the reads in flight.
+//! With the `single-threaded` feature, a read that spins until it is cancelled
+//! would never return, as it runs on the thread that would cancel it: those
+//! tests are skipped.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::dispatch::Dispatcher;
+#[cfg(not(feature = "single-threaded"))]
+use salsa::{Cancelled, Database as _};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a1);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a1(db: &dyn Db, input: MyInput) -> i32 {
+    if input.field(db) == 0 {
+        // Tell the main thread we have started, then spin until cancelled.
+        db.signal(1);
+        loop {
+            db.unwind_if_cancelled();
+            std::thread::yield_now();
+        }
+    }
+    input.field(db) * 10
+}
+
+#[test]
+fn reads_run_on_snapshots() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 1);
+    let dispatcher: Dispatcher<Database, ()> = Dispatcher::new(db, 2);
+    let requests: Vec<_> = (0..4)
+        .map(|_| dispatcher.read(None, move |db| a1(db, input)))
+        .collect();
+    for request in requests {
+        assert_eq!(request.wait().unwrap(), 10);
+    }
+}
+
+#[test]
+#[cfg(not(feature = "single-threaded"))]
+fn newer_reads_supersede_older_ones() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 0);
+    let dispatcher = Dispatcher::new(db, 1);
+    let old = dispatcher.read(Some("a1"), move |db| a1(db, input));
+    dispatcher.db().wait_for(1);
+
+    // The other key is left alone.
+    let other = dispatcher.read(Some("other"), |_| 22);
+    let new = dispatcher.read(Some("a1"), |_| 11);
+    assert!(matches!(old.wait(), Err(Cancelled::Requested { .. })));
+    assert_eq!(new.wait().unwrap(), 11);
+    assert_eq!(other.wait().unwrap(), 22);
+}
+
+#[test]
+#[cfg(not(feature = "single-threaded"))]
+fn writes_cancel_reads_in_flight() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 0);
+    let mut dispatcher = Dispatcher::new(db, 1);
+    let running = dispatcher.read(None, move |db| a1(db, input));
+    // Waits for the running read, on the only worker.
+    let waiting = dispatcher.read(None, move |db| a1(db, input));
+    dispatcher.db().wait_for(1);
+
+    dispatcher.write(|db| input.set_field(db).to(2));
+    assert!(matches!(
+        running.wait(),
+        Err(Cancelled::PendingWrite { .. })
+    ));
+    assert!(matches!(
+        waiting.wait(),
+        Err(Cancelled::PendingWrite { .. })
+    ));
+
+    // Reads see the write.
+    let read = dispatcher.read(Some(()), move |db| a1(db, input));
+    assert_eq!(read.wait().unwrap(), 20);
+    assert!(!dispatcher.db().is_write_pending());
+}
+
+#[test]
+#[cfg(not(feature = "single-threaded"))]
+fn requests_can_be_cancelled() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 0);
+    let dispatcher: Dispatcher<Database, ()> = Dispatcher::new(db, 1);
+    let request = dispatcher.read(None, move |db| a1(db, input));
+    dispatcher.db().wait_for(1);
+    assert!(!request.is_done());
+    request.cancel();
+    assert!(matches!(request.wait(), Err(Cancelled::Requested { .. })));
+}
+
+#[test]
+#[cfg(feature = "single-threaded")]
+fn reads_run_on_the_calling_thread() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 1);
+    let dispatcher: Dispatcher<Database, ()> = Dispatcher::new(db, 2);
+    let caller = std::thread::current().id();
+    let request = dispatcher.read(None, move |db| (a1(db, input), std::thread::current().id()));
+    assert!(request.is_done());
+    assert_eq!(request.wait().unwrap(), (10, caller));
+}
@@ -0,0 +1,81 @@
+//! Test that `salsa::par_map` computes results on other threads
+//! and records the dependencies of the calling query.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Send {
+    fn fork_db(&self) -> Box<dyn Db>;
+
+    fn threads(&self) -> &Mutex<HashSet<ThreadId>>;
+}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(List, Item, total, square);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct List {
+    items: Vec<Item>,
+}
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct Item {
+    value: u32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn total(db: &dyn Db, list: List) -> u32 {
+    // The closure coerces `db` to the lifetime `square` expects.
+    #[allow(clippy::redundant_closure)]
+    salsa::par_map(db, list.items(db), fork, |db, item| square(db, item))
+        .into_iter()
+        .sum()
+}
+
+fn fork<'db>(db: &(dyn Db + 'db)) -> Box<dyn Db + 'db> {
+    db.fork_db()
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn square(db: &dyn Db, item: Item) -> u32 {
+    db.threads()
+        .lock()
+        .unwrap()
+        .insert(std::thread::current().id());
+    item.value(db) * item.value(db)
+}
+
+impl Db for Database {
+    fn fork_db(&self) -> Box<dyn Db> {
+        Box::new(self.fork())
+    }
+
+    fn threads(&self) -> &Mutex<HashSet<ThreadId>> {
+        &self.knobs().threads
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let items: Vec<Item> = (1..=8).map(|value| Item::new(&db, value)).collect();
+    let list = List::new(&db, items.clone());
+
+    assert_eq!(total(&db, list), 204);
+    let threads = db.threads().lock().unwrap().clone();
+    if cfg!(feature = "single-threaded") {
+        assert_eq!(threads.len(), 1);
+        assert!(threads.contains(&std::thread::current().id()));
+    } else {
+        assert!(!threads.is_empty());
+        assert!(!threads.contains(&std::thread::current().id()));
+    }
+
+    // `total` depends on each `square`, even though they were computed elsewhere.
+    items[1].set_value(&mut db).to(3);
+    assert_eq!(total(&db, list), 209);
+}
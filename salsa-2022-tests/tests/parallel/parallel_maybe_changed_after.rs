@@ -0,0 +1,76 @@
+//! Test that a thread validating a query that another thread is re-executing
+//! blocks until it completes, and then reuses its result; the blocking is
+//! counted in the query statistics.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Database as _, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, inner, outer_a, outer_b);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn inner(db: &dyn Db, input: MyInput) -> i32 {
+    if input.field(db) == 2 {
+        // Tell thread B that we are executing, and wait until it blocks on us.
+        db.signal(1);
+        db.wait_for(2);
+    }
+    input.field(db) * 10
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn outer_a(db: &dyn Db, input: MyInput) -> i32 {
+    inner(db, input) + 1
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn outer_b(db: &dyn Db, input: MyInput) -> i32 {
+    inner(db, input) + 2
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(outer_a(&db, input), 11);
+    assert_eq!(outer_b(&db, input), 12);
+
+    // Thread A re-executes `inner` while validating `outer_a`; thread B validates
+    // `outer_b` meanwhile, and so must wait for `inner`.
+    input.set_field(&mut db).to(2);
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || outer_a(&*db, input)
+    });
+    let thread_b = std::thread::spawn({
+        let db = db.snapshot();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.set(2);
+            outer_b(&*db, input)
+        }
+    });
+    assert_eq!(thread_a.join().unwrap(), 21);
+    assert_eq!(thread_b.join().unwrap(), 22);
+
+    let inner_key = inner::database_key(&db, input);
+    let statistics = db.query_statistics();
+    assert_eq!(statistics.get(inner_key.ingredient_index()).executions, 2);
+
+    // Thread B blocked once, on `inner`.
+    let blocking = statistics.blocking(inner_key);
+    assert_eq!(blocking.blocks, 1);
+    assert!(blocking.wait_time > std::time::Duration::ZERO);
+    assert_eq!(statistics.total_blocking(), blocking);
+    assert_eq!(statistics.most_blocked(3), [(inner_key, blocking)]);
+}
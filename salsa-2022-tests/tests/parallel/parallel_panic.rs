@@ -0,0 +1,68 @@
+//! Test that a query panicking on one thread makes the threads waiting
+//! for it panic with `QueryPanicked`, and that it is not executed again
+//! until the next revision.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Database as _, ParallelDatabase, QueryPanicked};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a(db: &dyn Db, input: MyInput) -> i32 {
+    if input.field(db) < 0 {
+        // Tell thread B that we are executing, and wait until it blocks on us.
+        db.signal(1);
+        db.wait_for(2);
+        panic!("negative field");
+    }
+    input.field(db)
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, -1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || a(&*db, input)
+    });
+    let thread_b = std::thread::spawn({
+        let db = db.snapshot();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.set(2);
+            a(&*db, input)
+        }
+    });
+
+    // Thread A sees its own panic, thread B a `QueryPanicked`.
+    let err_a = thread_a.join().unwrap_err();
+    assert_eq!(err_a.downcast_ref::<&str>(), Some(&"negative field"));
+    let err_b = thread_b.join().unwrap_err();
+    let panicked = err_b.downcast_ref::<QueryPanicked>().unwrap();
+    assert_eq!(panicked.database_key(), a::database_key(&db, input));
+    assert_eq!(panicked.message(), Some("negative field"));
+
+    // The panic is remembered for the rest of the revision.
+    let panicked =
+        QueryPanicked::catch(std::panic::AssertUnwindSafe(|| a(&db, input))).unwrap_err();
+    assert_eq!(panicked.message(), Some("negative field"));
+    let a_index = a::database_key(&db, input).ingredient_index();
+    assert_eq!(db.query_statistics().get(a_index).executions, 1);
+
+    // In a new revision, the query is executed again.
+    input.set_field(&mut db).to(1);
+    assert_eq!(a(&db, input), 1);
+}
@@ -0,0 +1,85 @@
+//! Test `Snapshot::validate_all`, which verifies the memos of the database on
+//! worker threads ahead of the queries reading them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Database as _, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, MyList, double, total, triple);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: u32,
+}
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyList {
+    #[return_ref]
+    inputs: Vec<MyInput>,
+}
+
+/// How many times `double` was executed.
+static DOUBLE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn double(db: &dyn Db, input: MyInput) -> u32 {
+    DOUBLE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    input.field(db) * 2
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn total(db: &dyn Db, list: MyList) -> u32 {
+    list.inputs(db).iter().map(|&input| double(db, input)).sum()
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn triple(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db) * 3
+}
+
+#[test]
+fn memos_are_verified_ahead_of_reads() {
+    let mut db = Database::default();
+    let inputs: Vec<_> = (0..8).map(|i| MyInput::new(&db, i)).collect();
+    let list = MyList::new(&db, inputs.clone());
+    assert_eq!(total(&db, list), 56);
+
+    inputs[3].set_field(&mut db).to(10);
+    let executions = DOUBLE_EXECUTIONS.load(Ordering::SeqCst);
+    // `total` and the 8 `double`s.
+    assert_eq!(db.snapshot().validate_all(4).unwrap(), 9);
+    assert_eq!(DOUBLE_EXECUTIONS.load(Ordering::SeqCst), executions + 1);
+
+    // All memos are up to date already.
+    let current = db.current_revision();
+    assert_eq!(total::memo_info(&db, list).unwrap().verified_at, current);
+    for &input in &inputs {
+        assert_eq!(double::memo_info(&db, input).unwrap().verified_at, current);
+    }
+    assert_eq!(total(&db, list), 70);
+    assert_eq!(DOUBLE_EXECUTIONS.load(Ordering::SeqCst), executions + 1);
+}
+
+#[test]
+fn cancelled_validation_stops() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(triple(&db, input), 3);
+
+    input.set_field(&mut db).to(2);
+    let snapshot = db.snapshot();
+    snapshot.cancellation_handle().cancel();
+    let cancelled = snapshot.validate_all(2).unwrap_err();
+    assert_eq!(cancelled.reason(), "cancellation request");
+    assert_ne!(
+        triple::memo_info(&db, input).unwrap().verified_at,
+        db.current_revision()
+    );
+}
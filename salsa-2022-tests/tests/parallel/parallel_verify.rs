@@ -0,0 +1,161 @@
+//! Test `Storage::set_parallel_verification`, which verifies the inputs of
+//! memos on other threads.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> {
+    fn threads(&self) -> &Mutex<HashSet<ThreadId>>;
+}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(
+    List,
+    Item,
+    Lists,
+    grand_total,
+    total,
+    square,
+    checked_total,
+    is_checked,
+    checked,
+);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct List {
+    items: Vec<Item>,
+    checked: bool,
+}
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct Item {
+    value: u32,
+}
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct Lists {
+    lists: Vec<List>,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn grand_total(db: &dyn Db, lists: Lists) -> u32 {
+    lists
+        .lists(db)
+        .into_iter()
+        .map(|list| total(db, list))
+        .sum()
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn total(db: &dyn Db, list: List) -> u32 {
+    list.items(db)
+        .into_iter()
+        .map(|item| square(db, item))
+        .sum()
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn square(db: &dyn Db, item: Item) -> u32 {
+    db.threads()
+        .lock()
+        .unwrap()
+        .insert(std::thread::current().id());
+    item.value(db) * item.value(db)
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn checked_total(db: &dyn Db, list: List) -> u32 {
+    if !is_checked(db, list) {
+        return 0;
+    }
+    list.items(db)
+        .into_iter()
+        .map(|item| checked(db, item))
+        .sum()
+}
+
+/// Slow, so that the other threads verify the inputs after it before it is verified.
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn is_checked(db: &dyn Db, list: List) -> bool {
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    list.checked(db)
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn checked(db: &dyn Db, item: Item) -> u32 {
+    assert_ne!(item.value(db), 0, "unchecked item");
+    item.value(db)
+}
+
+impl Db for Database {
+    fn threads(&self) -> &Mutex<HashSet<ThreadId>> {
+        &self.knobs().threads
+    }
+}
+
+fn setup(db: &mut Database, checked: bool) -> (List, Vec<Item>) {
+    db.set_parallel_verification(4);
+    let items: Vec<Item> = (1..=8).map(|value| Item::new(db, value)).collect();
+    (List::new(db, items.clone(), checked), items)
+}
+
+#[test]
+fn inputs_verified_on_other_threads() {
+    let mut db = Database::default();
+    let (list, items) = setup(&mut db, true);
+    assert_eq!(total(&db, list), 204);
+    db.threads().lock().unwrap().clear();
+
+    items[5].set_value(&mut db).to(1);
+    assert_eq!(total(&db, list), 169);
+    let threads = db.threads().lock().unwrap().clone();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(
+        threads.contains(&std::thread::current().id()),
+        cfg!(feature = "single-threaded")
+    );
+}
+
+#[test]
+fn changed_input_comes_first() {
+    let mut db = Database::default();
+    let (list, items) = setup(&mut db, true);
+    assert_eq!(checked_total(&db, list), 36);
+
+    // A thread verifies `checked(items[3])`, which panics, but `checked_total`
+    // does not read it anymore since `is_checked`, its first input, changed.
+    list.set_checked(&mut db).to(false);
+    items[3].set_value(&mut db).to(0);
+    assert_eq!(checked_total(&db, list), 0);
+}
+
+#[test]
+fn workers_are_reused_and_do_not_nest() {
+    let mut db = Database::default();
+    db.set_parallel_verification(4);
+    let items: Vec<Item> = (1..=64).map(|value| Item::new(&db, value)).collect();
+    let lists: Vec<List> = items
+        .chunks(8)
+        .map(|chunk| List::new(&db, chunk.to_vec(), true))
+        .collect();
+    let lists = Lists::new(&db, lists);
+    grand_total(&db, lists);
+    db.threads().lock().unwrap().clear();
+
+    // Each edit makes workers verify the wide `total` memos, which they verify
+    // on their own thread, always on the same 4 workers.
+    for (edit, item) in items.iter().step_by(9).enumerate() {
+        item.set_value(&mut db).to(100 + edit as u32);
+        grand_total(&db, lists);
+    }
+    let threads = db.threads().lock().unwrap().clone();
+    assert!(threads.len() <= 4, "{} threads", threads.len());
+    assert_eq!(
+        threads.contains(&std::thread::current().id()),
+        cfg!(feature = "single-threaded")
+    );
+}
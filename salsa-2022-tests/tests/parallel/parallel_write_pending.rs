@@ -0,0 +1,53 @@
+//! Test that a query on a snapshot can see that a write is pending
+//! and return early, letting the write proceed.
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Database as _, ParallelDatabase};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, a1);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn a1(db: &dyn Db, input: MyInput) -> i32 {
+    let field = input.field(db);
+    if field == 0 {
+        // Tell the main thread we have started, then work until it wants to write.
+        db.signal(1);
+        while !db.is_write_pending() {
+            std::thread::yield_now();
+        }
+        db.report_untracked_read();
+        return -1;
+    }
+    field * 10
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 0);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || a1(&*db, input)
+    });
+
+    assert!(!db.is_write_pending());
+    // Setting the field blocks until the snapshot held by thread A is dropped.
+    db.wait_for(1);
+    input.set_field(&mut db).to(1);
+    assert_eq!(thread_a.join().unwrap(), -1);
+    assert!(!db.is_write_pending());
+
+    assert_eq!(a1(&db, input), 10);
+}
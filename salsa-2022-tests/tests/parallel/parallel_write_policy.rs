@@ -0,0 +1,103 @@
+//! Test that the write policy decides whether a write cancels the queries
+//! running on a snapshot, or waits for them to complete.
+
+use std::time::{Duration, Instant};
+
+use crate::setup::Database;
+use crate::setup::Knobs;
+use salsa::{Cancelled, ParallelDatabase, WritePolicy};
+
+pub(crate) trait Db: salsa::DbWithJar<Jar> + Knobs {}
+
+impl<T: salsa::DbWithJar<Jar> + Knobs> Db for T {}
+
+#[salsa::jar(db = Db)]
+pub(crate) struct Jar(MyInput, slow, endless);
+
+#[salsa::input(jar = Jar)]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+/// Tells the main thread it has started, then takes a while to complete.
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn slow(db: &dyn Db, input: MyInput) -> i32 {
+    db.signal(1);
+    for _ in 0..20 {
+        db.unwind_if_cancelled();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    input.field(db) * 10
+}
+
+/// Tells the main thread it has started, then spins until it is cancelled.
+#[salsa::tracked(jar = Jar)]
+pub(crate) fn endless(db: &dyn Db, _input: MyInput) -> i32 {
+    db.signal(1);
+    loop {
+        db.unwind_if_cancelled();
+        std::thread::yield_now();
+    }
+}
+
+#[test]
+fn fair_write_waits_for_queries() {
+    let mut db = Database::default();
+    db.set_write_policy(WritePolicy::Fair);
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || slow(&*db, input)
+    });
+
+    // The query completes with the old value, then the write happens.
+    db.wait_for(1);
+    input.set_field(&mut db).to(2);
+    assert_eq!(thread_a.join().unwrap(), 10);
+    assert_eq!(slow(&db, input), 20);
+}
+
+#[test]
+fn read_batching_write_cancels_after_max_latency() {
+    let mut db = Database::default();
+    let max_latency = Duration::from_millis(50);
+    db.set_write_policy(WritePolicy::ReadBatching { max_latency });
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || endless(&*db, input)
+    });
+
+    db.wait_for(1);
+    let start = Instant::now();
+    input.set_field(&mut db).to(2);
+    assert!(start.elapsed() >= max_latency);
+
+    match thread_a.join() {
+        Ok(value) => panic!("expected cancellation, got {:?}", value),
+        Err(payload) => match payload.downcast::<Cancelled>() {
+            Ok(_) => {}
+            Err(payload) => std::panic::resume_unwind(payload),
+        },
+    }
+}
+
+#[test]
+fn read_batching_write_lets_quick_queries_complete() {
+    let mut db = Database::default();
+    db.set_write_policy(WritePolicy::ReadBatching {
+        max_latency: Duration::from_secs(60),
+    });
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.snapshot();
+        move || slow(&*db, input)
+    });
+
+    db.wait_for(1);
+    input.set_field(&mut db).to(2);
+    assert_eq!(thread_a.join().unwrap(), 10);
+}
@@ -1,4 +1,9 @@
-use std::{cell::Cell, sync::Arc};
+use std::{
+    cell::Cell,
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    thread::ThreadId,
+};
 
 use crate::signal::Signal;
 
@@ -23,9 +28,23 @@ pub(crate) struct KnobsStruct {
 
     /// When this database is about to block, send a signal.
     pub(crate) signal_on_will_block: Cell<usize>,
+
+    /// The threads on which `parallel_map::square` has executed.
+    pub(crate) threads: Arc<Mutex<HashSet<ThreadId>>>,
 }
 
 #[salsa::db(
+    crate::parallel_anchored::Jar,
+    crate::parallel_cancellation::Jar,
+    crate::parallel_cancellation_requested::Jar,
+    crate::parallel_dispatch::Jar,
+    crate::parallel_map::Jar,
+    crate::parallel_maybe_changed_after::Jar,
+    crate::parallel_verify::Jar,
+    crate::parallel_panic::Jar,
+    crate::parallel_validate_all::Jar,
+    crate::parallel_write_pending::Jar,
+    crate::parallel_write_policy::Jar,
     crate::parallel_cycle_one_recover::Jar,
     crate::parallel_cycle_none_recover::Jar,
     crate::parallel_cycle_mid_recover::Jar,
@@ -45,6 +64,26 @@ impl salsa::Database for Database {
     }
 }
 
+impl Database {
+    /// Creates a handle that can be used on another thread while a query is in progress.
+    pub(crate) fn fork(&self) -> Database {
+        Database {
+            storage: self.storage.fork(),
+            knobs: self.knobs.clone(),
+        }
+    }
+
+    /// Makes this database verify memos with at least `min_inputs` inputs on 4 threads.
+    pub(crate) fn set_parallel_verification(&mut self, min_inputs: usize) {
+        self.storage
+            .set_parallel_verification(4, min_inputs, Database::fork);
+    }
+
+    pub(crate) fn set_write_policy(&mut self, policy: salsa::WritePolicy) {
+        self.storage.set_write_policy(policy);
+    }
+}
+
 impl salsa::ParallelDatabase for Database {
     fn snapshot(&self) -> salsa::Snapshot<Self> {
         salsa::Snapshot::new(Database {
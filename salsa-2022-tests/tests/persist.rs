@@ -0,0 +1,187 @@
+//! Test that the contents of `persist` ingredients can be
+//! serialized and loaded into a fresh database.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    MyInput,
+    MyInterned,
+    MyTracked,
+    double,
+    interned_len,
+    tracked_sum,
+    not_persisted,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input(persist)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned(persist)]
+struct MyInterned {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(persist)]
+struct MyTracked {
+    value: u32,
+}
+
+#[salsa::tracked(persist)]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("double({})", input.field(db)));
+    input.field(db) * 2
+}
+
+#[salsa::tracked(persist)]
+fn interned_len(db: &dyn Db, interned: MyInterned) -> usize {
+    db.push_log(format!("interned_len({})", interned.text(db)));
+    interned.text(db).len()
+}
+
+#[salsa::tracked(persist)]
+fn tracked_sum(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("tracked_sum({})", input.field(db)));
+    let tracked = MyTracked::new(db, input.field(db) + 1);
+    tracked.value(db) + double(db, input)
+}
+
+#[salsa::tracked]
+fn not_persisted(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("not_persisted({})", input.field(db)));
+    input.field(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn to_json(db: &Database) -> Vec<u8> {
+    let mut bytes = vec![];
+    db.serialize(&mut serde_json::Serializer::new(&mut bytes))
+        .unwrap();
+    bytes
+}
+
+fn from_json(bytes: &[u8]) -> Database {
+    let mut db = Database::default();
+    db.deserialize(&mut serde_json::Deserializer::from_slice(bytes))
+        .unwrap();
+    db
+}
+
+#[test]
+fn memos_are_reused_after_restore() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(double(&db, input), 44);
+    assert_eq!(tracked_sum(&db, input), 67);
+    assert_eq!(not_persisted(&db, input), 22);
+    db.assert_logs(expect![[r#"
+        [
+            "double(22)",
+            "tracked_sum(22)",
+            "not_persisted(22)",
+        ]"#]]);
+
+    let mut db = from_json(&to_json(&db));
+
+    // The input keeps its id, and the memos that depend on it are still valid.
+    assert_eq!(input.field(&db), 22);
+    assert_eq!(double(&db, input), 44);
+    assert_eq!(tracked_sum(&db, input), 67);
+    assert_eq!(not_persisted(&db, input), 22);
+    db.assert_logs(expect![[r#"
+        [
+            "not_persisted(22)",
+        ]"#]]);
+
+    // Changing the input invalidates the restored memos as usual.
+    input.set_field(&mut db).to(10);
+    assert_eq!(tracked_sum(&db, input), 31);
+    db.assert_logs(expect![[r#"
+        [
+            "tracked_sum(10)",
+            "double(10)",
+        ]"#]]);
+}
+
+#[test]
+fn interned_ids_are_stable() {
+    let mut db = Database::default();
+    let a = MyInterned::new(&db, "a".to_string());
+    let bc = MyInterned::new(&db, "bc".to_string());
+    assert_eq!(interned_len(&db, bc), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "interned_len(bc)",
+        ]"#]]);
+
+    let mut db = from_json(&to_json(&db));
+    assert_eq!(MyInterned::new(&db, "a".to_string()), a);
+    assert_eq!(MyInterned::new(&db, "bc".to_string()), bc);
+    assert_eq!(interned_len(&db, bc), 2);
+    assert_ne!(MyInterned::new(&db, "d".to_string()), a);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn new_inputs_after_restore_get_fresh_ids() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    let db = from_json(&to_json(&db));
+    let new_input = MyInput::new(&db, 2);
+    assert_ne!(input, new_input);
+    assert_eq!(input.field(&db), 1);
+    assert_eq!(new_input.field(&db), 2);
+}
+
+#[test]
+fn mismatched_ingredients_are_detected_before_restoring_any() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(double(&db, input), 44);
+
+    // Only the last ingredient does not match.
+    let json = String::from_utf8(to_json(&db)).unwrap();
+    let (head, tail) = json.rsplit_once(r#""debug_name":""#).unwrap();
+    let (_, tail) = tail.split_once('"').unwrap();
+    let json = format!(r#"{head}"debug_name":"renamed"{tail}"#);
+
+    let mut db = Database::default();
+    let fresh_input = MyInput::new(&db, 1);
+    let error = db
+        .deserialize(&mut serde_json::Deserializer::from_str(&json))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("persisted data for `renamed`"),
+        "{error}"
+    );
+
+    // Nothing was restored.
+    assert_eq!(fresh_input, input);
+    assert_eq!(fresh_input.field(&db), 1);
+    assert_eq!(double(&db, fresh_input), 2);
+}
@@ -0,0 +1,155 @@
+//! Test the generated `pin` and `unpin` functions of tracked functions,
+//! which exempt a memo from eviction.
+
+use salsa::{Database as _, Durability};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, parse, item_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(return_ref, lru = 2)]
+fn parse(db: &dyn Db, file: File) -> Vec<String> {
+    db.push_log(format!("parse({})", file.text(db)));
+    file.text(db).split(',').map(str::to_string).collect()
+}
+
+#[salsa::tracked]
+fn item_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("item_count({})", file.text(db)));
+    parse(db, file).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn lru_keeps_pinned_memos() {
+    let mut db = Database::default();
+    let files: Vec<File> = ["a", "b", "c"]
+        .into_iter()
+        .map(|text| File::new(&db, text.to_string()))
+        .collect();
+    let parse_all = |db: &Database| {
+        for &file in &files {
+            parse(db, file);
+        }
+    };
+    parse::pin(&db, files[0]);
+    parse_all(&db);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(a)",
+            "parse(b)",
+            "parse(c)",
+        ]"#]]);
+
+    // The pinned memo is kept, and leaves the capacity of the LRU to the others.
+    db.synthetic_write(Durability::LOW);
+    parse_all(&db);
+    db.assert_logs(expect!["[]"]);
+
+    // Once unpinned, three memos don't fit in the LRU.
+    parse::unpin(&db, files[0]);
+    db.synthetic_write(Durability::LOW);
+    parse_all(&db);
+    db.synthetic_write(Durability::LOW);
+    parse_all(&db);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(b)",
+            "parse(c)",
+            "parse(a)",
+            "parse(b)",
+            "parse(c)",
+        ]"#]]);
+}
+
+#[test]
+fn evict_values_skips_pinned_memos() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+    parse(&db, a);
+    parse(&db, b);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(a)",
+            "parse(b)",
+        ]"#]]);
+
+    parse::pin(&db, a);
+    let parse_index = parse::database_key(&db, a).ingredient_index();
+    assert_eq!(db.evict_values(parse_index), 1);
+    assert_eq!(db.evict_values(parse::database_key(&db, a)), 0);
+    parse(&db, a);
+    parse(&db, b);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(b)",
+        ]"#]]);
+}
+
+#[test]
+fn sweep_keeps_pinned_memos_and_their_dependencies() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a,b".to_string());
+    let b = File::new(&db, "c".to_string());
+    assert_eq!(item_count(&db, a), 2);
+    assert_eq!(item_count(&db, b), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count(a,b)",
+            "parse(a,b)",
+            "item_count(c)",
+            "parse(c)",
+        ]"#]]);
+
+    // Neither is used in the new revision, but the pinned `item_count`
+    // keeps the `parse` it read as well.
+    item_count::pin(&db, a);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(db.sweep(), 2);
+    assert_eq!(item_count(&db, a), 2);
+    assert_eq!(item_count(&db, b), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count(c)",
+            "parse(c)",
+        ]"#]]);
+
+    item_count::unpin(&db, a);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(item_count(&db, b), 1);
+    assert_eq!(db.sweep(), 2);
+    assert_eq!(item_count(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "item_count(a,b)",
+            "parse(a,b)",
+        ]"#]]);
+}
@@ -0,0 +1,123 @@
+//! Test the `purge` and `purge_key` functions of tracked functions, which
+//! drop their memos so that they are recomputed.
+
+use std::collections::HashMap;
+
+use expect_test::expect;
+use parking_lot::Mutex;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, size, describe, name_len);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    /// Sizes of the files, which salsa does not know about.
+    fn sizes(&self) -> &Mutex<HashMap<String, usize>>;
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::tracked]
+fn size(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("size({})", file.name(db)));
+    db.sizes().lock()[file.name(db)]
+}
+
+#[salsa::tracked]
+fn describe(db: &dyn Db, file: File) -> String {
+    db.push_log(format!("describe({})", file.name(db)));
+    format!("{}: {}", file.name(db), size(db, file))
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("name_len({})", file.name(db)));
+    file.name(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    sizes: Mutex<HashMap<String, usize>>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn sizes(&self) -> &Mutex<HashMap<String, usize>> {
+        &self.sizes
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn setup() -> (Database, File, File) {
+    let mut db = Database::default();
+    db.sizes
+        .lock()
+        .extend([("a".to_string(), 1), ("b".to_string(), 2)]);
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+    for file in [a, b] {
+        describe(&db, file);
+        name_len(&db, file);
+    }
+    db.assert_logs(expect![[r#"
+        [
+            "describe(a)",
+            "size(a)",
+            "name_len(a)",
+            "describe(b)",
+            "size(b)",
+            "name_len(b)",
+        ]"#]]);
+    (db, a, b)
+}
+
+#[test]
+fn purge() {
+    let (mut db, a, b) = setup();
+    db.sizes.lock().insert("a".to_string(), 10);
+    size::purge(&mut db);
+    assert_eq!(describe(&db, a), "a: 10");
+    assert_eq!(describe(&db, b), "b: 2");
+    assert_eq!(name_len(&db, a), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "describe(a)",
+            "size(a)",
+            "describe(b)",
+            "size(b)",
+        ]"#]]);
+}
+
+#[test]
+fn purge_key() {
+    let (mut db, a, b) = setup();
+    db.sizes.lock().insert("b".to_string(), 20);
+    size::purge_key(&mut db, b);
+    assert_eq!(describe(&db, a), "a: 1");
+    assert_eq!(describe(&db, b), "b: 20");
+    db.assert_logs(expect![[r#"
+        [
+            "describe(b)",
+            "size(b)",
+        ]"#]]);
+
+    // Purging a query that has no memo changes nothing.
+    let c = File::new(&db, "c".to_string());
+    name_len::purge_key(&mut db, c);
+    assert_eq!(describe(&db, b), "b: 20");
+    db.assert_logs(expect!["[]"]);
+}
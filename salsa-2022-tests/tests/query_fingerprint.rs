@@ -0,0 +1,168 @@
+//! Test that the values persisted or cached by one version of a tracked function
+//! are not reused by another version, whose fingerprint differs.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use expect_test::expect;
+use parking_lot::Mutex;
+use salsa::cache::{CacheBackend, Fingerprint};
+use salsa::function::Configuration;
+use salsa::AsId;
+use salsa_2022_tests::HasLogger;
+use test_log::test;
+
+/// The first version of a program, counting words.
+mod v1 {
+    use salsa_2022_tests::{HasLogger, Logger};
+
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(File, word_count);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+    #[salsa::input(jar = Jar, persist)]
+    pub(crate) struct File {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    fn text_fingerprint(db: &dyn Db, file: File) -> salsa::cache::Fingerprint {
+        salsa::cache::Fingerprint::of(file.text(db))
+    }
+
+    #[salsa::tracked(jar = Jar, persist, cache = text_fingerprint)]
+    pub(crate) fn word_count(db: &dyn Db, file: File) -> usize {
+        db.push_log("v1::word_count".to_string());
+        file.text(db).split_whitespace().count()
+    }
+
+    #[salsa::db(Jar)]
+    #[derive(Default)]
+    pub(crate) struct Database {
+        pub(crate) storage: salsa::Storage<Self>,
+        logger: Logger,
+    }
+
+    impl salsa::Database for Database {}
+
+    impl Db for Database {}
+
+    impl HasLogger for Database {
+        fn logger(&self) -> &Logger {
+            &self.logger
+        }
+    }
+}
+
+/// The same program, counting words differently in a new version of `word_count`.
+mod v2 {
+    use salsa_2022_tests::{HasLogger, Logger};
+
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(File, word_count);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+    #[salsa::input(jar = Jar, persist)]
+    pub(crate) struct File {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    fn text_fingerprint(db: &dyn Db, file: File) -> salsa::cache::Fingerprint {
+        salsa::cache::Fingerprint::of(file.text(db))
+    }
+
+    #[salsa::tracked(jar = Jar, persist, cache = text_fingerprint, version = 2)]
+    pub(crate) fn word_count(db: &dyn Db, file: File) -> usize {
+        db.push_log("v2::word_count".to_string());
+        file.text(db).split(' ').count()
+    }
+
+    #[salsa::db(Jar)]
+    #[derive(Default)]
+    pub(crate) struct Database {
+        pub(crate) storage: salsa::Storage<Self>,
+        logger: Logger,
+    }
+
+    impl salsa::Database for Database {}
+
+    impl Db for Database {}
+
+    impl HasLogger for Database {
+        fn logger(&self) -> &Logger {
+            &self.logger
+        }
+    }
+}
+
+#[test]
+fn persisted_memos_of_another_version_are_not_loaded() {
+    let db = v1::Database::default();
+    let file = v1::File::new(&db, "a  b".to_string());
+    assert_eq!(v1::word_count(&db, file), 2);
+    let persisted = db.storage.persist().unwrap();
+
+    let mut new_db = v2::Database::default();
+    new_db.storage.restore(persisted).unwrap();
+    let file = v2::File::from_id(file.as_id());
+    assert_eq!(file.text(&new_db), "a  b");
+    assert_eq!(v2::word_count(&new_db, file), 3);
+    new_db.assert_logs(expect![[r#"
+        [
+            "v2::word_count",
+        ]"#]]);
+
+    // The same version reuses them.
+    let persisted = new_db.storage.persist().unwrap();
+    let mut same_db = v2::Database::default();
+    same_db.storage.restore(persisted).unwrap();
+    assert_eq!(v2::word_count(&same_db, file), 3);
+    same_db.assert_logs(expect!["[]"]);
+}
+
+/// A cache in memory, e.g., of another machine.
+#[derive(Default)]
+struct MemoryCache {
+    values: Mutex<HashMap<(String, Fingerprint), Vec<u8>>>,
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, query: &str, fingerprint: Fingerprint) -> Option<Vec<u8>> {
+        self.values
+            .lock()
+            .get(&(query.to_string(), fingerprint))
+            .cloned()
+    }
+
+    fn put(&self, query: &str, fingerprint: Fingerprint, value: Vec<u8>) {
+        self.values
+            .lock()
+            .insert((query.to_string(), fingerprint), value);
+    }
+}
+
+#[test]
+fn cached_values_of_another_version_are_not_found() {
+    let cache = Arc::new(MemoryCache::default());
+    let db = v1::Database::default();
+    db.storage.set_cache_backend(Some(cache.clone()));
+    let file = v1::File::new(&db, "a  b".to_string());
+    assert_eq!(v1::word_count(&db, file), 2);
+
+    let mut new_db = v2::Database::default();
+    new_db.storage.set_cache_backend(Some(cache.clone()));
+    let file = v2::File::new(&new_db, "a  b".to_string());
+    assert_eq!(v2::word_count(&new_db, file), 3);
+    new_db.assert_logs(expect![[r#"
+        [
+            "v2::word_count",
+        ]"#]]);
+    assert_eq!(cache.values.lock().len(), 2);
+    assert_ne!(
+        <v1::word_count as Configuration>::QUERY_FINGERPRINT,
+        <v2::word_count as Configuration>::QUERY_FINGERPRINT
+    );
+}
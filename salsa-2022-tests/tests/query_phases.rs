@@ -0,0 +1,105 @@
+//! Test the `phase` option of tracked functions, which aggregates the query
+//! statistics of the functions of a phase, across jars.
+
+use std::time::Duration;
+
+use salsa::statistics::QueryCounts;
+use salsa::Database as _;
+use test_log::test;
+
+mod parser {
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(File, parse);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> {}
+
+    #[salsa::input(jar = Jar)]
+    pub(crate) struct File {
+        #[return_ref]
+        pub(crate) text: String,
+    }
+
+    #[salsa::tracked(jar = Jar, phase = "parse")]
+    pub(crate) fn parse(db: &dyn Db, file: File) -> Vec<String> {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        file.text(db).split_whitespace().map(String::from).collect()
+    }
+}
+
+mod checker {
+    use crate::parser::{self, File};
+
+    #[salsa::jar(db = Db)]
+    pub(crate) struct Jar(check_file, count_words);
+
+    pub(crate) trait Db: salsa::DbWithJar<Jar> + parser::Db {}
+
+    #[salsa::tracked(jar = Jar, phase = "typeck")]
+    pub(crate) fn check_file(db: &dyn Db, file: File) -> bool {
+        count_words(db, file) > 0
+    }
+
+    #[salsa::tracked(jar = Jar, phase = "typeck")]
+    pub(crate) fn count_words(db: &dyn Db, file: File) -> usize {
+        parser::parse(db, file).len()
+    }
+}
+
+#[salsa::db(parser::Jar, checker::Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl parser::Db for Database {}
+
+impl checker::Db for Database {}
+
+#[test]
+fn counts_per_phase() {
+    let mut db = Database::default();
+    let file = parser::File::new(&db, "a b".to_string());
+    assert!(checker::check_file(&db, file));
+
+    let statistics = db.query_statistics();
+    assert_eq!(
+        statistics.phase("typeck").counts,
+        QueryCounts {
+            executions: 2,
+            validations: 0,
+            backdates: 0,
+        }
+    );
+    assert_eq!(statistics.phase("parse").counts.executions, 1);
+    assert_eq!(
+        statistics
+            .phases()
+            .map(|(phase, _)| phase)
+            .collect::<Vec<_>>(),
+        ["parse", "typeck"]
+    );
+
+    // `typeck` includes the time spent parsing, but only once, even though
+    // both of its functions were executing.
+    let parse_time = statistics.phase("parse").time;
+    let typeck_time = statistics.phase("typeck").time;
+    assert!(parse_time >= Duration::from_millis(20));
+    assert!(typeck_time >= parse_time);
+    assert!(typeck_time < parse_time * 2);
+
+    db.reset_query_statistics();
+    file.set_text(&mut db).to("c d".to_string());
+    assert!(checker::check_file(&db, file));
+    let statistics = db.query_statistics();
+    assert_eq!(
+        statistics.phase("typeck").counts,
+        QueryCounts {
+            executions: 1,
+            validations: 1,
+            backdates: 1,
+        }
+    );
+    assert_eq!(statistics.phase("lint"), Default::default());
+}
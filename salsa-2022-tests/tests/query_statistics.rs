@@ -0,0 +1,101 @@
+//! Test that `query_statistics` counts executions, validations, and backdates
+//! per function, and that the counts can be reset.
+
+use salsa::{Database as _, DebugWithDb};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, is_even, describe);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn is_even(db: &dyn Db, input: MyInput) -> bool {
+    input.field(db).is_multiple_of(2)
+}
+
+#[salsa::tracked]
+fn describe(db: &dyn Db, input: MyInput) -> &'static str {
+    if is_even(db, input) {
+        "even"
+    } else {
+        "odd"
+    }
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn statistics() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 2);
+    assert_eq!(describe(&db, input), "even");
+    assert_eq!(describe(&db, input), "even");
+    expect![[r#"
+        {
+            is_even(): QueryCounts {
+                executions: 1,
+                validations: 0,
+                backdates: 0,
+            },
+            describe(): QueryCounts {
+                executions: 1,
+                validations: 0,
+                backdates: 0,
+            },
+        }
+    "#]]
+    .assert_debug_eq(&db.query_statistics().debug(&db));
+
+    // `is_even` is re-executed and backdated, so `describe` is only validated.
+    db.reset_query_statistics();
+    input.set_field(&mut db).to(4);
+    assert_eq!(describe(&db, input), "even");
+    let statistics = db.query_statistics();
+    let is_even_index = is_even::database_key(&db, input).ingredient_index();
+    let describe_index = describe::database_key(&db, input).ingredient_index();
+    expect![[r#"
+        QueryCounts {
+            executions: 1,
+            validations: 0,
+            backdates: 1,
+        }
+    "#]]
+    .assert_debug_eq(&statistics.get(is_even_index));
+    expect![[r#"
+        QueryCounts {
+            executions: 0,
+            validations: 1,
+            backdates: 0,
+        }
+    "#]]
+    .assert_debug_eq(&statistics.get(describe_index));
+
+    // Now both functions have to be re-executed.
+    db.reset_query_statistics();
+    input.set_field(&mut db).to(5);
+    assert_eq!(describe(&db, input), "odd");
+    expect![[r#"
+        QueryCounts {
+            executions: 2,
+            validations: 0,
+            backdates: 0,
+        }
+    "#]]
+    .assert_debug_eq(&db.query_statistics().total());
+}
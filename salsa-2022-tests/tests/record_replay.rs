@@ -0,0 +1,149 @@
+//! Test that the mutations of `persist` inputs can be recorded,
+//! and replayed revision by revision in a fresh database.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Scratch, line_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input(persist)]
+struct File {
+    #[return_ref]
+    text: String,
+    version: u32,
+}
+
+/// Not persistent, so not recorded.
+#[salsa::input]
+struct Scratch {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("line_count(version {})", file.version(db)));
+    file.text(db).lines().count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn record_edits() -> (File, salsa::record::Recording) {
+    let mut db = Database::default();
+    db.start_recording();
+
+    let file = File::new(&db, "a".to_string(), 0);
+    Scratch::new(&db, 1);
+    line_count(&db, file);
+
+    file.set_text(&mut db).to("a\nb".to_string());
+    file.set_version(&mut db).to(1);
+    line_count(&db, file);
+
+    db.transaction(|db| {
+        file.set_text(db).to("a\nb\nc".to_string());
+        file.set_version(db).to(2);
+    });
+    line_count(&db, file);
+
+    let recording = db.take_recording();
+
+    // Not recorded anymore.
+    file.set_version(&mut db).to(3);
+    assert_eq!(db.take_recording().revisions().len(), 0);
+    (file, recording)
+}
+
+#[test]
+fn replay() {
+    let (file, recording) = record_edits();
+    let mutations: Vec<Vec<String>> = recording
+        .revisions()
+        .iter()
+        .map(|revision| {
+            revision
+                .mutations()
+                .iter()
+                .map(|mutation| format!("{}: {:?}", mutation.debug_name(), mutation.mutation()))
+                .collect()
+        })
+        .collect();
+    expect![[r#"
+        [
+            [
+                "File: NewInput",
+                "text: NewField { value: String(\"a\"), durability: Durability(0) }",
+                "version: NewField { value: Number(0), durability: Durability(0) }",
+            ],
+            [
+                "text: SetField { value: String(\"a\\nb\"), durability: Durability(0) }",
+            ],
+            [
+                "version: SetField { value: Number(1), durability: Durability(0) }",
+            ],
+            [
+                "text: SetField { value: String(\"a\\nb\\nc\"), durability: Durability(0) }",
+                "version: SetField { value: Number(2), durability: Durability(0) }",
+            ],
+        ]
+    "#]]
+    .assert_debug_eq(&mutations);
+
+    // The recording can be written out and read back.
+    let recording: salsa::record::Recording =
+        serde_json::from_str(&serde_json::to_string(&recording).unwrap()).unwrap();
+
+    let mut db = Database::default();
+    let mut line_counts = vec![];
+    for revision in recording.revisions() {
+        db.replay(revision).unwrap();
+        line_counts.push(line_count(&db, file));
+    }
+    assert_eq!(line_counts, vec![1, 2, 2, 3]);
+    assert_eq!(file.version(&db), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(version 0)",
+            "line_count(version 0)",
+            "line_count(version 1)",
+            "line_count(version 2)",
+        ]"#]]);
+}
+
+#[test]
+fn replay_into_used_database() {
+    let (_, recording) = record_edits();
+    let mut db = Database::default();
+    File::new(&db, String::new(), 0);
+    let err = db.replay(&recording.revisions()[0]).unwrap_err();
+    expect![[r#"
+        ReplayMismatch {
+            index: IngredientIndex(
+                2,
+            ),
+            id: Id {
+                value: 1,
+            },
+        }
+    "#]]
+    .assert_debug_eq(&err);
+}
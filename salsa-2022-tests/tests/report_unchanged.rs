@@ -0,0 +1,108 @@
+//! Test `Database::report_unchanged`, with which a query backdates a value
+//! that is not equal to its previous one.
+
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, item, name_len);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+
+    /// Set when the last edit only moved the text around.
+    moved: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Item {
+    name: String,
+    offset: usize,
+}
+
+#[salsa::tracked]
+fn item(db: &dyn Db, file: File) -> Item {
+    db.push_log(format!("item({:?})", file.text(db)));
+    if file.moved(db) {
+        db.report_unchanged();
+    }
+    let text = file.text(db);
+    Item {
+        name: text.trim().to_string(),
+        offset: text.len() - text.trim_start().len(),
+    }
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn Db, file: File) -> usize {
+    db.push_log("name_len".to_string());
+    item(db, file).name.len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn moved_value_is_backdated() {
+    let mut db = Database::default();
+    let file = File::new(&db, "foo".to_string(), false);
+    assert_eq!(name_len(&db, file), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "name_len",
+            "item(\"foo\")",
+        ]"#]]);
+
+    // `item` changed, but reported it did not: `name_len` is not re-executed.
+    file.set_text(&mut db).to("  foo".to_string());
+    file.set_moved(&mut db).to(true);
+    assert_eq!(name_len(&db, file), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "item(\"  foo\")",
+        ]"#]]);
+    // The new value is memoized all the same.
+    assert_eq!(
+        item(&db, file),
+        Item {
+            name: "foo".to_string(),
+            offset: 2
+        }
+    );
+
+    file.set_text(&mut db).to("  quux".to_string());
+    file.set_moved(&mut db).to(false);
+    assert_eq!(name_len(&db, file), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "item(\"  quux\")",
+            "name_len",
+        ]"#]]);
+}
+
+#[test]
+fn outside_of_a_query() {
+    let db = Database::default();
+    db.report_unchanged();
+}
@@ -0,0 +1,94 @@
+//! Test `#[return_ref(deref)]` fields, whose getters return a reference to the
+//! target of the field value (e.g., `&str` for a `String`).
+
+use expect_test::expect;
+use salsa::DebugWithDb;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(SourceFile, Word, Block, blocks);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct SourceFile {
+    #[return_ref(deref)]
+    text: String,
+
+    #[return_ref(deref)]
+    line_lengths: Vec<usize>,
+
+    #[return_ref]
+    path: String,
+}
+
+#[salsa::interned]
+struct Word {
+    #[return_ref(deref)]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Block {
+    #[id]
+    #[return_ref(deref)]
+    name: String,
+
+    #[return_ref(deref)]
+    words: Vec<Word>,
+}
+
+#[salsa::tracked]
+fn blocks(db: &dyn Db, file: SourceFile) -> Vec<Block> {
+    let text: &str = file.text(db);
+    text.split("\n\n")
+        .map(|block| {
+            let (name, rest) = block.split_once(':').unwrap();
+            let words = rest
+                .split_whitespace()
+                .map(|word| Word::new(db, word.to_string()))
+                .collect();
+            Block::new(db, name.to_string(), words)
+        })
+        .collect()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn getters_return_targets() {
+    let mut db = Database::default();
+    let file = SourceFile::new(
+        &db,
+        "a: x y\n\nb: z".to_string(),
+        vec![6, 0, 4],
+        "main.txt".to_string(),
+    );
+    let lengths: &[usize] = file.line_lengths(&db);
+    assert_eq!(lengths, [6, 0, 4]);
+    let path: &String = file.path(&db);
+    assert_eq!(path, "main.txt");
+
+    let blocks = blocks(&db, file);
+    let names: Vec<&str> = blocks.iter().map(|block| block.name(&db)).collect();
+    assert_eq!(names, ["a", "b"]);
+    let words: &[Word] = blocks[0].words(&db);
+    let words: Vec<&str> = words.iter().map(|word| word.text(&db)).collect();
+    assert_eq!(words, ["x", "y"]);
+    expect![[r#"Block { [salsa id]: 0, name: "a" }"#]]
+        .assert_eq(&format!("{:?}", blocks[0].debug(&db)));
+    expect![[r#"Block { [salsa id]: 0, name: "a", words: [Word { [salsa id]: 0, text: "x" }, Word { [salsa id]: 1, text: "y" }] }"#]]
+        .assert_eq(&format!("{:?}", blocks[0].debug_all(&db)));
+
+    // The setter still takes the value of the field.
+    file.set_text(&mut db).to("c: w".to_string());
+    assert_eq!(file.text(&db), "c: w");
+}
@@ -0,0 +1,91 @@
+//! Test the `DidStartRevision` event and `last_changed_revision`, which let
+//! caches outside of salsa tell whether the inputs they depend on moved.
+
+use salsa::{Database as _, Durability};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let salsa::EventKind::DidStartRevision { revision } = event.kind {
+            assert_eq!(revision, self.current_revision());
+            self.push_log(format!("DidStartRevision({revision:?})"));
+        }
+    }
+}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn writes_report_new_revisions() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    input.set_field(&mut db).to(2);
+    input.set_field(&mut db).to(3);
+    db.transaction(|db| {
+        input.set_field(db).to(4);
+        input.set_field(db).to(5);
+    });
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidStartRevision(R2)",
+            "DidStartRevision(R3)",
+            "DidStartRevision(R4)",
+            "DidStartRevision(R5)",
+        ]"#]]);
+}
+
+#[test]
+fn last_changed_revision_per_durability() {
+    let mut db = Database::default();
+    let low = MyInput::new(&db, 1);
+    let high = MyInput::new(&db, 1);
+    // The old value has low durability, so the first write moves the revisions
+    // of low durability only.
+    high.set_field(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(2);
+    high.set_field(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(3);
+    let r3 = db.current_revision();
+
+    // A low-durability write only moves the revisions of low durability.
+    low.set_field(&mut db).to(2);
+    let r4 = db.current_revision();
+    assert_eq!(db.last_changed_revision(Durability::LOW), r4);
+    assert_eq!(db.last_changed_revision(Durability::HIGH), r3);
+
+    high.set_field(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(4);
+    let r5 = db.current_revision();
+    assert_eq!(db.last_changed_revision(Durability::LOW), r5);
+    assert_eq!(db.last_changed_revision(Durability::HIGH), r5);
+}
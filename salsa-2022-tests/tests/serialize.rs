@@ -0,0 +1,129 @@
+//! Test the `serialize` option of salsa structs, whose `Serialize` and
+//! `Deserialize` impls go through the attached database.
+
+use expect_test::expect;
+use salsa::serde::{Deserialize, Serialize};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(SourceFile, Word, Definition, definitions, reparse);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input(serialize)]
+struct SourceFile {
+    #[return_ref]
+    path: String,
+
+    #[default]
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned(serialize)]
+struct Word {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(serialize)]
+struct Definition {
+    name: Word,
+    arity: usize,
+}
+
+#[salsa::tracked]
+fn definitions(db: &dyn Db, file: SourceFile) -> Vec<Definition> {
+    file.text(db)
+        .lines()
+        .map(|line| {
+            let mut words = line.split_whitespace();
+            let name = Word::new(db, words.next().unwrap().to_string());
+            Definition::new(db, name, words.count())
+        })
+        .collect()
+}
+
+/// Deserializes definitions, which creates them, so it has to be a tracked function.
+#[salsa::tracked]
+fn reparse(db: &dyn Db, file: SourceFile) -> Vec<(String, usize)> {
+    let definitions: Vec<Definition> = serde_json::from_str(file.text(db)).unwrap();
+    definitions
+        .into_iter()
+        .map(|definition| (definition.name(db).text(db).clone(), definition.arity(db)))
+        .collect()
+}
+
+/// An analysis result, as sent to another process.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "salsa::serde")]
+struct Report {
+    file: SourceFile,
+    names: Vec<Word>,
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn ids_are_resolved_by_the_attached_database() {
+    let db1 = Database::default();
+    // Intern a word first, so that the ids differ between the databases.
+    Word::new(&db1, "unused".to_string());
+    let file = SourceFile::builder("a.txt".to_string())
+        .text("f x y\ng".to_string())
+        .new(&db1);
+    let names = definitions(&db1, file)
+        .iter()
+        .map(|definition| definition.name(&db1))
+        .collect();
+    let report = Report { file, names };
+    let json = salsa::attach(&db1, || serde_json::to_string(&report).unwrap());
+    expect![[r#"{"file":{"path":"a.txt","text":"f x y\ng"},"names":[{"text":"f"},{"text":"g"}]}"#]]
+        .assert_eq(&json);
+
+    let db2 = Database::default();
+    let g = Word::new(&db2, "g".to_string());
+    let report: Report = salsa::attach(&db2, || serde_json::from_str(&json).unwrap());
+    assert_eq!(report.file.path(&db2), "a.txt");
+    assert_eq!(report.file.text(&db2), "f x y\ng");
+    assert_eq!(report.names[0].text(&db2), "f");
+    // The word is interned again, so it is the one already in the database.
+    assert_eq!(report.names[1], g);
+}
+
+#[test]
+fn tracked_structs_are_created_by_the_query_deserializing_them() {
+    let db1 = Database::default();
+    let file = SourceFile::builder("a.txt".to_string())
+        .text("f x y\ng".to_string())
+        .new(&db1);
+    let json = salsa::attach(&db1, || {
+        serde_json::to_string(&definitions(&db1, file)).unwrap()
+    });
+    expect![[r#"[{"name":{"text":"f"},"arity":2},{"name":{"text":"g"},"arity":0}]"#]]
+        .assert_eq(&json);
+
+    let db2 = Database::default();
+    let serialized = SourceFile::builder("a.json".to_string())
+        .text(json)
+        .new(&db2);
+    let definitions = salsa::attach(&db2, || reparse(&db2, serialized));
+    assert_eq!(definitions, [("f".to_string(), 2), ("g".to_string(), 0)]);
+}
+
+#[test]
+fn serializing_requires_an_attached_database() {
+    let db = Database::default();
+    let word = Word::new(&db, "f".to_string());
+    let error = serde_json::to_string(&word).unwrap_err();
+    expect!["no database is attached to (de)serialize `Word`, see `salsa::attach`"]
+        .assert_eq(&error.to_string());
+}
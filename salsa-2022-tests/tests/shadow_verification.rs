@@ -0,0 +1,142 @@
+//! Test that shadow verification re-executes the functions whose memos are
+//! validated, and reports those whose result changed anyway.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, read_a, read_global, classify, double);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn read_a(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("read_a({:?})", input.a(db)));
+    input.a(db)
+}
+
+static GLOBAL: AtomicU32 = AtomicU32::new(0);
+
+/// Reads state outside the database without reporting it.
+#[salsa::tracked]
+fn read_global(db: &dyn Db, input: MyInput) -> u32 {
+    input.a(db) + GLOBAL.load(Ordering::SeqCst)
+}
+
+/// Equal to every other `Class`.
+#[derive(Debug, Clone)]
+struct Class(u32);
+
+impl PartialEq for Class {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Class {}
+
+#[salsa::tracked]
+fn classify(db: &dyn Db, input: MyInput) -> Class {
+    Class(input.a(db))
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    classify(db, input).0 * 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn reexecutes_validated_memos() {
+    let mut db = Database::default();
+    db.set_shadow_verification(true);
+    let input = MyInput::new(&db, 1, 1);
+    assert_eq!(read_a(&db, input), 1);
+
+    input.set_b(&mut db).to(2);
+    assert_eq!(read_a(&db, input), 1);
+
+    input.set_a(&mut db).to(3);
+    assert_eq!(read_a(&db, input), 3);
+
+    // Validated in the second revision, and checked by executing it again.
+    db.assert_logs(expect![[r#"
+        [
+            "read_a(1)",
+            "read_a(1)",
+            "read_a(3)",
+        ]"#]]);
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 1);
+    assert_eq!(read_a(&db, input), 1);
+
+    input.set_b(&mut db).to(2);
+    assert_eq!(read_a(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "read_a(1)",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(
+    expected = "shadow verification failed for read_global(0): the memoized value 1 was validated, \
+                but executing the function again returns 11"
+)]
+fn untracked_read() {
+    let mut db = Database::default();
+    db.set_shadow_verification(true);
+    let input = MyInput::new(&db, 1, 1);
+    assert_eq!(read_global(&db, input), 1);
+
+    GLOBAL.store(10, Ordering::SeqCst);
+    input.set_b(&mut db).to(2);
+    read_global(&db, input);
+}
+
+#[test]
+#[should_panic(
+    expected = "shadow verification failed for double(0): the memoized value 2 was validated, \
+                but executing the function again returns 4"
+)]
+fn wrong_eq() {
+    let mut db = Database::default();
+    db.set_shadow_verification(true);
+    let input = MyInput::new(&db, 1, 1);
+    assert_eq!(double(&db, input), 2);
+
+    // `classify` is executed again and backdated, so `double` is not.
+    input.set_a(&mut db).to(2);
+    double(&db, input);
+}
@@ -0,0 +1,129 @@
+//! Test `specify` on tracked functions taking several arguments,
+//! whose first argument is a tracked struct created by the specifying query.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Source,
+    Scope,
+    Name,
+    infer,
+    type_of,
+    check,
+    specify_in_another_query,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+/// Bindings like `a = 1; b = true`.
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Scope {
+    source: Source,
+}
+
+#[salsa::interned]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+/// Infers the type of each binding of a new scope, writing the results to `type_of`.
+#[salsa::tracked]
+fn infer(db: &dyn Db, source: Source) -> Scope {
+    db.push_log("infer".to_string());
+    let scope = Scope::new(db, source);
+    for binding in source.text(db).split(';') {
+        let (name, value) = binding.split_once('=').unwrap();
+        let name = Name::new(db, name.trim().to_string());
+        let ty = match value.trim() {
+            "true" | "false" => "bool",
+            _ => "int",
+        };
+        type_of::specify(db, scope, name, ty.to_string());
+    }
+    scope
+}
+
+/// Defaults to `unknown` for names that `infer` does not know about.
+#[salsa::tracked(specify)]
+fn type_of(db: &dyn Db, _scope: Scope, name: Name) -> String {
+    db.push_log(format!("type_of({})", name.text(db)));
+    "unknown".to_string()
+}
+
+/// Reads the inferred type of `name`.
+#[salsa::tracked]
+fn check(db: &dyn Db, source: Source, name: Name) -> String {
+    let scope = infer(db, source);
+    type_of(db, scope, name)
+}
+
+/// Specifies a type in a scope that `infer` created.
+#[salsa::tracked]
+fn specify_in_another_query(db: &dyn Db, source: Source) {
+    let scope = infer(db, source);
+    let name = Name::new(db, "a".to_string());
+    type_of::specify(db, scope, name, "bool".to_string());
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn specify_from_the_creating_query() {
+    let mut db = Database::default();
+    let source = Source::new(&db, "a = 1; b = true".to_string());
+    let a = Name::new(&db, "a".to_string());
+    let b = Name::new(&db, "b".to_string());
+    let c = Name::new(&db, "c".to_string());
+
+    assert_eq!(check(&db, source, a), "int");
+    assert_eq!(check(&db, source, b), "bool");
+    assert_eq!(check(&db, source, c), "unknown");
+    db.assert_logs(expect![[r#"
+        [
+            "infer",
+            "type_of(c)",
+        ]"#]]);
+
+    // `infer` runs again, and specifies the new types.
+    source.set_text(&mut db).to("a = 1; b = 2".to_string());
+    assert_eq!(check(&db, source, a), "int");
+    assert_eq!(check(&db, source, b), "int");
+    db.assert_logs(expect![[r#"
+        [
+            "infer",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "can only use `specfiy` on entities created during current query")]
+fn specify_from_another_query() {
+    let db = Database::default();
+    let source = Source::new(&db, "a = 1".to_string());
+    specify_in_another_query(&db, source);
+}
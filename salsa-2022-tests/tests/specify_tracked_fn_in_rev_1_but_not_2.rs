@@ -207,6 +207,7 @@ fn test_run_0_then_5_then_20() {
     assert_eq!(final_result(&db, input), 100);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R2 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
@@ -227,6 +228,7 @@ fn test_run_0_then_5_then_20() {
     assert_eq!(final_result(&db, input), 200);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R3 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
@@ -280,6 +282,7 @@ fn test_run_0_then_5_then_10_then_20() {
     assert_eq!(final_result(&db, input), 100);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R2 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
@@ -300,6 +303,7 @@ fn test_run_0_then_5_then_10_then_20() {
     assert_eq!(final_result(&db, input), 100);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R3 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
@@ -322,6 +326,7 @@ fn test_run_0_then_5_then_10_then_20() {
     assert_eq!(final_result(&db, input), 200);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R4 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
@@ -367,6 +372,7 @@ fn test_run_5_then_20() {
     assert_eq!(final_result(&db, input), 200);
     db.assert_logs(expect![[r#"
         [
+            "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidStartRevision { revision: R2 } }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: WillCheckCancellation }",
             "Event { runtime_id: RuntimeId { counter: 0 }, kind: DidValidateMemoizedValue { database_key: input(0) } }",
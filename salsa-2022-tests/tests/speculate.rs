@@ -0,0 +1,145 @@
+//! Test `Database::speculate`, which discards the writes made in it.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Opaque, line_count, is_long, name_len);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    name: String,
+    #[return_ref]
+    text: String,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+struct NotClone(u32);
+
+#[salsa::input]
+struct Opaque {
+    #[return_ref]
+    value: NotClone,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("line_count({})", file.name(db)));
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked]
+fn is_long(db: &dyn Db, file: File) -> bool {
+    db.push_log(format!("is_long({})", file.name(db)));
+    line_count(db, file) > 2
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("name_len({})", file.name(db)));
+    file.name(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn writes_are_discarded() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a.txt".to_string(), "1\n2".to_string());
+    assert!(!is_long(&db, file));
+    assert_eq!(name_len(&db, file), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "is_long(a.txt)",
+            "line_count(a.txt)",
+            "name_len(a.txt)",
+        ]"#]]);
+
+    let speculative = db.speculate(|db| {
+        file.set_text(db).to("1\n2\n3".to_string());
+        is_long(db, file)
+    });
+    assert!(speculative);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a.txt)",
+            "is_long(a.txt)",
+        ]"#]]);
+
+    // The line count goes back to its previous value, so `is_long` is backdated;
+    // `name_len` did not depend on the discarded write.
+    assert_eq!(file.text(&db), "1\n2");
+    assert!(!is_long(&db, file));
+    assert_eq!(name_len(&db, file), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a.txt)",
+            "is_long(a.txt)",
+        ]"#]]);
+}
+
+#[test]
+fn nested_speculations() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a.txt".to_string(), "1".to_string());
+    db.speculate(|db| {
+        file.set_text(db).to("1\n2".to_string());
+        db.speculate(|db| {
+            file.set_text(db).update(|text| text.push_str("\n3"));
+            assert_eq!(line_count(db, file), 3);
+        });
+        assert_eq!(line_count(db, file), 2);
+    });
+    assert_eq!(line_count(&db, file), 1);
+}
+
+#[test]
+#[should_panic(expected = "`text` read from input")]
+fn new_inputs_are_deleted() {
+    let mut db = Database::default();
+    let file = db.speculate(|db| {
+        let file = File::new(db, "b.txt".to_string(), "1".to_string());
+        assert_eq!(line_count(db, file), 1);
+        file
+    });
+    file.text(&db);
+}
+
+#[test]
+fn deleted_inputs_are_put_back() {
+    let mut db = Database::default();
+    let file = File::new(&db, "a.txt".to_string(), "1".to_string());
+    db.speculate(|db| file.delete(db));
+    assert_eq!(file.text(&db), "1");
+    assert_eq!(line_count(&db, file), 1);
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot set `value` during a speculation, since its type does not implement `Clone`"
+)]
+fn field_without_clone() {
+    let mut db = Database::default();
+    let opaque = Opaque::new(&db, NotClone(1));
+    db.speculate(|db| opaque.set_value(db).to(NotClone(2)));
+}
@@ -0,0 +1,122 @@
+//! Test that `storage_usage` counts the values stored by each ingredient.
+
+use salsa::{key::DependencyIndex, Database as _, DebugWithDb};
+use test_log::test;
+
+use expect_test::expect;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Word, Line, Diagnostic, lines, word_count);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct Word {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Line {
+    words: Vec<Word>,
+}
+
+#[salsa::accumulator]
+struct Diagnostic(String);
+
+#[salsa::tracked]
+fn lines(db: &dyn Db, input: MyInput) -> Vec<Line> {
+    input
+        .text(db)
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                Diagnostic::push(db, "empty line".to_string());
+            }
+            let words = line
+                .split_whitespace()
+                .map(|word| Word::new(db, word.to_string()))
+                .collect();
+            Line::new(db, words)
+        })
+        .collect()
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Db, input: MyInput) -> usize {
+    lines(db, input)
+        .iter()
+        .map(|line| line.words(db).len())
+        .sum()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+/// The number of entries of each ingredient that has any,
+/// as the number of bytes depends on the platform.
+fn entries(db: &Database) -> Vec<String> {
+    db.storage_usage()
+        .iter()
+        .filter(|(_, usage)| usage.entries > 0)
+        .map(|(index, usage)| {
+            let index = DependencyIndex::from(index);
+            format!("{:?}: {}", index.debug(db), usage.entries)
+        })
+        .collect()
+}
+
+#[test]
+fn counts_entries() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "a b\n\nb c d".to_string());
+    assert_eq!(word_count(&db, input), 5);
+    let _ = lines::accumulated::<Diagnostic>(&db, input);
+
+    expect![[r#"
+        [
+            "text(): 1",
+            "MyInput(): 1",
+            "Word(): 4",
+            "words(): 3",
+            "Line(): 3",
+            "Diagnostic(): 1",
+            "lines(): 1",
+            "word_count(): 1",
+        ]
+    "#]]
+    .assert_debug_eq(&entries(&db));
+
+    let usage = db.storage_usage();
+    let words = usage.iter().nth(2).unwrap().1;
+    assert!(words.bytes >= 4 * std::mem::size_of::<String>());
+    assert!(usage.total().bytes > words.bytes);
+    assert_eq!(
+        usage.get(word_count::database_key(&db, input).ingredient_index()),
+        usage.iter().last().unwrap().1
+    );
+
+    // Discarding the memos frees their entries.
+    input.set_text(&mut db).to("a".to_string());
+    db.sweep();
+    expect![[r#"
+        [
+            "text(): 1",
+            "MyInput(): 1",
+        ]
+    "#]]
+    .assert_debug_eq(&entries(&db));
+}
@@ -0,0 +1,90 @@
+//! Test `Database::subscribe`, whose subscriptions are notified when the value
+//! at their key may have changed.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, diagnostics);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn diagnostics(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("diagnostics({:?})", file.text(db)));
+    file.text(db).matches("error").count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn notified_when_the_value_changes() {
+    let mut db = Database::default();
+    let file = File::new(&db, "error".to_string());
+    let other = File::new(&db, "ok".to_string());
+    assert_eq!(diagnostics(&db, file), 1);
+    assert_eq!(diagnostics(&db, other), 0);
+    let subscription = db.subscribe(diagnostics::database_key(&db, file));
+    let other_subscription = db.subscribe(diagnostics::database_key(&db, other));
+    db.assert_logs_len(2);
+
+    // The diagnostics are the same: no notification.
+    file.set_text(&mut db).to("error, again".to_string());
+    assert_eq!(db.notify_subscribers(), 0);
+    assert_eq!(subscription.changed(), None);
+    db.assert_logs(expect![[r#"
+        [
+            "diagnostics(\"error, again\")",
+        ]"#]]);
+
+    file.set_text(&mut db)
+        .to("error, and another error".to_string());
+    assert_eq!(db.notify_subscribers(), 1);
+    assert_eq!(subscription.changed(), Some(db.current_revision()));
+    assert_eq!(subscription.changed(), None);
+    assert_eq!(other_subscription.changed(), None);
+
+    // Already notified in this revision.
+    assert_eq!(db.notify_subscribers(), 0);
+    db.assert_logs_len(1);
+    assert_eq!(diagnostics(&db, file), 2);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn dropped_subscriptions_are_not_notified() {
+    let mut db = Database::default();
+    let file = File::new(&db, "ok".to_string());
+    assert_eq!(diagnostics(&db, file), 0);
+    let subscription = db.subscribe(diagnostics::database_key(&db, file));
+    drop(subscription);
+    db.assert_logs_len(1);
+
+    // The dropped subscription is gone, so its value is not even verified.
+    file.set_text(&mut db).to("error".to_string());
+    assert_eq!(db.notify_subscribers(), 0);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,207 @@
+//! Test that `sweep` discards memos, tracked structs, and interned values not used
+//! in the current revision, and keeps everything the used memos depend on; and that
+//! `compact` keeps what is left.
+
+use salsa::{Database as _, Durability};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    MyInput,
+    MyTracked,
+    final_result,
+    create_tracked_structs,
+    contribution_from_struct,
+    MyInterned,
+    intern_field,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn final_result(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log(format!("final_result({:?})", input));
+    let mut sum = 0;
+    for tracked_struct in create_tracked_structs(db, input) {
+        sum += contribution_from_struct(db, tracked_struct);
+    }
+    sum
+}
+
+#[salsa::tracked]
+struct MyTracked {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked_structs(db: &dyn Db, input: MyInput) -> Vec<MyTracked> {
+    db.push_log(format!("create_tracked_structs({:?})", input));
+    (0..input.field(db))
+        .map(|i| MyTracked::new(db, i))
+        .collect()
+}
+
+#[salsa::tracked]
+fn contribution_from_struct(db: &dyn Db, tracked: MyTracked) -> u32 {
+    tracked.field(db) * 2
+}
+
+#[salsa::interned]
+struct MyInterned {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn intern_field(db: &dyn Db, input: MyInput) -> MyInterned {
+    db.push_log(format!("intern_field({:?})", input));
+    MyInterned::new(db, input.field(db))
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn sweep_discards_unused_memos() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 2);
+    let b = MyInput::new(&db, 1);
+
+    assert_eq!(final_result(&db, a), 2);
+    assert_eq!(final_result(&db, b), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "final_result(MyInput(Id { value: 1 }))",
+            "create_tracked_structs(MyInput(Id { value: 1 }))",
+            "final_result(MyInput(Id { value: 2 }))",
+            "create_tracked_structs(MyInput(Id { value: 2 }))",
+        ]"#]]);
+
+    // Everything is in use in the revision that computed it.
+    assert_eq!(db.sweep(), 0);
+
+    // Only `a` is used in the new revision. For `b`, we discard the two memos,
+    // the tracked struct, its field, and `contribution_from_struct` for it.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(final_result(&db, a), 2);
+    assert_eq!(db.sweep(), 5);
+    db.assert_logs(expect!["[]"]);
+
+    // `b` has to be recomputed, `a` is still memoized.
+    assert_eq!(final_result(&db, b), 0);
+    assert_eq!(final_result(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "final_result(MyInput(Id { value: 2 }))",
+            "create_tracked_structs(MyInput(Id { value: 2 }))",
+        ]"#]]);
+}
+
+#[test]
+fn sweep_keeps_dependencies_of_durably_verified_memos() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 0);
+    a.set_field(&mut db).with_durability(Durability::HIGH).to(2);
+
+    assert_eq!(final_result(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "final_result(MyInput(Id { value: 1 }))",
+            "create_tracked_structs(MyInput(Id { value: 1 }))",
+        ]"#]]);
+
+    // `final_result` is verified without looking at its inputs, since only
+    // low durability inputs changed. Its dependencies must survive the sweep anyway.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(final_result(&db, a), 2);
+    assert_eq!(db.sweep(), 0);
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(create_tracked_structs(&db, a).len(), 2);
+    assert_eq!(final_result(&db, a), 2);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn sweep_reclaims_interned_values() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    let outside = MyInterned::new(&db, 3);
+
+    let interned_a = intern_field(&db, a);
+    let interned_b = intern_field(&db, b);
+    db.assert_logs(expect![[r#"
+        [
+            "intern_field(MyInput(Id { value: 1 }))",
+            "intern_field(MyInput(Id { value: 2 }))",
+        ]"#]]);
+
+    // The memo for `b` and the value it interned are discarded.
+    // Values interned outside of a query are always kept.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(intern_field(&db, a), interned_a);
+    assert_eq!(db.sweep(), 2);
+    assert_eq!(interned_a.value(&db), 1);
+    assert_eq!(outside.value(&db), 3);
+
+    // Interning the value again produces a fresh id.
+    let new_interned_b = intern_field(&db, b);
+    assert_ne!(new_interned_b, interned_b);
+    assert_eq!(new_interned_b.value(&db), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "intern_field(MyInput(Id { value: 2 }))",
+        ]"#]]);
+}
+
+#[test]
+fn compact_after_sweep_keeps_remaining_values() {
+    let mut db = Database::default();
+    let inputs: Vec<_> = (0..100).map(|i| MyInput::new(&db, i % 3)).collect();
+    for &input in &inputs {
+        final_result(&db, input);
+        intern_field(&db, input);
+    }
+    db.assert_logs_len(300);
+
+    // Only one input is used in the new revision, so the values of all the others
+    // go away, leaving the tables with room for a hundred of them.
+    let a = inputs[2];
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(final_result(&db, a), 2);
+    let interned_a = intern_field(&db, a);
+    assert!(db.sweep() > 0);
+
+    // Compacting keeps what is left, and does not start a new revision.
+    let revision = db.current_revision();
+    let usage = db.storage_usage();
+    db.compact();
+    assert_eq!(db.current_revision(), revision);
+    assert_eq!(db.storage_usage(), usage);
+    assert_eq!(final_result(&db, a), 2);
+    assert_eq!(intern_field(&db, a), interned_a);
+    assert_eq!(interned_a.value(&db), 2);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,140 @@
+//! Test the built-in `salsa::Symbol` interned string type.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa::{DebugWithDb, Symbol};
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, first_word, has_word);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input(persist)]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(persist)]
+fn first_word(db: &dyn Db, input: MyInput) -> Option<Symbol> {
+    db.push_log(format!("first_word({:?})", input.text(db)));
+    let word = input.text(db).split_whitespace().next()?;
+    Some(Symbol::new(db, word))
+}
+
+#[salsa::tracked]
+fn has_word(db: &dyn Db, input: MyInput, word: Symbol) -> bool {
+    db.push_log(format!("has_word({:?})", word.as_str(db)));
+    input
+        .text(db)
+        .split_whitespace()
+        .any(|w| w == word.as_str(db))
+}
+
+const FN: Symbol = Symbol::predefined(0);
+const LET: Symbol = Symbol::predefined(1);
+
+#[salsa::db(Jar)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            storage: salsa::Storage::default().with_symbols(&["fn", "let"]),
+            logger: Default::default(),
+        }
+    }
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn equal_strings_have_equal_symbols() {
+    let db = Database::default();
+    let foo = Symbol::new(&db, "foo");
+    let bar = Symbol::new(&db, &String::from("bar"));
+    assert_eq!(foo, Symbol::new(&db, "foo"));
+    assert_ne!(foo, bar);
+    assert_eq!(foo.as_str(&db), "foo");
+    assert_eq!(bar.as_str(&db), "bar");
+}
+
+#[test]
+fn predefined_symbols() {
+    let db = Database::default();
+    assert_eq!(Symbol::new(&db, "fn"), FN);
+    assert_eq!(Symbol::new(&db, "let"), LET);
+    assert_eq!(LET.as_str(&db), "let");
+    assert_ne!(Symbol::new(&db, "foo"), FN);
+}
+
+#[test]
+#[should_panic(expected = "`fn` is predefined twice")]
+fn predefined_twice() {
+    let _ = salsa::Storage::<Database>::default().with_symbols(&["fn", "fn"]);
+}
+
+#[test]
+fn debug() {
+    let db = Database::default();
+    let foo = Symbol::new(&db, "foo");
+    expect![[r#"Symbol(2)"#]].assert_eq(&format!("{:?}", foo));
+    expect![[r#""foo""#]].assert_eq(&format!("{:?}", foo.debug(&db)));
+}
+
+#[test]
+fn symbols_in_queries() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "let x = 1".to_string());
+    assert_eq!(first_word(&db, input), Some(LET));
+    assert!(has_word(&db, input, Symbol::new(&db, "x")));
+    assert!(!has_word(&db, input, FN));
+    db.assert_logs(expect![[r#"
+        [
+            "first_word(\"let x = 1\")",
+            "has_word(\"x\")",
+            "has_word(\"fn\")",
+        ]"#]]);
+
+    // The first word is the same symbol, so `first_word` is backdated.
+    input.set_text(&mut db).to("let y = 2".to_string());
+    assert_eq!(first_word(&db, input), Some(LET));
+    assert!(!has_word(&db, input, Symbol::new(&db, "x")));
+    db.assert_logs(expect![[r#"
+        [
+            "first_word(\"let y = 2\")",
+            "has_word(\"x\")",
+        ]"#]]);
+}
+
+#[test]
+fn symbols_are_persisted() {
+    let db = Database::default();
+    let input = MyInput::new(&db, "foo bar".to_string());
+    let foo = first_word(&db, input).unwrap();
+
+    let mut bytes = vec![];
+    db.serialize(&mut serde_json::Serializer::new(&mut bytes))
+        .unwrap();
+
+    let mut db = Database::default();
+    db.deserialize(&mut serde_json::Deserializer::from_slice(&bytes))
+        .unwrap();
+    assert_eq!(first_word(&db, input), Some(foo));
+    assert_eq!(foo.as_str(&db), "foo");
+    assert_eq!(Symbol::new(&db, "foo"), foo);
+    db.assert_logs(expect!["[]"]);
+}
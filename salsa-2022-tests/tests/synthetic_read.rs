@@ -0,0 +1,89 @@
+//! Test `Database::report_synthetic_read`, for queries reading state
+//! outside of salsa that changes rarely.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use salsa::{Database as _, Durability};
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, toolchain_version, is_supported);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    fn installed_toolchain(&self) -> u32;
+}
+
+#[salsa::input]
+struct MyInput {
+    minimum_version: u32,
+}
+
+#[salsa::tracked]
+fn toolchain_version(db: &dyn Db) -> u32 {
+    db.push_log("toolchain_version".to_string());
+    db.report_synthetic_read(Durability::HIGH);
+    db.installed_toolchain()
+}
+
+#[salsa::tracked]
+fn is_supported(db: &dyn Db, input: MyInput) -> bool {
+    db.push_log("is_supported".to_string());
+    toolchain_version(db) >= input.minimum_version(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    toolchain: AtomicU32,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn installed_toolchain(&self) -> u32 {
+        self.toolchain.load(Ordering::SeqCst)
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn reexecuted_after_change_of_durability() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    assert!(!is_supported(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "is_supported",
+            "toolchain_version",
+        ]"#]]);
+
+    // Changes of low durability inputs do not re-execute the query.
+    db.toolchain.store(1, Ordering::SeqCst);
+    input.set_minimum_version(&mut db).to(2);
+    assert!(!is_supported(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "is_supported",
+        ]"#]]);
+
+    // Changes of high durability ones do.
+    db.toolchain.store(2, Ordering::SeqCst);
+    db.synthetic_write(Durability::HIGH);
+    assert!(is_supported(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "toolchain_version",
+            "is_supported",
+        ]"#]]);
+}
@@ -0,0 +1,147 @@
+//! Test `Database::synthetic_write_for`, which only re-executes
+//! the queries depending on a single value.
+
+use std::collections::HashMap;
+
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use parking_lot::Mutex;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, file_text, line_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    fn read_file(&self, path: &str) -> String;
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    path: String,
+}
+
+/// Reads the file from a cache outside of salsa, without telling salsa:
+/// changes of the cache are reported with a synthetic write.
+#[salsa::tracked]
+fn file_text(db: &dyn Db, file: File) -> String {
+    db.push_log(format!("file_text({})", file.path(db)));
+    db.read_file(file.path(db))
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("line_count({})", file.path(db)));
+    file_text(db, file).lines().count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    files: Mutex<HashMap<String, String>>,
+}
+
+impl Database {
+    fn write_file(&self, path: &str, text: &str) {
+        self.files.lock().insert(path.to_string(), text.to_string());
+    }
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn read_file(&self, path: &str) -> String {
+        self.files.lock().get(path).cloned().unwrap_or_default()
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn only_reexecutes_the_value_written() {
+    let mut db = Database::default();
+    db.write_file("a", "1\n2");
+    db.write_file("b", "1");
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+
+    assert_eq!(line_count(&db, a), 2);
+    assert_eq!(line_count(&db, b), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "file_text(a)",
+            "line_count(b)",
+            "file_text(b)",
+        ]"#]]);
+
+    db.write_file("a", "1\n2\n3");
+    db.write_file("b", "1\n2");
+    db.synthetic_write_for(file_text::database_key(&db, a));
+    assert_eq!(line_count(&db, a), 3);
+    assert_eq!(line_count(&db, b), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "file_text(a)",
+            "line_count(a)",
+        ]"#]]);
+}
+
+#[test]
+fn backdates_unchanged_values() {
+    let mut db = Database::default();
+    db.write_file("a", "1\n2");
+    let a = File::new(&db, "a".to_string());
+
+    assert_eq!(line_count(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "file_text(a)",
+        ]"#]]);
+
+    db.synthetic_write_for(file_text::database_key(&db, a));
+    assert_eq!(line_count(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "file_text(a)",
+        ]"#]]);
+}
+
+#[test]
+fn whole_ingredient() {
+    let mut db = Database::default();
+    db.write_file("a", "1\n2");
+    db.write_file("b", "1");
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+
+    assert_eq!(line_count(&db, a), 2);
+    assert_eq!(line_count(&db, b), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(a)",
+            "file_text(a)",
+            "line_count(b)",
+            "file_text(b)",
+        ]"#]]);
+
+    db.write_file("b", "1\n2");
+    db.synthetic_write_for(file_text::database_key(&db, a).ingredient_index());
+    assert_eq!(line_count(&db, a), 2);
+    assert_eq!(line_count(&db, b), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "file_text(a)",
+            "file_text(b)",
+            "line_count(b)",
+        ]"#]]);
+}
@@ -0,0 +1,103 @@
+//! Test `salsa::testing`: its logger records the queries executed, and the
+//! assertions check them against the last checkpoint.
+
+use salsa::testing::{EventLogger, HasLogger};
+use salsa::ParallelDatabase;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, length, doubled, unrelated);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+    other: u32,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Db, input: MyInput) -> usize {
+    input.text(db).len()
+}
+
+#[salsa::tracked]
+fn doubled(db: &dyn Db, input: MyInput) -> usize {
+    length(db, input) * 2
+}
+
+#[salsa::tracked]
+fn unrelated(db: &dyn Db, input: MyInput) -> u32 {
+    input.other(db)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: EventLogger,
+}
+
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: salsa::Event) {
+        self.logger.log(&event);
+    }
+}
+
+impl salsa::ParallelDatabase for Database {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(Database {
+            storage: self.storage.snapshot(),
+            logger: self.logger.clone(),
+        })
+    }
+}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &EventLogger {
+        &self.logger
+    }
+}
+
+#[test]
+fn edits_execute_what_depends_on_them() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "abc".to_string(), 1);
+    doubled(&db, input);
+    unrelated(&db, input);
+    salsa::assert_executed!(db, [length, doubled, unrelated]);
+
+    db.logger.checkpoint();
+    input.set_text(&mut db).to("abd".to_string());
+    doubled(&db, input);
+    unrelated(&db, input);
+    // `length` did not change, so `doubled` is not executed again.
+    salsa::assert_executed!(db, [length]);
+    salsa::assert_not_executed!(db, [doubled, crate::unrelated]);
+    assert_eq!(db.logger.executed().len(), 1);
+}
+
+#[test]
+fn snapshots_share_the_logger() {
+    let db = Database::default();
+    let input = MyInput::new(&db, "abc".to_string(), 1);
+    let snapshot = db.snapshot();
+    std::thread::spawn(move || length(&*snapshot, input))
+        .join()
+        .unwrap();
+    salsa::assert_executed!(&db, [length]);
+}
+
+#[test]
+#[should_panic(
+    expected = "expected `doubled` not to have executed since the last checkpoint, but the queries executed were [doubled(0), length(0)]"
+)]
+fn assertions_list_the_queries_executed() {
+    let db = Database::default();
+    let input = MyInput::new(&db, "abc".to_string(), 1);
+    doubled(&db, input);
+    salsa::assert_not_executed!(db, [doubled]);
+}
@@ -0,0 +1,86 @@
+//! Test that the work of a database handle unwinds with `Cancelled::Timeout`
+//! once the deadline of its cancellation handle has passed.
+
+use std::time::Duration;
+
+use salsa::{Cancelled, Database as _, ParallelDatabase};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, slow);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    iterations: Option<u32>,
+}
+
+/// Loops `iterations` times, or forever if it is `None`.
+#[salsa::tracked]
+fn slow(db: &dyn Db, input: MyInput) -> u32 {
+    let iterations = input.iterations(db);
+    let mut done = 0;
+    while Some(done) != iterations {
+        db.unwind_if_cancelled();
+        done += 1;
+    }
+    done
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl salsa::ParallelDatabase for Database {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(Database {
+            storage: self.storage.snapshot(),
+        })
+    }
+}
+
+impl Db for Database {}
+
+#[test]
+fn snapshot_times_out() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, None);
+
+    {
+        let snapshot = db.snapshot();
+        snapshot
+            .cancellation_handle()
+            .set_timeout(Duration::from_millis(10));
+        match Cancelled::catch(std::panic::AssertUnwindSafe(|| slow(&*snapshot, input))) {
+            Err(Cancelled::Timeout { .. }) => {}
+            result => panic!("expected a timeout, got {:?}", result),
+        }
+    }
+
+    // The deadline belonged to the snapshot: the database itself has none.
+    assert_eq!(db.cancellation_handle().deadline(), None);
+    input.set_iterations(&mut db).to(Some(3));
+    assert_eq!(slow(&db, input), 3);
+}
+
+#[test]
+fn deadline_can_be_cleared() {
+    let db = Database::default();
+    let input = MyInput::new(&db, Some(3));
+
+    let handle = db.cancellation_handle();
+    handle.set_timeout(Duration::ZERO);
+    std::thread::sleep(Duration::from_millis(1));
+    assert!(matches!(
+        Cancelled::catch(std::panic::AssertUnwindSafe(|| slow(&db, input))),
+        Err(Cancelled::Timeout { .. })
+    ));
+
+    handle.set_deadline(None);
+    assert_eq!(slow(&db, input), 3);
+}
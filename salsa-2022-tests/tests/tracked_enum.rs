@@ -0,0 +1,203 @@
+//! Test tracked enums, whose variants are salsa structs,
+//! as arguments of tracked functions and fields of tracked structs.
+
+use std::collections::HashSet;
+
+use salsa::DebugWithDb;
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Source,
+    Function,
+    Const,
+    Name,
+    Module,
+    parse,
+    item_name,
+    item_size,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Function {
+    #[id]
+    name: String,
+    body: String,
+}
+
+#[salsa::tracked]
+struct Const {
+    #[id]
+    name: String,
+    value: u32,
+}
+
+#[salsa::interned]
+struct Name {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+enum Item {
+    Function(Function),
+    Const(Const),
+    Alias(Name),
+}
+
+#[salsa::tracked]
+struct Module {
+    #[return_ref]
+    items: Vec<Item>,
+}
+
+/// Parses lines like `fn name body`, `const name value` and `alias name`.
+#[salsa::tracked]
+fn parse(db: &dyn Db, source: Source) -> Module {
+    let items = source
+        .text(db)
+        .lines()
+        .map(|line| {
+            let words: Vec<_> = line.split_whitespace().collect();
+            match words[..] {
+                ["fn", name, body] => Function::new(db, name.to_string(), body.to_string()).into(),
+                ["const", name, value] => {
+                    Const::new(db, name.to_string(), value.parse().unwrap()).into()
+                }
+                ["alias", name] => Item::Alias(Name::new(db, name.to_string())),
+                _ => panic!("unexpected line {line:?}"),
+            }
+        })
+        .collect();
+    Module::new(db, items)
+}
+
+#[salsa::tracked]
+fn item_name(db: &dyn Db, item: Item) -> String {
+    db.push_log(format!("item_name({item:?})"));
+    match item {
+        Item::Function(f) => f.name(db),
+        Item::Const(c) => c.name(db),
+        Item::Alias(n) => n.text(db).clone(),
+    }
+}
+
+#[salsa::tracked]
+fn item_size(db: &dyn Db, item: Item, scale: u32) -> u32 {
+    db.push_log(format!("item_size({item:?}, {scale})"));
+    let size = match item {
+        Item::Function(f) => f.body(db).len() as u32,
+        Item::Const(c) => c.value(db),
+        Item::Alias(_) => 0,
+    };
+    size * scale
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn as_id_round_trips() {
+    let db = Database::default();
+    let source = Source::new(&db, "fn a x\nconst a 1\nalias a\nconst b 2".to_string());
+    let items = parse(&db, source).items(&db);
+    for &item in items {
+        assert_eq!(
+            <Item as salsa::AsId>::from_id(salsa::AsId::as_id(item)),
+            item
+        );
+    }
+    // Variants holding salsa structs with the same id get different ids.
+    let ids: HashSet<_> = items[..3].iter().map(|&i| salsa::AsId::as_id(i)).collect();
+    assert_eq!(ids.len(), 3);
+}
+
+#[test]
+fn tracked_fn_on_enum() {
+    let mut db = Database::default();
+    let source = Source::new(&db, "fn f abc\nconst c 7\nalias a".to_string());
+    let module = parse(&db, source);
+    let names: Vec<_> = module
+        .items(&db)
+        .iter()
+        .map(|&i| item_name(&db, i))
+        .collect();
+    assert_eq!(names, ["f", "c", "a"]);
+    let sizes: Vec<_> = module
+        .items(&db)
+        .iter()
+        .map(|&i| item_size(&db, i, 2))
+        .collect();
+    assert_eq!(sizes, [6, 14, 0]);
+    db.assert_logs(expect![[r#"
+        [
+            "item_name(Function(Function(Id { value: 1 })))",
+            "item_name(Const(Const(Id { value: 1 })))",
+            "item_name(Alias(Name(Id { value: 1 })))",
+            "item_size(Function(Function(Id { value: 1 })), 2)",
+            "item_size(Const(Const(Id { value: 1 })), 2)",
+            "item_size(Alias(Name(Id { value: 1 })), 2)",
+        ]"#]]);
+
+    // Only the memos reading the changed constant re-execute.
+    source
+        .set_text(&mut db)
+        .to("fn f abc\nconst c 8\nalias a".to_string());
+    let module = parse(&db, source);
+    let names: Vec<_> = module
+        .items(&db)
+        .iter()
+        .map(|&i| item_name(&db, i))
+        .collect();
+    assert_eq!(names, ["f", "c", "a"]);
+    let sizes: Vec<_> = module
+        .items(&db)
+        .iter()
+        .map(|&i| item_size(&db, i, 2))
+        .collect();
+    assert_eq!(sizes, [6, 16, 0]);
+    db.assert_logs(expect![[r#"
+        [
+            "item_size(Const(Const(Id { value: 1 })), 2)",
+        ]"#]]);
+}
+
+#[test]
+fn debug_with_db() {
+    let db = Database::default();
+    let source = Source::new(&db, "fn f abc\nconst c 7".to_string());
+    let module = parse(&db, source);
+    expect![[r#"
+        Module { [salsa id]: 0, items: [Function(Function { [salsa id]: 0, name: "f", body: "abc" }), Const(Const { [salsa id]: 0, name: "c", value: 7 })] }
+    "#]]
+    .assert_eq(&format!("{:?}\n", module.debug_all(&db)));
+    expect![[r#"
+        Const(Const { [salsa id]: 0, name: "c" })
+    "#]]
+    .assert_eq(&format!("{:?}\n", module.items(&db)[1].debug(&db)));
+}
@@ -0,0 +1,96 @@
+//! Test the `boxed` option of tracked functions, whose values are stored boxed
+//! in their memos.
+
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, archive, checksum, archive_with_history);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    seed: u64,
+}
+
+/// A bulky value, which would take this much space in its memo unboxed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Archive([u64; 512]);
+
+fn make_archive(seed: u64) -> Archive {
+    let mut words = [0; 512];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = seed * i as u64;
+    }
+    Archive(words)
+}
+
+#[salsa::tracked(return_ref, boxed)]
+fn archive(db: &dyn Db, input: MyInput) -> Archive {
+    make_archive(input.seed(db))
+}
+
+#[salsa::tracked(boxed)]
+fn checksum(db: &dyn Db, input: MyInput) -> u64 {
+    archive(db, input).0.iter().sum()
+}
+
+#[salsa::tracked(return_ref, boxed, history = 1)]
+fn archive_with_history(db: &dyn Db, input: MyInput) -> Archive {
+    make_archive(input.seed(db))
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn boxed_values_are_returned_unboxed() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(archive(&db, input), &make_archive(1));
+    assert_eq!(checksum(&db, input), 511 * 512 / 2);
+
+    input.set_seed(&mut db).to(2);
+    assert_eq!(archive(&db, input), &make_archive(2));
+    assert_eq!(checksum(&db, input), 511 * 512);
+
+    let entries = archive::entries(&db);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].0, input);
+    assert_eq!(entries[0].1, &make_archive(2));
+}
+
+#[test]
+fn boxed_values_are_on_the_heap() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 1);
+    archive(&db, input);
+    let index = archive::database_key(&db, input).ingredient_index();
+    assert_eq!(
+        db.storage_usage().get(index).heap_bytes,
+        std::mem::size_of::<Archive>()
+    );
+}
+
+#[test]
+fn boxed_values_kept_by_history() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    archive_with_history(&db, input);
+    let r1 = db.current_revision();
+
+    input.set_seed(&mut db).to(2);
+    assert_eq!(archive_with_history(&db, input), &make_archive(2));
+    assert_eq!(
+        archive_with_history::value_as_of(&db, input, r1),
+        Some(&make_archive(1))
+    );
+}
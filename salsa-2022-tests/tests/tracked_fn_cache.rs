@@ -0,0 +1,128 @@
+//! Test the `cache` option of tracked functions, whose values are shared
+//! between databases through a cache backend.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use expect_test::expect;
+use parking_lot::Mutex;
+use salsa::cache::{CacheBackend, Fingerprint};
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, word_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+fn text_fingerprint(db: &dyn Db, file: File) -> Fingerprint {
+    Fingerprint::of(file.text(db))
+}
+
+#[salsa::tracked(cache = text_fingerprint)]
+fn word_count(db: &dyn Db, file: File) -> usize {
+    db.push_log(format!("word_count({:?})", file.text(db)));
+    file.text(db).split_whitespace().count()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+/// A cache in memory, e.g., of another machine.
+#[derive(Default)]
+struct MemoryCache {
+    values: Mutex<HashMap<(String, Fingerprint), Vec<u8>>>,
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, query: &str, fingerprint: Fingerprint) -> Option<Vec<u8>> {
+        self.values
+            .lock()
+            .get(&(query.to_string(), fingerprint))
+            .cloned()
+    }
+
+    fn put(&self, query: &str, fingerprint: Fingerprint, value: Vec<u8>) {
+        self.values
+            .lock()
+            .insert((query.to_string(), fingerprint), value);
+    }
+}
+
+fn database(cache: &Arc<MemoryCache>) -> Database {
+    let db = Database::default();
+    db.storage.set_cache_backend(Some(cache.clone()));
+    db
+}
+
+#[test]
+fn values_are_shared_between_databases() {
+    let cache = Arc::new(MemoryCache::default());
+
+    let mut db1 = database(&cache);
+    let file = File::new(&db1, "a b c".to_string());
+    assert_eq!(word_count(&db1, file), 3);
+    db1.assert_logs(expect![[r#"
+        [
+            "word_count(\"a b c\")",
+        ]"#]]);
+    assert_eq!(cache.values.lock().len(), 1);
+
+    // The other database finds the value in the cache.
+    let mut db2 = database(&cache);
+    let file = File::new(&db2, "a b c".to_string());
+    assert_eq!(word_count(&db2, file), 3);
+    db2.assert_logs(expect!["[]"]);
+
+    // The value depends on what was fingerprinted.
+    file.set_text(&mut db2).to("a b".to_string());
+    assert_eq!(word_count(&db2, file), 2);
+    db2.assert_logs(expect![[r#"
+        [
+            "word_count(\"a b\")",
+        ]"#]]);
+    assert_eq!(cache.values.lock().len(), 2);
+}
+
+#[test]
+fn values_are_computed_without_a_backend() {
+    let cache = Arc::new(MemoryCache::default());
+    let db1 = database(&cache);
+    let file = File::new(&db1, "a b c".to_string());
+    assert_eq!(word_count(&db1, file), 3);
+
+    let mut db2 = Database::default();
+    let file = File::new(&db2, "a b c".to_string());
+    assert_eq!(word_count(&db2, file), 3);
+    db2.assert_logs(expect![[r#"
+        [
+            "word_count(\"a b c\")",
+        ]"#]]);
+}
+
+#[test]
+fn combined_fingerprints_are_those_of_the_pair() {
+    // Values stored by earlier builds must still be found at the same fingerprints.
+    let (a, b) = (Fingerprint::of("a"), Fingerprint(u128::MAX));
+    assert_eq!(a.combine(b), Fingerprint::of(&(a, b)));
+}
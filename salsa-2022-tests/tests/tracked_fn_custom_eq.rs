@@ -0,0 +1,79 @@
+//! Test that a tracked function with `eq = ...` uses the given function,
+//! rather than `PartialEq`, to decide whether its value can be backdated.
+
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, parse, word_count);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+/// A word together with its offset in the text.
+#[derive(Clone, Debug)]
+struct Word {
+    text: String,
+    offset: usize,
+}
+
+#[salsa::tracked(eq = same_words)]
+fn parse(db: &dyn Db, input: MyInput) -> Vec<Word> {
+    input
+        .text(db)
+        .split(' ')
+        .scan(0, |offset, text| {
+            let word = Word {
+                text: text.to_string(),
+                offset: *offset,
+            };
+            *offset += text.len() + 1;
+            Some(word)
+        })
+        .filter(|word| !word.text.is_empty())
+        .collect()
+}
+
+/// Ignores the offsets, so that only changes to the words themselves
+/// cause `word_count` to be re-executed.
+fn same_words(old: &[Word], new: &[Word]) -> bool {
+    old.iter().map(|w| &w.text).eq(new.iter().map(|w| &w.text))
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Db, input: MyInput) -> usize {
+    parse(db, input).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn custom_eq_backdates() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "hello world".to_string());
+    assert_eq!(word_count(&db, input), 2);
+
+    // Only the offsets change, so `parse` is backdated and `word_count` is reused.
+    db.reset_query_statistics();
+    input.set_text(&mut db).to(" hello  world".to_string());
+    assert_eq!(word_count(&db, input), 2);
+    assert_eq!(parse(&db, input)[1].offset, 8);
+    let statistics = db.query_statistics();
+    let parse = statistics.get(parse::database_key(&db, input).ingredient_index());
+    let word_count = statistics.get(word_count::database_key(&db, input).ingredient_index());
+    assert_eq!((parse.executions, parse.backdates), (1, 1));
+    assert_eq!((word_count.executions, word_count.validations), (0, 1));
+}
@@ -0,0 +1,88 @@
+//! Test the `diff` option of tracked functions, which tell what changed in their
+//! value since a revision.
+
+use salsa::diff::Diffable;
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, diagnostics, error_count);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+/// The lines with an error.
+#[salsa::tracked(return_ref, diff)]
+fn diagnostics(db: &dyn Db, file: File) -> Vec<String> {
+    file.text(db)
+        .lines()
+        .filter(|line| line.contains("error"))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[salsa::tracked(diff)]
+fn error_count(db: &dyn Db, file: File) -> Vec<usize> {
+    vec![diagnostics(db, file).len()]
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn strings(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn delta_since_a_revision() {
+    let mut db = Database::default();
+    let file = File::new(&db, "error a\nok\nerror b".to_string());
+    let (value, delta) = diagnostics::with_delta(&db, file, db.current_revision());
+    assert_eq!(value, &strings(&["error a", "error b"]));
+    assert!(delta.unwrap().is_empty());
+    let r1 = db.current_revision();
+
+    file.set_text(&mut db)
+        .to("error b\nerror c\nok".to_string());
+    let (value, delta) = diagnostics::with_delta(&db, file, r1);
+    assert_eq!(value, &strings(&["error b", "error c"]));
+    let delta = delta.unwrap();
+    assert_eq!(delta.added, ["error c"]);
+    assert_eq!(delta.removed, ["error a"]);
+
+    // Nothing changed since the current revision.
+    let (_, delta) = diagnostics::with_delta(&db, file, db.current_revision());
+    assert!(delta.unwrap().is_empty());
+
+    // The value of the first revision is forgotten once the value changes again.
+    file.set_text(&mut db).to("error d".to_string());
+    let (_, delta) = diagnostics::with_delta(&db, file, r1);
+    assert_eq!(delta, None);
+
+    assert_eq!(error_count(&db, file), [1]);
+    let (value, delta) = error_count::with_delta(&db, file, db.current_revision());
+    assert_eq!(value, [1]);
+    assert!(delta.unwrap().is_empty());
+}
+
+#[test]
+fn list_delta_counts_duplicates() {
+    let old = vec![1, 2, 2, 3];
+    let new = vec![2, 3, 3, 4];
+    let delta = Vec::diff(&old, &new);
+    assert_eq!(delta.added, [3, 4]);
+    assert_eq!(delta.removed, [1, 2]);
+    assert!(Vec::diff(&new, &new).is_empty());
+}
@@ -0,0 +1,116 @@
+//! Test the `entries` function of tracked functions, which lists their memoized values.
+
+use expect_test::expect;
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, line_count, line_of, total);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    name: String,
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked(return_ref)]
+fn line_of(db: &dyn Db, file: File, line: usize) -> String {
+    file.text(db)
+        .lines()
+        .nth(line)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[salsa::tracked]
+fn total(_db: &dyn Db) -> usize {
+    0
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn entries_and_staleness() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a".to_string(), "1\n2".to_string());
+    let b = File::new(&db, "b".to_string(), "1".to_string());
+    assert_eq!(line_count::entries(&db), vec![]);
+    line_count(&db, a);
+    line_count(&db, b);
+    line_of(&db, a, 1);
+    line_of(&db, b, 0);
+
+    b.set_text(&mut db).to("1\n2\n3".to_string());
+    line_count(&db, b);
+
+    // The entry of `a` was not verified since `b` changed, so it may be stale.
+    let current = db.current_revision();
+    let entries: Vec<_> = line_count::entries(&db)
+        .into_iter()
+        .map(|(file, &count, changed_at, verified_at)| {
+            (
+                file.name(&db).clone(),
+                count,
+                changed_at,
+                verified_at == current,
+            )
+        })
+        .collect();
+    expect![[r#"
+        [
+            (
+                "a",
+                2,
+                R1,
+                false,
+            ),
+            (
+                "b",
+                3,
+                R2,
+                true,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&entries);
+
+    let entries: Vec<_> = line_of::entries(&db)
+        .into_iter()
+        .map(|((file, line), text, ..)| (file.name(&db).clone(), line, text.clone()))
+        .collect();
+    expect![[r#"
+        [
+            (
+                "a",
+                1,
+                "2",
+            ),
+            (
+                "b",
+                0,
+                "1",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&entries);
+
+    total(&db);
+    assert_eq!(total::entries(&db).len(), 1);
+}
@@ -0,0 +1,133 @@
+//! Test generic tracked functions, where each instantiation
+//! listed in the jar gets its own memoized values.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Function,
+    lower<Upper>,
+    lower<Reversed>,
+    repeat<Upper>,
+    repeat<Reversed>,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Function {
+    #[return_ref]
+    name: String,
+}
+
+trait LowerTarget {
+    type Output: Clone + Eq + std::fmt::Debug;
+
+    const NAME: &'static str;
+
+    fn lower(name: &str) -> Self::Output;
+}
+
+struct Upper;
+
+impl LowerTarget for Upper {
+    type Output = String;
+
+    const NAME: &'static str = "Upper";
+
+    fn lower(name: &str) -> String {
+        name.to_uppercase()
+    }
+}
+
+struct Reversed;
+
+impl LowerTarget for Reversed {
+    type Output = Vec<char>;
+
+    const NAME: &'static str = "Reversed";
+
+    fn lower(name: &str) -> Vec<char> {
+        name.chars().rev().collect()
+    }
+}
+
+#[salsa::tracked]
+fn lower<T: LowerTarget>(db: &dyn Db, function: Function) -> T::Output {
+    db.push_log(format!("lower::<{}>({})", T::NAME, function.name(db)));
+    T::lower(function.name(db))
+}
+
+#[salsa::tracked(return_ref)]
+fn repeat<T>(db: &dyn Db, function: Function, count: usize) -> Vec<T::Output>
+where
+    T: LowerTarget,
+    // Proves that the jar has the ingredients of `lower::<T>`.
+    Jar: salsa::storage::HasIngredientsFor<lower<T>>,
+{
+    db.push_log(format!("repeat::<{}>({count})", T::NAME));
+    vec![lower::<T>(db, function); count]
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let function = Function::new(&db, "abc".to_string());
+
+    assert_eq!(lower::<Upper>(&db, function), "ABC");
+    assert_eq!(lower::<Reversed>(&db, function), ['c', 'b', 'a']);
+    assert_eq!(lower::<Upper>(&db, function), "ABC");
+    assert_eq!(repeat::<Upper>(&db, function, 2), &["ABC", "ABC"]);
+    assert_eq!(repeat::<Reversed>(&db, function, 1), &[vec!['c', 'b', 'a']]);
+    db.assert_logs(expect![[r#"
+        [
+            "lower::<Upper>(abc)",
+            "lower::<Reversed>(abc)",
+            "repeat::<Upper>(2)",
+            "repeat::<Reversed>(1)",
+        ]"#]]);
+
+    function.set_name(&mut db).to("xy".to_string());
+    assert_eq!(repeat::<Upper>(&db, function, 2), &["XY", "XY"]);
+    assert_eq!(lower::<Reversed>(&db, function), ['y', 'x']);
+    db.assert_logs(expect![[r#"
+        [
+            "lower::<Upper>(xy)",
+            "repeat::<Upper>(2)",
+            "lower::<Reversed>(xy)",
+        ]"#]]);
+}
+
+#[test]
+fn set() {
+    let mut db = Database::default();
+    let function = Function::new(&db, "abc".to_string());
+
+    lower::<Upper>::set(&mut db, function, "set".to_string());
+    assert_eq!(lower::<Upper>(&db, function), "set");
+    assert_eq!(lower::<Reversed>(&db, function), ['c', 'b', 'a']);
+    db.assert_logs(expect![[r#"
+        [
+            "lower::<Reversed>(abc)",
+        ]"#]]);
+}
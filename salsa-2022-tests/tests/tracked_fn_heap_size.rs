@@ -0,0 +1,74 @@
+//! Test the `heap_size` option of tracked functions,
+//! whose result is reported by `storage_usage`.
+
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, words, word_count);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+fn strings_heap_size(strings: &Vec<String>) -> usize {
+    strings.capacity() * std::mem::size_of::<String>()
+        + strings.iter().map(|s| s.capacity()).sum::<usize>()
+}
+
+#[salsa::tracked(return_ref, heap_size = strings_heap_size)]
+fn words(db: &dyn Db, input: MyInput) -> Vec<String> {
+    input
+        .text(db)
+        .split_whitespace()
+        .map(|word| word.to_string())
+        .collect()
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Db, input: MyInput) -> usize {
+    words(db, input).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn reports_heap_size() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, "hello world".to_string());
+    let b = MyInput::new(&db, "salsa".to_string());
+    assert_eq!(word_count(&db, a), 2);
+    assert_eq!(word_count(&db, b), 1);
+
+    let words_index = words::database_key(&db, a).ingredient_index();
+    let count_index = word_count::database_key(&db, a).ingredient_index();
+    let heap_size =
+        |db: &Database| strings_heap_size(words(db, a)) + strings_heap_size(words(db, b));
+
+    let usage = db.storage_usage();
+    assert_eq!(usage.get(words_index).heap_bytes, heap_size(&db));
+    assert!(usage.get(words_index).heap_bytes >= "helloworldsalsa".len());
+    assert_eq!(usage.get(count_index).heap_bytes, 0);
+    assert_eq!(usage.total().heap_bytes, heap_size(&db));
+
+    // The heap size follows the values as they are recomputed.
+    a.set_text(&mut db).to("".to_string());
+    assert_eq!(word_count(&db, a), 0);
+    assert_eq!(
+        db.storage_usage().get(words_index).heap_bytes,
+        heap_size(&db)
+    );
+    assert_eq!(heap_size(&db), strings_heap_size(words(&db, b)));
+}
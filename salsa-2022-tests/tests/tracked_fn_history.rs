@@ -0,0 +1,109 @@
+//! Test the `history` option of tracked functions, which keeps the values
+//! replaced by the last changes of the memoized values.
+
+use salsa::Database as _;
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, diagnostics, diagnostics_ref);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    errors: u32,
+}
+
+#[salsa::tracked(history = 2)]
+fn diagnostics(db: &dyn Db, input: MyInput) -> Vec<String> {
+    (0..input.errors(db))
+        .map(|i| format!("error {i}"))
+        .collect()
+}
+
+#[salsa::tracked(return_ref, history = 1)]
+fn diagnostics_ref(db: &dyn Db, input: MyInput) -> Vec<String> {
+    diagnostics(db, input)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn previous_values() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    let r1 = db.current_revision();
+    assert_eq!(diagnostics(&db, input), vec!["error 0"]);
+
+    input.set_errors(&mut db).to(2);
+    let r2 = db.current_revision();
+    assert_eq!(diagnostics(&db, input), vec!["error 0", "error 1"]);
+
+    // The value at `r1` was replaced, and is kept by the history.
+    assert_eq!(
+        diagnostics::value_as_of(&db, input, r1),
+        Some(vec!["error 0".to_string()])
+    );
+    assert_eq!(
+        diagnostics::value_as_of(&db, input, r2),
+        Some(vec!["error 0".to_string(), "error 1".to_string()])
+    );
+
+    input.set_errors(&mut db).to(0);
+    assert!(diagnostics(&db, input).is_empty());
+    input.set_errors(&mut db).to(3);
+    diagnostics(&db, input);
+
+    // Only the two values before the last one are kept.
+    assert_eq!(diagnostics::value_as_of(&db, input, r1), None);
+    assert_eq!(diagnostics::value_as_of(&db, input, r2).unwrap().len(), 2);
+}
+
+#[test]
+fn unknown_revisions() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    // Nothing was computed in the first revision.
+    let r1 = db.current_revision();
+    input.set_errors(&mut db).to(2);
+    assert_eq!(diagnostics::value_as_of(&db, input, r1), None);
+    assert_eq!(diagnostics_ref(&db, input).len(), 2);
+
+    // A value that did not change covers the revisions in which it was verified.
+    let r2 = db.current_revision();
+    input.set_errors(&mut db).to(2);
+    let r3 = db.current_revision();
+    assert_eq!(diagnostics_ref(&db, input).len(), 2);
+    assert_eq!(
+        diagnostics_ref::value_as_of(&db, input, r2).unwrap().len(),
+        2
+    );
+    assert_eq!(
+        diagnostics_ref::value_as_of(&db, input, r3).unwrap().len(),
+        2
+    );
+
+    // A revision in which the value was neither computed nor verified is unknown.
+    input.set_errors(&mut db).to(1);
+    let unverified = db.current_revision();
+    input.set_errors(&mut db).to(0);
+    assert!(diagnostics_ref(&db, input).is_empty());
+    let now = db.current_revision();
+    assert_eq!(
+        diagnostics_ref::value_as_of(&db, input, r3).unwrap().len(),
+        2
+    );
+    assert_eq!(diagnostics_ref::value_as_of(&db, input, unverified), None);
+    assert_eq!(diagnostics_ref::value_as_of(&db, input, now), Some(&vec![]));
+}
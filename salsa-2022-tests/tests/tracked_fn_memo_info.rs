@@ -0,0 +1,90 @@
+//! Test the `memo_info` function of tracked functions, which tells the
+//! durability and revisions of their memoized values.
+
+use salsa::function::{MemoInfo, OriginKind};
+use salsa::{Database as _, Durability};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Config, File, config_value, file_len, volatile_len);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct Config {
+    value: u32,
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn config_value(db: &dyn Db, config: Config) -> u32 {
+    config.value(db)
+}
+
+#[salsa::tracked]
+fn file_len(db: &dyn Db, config: Config, file: File) -> usize {
+    config_value(db, config) as usize + file.text(db).len()
+}
+
+#[salsa::tracked(volatile)]
+fn volatile_len(db: &dyn Db, file: File) -> usize {
+    file.text(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn durability_is_the_lowest_of_the_inputs() {
+    let mut db = Database::default();
+    let config = Config::new(&db, 1);
+    config
+        .set_value(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(2);
+    let file = File::new(&db, "abc".to_string());
+    assert_eq!(config_value::memo_info(&db, config), None);
+
+    file_len(&db, config, file);
+    let revision = db.current_revision();
+    assert_eq!(
+        config_value::memo_info(&db, config),
+        Some(MemoInfo {
+            durability: Durability::HIGH,
+            changed_at: revision,
+            verified_at: revision,
+            origin_kind: OriginKind::Derived,
+        })
+    );
+    let info = file_len::memo_info(&db, config, file).unwrap();
+    assert_eq!(info.durability, Durability::LOW);
+
+    // After an edit, the memo is verified again, and backdated since its value
+    // did not change.
+    file.set_text(&mut db).to("abd".to_string());
+    file_len(&db, config, file);
+    let info = file_len::memo_info(&db, config, file).unwrap();
+    assert_eq!(info.verified_at, db.current_revision());
+    assert_eq!(info.changed_at, revision);
+}
+
+#[test]
+fn volatile_functions_read_untracked_inputs() {
+    let db = Database::default();
+    let file = File::new(&db, "abc".to_string());
+    volatile_len(&db, file);
+    let info = volatile_len::memo_info(&db, file).unwrap();
+    assert_eq!(info.origin_kind, OriginKind::DerivedUntracked);
+}
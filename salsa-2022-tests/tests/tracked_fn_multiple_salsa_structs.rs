@@ -0,0 +1,108 @@
+//! Test tracked functions taking several salsa structs,
+//! whose memos are keyed on the tuple of their ids.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Source, Item, parse, pair, pair_with_input);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Item {
+    #[id]
+    name: String,
+}
+
+#[salsa::tracked]
+fn parse(db: &dyn Db, source: Source) -> Vec<Item> {
+    source
+        .text(db)
+        .split_whitespace()
+        .map(|name| Item::new(db, name.to_string()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn pair(db: &dyn Db, a: Item, b: Item) -> String {
+    db.push_log(format!("pair({}, {})", a.name(db), b.name(db)));
+    format!("{}{}", a.name(db), b.name(db))
+}
+
+#[salsa::tracked]
+fn pair_with_input(db: &dyn Db, a: Item, source: Source) -> usize {
+    db.push_log(format!("pair_with_input({})", a.name(db)));
+    a.name(db).len() + source.text(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn memoized_per_tuple() {
+    let mut db = Database::default();
+    let source = Source::new(&db, "x y".to_string());
+    let items = parse(&db, source);
+    let (x, y) = (items[0], items[1]);
+
+    assert_eq!(pair(&db, x, y), "xy");
+    assert_eq!(pair(&db, y, x), "yx");
+    assert_eq!(pair(&db, x, y), "xy");
+    assert_eq!(pair_with_input(&db, y, source), 4);
+    assert_eq!(pair_with_input(&db, y, source), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "pair(x, y)",
+            "pair(y, x)",
+            "pair_with_input(y)",
+        ]"#]]);
+}
+
+#[test]
+fn deleting_a_struct_keeps_unrelated_memos() {
+    let mut db = Database::default();
+    let source = Source::new(&db, "x y z".to_string());
+    let items = parse(&db, source);
+    let (x, y) = (items[0], items[1]);
+
+    assert_eq!(pair(&db, x, y), "xy");
+    assert_eq!(pair(&db, y, x), "yx");
+    assert_eq!(pair(&db, x, x), "xx");
+    db.assert_logs(expect![[r#"
+        [
+            "pair(x, y)",
+            "pair(y, x)",
+            "pair(x, x)",
+        ]"#]]);
+
+    // `z` is deleted, but none of the memos of `pair` take it.
+    source.set_text(&mut db).to("x y".to_string());
+    assert_eq!(parse(&db, source), [x, y]);
+    assert_eq!(pair(&db, x, y), "xy");
+    assert_eq!(pair(&db, y, x), "yx");
+    assert_eq!(pair(&db, x, x), "xx");
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,66 @@
+//! Test that a `no_eq` tracked function is never backdated, so its readers
+//! re-execute whenever it does, and that its value does not need to implement `Eq`.
+
+use std::sync::Arc;
+
+use salsa::Database as _;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, make_scaler, scaled);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    factor: u32,
+    value: u32,
+}
+
+#[derive(Clone)]
+struct Scaler(Arc<dyn Fn(u32) -> u32 + Send + Sync>);
+
+impl std::fmt::Debug for Scaler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Scaler(..)")
+    }
+}
+
+#[salsa::tracked(no_eq)]
+fn make_scaler(db: &dyn Db, input: MyInput) -> Scaler {
+    let factor = input.factor(db) % 2;
+    Scaler(Arc::new(move |v| v * factor))
+}
+
+#[salsa::tracked]
+fn scaled(db: &dyn Db, input: MyInput) -> u32 {
+    (make_scaler(db, input).0)(input.value(db))
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn no_eq_is_never_backdated() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(scaled(&db, input), 10);
+
+    // `make_scaler` computes an equivalent closure, but we can't tell,
+    // so `scaled` has to be re-executed too.
+    db.reset_query_statistics();
+    input.set_factor(&mut db).to(3);
+    assert_eq!(scaled(&db, input), 10);
+    let statistics = db.query_statistics();
+    let make_scaler = statistics.get(make_scaler::database_key(&db, input).ingredient_index());
+    let scaled = statistics.get(scaled::database_key(&db, input).ingredient_index());
+    assert_eq!((make_scaler.executions, make_scaler.backdates), (1, 0));
+    assert_eq!(scaled.executions, 1);
+}
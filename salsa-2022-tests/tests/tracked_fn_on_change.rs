@@ -0,0 +1,140 @@
+//! Test the `on_change` function generated for tracked functions, whose
+//! callbacks are invoked when a value changes.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    MyInput,
+    MyTracked,
+    compile,
+    length_parity,
+    make_tracked,
+    tracked_value,
+);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn compile(db: &dyn Db, input: MyInput) -> String {
+    input.text(db).to_uppercase()
+}
+
+#[salsa::tracked]
+fn length_parity(db: &dyn Db, input: MyInput) -> bool {
+    input.text(db).len().is_multiple_of(2)
+}
+
+#[salsa::tracked]
+struct MyTracked {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn make_tracked(db: &dyn Db, input: MyInput) -> MyTracked {
+    let tracked = MyTracked::new(db, 0);
+    tracked_value::specify(db, tracked, input.text(db).len() as u32);
+    tracked
+}
+
+#[salsa::tracked(specify)]
+fn tracked_value(_db: &dyn Db, _tracked: MyTracked) -> u32 {
+    panic!("always specified")
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+type Changes = Arc<Mutex<Vec<String>>>;
+
+/// Records the changes the callbacks are invoked with.
+fn recorder<V: std::fmt::Debug>() -> (Changes, impl Fn(&V, &V) + Send + Sync + 'static) {
+    let changes = Changes::default();
+    let callback = {
+        let changes = changes.clone();
+        move |old: &V, new: &V| changes.lock().push(format!("{old:?} -> {new:?}"))
+    };
+    (changes, callback)
+}
+
+#[test]
+fn callback_sees_changed_values() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "ab".to_string());
+    let (changes, callback) = recorder();
+    compile::on_change(&db, input, callback);
+
+    // The first value replaces none.
+    assert_eq!(compile(&db, input), "AB");
+    assert!(changes.lock().is_empty());
+
+    input.set_text(&mut db).to("abc".to_string());
+    assert_eq!(compile(&db, input), "ABC");
+    assert_eq!(*changes.lock(), ["\"AB\" -> \"ABC\""]);
+}
+
+#[test]
+fn callback_not_invoked_when_backdated() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "ab".to_string());
+    let (changes, callback) = recorder();
+    length_parity::on_change(&db, input, callback);
+    assert!(length_parity(&db, input));
+
+    input.set_text(&mut db).to("abcd".to_string());
+    assert!(length_parity(&db, input));
+    assert!(changes.lock().is_empty());
+
+    input.set_text(&mut db).to("abc".to_string());
+    assert!(!length_parity(&db, input));
+    assert_eq!(*changes.lock(), ["true -> false"]);
+}
+
+#[test]
+fn callbacks_are_per_key() {
+    let mut db = Database::default();
+    let input1 = MyInput::new(&db, "a".to_string());
+    let input2 = MyInput::new(&db, "b".to_string());
+    let (changes1, callback1) = recorder();
+    let (changes2, callback2) = recorder();
+    compile::on_change(&db, input1, callback1);
+    compile::on_change(&db, input2, callback2);
+    compile(&db, input1);
+    compile(&db, input2);
+
+    input2.set_text(&mut db).to("c".to_string());
+    compile(&db, input1);
+    compile(&db, input2);
+    assert!(changes1.lock().is_empty());
+    assert_eq!(*changes2.lock(), ["\"B\" -> \"C\""]);
+}
+
+#[test]
+fn callback_sees_specified_values() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "ab".to_string());
+    let tracked = make_tracked(&db, input);
+    let (changes, callback) = recorder();
+    tracked_value::on_change(&db, tracked, callback);
+
+    input.set_text(&mut db).to("abc".to_string());
+    let tracked = make_tracked(&db, input);
+    assert_eq!(tracked_value(&db, tracked), 3);
+    assert_eq!(*changes.lock(), ["2 -> 3"]);
+}
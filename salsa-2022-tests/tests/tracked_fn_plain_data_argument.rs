@@ -0,0 +1,185 @@
+//! Test tracked functions with the `intern_args` option, whose only argument
+//! is plain data (not a salsa struct), which gets interned into their key.
+
+use std::path::PathBuf;
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Config,
+    scaled,
+    greeting,
+    area,
+    sum,
+    file_name,
+    describe,
+    offset,
+    shout,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input(singleton)]
+struct Config {
+    factor: u32,
+}
+
+#[salsa::tracked(intern_args)]
+fn scaled(db: &dyn Db, value: u32) -> u32 {
+    db.push_log(format!("scaled({value})"));
+    value * Config::get(db).factor(db)
+}
+
+#[salsa::tracked(return_ref, intern_args)]
+fn greeting(db: &dyn Db, name: String) -> String {
+    db.push_log(format!("greeting({name})"));
+    format!("hello, {name}")
+}
+
+#[salsa::tracked(intern_args)]
+fn area(db: &dyn Db, size: (u32, u32)) -> u32 {
+    db.push_log(format!("area({size:?})"));
+    scaled(db, size.0) * scaled(db, size.1)
+}
+
+#[salsa::tracked(intern_args)]
+fn sum(db: &dyn Db, values: Option<Vec<u32>>) -> u32 {
+    db.push_log(format!("sum({values:?})"));
+    values.into_iter().flatten().map(|v| scaled(db, v)).sum()
+}
+
+#[salsa::tracked(intern_args)]
+fn file_name(db: &dyn Db, path: PathBuf) -> Option<String> {
+    db.push_log(format!("file_name({})", path.display()));
+    Some(path.file_name()?.to_str()?.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Shape {
+    Square(u32),
+    Rectangle { width: u32, height: u32 },
+}
+
+#[salsa::tracked(intern_args)]
+fn describe(db: &dyn Db, shape: Shape) -> u32 {
+    db.push_log(format!("describe({shape:?})"));
+    match shape {
+        Shape::Square(side) => area(db, (side, side)),
+        Shape::Rectangle { width, height } => area(db, (width, height)),
+    }
+}
+
+type Offset = u32;
+
+#[salsa::tracked(intern_args)]
+fn offset(db: &dyn Db, offset: Offset) -> Offset {
+    db.push_log(format!("offset({offset})"));
+    scaled(db, offset) + 1
+}
+
+mod user {
+    /// A user type that happens to be named like the standard one.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub struct String(pub std::string::String);
+}
+
+#[salsa::tracked(intern_args)]
+fn shout(db: &dyn Db, text: user::String) -> String {
+    db.push_log(format!("shout({})", text.0));
+    text.0.to_uppercase()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    Config::new(&db, 2);
+
+    assert_eq!(scaled(&db, 3), 6);
+    assert_eq!(scaled(&db, 3), 6);
+    assert_eq!(greeting(&db, "world".to_string()), "hello, world");
+    assert_eq!(greeting(&db, "world".to_string()), "hello, world");
+    assert_eq!(area(&db, (3, 4)), 48);
+    assert_eq!(sum(&db, Some(vec![3, 4])), 14);
+    assert_eq!(sum(&db, None), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "scaled(3)",
+            "greeting(world)",
+            "area((3, 4))",
+            "scaled(4)",
+            "sum(Some([3, 4]))",
+            "sum(None)",
+        ]"#]]);
+
+    Config::get(&db).set_factor(&mut db).to(3);
+    assert_eq!(area(&db, (3, 4)), 108);
+    assert_eq!(greeting(&db, "world".to_string()), "hello, world");
+    db.assert_logs(expect![[r#"
+        [
+            "scaled(3)",
+            "area((3, 4))",
+            "scaled(4)",
+        ]"#]]);
+}
+
+#[test]
+fn any_hashable_argument() {
+    let mut db = Database::default();
+    Config::new(&db, 2);
+
+    assert_eq!(
+        file_name(&db, PathBuf::from("src/lib.rs")),
+        Some("lib.rs".to_string())
+    );
+    assert_eq!(
+        file_name(&db, PathBuf::from("src/lib.rs")).unwrap(),
+        "lib.rs"
+    );
+    assert_eq!(describe(&db, Shape::Square(1)), 4);
+    assert_eq!(
+        describe(
+            &db,
+            Shape::Rectangle {
+                width: 1,
+                height: 2
+            }
+        ),
+        8
+    );
+    assert_eq!(offset(&db, 1), 3);
+    assert_eq!(shout(&db, user::String("hey".to_string())), "HEY");
+    assert_eq!(shout(&db, user::String("hey".to_string())), "HEY");
+    db.assert_logs(expect![[r#"
+        [
+            "file_name(src/lib.rs)",
+            "describe(Square(1))",
+            "area((1, 1))",
+            "scaled(1)",
+            "describe(Rectangle { width: 1, height: 2 })",
+            "area((1, 2))",
+            "scaled(2)",
+            "offset(1)",
+            "shout(hey)",
+        ]"#]]);
+}
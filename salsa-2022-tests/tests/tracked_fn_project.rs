@@ -0,0 +1,101 @@
+//! Test the `project` option of tracked functions, whose projection queries let
+//! the callers reading one field of the value not depend on the others.
+
+use expect_test::expect;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Function, signature_of, is_unit, arity);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Function {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Signature {
+    params: Vec<String>,
+    return_type: String,
+}
+
+/// Parses `a, b -> T`.
+#[salsa::tracked(project(params: Vec<String>, return_type: String))]
+fn signature_of(db: &dyn Db, function: Function) -> Signature {
+    db.push_log(format!("signature_of({:?})", function.text(db)));
+    let (params, return_type) = function.text(db).split_once(" -> ").unwrap();
+    Signature {
+        params: params.split(", ").map(|param| param.to_string()).collect(),
+        return_type: return_type.to_string(),
+    }
+}
+
+#[salsa::tracked]
+fn is_unit(db: &dyn Db, function: Function) -> bool {
+    db.push_log("is_unit".to_string());
+    signature_of::return_type(db, function) == "()"
+}
+
+#[salsa::tracked]
+fn arity(db: &dyn Db, function: Function) -> usize {
+    db.push_log("arity".to_string());
+    signature_of::params(db, function).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn callers_of_one_field_ignore_the_others() {
+    let mut db = Database::default();
+    let function = Function::new(&db, "a, b -> ()".to_string());
+    assert!(is_unit(&db, function));
+    assert_eq!(arity(&db, function), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "is_unit",
+            "signature_of(\"a, b -> ()\")",
+            "arity",
+        ]"#]]);
+
+    // Only the parameters change: `is_unit` is not executed again.
+    function.set_text(&mut db).to("a -> ()".to_string());
+    assert!(is_unit(&db, function));
+    assert_eq!(arity(&db, function), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "signature_of(\"a -> ()\")",
+            "arity",
+        ]"#]]);
+
+    // Only the return type changes: `arity` is not executed again.
+    function.set_text(&mut db).to("a -> u32".to_string());
+    assert!(!is_unit(&db, function));
+    assert_eq!(arity(&db, function), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "signature_of(\"a -> u32\")",
+            "is_unit",
+        ]"#]]);
+
+    assert_eq!(signature_of::return_type(&db, function), "u32");
+    assert_eq!(signature_of::params(&db, function), ["a"]);
+    assert_eq!(signature_of(&db, function).return_type, "u32");
+}
@@ -0,0 +1,99 @@
+//! Test the `retry_errors` option of tracked functions, whose `Err` results
+//! are re-executed in the next revision even if no input changed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, Other, read, line_count);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    /// Whether the files are locked, which salsa does not know about.
+    fn locked(&self) -> bool;
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    path: String,
+}
+
+#[salsa::input]
+struct Other {
+    field: u32,
+}
+
+#[salsa::tracked(retry_errors)]
+fn read(db: &dyn Db, file: File) -> Result<String, String> {
+    db.push_log(format!("read({})", file.path(db)));
+    if db.locked() {
+        Err(format!("{} is locked", file.path(db)))
+    } else {
+        Ok("a\nb".to_string())
+    }
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, file: File) -> Option<usize> {
+    db.push_log(format!("line_count({})", file.path(db)));
+    read(db, file).ok().map(|text| text.lines().count())
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    locked: AtomicBool,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn errors_are_retried() {
+    let mut db = Database::default();
+    let file = File::new(&db, "main.txt".to_string());
+    let other = Other::new(&db, 0);
+    db.locked.store(true, Ordering::SeqCst);
+    assert_eq!(line_count(&db, file), None);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(main.txt)",
+            "read(main.txt)",
+        ]"#]]);
+
+    // The error is memoized for the rest of the revision.
+    db.locked.store(false, Ordering::SeqCst);
+    assert_eq!(read(&db, file), Err("main.txt is locked".to_string()));
+    db.assert_logs(expect!["[]"]);
+
+    // It is retried in the next one, although no input it read changed.
+    other.set_field(&mut db).to(1);
+    assert_eq!(line_count(&db, file), Some(2));
+    db.assert_logs(expect![[r#"
+        [
+            "read(main.txt)",
+            "line_count(main.txt)",
+        ]"#]]);
+
+    // A success is memoized until an input changes, as usual.
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(line_count(&db, file), Some(2));
+    db.assert_logs(expect!["[]"]);
+}
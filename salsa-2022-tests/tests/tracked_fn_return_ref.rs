@@ -0,0 +1,46 @@
+//! Test that a `return_ref` tracked function hands out a reference to the
+//! memoized value, so the value type does not even need to implement `Clone`.
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, parse);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Ast {
+    words: Vec<String>,
+}
+
+#[salsa::tracked(return_ref)]
+fn parse(db: &dyn Db, input: MyInput) -> Ast {
+    Ast {
+        words: input.text(db).split(' ').map(String::from).collect(),
+    }
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn return_ref_borrows_the_memo() {
+    let db = Database::default();
+    let input = MyInput::new(&db, "hello world".to_string());
+    let ast: &Ast = parse(&db, input);
+    assert_eq!(ast.words, ["hello", "world"]);
+    assert!(std::ptr::eq(ast, parse(&db, input)));
+}
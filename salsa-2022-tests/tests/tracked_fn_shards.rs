@@ -0,0 +1,70 @@
+//! Test the `shards` option of tracked functions, for memo maps
+//! that many threads read and write at once.
+
+use salsa::ParallelDatabase;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, double, sum_doubles);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(shards = 64)]
+fn double(db: &dyn Db, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn sum_doubles(db: &dyn Db, input: MyInput, count: u32) -> u32 {
+    (0..count).map(|_| double(db, input)).sum()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl ParallelDatabase for Database {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(Database {
+            storage: self.storage.snapshot(),
+        })
+    }
+}
+
+#[test]
+fn fetch_from_many_threads() {
+    let mut db = Database::default();
+    let inputs: Vec<MyInput> = (0..100).map(|i| MyInput::new(&db, i)).collect();
+
+    let fetch_all = |db: &Database| {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let db = db.snapshot();
+                    let inputs = &inputs;
+                    scope.spawn(move || inputs.iter().map(|&input| double(&*db, input)).sum())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<u32>>()
+        })
+    };
+    assert_eq!(fetch_all(&db), vec![9900; 8]);
+
+    inputs[0].set_field(&mut db).to(100);
+    assert_eq!(fetch_all(&db), vec![10100; 8]);
+    assert_eq!(sum_doubles(&db, inputs[1], 3), 6);
+}
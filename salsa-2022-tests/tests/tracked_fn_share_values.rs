@@ -0,0 +1,73 @@
+//! Test the `share_values` option of tracked functions, which stores their
+//! equal values once, shared by all keys.
+
+use std::sync::Arc;
+
+use salsa::Database as _;
+
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(File, diagnostics, lengths);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(return_ref, share_values)]
+fn diagnostics(db: &dyn Db, file: File) -> Arc<Vec<String>> {
+    Arc::new(
+        file.text(db)
+            .split_whitespace()
+            .filter(|word| word.starts_with('!'))
+            .map(|word| format!("unexpected `{word}`"))
+            .collect(),
+    )
+}
+
+#[salsa::tracked]
+fn lengths(db: &dyn Db, file: File) -> Arc<Vec<usize>> {
+    Arc::new(file.text(db).split_whitespace().map(str::len).collect())
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+#[test]
+fn equal_values_are_shared() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a b".to_string());
+    let b = File::new(&db, "c".to_string());
+    let c = File::new(&db, "!d".to_string());
+
+    assert!(Arc::ptr_eq(diagnostics(&db, a), diagnostics(&db, b)));
+    assert!(!Arc::ptr_eq(diagnostics(&db, a), diagnostics(&db, c)));
+    assert_eq!(**diagnostics(&db, c), ["unexpected `!d`"]);
+    // Without the option, equal values are stored separately.
+    assert!(!Arc::ptr_eq(&lengths(&db, a), &lengths(&db, b)));
+
+    // A value is shared by the keys computed in later revisions too.
+    let shared = diagnostics(&db, c).clone();
+    a.set_text(&mut db).to("!d".to_string());
+    assert!(Arc::ptr_eq(diagnostics(&db, a), &shared));
+
+    // Once no memo stores a value, it is forgotten.
+    let weak = Arc::downgrade(&shared);
+    drop(shared);
+    a.set_text(&mut db).to("e".to_string());
+    c.set_text(&mut db).to("f".to_string());
+    assert!(Arc::ptr_eq(diagnostics(&db, a), diagnostics(&db, c)));
+    db.synthetic_write(salsa::Durability::LOW);
+    assert!(weak.upgrade().is_none());
+}
@@ -0,0 +1,167 @@
+//! Test the `update` option of tracked functions, whose new values are written
+//! into their old ones.
+
+use expect_test::expect;
+use salsa::Database as _;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, parse, item_count, parse_with_history);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Ast {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Item {
+    name: String,
+    arity: usize,
+}
+
+impl salsa::Update for Ast {
+    fn update(&mut self, new_value: Self) {
+        self.items.update(new_value.items);
+    }
+}
+
+impl salsa::Update for Item {
+    fn update(&mut self, new_value: Self) {
+        self.name.update(new_value.name);
+        self.arity.update(new_value.arity);
+    }
+}
+
+fn parse_text(text: &str) -> Ast {
+    let items = text
+        .split_whitespace()
+        .map(|word| Item {
+            name: word.to_string(),
+            arity: word.len(),
+        })
+        .collect();
+    Ast { items }
+}
+
+#[salsa::tracked(return_ref, update)]
+fn parse(db: &dyn Db, input: MyInput) -> Ast {
+    db.push_log(format!("parse({:?})", input.text(db)));
+    parse_text(input.text(db))
+}
+
+#[salsa::tracked]
+fn item_count(db: &dyn Db, input: MyInput) -> usize {
+    db.push_log("item_count".to_string());
+    parse(db, input).items.len()
+}
+
+#[salsa::tracked(return_ref, update, history = 1)]
+fn parse_with_history(db: &dyn Db, input: MyInput) -> Ast {
+    parse_text(input.text(db))
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn new_value_is_written_into_old_one() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "foo bar".to_string());
+    let items = parse(&db, input).items.as_ptr();
+    assert_eq!(item_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(\"foo bar\")",
+            "item_count",
+        ]"#]]);
+
+    // The vector of items is the old one, whose items were written into.
+    input.set_text(&mut db).to("foo baz".to_string());
+    let ast = parse(&db, input);
+    assert_eq!(ast, &parse_text("foo baz"));
+    assert_eq!(ast.items.as_ptr(), items);
+
+    assert_eq!(item_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(\"foo baz\")",
+            "item_count",
+        ]"#]]);
+
+    // An equal value is still backdated.
+    input.set_text(&mut db).to("foo  baz".to_string());
+    assert_eq!(item_count(&db, input), 2);
+    assert_eq!(parse(&db, input).items.as_ptr(), items);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(\"foo  baz\")",
+        ]"#]]);
+
+    input.set_text(&mut db).to("foo".to_string());
+    assert_eq!(parse(&db, input), &parse_text("foo"));
+    input.set_text(&mut db).to("foo bar quux".to_string());
+    assert_eq!(parse(&db, input), &parse_text("foo bar quux"));
+    assert_eq!(item_count(&db, input), 3);
+}
+
+#[test]
+fn old_value_kept_by_history_is_not_written_into() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "foo bar".to_string());
+    let items = parse_with_history(&db, input).items.as_ptr();
+    let r1 = db.current_revision();
+
+    input.set_text(&mut db).to("foo baz".to_string());
+    let ast = parse_with_history(&db, input);
+    assert_eq!(ast, &parse_text("foo baz"));
+    assert_ne!(ast.items.as_ptr(), items);
+    assert_eq!(
+        parse_with_history::value_as_of(&db, input, r1),
+        Some(&parse_text("foo bar"))
+    );
+}
+
+#[test]
+fn old_value_handed_out_by_entries_is_not_written_into() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, "foo bar".to_string());
+    parse(&db, input);
+
+    // `entries` does not verify the memo, so the value it hands out in the new
+    // revision must survive re-executing `parse`.
+    input.set_text(&mut db).to("foo baz".to_string());
+    let entries = parse::entries(&db);
+    let old_ast = entries[0].1;
+    let ast = parse(&db, input);
+    assert_eq!(ast, &parse_text("foo baz"));
+    assert_eq!(old_ast, &parse_text("foo bar"));
+    assert_ne!(ast.items.as_ptr(), old_ast.items.as_ptr());
+    db.assert_logs(expect![[r#"
+        [
+            "parse(\"foo bar\")",
+            "parse(\"foo baz\")",
+        ]"#]]);
+}
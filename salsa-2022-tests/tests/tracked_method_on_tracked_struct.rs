@@ -0,0 +1,122 @@
+//! Test tracked methods on a tracked struct, including one with an
+//! extra argument, and that they are memoized like tracked functions.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(
+    Source,
+    Function,
+    Function_signature,
+    Function_param_name,
+    parse,
+);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Function {
+    #[id]
+    name: String,
+    #[return_ref]
+    params: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Signature {
+    name: String,
+    arity: usize,
+}
+
+#[salsa::tracked]
+impl Function {
+    #[salsa::tracked]
+    fn signature(self, db: &dyn Db) -> Signature {
+        db.push_log(format!("signature({})", self.name(db)));
+        Signature {
+            name: self.name(db),
+            arity: self.params(db).len(),
+        }
+    }
+
+    #[salsa::tracked]
+    fn param_name(self, db: &dyn Db, index: usize) -> Option<String> {
+        db.push_log(format!("param_name({}, {index})", self.name(db)));
+        self.params(db).get(index).cloned()
+    }
+}
+
+/// Parses `name(param, ...)`.
+#[salsa::tracked]
+fn parse(db: &dyn Db, source: Source) -> Function {
+    let (name, params) = source.text(db).split_once('(').unwrap();
+    let params = params
+        .trim_end_matches(')')
+        .split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    Function::new(db, name.to_string(), params)
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = Database::default();
+    let source = Source::new(&db, "add(a, b)".to_string());
+
+    let function = parse(&db, source);
+    assert_eq!(
+        function.signature(&db),
+        Signature {
+            name: "add".to_string(),
+            arity: 2,
+        }
+    );
+    assert_eq!(function.param_name(&db, 1), Some("b".to_string()));
+    assert_eq!(function.param_name(&db, 2), None);
+    assert_eq!(function.signature(&db).arity, 2);
+    assert_eq!(function.param_name(&db, 1), Some("b".to_string()));
+    db.assert_logs(expect![[r#"
+        [
+            "signature(add)",
+            "param_name(add, 1)",
+            "param_name(add, 2)",
+        ]"#]]);
+
+    // Changing the parameters re-executes the methods on the same `Function`.
+    source.set_text(&mut db).to("add(a, b, c)".to_string());
+    let function = parse(&db, source);
+    assert_eq!(function.signature(&db).arity, 3);
+    assert_eq!(function.param_name(&db, 2), Some("c".to_string()));
+    db.assert_logs(expect![[r#"
+        [
+            "signature(add)",
+            "param_name(add, 2)",
+        ]"#]]);
+}
@@ -0,0 +1,103 @@
+//! Test that the ids of tracked structs with the `content_ids` option
+//! do not depend on the order in which they are created.
+
+use salsa::AsId;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Item, CountedItem, items, counted_items);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    names: Vec<String>,
+}
+
+#[salsa::tracked(content_ids)]
+struct Item {
+    #[id]
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::tracked]
+struct CountedItem {
+    #[id]
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Db, input: MyInput) -> Vec<Item> {
+    input
+        .names(db)
+        .iter()
+        .map(|name| Item::new(db, name.clone()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn counted_items(db: &dyn Db, input: MyInput) -> Vec<CountedItem> {
+    input
+        .names(db)
+        .iter()
+        .map(|name| CountedItem::new(db, name.clone()))
+        .collect()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn same_ids_in_any_order() {
+    let db1 = Database::default();
+    let input1 = MyInput::new(&db1, names(&["a", "b", "a"]));
+    let items1 = items(&db1, input1);
+
+    let db2 = Database::default();
+    let input2 = MyInput::new(&db2, names(&["b", "a", "a"]));
+    let items2 = items(&db2, input2);
+
+    // The structs named "a" are told apart by the order in which they were created.
+    assert_eq!(items1[0].as_id(), items2[1].as_id());
+    assert_eq!(items1[1].as_id(), items2[0].as_id());
+    assert_eq!(items1[2].as_id(), items2[2].as_id());
+    assert_ne!(items1[0].as_id(), items1[2].as_id());
+    assert_eq!(items1[1].name(&db1), "b");
+    assert_eq!(items2[0].name(&db2), "b");
+
+    // Without the option, the ids are given out in order.
+    let counted1 = counted_items(&db1, input1);
+    let counted2 = counted_items(&db2, input2);
+    assert_ne!(counted1[0].as_id(), counted2[1].as_id());
+    assert_eq!(counted1[0].as_id(), counted2[0].as_id());
+}
+
+#[test]
+fn same_id_when_recreated() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, names(&["a", "b"]));
+    let b = items(&db, input)[1].as_id();
+
+    // `a` is no longer created, and deleted; `b` keeps its id.
+    input.set_names(&mut db).to(names(&["b"]));
+    assert_eq!(items(&db, input)[0].as_id(), b);
+
+    input.set_names(&mut db).to(names(&["c", "b", "a"]));
+    let new_items = items(&db, input);
+    assert_eq!(new_items[1].as_id(), b);
+    assert_eq!(new_items[1].name(&db), "b");
+}
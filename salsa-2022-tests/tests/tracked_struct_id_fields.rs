@@ -0,0 +1,110 @@
+//! Test that tracked structs are matched up by their `#[id]` fields when the
+//! query creating them is re-executed, so that reordering them or changing
+//! their other fields reuses the same structs.
+
+use expect_test::expect;
+use salsa::AsId;
+use salsa_2022_tests::{HasLogger, Logger};
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(Source, Function, functions, body_len, name_len);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct Source {
+    /// A name and a body for each function.
+    #[return_ref]
+    functions: Vec<(String, String)>,
+}
+
+#[salsa::tracked]
+struct Function {
+    #[id]
+    #[return_ref]
+    name: String,
+
+    #[return_ref]
+    body: String,
+}
+
+#[salsa::tracked]
+fn functions(db: &dyn Db, source: Source) -> Vec<Function> {
+    source
+        .functions(db)
+        .iter()
+        .map(|(name, body)| Function::new(db, name.clone(), body.clone()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn body_len(db: &dyn Db, function: Function) -> usize {
+    db.push_log(format!("body_len({})", function.name(db)));
+    function.body(db).len()
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn Db, function: Function) -> usize {
+    db.push_log(format!("name_len({})", function.name(db)));
+    function.name(db).len()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+fn lens(db: &Database, source: Source) -> Vec<(usize, usize)> {
+    functions(db, source)
+        .into_iter()
+        .map(|function| (name_len(db, function), body_len(db, function)))
+        .collect()
+}
+
+#[test]
+fn reorder_and_change_payload() {
+    let mut db = Database::default();
+    let source = Source::new(
+        &db,
+        vec![
+            ("foo".to_string(), "1".to_string()),
+            ("barbaz".to_string(), "22".to_string()),
+        ],
+    );
+    assert_eq!(lens(&db, source), vec![(3, 1), (6, 2)]);
+    let ids: Vec<_> = functions(&db, source).iter().map(|f| f.as_id()).collect();
+    db.assert_logs(expect![[r#"
+        [
+            "name_len(foo)",
+            "body_len(foo)",
+            "name_len(barbaz)",
+            "body_len(barbaz)",
+        ]"#]]);
+
+    // Swap the functions and change the body of `foo`: the structs are the
+    // same as before, and only the length of the body of `foo` is computed again.
+    source.set_functions(&mut db).to(vec![
+        ("barbaz".to_string(), "22".to_string()),
+        ("foo".to_string(), "4444".to_string()),
+    ]);
+    assert_eq!(lens(&db, source), vec![(6, 2), (3, 4)]);
+    let new_ids: Vec<_> = functions(&db, source).iter().map(|f| f.as_id()).collect();
+    assert_eq!(new_ids, vec![ids[1], ids[0]]);
+    db.assert_logs(expect![[r#"
+        [
+            "body_len(foo)",
+        ]"#]]);
+}
@@ -0,0 +1,113 @@
+//! Test tracked struct fields whose types are not `Clone`, which `#[return_ref]`
+//! getters return a reference to.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, Artifact, compile, code_len, digest);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    source: u32,
+    optimize: bool,
+}
+
+/// Not `Clone`.
+#[derive(Debug, PartialEq, Eq)]
+struct Bytecode(Vec<u8>);
+
+/// Neither `Clone` nor `Eq`.
+#[derive(Debug)]
+struct Listing(String);
+
+#[salsa::tracked]
+struct Artifact {
+    #[return_ref]
+    code: Bytecode,
+
+    #[return_ref]
+    #[no_eq]
+    listing: Listing,
+}
+
+#[salsa::tracked]
+fn compile(db: &dyn Db, input: MyInput) -> Artifact {
+    // The source is compiled the same way whether optimized or not.
+    let source = input.source(db);
+    input.optimize(db);
+    let code = Bytecode(vec![source as u8; source as usize]);
+    Artifact::new(db, code, Listing(format!("push {source}")))
+}
+
+#[salsa::tracked]
+fn code_len(db: &dyn Db, input: MyInput) -> usize {
+    db.push_log(format!("code_len({})", input.source(db)));
+    compile(db, input).code(db).0.len()
+}
+
+#[salsa::tracked]
+fn digest(db: &dyn Db, input: MyInput) -> String {
+    db.push_log(format!("digest({})", input.source(db)));
+    compile(db, input).listing(db).0.clone()
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn read_non_clone_fields() {
+    let db = Database::default();
+    let input = MyInput::new(&db, 3, false);
+    let artifact = compile(&db, input);
+    assert_eq!(artifact.code(&db).0, vec![3, 3, 3]);
+    assert_eq!(artifact.listing(&db).0, "push 3");
+}
+
+#[test]
+fn equal_field_is_backdated() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 3, false);
+    assert_eq!(code_len(&db, input), 3);
+    assert_eq!(digest(&db, input), "push 3");
+    db.assert_logs(expect![[r#"
+        [
+            "code_len(3)",
+            "digest(3)",
+        ]"#]]);
+
+    // The bytecode is the same, so `code_len` is not re-executed, but the listing
+    // is `no_eq`, so `digest` is.
+    input.set_optimize(&mut db).to(true);
+    assert_eq!(code_len(&db, input), 3);
+    assert_eq!(digest(&db, input), "push 3");
+    db.assert_logs(expect![[r#"
+        [
+            "digest(3)",
+        ]"#]]);
+
+    input.set_source(&mut db).to(4);
+    assert_eq!(code_len(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "code_len(4)",
+        ]"#]]);
+}
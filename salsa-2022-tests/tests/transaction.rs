@@ -0,0 +1,135 @@
+//! Test that the writes in a `transaction` share a single revision,
+//! unless a value is read in between (fetched, read for what it accumulated,
+//! or checked for changes).
+
+use salsa::{storage::HasJarsDyn, Database as _};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, sum, Log, logged_sum);
+
+trait Db: salsa::DbWithJar<Jar> {}
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Db, input: MyInput) -> u32 {
+    input.a(db) + input.b(db)
+}
+
+#[salsa::accumulator]
+struct Log(String);
+
+#[salsa::tracked]
+#[allow(dead_code)]
+fn logged_sum(db: &dyn Db, input: MyInput) {
+    Log::push(db, format!("sum = {}", sum(db, input)));
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+/// The revision in which `sum(input)` was last verified.
+fn sum_verified_at(db: &Database, input: MyInput) -> salsa::Revision {
+    db.value_revisions(sum::database_key(db, input))
+        .unwrap()
+        .verified_at
+        .unwrap()
+}
+
+#[test]
+fn writes_share_a_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+    assert_eq!(sum(&db, input), 3);
+
+    let edited = db.transaction(|db| {
+        input.set_a(db).to(10);
+        input.set_b(db).to(20);
+        input.set_a(db).to(30);
+        "edited"
+    });
+    assert_eq!(edited, "edited");
+    assert_eq!(sum(&db, input), 50);
+    expect![[r#"
+        R2
+    "#]]
+    .assert_debug_eq(&sum_verified_at(&db, input));
+
+    // Outside of a transaction, each write starts a new revision.
+    input.set_a(&mut db).to(1);
+    input.set_b(&mut db).to(2);
+    assert_eq!(sum(&db, input), 3);
+    expect![[r#"
+        R4
+    "#]]
+    .assert_debug_eq(&sum_verified_at(&db, input));
+}
+
+#[test]
+fn fetch_in_transaction_starts_new_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+
+    db.transaction(|db| {
+        input.set_a(db).to(10);
+        assert_eq!(sum(db, input), 12);
+        input.set_b(db).to(20);
+        input.set_a(db).to(30);
+    });
+    assert_eq!(sum(&db, input), 50);
+    expect![[r#"
+        R3
+    "#]]
+    .assert_debug_eq(&sum_verified_at(&db, input));
+}
+
+#[test]
+fn accumulated_in_transaction_starts_new_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+
+    db.transaction(|db| {
+        input.set_a(db).to(10);
+        let logs = logged_sum::accumulated::<Log>(db, input);
+        assert_eq!(logs, vec!["sum = 12".to_string()]);
+        input.set_b(db).to(20);
+    });
+    assert_eq!(sum(&db, input), 30);
+    expect![[r#"
+        R3
+    "#]]
+    .assert_debug_eq(&sum_verified_at(&db, input));
+}
+
+#[test]
+fn change_check_in_transaction_starts_new_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1, 2);
+    assert_eq!(sum(&db, input), 3);
+    let computed_at = sum_verified_at(&db, input);
+
+    db.transaction(|db| {
+        input.set_a(db).to(10);
+        assert!(db.has_changed_since(sum::database_key(db, input), computed_at));
+        input.set_b(db).to(20);
+    });
+    assert_eq!(sum(&db, input), 30);
+    expect![[r#"
+        R3
+    "#]]
+    .assert_debug_eq(&sum_verified_at(&db, input));
+}
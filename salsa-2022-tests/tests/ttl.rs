@@ -0,0 +1,117 @@
+//! Test `#[salsa::tracked(ttl = ...)]`, for functions reading state
+//! outside of salsa whose values should only be trusted for a while.
+
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, latest_version, cached_version, is_outdated);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    fn registry(&self) -> u32;
+}
+
+#[salsa::input]
+struct MyInput {
+    installed: u32,
+}
+
+#[salsa::tracked(ttl = Duration::ZERO)]
+fn latest_version(db: &dyn Db) -> u32 {
+    db.push_log("latest_version".to_string());
+    db.registry()
+}
+
+#[salsa::tracked(ttl = Duration::from_secs(3600))]
+fn cached_version(db: &dyn Db) -> u32 {
+    db.push_log("cached_version".to_string());
+    db.registry()
+}
+
+#[salsa::tracked]
+fn is_outdated(db: &dyn Db) -> bool {
+    db.push_log("is_outdated".to_string());
+    latest_version(db) > 1
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    registry: AtomicU32,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn registry(&self) -> u32 {
+        self.registry.load(Ordering::SeqCst)
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn expired_in_new_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    db.registry.store(1, Ordering::SeqCst);
+
+    // Memoized within a revision, even once expired.
+    assert!(!is_outdated(&db));
+    db.registry.store(2, Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(1));
+    assert_eq!(latest_version(&db), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "is_outdated",
+            "latest_version",
+        ]"#]]);
+
+    // In a new revision, the expired value is recomputed, and so is what
+    // depends on it, although none of them read the input that changed.
+    input.set_installed(&mut db).to(2);
+    assert!(is_outdated(&db));
+    db.assert_logs(expect![[r#"
+        [
+            "latest_version",
+            "is_outdated",
+        ]"#]]);
+
+    // When the recomputed value is the same, it is backdated.
+    std::thread::sleep(Duration::from_millis(1));
+    input.set_installed(&mut db).to(3);
+    assert!(is_outdated(&db));
+    db.assert_logs(expect![[r#"
+        [
+            "latest_version",
+        ]"#]]);
+}
+
+#[test]
+fn reused_until_expired() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+    db.registry.store(1, Ordering::SeqCst);
+
+    assert_eq!(cached_version(&db), 1);
+    db.registry.store(2, Ordering::SeqCst);
+    input.set_installed(&mut db).to(2);
+    assert_eq!(cached_version(&db), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "cached_version",
+        ]"#]]);
+}
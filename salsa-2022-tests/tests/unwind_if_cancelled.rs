@@ -0,0 +1,63 @@
+//! Test that calling `Database::unwind_if_cancelled` from a tracked function
+//! that is not cancelled does not add a dependency to it.
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, sum);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {}
+
+#[salsa::input]
+struct MyInput {
+    items: u32,
+    unrelated: u32,
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log("sum".to_string());
+    let mut sum = 0;
+    for item in 0..input.items(db) {
+        db.unwind_if_cancelled();
+        sum += item;
+    }
+    sum
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn adds_no_dependency() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 4, 0);
+
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "sum",
+        ]"#]]);
+
+    // A new revision in which only a field not read by `sum` changed.
+    input.set_unrelated(&mut db).to(1);
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,99 @@
+//! Test `#[salsa::tracked(volatile)]`, for functions reading state
+//! outside of salsa, which re-execute in every revision.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use salsa_2022_tests::{HasLogger, Logger};
+
+use expect_test::expect;
+use test_log::test;
+
+#[salsa::jar(db = Db)]
+struct Jar(MyInput, now, is_expired, unrelated);
+
+trait Db: salsa::DbWithJar<Jar> + HasLogger {
+    fn clock(&self) -> u32;
+}
+
+#[salsa::input]
+struct MyInput {
+    deadline: u32,
+}
+
+#[salsa::tracked(volatile)]
+fn now(db: &dyn Db) -> u32 {
+    db.push_log("now".to_string());
+    db.clock()
+}
+
+#[salsa::tracked]
+fn is_expired(db: &dyn Db, input: MyInput) -> bool {
+    db.push_log("is_expired".to_string());
+    now(db) > input.deadline(db)
+}
+
+#[salsa::tracked]
+fn unrelated(db: &dyn Db, input: MyInput) -> u32 {
+    db.push_log("unrelated".to_string());
+    input.deadline(db) * 2
+}
+
+#[salsa::db(Jar)]
+#[derive(Default)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+    clock: AtomicU32,
+}
+
+impl salsa::Database for Database {}
+
+impl Db for Database {
+    fn clock(&self) -> u32 {
+        self.clock.load(Ordering::SeqCst)
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn reexecuted_in_each_revision() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 10);
+    let other = MyInput::new(&db, 0);
+
+    // Memoized within a revision, even if the clock moves.
+    assert!(!is_expired(&db, input));
+    db.clock.store(20, Ordering::SeqCst);
+    assert_eq!(now(&db), 0);
+    assert_eq!(unrelated(&db, input), 20);
+    db.assert_logs(expect![[r#"
+        [
+            "is_expired",
+            "now",
+            "unrelated",
+        ]"#]]);
+
+    // A new revision re-executes `now`, and what depends on it.
+    other.set_deadline(&mut db).to(1);
+    assert!(is_expired(&db, input));
+    assert_eq!(unrelated(&db, input), 20);
+    db.assert_logs(expect![[r#"
+        [
+            "now",
+            "is_expired",
+        ]"#]]);
+
+    // When `now` returns the same value, it is backdated,
+    // so the memos depending on it are still valid.
+    other.set_deadline(&mut db).to(2);
+    assert!(is_expired(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "now",
+        ]"#]]);
+}
@@ -1,8 +1,13 @@
+// These items are never used by a database; the test only checks the
+// code the macros generate for them.
+#[allow(dead_code)]
 trait Db: salsa::DbWithJar<Jar> {}
 
+#[allow(dead_code)]
 #[salsa::jar(db = Db)]
 struct Jar(TokenTree);
 
+#[allow(dead_code)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum Token {}
 
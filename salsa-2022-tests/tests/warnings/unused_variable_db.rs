@@ -1,5 +1,7 @@
+#[allow(dead_code)]
 trait Db: salsa::DbWithJar<Jar> {}
 
+#[allow(dead_code)]
 #[salsa::jar(db = Db)]
 struct Jar(Keywords);
 
@@ -16,7 +16,7 @@ trait QueryGroup: salsa::Database + AsRef<HashMap<u32, u32>> {
 }
 
 fn a(db: &dyn QueryGroup, x: u32) -> u32 {
-    let durability = if x % 2 == 0 {
+    let durability = if x.is_multiple_of(2) {
         Durability::LOW
     } else {
         Durability::HIGH